@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long aggregate statistics are cached when not configured
+pub const DEFAULT_STATISTICS_TTL: Duration = Duration::from_secs(5);
+
+/// Short-lived copy of one expensive aggregate.
+///
+/// An entry is served until its TTL runs out or the database's sample
+/// generation moves past the one it was computed at, so a stored or deleted
+/// sample is reflected by the very next request.
+#[derive(Debug, Clone)]
+pub struct AggregateCache<T> {
+    ttl: Duration,
+    entry: Arc<Mutex<Option<CacheEntry<T>>>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    bypassed: Arc<AtomicU64>,
+}
+
+#[derive(Debug)]
+struct CacheEntry<T> {
+    value: T,
+    generation: u64,
+    computed_at: Instant,
+}
+
+/// Effectiveness of an [`AggregateCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheReport {
+    pub ttl_ms: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// Requests that asked to skip the cache
+    pub bypassed: u64,
+}
+
+impl<T: Clone> AggregateCache<T> {
+    /// Cache keeping values for `ttl`; a zero TTL caches nothing
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: Arc::new(Mutex::new(None)),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+            bypassed: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// The cached value if it is still valid at `generation`, otherwise the
+    /// result of `compute`, which replaces it. `bypass` always computes.
+    pub fn get_or_compute<E>(
+        &self,
+        generation: u64,
+        bypass: bool,
+        compute: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut entry = self.entry.lock().unwrap_or_else(|e| e.into_inner());
+        if bypass {
+            self.bypassed.fetch_add(1, Ordering::Relaxed);
+        } else if let Some(cached) = entry.as_ref() {
+            if cached.generation == generation && cached.computed_at.elapsed() < self.ttl {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(cached.value.clone());
+            }
+        }
+        if !bypass {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let value = compute()?;
+        *entry = Some(CacheEntry { value: value.clone(), generation, computed_at: Instant::now() });
+        Ok(value)
+    }
+
+    pub fn report(&self) -> CacheReport {
+        CacheReport {
+            ttl_ms: self.ttl.as_millis().min(u64::MAX as u128) as u64,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            bypassed: self.bypassed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<T: Clone> Default for AggregateCache<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_STATISTICS_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compute(calls: &mut u32) -> Result<u32, ()> {
+        *calls += 1;
+        Ok(*calls)
+    }
+
+    #[test]
+    fn test_serves_cached_value_until_generation_changes() {
+        let cache = AggregateCache::new(Duration::from_secs(60));
+        let mut calls = 0;
+
+        assert_eq!(cache.get_or_compute(1, false, || compute(&mut calls)), Ok(1));
+        assert_eq!(cache.get_or_compute(1, false, || compute(&mut calls)), Ok(1));
+        assert_eq!(cache.get_or_compute(2, false, || compute(&mut calls)), Ok(2));
+        assert_eq!(cache.get_or_compute(2, true, || compute(&mut calls)), Ok(3));
+        assert_eq!(cache.get_or_compute(2, false, || compute(&mut calls)), Ok(3));
+
+        let report = cache.report();
+        assert_eq!((report.hits, report.misses, report.bypassed), (2, 2, 1));
+    }
+
+    #[test]
+    fn test_zero_ttl_caches_nothing() {
+        let cache = AggregateCache::new(Duration::ZERO);
+        let mut calls = 0;
+
+        cache.get_or_compute(1, false, || compute(&mut calls)).unwrap();
+        cache.get_or_compute(1, false, || compute(&mut calls)).unwrap();
+        assert_eq!(calls, 2);
+        assert_eq!(cache.report().hits, 0);
+    }
+
+    #[test]
+    fn test_errors_are_not_cached() {
+        let cache: AggregateCache<u32> = AggregateCache::new(Duration::from_secs(60));
+
+        assert_eq!(cache.get_or_compute(1, false, || Err("busy")), Err("busy"));
+        assert_eq!(cache.get_or_compute(1, false, || Ok::<_, &str>(7)), Ok(7));
+        assert_eq!(cache.get_or_compute(1, false, || Err("busy")), Ok(7));
+    }
+}
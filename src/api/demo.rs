@@ -0,0 +1,102 @@
+use crate::audit::AuditLogger;
+use crate::database::Database;
+use crate::error::Result;
+use crate::sample::{Sample, SampleMetadata, SampleStatus};
+use crate::temperature::TemperatureReading;
+use chrono::{Duration, Utc};
+
+/// Prefix marking the IDs of seeded demo samples
+pub const DEMO_PREFIX: &str = "DEMO-";
+/// User recorded on the audit events of seeded samples
+pub const DEMO_USER: &str = "demo-seed";
+/// Readings logged per demo storage location
+const READINGS_PER_LOCATION: i64 = 12;
+
+/// Product line, storage conditions, range and location of each kind of demo sample
+const DEMO_PRODUCTS: &[(&str, &str, (f32, f32), &str)] = &[
+    ("Vaccines", "Refrigerated", (2.0, 8.0), "Demo Cold Room 1"),
+    ("Insulin", "Refrigerated", (2.0, 8.0), "Demo Cold Room 2"),
+    ("Blood Plasma", "Frozen", (-30.0, -18.0), "Demo Freezer 1"),
+    ("Monoclonal Antibodies", "Refrigerated", (2.0, 8.0), "Demo Cold Room 3"),
+];
+
+/// Status changes each demo sample goes through after creation, in turn
+const DEMO_STATUSES: &[&[SampleStatus]] = &[
+    &[SampleStatus::Stored],
+    &[SampleStatus::Stored],
+    &[SampleStatus::InTransit],
+    &[SampleStatus::InTransit, SampleStatus::InUse],
+    &[],
+];
+
+/// Store `count` demo samples with a recent temperature history at their
+/// locations and a creation event each. Sample IDs start with [`DEMO_PREFIX`].
+pub fn seed_demo_data(db: &Database, audit_logger: &mut AuditLogger, count: usize) -> Result<Vec<Sample>> {
+    let now = Utc::now();
+    let mut samples = Vec::with_capacity(count);
+    for i in 0..count {
+        let (product_line, storage, range, location) = DEMO_PRODUCTS[i % DEMO_PRODUCTS.len()];
+        let metadata = SampleMetadata {
+            batch_number: format!("{}BATCH-{:03}", DEMO_PREFIX, i / 5 + 1),
+            production_date: now - Duration::days(30 + i as i64),
+            expiry_date: Some(now + Duration::days(20 + 15 * i as i64)),
+            temperature_range: Some(range),
+            storage_conditions: storage.to_string(),
+            manufacturer: "Demo Pharma".to_string(),
+            product_line: product_line.to_string(),
+        };
+        let mut sample = Sample::new(format!("{}{:04}", DEMO_PREFIX, i + 1), metadata, Some(location.to_string()));
+        for &status in DEMO_STATUSES[i % DEMO_STATUSES.len()] {
+            sample.try_update_status(status)?;
+        }
+        db.store_sample(&sample)?;
+        audit_logger.log_sample_created(&sample, Some(DEMO_USER.to_string()))?;
+        samples.push(sample);
+    }
+
+    for (_, _, (min, max), location) in DEMO_PRODUCTS.iter().take(count) {
+        for step in 0..READINGS_PER_LOCATION {
+            // A gentle swing around the middle of the range, touching its edges
+            let swing = ((step as f32) * 0.9).sin() * (max - min) / 2.0;
+            let reading = TemperatureReading {
+                temperature: (min + max) / 2.0 + swing,
+                timestamp: now - Duration::minutes(5 * (READINGS_PER_LOCATION - step)),
+                sensor_id: format!("{}SENSOR", DEMO_PREFIX),
+                location: Some(location.to_string()),
+            };
+            db.log_sample_temperature(&reading, (*min, *max))?;
+        }
+    }
+
+    Ok(samples)
+}
+
+/// Delete every seeded demo sample, returning how many were removed
+pub fn purge_demo_data(db: &Database) -> Result<usize> {
+    let mut purged = 0;
+    for sample in db.get_all_samples()? {
+        if sample.sample_id.starts_with(DEMO_PREFIX) && db.delete_sample(&sample.sample_id)? {
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_and_purge() {
+        let db = Database::in_memory().unwrap();
+        let mut logger = AuditLogger::new();
+        let samples = seed_demo_data(&db, &mut logger, 6).unwrap();
+        assert_eq!(samples.len(), 6);
+        assert!(samples.iter().all(|s| s.sample_id.starts_with(DEMO_PREFIX) && s.verify_integrity()));
+        assert!(!db.get_sample_temperature_log("DEMO-0001", None).unwrap().is_empty());
+        assert_eq!(logger.get_all_events().len(), 6);
+
+        assert_eq!(purge_demo_data(&db).unwrap(), 6);
+        assert!(db.get_all_samples().unwrap().is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::error::SampleGuardError;
+use crate::error::{DatabaseErrorKind, SampleGuardError};
 use actix_web::{HttpResponse, ResponseError};
 use serde_json::json;
 
@@ -13,14 +13,65 @@ pub enum ApiError {
     
     #[error("Not found: {0}")]
     NotFound(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
     
     #[error("Internal server error: {0}")]
     Internal(String),
+
+    /// The deployment lacks hardware the endpoint needs
+    #[error("Capability not available: {0}")]
+    CapabilityMissing(&'static str),
+
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    /// The server is handling as many requests as it is allowed to
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    /// No valid API key was presented
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The API key's role does not allow the request
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// The client exceeded its request rate
+    #[error("Too many requests; retry after {retry_after}s")]
+    TooManyRequests { retry_after: u64 },
 }
 
 impl ResponseError for ApiError {
     fn error_response(&self) -> HttpResponse {
         match self {
+            ApiError::SampleGuard(e @ SampleGuardError::InvalidStatusTransition { from, to }) => {
+                HttpResponse::Conflict().json(json!({
+                    "error": "Invalid status transition",
+                    "from": format!("{:?}", from),
+                    "to": format!("{:?}", to),
+                    "message": e.to_string()
+                }))
+            }
+            ApiError::SampleGuard(e @ SampleGuardError::DatabaseError { kind: DatabaseErrorKind::ConstraintViolation, .. }) => {
+                HttpResponse::Conflict().json(json!({
+                    "error": "Conflict",
+                    "message": e.to_string()
+                }))
+            }
+            ApiError::SampleGuard(e @ SampleGuardError::DatabaseError { kind: DatabaseErrorKind::Busy, .. }) => {
+                HttpResponse::ServiceUnavailable()
+                    .insert_header((actix_web::http::header::RETRY_AFTER, "1"))
+                    .json(json!({
+                        "error": "Service unavailable",
+                        "message": e.to_string()
+                    }))
+            }
             ApiError::SampleGuard(e) => {
                 HttpResponse::InternalServerError().json(json!({
                     "error": "SampleGuard error",
@@ -39,13 +90,88 @@ impl ResponseError for ApiError {
                     "message": msg
                 }))
             }
+            ApiError::Conflict(msg) => {
+                HttpResponse::Conflict().json(json!({
+                    "error": "Conflict",
+                    "message": msg
+                }))
+            }
             ApiError::Internal(msg) => {
                 HttpResponse::InternalServerError().json(json!({
                     "error": "Internal server error",
                     "message": msg
                 }))
             }
+            ApiError::UnsupportedMediaType(msg) => {
+                HttpResponse::UnsupportedMediaType().json(json!({
+                    "error": "Unsupported media type",
+                    "message": msg
+                }))
+            }
+            ApiError::PayloadTooLarge(msg) => {
+                HttpResponse::PayloadTooLarge().json(json!({
+                    "error": "Payload too large",
+                    "message": msg
+                }))
+            }
+            ApiError::ServiceUnavailable(msg) => {
+                HttpResponse::ServiceUnavailable()
+                    .insert_header((actix_web::http::header::RETRY_AFTER, "1"))
+                    .json(json!({
+                        "error": "Service unavailable",
+                        "message": msg
+                    }))
+            }
+            ApiError::Unauthorized(msg) => {
+                HttpResponse::Unauthorized()
+                    .insert_header((actix_web::http::header::WWW_AUTHENTICATE, "Bearer"))
+                    .json(json!({
+                        "error": "Unauthorized",
+                        "message": msg
+                    }))
+            }
+            ApiError::Forbidden(msg) => {
+                HttpResponse::Forbidden().json(json!({
+                    "error": "Forbidden",
+                    "message": msg
+                }))
+            }
+            ApiError::TooManyRequests { retry_after } => {
+                HttpResponse::TooManyRequests()
+                    .insert_header((actix_web::http::header::RETRY_AFTER, retry_after.to_string()))
+                    .json(json!({
+                        "error": "Too many requests",
+                        "message": self.to_string()
+                    }))
+            }
+            ApiError::CapabilityMissing(capability) => {
+                HttpResponse::NotImplemented().json(json!({
+                    "error": "Capability missing",
+                    "code": "CAPABILITY_MISSING",
+                    "capability": capability,
+                    "message": self.to_string()
+                }))
+            }
         }
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_of(kind: DatabaseErrorKind) -> u16 {
+        let error = SampleGuardError::database_message(kind, "test");
+        ApiError::from(error).error_response().status().as_u16()
+    }
+
+    #[test]
+    fn test_database_errors_map_to_status_codes() {
+        assert_eq!(status_of(DatabaseErrorKind::ConstraintViolation), 409);
+        assert_eq!(status_of(DatabaseErrorKind::Busy), 503);
+        assert_eq!(status_of(DatabaseErrorKind::NotFound), 500);
+        assert_eq!(status_of(DatabaseErrorKind::Corrupt), 500);
+        assert_eq!(status_of(DatabaseErrorKind::Other), 500);
+    }
+}
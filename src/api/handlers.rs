@@ -1,13 +1,26 @@
+use crate::api::cache::AggregateCache;
 use crate::api::error::ApiError;
 use crate::api::models::*;
-use crate::database::Database;
-use crate::inventory::InventoryManager;
-use crate::temperature::TemperatureMonitor;
-use crate::audit::{AuditLogger, AuditEvent};
+use crate::api::middleware::{ApiKeys, ConnectionLimiter, RateLimiter};
+use crate::api::streaming;
+use crate::api::demo;
+use crate::config::Capabilities;
+use crate::database::{ConflictStrategy, Database, DatabaseStatistics, Page, SampleQuery};
+use crate::error::SampleGuardError;
+use crate::inventory::{diff_snapshots, InventoryManager};
+use crate::temperature::{TemperatureAlert, TemperatureMonitor};
+use crate::audit::{AuditLogger, AuditEventType, AuditSeverity, AuditCursor};
 use crate::sample::{Sample, SampleStatus, SampleMetadata};
 use crate::reader::MockRFIDReader;
+use crate::jobs::JobStatusRegistry;
+use crate::hardware::metrics::ReaderMetrics;
+use crate::hardware::HardwareDriver;
 use crate::SampleGuard;
-use actix_web::{web, HttpResponse, Result as ActixResult};
+use crate::vocabulary::StorageVocabulary;
+use crate::export::{self, ExportFormat};
+use crate::images::{self, ImageLimits, SampleImage};
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
 use std::sync::{Arc, Mutex};
 use chrono::Utc;
 
@@ -19,25 +32,116 @@ pub struct AppState {
     pub temperature_monitor: Arc<Mutex<TemperatureMonitor>>,
     pub audit_logger: Arc<Mutex<AuditLogger>>,
     pub sample_guard: Arc<Mutex<SampleGuard>>,
+    pub job_status: JobStatusRegistry,
+    /// Response times recorded by `hardware_driver`'s readers
+    pub reader_metrics: ReaderMetrics,
+    /// Present when readers are configured
+    pub hardware_driver: Option<Arc<Mutex<HardwareDriver>>>,
+    pub capabilities: Capabilities,
+    /// Allowed storage conditions; free text when `None`
+    pub storage_vocabulary: Option<StorageVocabulary>,
+    pub image_limits: ImageLimits,
+    pub connections: ConnectionLimiter,
+    pub api_keys: ApiKeys,
+    pub rate_limiter: RateLimiter,
+    /// Largest page a listing returns
+    pub max_page_size: usize,
+    /// Database aggregates behind `/statistics`
+    pub statistics_cache: AggregateCache<DatabaseStatistics>,
+    /// Where `POST /audit/archive` writes archives; archiving is off when `None`
+    pub audit_archive_dir: Option<std::path::PathBuf>,
+}
+
+impl AppState {
+    /// Fail with `CapabilityMissing` unless `present`
+    fn require(&self, present: fn(&Capabilities) -> bool, capability: &'static str) -> Result<(), ApiError> {
+        if present(&self.capabilities) {
+            Ok(())
+        } else {
+            Err(ApiError::CapabilityMissing(capability))
+        }
+    }
 }
 
 /// Health check endpoint
-pub async fn health_check() -> ActixResult<HttpResponse> {
+pub async fn health_check(state: web::Data<AppState>) -> ActixResult<HttpResponse> {
     Ok(HttpResponse::Ok().json(HealthResponse {
         status: "ok".to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
         timestamp: Utc::now(),
+        capabilities: Some(state.capabilities),
     }))
 }
 
+/// Hardware available to this deployment
+pub async fn get_capabilities(state: web::Data<AppState>) -> ActixResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(state.capabilities))
+}
+
 /// Get all samples
-pub async fn get_samples(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+pub async fn get_samples(
+    state: web::Data<AppState>,
+    query: web::Query<SamplePageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (offset, per_page) = page_bounds(&query, state.max_page_size)?;
     let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
-    let samples = db.get_all_samples()?;
-    
-    let responses: Vec<SampleResponse> = samples.iter().map(SampleResponse::from).collect();
+    let sort = query.sort.unwrap_or_default();
+    let labels = query.labels();
+    let samples = if labels.is_empty() {
+        db.get_samples_paged(offset, per_page, sort)?
+    } else {
+        db.get_samples_by_labels_paged(&labels, query.label_match.unwrap_or_default(), offset, per_page, sort)?
+    };
     
-    Ok(HttpResponse::Ok().json(responses))
+    Ok(HttpResponse::Ok().json(sample_page_response(&db, samples, offset, per_page)?))
+}
+
+/// Samples per page when neither `per_page` nor `limit` is given
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page a client may ask for, whatever the server allows
+const MAX_PAGE_SIZE: usize = 500;
+
+/// The offset and page size a listing asked for, either as `page` and
+/// `per_page` or as `offset` and `limit`, capping the size at `max`
+fn page_bounds(query: &SamplePageQuery, max: usize) -> Result<(usize, usize), ApiError> {
+    if query.page.is_some() && query.offset.is_some() {
+        return Err(ApiError::Validation("give either page or offset, not both".to_string()));
+    }
+    if query.per_page.is_some() && query.limit.is_some() {
+        return Err(ApiError::Validation("give either per_page or limit, not both".to_string()));
+    }
+    let per_page = query.per_page.or(query.limit).unwrap_or(DEFAULT_PAGE_SIZE);
+    if per_page == 0 {
+        return Err(ApiError::Validation("per_page and limit must be at least 1".to_string()));
+    }
+    if per_page > MAX_PAGE_SIZE {
+        return Err(ApiError::Validation(format!("per_page and limit must be at most {}", MAX_PAGE_SIZE)));
+    }
+    let per_page = per_page.min(max);
+    let offset = match (query.offset, query.page) {
+        (Some(offset), _) => offset,
+        (None, Some(0)) => return Err(ApiError::Validation("page starts at 1".to_string())),
+        (None, page) => (page.unwrap_or(1) - 1).saturating_mul(per_page),
+    };
+    Ok((offset, per_page))
+}
+
+fn sample_page_response(
+    db: &Database,
+    samples: Page<Sample>,
+    offset: usize,
+    per_page: usize,
+) -> Result<SamplePageResponse, ApiError> {
+    Ok(SamplePageResponse {
+        items: sample_responses(db, &samples.items)?,
+        total: samples.total_count,
+        has_more: samples.has_more,
+        page: offset / per_page + 1,
+        per_page,
+        total_pages: samples.total_count.div_ceil(per_page),
+        offset,
+    })
 }
 
 /// Get sample by ID
@@ -50,8 +154,58 @@ pub async fn get_sample(
     
     let sample = db.get_sample(&sample_id)?
         .ok_or_else(|| ApiError::NotFound(format!("Sample {} not found", sample_id)))?;
+    db.record_api_access(&sample_id)?;
     
-    Ok(HttpResponse::Ok().json(SampleResponse::from(&sample)))
+    Ok(HttpResponse::Ok().json(sample_response(&db, &sample)?))
+}
+
+/// Response for a stored sample, including its API access count and image
+fn sample_response(db: &Database, sample: &Sample) -> Result<SampleResponse, ApiError> {
+    let mut responses = sample_responses(db, std::slice::from_ref(sample))?;
+    Ok(responses.remove(0))
+}
+
+/// [`sample_response`] for many samples, loading access counts, images and
+/// labels with one query each rather than per sample
+fn sample_responses(db: &Database, samples: &[Sample]) -> Result<Vec<SampleResponse>, ApiError> {
+    let ids: Vec<&str> = samples.iter().map(|s| s.sample_id.as_str()).collect();
+    let access_counts = db.get_api_access_counts(&ids)?;
+    let images = db.get_sample_image_refs(&ids)?;
+    let labels = db.get_labels_for_samples(&ids)?;
+    Ok(samples
+        .iter()
+        .map(|sample| {
+            SampleResponse::from(sample)
+                .with_api_access_count(access_counts.get(&sample.sample_id).copied().unwrap_or_default())
+                .with_primary_image(images.get(&sample.sample_id).cloned())
+                .with_labels(labels.get(&sample.sample_id).cloned().unwrap_or_default())
+        })
+        .collect())
+}
+
+/// Validate a sample's integrity, reporting what changed since it was last validated
+pub async fn validate_sample(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    
+    let sample = db.get_sample(&sample_id)?
+        .ok_or_else(|| ApiError::NotFound(format!("Sample {} not found", sample_id)))?;
+    let result = state.sample_guard.lock().map_err(|e| ApiError::Internal(e.to_string()))?
+        .check_integrity(&sample)?;
+    let delta = db.get_last_validation(&sample_id)?.map(|previous| result.delta(&previous));
+    db.save_validation(&sample_id, &result)?;
+    
+    Ok(HttpResponse::Ok().json(ValidationResponse {
+        sample_id,
+        is_valid: result.is_valid,
+        violations: result.violations,
+        warnings: result.warnings,
+        delta,
+        validated_at: Utc::now(),
+    }))
 }
 
 /// Create a new sample
@@ -59,27 +213,104 @@ pub async fn create_sample(
     state: web::Data<AppState>,
     req: web::Json<CreateSampleRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    let req = req.into_inner();
+    let sample = new_sample(&state, req.into_inner())?;
+    
+    // Store in database
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    db.store_sample(&sample)?;
+    
+    // Log audit event
+    let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    logger.log_sample_created(&sample, None)?;
+    log::info!(sample_id = sample.sample_id.as_str(); "Sample created");
+    
+    Ok(HttpResponse::Created().json(SampleResponse::from(&sample)))
+}
+
+/// Build the sample a create request describes, normalizing its storage
+/// conditions against the configured vocabulary
+fn new_sample(state: &AppState, req: CreateSampleRequest) -> Result<Sample, ApiError> {
+    let storage_conditions = match &state.storage_vocabulary {
+        Some(vocabulary) => vocabulary
+            .normalize(&req.storage_conditions)
+            .map_err(|e| ApiError::Validation(e.to_string()))?,
+        None => req.storage_conditions,
+    };
     
     let metadata = SampleMetadata {
         batch_number: req.batch_number,
         production_date: req.production_date,
         expiry_date: req.expiry_date,
         temperature_range: req.temperature_range,
-        storage_conditions: req.storage_conditions,
+        storage_conditions,
         manufacturer: req.manufacturer,
         product_line: req.product_line,
     };
     
-    let sample = Sample::new(req.sample_id.clone(), metadata, req.location);
+    Ok(Sample::new(req.sample_id, metadata, req.location))
+}
+
+/// Create many samples in one transaction. Samples that fail to store are
+/// reported individually unless `all_or_nothing` is set, in which case the
+/// first failure discards the whole batch.
+pub async fn create_samples_batch(
+    state: web::Data<AppState>,
+    query: web::Query<BatchStoreQuery>,
+    req: web::Json<Vec<CreateSampleRequest>>,
+) -> Result<HttpResponse, ApiError> {
+    let samples = req
+        .into_inner()
+        .into_iter()
+        .map(|item| {
+            let sample_id = item.sample_id.clone();
+            new_sample(&state, item).map_err(|e| match e {
+                ApiError::Validation(message) => ApiError::Validation(format!("sample {}: {}", sample_id, message)),
+                other => other,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
     
-    // Store in database
     let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let result = db.store_samples_batch(&samples, query.all_or_nothing.unwrap_or(false))?;
+    
+    if !result.rolled_back {
+        let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+        for (item, sample) in result.items.iter().zip(&samples) {
+            if item.error.is_none() {
+                logger.log_sample_created(sample, None)?;
+            }
+        }
+    }
+    log::info!(stored = result.stored(), failed = result.failed(); "Sample batch stored");
+    
+    Ok(HttpResponse::Ok().json(BatchStoreResponse {
+        stored: result.stored(),
+        failed: result.failed(),
+        rolled_back: result.rolled_back,
+        items: result.items,
+    }))
+}
+
+/// Create a new sample with an existing sample's metadata
+pub async fn clone_sample(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<CloneSampleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let source_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let source = db.get_sample(&source_id)?
+        .ok_or_else(|| ApiError::NotFound(format!("Sample {} not found", source_id)))?;
+    if db.get_sample(&req.sample_id)?.is_some() {
+        return Err(ApiError::Conflict(format!("Sample {} already exists", req.sample_id)));
+    }
+    
+    let sample = source.clone_as_new(req.into_inner().sample_id);
     db.store_sample(&sample)?;
     
-    // Log audit event
     let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
     logger.log_sample_created(&sample, None)?;
+    log::info!(sample_id = sample.sample_id.as_str(), source_id = source_id.as_str(); "Sample cloned");
     
     Ok(HttpResponse::Created().json(SampleResponse::from(&sample)))
 }
@@ -109,18 +340,14 @@ pub async fn update_sample_status(
         _ => return Err(ApiError::Validation(format!("Invalid status: {}", req.status))),
     };
     
-    sample.update_status(new_status);
-    if let Some(location) = req.location {
-        sample.update_location(location);
-    }
-    
-    db.store_sample(&sample)?;
+    db.change_status(&mut sample, new_status, req.location, req.user_id.as_deref())?;
     
     // Log audit event
     let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
-    logger.log_status_change(&sample_id, old_status, new_status, None)?;
+    logger.log_status_change(&sample_id, old_status, new_status, req.user_id)?;
+    log::info!(sample_id = sample_id.as_str(), status:? = new_status; "Sample status changed");
     
-    Ok(HttpResponse::Ok().json(SampleResponse::from(&sample)))
+    Ok(HttpResponse::Ok().json(sample_response(&db, &sample)?))
 }
 
 /// Delete a sample
@@ -131,45 +358,411 @@ pub async fn delete_sample(
     let sample_id = path.into_inner();
     let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
     
-    let deleted = db.delete_sample(&sample_id)?;
+    let deleted = db.delete_sample(&sample_id).map_err(|e| match e {
+        SampleGuardError::InvalidSampleData(msg) => ApiError::Conflict(msg),
+        e => e.into(),
+    })?;
     if !deleted {
         return Err(ApiError::NotFound(format!("Sample {} not found", sample_id)));
     }
+    log::info!(sample_id = sample_id.as_str(); "Sample deleted");
     
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// Split a sample into aliquots
+pub async fn split_sample(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<SplitSampleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let parent_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    if db.get_sample(&parent_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("Sample {} not found", parent_id)));
+    }
+
+    let children = db.split_sample(&parent_id, &req.children).map_err(lineage_error)?;
+
+    let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    logger.log_sample_split(&parent_id, &children, None)?;
+    log::info!(sample_id = parent_id.as_str(), children = children.len(); "Sample split");
+
+    let responses: Vec<SampleResponse> = children.iter().map(SampleResponse::from).collect();
+    Ok(HttpResponse::Created().json(responses))
+}
+
+/// Pool several samples into a new one
+pub async fn merge_samples(
+    state: web::Data<AppState>,
+    req: web::Json<MergeSamplesRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let source_ids: Vec<&str> = req.source_ids.iter().map(String::as_str).collect();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let merged = db.merge_samples(&source_ids, &req.sample_id).map_err(lineage_error)?;
+
+    let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    logger.log_samples_merged(&source_ids, &merged, None)?;
+    log::info!(sample_id = merged.sample_id.as_str(); "Samples merged");
+
+    Ok(HttpResponse::Created().json(SampleResponse::from(&merged)))
+}
+
+/// Get the parents and children of a sample
+pub async fn get_sample_lineage(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    if db.get_sample(&sample_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("Sample {} not found", sample_id)));
+    }
+
+    Ok(HttpResponse::Ok().json(db.get_lineage(&sample_id)?))
+}
+
+/// Get the full chain of custody of a sample, oldest transfer first
+pub async fn get_sample_custody(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    if db.get_sample(&sample_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("Sample {} not found", sample_id)));
+    }
+
+    Ok(HttpResponse::Ok().json(db.get_custody_chain(&sample_id)?))
+}
+
+/// Get where a sample has been and who moved it, oldest first
+pub async fn get_sample_movements(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    if db.get_sample(&sample_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("Sample {} not found", sample_id)));
+    }
+
+    Ok(HttpResponse::Ok().json(db.get_movements(&sample_id)?))
+}
+
+/// Labels of a sample
+pub async fn get_sample_labels(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    if db.get_sample(&sample_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("Sample {} not found", sample_id)));
+    }
+
+    Ok(HttpResponse::Ok().json(db.get_labels(&sample_id)?))
+}
+
+/// Attach a label to a sample, returning its labels
+pub async fn add_sample_label(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<AddLabelRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    if db.get_sample(&sample_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("Sample {} not found", sample_id)));
+    }
+    db.add_label(&sample_id, &req.label).map_err(lineage_error)?;
+
+    Ok(HttpResponse::Ok().json(db.get_labels(&sample_id)?))
+}
+
+/// Detach a label from a sample
+pub async fn remove_sample_label(
+    state: web::Data<AppState>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (sample_id, label) = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    if db.remove_label(&sample_id, &label)? {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(ApiError::NotFound(format!("Sample {} has no label {}", sample_id, label)))
+    }
+}
+
+/// Rejected split/merge/archive input is a client error
+fn lineage_error(e: SampleGuardError) -> ApiError {
+    match e {
+        SampleGuardError::InvalidSampleData(msg) => ApiError::Validation(msg),
+        e => e.into(),
+    }
+}
+
+/// Bytes buffered before an export chunk is sent
+const EXPORT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Export chunks allowed to wait for a slow client before the export pauses
+const EXPORT_CHANNEL_CAPACITY: usize = 4;
+
+/// Stream every sample as CSV or JSONL without collecting them first
+pub async fn export_samples(
+    state: web::Data<AppState>,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let format: ExportFormat = query
+        .format
+        .as_deref()
+        .unwrap_or("jsonl")
+        .parse()
+        .map_err(|e: SampleGuardError| ApiError::Validation(e.to_string()))?;
+
+    let (writer, body) = streaming::channel(EXPORT_CHUNK_BYTES, EXPORT_CHANNEL_CAPACITY);
+    let database = Arc::clone(&state.database);
+    actix_web::rt::task::spawn_blocking(move || {
+        let db = match database.lock() {
+            Ok(db) => db,
+            Err(e) => return log::error!("Sample export failed: {}", e),
+        };
+        // The status line is already sent, so a failure can only truncate the body
+        match export::write_samples(&db, format, writer) {
+            Ok(count) => log::info!("Exported {} samples", count),
+            Err(e) => log::warn!("Sample export stopped: {}", e),
+        }
+    });
+
+    Ok(HttpResponse::Ok().content_type(format.content_type()).body(body))
+}
+
+/// Stream the samples matching the search parameters, or every sample when
+/// none are given, as CSV
+pub async fn export_samples_csv(
+    state: web::Data<AppState>,
+    query: web::Query<SampleSearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_query = query.to_sample_query().map_err(ApiError::Validation)?;
+    let filter = (sample_query != SampleQuery::default()).then_some(sample_query);
+
+    let (writer, body) = streaming::channel(EXPORT_CHUNK_BYTES, EXPORT_CHANNEL_CAPACITY);
+    let database = Arc::clone(&state.database);
+    actix_web::rt::task::spawn_blocking(move || {
+        let db = match database.lock() {
+            Ok(db) => db,
+            Err(e) => return log::error!("Sample export failed: {}", e),
+        };
+        match db.export_samples_to_csv(writer, filter.as_ref()) {
+            Ok(count) => log::info!("Exported {} samples", count),
+            Err(e) => log::warn!("Sample export stopped: {}", e),
+        }
+    });
+
+    Ok(HttpResponse::Ok().content_type(ExportFormat::Csv.content_type()).body(body))
+}
+
+/// Largest CSV accepted by a sample import
+const CSV_IMPORT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// Import samples from a CSV body in the export's layout. Invalid rows are
+/// reported per line and the rest imported.
+pub async fn import_samples_csv(
+    state: web::Data<AppState>,
+    query: web::Query<CsvImportQuery>,
+    payload: web::Payload,
+) -> Result<HttpResponse, ApiError> {
+    let body = payload
+        .to_bytes_limited(CSV_IMPORT_MAX_BYTES)
+        .await
+        .map_err(|_| ApiError::PayloadTooLarge(format!("Imports are limited to {} bytes", CSV_IMPORT_MAX_BYTES)))?
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let report = db
+        .import_samples_from_csv(body.as_ref(), query.on_conflict.unwrap_or(ConflictStrategy::Skip))
+        .map_err(|e| match e {
+            SampleGuardError::InvalidSampleData(msg) => ApiError::Validation(msg),
+            e => e.into(),
+        })?;
+    drop(db);
+
+    if !report.rolled_back {
+        let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+        logger.log_event(
+            AuditEventType::UserAction,
+            None,
+            None,
+            serde_json::json!({
+                "action": "csv_import",
+                "inserted": report.inserted(),
+                "updated": report.updated(),
+                "skipped": report.skipped(),
+                "failed": report.failed(),
+            }),
+            AuditSeverity::Info,
+        )?;
+    }
+    log::info!(inserted = report.inserted(), failed = report.failed(); "CSV import finished");
+
+    Ok(HttpResponse::Ok().json(ImportResponse {
+        inserted: report.inserted(),
+        updated: report.updated(),
+        skipped: report.skipped(),
+        failed: report.failed(),
+        rolled_back: report.rolled_back,
+        records: report.records,
+    }))
+}
+
 /// Get samples by batch
 pub async fn get_samples_by_batch(
     state: web::Data<AppState>,
     path: web::Path<String>,
+    query: web::Query<SamplePageQuery>,
 ) -> Result<HttpResponse, ApiError> {
     let batch_number = path.into_inner();
+    let (offset, per_page) = page_bounds(&query, state.max_page_size)?;
     let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
     
-    let samples = db.get_samples_by_batch(&batch_number)?;
-    let responses: Vec<SampleResponse> = samples.iter().map(SampleResponse::from).collect();
+    let samples = db.get_samples_by_batch_paged(&batch_number, offset, per_page, query.sort.unwrap_or_default())?;
     
+    Ok(HttpResponse::Ok().json(sample_page_response(&db, samples, offset, per_page)?))
+}
+
+/// Days ahead `GET /samples/expiring` looks when `days` is not given
+const DEFAULT_EXPIRING_DAYS: u32 = 30;
+
+/// Get samples that expire by the end of the day `days` from today, or
+/// every sample expiring at or before `before`
+pub async fn get_expiring_samples(
+    state: web::Data<AppState>,
+    query: web::Query<ExpiringSamplesQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let cutoff = query.cutoff().map_err(ApiError::Validation)?;
+    if cutoff.is_some() && query.days.is_some() {
+        return Err(ApiError::Validation("give either days or before, not both".to_string()));
+    }
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let samples = match cutoff {
+        Some(cutoff) => db.get_samples_expiring_before(cutoff)?,
+        None => db.get_samples_expiring_within(query.days.unwrap_or(DEFAULT_EXPIRING_DAYS))?,
+    };
+    let responses = sample_responses(&db, &samples)?;
+
+    Ok(HttpResponse::Ok().json(responses))
+}
+
+/// Samples matching every given search field, newest first
+pub async fn search_samples(
+    state: web::Data<AppState>,
+    query: web::Query<SampleSearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_query = query.to_sample_query().map_err(ApiError::Validation)?;
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let samples = db.search_samples(&sample_query)?;
+    let responses = sample_responses(&db, &samples)?;
+
+    Ok(HttpResponse::Ok().json(responses))
+}
+
+/// `search_samples` with the criteria given as a JSON `SampleQuery`, times
+/// in RFC 3339
+pub async fn search_samples_by_body(
+    state: web::Data<AppState>,
+    query: web::Json<SampleQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let samples = db.search_samples(&query)?;
+    let responses = sample_responses(&db, &samples)?;
+
+    Ok(HttpResponse::Ok().json(responses))
+}
+
+/// Get samples past their expiry date
+pub async fn get_expired_samples(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let samples = db.get_expired_samples()?;
+    let responses = sample_responses(&db, &samples)?;
+
     Ok(HttpResponse::Ok().json(responses))
 }
 
 /// Scan inventory
 pub async fn scan_inventory(
     state: web::Data<AppState>,
+    query: web::Query<InventoryScanQuery>,
 ) -> Result<HttpResponse, ApiError> {
+    state.require(|c| c.has_reader, Capabilities::RFID_READER)?;
     let mut inventory = state.inventory.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
     let mut reader = MockRFIDReader::new();
+    let max_results = query.max_results.or(inventory.max_results());
     
     // In a real implementation, this would use the actual reader from state
-    let results = inventory.scan_tags(&mut reader, std::time::Duration::from_millis(100))?;
+    let scan = inventory.scan_tags_capped(&mut reader, std::time::Duration::from_millis(100), max_results)?;
+
+    if !scan.events.is_empty() {
+        let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+        for event in &scan.events {
+            logger.log_inventory_event(event, None)?;
+        }
+    }
     
     Ok(HttpResponse::Ok().json(InventoryScanResponse {
-        tags: results.clone(),
-        count: results.len(),
+        count: scan.tags.len(),
+        truncated: scan.truncated,
+        total_seen: scan.total_seen,
+        events: scan.events,
+        tags: scan.tags,
         timestamp: Utc::now(),
     }))
 }
 
+/// Store a snapshot of the current inventory as a baseline
+pub async fn create_inventory_snapshot(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let snapshot = state.inventory.lock().map_err(|e| ApiError::Internal(e.to_string()))?.snapshot();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let id = db.save_inventory_snapshot(&snapshot)?;
+    
+    Ok(HttpResponse::Created().json(InventorySnapshotResponse {
+        id,
+        taken_at: snapshot.taken_at,
+        tag_count: snapshot.tags.len(),
+    }))
+}
+
+/// Compare a stored baseline with another snapshot or the current inventory
+pub async fn compare_inventory(
+    state: web::Data<AppState>,
+    req: web::Json<InventoryCompareRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let req = req.into_inner();
+    let current = state.inventory.lock().map_err(|e| ApiError::Internal(e.to_string()))?.snapshot();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let stored = |id: i64| {
+        db.get_inventory_snapshot(id)?
+            .ok_or_else(|| ApiError::NotFound(format!("Inventory snapshot {} not found", id)))
+    };
+    let baseline = stored(req.baseline_id)?;
+    let snapshot = match req.snapshot_id {
+        Some(id) => stored(id)?,
+        None => current,
+    };
+    
+    Ok(HttpResponse::Ok().json(InventoryCompareResponse {
+        baseline_id: req.baseline_id,
+        snapshot_id: req.snapshot_id,
+        diff: diff_snapshots(&baseline, &snapshot),
+    }))
+}
+
 /// Get inventory report
 pub async fn get_inventory_report(
     state: web::Data<AppState>,
@@ -180,21 +773,160 @@ pub async fn get_inventory_report(
     Ok(HttpResponse::Ok().json(report))
 }
 
-/// Read temperature
+/// Read temperature, linking any violation to samples at the reading's location
 pub async fn read_temperature(
     state: web::Data<AppState>,
+    query: web::Query<TemperatureReadQuery>,
 ) -> Result<HttpResponse, ApiError> {
+    state.require(|c| c.has_temperature_sensor, Capabilities::TEMPERATURE_SENSOR)?;
+    let query = query.into_inner();
     let mut monitor = state.temperature_monitor.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
-    let reading = monitor.read_temperature(None)?;
-    let violations = monitor.get_violations();
+    let (reading, violation) = monitor.read_temperature_checked(query.location)?;
+    for alert in monitor.take_alerts() {
+        match alert {
+            TemperatureAlert::Violation(v) => log::warn!(
+                sensor_id = v.reading.sensor_id.as_str();
+                "Temperature {} outside {:?} ({:?})", v.reading.temperature, v.expected_range, v.severity
+            ),
+            TemperatureAlert::Suppressed { sensor_id, count, .. } => log::warn!(
+                sensor_id = sensor_id.as_str();
+                "{} further temperature violations during the alert cooldown", count
+            ),
+            TemperatureAlert::SensorDisagreement(d) => log::warn!(
+                "Temperature sensors {:?} disagree with the median {}", d.outliers, d.median
+            ),
+        }
+    }
+
+    let mut affected_samples = Vec::new();
+    if reading.location.is_some() {
+        let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+        db.log_sample_temperature(&reading, monitor.get_expected_range())?;
+    }
+    if let Some(violation) = violation {
+        let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+        affected_samples = db.link_temperature_violation(&violation, query.flag_samples.unwrap_or(false))?;
+        
+        let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+        for sample_id in &affected_samples {
+            logger.log_temperature_violation(
+                Some(sample_id.clone()),
+                reading.temperature,
+                violation.expected_range,
+                None,
+            )?;
+        }
+    }
     
     Ok(HttpResponse::Ok().json(TemperatureResponse {
         reading: reading.clone(),
         within_range: monitor.is_within_range(reading.temperature),
-        violations: violations.len(),
+        violations: monitor.get_violations().len(),
+        affected_samples,
+    }))
+}
+
+/// Get temperature excursions that affected a sample
+pub async fn get_sample_temperature_excursions(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    
+    if db.get_sample(&sample_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("Sample {} not found", sample_id)));
+    }
+    let excursions = db.get_temperature_excursions(&sample_id)?;
+    
+    Ok(HttpResponse::Ok().json(excursions))
+}
+
+/// Get the temperature log of a sample with its time out of range
+pub async fn get_sample_temperature(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<SampleTemperatureQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    
+    if db.get_sample(&sample_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("Sample {} not found", sample_id)));
+    }
+    let readings = db.get_sample_temperature_log(&sample_id, query.since)?;
+    let summary = db.get_sample_temperature_summary(&sample_id, query.since, Utc::now())?;
+    
+    Ok(HttpResponse::Ok().json(SampleTemperatureResponse {
+        sample_id,
+        readings,
+        summary,
     }))
 }
 
+/// Set the primary image of a sample from a PNG or JPEG request body
+pub async fn set_sample_image(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if !images::is_image_content_type(content_type) {
+        return Err(ApiError::UnsupportedMediaType(format!("Expected an image, got '{}'", content_type)));
+    }
+
+    let limit = state.image_limits.max_bytes;
+    let body = payload
+        .to_bytes_limited(limit)
+        .await
+        .map_err(|_| ApiError::PayloadTooLarge(format!("Images are limited to {} bytes", limit)))?
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    if images::sniff_content_type(&body).is_none() {
+        return Err(ApiError::UnsupportedMediaType("Upload is not a PNG or JPEG image".to_string()));
+    }
+    let image = SampleImage::from_upload(body.to_vec(), &state.image_limits)
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    if db.get_sample(&sample_id)?.is_none() {
+        return Err(ApiError::NotFound(format!("Sample {} not found", sample_id)));
+    }
+    db.set_sample_image(&sample_id, &image)?;
+    
+    Ok(HttpResponse::Ok().json(image.reference))
+}
+
+/// Get the primary image of a sample
+pub async fn get_sample_image(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    sample_image_response(&state, &path.into_inner(), false)
+}
+
+/// Get the PNG thumbnail of a sample's primary image
+pub async fn get_sample_image_thumbnail(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    sample_image_response(&state, &path.into_inner(), true)
+}
+
+fn sample_image_response(state: &AppState, sample_id: &str, thumbnail: bool) -> Result<HttpResponse, ApiError> {
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let (content_type, data) = db
+        .get_sample_image_data(sample_id, thumbnail)?
+        .ok_or_else(|| ApiError::NotFound(format!("Sample {} has no image", sample_id)))?;
+    
+    Ok(HttpResponse::Ok().content_type(content_type).body(data))
+}
+
 /// Get temperature statistics
 pub async fn get_temperature_statistics(
     state: web::Data<AppState>,
@@ -205,6 +937,75 @@ pub async fn get_temperature_statistics(
     Ok(HttpResponse::Ok().json(stats))
 }
 
+/// A sensor's readings and violations stored in the database, which
+/// outlive the monitor's in-memory history when `temperature.database` is set
+pub async fn get_temperature_history(
+    state: web::Data<AppState>,
+    query: web::Query<TemperatureHistoryQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (from, to) = query.span(Utc::now()).map_err(ApiError::Validation)?;
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let readings = db.get_temperature_readings(&query.sensor_id, from, to)?;
+    let violations = db.get_temperature_violations(&query.sensor_id, from, to)?;
+
+    Ok(HttpResponse::Ok().json(TemperatureHistoryResponse {
+        sensor_id: query.sensor_id.clone(),
+        from,
+        to,
+        readings,
+        violations,
+    }))
+}
+
+/// Direction of the recent temperature trend and any excursion it predicts
+pub async fn get_temperature_trend(
+    state: web::Data<AppState>,
+    query: web::Query<TemperatureTrendQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if query.window.is_some_and(|w| w < 2) {
+        return Err(ApiError::Validation("window must be at least 2".to_string()));
+    }
+    let monitor = state.temperature_monitor.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let window = query.window.unwrap_or_else(|| monitor.trend_window());
+
+    Ok(HttpResponse::Ok().json(TemperatureTrendResponse {
+        direction: monitor.compute_trend(window),
+        predicted_violation_in_ms: monitor.predict_violation_in().map(|wait| wait.num_milliseconds()),
+        alert: monitor.trend_alert(),
+    }))
+}
+
+/// Stored temperature readings, or violations with `?violations=true`, as
+/// CSV, optionally limited to `start`..=`end`
+pub async fn export_temperature_csv(
+    state: web::Data<AppState>,
+    query: web::Query<TemperatureExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let violations = query.violations.unwrap_or(false);
+    temperature_csv_response(&state, &query, violations)
+}
+
+/// Stored temperature violations as CSV, optionally limited to `start`..=`end`
+pub async fn export_temperature_violations_csv(
+    state: web::Data<AppState>,
+    query: web::Query<TemperatureExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    temperature_csv_response(&state, &query, true)
+}
+
+fn temperature_csv_response(state: &AppState, query: &TemperatureExportQuery, violations: bool) -> Result<HttpResponse, ApiError> {
+    let range = query.date_range().map_err(ApiError::Validation)?;
+    let monitor = state.temperature_monitor.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let mut csv = Vec::new();
+    if violations {
+        monitor.export_violations_csv(&mut csv, range.as_ref())
+    } else {
+        monitor.export_readings_csv(&mut csv, range.as_ref())
+    }.map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok(HttpResponse::Ok().content_type(ExportFormat::Csv.content_type()).body(csv))
+}
+
 /// Get audit events
 pub async fn get_audit_events(
     state: web::Data<AppState>,
@@ -212,31 +1013,135 @@ pub async fn get_audit_events(
 ) -> Result<HttpResponse, ApiError> {
     let logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
     
-    let events = if let Some(sample_id) = query.get("sample_id") {
-        logger.get_events_by_sample(sample_id)
-    } else if let Some(event_type) = query.get("event_type") {
-        use crate::audit::AuditEventType;
-        let event_type_enum = match event_type.as_str() {
-            "SampleCreated" => AuditEventType::SampleCreated,
-            "SampleRead" => AuditEventType::SampleRead,
-            "ViolationDetected" => AuditEventType::ViolationDetected,
-            _ => return Err(ApiError::Validation(format!("Invalid event type: {}", event_type))),
-        };
-        logger.get_events_by_type(&event_type_enum)
-    } else {
-        logger.get_all_events()
+    let event_type = match query.get("event_type").map(String::as_str) {
+        None => None,
+        Some("SampleCreated") => Some(AuditEventType::SampleCreated),
+        Some("SampleRead") => Some(AuditEventType::SampleRead),
+        Some("ViolationDetected") => Some(AuditEventType::ViolationDetected),
+        Some(other) => return Err(ApiError::Validation(format!("Invalid event type: {}", other))),
     };
-    
-    let events_vec: Vec<&AuditEvent> = events;
-    let events_cloned: Vec<AuditEvent> = events_vec.iter().map(|e| (*e).clone()).collect();
-    let total = events_cloned.len();
+    let events = match query.get("sample_id") {
+        Some(sample_id) => logger.query_events(None, Some(sample_id), None, None, None),
+        None => logger.query_events(event_type.as_ref(), None, None, None, None),
+    }.map_err(|e| ApiError::Internal(e.to_string()))?;
+    let total = events.len();
     
     Ok(HttpResponse::Ok().json(AuditQueryResponse {
-        events: events_cloned,
+        events,
         total,
     }))
 }
 
+/// Events in an audit export page unless the request asks for fewer
+const AUDIT_EXPORT_DEFAULT_LIMIT: usize = 1000;
+
+/// Most events in one audit export page, bounding what a request copies
+const AUDIT_EXPORT_MAX_LIMIT: usize = 10_000;
+
+/// Header carrying the cursor of the next audit export page
+pub const NEXT_CURSOR_HEADER: &str = "X-Next-Cursor";
+
+/// Stream the audit events matching the query as a CSV (or JSONL)
+/// download, oldest first
+pub async fn export_audit_events_file(
+    state: web::Data<AppState>,
+    query: web::Query<AuditEventsExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let format: ExportFormat = query.format.as_deref().unwrap_or("csv").parse()
+        .map_err(|e: SampleGuardError| ApiError::Validation(e.to_string()))?;
+    let filter = query.to_filter().map_err(ApiError::Validation)?;
+    let events = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?.query(&filter)?;
+
+    let (mut writer, body) = streaming::channel(EXPORT_CHUNK_BYTES, EXPORT_CHANNEL_CAPACITY);
+    actix_web::rt::task::spawn_blocking(move || {
+        use std::io::Write;
+
+        let written = match format {
+            ExportFormat::Csv => crate::audit::write_events_csv(&events, &mut writer).map(drop),
+            ExportFormat::Jsonl => events.iter().try_for_each(|event| {
+                serde_json::to_writer(&mut writer, event)?;
+                writer.write_all(b"\n")?;
+                Ok::<_, SampleGuardError>(())
+            }),
+        };
+        if let Err(e) = written.and_then(|_| writer.flush().map_err(SampleGuardError::from)) {
+            log::warn!("Audit export stopped: {}", e);
+        }
+    });
+
+    let extension = match format {
+        ExportFormat::Csv => "csv",
+        ExportFormat::Jsonl => "jsonl",
+    };
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"audit-events.{}\"", extension)))
+        .body(body))
+}
+
+/// Move audit events older than the cutoff out of memory into a gzipped
+/// archive in `audit.archive_dir`
+pub async fn archive_audit_events(
+    state: web::Data<AppState>,
+    body: web::Json<AuditArchiveRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let dir = state.audit_archive_dir.as_ref()
+        .ok_or_else(|| ApiError::Validation("audit archiving needs audit.archive_dir to be set".to_string()))?;
+    if body.cutoff > Utc::now() {
+        return Err(ApiError::Validation("cutoff must not be in the future".to_string()));
+    }
+    let path = dir.join(format!("audit-{}.json.gz", Utc::now().format("%Y%m%dT%H%M%S%.6fZ")));
+
+    let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let report = logger.archive_events_before(body.cutoff, path)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Stream a page of audit events as JSONL, oldest first. The cursor of the
+/// next page is in `X-Next-Cursor`, which is absent once the page reaches
+/// the newest event.
+pub async fn export_audit_events(
+    state: web::Data<AppState>,
+    query: web::Query<AuditExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(str::parse::<AuditCursor>)
+        .transpose()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    let limit = query.limit.unwrap_or(AUDIT_EXPORT_DEFAULT_LIMIT);
+    if limit == 0 {
+        return Err(ApiError::Validation("limit must be at least 1".to_string()));
+    }
+
+    let (events, next) = {
+        let logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+        logger.export_batch(cursor, limit.min(AUDIT_EXPORT_MAX_LIMIT))
+    };
+
+    let (mut writer, body) = streaming::channel(EXPORT_CHUNK_BYTES, EXPORT_CHANNEL_CAPACITY);
+    actix_web::rt::task::spawn_blocking(move || {
+        use std::io::Write;
+
+        let written = events.iter().try_for_each(|event| {
+            serde_json::to_writer(&mut writer, event)?;
+            writer.write_all(b"\n")?;
+            Ok::<_, SampleGuardError>(())
+        });
+        if let Err(e) = written.and_then(|_| writer.flush().map_err(SampleGuardError::from)) {
+            log::warn!("Audit export stopped: {}", e);
+        }
+    });
+
+    let mut response = HttpResponse::Ok();
+    response.content_type(ExportFormat::Jsonl.content_type());
+    if let Some(next) = next {
+        response.insert_header((NEXT_CURSOR_HEADER, next.to_string()));
+    }
+    Ok(response.body(body))
+}
+
 /// Get audit statistics
 pub async fn get_audit_statistics(
     state: web::Data<AppState>,
@@ -247,16 +1152,101 @@ pub async fn get_audit_statistics(
     Ok(HttpResponse::Ok().json(stats))
 }
 
+/// Requests in flight and rejected by the connection limit
+pub async fn get_connection_metrics(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(state.connections.report()))
+}
+
+/// Hits and misses of the response caches
+pub async fn get_cache_metrics(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(CacheMetricsResponse {
+        statistics: state.statistics_cache.report(),
+    }))
+}
+
+/// Reader response-time histograms per reader type and command, recorded
+/// by the hardware driver
+pub async fn get_reader_latency(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    state.require(|c| c.has_hardware_driver, Capabilities::HARDWARE_DRIVER)?;
+    Ok(HttpResponse::Ok().json(state.reader_metrics.report()))
+}
+
+/// Get background job statuses
+pub async fn get_job_statuses(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(state.job_status.snapshot()))
+}
+
+/// List samples in cold storage
+pub async fn get_archived_samples(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(db.list_archived_samples()?))
+}
+
+/// Delete the samples seeded as demo data
+pub async fn purge_demo_data(
+    state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let purged = demo::purge_demo_data(&db)?;
+    log::info!("Purged {} demo samples", purged);
+    Ok(HttpResponse::Ok().json(DemoPurgeResponse { purged }))
+}
+
+/// Move a sample into cold storage
+pub async fn archive_sample(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let archived = db.archive_sample(&sample_id)
+        .map_err(lineage_error)?
+        .ok_or_else(|| ApiError::NotFound(format!("Sample {} not found", sample_id)))?;
+
+    let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    logger.log_sample_archived(&archived, None)?;
+    log::info!(sample_id = sample_id.as_str(); "Sample archived");
+
+    Ok(HttpResponse::Ok().json(archived))
+}
+
+/// Bring a sample back from cold storage
+pub async fn restore_sample(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sample_id = path.into_inner();
+    let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let sample = db.restore_sample(&sample_id)
+        .map_err(lineage_error)?
+        .ok_or_else(|| ApiError::NotFound(format!("No archived sample {}", sample_id)))?;
+
+    let mut logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
+    logger.log_sample_restored(&sample, None)?;
+    log::info!(sample_id = sample_id.as_str(); "Sample restored");
+
+    Ok(HttpResponse::Ok().json(SampleResponse::from(&sample)))
+}
+
 /// Get system statistics
 pub async fn get_statistics(
     state: web::Data<AppState>,
+    query: web::Query<StatisticsQuery>,
 ) -> Result<HttpResponse, ApiError> {
     let db = state.database.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
     let inventory = state.inventory.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
     let monitor = state.temperature_monitor.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
     let logger = state.audit_logger.lock().map_err(|e| ApiError::Internal(e.to_string()))?;
     
-    let db_stats = db.get_statistics()?;
+    let db_stats = state.statistics_cache.get_or_compute(
+        db.sample_generation(),
+        query.no_cache.unwrap_or(false),
+        || db.get_statistics(),
+    )?;
     let temp_stats = monitor.get_statistics();
     let audit_stats = logger.get_statistics();
     
@@ -293,6 +1283,18 @@ mod tests {
             temperature_monitor: Arc::new(Mutex::new(temperature_monitor)),
             audit_logger: Arc::new(Mutex::new(audit_logger)),
             sample_guard: Arc::new(Mutex::new(sample_guard)),
+            job_status: JobStatusRegistry::new(),
+            reader_metrics: ReaderMetrics::new(),
+            hardware_driver: None,
+            capabilities: Capabilities::all(),
+            storage_vocabulary: None,
+            image_limits: ImageLimits::default(),
+            connections: ConnectionLimiter::default(),
+            api_keys: ApiKeys::default(),
+            rate_limiter: RateLimiter::default(),
+            max_page_size: 1000,
+            statistics_cache: AggregateCache::default(),
+            audit_archive_dir: None,
         }
     }
 
@@ -303,14 +1305,14 @@ mod tests {
 
     #[actix_web::test]
     async fn test_health_check_handler() {
-        let resp = health_check().await.unwrap();
+        let resp = health_check(web::Data::new(create_test_state())).await.unwrap();
         assert_eq!(resp.status(), 200);
     }
 
     #[actix_web::test]
     async fn test_get_samples_empty() {
         let state = web::Data::new(create_test_state());
-        let result = get_samples(state).await;
+        let result = get_samples(state, web::Query(SamplePageQuery::default())).await;
         assert!(result.is_ok());
         let resp = result.unwrap();
         assert_eq!(resp.status(), 200);
@@ -368,7 +1370,8 @@ mod tests {
     #[actix_web::test]
     async fn test_read_temperature() {
         let state = web::Data::new(create_test_state());
-        let result = read_temperature(state).await;
+        let query = web::Query(TemperatureReadQuery { location: None, flag_samples: None });
+        let result = read_temperature(state, query).await;
         assert!(result.is_ok());
     }
 
@@ -389,7 +1392,22 @@ mod tests {
     #[actix_web::test]
     async fn test_get_statistics() {
         let state = web::Data::new(create_test_state());
-        let result = get_statistics(state).await;
+        let result = get_statistics(state, web::Query(StatisticsQuery::default())).await;
+        assert!(result.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn test_get_reader_latency() {
+        let state = create_test_state();
+        state.reader_metrics.record("Impinj Speedway", "ReadTag", 12);
+        let result = get_reader_latency(web::Data::new(state)).await;
+        assert!(result.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn test_get_job_statuses() {
+        let state = web::Data::new(create_test_state());
+        let result = get_job_statuses(state).await;
         assert!(result.is_ok());
     }
 }
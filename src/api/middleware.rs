@@ -0,0 +1,386 @@
+use crate::api::error::ApiError;
+use crate::api::handlers::AppState;
+use crate::config::{ApiKeyConfig, ApiRole, AuthConfig, RateLimitConfig};
+use crate::logging::with_correlation_id;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, HttpMessage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Header carrying the request's correlation ID, in both directions
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Tag everything logged while handling a request with a correlation ID,
+/// taken from the `X-Request-Id` header or generated, and echo it back
+pub async fn correlation_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let id = req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let mut res = with_correlation_id(id.clone(), next.call(req)).await?;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(res)
+}
+
+/// Path always admitted through the slot reserved by [`ConnectionLimiter`]
+pub const HEALTH_PATH: &str = "/api/v1/health";
+
+/// Caps the requests handled at once across all workers.
+///
+/// One slot above the limit is reserved for health checks, so the server can
+/// still report itself alive while saturated.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionLimiter {
+    max_connections: Option<usize>,
+    active: Arc<AtomicUsize>,
+    rejected: Arc<AtomicU64>,
+}
+
+/// Current load of a [`ConnectionLimiter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionReport {
+    pub active: usize,
+    pub max_connections: Option<usize>,
+    pub rejected: u64,
+}
+
+/// A held slot, released when dropped
+#[derive(Debug)]
+pub struct ConnectionPermit {
+    active: Arc<AtomicUsize>,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ConnectionLimiter {
+    /// Limiter admitting `max_connections` requests at once; unlimited when `None`
+    pub fn new(max_connections: Option<usize>) -> Self {
+        Self { max_connections, ..Self::default() }
+    }
+
+    /// Take a slot, using the reserved one if `reserved` and the rest are
+    /// taken. Returns `None`, counting a rejection, when none is free.
+    pub fn try_acquire(&self, reserved: bool) -> Option<ConnectionPermit> {
+        let capacity = self.max_connections.map(|max| if reserved { max + 1 } else { max });
+        let acquired = self.active.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| {
+            match capacity {
+                Some(capacity) if active >= capacity => None,
+                _ => Some(active + 1),
+            }
+        });
+        match acquired {
+            Ok(_) => Some(ConnectionPermit { active: Arc::clone(&self.active) }),
+            Err(_) => {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn report(&self) -> ConnectionReport {
+        ConnectionReport {
+            active: self.active.load(Ordering::SeqCst),
+            max_connections: self.max_connections,
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Answer 503 once the app's [`ConnectionLimiter`] is saturated, letting
+/// health checks through on the reserved slot
+pub async fn limit_connections(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    match state.connections.try_acquire(req.path() == HEALTH_PATH) {
+        Some(_permit) => next.call(req).await.map(ServiceResponse::map_into_left_body),
+        None => {
+            let error = ApiError::ServiceUnavailable("Too many concurrent requests".to_string());
+            Ok(req.error_response(error).map_into_right_body())
+        }
+    }
+}
+
+/// Header carrying an API key, as an alternative to `Authorization: Bearer`
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Caller identified by [`authenticate`], stored in the request extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiClient {
+    pub name: String,
+    pub role: ApiRole,
+}
+
+/// API keys accepted by [`authenticate`]; every request is let through
+/// when authentication is disabled
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys {
+    enabled: bool,
+    keys: Arc<Vec<ApiKeyConfig>>,
+}
+
+impl ApiKeys {
+    pub fn from_config(config: &AuthConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            keys: Arc::new(config.api_keys.clone()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Client owning `presented`, comparing against every key in constant time
+    pub fn identify(&self, presented: &str) -> Option<ApiClient> {
+        let mut found = None;
+        for key in self.keys.iter() {
+            if constant_time_eq(key.key.as_bytes(), presented.as_bytes()) && found.is_none() {
+                found = Some(ApiClient { name: key.name.clone(), role: key.role });
+            }
+        }
+        found
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Whether `role` may make a `method` request to `path`.
+///
+/// Read-only keys may only read; operators may also write, except under the
+/// admin scope, audit archiving, and deletions, which need an admin key.
+pub fn role_permits(role: ApiRole, method: &Method, path: &str) -> bool {
+    let read = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    let admin_only = path.starts_with("/api/v1/admin")
+        || path.starts_with("/api/v1/audit/archive")
+        || *method == Method::DELETE;
+    match role {
+        ApiRole::Admin => true,
+        ApiRole::Operator => read || !admin_only,
+        ApiRole::ReadOnly => read,
+    }
+}
+
+fn presented_key(req: &ServiceRequest) -> Option<&str> {
+    let headers = req.headers();
+    if let Some(key) = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some(key.trim());
+    }
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Require an API key allowed to make the request when the app's
+/// [`ApiKeys`] are enabled, answering 401 or 403 otherwise. Health checks
+/// are always let through.
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    if !state.api_keys.is_enabled() || req.path() == HEALTH_PATH {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    let client = match presented_key(&req) {
+        Some(key) => state.api_keys.identify(key),
+        None => {
+            let error = ApiError::Unauthorized("API key required".to_string());
+            return Ok(req.error_response(error).map_into_right_body());
+        }
+    };
+    let Some(client) = client else {
+        let error = ApiError::Unauthorized("Unknown API key".to_string());
+        return Ok(req.error_response(error).map_into_right_body());
+    };
+    if !role_permits(client.role, req.method(), req.path()) {
+        let error = ApiError::Forbidden(format!(
+            "API key '{}' may not {} {}",
+            client.name,
+            req.method(),
+            req.path()
+        ));
+        return Ok(req.error_response(error).map_into_right_body());
+    }
+    req.extensions_mut().insert(client);
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+/// Clients tracked before idle buckets are pruned
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Per-client token buckets refilled at `requests_per_second` up to `burst`
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    limit: Option<(f64, f64)>,
+    buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    /// Limiter enforcing `config`; unlimited when it is disabled
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let limit = config
+            .enabled
+            .then(|| (f64::from(config.requests_per_second), f64::from(config.burst)));
+        Self { limit, ..Self::default() }
+    }
+
+    /// Spend one of `client`'s tokens, or return how long until one is free
+    pub fn try_acquire(&self, client: &str) -> std::result::Result<(), Duration> {
+        self.try_acquire_at(client, Instant::now())
+    }
+
+    fn try_acquire_at(&self, client: &str, now: Instant) -> std::result::Result<(), Duration> {
+        let Some((rate, burst)) = self.limit else {
+            return Ok(());
+        };
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(client) {
+            // A bucket that would have refilled completely is the same as none
+            buckets.retain(|_, b| (now - b.refilled).as_secs_f64() * rate + b.tokens < burst);
+        }
+        let bucket = buckets
+            .entry(client.to_string())
+            .or_insert(TokenBucket { tokens: burst, refilled: now });
+        let elapsed = now.saturating_duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.refilled = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// Answer 429 once a client has spent its [`RateLimiter`] tokens. Clients
+/// are told apart by API key when [`authenticate`] ran first, else by peer
+/// address; health checks are never limited.
+pub async fn rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    if req.path() == HEALTH_PATH {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    let client = match req.extensions().get::<ApiClient>() {
+        Some(client) => format!("key:{}", client.name),
+        None => format!("addr:{}", req.peer_addr().map(|a| a.ip().to_string()).unwrap_or_default()),
+    };
+    match state.rate_limiter.try_acquire(&client) {
+        Ok(()) => next.call(req).await.map(ServiceResponse::map_into_left_body),
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            Ok(req.error_response(ApiError::TooManyRequests { retry_after }).map_into_right_body())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(requests_per_second: u32, burst: u32) -> RateLimiter {
+        RateLimiter::new(&RateLimitConfig { enabled: true, requests_per_second, burst })
+    }
+
+    #[test]
+    fn test_role_permits() {
+        let get = Method::GET;
+        let post = Method::POST;
+        assert!(role_permits(ApiRole::ReadOnly, &get, "/api/v1/samples"));
+        assert!(!role_permits(ApiRole::ReadOnly, &post, "/api/v1/samples"));
+        assert!(role_permits(ApiRole::Operator, &post, "/api/v1/samples"));
+        assert!(!role_permits(ApiRole::Operator, &Method::DELETE, "/api/v1/samples/S-1"));
+        assert!(!role_permits(ApiRole::Operator, &post, "/api/v1/admin/jobs/purge"));
+        assert!(!role_permits(ApiRole::Operator, &post, "/api/v1/audit/archive"));
+        assert!(role_permits(ApiRole::Operator, &get, "/api/v1/admin/jobs"));
+        assert!(role_permits(ApiRole::Admin, &Method::DELETE, "/api/v1/admin/demo-data"));
+    }
+
+    #[test]
+    fn test_identify_api_key() {
+        let keys = ApiKeys::from_config(&AuthConfig {
+            enabled: true,
+            api_keys: vec![ApiKeyConfig {
+                name: "lab".to_string(),
+                key: "0123456789abcdef".to_string(),
+                role: ApiRole::Operator,
+            }],
+        });
+        assert_eq!(
+            keys.identify("0123456789abcdef"),
+            Some(ApiClient { name: "lab".to_string(), role: ApiRole::Operator })
+        );
+        assert_eq!(keys.identify("0123456789abcdeX"), None);
+        assert_eq!(keys.identify("0123456789abcde"), None);
+    }
+
+    #[test]
+    fn test_token_bucket_refills_at_rate() {
+        let limiter = limiter(2, 3);
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.try_acquire_at("a", start).is_ok());
+        }
+        let wait = limiter.try_acquire_at("a", start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+        // Other clients have their own bucket
+        assert!(limiter.try_acquire_at("b", start).is_ok());
+        
+        assert!(limiter.try_acquire_at("a", start + Duration::from_millis(500)).is_ok());
+        assert!(limiter.try_acquire_at("a", start + Duration::from_millis(500)).is_err());
+        // Never refills beyond the burst
+        let later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.try_acquire_at("a", later).is_ok());
+        }
+        assert!(limiter.try_acquire_at("a", later).is_err());
+    }
+
+    #[test]
+    fn test_disabled_limiter_admits_everything() {
+        let limiter = RateLimiter::new(&RateLimitConfig::default());
+        for _ in 0..1000 {
+            assert!(limiter.try_acquire("a").is_ok());
+        }
+    }
+}
@@ -2,9 +2,13 @@ pub mod handlers;
 pub mod routes;
 pub mod models;
 pub mod error;
+pub mod demo;
+pub mod middleware;
 pub mod server;
+pub mod streaming;
+pub mod cache;
 
 pub use routes::configure_routes;
 pub use error::ApiError;
-pub use server::{create_app_state, start_server};
+pub use server::{create_app_state, create_app_state_from_config, start_server, start_server_with_config, start_server_with_state};
 
@@ -1,7 +1,12 @@
-use crate::sample::Sample;
-use crate::inventory::TagScanResult;
-use crate::temperature::TemperatureReading;
-use crate::audit::AuditEvent;
+use crate::sample::{Sample, SampleStatus};
+use crate::config::Capabilities;
+use crate::database::{BatchItem, ChildSpec, ConflictStrategy, ImportRecord, LabelMatch, SampleQuery, SampleTemperatureSummary, SortOrder};
+use crate::images::ImageReference;
+use crate::integrity::{ValidationDelta, Violation, Warning};
+use crate::inventory::{InventoryEvent, SnapshotDiff, TagScanResult};
+use crate::temperature::{DateRange, TemperatureReading, TemperatureViolation, TrendAlert, TrendDirection};
+use crate::audit::{AuditEvent, AuditEventType, AuditFilter, AuditSeverity};
+use crate::api::cache::CacheReport;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
@@ -19,11 +24,58 @@ pub struct CreateSampleRequest {
     pub location: Option<String>,
 }
 
+/// Query parameters for `POST /samples/batch`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BatchStoreQuery {
+    /// Discard the whole batch at the first sample that fails to store
+    pub all_or_nothing: Option<bool>,
+}
+
+/// Outcome of a batch create
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchStoreResponse {
+    pub stored: usize,
+    pub failed: usize,
+    /// Set when an all-or-nothing batch failed; nothing was stored
+    pub rolled_back: bool,
+    pub items: Vec<BatchItem>,
+}
+
 /// Request to update sample status
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateSampleStatusRequest {
     pub status: String,
     pub location: Option<String>,
+    /// Who is making the change, recorded in the sample's history
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+/// Request to split a sample into aliquots
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitSampleRequest {
+    pub children: Vec<ChildSpec>,
+}
+
+/// Request to create a sample from an existing one's metadata
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloneSampleRequest {
+    /// ID of the new sample
+    pub sample_id: String,
+}
+
+/// Request to pool samples into a new one
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergeSamplesRequest {
+    pub source_ids: Vec<String>,
+    /// ID of the pooled sample
+    pub sample_id: String,
+}
+
+/// Request to attach a label to a sample
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddLabelRequest {
+    pub label: String,
 }
 
 /// Response for sample operations
@@ -36,7 +88,33 @@ pub struct SampleResponse {
     pub location: Option<String>,
     pub created_at: DateTime<Utc>,
     pub last_updated: DateTime<Utc>,
+    /// Physical tag reads
     pub read_count: u64,
+    /// Reads of the sample through the API
+    #[serde(default)]
+    pub api_access_count: u64,
+    /// Photo of the sample or its label
+    #[serde(default)]
+    pub primary_image: Option<ImageReference>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+impl SampleResponse {
+    pub fn with_api_access_count(mut self, count: u64) -> Self {
+        self.api_access_count = count;
+        self
+    }
+
+    pub fn with_primary_image(mut self, image: Option<ImageReference>) -> Self {
+        self.primary_image = image;
+        self
+    }
+
+    pub fn with_labels(mut self, labels: Vec<String>) -> Self {
+        self.labels = labels;
+        self
+    }
 }
 
 impl From<&Sample> for SampleResponse {
@@ -50,6 +128,9 @@ impl From<&Sample> for SampleResponse {
             created_at: sample.created_at,
             last_updated: sample.last_updated,
             read_count: sample.read_count,
+            api_access_count: 0,
+            primary_image: None,
+            labels: Vec::new(),
         }
     }
 }
@@ -59,15 +140,351 @@ impl From<&Sample> for SampleResponse {
 pub struct InventoryScanResponse {
     pub tags: Vec<TagScanResult>,
     pub count: usize,
+    /// More tags were seen than `max_results` allowed
+    #[serde(default)]
+    pub truncated: bool,
+    /// Distinct tags seen, including those beyond the cap
+    #[serde(default)]
+    pub total_seen: usize,
+    /// Tags that arrived or departed since the previous scan
+    #[serde(default)]
+    pub events: Vec<InventoryEvent>,
     pub timestamp: DateTime<Utc>,
 }
 
+/// An inventory snapshot stored for later comparison
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InventorySnapshotResponse {
+    pub id: i64,
+    pub taken_at: DateTime<Utc>,
+    pub tag_count: usize,
+}
+
+/// Request to compare inventory against a stored baseline
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InventoryCompareRequest {
+    pub baseline_id: i64,
+    /// Stored snapshot to compare with; the current inventory when unset
+    #[serde(default)]
+    pub snapshot_id: Option<i64>,
+}
+
+/// Difference between a baseline and a later inventory
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InventoryCompareResponse {
+    pub baseline_id: i64,
+    pub snapshot_id: Option<i64>,
+    pub diff: SnapshotDiff,
+}
+
+/// Integrity validation of a sample and how it changed since the last one
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationResponse {
+    pub sample_id: String,
+    pub is_valid: bool,
+    pub violations: Vec<Violation>,
+    pub warnings: Vec<Warning>,
+    /// `None` the first time a sample is validated
+    pub delta: Option<ValidationDelta>,
+    pub validated_at: DateTime<Utc>,
+}
+
+/// Query parameters for an inventory scan
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InventoryScanQuery {
+    /// Stop collecting tags once this many have been seen
+    pub max_results: Option<usize>,
+}
+
+/// Query parameters for a sample export
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportQuery {
+    /// `csv` or `jsonl` (the default)
+    pub format: Option<String>,
+}
+
+/// Query parameters for a temperature CSV export
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TemperatureExportQuery {
+    /// Export violations instead of readings
+    pub violations: Option<bool>,
+    /// RFC 3339 time, or a `YYYY-MM-DD` date meaning its start in UTC
+    pub start: Option<String>,
+    pub end: Option<String>,
+}
+
+impl TemperatureExportQuery {
+    /// The requested time span, `None` when neither end is given
+    pub fn date_range(&self) -> Result<Option<DateRange>, String> {
+        let range = DateRange {
+            start: parse_query_time("start", self.start.as_deref())?,
+            end: parse_query_time("end", self.end.as_deref())?,
+        };
+        Ok((range != DateRange::default()).then_some(range))
+    }
+}
+
+/// Query parameters for a CSV sample import
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CsvImportQuery {
+    /// What to do with rows whose sample id is already stored; `skip` by default
+    pub on_conflict: Option<ConflictStrategy>,
+}
+
+/// Outcome of a CSV sample import
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportResponse {
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// Set when a conflict under `on_conflict=fail` aborted the import;
+    /// nothing was stored
+    pub rolled_back: bool,
+    pub records: Vec<ImportRecord>,
+}
+
+/// Query parameters for a paged sample listing
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SamplePageQuery {
+    /// 1-based page number, 1 when unset
+    pub page: Option<usize>,
+    /// Samples per page, 50 when unset; at most 500, and capped at the
+    /// server's maximum
+    #[serde(alias = "page_size")]
+    pub per_page: Option<usize>,
+    /// Alternative to `per_page`
+    pub limit: Option<usize>,
+    /// Samples to skip; an alternative to `page`
+    pub offset: Option<usize>,
+    /// `created_at` (the default), `last_updated`, `sample_id`,
+    /// `expiry_date` or `batch_number`
+    #[serde(alias = "order_by")]
+    pub sort: Option<SortOrder>,
+    /// Comma-separated labels the samples must carry
+    pub label: Option<String>,
+    /// `all` (the default) to require every label, `any` for at least one
+    pub label_match: Option<LabelMatch>,
+}
+
+impl SamplePageQuery {
+    /// Labels named by `label`, without blanks
+    pub fn labels(&self) -> Vec<&str> {
+        self.label
+            .as_deref()
+            .map(|labels| labels.split(',').map(str::trim).filter(|l| !l.is_empty()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// One page of a sample listing
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SamplePageResponse {
+    pub items: Vec<SampleResponse>,
+    /// Matching samples across all pages
+    pub total: usize,
+    pub has_more: bool,
+    /// Page holding `offset`, counting from 1
+    pub page: usize,
+    pub per_page: usize,
+    pub total_pages: usize,
+    pub offset: usize,
+}
+
+/// Query parameters for samples nearing expiry
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExpiringSamplesQuery {
+    /// Days ahead to look, 30 when unset
+    pub days: Option<u32>,
+    /// Instead of `days`, every sample expiring at or before this RFC 3339
+    /// time or `YYYY-MM-DD` date, including those already expired
+    pub before: Option<String>,
+}
+
+impl ExpiringSamplesQuery {
+    pub fn cutoff(&self) -> Result<Option<DateTime<Utc>>, String> {
+        parse_query_time("before", self.before.as_deref())
+    }
+}
+
+/// Query parameters for a sample search; every given field must match
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SampleSearchQuery {
+    pub id_contains: Option<String>,
+    pub batch_number: Option<String>,
+    /// Status name, e.g. `Stored`
+    pub status: Option<SampleStatus>,
+    pub manufacturer: Option<String>,
+    pub product_line: Option<String>,
+    pub location_contains: Option<String>,
+    /// RFC 3339 time, or a `YYYY-MM-DD` date meaning its start in UTC
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub expiring_before: Option<String>,
+}
+
+impl SampleSearchQuery {
+    pub fn to_sample_query(&self) -> Result<SampleQuery, String> {
+        Ok(SampleQuery {
+            id_contains: self.id_contains.clone(),
+            batch_number: self.batch_number.clone(),
+            status: self.status,
+            manufacturer: self.manufacturer.clone(),
+            product_line: self.product_line.clone(),
+            location_contains: self.location_contains.clone(),
+            created_after: parse_query_time("created_after", self.created_after.as_deref())?,
+            created_before: parse_query_time("created_before", self.created_before.as_deref())?,
+            expiring_before: parse_query_time("expiring_before", self.expiring_before.as_deref())?,
+        })
+    }
+}
+
+/// Read a time query parameter given as RFC 3339 or as a `YYYY-MM-DD`
+/// date, which means the start of that day in UTC
+fn parse_query_time(field: &str, value: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    if let Ok(time) = value.parse::<DateTime<Utc>>() {
+        return Ok(Some(time));
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| Some(date.and_time(chrono::NaiveTime::MIN).and_utc()))
+        .map_err(|_| format!("{} must be an RFC 3339 time or YYYY-MM-DD date, got '{}'", field, value))
+}
+
+/// Query parameters for the statistics endpoint
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StatisticsQuery {
+    /// Recompute rather than reuse cached aggregates
+    pub no_cache: Option<bool>,
+}
+
+/// Query parameters for a paged audit export
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditExportQuery {
+    /// `X-Next-Cursor` of the previous page; starts at the oldest event when unset
+    pub cursor: Option<String>,
+    /// Most events in the page
+    pub limit: Option<usize>,
+}
+
+/// Query parameters for a filtered audit event export
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditEventsExportQuery {
+    /// `csv` (the default) or `jsonl`
+    pub format: Option<String>,
+    /// RFC 3339 time, or a `YYYY-MM-DD` date meaning its start in UTC
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub event_type: Option<AuditEventType>,
+    pub sample_id: Option<String>,
+    pub severity: Option<AuditSeverity>,
+}
+
+impl AuditEventsExportQuery {
+    pub fn to_filter(&self) -> Result<AuditFilter, String> {
+        Ok(AuditFilter {
+            event_type: self.event_type.clone(),
+            sample_id: self.sample_id.clone(),
+            severity: self.severity.clone(),
+            start_time: parse_query_time("start", self.start.as_deref())?,
+            end_time: parse_query_time("end", self.end.as_deref())?,
+        })
+    }
+}
+
+/// Body of an audit archive request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditArchiveRequest {
+    /// Events logged before this time are archived
+    pub cutoff: DateTime<Utc>,
+}
+
+/// Query parameters for a sensor's stored temperature history
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TemperatureHistoryQuery {
+    pub sensor_id: String,
+    /// RFC 3339 time, or a `YYYY-MM-DD` date meaning its start in UTC;
+    /// a day before `to` when unset
+    pub from: Option<String>,
+    /// Now when unset
+    pub to: Option<String>,
+}
+
+impl TemperatureHistoryQuery {
+    /// The requested span, defaulting as documented on the fields
+    pub fn span(&self, now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+        let to = parse_query_time("to", self.to.as_deref())?.unwrap_or(now);
+        let from = parse_query_time("from", self.from.as_deref())?.unwrap_or(to - chrono::Duration::days(1));
+        if from > to {
+            return Err("from must not be after to".to_string());
+        }
+        Ok((from, to))
+    }
+}
+
+/// A sensor's stored readings and violations over a span, oldest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemperatureHistoryResponse {
+    pub sensor_id: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub readings: Vec<TemperatureReading>,
+    pub violations: Vec<TemperatureViolation>,
+}
+
+/// Query parameters for the temperature trend
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TemperatureTrendQuery {
+    /// Readings the direction is fitted to; the monitor's trend window
+    /// when unset
+    pub window: Option<usize>,
+}
+
+/// Recent temperature trend and the excursion it predicts
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemperatureTrendResponse {
+    /// `None` until there are two readings
+    pub direction: Option<TrendDirection>,
+    /// Milliseconds until the trend leaves the expected range
+    pub predicted_violation_in_ms: Option<i64>,
+    pub alert: Option<TrendAlert>,
+}
+
+/// Query parameters for a temperature reading
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemperatureReadQuery {
+    /// Location the sensor is monitoring
+    pub location: Option<String>,
+    /// Mark co-located samples as compromised on a violation
+    pub flag_samples: Option<bool>,
+}
+
+/// Query parameters for a sample's temperature log
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SampleTemperatureQuery {
+    /// Only readings taken at or after this time
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// A sample's temperature log with its time out of range
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SampleTemperatureResponse {
+    pub sample_id: String,
+    pub readings: Vec<TemperatureReading>,
+    pub summary: SampleTemperatureSummary,
+}
+
 /// Response for temperature reading
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TemperatureResponse {
     pub reading: TemperatureReading,
     pub within_range: bool,
     pub violations: usize,
+    /// Samples linked to a violation caused by this reading
+    #[serde(default)]
+    pub affected_samples: Vec<String>,
 }
 
 /// Response for audit query
@@ -77,12 +494,20 @@ pub struct AuditQueryResponse {
     pub total: usize,
 }
 
+/// Demo samples removed by a purge
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DemoPurgeResponse {
+    pub purged: usize,
+}
+
 /// Health check response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub capabilities: Option<Capabilities>,
 }
 
 /// Statistics response
@@ -94,3 +519,9 @@ pub struct StatisticsResponse {
     pub audit_events: usize,
 }
 
+/// Effectiveness of the response caches
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheMetricsResponse {
+    pub statistics: CacheReport,
+}
+
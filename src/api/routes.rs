@@ -6,30 +6,77 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/v1")
             .route("/health", web::get().to(health_check))
+            .route("/capabilities", web::get().to(get_capabilities))
             .route("/statistics", web::get().to(get_statistics))
             .service(
                 web::scope("/samples")
                     .route("", web::get().to(get_samples))
                     .route("", web::post().to(create_sample))
+                    .route("/batch", web::post().to(create_samples_batch))
+                    .route("/merge", web::post().to(merge_samples))
+                    .route("/export", web::get().to(export_samples))
+                    .route("/export.csv", web::get().to(export_samples_csv))
+                    .route("/import", web::post().to(import_samples_csv))
+                    .route("/expiring", web::get().to(get_expiring_samples))
+                    .route("/expired", web::get().to(get_expired_samples))
+                    .route("/search", web::get().to(search_samples))
+                    .route("/search", web::post().to(search_samples_by_body))
                     .route("/{sample_id}", web::get().to(get_sample))
                     .route("/{sample_id}/status", web::put().to(update_sample_status))
+                    .route("/{sample_id}/validate", web::post().to(validate_sample))
+                    .route("/{sample_id}/image", web::put().to(set_sample_image))
+                    .route("/{sample_id}/image", web::get().to(get_sample_image))
+                    .route("/{sample_id}/image/thumbnail", web::get().to(get_sample_image_thumbnail))
+                    .route("/{sample_id}/temperature", web::get().to(get_sample_temperature))
+                    .route("/{sample_id}/temperature-excursions", web::get().to(get_sample_temperature_excursions))
+                    .route("/{sample_id}/split", web::post().to(split_sample))
+                    .route("/{sample_id}/clone", web::post().to(clone_sample))
+                    .route("/{sample_id}/lineage", web::get().to(get_sample_lineage))
+                    .route("/{sample_id}/custody", web::get().to(get_sample_custody))
+                    .route("/{sample_id}/movements", web::get().to(get_sample_movements))
+                    .route("/{sample_id}/labels", web::get().to(get_sample_labels))
+                    .route("/{sample_id}/labels", web::post().to(add_sample_label))
+                    .route("/{sample_id}/labels/{label}", web::delete().to(remove_sample_label))
                     .route("/{sample_id}", web::delete().to(delete_sample))
                     .route("/batch/{batch_number}", web::get().to(get_samples_by_batch)),
             )
             .service(
                 web::scope("/inventory")
                     .route("/scan", web::post().to(scan_inventory))
-                    .route("/report", web::get().to(get_inventory_report)),
+                    .route("/report", web::get().to(get_inventory_report))
+                    .route("/snapshots", web::post().to(create_inventory_snapshot))
+                    .route("/compare", web::post().to(compare_inventory)),
             )
             .service(
                 web::scope("/temperature")
                     .route("/read", web::post().to(read_temperature))
-                    .route("/statistics", web::get().to(get_temperature_statistics)),
+                    .route("/statistics", web::get().to(get_temperature_statistics))
+                    .route("/trend", web::get().to(get_temperature_trend))
+                    .route("/history", web::get().to(get_temperature_history))
+                    .route("/export.csv", web::get().to(export_temperature_csv))
+                    .route("/violations/export.csv", web::get().to(export_temperature_violations_csv)),
             )
             .service(
                 web::scope("/audit")
                     .route("/events", web::get().to(get_audit_events))
+                    .route("/events/export", web::get().to(export_audit_events_file))
+                    .route("/export", web::get().to(export_audit_events))
+                    .route("/archive", web::post().to(archive_audit_events))
                     .route("/statistics", web::get().to(get_audit_statistics)),
+            )
+            .service(
+                web::scope("/admin")
+                    .route("/jobs", web::get().to(get_job_statuses))
+                    .route("/demo-data", web::delete().to(purge_demo_data))
+                    .route("/archive", web::get().to(get_archived_samples))
+                    .route("/archive/{sample_id}", web::post().to(archive_sample))
+                    .route("/archive/{sample_id}/restore", web::post().to(restore_sample)),
+            )
+            .service(
+                web::scope("/metrics")
+                    .route("/reader-latency", web::get().to(get_reader_latency))
+                    .route("/connections", web::get().to(get_connection_metrics))
+                    .route("/cache", web::get().to(get_cache_metrics)),
             ),
     );
 }
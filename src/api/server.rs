@@ -1,48 +1,154 @@
 use crate::api::handlers::AppState;
+use crate::api::demo;
 use crate::api::routes::configure_routes;
+use crate::config::{parse_duration, SampleGuardConfig, ServerConfig};
 use crate::database::Database;
+use crate::error::Result;
 use crate::inventory::InventoryManager;
+use crate::hardware::metrics::ReaderMetrics;
+use crate::hardware::HardwareDriver;
+use crate::jobs::{JobContext, JobStatusRegistry};
 use crate::temperature::{TemperatureMonitor, MockTemperatureSensor};
-use crate::audit::AuditLogger;
+use crate::audit::{AuditLogger, DatabaseAuditSink};
 use crate::reader::MockRFIDReader;
 use crate::SampleGuard;
+use crate::vocabulary::StorageVocabulary;
+use crate::images::ImageLimits;
+use crate::notifications::NotificationDispatcher;
+use crate::transitions::NotificationHook;
+use crate::api::cache::{AggregateCache, DEFAULT_STATISTICS_TTL};
+use crate::api::middleware::{authenticate, correlation_id, limit_connections, rate_limit, ApiKeys, ConnectionLimiter, RateLimiter};
 use actix_web::{web, App, HttpServer};
 use std::sync::{Arc, Mutex};
 
 /// Create application state
 pub fn create_app_state() -> AppState {
-    // Create in-memory database for testing/demo
-    let database = Database::in_memory()
-        .expect("Failed to create database");
-    
+    create_app_state_from_config(&SampleGuardConfig::default())
+        .expect("Failed to create application state")
+}
+
+/// Create application state using the database and audit settings from `config`
+pub fn create_app_state_from_config(config: &SampleGuardConfig) -> Result<AppState> {
+    // Fall back to an in-memory database for testing/demo
+    let mut database = Database::from_config(&config.database)?;
+    if !config.webhooks.is_empty() {
+        let dispatcher = NotificationDispatcher::from_config(&config.webhooks)?;
+        database.register_transition_hook(Arc::new(NotificationHook::new(dispatcher)));
+    }
+
     let inventory = InventoryManager::new();
-    let sensor = Box::new(MockTemperatureSensor::new("API-SENSOR".to_string(), 5.0));
-    let temperature_monitor = TemperatureMonitor::new(sensor, (2.0, 8.0))
-        .expect("Failed to create temperature monitor");
-    let audit_logger = AuditLogger::new();
+    let temperature = &config.temperature;
+    let sensor = Box::new(MockTemperatureSensor::new(
+        temperature.sensor_id.clone(),
+        (temperature.min_celsius + temperature.max_celsius) / 2.0,
+    ));
+    let mut temperature_monitor = TemperatureMonitor::new(sensor, (temperature.min_celsius, temperature.max_celsius))?;
+    if let Some(cooldown) = &temperature.alert_cooldown {
+        let cooldown = chrono::Duration::from_std(parse_duration(cooldown)?).unwrap_or(chrono::Duration::MAX);
+        temperature_monitor = temperature_monitor.with_alert_cooldown(cooldown);
+    }
+    if temperature.database {
+        match &config.database.path {
+            Some(path) => temperature_monitor = temperature_monitor.with_database(Database::new(path)?),
+            None => log::warn!("temperature.database needs database.path; temperature history is not persisted"),
+        }
+    }
+    temperature_monitor.on_trend_alert(Box::new(|alert| {
+        log::warn!(
+            "Temperature trend {:?} predicts an excursion at {}",
+            alert.direction,
+            alert.predicted_violation_at.to_rfc3339()
+        );
+    }));
+    let mut audit_logger = AuditLogger::from_config(&config.audit)?;
+    if config.audit.database {
+        match &config.database.path {
+            Some(path) => audit_logger = audit_logger.with_sink(DatabaseAuditSink::open(path)?),
+            None => log::warn!("audit.database needs database.path; audit events are not persisted"),
+        }
+    }
+    if config.server.seed_demo_data {
+        let seeded = demo::seed_demo_data(&database, &mut audit_logger, config.server.demo_sample_count)?;
+        log::info!("Seeded {} demo samples", seeded.len());
+    }
     let reader = Box::new(MockRFIDReader::new());
-    let sample_guard = SampleGuard::new(reader);
-    
-    AppState {
+    let sample_guard = SampleGuard::new(reader).with_allow_legacy_checksums(config.database.allow_legacy_checksums);
+    let reader_metrics = ReaderMetrics::new();
+    let hardware_driver = if config.capabilities().has_hardware_driver {
+        let mut driver = HardwareDriver::with_metrics(reader_metrics.clone());
+        if let Err(e) = driver.initialize_all() {
+            log::warn!("Hardware readers failed to initialize: {}", e);
+        }
+        Some(Arc::new(Mutex::new(driver)))
+    } else {
+        None
+    };
+    let statistics_ttl = match &config.server.statistics_cache_ttl {
+        Some(ttl) => parse_duration(ttl)?,
+        None => DEFAULT_STATISTICS_TTL,
+    };
+
+    Ok(AppState {
         database: Arc::new(Mutex::new(database)),
         inventory: Arc::new(Mutex::new(inventory)),
         temperature_monitor: Arc::new(Mutex::new(temperature_monitor)),
         audit_logger: Arc::new(Mutex::new(audit_logger)),
         sample_guard: Arc::new(Mutex::new(sample_guard)),
+        job_status: JobStatusRegistry::new(),
+        reader_metrics,
+        hardware_driver,
+        capabilities: config.capabilities(),
+        storage_vocabulary: StorageVocabulary::from_terms(&config.samples.storage_conditions),
+        image_limits: ImageLimits::from_config(&config.samples),
+        connections: ConnectionLimiter::new(config.server.max_connections),
+        api_keys: ApiKeys::from_config(&config.auth),
+        rate_limiter: RateLimiter::new(&config.rate_limit),
+        max_page_size: config.server.max_page_size,
+        statistics_cache: AggregateCache::new(statistics_ttl),
+        audit_archive_dir: config.audit.archive_dir.clone(),
+    })
+}
+
+impl AppState {
+    /// Resources shared with background jobs
+    pub fn job_context(&self) -> JobContext {
+        JobContext {
+            database: Arc::clone(&self.database),
+            temperature_monitor: Arc::clone(&self.temperature_monitor),
+            audit_logger: Arc::clone(&self.audit_logger),
+            inventory: Arc::clone(&self.inventory),
+        }
     }
 }
 
 /// Start the HTTP server
 pub async fn start_server(host: &str, port: u16) -> std::io::Result<()> {
-    let app_state = create_app_state();
-    
-    HttpServer::new(move || {
+    start_server_with_state(create_app_state(), host, port).await
+}
+
+/// Start the HTTP server with pre-built application state
+pub async fn start_server_with_state(app_state: AppState, host: &str, port: u16) -> std::io::Result<()> {
+    let config = ServerConfig { host: host.to_string(), port, ..ServerConfig::default() };
+    start_server_with_config(app_state, &config).await
+}
+
+/// Start the HTTP server with pre-built application state and the
+/// connection limits from `config`
+pub async fn start_server_with_config(app_state: AppState, config: &ServerConfig) -> std::io::Result<()> {
+    let mut server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(app_state.clone()))
+            .wrap(actix_web::middleware::from_fn(rate_limit))
+            .wrap(actix_web::middleware::from_fn(authenticate))
+            .wrap(actix_web::middleware::from_fn(limit_connections))
+            .wrap(actix_web::middleware::from_fn(correlation_id))
             .configure(configure_routes)
-    })
-    .bind((host, port))?
-    .run()
-    .await
+    });
+    if let Some(max) = config.max_connections_per_worker {
+        server = server.max_connections(max);
+    }
+    server
+        .bind((config.host.as_str(), config.port))?
+        .run()
+        .await
 }
-
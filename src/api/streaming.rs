@@ -0,0 +1,107 @@
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::web::Bytes;
+use std::convert::Infallible;
+use std::io::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Create a writer for a blocking producer and the response body it feeds.
+///
+/// The writer sends `chunk_bytes`-sized chunks and blocks once `capacity`
+/// chunks are waiting, so at most about `chunk_bytes * (capacity + 1)` bytes
+/// are buffered however much is written.
+pub fn channel(chunk_bytes: usize, capacity: usize) -> (ChannelWriter, ChannelBody) {
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    let writer = ChannelWriter {
+        tx,
+        buffer: Vec::with_capacity(chunk_bytes),
+        chunk_bytes: chunk_bytes.max(1),
+    };
+    (writer, ChannelBody { rx })
+}
+
+/// Blocking `Write` end of [`channel`]; must not be used on an async thread
+pub struct ChannelWriter {
+    tx: mpsc::Sender<Bytes>,
+    buffer: Vec<u8>,
+    chunk_bytes: usize,
+}
+
+impl ChannelWriter {
+    fn send_buffer(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::replace(&mut self.buffer, Vec::with_capacity(self.chunk_bytes)));
+        self.tx
+            .blocking_send(chunk)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Response body was dropped"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= self.chunk_bytes {
+            self.send_buffer()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.send_buffer()
+    }
+}
+
+/// Response body yielding chunks as the producer sends them; ends when the
+/// writer is dropped
+pub struct ChannelBody {
+    rx: mpsc::Receiver<Bytes>,
+}
+
+impl MessageBody for ChannelBody {
+    type Error = Infallible;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        self.get_mut().rx.poll_recv(cx).map(|chunk| chunk.map(Ok))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_writer_sends_bounded_chunks() {
+        let (mut writer, body) = channel(64, 2);
+        let producer = actix_web::rt::task::spawn_blocking(move || {
+            for i in 0..1000 {
+                writeln!(writer, "row {}", i).unwrap();
+            }
+            writer.flush().unwrap();
+        });
+
+        let mut rx = body.rx;
+        let mut received = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            // A chunk is sent as soon as it reaches the limit, so it overshoots by at most one write
+            assert!(chunk.len() < 64 + "row 999\n".len());
+            received.extend_from_slice(&chunk);
+        }
+        producer.await.unwrap();
+        assert_eq!(String::from_utf8(received).unwrap().lines().count(), 1000);
+    }
+
+    #[actix_web::test]
+    async fn test_writer_fails_once_body_is_dropped() {
+        let (mut writer, body) = channel(8, 1);
+        drop(body);
+        let result = actix_web::rt::task::spawn_blocking(move || writer.write_all(&[0; 32])).await.unwrap();
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+    }
+}
@@ -1,11 +1,16 @@
 use crate::error::{SampleGuardError, Result};
+use crate::config::AuditConfig;
+use crate::database::{ArchivedSample, Database};
+use crate::inventory::InventoryEvent;
 use crate::sample::{Sample, SampleStatus};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Audit event type
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +30,14 @@ pub enum AuditEventType {
     SystemShutdown,
     UserAction,
     ConfigurationChanged,
+    JobExecuted,
+    ExpiryWarning,
+    SampleSplit,
+    SamplesMerged,
+    SampleArchived,
+    SampleRestored,
+    TagArrived,
+    TagDeparted,
 }
 
 /// Audit event
@@ -37,6 +50,38 @@ pub struct AuditEvent {
     pub sample_id: Option<String>,
     pub details: serde_json::Value,
     pub severity: AuditSeverity,
+    /// `event_hash` of the event logged before this one, all zeros for the
+    /// first; set when the logger chains hashes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<[u8; 32]>,
+    /// SHA-256 of `prev_hash` followed by the event serialized without
+    /// this field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_hash: Option<[u8; 32]>,
+}
+
+impl AuditEvent {
+    /// Hash the event should carry in a chain, from its `prev_hash` and
+    /// every other field
+    pub fn compute_hash(&self) -> [u8; 32] {
+        use sha2::{Digest, Sha256};
+
+        let unhashed = AuditEvent { event_hash: None, ..self.clone() };
+        let mut hasher = Sha256::new();
+        hasher.update(self.prev_hash.unwrap_or_default());
+        hasher.update(serde_json::to_vec(&unhashed).unwrap_or_default());
+        hasher.finalize().into()
+    }
+
+    /// Raw tag bytes attached to the event, for replaying a failed read
+    /// offline. Truncated payloads hold only their first bytes; check
+    /// `details.raw_tag.truncated`.
+    pub fn raw_tag(&self) -> Option<Vec<u8>> {
+        use base64::Engine;
+
+        let data = self.details.get("raw_tag")?.get("data")?.as_str()?;
+        base64::engine::general_purpose::STANDARD.decode(data).ok()
+    }
 }
 
 /// Audit severity level
@@ -48,11 +93,373 @@ pub enum AuditSeverity {
     Critical,
 }
 
+/// Flush policy for audit file output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// Flush after every event (strict durability)
+    #[default]
+    Immediate,
+    /// Flush once `max_pending` events are buffered or `max_delay` has passed since the last flush.
+    /// The delay is checked when events are logged; call `flush()` to force pending writes out.
+    Batched { max_pending: usize, max_delay: Duration },
+}
+
+/// Position in the audit log for resuming a paged export. Positions count
+/// every event ever logged, so appends and evictions do not shift them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AuditCursor(u64);
+
+impl std::fmt::Display for AuditCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for AuditCursor {
+    type Err = SampleGuardError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse()
+            .map(AuditCursor)
+            .map_err(|_| SampleGuardError::InvalidSampleData(format!("Invalid audit cursor: {}", s)))
+    }
+}
+
+/// Criteria for an audit event query; unset fields match every event
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub event_type: Option<AuditEventType>,
+    pub sample_id: Option<String>,
+    pub severity: Option<AuditSeverity>,
+    /// Earliest timestamp, inclusive
+    pub start_time: Option<DateTime<Utc>>,
+    /// Latest timestamp, inclusive
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl AuditFilter {
+    /// Whether `event` meets every criterion
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        self.event_type.as_ref().is_none_or(|t| event.event_type == *t)
+            && self.sample_id.as_deref().is_none_or(|id| event.sample_id.as_deref() == Some(id))
+            && self.severity.as_ref().is_none_or(|s| event.severity == *s)
+            && self.start_time.is_none_or(|start| event.timestamp >= start)
+            && self.end_time.is_none_or(|end| event.timestamp <= end)
+    }
+}
+
+/// Further destination for logged events, alongside the in-memory log and
+/// the JSON-lines file
+pub trait AuditSink: Send {
+    /// Keep `event`
+    fn record(&mut self, event: &AuditEvent) -> Result<()>;
+
+    /// Write out anything buffered
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Events matching `filter`, oldest first, or `None` if the sink cannot
+    /// be queried
+    fn query(&self, _filter: &AuditFilter) -> Option<Result<Vec<AuditEvent>>> {
+        None
+    }
+
+    /// Every stored event in the order it was recorded, or `None` if the
+    /// sink cannot be read back
+    fn recorded_events(&self) -> Option<Result<Vec<AuditEvent>>> {
+        None
+    }
+
+    /// The newest stored event, or `None` if the sink cannot be read back
+    fn last_recorded(&self) -> Option<Result<Option<AuditEvent>>> {
+        self.recorded_events().map(|events| events.map(|mut events| events.pop()))
+    }
+}
+
+/// Sink keeping events in the `audit_events` table, so they outlive the
+/// process and can be queried after a restart
+pub struct DatabaseAuditSink {
+    database: Database,
+}
+
+impl DatabaseAuditSink {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Sink on its own connection to the database file at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(Database::new(path)?))
+    }
+}
+
+impl AuditSink for DatabaseAuditSink {
+    fn record(&mut self, event: &AuditEvent) -> Result<()> {
+        self.database.store_audit_event(event)
+    }
+
+    fn query(&self, filter: &AuditFilter) -> Option<Result<Vec<AuditEvent>>> {
+        Some(self.database.query_audit_events(filter))
+    }
+
+    fn recorded_events(&self) -> Option<Result<Vec<AuditEvent>>> {
+        Some(self.database.audit_events_in_log_order())
+    }
+
+    fn last_recorded(&self) -> Option<Result<Option<AuditEvent>>> {
+        Some(self.database.last_audit_event())
+    }
+}
+
+/// When a file-backed audit log is rotated and how many old files are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RotationPolicy {
+    /// Rotate once the file reaches this size
+    pub max_bytes: u64,
+    /// Rotated files kept as `<file>.1` (newest) to `<file>.<max_files>`;
+    /// older ones are deleted
+    pub max_files: usize,
+    /// Gzip rotated files to `<file>.<n>.gz`
+    #[serde(default)]
+    pub compress: bool,
+}
+
+/// Audit file that rotates by size.
+///
+/// Clones share the file, and each `write` is appended whole under a lock,
+/// so several loggers or threads can write to one log. Rotation happens
+/// only after a write ending in a newline, so an event written in one call,
+/// as `AuditLogger` does, never straddles two files. The current file is
+/// flushed before it is renamed away and the new one is opened before the
+/// lock is released, so nothing written is lost.
+#[derive(Clone)]
+pub struct RotatingFileWriter {
+    inner: Arc<Mutex<RotatingFile>>,
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    policy: RotationPolicy,
+    file: BufWriter<File>,
+    /// Size of the current file including buffered bytes
+    len: u64,
+}
+
+impl RotatingFileWriter {
+    /// Append to `path`, rotating it under `policy`
+    pub fn open<P: AsRef<Path>>(path: P, policy: RotationPolicy) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_append(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            inner: Arc::new(Mutex::new(RotatingFile { path, policy, file: BufWriter::new(file), len })),
+        })
+    }
+
+    /// Rotated files that exist, newest first
+    pub fn list_rotated_files(&self) -> Vec<PathBuf> {
+        self.lock().rotated_files()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RotatingFile> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Write for RotatingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut file = self.lock();
+        file.file.write_all(buf)?;
+        file.len += buf.len() as u64;
+        if file.len >= file.policy.max_bytes && buf.ends_with(b"\n") {
+            file.rotate()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.lock().file.flush()
+    }
+}
+
+impl RotatingFile {
+    /// `<file>.<n>`, with `.gz` when compressed
+    fn rotated_path(&self, n: usize, compressed: bool) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        if compressed {
+            name.push(".gz");
+        }
+        PathBuf::from(name)
+    }
+
+    fn rotated_files(&self) -> Vec<PathBuf> {
+        (1..=self.policy.max_files)
+            .flat_map(|n| [self.rotated_path(n, true), self.rotated_path(n, false)])
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_all()?;
+
+        // Shift older files up one, dropping the oldest
+        for n in (1..=self.policy.max_files).rev() {
+            for compressed in [true, false] {
+                let from = self.rotated_path(n, compressed);
+                if !from.exists() {
+                    continue;
+                }
+                if n == self.policy.max_files {
+                    std::fs::remove_file(&from)?;
+                } else {
+                    std::fs::rename(&from, self.rotated_path(n + 1, compressed))?;
+                }
+            }
+        }
+
+        if self.policy.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let rotated = self.rotated_path(1, false);
+            std::fs::rename(&self.path, &rotated)?;
+            if self.policy.compress {
+                compress_file(&rotated, &self.rotated_path(1, true))?;
+            }
+        }
+
+        self.file = BufWriter::new(open_append(&self.path)?);
+        self.len = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Gzip `from` into `to` via a temporary file, then remove `from`
+fn compress_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mut partial = to.to_path_buf().into_os_string();
+    partial.push(".tmp");
+    let partial = PathBuf::from(partial);
+
+    let mut encoder = flate2::write::GzEncoder::new(File::create(&partial)?, flate2::Compression::default());
+    std::io::copy(&mut File::open(from)?, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    std::fs::rename(&partial, to)?;
+    std::fs::remove_file(from)
+}
+
+/// Columns of an audit CSV export
+pub const AUDIT_CSV_HEADER: [&str; 7] = ["event_id", "timestamp", "event_type", "severity", "user_id", "sample_id", "details"];
+
+/// Write `events` to `out` as CSV under [`AUDIT_CSV_HEADER`], returning the
+/// row count. Timestamps are RFC 3339 and `details` is its JSON text.
+pub fn write_events_csv<W: Write>(events: &[AuditEvent], out: W) -> Result<usize> {
+    let mut csv = csv::Writer::from_writer(out);
+    csv.write_record(AUDIT_CSV_HEADER).map_err(std::io::Error::from)?;
+    for event in events {
+        csv.write_record([
+            event.event_id.to_string(),
+            event.timestamp.to_rfc3339(),
+            format!("{:?}", event.event_type),
+            format!("{:?}", event.severity),
+            event.user_id.clone().unwrap_or_default(),
+            event.sample_id.clone().unwrap_or_default(),
+            event.details.to_string(),
+        ]).map_err(std::io::Error::from)?;
+    }
+    csv.flush()?;
+    Ok(events.len())
+}
+
+/// Write `events` to `path` as a gzipped JSON array via a temporary file,
+/// refusing to replace an existing archive
+fn write_archive(path: &Path, events: &[&AuditEvent]) -> Result<()> {
+    if path.exists() {
+        return Err(SampleGuardError::IoError(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Audit archive {} already exists", path.display()),
+        )));
+    }
+    let mut partial = path.to_path_buf().into_os_string();
+    partial.push(".tmp");
+    let partial = PathBuf::from(partial);
+
+    let mut encoder = flate2::write::GzEncoder::new(BufWriter::new(File::create(&partial)?), flate2::Compression::default());
+    serde_json::to_writer(&mut encoder, events).map_err(SampleGuardError::SerializationError)?;
+    let file = encoder.finish()?.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Where an audit hash chain first fails to hold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrokenLink {
+    /// Position among the events held in memory, oldest first
+    pub index: usize,
+    pub event_id: uuid::Uuid,
+    pub reason: String,
+}
+
+/// Result of `AuditLogger::verify_chain`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainVerification {
+    /// Events checked before the first broken link, or all of them
+    pub events_checked: usize,
+    pub broken_link: Option<BrokenLink>,
+}
+
+impl ChainVerification {
+    pub fn is_intact(&self) -> bool {
+        self.broken_link.is_none()
+    }
+}
+
+/// Largest raw tag attached to an event unless configured otherwise
+pub const DEFAULT_RAW_TAG_MAX_BYTES: usize = 4096;
+
+/// Events held in memory before the oldest are dropped, unless configured
+/// otherwise
+pub const DEFAULT_MAX_EVENTS: usize = 10_000;
+
+/// Outcome of `AuditLogger::archive_events_before`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveReport {
+    /// Gzipped JSON array of the archived events; not written when none
+    /// were older than the cutoff
+    pub path: PathBuf,
+    pub events_archived: usize,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+}
+
 /// Audit logger for tracking all system operations
 pub struct AuditLogger {
     events: VecDeque<AuditEvent>,
     max_events: usize,
-    file_writer: Option<BufWriter<File>>,
+    /// Position of the oldest event still held
+    first_position: u64,
+    file_writer: Option<Box<dyn Write + Send>>,
+    flush_policy: FlushPolicy,
+    pending_writes: usize,
+    last_flush: Instant,
+    /// Most raw tag bytes attached to read events; capture is off when `None`
+    raw_tag_limit: Option<usize>,
+    sinks: Vec<Box<dyn AuditSink>>,
+    /// Handle on the file output when it rotates
+    rotating_file: Option<RotatingFileWriter>,
+    hash_chain: bool,
+    /// `event_hash` of the newest chained event; `None` until the chain is
+    /// resumed from stored events on the first chained write
+    last_hash: Option<[u8; 32]>,
+    /// Audit file, read back to resume the hash chain
+    file_path: Option<PathBuf>,
 }
 
 impl AuditLogger {
@@ -60,8 +467,18 @@ impl AuditLogger {
     pub fn new() -> Self {
         Self {
             events: VecDeque::new(),
-            max_events: 10000,
+            max_events: DEFAULT_MAX_EVENTS,
+            first_position: 0,
             file_writer: None,
+            flush_policy: FlushPolicy::Immediate,
+            pending_writes: 0,
+            last_flush: Instant::now(),
+            raw_tag_limit: None,
+            sinks: Vec::new(),
+            rotating_file: None,
+            hash_chain: false,
+            last_hash: None,
+            file_path: None,
         }
     }
 
@@ -70,14 +487,128 @@ impl AuditLogger {
         let file = OpenOptions::new()
             .create(true)
             .append(true)
-            .open(path)
-            .map_err(|e| SampleGuardError::IoError(e))?;
+            .open(path.as_ref())
+            .map_err(SampleGuardError::IoError)?;
 
-        Ok(Self {
-            events: VecDeque::new(),
-            max_events: 10000,
-            file_writer: Some(BufWriter::new(file)),
-        })
+        let mut logger = Self::with_writer(BufWriter::new(file));
+        logger.file_path = Some(path.as_ref().to_path_buf());
+        Ok(logger)
+    }
+
+    /// Create audit logger with file output rotated under `policy`
+    pub fn with_rotating_file<P: AsRef<Path>>(path: P, policy: RotationPolicy) -> Result<Self> {
+        let writer = RotatingFileWriter::open(path.as_ref(), policy)?;
+        let mut logger = Self::with_writer(writer.clone());
+        logger.rotating_file = Some(writer);
+        logger.file_path = Some(path.as_ref().to_path_buf());
+        Ok(logger)
+    }
+
+    /// Create the audit logger described by the `[audit]` settings
+    pub fn from_config(config: &AuditConfig) -> Result<Self> {
+        let mut logger = match (&config.file, config.rotation) {
+            (Some(path), Some(policy)) => Self::with_rotating_file(path, policy)?,
+            (Some(path), None) => Self::with_file(path)?,
+            (None, _) => Self::new(),
+        };
+        if config.hash_chain {
+            logger = logger.with_hash_chain();
+        }
+        if let Some(max_events) = config.max_events {
+            logger = logger.with_max_events(max_events);
+        }
+        if config.capture_raw_tags {
+            logger = logger.with_raw_tag_capture(config.raw_tag_max_bytes.unwrap_or(DEFAULT_RAW_TAG_MAX_BYTES));
+        }
+        Ok(logger)
+    }
+
+    /// Create audit logger writing JSON lines to an arbitrary writer
+    pub fn with_writer<W: Write + Send + 'static>(writer: W) -> Self {
+        let mut logger = Self::new();
+        logger.file_writer = Some(Box::new(writer));
+        logger
+    }
+
+    /// Set the flush policy for file output
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// Attach up to `max_bytes` of the raw tag, base64-encoded, to read and
+    /// violation events logged with tag data
+    pub fn with_raw_tag_capture(mut self, max_bytes: usize) -> Self {
+        self.raw_tag_limit = Some(max_bytes);
+        self
+    }
+
+    /// Also send every event to `sink`. The first sink that can be queried
+    /// answers `query_events`.
+    pub fn with_sink<S: AuditSink + 'static>(mut self, sink: S) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Hold up to `max_events` in memory, dropping the oldest beyond that;
+    /// archive them first with `archive_events_before` to keep them
+    pub fn with_max_events(mut self, max_events: usize) -> Self {
+        self.max_events = max_events.max(1);
+        self
+    }
+
+    /// Most events held in memory
+    pub fn max_events(&self) -> usize {
+        self.max_events
+    }
+
+    /// Link every event logged from now on to the one before it by hash,
+    /// so edits and deletions can be found with `verify_chain`. The chain
+    /// carries on from the newest event already stored in a readable sink,
+    /// or failing that in the audit file.
+    pub fn with_hash_chain(mut self) -> Self {
+        self.hash_chain = true;
+        self
+    }
+
+    /// Rotated audit files that exist, newest first; empty unless the file
+    /// output rotates
+    pub fn list_rotated_files(&self) -> Vec<PathBuf> {
+        self.rotating_file.as_ref().map(RotatingFileWriter::list_rotated_files).unwrap_or_default()
+    }
+
+    /// Get the flush policy for file output
+    pub fn flush_policy(&self) -> FlushPolicy {
+        self.flush_policy
+    }
+
+    /// Number of events written but not yet flushed
+    pub fn pending_writes(&self) -> usize {
+        self.pending_writes
+    }
+
+    /// Flush any buffered events to the file output and sinks
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(writer) = &mut self.file_writer {
+            writer.flush()
+                .map_err(SampleGuardError::IoError)?;
+        }
+        for sink in &mut self.sinks {
+            sink.flush()?;
+        }
+        self.pending_writes = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    /// Check whether the flush policy requires flushing now
+    fn should_flush(&self) -> bool {
+        match self.flush_policy {
+            FlushPolicy::Immediate => true,
+            FlushPolicy::Batched { max_pending, max_delay } => {
+                self.pending_writes >= max_pending || self.last_flush.elapsed() >= max_delay
+            }
+        }
     }
 
     /// Log an audit event
@@ -89,7 +620,7 @@ impl AuditLogger {
         details: serde_json::Value,
         severity: AuditSeverity,
     ) -> Result<()> {
-        let event = AuditEvent {
+        let mut event = AuditEvent {
             event_id: uuid::Uuid::new_v4(),
             event_type,
             timestamp: Utc::now(),
@@ -97,27 +628,90 @@ impl AuditLogger {
             sample_id,
             details,
             severity,
+            prev_hash: None,
+            event_hash: None,
         };
+        if self.hash_chain {
+            let prev_hash = match self.last_hash {
+                Some(hash) => hash,
+                None => self.stored_chain_head()?,
+            };
+            event.prev_hash = Some(prev_hash);
+            let hash = event.compute_hash();
+            event.event_hash = Some(hash);
+            self.last_hash = Some(hash);
+        }
 
         // Store in memory
         self.events.push_back(event.clone());
         if self.events.len() > self.max_events {
             self.events.pop_front();
+            self.first_position += 1;
         }
 
         // Write to file if configured
         if let Some(writer) = &mut self.file_writer {
-            let json = serde_json::to_string(&event)
-                .map_err(|e| SampleGuardError::SerializationError(e))?;
-            writeln!(writer, "{}", json)
-                .map_err(|e| SampleGuardError::IoError(e))?;
-            writer.flush()
-                .map_err(|e| SampleGuardError::IoError(e))?;
+            // One write per event, so a rotating file never splits it
+            let mut line = serde_json::to_vec(&event)
+                .map_err(SampleGuardError::SerializationError)?;
+            line.push(b'\n');
+            writer.write_all(&line)
+                .map_err(SampleGuardError::IoError)?;
+            self.pending_writes += 1;
+
+            if self.should_flush() {
+                self.flush()?;
+            }
+        }
+
+        for sink in &mut self.sinks {
+            sink.record(&event)?;
         }
 
         Ok(())
     }
 
+    /// `event_hash` of the newest event stored by an earlier run, from the
+    /// first readable sink or else the audit file; zero when nothing
+    /// chained has been stored
+    fn stored_chain_head(&self) -> Result<[u8; 32]> {
+        let newest = match self.sinks.iter().find_map(|sink| sink.last_recorded()) {
+            Some(newest) => newest?,
+            None => match &self.file_path {
+                Some(path) => self.last_event_in_files(path)?,
+                None => None,
+            },
+        };
+        Ok(newest.and_then(|event| event.event_hash).unwrap_or([0; 32]))
+    }
+
+    /// Newest event written to the audit file at `path`, looking in the
+    /// newest rotated file when the current one is empty
+    fn last_event_in_files(&self, path: &Path) -> Result<Option<AuditEvent>> {
+        let rotated = self.list_rotated_files();
+        for file in std::iter::once(path).chain(rotated.first().map(PathBuf::as_path)) {
+            if !file.exists() {
+                continue;
+            }
+            let reader: Box<dyn std::io::Read> = if file.extension().is_some_and(|e| e == "gz") {
+                Box::new(flate2::read::GzDecoder::new(File::open(file)?))
+            } else {
+                Box::new(File::open(file)?)
+            };
+            let mut last = None;
+            for line in std::io::BufRead::lines(std::io::BufReader::new(reader)) {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    last = Some(line);
+                }
+            }
+            if let Some(line) = last {
+                return Ok(Some(serde_json::from_str(&line)?));
+            }
+        }
+        Ok(None)
+    }
+
     /// Log sample creation
     pub fn log_sample_created(&mut self, sample: &Sample, user_id: Option<String>) -> Result<()> {
         let details = serde_json::json!({
@@ -137,10 +731,16 @@ impl AuditLogger {
 
     /// Log sample read
     pub fn log_sample_read(&mut self, sample: &Sample, user_id: Option<String>) -> Result<()> {
-        let details = serde_json::json!({
+        self.log_sample_read_with_tag(sample, &[], user_id)
+    }
+
+    /// Log a sample read from `raw_tag`, attaching the bytes if raw tag capture is on
+    pub fn log_sample_read_with_tag(&mut self, sample: &Sample, raw_tag: &[u8], user_id: Option<String>) -> Result<()> {
+        let mut details = serde_json::json!({
             "sample_id": sample.sample_id,
             "read_count": sample.read_count,
         });
+        self.attach_raw_tag(&mut details, raw_tag);
 
         self.log_event(
             AuditEventType::SampleRead,
@@ -188,6 +788,80 @@ impl AuditLogger {
         )
     }
 
+    /// Log a split of `parent_id` into aliquots
+    pub fn log_sample_split(&mut self, parent_id: &str, children: &[Sample], user_id: Option<String>) -> Result<()> {
+        let details = serde_json::json!({
+            "children": children.iter().map(|c| c.sample_id.as_str()).collect::<Vec<_>>(),
+        });
+
+        self.log_event(
+            AuditEventType::SampleSplit,
+            user_id,
+            Some(parent_id.to_string()),
+            details,
+            AuditSeverity::Info,
+        )
+    }
+
+    /// Log a pooling of `source_ids` into a new sample
+    pub fn log_samples_merged(&mut self, source_ids: &[&str], merged: &Sample, user_id: Option<String>) -> Result<()> {
+        let details = serde_json::json!({
+            "sources": source_ids,
+        });
+
+        self.log_event(
+            AuditEventType::SamplesMerged,
+            user_id,
+            Some(merged.sample_id.clone()),
+            details,
+            AuditSeverity::Info,
+        )
+    }
+
+    /// Log a sample moving into cold storage
+    pub fn log_sample_archived(&mut self, archived: &ArchivedSample, user_id: Option<String>) -> Result<()> {
+        let details = serde_json::json!({
+            "status": archived.status,
+            "batch_number": archived.batch_number,
+        });
+
+        self.log_event(
+            AuditEventType::SampleArchived,
+            user_id,
+            Some(archived.sample_id.clone()),
+            details,
+            AuditSeverity::Info,
+        )
+    }
+
+    /// Log a sample brought back from cold storage
+    pub fn log_sample_restored(&mut self, sample: &Sample, user_id: Option<String>) -> Result<()> {
+        self.log_event(
+            AuditEventType::SampleRestored,
+            user_id,
+            Some(sample.sample_id.clone()),
+            serde_json::json!({ "status": sample.status }),
+            AuditSeverity::Info,
+        )
+    }
+
+    /// Log a tag entering or leaving the reader field
+    pub fn log_inventory_event(&mut self, event: &InventoryEvent, user_id: Option<String>) -> Result<()> {
+        let (event_type, severity) = match event {
+            InventoryEvent::TagArrived(_) => (AuditEventType::TagArrived, AuditSeverity::Info),
+            InventoryEvent::TagDeparted(_) => (AuditEventType::TagDeparted, AuditSeverity::Warning),
+        };
+        let tag = event.tag();
+        let details = serde_json::json!({
+            "epc": tag.epc,
+            "tag_id": tag.tag_id,
+            "antenna": tag.antenna,
+            "rssi": tag.rssi,
+        });
+
+        self.log_event(event_type, user_id, None, details, severity)
+    }
+
     /// Log integrity violation
     pub fn log_integrity_violation(
         &mut self,
@@ -208,6 +882,48 @@ impl AuditLogger {
         )
     }
 
+    /// Log a tag that failed to decode or validate on read, attaching the
+    /// bytes if raw tag capture is on. `sample_id` is unknown when the tag
+    /// could not be decoded.
+    pub fn log_tag_violation(
+        &mut self,
+        sample_id: Option<&str>,
+        violations: Vec<String>,
+        raw_tag: &[u8],
+        user_id: Option<String>,
+    ) -> Result<()> {
+        let mut details = serde_json::json!({
+            "violations": violations,
+        });
+        self.attach_raw_tag(&mut details, raw_tag);
+
+        self.log_event(
+            AuditEventType::ViolationDetected,
+            user_id,
+            sample_id.map(str::to_string),
+            details,
+            AuditSeverity::Error,
+        )
+    }
+
+    fn attach_raw_tag(&self, details: &mut serde_json::Value, raw_tag: &[u8]) {
+        use base64::Engine;
+
+        let Some(limit) = self.raw_tag_limit else {
+            return;
+        };
+        if raw_tag.is_empty() {
+            return;
+        }
+        let kept = &raw_tag[..raw_tag.len().min(limit)];
+        details["raw_tag"] = serde_json::json!({
+            "encoding": "base64",
+            "data": base64::engine::general_purpose::STANDARD.encode(kept),
+            "length": raw_tag.len(),
+            "truncated": kept.len() < raw_tag.len(),
+        });
+    }
+
     /// Log temperature violation
     pub fn log_temperature_violation(
         &mut self,
@@ -269,7 +985,9 @@ impl AuditLogger {
             .collect()
     }
 
-    /// Query events with filters
+    /// Query events with filters, oldest first. Answered by the first
+    /// queryable sink when there is one, so events from before a restart or
+    /// evicted from memory are included; otherwise from memory.
     pub fn query_events(
         &self,
         event_type: Option<&AuditEventType>,
@@ -277,38 +995,79 @@ impl AuditLogger {
         severity: Option<&AuditSeverity>,
         start_time: Option<DateTime<Utc>>,
         end_time: Option<DateTime<Utc>>,
-    ) -> Vec<&AuditEvent> {
-        self.events
-            .iter()
-            .filter(|e| {
-                if let Some(et) = event_type {
-                    if e.event_type != *et {
-                        return false;
-                    }
-                }
-                if let Some(sid) = sample_id {
-                    if e.sample_id.as_ref().map(|s| s != sid).unwrap_or(true) {
-                        return false;
-                    }
-                }
-                if let Some(sev) = severity {
-                    if e.severity != *sev {
-                        return false;
-                    }
-                }
-                if let Some(start) = start_time {
-                    if e.timestamp < start {
-                        return false;
-                    }
-                }
-                if let Some(end) = end_time {
-                    if e.timestamp > end {
-                        return false;
-                    }
-                }
-                true
-            })
-            .collect()
+    ) -> Result<Vec<AuditEvent>> {
+        self.query(&AuditFilter {
+            event_type: event_type.cloned(),
+            sample_id: sample_id.map(str::to_string),
+            severity: severity.cloned(),
+            start_time,
+            end_time,
+        })
+    }
+
+    /// Events matching `filter`, from the same source as `query_events`
+    pub fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditEvent>> {
+        if let Some(events) = self.sinks.iter().find_map(|sink| sink.query(filter)) {
+            return events;
+        }
+        Ok(self.events.iter().filter(|e| filter.matches(e)).cloned().collect())
+    }
+
+    /// Events matching `filter`, or all of them, as CSV with the columns of
+    /// [`AUDIT_CSV_HEADER`]; taken from the same source as `query_events`
+    pub fn export_csv(&self, filter: Option<&AuditFilter>) -> Result<String> {
+        let events = self.query(filter.unwrap_or(&AuditFilter::default()))?;
+        let mut csv = Vec::new();
+        write_events_csv(&events, &mut csv)?;
+        String::from_utf8(csv).map_err(|e| SampleGuardError::InvalidSampleData(e.to_string()))
+    }
+
+    /// Check that each event's hash matches its contents and links to the
+    /// event before it. With a readable sink every stored event is walked
+    /// from the start, skipping any logged before chaining was turned on.
+    /// Otherwise the events held in memory are walked, and once older ones
+    /// have been evicted the oldest held event's `prev_hash` is taken on
+    /// trust. Fails when hash chaining is off.
+    pub fn verify_chain(&self) -> Result<ChainVerification> {
+        if !self.hash_chain {
+            return Err(SampleGuardError::InvalidSampleData("Audit hash chaining is not enabled".to_string()));
+        }
+
+        if let Some(stored) = self.sinks.iter().find_map(|sink| sink.recorded_events()) {
+            let stored = stored?;
+            let unchained = stored.iter().take_while(|e| e.prev_hash.is_none() && e.event_hash.is_none()).count();
+            let mut verification = Self::walk_chain(&stored[unchained..], Some([0; 32]));
+            verification.events_checked += unchained;
+            if let Some(broken) = &mut verification.broken_link {
+                broken.index += unchained;
+            }
+            return Ok(verification);
+        }
+        let held: Vec<&AuditEvent> = self.events.iter().collect();
+        Ok(Self::walk_chain(&held, (self.first_position == 0).then_some([0u8; 32])))
+    }
+
+    /// Walk `events` in order, expecting the first to follow `expected_prev`
+    /// when given
+    fn walk_chain<E: std::borrow::Borrow<AuditEvent>>(events: &[E], mut expected_prev: Option<[u8; 32]>) -> ChainVerification {
+        for (index, event) in events.iter().map(std::borrow::Borrow::borrow).enumerate() {
+            let broken = |reason: &str| ChainVerification {
+                events_checked: index,
+                broken_link: Some(BrokenLink { index, event_id: event.event_id, reason: reason.to_string() }),
+            };
+            let (Some(prev_hash), Some(event_hash)) = (event.prev_hash, event.event_hash) else {
+                return broken("event is not chained");
+            };
+            if expected_prev.is_some_and(|expected| expected != prev_hash) {
+                return broken("previous hash does not match the event before it");
+            }
+            if event.compute_hash() != event_hash {
+                return broken("event hash does not match its contents");
+            }
+            expected_prev = Some(event_hash);
+        }
+
+        ChainVerification { events_checked: events.len(), broken_link: None }
     }
 
     /// Get audit statistics
@@ -331,14 +1090,101 @@ impl AuditLogger {
 
     /// Clear all events
     pub fn clear(&mut self) {
+        self.first_position += self.events.len() as u64;
         self.events.clear();
     }
 
+    /// Move every held event older than `cutoff` to a gzipped JSON file at
+    /// `path`, which must not exist yet, and record the archive as a
+    /// `ConfigurationChanged` event. Nothing is written when no event is
+    /// older than `cutoff`.
+    pub fn archive_events_before<P: AsRef<Path>>(&mut self, cutoff: DateTime<Utc>, path: P) -> Result<ArchiveReport> {
+        let path = path.as_ref();
+        let archived: Vec<&AuditEvent> = self.events.iter().filter(|e| e.timestamp < cutoff).collect();
+        let mut report = ArchiveReport {
+            path: path.to_path_buf(),
+            events_archived: archived.len(),
+            oldest: archived.iter().map(|e| e.timestamp).min(),
+            newest: archived.iter().map(|e| e.timestamp).max(),
+        };
+        if archived.is_empty() {
+            return Ok(report);
+        }
+        write_archive(path, &archived)?;
+
+        let before = self.events.len();
+        self.events.retain(|e| e.timestamp >= cutoff);
+        report.events_archived = before - self.events.len();
+        // Keep the end cursor where it was, so tailing exports carry on
+        self.first_position += report.events_archived as u64;
+
+        self.log_event(
+            AuditEventType::ConfigurationChanged,
+            None,
+            None,
+            serde_json::json!({
+                "action": "audit_archive",
+                "path": path.display().to_string(),
+                "cutoff": cutoff.to_rfc3339(),
+                "events_archived": report.events_archived,
+            }),
+            AuditSeverity::Info,
+        )?;
+        Ok(report)
+    }
+
+    /// Bring the events of an archive written by `archive_events_before`
+    /// back into memory in timestamp order, skipping those already held.
+    /// Fails without changes when they would not fit under `max_events`.
+    /// Returns the number restored.
+    pub fn import_archive<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(SampleGuardError::IoError)?;
+        let archived: Vec<AuditEvent> = serde_json::from_reader(flate2::read::GzDecoder::new(std::io::BufReader::new(file)))
+            .map_err(SampleGuardError::SerializationError)?;
+
+        let held: std::collections::HashSet<uuid::Uuid> = self.events.iter().map(|e| e.event_id).collect();
+        let restored: Vec<AuditEvent> = archived.into_iter().filter(|e| !held.contains(&e.event_id)).collect();
+        if self.events.len() + restored.len() > self.max_events {
+            return Err(SampleGuardError::InvalidSampleData(format!(
+                "Restoring {} archived events would exceed the {} held in memory",
+                restored.len(),
+                self.max_events
+            )));
+        }
+
+        let count = restored.len();
+        let mut events: Vec<AuditEvent> = self.events.drain(..).chain(restored).collect();
+        events.sort_by_key(|e| e.timestamp);
+        self.events = events.into();
+        self.first_position = self.first_position.saturating_sub(count as u64);
+        Ok(count)
+    }
+
     /// Export events to JSON
     pub fn export_json(&self) -> Result<String> {
         let events: Vec<&AuditEvent> = self.events.iter().collect();
         serde_json::to_string(&events)
-            .map_err(|e| SampleGuardError::SerializationError(e))
+            .map_err(SampleGuardError::SerializationError)
+    }
+
+    /// Up to `limit` events from `cursor` on (from the oldest held event
+    /// when `None`), oldest first, and the cursor to continue from, or
+    /// `None` once the newest event has been returned. Events evicted
+    /// since the cursor was issued are skipped.
+    pub fn export_batch(&self, cursor: Option<AuditCursor>, limit: usize) -> (Vec<AuditEvent>, Option<AuditCursor>) {
+        let start = cursor.map_or(self.first_position, |c| c.0.max(self.first_position));
+        let end = self.end_cursor().0;
+        let skip = usize::try_from(start - self.first_position).unwrap_or(usize::MAX);
+        let events: Vec<AuditEvent> = self.events.iter().skip(skip).take(limit).cloned().collect();
+
+        let next = start + events.len() as u64;
+        (events, (next < end).then_some(AuditCursor(next)))
+    }
+
+    /// Cursor just past the newest event, where events logged from now on begin
+    pub fn end_cursor(&self) -> AuditCursor {
+        AuditCursor(self.first_position + self.events.len() as u64)
     }
 }
 
@@ -348,6 +1194,16 @@ impl Default for AuditLogger {
     }
 }
 
+impl Drop for AuditLogger {
+    fn drop(&mut self) {
+        if self.pending_writes > 0 || !self.sinks.is_empty() {
+            if let Err(e) = self.flush() {
+                log::error!("Failed to flush audit log on drop: {}", e);
+            }
+        }
+    }
+}
+
 /// Audit statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditStatistics {
@@ -361,6 +1217,26 @@ mod tests {
     use super::*;
     use crate::sample::SampleMetadata;
     use chrono::Utc;
+    use std::sync::{Arc, Mutex};
+
+    /// Writer that records bytes written and the number of flush calls
+    #[derive(Clone, Default)]
+    struct CountingWriter {
+        data: Arc<Mutex<Vec<u8>>>,
+        flushes: Arc<Mutex<usize>>,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.data.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            *self.flushes.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
 
     fn create_test_sample(id: &str) -> Sample {
         let metadata = SampleMetadata {
@@ -440,6 +1316,24 @@ mod tests {
         assert_eq!(events.len(), 1);
     }
 
+    #[test]
+    fn test_log_inventory_event() {
+        let tag = crate::inventory::TagScanResult {
+            epc: "E2-001".to_string(),
+            tag_id: "T-001".to_string(),
+            rssi: -55,
+            antenna: 2,
+            timestamp: Utc::now(),
+        };
+        let mut logger = AuditLogger::new();
+        logger.log_inventory_event(&InventoryEvent::TagDeparted(tag), None).unwrap();
+
+        let events = logger.get_events_by_type(&AuditEventType::TagDeparted);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].severity, AuditSeverity::Warning);
+        assert_eq!(events[0].details["epc"], "E2-001");
+    }
+
     #[test]
     fn test_log_integrity_violation() {
         let mut logger = AuditLogger::new();
@@ -521,7 +1415,7 @@ mod tests {
             None,
             None,
             None,
-        );
+        ).unwrap();
         assert_eq!(created_events.len(), 1);
     }
 
@@ -577,5 +1471,240 @@ mod tests {
         assert!(!json.is_empty());
         assert!(json.contains("SystemStartup"));
     }
-}
 
+    #[test]
+    fn test_immediate_flush_policy() {
+        let writer = CountingWriter::default();
+        let mut logger = AuditLogger::with_writer(writer.clone());
+        for i in 0..10 {
+            logger.log_event(
+                AuditEventType::UserAction,
+                None,
+                None,
+                serde_json::json!({"action": i}),
+                AuditSeverity::Info,
+            ).unwrap();
+        }
+
+        assert_eq!(*writer.flushes.lock().unwrap(), 10);
+        assert_eq!(logger.pending_writes(), 0);
+    }
+
+    #[test]
+    fn test_batched_flush_policy() {
+        let writer = CountingWriter::default();
+        let mut logger = AuditLogger::with_writer(writer.clone())
+            .with_flush_policy(FlushPolicy::Batched {
+                max_pending: 50,
+                max_delay: Duration::from_secs(3600),
+            });
+
+        for i in 0..120 {
+            logger.log_event(
+                AuditEventType::UserAction,
+                None,
+                None,
+                serde_json::json!({"action": i}),
+                AuditSeverity::Info,
+            ).unwrap();
+        }
+
+        assert_eq!(*writer.flushes.lock().unwrap(), 2);
+        assert_eq!(logger.pending_writes(), 20);
+
+        logger.flush().unwrap();
+        assert_eq!(*writer.flushes.lock().unwrap(), 3);
+
+        let data = writer.data.lock().unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&data).unwrap().lines().collect();
+        assert_eq!(lines.len(), 120);
+        for line in lines {
+            let event: AuditEvent = serde_json::from_str(line).unwrap();
+            assert_eq!(event.event_type, AuditEventType::UserAction);
+        }
+    }
+
+    #[test]
+    fn test_batched_flush_on_delay() {
+        let writer = CountingWriter::default();
+        let mut logger = AuditLogger::with_writer(writer.clone())
+            .with_flush_policy(FlushPolicy::Batched {
+                max_pending: 1000,
+                max_delay: Duration::ZERO,
+            });
+
+        logger.log_event(
+            AuditEventType::UserAction,
+            None,
+            None,
+            serde_json::json!({}),
+            AuditSeverity::Info,
+        ).unwrap();
+
+        assert_eq!(*writer.flushes.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_flush_on_drop() {
+        let writer = CountingWriter::default();
+        {
+            let mut logger = AuditLogger::with_writer(writer.clone())
+                .with_flush_policy(FlushPolicy::Batched {
+                    max_pending: 100,
+                    max_delay: Duration::from_secs(3600),
+                });
+            for _ in 0..5 {
+                logger.log_event(
+                    AuditEventType::UserAction,
+                    None,
+                    None,
+                    serde_json::json!({}),
+                    AuditSeverity::Info,
+                ).unwrap();
+            }
+            assert_eq!(*writer.flushes.lock().unwrap(), 0);
+        }
+
+        assert_eq!(*writer.flushes.lock().unwrap(), 1);
+        let data = writer.data.lock().unwrap();
+        assert_eq!(std::str::from_utf8(&data).unwrap().lines().count(), 5);
+    }
+
+    fn log_numbered(logger: &mut AuditLogger, n: u64) {
+        logger.log_event(
+            AuditEventType::UserAction,
+            None,
+            None,
+            serde_json::json!({ "n": n }),
+            AuditSeverity::Info,
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_export_batch_pages_every_event_once() {
+        let mut logger = AuditLogger::new();
+        for n in 0..10 {
+            log_numbered(&mut logger, n);
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (events, next) = logger.export_batch(cursor, 3);
+            seen.extend(events.iter().map(|e| e.details["n"].as_u64().unwrap()));
+            // Appends between pages land after the cursor and are picked up too
+            if seen.len() == 6 {
+                log_numbered(&mut logger, 10);
+                log_numbered(&mut logger, 11);
+            }
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(seen, (0..12).collect::<Vec<_>>());
+
+        // A cursor at the end picks up events logged later
+        let end = logger.end_cursor();
+        let (events, next) = logger.export_batch(Some(end), 3);
+        assert!(events.is_empty() && next.is_none());
+        log_numbered(&mut logger, 12);
+        let (events, next) = logger.export_batch(Some(end), 3);
+        assert_eq!(events[0].details["n"], 12);
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn test_export_batch_skips_evicted_events() {
+        let mut logger = AuditLogger::new();
+        logger.max_events = 5;
+        for n in 0..5 {
+            log_numbered(&mut logger, n);
+        }
+        let (_, cursor) = logger.export_batch(None, 2);
+        assert_eq!(cursor.unwrap().to_string(), "2");
+
+        for n in 5..9 {
+            log_numbered(&mut logger, n);
+        }
+        // Events 2 and 3 were evicted; the page resumes at the oldest held
+        let (events, _) = logger.export_batch(cursor, 2);
+        let numbers: Vec<u64> = events.iter().map(|e| e.details["n"].as_u64().unwrap()).collect();
+        assert_eq!(numbers, vec![4, 5]);
+
+        logger.clear();
+        let (events, next) = logger.export_batch(cursor, 2);
+        assert!(events.is_empty() && next.is_none());
+        assert!("not-a-cursor".parse::<AuditCursor>().is_err());
+        assert_eq!("4".parse::<AuditCursor>().unwrap(), AuditCursor(4));
+    }
+
+    fn chained_logger(count: u64) -> AuditLogger {
+        let mut logger = AuditLogger::new().with_hash_chain();
+        for n in 0..count {
+            log_numbered(&mut logger, n);
+        }
+        logger
+    }
+
+    #[test]
+    fn test_verify_chain_intact() {
+        let logger = chained_logger(10);
+        let verification = logger.verify_chain().unwrap();
+        assert!(verification.is_intact());
+        assert_eq!(verification.events_checked, 10);
+        assert_eq!(logger.events[0].prev_hash, Some([0; 32]));
+        assert_eq!(logger.events[1].prev_hash, logger.events[0].event_hash);
+
+        let exported: Vec<AuditEvent> = serde_json::from_str(&logger.export_json().unwrap()).unwrap();
+        assert_eq!(exported[9].event_hash, logger.events[9].event_hash);
+
+        assert!(AuditLogger::new().verify_chain().is_err());
+    }
+
+    #[test]
+    fn test_verify_chain_detects_mutated_details() {
+        let mut logger = chained_logger(10);
+        logger.events[4].details = serde_json::json!({"n": 40});
+        let verification = logger.verify_chain().unwrap();
+        let broken = verification.broken_link.unwrap();
+        assert_eq!(broken.index, 4);
+        assert_eq!(broken.event_id, logger.events[4].event_id);
+        assert_eq!(verification.events_checked, 4);
+    }
+
+    #[test]
+    fn test_verify_chain_flags_exactly_the_mutated_index() {
+        let mut logger = chained_logger(6);
+        for index in 0..6 {
+            let original = logger.events[index].details.clone();
+            logger.events[index].details["n"] = serde_json::json!("tampered");
+            let broken = logger.verify_chain().unwrap().broken_link.unwrap();
+            assert_eq!(broken.index, index);
+            assert_eq!(broken.event_id, logger.events[index].event_id);
+
+            logger.events[index].details = original;
+            assert!(logger.verify_chain().unwrap().is_intact());
+        }
+    }
+
+    #[test]
+    fn test_verify_chain_detects_deleted_event() {
+        let mut logger = chained_logger(10);
+        logger.events.remove(5);
+        let broken = logger.verify_chain().unwrap().broken_link.unwrap();
+        assert_eq!(broken.index, 5);
+        assert_eq!(broken.event_id, logger.events[5].event_id);
+        assert!(broken.reason.contains("previous hash"));
+    }
+
+    #[test]
+    fn test_verify_chain_after_eviction() {
+        let mut logger = chained_logger(0);
+        logger.max_events = 5;
+        for n in 0..8 {
+            log_numbered(&mut logger, n);
+        }
+        assert!(logger.verify_chain().unwrap().is_intact());
+    }
+}
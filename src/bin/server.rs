@@ -1,9 +1,15 @@
 use sample_guard::api::start_server;
+use sample_guard::logging::{self, LoggingOptions};
 use std::env;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    let log_format = env::var("LOG_FORMAT")
+        .unwrap_or_else(|_| "text".to_string())
+        .parse()
+        .expect("LOG_FORMAT must be text or json");
+    logging::init(&LoggingOptions { format: log_format, level: None })
+        .expect("failed to initialize logging");
     
     let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
     let port = env::var("PORT")
@@ -211,7 +211,7 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     // ============================================================================
     print_section("PHASE 5: SAMPLE STATUS TRANSITIONS");
     
-    let statuses = vec![
+    let statuses = [
         SampleStatus::InProduction,
         SampleStatus::InTransit,
         SampleStatus::Stored,
@@ -227,7 +227,11 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
                 &format!("Updating {}: {:?} -> {:?}", sample.sample_id, old_status, new_status));
             
             let mut updated_sample = sample.clone();
-            updated_sample.update_status(new_status);
+            if let Err(e) = updated_sample.try_update_status(new_status) {
+                print_transaction(step_counter, "STATUS_UPDATE", "REJECTED", &e.to_string());
+                step_counter += 1;
+                continue;
+            }
             db.store_sample(&updated_sample)?;
             
             audit_logger.log_status_change(
@@ -282,7 +286,7 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     
     // Read tags from hardware
     if !hw_tags.is_empty() {
-        for (_idx, epc) in hw_tags.iter().take(3).enumerate() {
+        for epc in hw_tags.iter().take(3) {
             print_transaction(step_counter, "HW_READ", "IN_PROGRESS", &format!("Reading tag {} from Impinj reader", epc));
             match hardware_driver.read_tag_impinj(epc) {
                 Ok(data) => {
@@ -302,12 +306,12 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     // Get reader configurations
     print_transaction(step_counter, "HW_CONFIG", "IN_PROGRESS", "Retrieving Impinj reader configuration");
     let impinj_config = hardware_driver.get_reader_config("impinj")?;
-    print_transaction(step_counter, "HW_CONFIG", "SUCCESS", &format!("Impinj config: {}", impinj_config));
+    print_transaction(step_counter, "HW_CONFIG", "SUCCESS", &format!("Impinj config: {}", serde_json::to_string(&impinj_config)?));
     step_counter += 1;
     
     print_transaction(step_counter, "HW_CONFIG", "IN_PROGRESS", "Retrieving Zebra reader configuration");
     let zebra_config = hardware_driver.get_reader_config("zebra")?;
-    print_transaction(step_counter, "HW_CONFIG", "SUCCESS", &format!("Zebra config: {}", zebra_config));
+    print_transaction(step_counter, "HW_CONFIG", "SUCCESS", &format!("Zebra config: {}", serde_json::to_string(&zebra_config)?));
     step_counter += 1;
     
     // ============================================================================
@@ -0,0 +1,179 @@
+use crate::config::DatabaseConfig;
+use crate::database::{open_backend, ConflictStrategy, Database, DatabaseStatistics, ImportOutcome, ImportReport, VerificationReport};
+use crate::error::Result;
+use std::fmt::Write;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Number of rows `db verify` loads at a time
+pub const VERIFY_BATCH_SIZE: usize = 500;
+
+/// Apply pending migrations, returning the schema versions before and after
+pub fn migrate(path: &Path) -> Result<(u32, u32)> {
+    Database::open_without_migrate(path)?.migrate()
+}
+
+/// Load extended database statistics
+pub fn stats(path: &Path) -> Result<DatabaseStatistics> {
+    Database::new(path)?.get_statistics()
+}
+
+/// Load statistics from the configured backend, which may be Postgres
+pub fn backend_stats(config: &DatabaseConfig) -> Result<DatabaseStatistics> {
+    open_backend(config)?.get_statistics()
+}
+
+/// Verify every stored sample
+pub fn verify(path: &Path) -> Result<VerificationReport> {
+    Database::new(path)?.verify_samples(VERIFY_BATCH_SIZE)
+}
+
+/// Rewrite legacy sample checksums in the current layout
+pub fn upgrade_checksums(path: &Path) -> Result<usize> {
+    Database::new(path)?.upgrade_checksums()
+}
+
+/// Import samples from a JSON-lines file
+pub fn import(path: &Path, file: &Path, strategy: ConflictStrategy) -> Result<ImportReport> {
+    let reader = BufReader::new(std::fs::File::open(file)?);
+    Database::new(path)?.import_jsonl(reader, strategy)
+}
+
+/// Render an import report, listing records that were not imported
+pub fn format_import(report: &ImportReport) -> String {
+    let mut out = String::new();
+    for record in &report.records {
+        if let ImportOutcome::Failed(reason) = &record.outcome {
+            let _ = writeln!(
+                out,
+                "FAILED line {} ({}): {}",
+                record.line,
+                record.sample_id.as_deref().unwrap_or("-"),
+                reason
+            );
+        }
+    }
+    if report.rolled_back {
+        let _ = writeln!(out, "Import aborted on conflict; no changes were made");
+    } else {
+        let _ = writeln!(
+            out,
+            "Inserted {}, updated {}, skipped {}, failed {}",
+            report.inserted(),
+            report.updated(),
+            report.skipped(),
+            report.failed()
+        );
+    }
+    out
+}
+
+/// Render statistics as a plain-text table
+pub fn format_stats_table(stats: &DatabaseStatistics) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{:<28} {:>8}", "Total samples", stats.total_samples);
+    let _ = writeln!(out, "{:<28} {:>8}", "Expired", stats.expired);
+    let _ = writeln!(out, "{:<28} {:>8}", "Expiring within 30 days", stats.expiring_within_30_days);
+
+    let _ = writeln!(out, "\nBy status");
+    let mut statuses: Vec<_> = stats.status_counts.iter().collect();
+    statuses.sort();
+    for (status, count) in statuses {
+        let _ = writeln!(out, "  {:<26} {:>8}", status, count);
+    }
+
+    let _ = writeln!(out, "\nBy manufacturer");
+    let mut manufacturers: Vec<_> = stats.manufacturer_counts.iter().collect();
+    manufacturers.sort();
+    for (manufacturer, count) in manufacturers {
+        let _ = writeln!(out, "  {:<26} {:>8}", manufacturer, count);
+    }
+
+    out
+}
+
+/// Render a verification report
+pub fn format_verification(report: &VerificationReport) -> String {
+    let mut out = String::new();
+    for corrupt in &report.corrupt {
+        let _ = writeln!(out, "CORRUPT {}: {}", corrupt.sample_id, corrupt.reason);
+    }
+    let _ = writeln!(
+        out,
+        "Checked {} samples, {} corrupt",
+        report.checked,
+        report.corrupt.len()
+    );
+    if report.legacy_checksums > 0 {
+        let _ = writeln!(
+            out,
+            "{} samples use the legacy checksum layout; rerun with --upgrade-checksums to rewrite them",
+            report.legacy_checksums
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SCHEMA_VERSION;
+    use crate::sample::{Sample, SampleMetadata};
+    use chrono::Utc;
+
+    fn seed(path: &Path, count: usize) {
+        let db = Database::new(path).unwrap();
+        for i in 0..count {
+            let metadata = SampleMetadata {
+                batch_number: "BATCH-CLI".to_string(),
+                production_date: Utc::now(),
+                expiry_date: Some(Utc::now() + chrono::Duration::days(10)),
+                temperature_range: None,
+                storage_conditions: "Ambient".to_string(),
+                manufacturer: if i % 2 == 0 { "Acme" } else { "Globex" }.to_string(),
+                product_line: "Test".to_string(),
+            };
+            db.store_sample(&Sample::new(format!("CLI-{:03}", i), metadata, None)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_verify_flags_corrupted_row() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.db");
+        seed(&path, 4);
+
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute(
+            "UPDATE samples SET integrity_checksum = 'not-hex' WHERE sample_id = 'CLI-002'",
+            [],
+        ).unwrap();
+
+        let report = verify(&path).unwrap();
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(report.corrupt[0].sample_id, "CLI-002");
+        assert!(format_verification(&report).contains("CORRUPT CLI-002"));
+    }
+
+    #[test]
+    fn test_migrate_reports_version_transition() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fresh.db");
+
+        assert_eq!(migrate(&path).unwrap(), (0, SCHEMA_VERSION));
+        assert_eq!(migrate(&path).unwrap(), (SCHEMA_VERSION, SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn test_stats_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.db");
+        seed(&path, 3);
+
+        let stats = stats(&path).unwrap();
+        assert_eq!(stats.manufacturer_counts.get("Acme"), Some(&2));
+        assert_eq!(stats.expiring_within_30_days, 3);
+        assert!(format_stats_table(&stats).contains("Globex"));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::audit::{AuditEventType, AuditLogger, AuditSeverity};
+use crate::commission::{verify_with, VerifyOptions, VerifyOutcome};
+use crate::config::{parse_duration, ReaderSettings};
+use crate::database::Database;
+use crate::error::{SampleGuardError, Result};
+use crate::hardware::simulator::{ScanStrategy, SimulatedTag, TagSimulator};
+use crate::encryption::RFIDEncryption;
+use crate::sample::{default_encryption, Sample, SampleMetadata};
+use crate::tag::TagData;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// ASCII bell, so station terminals can beep on each result
+pub const BELL: char = '\x07';
+
+/// Reader field the encode station writes tags through
+pub trait EncodingField {
+    /// EPC of a blank tag currently in the field, if any
+    fn find_blank_tag(&mut self) -> Result<Option<String>>;
+
+    /// Largest payload a tag can hold, in bytes
+    fn tag_capacity(&self) -> usize;
+
+    fn write_tag(&mut self, epc: &str, data: &[u8]) -> Result<()>;
+
+    fn read_tag(&mut self, epc: &str) -> Result<TagData>;
+}
+
+impl EncodingField for TagSimulator {
+    fn find_blank_tag(&mut self) -> Result<Option<String>> {
+        let mut blank: Vec<&SimulatedTag> = self.get_tags()
+            .into_iter()
+            .filter(|t| t.data.is_empty())
+            .collect();
+        blank.sort_by(|a, b| a.epc.cmp(&b.epc));
+        Ok(blank.first().map(|t| t.epc.clone()))
+    }
+
+    fn tag_capacity(&self) -> usize {
+        4096
+    }
+
+    fn write_tag(&mut self, epc: &str, data: &[u8]) -> Result<()> {
+        TagSimulator::write_tag(self, epc, data.to_vec())
+    }
+
+    fn read_tag(&mut self, epc: &str) -> Result<TagData> {
+        TagSimulator::read_tag(self, epc)
+    }
+}
+
+/// Build the training simulator described by the reader settings
+pub fn simulator_from_settings(settings: &ReaderSettings) -> Result<TagSimulator> {
+    if settings.kind != "simulator" {
+        return Err(SampleGuardError::ConfigError(format!(
+            "Reader kind '{}' is not supported by the encode station",
+            settings.kind
+        )));
+    }
+
+    let mut simulator = TagSimulator::new();
+    if !settings.antennas.is_empty() {
+        simulator = simulator.with_scan_strategy(ScanStrategy::Hopping {
+            antennas: settings.antennas.clone(),
+            dwell: parse_duration(&settings.antenna_dwell)?,
+        });
+    }
+    for i in 0..settings.simulated_blank_tags {
+        simulator.add_tag(SimulatedTag::new(
+            format!("E2000000{:016}", i),
+            format!("BLANK-{:04}", i),
+            Vec::new(),
+        ));
+    }
+    Ok(simulator)
+}
+
+/// Encode station behaviour
+#[derive(Debug, Clone)]
+pub struct StationOptions {
+    /// Run the preflight checks without writing any tag
+    pub dry_run: bool,
+    /// How long to wait for a blank tag before skipping a work order
+    pub tag_wait: Duration,
+    pub verify: VerifyOptions,
+}
+
+impl StationOptions {
+    /// Options taken from the reader settings
+    pub fn from_settings(settings: &ReaderSettings) -> Result<Self> {
+        Ok(Self {
+            dry_run: false,
+            tag_wait: parse_duration(&settings.tag_wait)?,
+            verify: VerifyOptions::default().with_retries(settings.verify_retries),
+        })
+    }
+}
+
+/// Counts for an encode station session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationSummary {
+    pub encoded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+/// Interactive loop that encodes one tag per scanned work order
+pub struct EncodeStation<F: EncodingField> {
+    field: F,
+    database: Database,
+    audit_logger: AuditLogger,
+    options: StationOptions,
+    summary: StationSummary,
+    encryption: RFIDEncryption,
+}
+
+impl<F: EncodingField> EncodeStation<F> {
+    pub fn new(field: F, database: Database, audit_logger: AuditLogger, options: StationOptions) -> Self {
+        Self {
+            field,
+            database,
+            audit_logger,
+            options,
+            summary: StationSummary::default(),
+            encryption: default_encryption().clone(),
+        }
+    }
+
+    /// Encrypt tags with `encryption` instead of the default key
+    pub fn with_encryption(mut self, encryption: RFIDEncryption) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    pub fn field(&self) -> &F {
+        &self.field
+    }
+
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    pub fn audit_logger(&self) -> &AuditLogger {
+        &self.audit_logger
+    }
+
+    /// Process work orders from `input` until it ends or `shutdown` is set.
+    ///
+    /// A shutdown request is honoured between tags, so the tag being encoded
+    /// is always finished. The session summary is written to the audit log.
+    pub fn run<I: BufRead, O: Write>(
+        &mut self,
+        input: I,
+        output: &mut O,
+        shutdown: &AtomicBool,
+    ) -> Result<StationSummary> {
+        writeln!(output, "Scan a work order (Ctrl-C to finish)")?;
+
+        for line in input.lines() {
+            if shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+            let work_order = line?;
+            let work_order = work_order.trim();
+            if work_order.is_empty() {
+                continue;
+            }
+
+            let result = self.encode(work_order);
+            match &result {
+                Ok(message) => writeln!(output, "PASS {} {}{}", work_order, message, BELL)?,
+                Err(e) => writeln!(output, "FAIL {} {}{}", work_order, e, BELL)?,
+            }
+
+            if shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+        }
+
+        self.audit_logger.log_event(
+            AuditEventType::UserAction,
+            None,
+            None,
+            serde_json::json!({
+                "action": "encode_station_session",
+                "dry_run": self.options.dry_run,
+                "encoded": self.summary.encoded,
+                "failed": self.summary.failed,
+                "skipped": self.summary.skipped,
+            }),
+            AuditSeverity::Info,
+        )?;
+        self.audit_logger.flush()?;
+
+        writeln!(
+            output,
+            "Session complete: {} encoded, {} failed, {} skipped",
+            self.summary.encoded, self.summary.failed, self.summary.skipped
+        )?;
+        Ok(self.summary)
+    }
+
+    fn encode(&mut self, work_order: &str) -> std::result::Result<String, String> {
+        let sample = match self.lookup_or_create(work_order) {
+            Ok(sample) => sample,
+            Err(e) => {
+                self.summary.failed += 1;
+                return Err(format!("sample lookup failed: {}", e));
+            }
+        };
+
+        let epc = match self.wait_for_blank_tag() {
+            Ok(Some(epc)) => epc,
+            Ok(None) => {
+                self.summary.skipped += 1;
+                return Err("no blank tag in field".to_string());
+            }
+            Err(e) => {
+                self.summary.failed += 1;
+                return Err(format!("reader error: {}", e));
+            }
+        };
+
+        let result = self.write_and_verify(&sample, &epc);
+        match &result {
+            Ok(_) => self.summary.encoded += 1,
+            Err(_) => self.summary.failed += 1,
+        }
+        result
+    }
+
+    fn lookup_or_create(&mut self, sample_id: &str) -> Result<Sample> {
+        if let Some(sample) = self.database.get_sample(sample_id)? {
+            return Ok(sample);
+        }
+
+        let metadata = SampleMetadata {
+            batch_number: "UNASSIGNED".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: None,
+            storage_conditions: "Unspecified".to_string(),
+            manufacturer: "Unspecified".to_string(),
+            product_line: "Unspecified".to_string(),
+        };
+        let sample = Sample::new(sample_id.to_string(), metadata, None);
+        if !self.options.dry_run {
+            self.database.store_sample(&sample)?;
+            self.audit_logger.log_sample_created(&sample, None)?;
+        }
+        Ok(sample)
+    }
+
+    fn wait_for_blank_tag(&mut self) -> Result<Option<String>> {
+        let deadline = Instant::now() + self.options.tag_wait;
+        loop {
+            if let Some(epc) = self.field.find_blank_tag()? {
+                return Ok(Some(epc));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    fn write_and_verify(&mut self, sample: &Sample, epc: &str) -> std::result::Result<String, String> {
+        let bytes = sample.to_tag_with_key(&self.encryption)
+            .and_then(|tag| tag.to_bytes())
+            .map_err(|e| format!("encoding failed: {}", e))?;
+
+        // Preflight: the encoded sample must fit on the tag
+        if bytes.len() > self.field.tag_capacity() {
+            return Err(format!(
+                "encoded sample is {} bytes, tag holds {}",
+                bytes.len(),
+                self.field.tag_capacity()
+            ));
+        }
+        if self.options.dry_run {
+            return Ok(format!("{} (dry run, {} bytes)", epc, bytes.len()));
+        }
+
+        self.field.write_tag(epc, &bytes).map_err(|e| format!("write failed: {}", e))?;
+        let field = &mut self.field;
+        match verify_with(|| field.read_tag(epc), &bytes, &self.options.verify) {
+            VerifyOutcome::Verified { .. } => {
+                let _ = self.audit_logger.log_sample_written(sample, None);
+                Ok(epc.to_string())
+            }
+            VerifyOutcome::Mismatch { attempts } => {
+                Err(format!("verification mismatch after {} reads", attempts))
+            }
+            VerifyOutcome::ReadFailed { attempts, last_error } => {
+                Err(format!("verification read failed after {} attempts: {}", attempts, last_error))
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use crate::database::Database;
+use crate::error::{SampleGuardError, Result};
+use crate::inventory::{InventoryManager, InventorySession, RssiTrend, SessionEvent, SessionTag, TagSource};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+const GREEN: &str = "\x1b[32m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Key commands accepted while watching, one per input line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchCommand {
+    /// `p`: pause or resume scanning
+    TogglePause,
+    /// `c`: forget all tracked tags
+    Clear,
+    /// `d`: dump the current view to CSV
+    Dump,
+    /// `q`: stop watching
+    Quit,
+}
+
+impl WatchCommand {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim() {
+            "p" => Some(WatchCommand::TogglePause),
+            "c" => Some(WatchCommand::Clear),
+            "d" => Some(WatchCommand::Dump),
+            "q" => Some(WatchCommand::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// `inventory watch` settings
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Time between the start of consecutive scan cycles
+    pub interval: Duration,
+    /// How long each scan cycle listens for tags
+    pub scan_duration: Duration,
+    /// Cycles a tag may go unseen before it is declared missing
+    pub missing_after: u32,
+    /// Redraw a table in place instead of printing one line per change
+    pub tty: bool,
+    /// Directory CSV dumps are written to
+    pub dump_dir: PathBuf,
+    /// Stop after this many cycles
+    pub max_cycles: Option<u64>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(2),
+            scan_duration: Duration::from_millis(500),
+            missing_after: 3,
+            tty: false,
+            dump_dir: PathBuf::from("."),
+            max_cycles: None,
+        }
+    }
+}
+
+/// Run continuous inventory cycles, rendering the tag population until
+/// `shutdown` is set, a quit command arrives or `max_cycles` is reached
+pub fn watch<S: TagSource + ?Sized, W: Write>(
+    source: &mut S,
+    database: Option<&Database>,
+    options: &WatchOptions,
+    output: &mut W,
+    commands: &Receiver<WatchCommand>,
+    shutdown: &AtomicBool,
+) -> Result<InventorySession> {
+    let mut manager = InventoryManager::new();
+    let mut session = InventorySession::new(options.missing_after);
+    let mut paused = false;
+
+    while !shutdown.load(Ordering::SeqCst) {
+        if options.max_cycles.is_some_and(|max| session.cycles() >= max) {
+            break;
+        }
+        let cycle_start = Instant::now();
+
+        while let Ok(command) = commands.try_recv() {
+            match command {
+                WatchCommand::TogglePause => {
+                    paused = !paused;
+                    writeln!(output, "{}", if paused { "Paused" } else { "Resumed" })?;
+                }
+                WatchCommand::Clear => {
+                    session.clear();
+                    writeln!(output, "Cleared")?;
+                }
+                WatchCommand::Dump => {
+                    let path = options.dump_dir
+                        .join(format!("inventory-{}.csv", chrono::Utc::now().format("%Y%m%dT%H%M%S")));
+                    std::fs::write(&path, session_csv(&session, database))?;
+                    writeln!(output, "Wrote {}", path.display())?;
+                }
+                WatchCommand::Quit => return Ok(session),
+            }
+        }
+
+        if !paused {
+            let events = manager.scan_cycle(source, &mut session, options.scan_duration)?;
+            if options.tty {
+                let appeared: Vec<&str> = events.iter()
+                    .filter_map(|e| match e {
+                        SessionEvent::Appeared(tag) => Some(tag.epc.as_str()),
+                        SessionEvent::Missing(_) => None,
+                    })
+                    .collect();
+                write!(output, "{}", render_table(&session, database, &appeared))?;
+            } else {
+                for event in &events {
+                    writeln!(output, "{}", event_line(event, database))?;
+                }
+            }
+            output.flush()?;
+        }
+
+        // Sleep in small steps so Ctrl-C is picked up promptly
+        while cycle_start.elapsed() < options.interval && !shutdown.load(Ordering::SeqCst) {
+            std::thread::sleep(options.interval.saturating_sub(cycle_start.elapsed()).min(Duration::from_millis(100)));
+        }
+    }
+
+    Ok(session)
+}
+
+fn sample_status(database: Option<&Database>, tag_id: &str) -> String {
+    match database.map(|db| db.get_sample(tag_id)) {
+        Some(Ok(Some(sample))) => format!("{:?}", sample.status),
+        Some(Ok(None)) => "Unknown".to_string(),
+        Some(Err(_)) => "Error".to_string(),
+        None => "-".to_string(),
+    }
+}
+
+fn trend_arrow(tag: &SessionTag) -> &'static str {
+    match tag.rssi_trend() {
+        RssiTrend::Rising => "↑",
+        RssiTrend::Falling => "↓",
+        RssiTrend::Steady => "→",
+    }
+}
+
+/// Single line describing a session event, for non-terminal output
+pub fn event_line(event: &SessionEvent, database: Option<&Database>) -> String {
+    match event {
+        SessionEvent::Appeared(tag) => format!(
+            "{} APPEARED {} sample={} rssi={} status={}",
+            tag.timestamp.format("%H:%M:%S"),
+            tag.epc,
+            tag.tag_id,
+            tag.rssi,
+            sample_status(database, &tag.tag_id)
+        ),
+        SessionEvent::Missing(tag) => format!(
+            "{} MISSING {} sample={} last_seen={}",
+            chrono::Utc::now().format("%H:%M:%S"),
+            tag.scan.epc,
+            tag.scan.tag_id,
+            tag.last_seen.format("%H:%M:%S")
+        ),
+    }
+}
+
+/// Full-screen table of the tracked tags. Tags in `appeared` are highlighted
+/// and tags missed in the latest cycle are dimmed.
+pub fn render_table(session: &InventorySession, database: Option<&Database>, appeared: &[&str]) -> String {
+    let mut out = String::from(CLEAR_SCREEN);
+    let tags = session.tags();
+    let _ = writeln!(
+        out,
+        "Inventory watch - cycle {} - {} tags  [p] pause  [c] clear  [d] dump CSV  [q] quit\n",
+        session.cycles(),
+        tags.len()
+    );
+    let _ = writeln!(out, "{:<26} {:<20} {:>6}   {:<10} {:<12}", "EPC", "SAMPLE", "RSSI", "LAST SEEN", "STATUS");
+
+    for tag in tags {
+        let style = if appeared.contains(&tag.scan.epc.as_str()) {
+            GREEN
+        } else if tag.is_fading() {
+            DIM
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            out,
+            "{}{:<26} {:<20} {:>6} {} {:<10} {:<12}{}",
+            style,
+            tag.scan.epc,
+            tag.scan.tag_id,
+            tag.scan.rssi,
+            trend_arrow(tag),
+            tag.last_seen.format("%H:%M:%S"),
+            sample_status(database, &tag.scan.tag_id),
+            if style.is_empty() { "" } else { RESET }
+        );
+    }
+    out
+}
+
+/// CSV rendering of the tracked tags
+pub fn session_csv(session: &InventorySession, database: Option<&Database>) -> String {
+    let mut out = String::from("epc,sample_id,rssi,antenna,first_seen,last_seen,missed_cycles,status\n");
+    for tag in session.tags() {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            tag.scan.epc,
+            tag.scan.tag_id,
+            tag.scan.rssi,
+            tag.scan.antenna,
+            tag.first_seen.to_rfc3339(),
+            tag.last_seen.to_rfc3339(),
+            tag.missed_cycles,
+            sample_status(database, &tag.scan.tag_id)
+        );
+    }
+    out
+}
+
+/// Emulated reader selected with `--reader`
+pub fn reader_source(kind: &str, simulator: crate::hardware::simulator::TagSimulator) -> Result<Box<dyn TagSource>> {
+    use crate::hardware::{ImpinjSpeedwayReader, ZebraFX9600Reader};
+
+    match kind {
+        "impinj" => Ok(Box::new(ImpinjSpeedwayReader::new().with_simulator(simulator))),
+        "zebra" => Ok(Box::new(ZebraFX9600Reader::new().with_simulator(simulator))),
+        "simulator" => Ok(Box::new(simulator)),
+        other => Err(SampleGuardError::ConfigError(format!(
+            "Unknown reader '{}' (expected impinj, zebra or simulator)", other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::simulator::{SimulatedTag, TagSimulator};
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_watch_command_parsing() {
+        assert_eq!(WatchCommand::parse("p\n"), Some(WatchCommand::TogglePause));
+        assert_eq!(WatchCommand::parse("d"), Some(WatchCommand::Dump));
+        assert_eq!(WatchCommand::parse("x"), None);
+    }
+
+    #[test]
+    fn test_tty_table_highlights_new_tags() {
+        let mut simulator = TagSimulator::new().with_network_delay(Duration::ZERO);
+        simulator.add_tag(SimulatedTag::new("E2001".to_string(), "S-1".to_string(), vec![]));
+
+        let mut session = InventorySession::new(2);
+        let results = simulator.scan_cycle(Duration::from_millis(20)).unwrap();
+        session.observe(results, chrono::Utc::now());
+
+        let table = render_table(&session, None, &["E2001"]);
+        assert!(table.starts_with(CLEAR_SCREEN));
+        assert!(table.contains(&format!("{}E2001", GREEN)));
+        assert!(session_csv(&session, None).contains("E2001,S-1,"));
+    }
+
+    #[test]
+    fn test_quit_command_stops_watch() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(WatchCommand::Quit).unwrap();
+        let mut simulator = TagSimulator::new();
+        let session = watch(
+            &mut simulator,
+            None,
+            &WatchOptions::default(),
+            &mut Vec::new(),
+            &rx,
+            &AtomicBool::new(false),
+        ).unwrap();
+        assert_eq!(session.cycles(), 0);
+    }
+}
@@ -0,0 +1,71 @@
+pub mod db;
+pub mod encode_station;
+pub mod inventory;
+pub mod self_test;
+pub mod serve;
+pub mod tag;
+pub mod worker;
+
+use crate::config::SampleGuardConfig;
+use crate::error::{SampleGuardError, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+pub use serve::run_serve;
+pub use worker::run_worker;
+
+/// Load configuration from `path`, or use defaults when no file is given,
+/// then apply environment overrides
+pub fn load_config(path: Option<&Path>) -> Result<SampleGuardConfig> {
+    let mut config = match path {
+        Some(path) => SampleGuardConfig::load(path)?,
+        None => SampleGuardConfig::default(),
+    };
+    config.apply_env_overrides()?;
+    Ok(config)
+}
+
+/// Load configuration and refuse it if validation finds any problem
+pub fn load_valid_config(path: Option<&Path>) -> Result<SampleGuardConfig> {
+    let config = load_config(path)?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// Output of `config check`: whether the configuration is valid, and the text to print
+pub fn check_config(path: Option<&Path>) -> (bool, String) {
+    let config = match load_config(path) {
+        Ok(config) => config,
+        Err(e) => return (false, format!("error: {}\n", e)),
+    };
+
+    let issues = config.issues();
+    if !issues.is_empty() {
+        let report: String = issues.iter().map(|i| format!("error: {}\n", i)).collect();
+        return (false, report);
+    }
+
+    match config.redacted().to_toml_string() {
+        Ok(dump) => (true, format!("OK\n\n{}", dump)),
+        Err(e) => (false, format!("error: {}\n", e)),
+    }
+}
+
+/// Resolve the database path from an explicit argument or the configuration
+pub fn database_path(explicit: Option<PathBuf>, config: &SampleGuardConfig) -> Result<PathBuf> {
+    explicit
+        .or_else(|| config.database.path.clone())
+        .ok_or_else(|| SampleGuardError::ConfigError(
+            "No database given; pass --db or set database.path in the configuration".to_string()
+        ))
+}
+
+/// Install a Ctrl-C handler and return the flag it sets
+pub fn shutdown_flag() -> Result<Arc<AtomicBool>> {
+    let flag = Arc::new(AtomicBool::new(false));
+    let handler_flag = Arc::clone(&flag);
+    ctrlc::set_handler(move || handler_flag.store(true, Ordering::SeqCst))
+        .map_err(|e| SampleGuardError::ConfigError(format!("Failed to install signal handler: {}", e)))?;
+    Ok(flag)
+}
@@ -0,0 +1,278 @@
+use crate::api::middleware::correlation_id;
+use crate::api::{configure_routes, create_app_state_from_config};
+use crate::audit::{AuditEventType, AuditLogger, AuditSeverity};
+use crate::cli::encode_station::{simulator_from_settings, EncodingField};
+use crate::commission::{verify_with, VerifyOptions};
+use crate::config::SampleGuardConfig;
+use crate::database::{Database, SCHEMA_VERSION};
+use crate::error::{SampleGuardError, Result};
+use crate::hardware::simulator::SimulatedTag;
+use crate::integrity::IntegrityValidator;
+use crate::sample::{default_encryption, Sample, SampleMetadata};
+use crate::tag::RFIDTag;
+use crate::temperature::{MockTemperatureSensor, TemperatureMonitor};
+use actix_web::{web, App, HttpServer};
+use chrono::Utc;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Outcome of one self-test step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepStatus {
+    Pass,
+    Fail(String),
+    /// Not run, because a step it depends on failed or the hardware
+    /// it checks is not configured
+    Skip(String),
+}
+
+/// A completed self-test step
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub name: &'static str,
+    pub status: StepStatus,
+    /// What the step verified, when it passed
+    pub detail: String,
+    pub duration: Duration,
+}
+
+/// Results of a self-test run
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestReport {
+    pub steps: Vec<StepResult>,
+}
+
+impl SelfTestReport {
+    /// Whether no step failed
+    pub fn passed(&self) -> bool {
+        !self.steps.iter().any(|s| matches!(s.status, StepStatus::Fail(_)))
+    }
+
+    pub fn step(&self, name: &str) -> Option<&StepResult> {
+        self.steps.iter().find(|s| s.name == name)
+    }
+
+    fn run<T>(&mut self, name: &'static str, step: impl FnOnce() -> Result<(T, String)>) -> Option<T> {
+        let start = Instant::now();
+        let (status, detail, value) = match step() {
+            Ok((value, detail)) => (StepStatus::Pass, detail, Some(value)),
+            Err(e) => (StepStatus::Fail(e.to_string()), String::new(), None),
+        };
+        self.steps.push(StepResult { name, status, detail, duration: start.elapsed() });
+        value
+    }
+
+    fn skip(&mut self, name: &'static str, reason: &str) {
+        self.steps.push(StepResult {
+            name,
+            status: StepStatus::Skip(reason.to_string()),
+            detail: String::new(),
+            duration: Duration::ZERO,
+        });
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            let (label, message) = match &step.status {
+                StepStatus::Pass => ("PASS", step.detail.as_str()),
+                StepStatus::Fail(e) => ("FAIL", e.as_str()),
+                StepStatus::Skip(reason) => ("SKIP", reason.as_str()),
+            };
+            writeln!(
+                f,
+                "{} {:<12} {:>7.1} ms  {}",
+                label,
+                step.name,
+                step.duration.as_secs_f64() * 1000.0,
+                message
+            )?;
+        }
+        let failed = self.steps.iter().filter(|s| matches!(s.status, StepStatus::Fail(_))).count();
+        if failed == 0 {
+            write!(f, "Self-test passed ({} steps)", self.steps.len())
+        } else {
+            write!(f, "Self-test FAILED ({} of {} steps)", failed, self.steps.len())
+        }
+    }
+}
+
+/// Run the self-test sequence against the configured subsystems. Every step
+/// runs even if earlier ones fail, except steps that need an earlier result.
+pub fn run_self_test(config: &SampleGuardConfig) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+    let sample = throwaway_sample();
+
+    report.run("config", || {
+        config.validate()?;
+        Ok(((), "configuration is valid".to_string()))
+    });
+
+    report.run("database", || {
+        let db = match &config.database.path {
+            Some(path) => Database::new(path)?,
+            None => Database::in_memory()?,
+        };
+        let version = db.schema_version()?;
+        if version != SCHEMA_VERSION {
+            return Err(fail(format!("schema version {}, expected {}", version, SCHEMA_VERSION)));
+        }
+        db.store_sample(&sample)?;
+        let stored = db.get_sample(&sample.sample_id)?;
+        db.delete_sample(&sample.sample_id)?;
+        match stored {
+            Some(stored) if stored.content_eq(&sample) => {
+                Ok(((), format!("schema v{}, sample stored and removed", version)))
+            }
+            _ => Err(fail("stored sample did not read back".to_string())),
+        }
+    });
+
+    let capabilities = config.capabilities();
+    let read_back = if capabilities.has_reader {
+        report.run("reader", || reader_step(config, &sample))
+    } else {
+        report.skip("reader", "no reader configured");
+        None
+    };
+
+    match read_back {
+        Some(decoded) => {
+            report.run("integrity", || {
+                let result = IntegrityValidator::new().validate(&decoded)?;
+                if !result.is_valid() {
+                    return Err(SampleGuardError::IntegrityViolation(result));
+                }
+                if !decoded.content_eq(&sample) {
+                    return Err(fail("decoded sample differs from the written one".to_string()));
+                }
+                Ok(((), "read-back sample is valid".to_string()))
+            });
+        }
+        None if capabilities.has_reader => report.skip("integrity", "reader step failed"),
+        None => report.skip("integrity", "no reader configured"),
+    }
+
+    if capabilities.has_temperature_sensor {
+        report.run("temperature", || temperature_step(config));
+    } else {
+        report.skip("temperature", "no temperature sensor configured");
+    }
+
+    report.run("audit", || {
+        let mut logger = match &config.audit.file {
+            Some(path) => AuditLogger::with_file(path)?,
+            None => AuditLogger::new(),
+        };
+        logger.log_event(
+            AuditEventType::UserAction,
+            None,
+            Some(sample.sample_id.clone()),
+            serde_json::json!({ "action": "self_test" }),
+            AuditSeverity::Info,
+        )?;
+        logger.flush()?;
+        let found = logger
+            .get_events_by_sample(&sample.sample_id)
+            .iter()
+            .any(|e| e.details["action"] == "self_test");
+        if !found {
+            return Err(fail("logged event could not be read back".to_string()));
+        }
+        Ok(((), "event logged and read back".to_string()))
+    });
+
+    report.run("http", || {
+        let status = actix_web::rt::System::new().block_on(check_http(config))?;
+        Ok(((), status))
+    });
+
+    report
+}
+
+/// Encode `sample` onto a simulated tag, verify it and decode it back
+fn reader_step(config: &SampleGuardConfig, sample: &Sample) -> Result<(Sample, String)> {
+    let mut field = simulator_from_settings(&config.reader)?;
+    if field.find_blank_tag()?.is_none() {
+        field.add_tag(SimulatedTag::new("E200SELFTEST".to_string(), "SELF-TEST".to_string(), Vec::new()));
+    }
+    let epc = field.find_blank_tag()?.ok_or_else(|| fail("no blank tag in field".to_string()))?;
+
+    let bytes = sample.to_tag_with_key(default_encryption())?.to_bytes()?;
+    EncodingField::write_tag(&mut field, &epc, &bytes)?;
+    let outcome = verify_with(|| EncodingField::read_tag(&mut field, &epc), &bytes, &VerifyOptions::default());
+    if !outcome.is_verified() {
+        return Err(fail(format!("read-back failed: {:?}", outcome)));
+    }
+
+    let data = EncodingField::read_tag(&mut field, &epc)?;
+    let decoded = Sample::from_tag_with_key(&RFIDTag::from_bytes(data.as_bytes())?, default_encryption())?;
+    Ok((decoded, format!("wrote and read back {} bytes on {}", bytes.len(), epc)))
+}
+
+/// Take one reading from the configured sensor
+fn temperature_step(config: &SampleGuardConfig) -> Result<((), String)> {
+    let t = &config.temperature;
+    let sensor = Box::new(MockTemperatureSensor::new(t.sensor_id.clone(), (t.min_celsius + t.max_celsius) / 2.0));
+    let mut monitor = TemperatureMonitor::new(sensor, (t.min_celsius, t.max_celsius))?;
+    let reading = monitor.read_temperature(Some("self-test".to_string()))?;
+    Ok(((), format!("{} read {:.1}°C", reading.sensor_id, reading.temperature)))
+}
+
+/// Start the API on an ephemeral port, request /health and shut it down
+async fn check_http(config: &SampleGuardConfig) -> Result<String> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let state = create_app_state_from_config(config)?;
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(state.clone()))
+            .wrap(actix_web::middleware::from_fn(correlation_id))
+            .configure(configure_routes)
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))?;
+    let addr = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    let running = actix_web::rt::spawn(server);
+
+    let request = async {
+        let mut stream = tokio::net::TcpStream::connect(addr).await?;
+        stream
+            .write_all(format!("GET /api/v1/health HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", addr).as_bytes())
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok::<String, std::io::Error>(response)
+    };
+    let response = tokio::time::timeout(Duration::from_secs(5), request).await;
+
+    handle.stop(true).await;
+    let _ = running.await;
+
+    let response = response.map_err(|_| fail("timed out waiting for /health".to_string()))??;
+    let status_line = response.lines().next().unwrap_or_default().to_string();
+    if !status_line.contains(" 200 ") {
+        return Err(fail(format!("/health returned '{}'", status_line)));
+    }
+    Ok(format!("/health on {} returned 200", addr))
+}
+
+fn fail(message: String) -> SampleGuardError {
+    SampleGuardError::IoError(std::io::Error::other(message))
+}
+
+fn throwaway_sample() -> Sample {
+    let metadata = SampleMetadata {
+        batch_number: "SELF-TEST".to_string(),
+        production_date: Utc::now(),
+        expiry_date: Some(Utc::now() + chrono::Duration::days(1)),
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Self-test".to_string(),
+        manufacturer: "SampleGuard".to_string(),
+        product_line: "Self-test".to_string(),
+    };
+    Sample::new(format!("SELF-TEST-{}", uuid::Uuid::new_v4()), metadata, None)
+}
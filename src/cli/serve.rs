@@ -0,0 +1,38 @@
+use crate::api::{create_app_state_from_config, start_server_with_config};
+use crate::config::SampleGuardConfig;
+use crate::error::Result;
+use crate::jobs::{JobScheduler, SystemClock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Run the HTTP API, with any configured jobs scheduled in-process
+pub async fn run_serve(config: &SampleGuardConfig) -> Result<()> {
+    let state = create_app_state_from_config(config)?;
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    let mut scheduler = JobScheduler::from_config(state.job_context(), Arc::new(SystemClock), &config.jobs)?
+        .with_registry(state.job_status.clone());
+    let worker = if scheduler.job_count() > 0 {
+        let shutdown = Arc::clone(&shutdown);
+        Some(std::thread::spawn(move || {
+            scheduler.run(&shutdown, super::worker::POLL_INTERVAL)
+        }))
+    } else {
+        None
+    };
+
+    let active = state.capabilities.active();
+    log::info!(
+        "Active capabilities: {}",
+        if active.is_empty() { "none (database only)".to_string() } else { active.join(", ") }
+    );
+    log::info!("Starting server on http://{}:{}", config.server.host, config.server.port);
+    let result = start_server_with_config(state, &config.server).await;
+
+    shutdown.store(true, Ordering::SeqCst);
+    if let Some(worker) = worker {
+        let _ = worker.join();
+    }
+
+    result.map_err(Into::into)
+}
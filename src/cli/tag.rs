@@ -0,0 +1,345 @@
+use crate::encryption::RFIDEncryption;
+use crate::error::{SampleGuardError, Result};
+use crate::sample::{default_encryption, ChecksumVersion, Sample};
+use crate::tag::{RFIDTag, BINARY_TAG_MAGIC, TAG_VERSION_SHARED_KEY};
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+/// Result of a single decode check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    Failed(String),
+    Skipped(String),
+    /// Passed, with something worth knowing
+    Warning(String),
+}
+
+/// A named check performed while decoding a tag dump
+#[derive(Debug, Clone)]
+pub struct DecodeCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+}
+
+/// Everything that could be recovered from a tag dump
+///
+/// Decoding never stops at the first problem: each stage records whether it
+/// passed, and later stages are skipped only when they cannot run at all.
+#[derive(Debug, Clone, Default)]
+pub struct TagDecodeReport {
+    pub format: Option<&'static str>,
+    pub tag_id: Option<String>,
+    pub header: Option<[u8; 16]>,
+    pub written_at: Option<DateTime<Utc>>,
+    pub read_count: Option<u64>,
+    pub payload_len: Option<usize>,
+    pub checks: Vec<DecodeCheck>,
+    pub sample: Option<Sample>,
+}
+
+impl TagDecodeReport {
+    fn check(&mut self, name: &'static str, status: CheckStatus) {
+        self.checks.push(DecodeCheck { name, status });
+    }
+
+    /// Status of the named check, if it ran
+    pub fn check_status(&self, name: &str) -> Option<&CheckStatus> {
+        self.checks.iter().find(|c| c.name == name).map(|c| &c.status)
+    }
+
+    /// Whether every check that ran passed
+    pub fn all_passed(&self) -> bool {
+        self.checks.iter().all(|c| !matches!(c.status, CheckStatus::Failed(_)))
+    }
+}
+
+impl fmt::Display for TagDecodeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Format:        {}", self.format.unwrap_or("unknown"))?;
+        if let Some(tag_id) = &self.tag_id {
+            writeln!(f, "Tag ID:        {}", tag_id)?;
+        }
+        if let Some(header) = &self.header {
+            writeln!(f, "Header:        {}", hex::encode(header))?;
+            writeln!(f, "  tag type:    0x{:02x}", header[0])?;
+            writeln!(f, "  version:     0x{:02x}", header[1])?;
+            writeln!(f, "  encrypted:   {}", header[2] != 0)?;
+        }
+        if let Some(written_at) = &self.written_at {
+            writeln!(f, "Written at:    {}", written_at.to_rfc3339())?;
+        }
+        if let Some(read_count) = self.read_count {
+            writeln!(f, "Read count:    {}", read_count)?;
+        }
+        if let Some(payload_len) = self.payload_len {
+            writeln!(f, "Payload:       {} bytes", payload_len)?;
+        }
+
+        writeln!(f, "Checks:")?;
+        for check in &self.checks {
+            match &check.status {
+                CheckStatus::Passed => writeln!(f, "  [PASS] {}", check.name)?,
+                CheckStatus::Failed(reason) => writeln!(f, "  [FAIL] {}: {}", check.name, reason)?,
+                CheckStatus::Skipped(reason) => writeln!(f, "  [SKIP] {}: {}", check.name, reason)?,
+                CheckStatus::Warning(reason) => writeln!(f, "  [WARN] {}: {}", check.name, reason)?,
+            }
+        }
+
+        if let Some(sample) = &self.sample {
+            let json = serde_json::to_string_pretty(sample).map_err(|_| fmt::Error)?;
+            writeln!(f, "Sample:")?;
+            writeln!(f, "{}", json)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a hex dump, ignoring whitespace, colons and an optional `0x` prefix
+pub fn parse_hex(input: &str) -> Result<Vec<u8>> {
+    let trimmed = input.trim();
+    let trimmed = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+    let cleaned: String = trimmed
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ':' && *c != '-')
+        .collect();
+    hex::decode(cleaned).map_err(|e| SampleGuardError::TagParseError(format!("Invalid hex: {}", e)))
+}
+
+/// Decode a raw tag dump in either format as far as possible, optionally
+/// decrypting with `key`. A sample checksum in the legacy layout fails
+/// unless `allow_legacy_checksums` is set, when it only warns.
+pub fn decode_tag(data: &[u8], key: Option<&[u8]>, allow_legacy_checksums: bool) -> TagDecodeReport {
+    let mut report = TagDecodeReport::default();
+
+    let tag = if data.starts_with(&BINARY_TAG_MAGIC) {
+        match RFIDTag::from_bytes_binary(data) {
+            Ok(tag) => {
+                report.format = Some("binary");
+                report.check("envelope", CheckStatus::Passed);
+                tag
+            }
+            Err(e) => {
+                report.check("envelope", CheckStatus::Failed(e.to_string()));
+                return report;
+            }
+        }
+    } else {
+        match decode_json_envelope(data, &mut report) {
+            Some(tag) => tag,
+            None => return report,
+        }
+    };
+
+    let layout = &tag.memory_layout;
+    report.tag_id = Some(tag.tag_id.clone());
+    report.header = Some(layout.header);
+    report.payload_len = Some(layout.payload.len());
+    let written_secs = u64::from_be_bytes(layout.metadata[0..8].try_into().unwrap_or([0; 8]));
+    report.written_at = DateTime::from_timestamp(written_secs as i64, 0);
+    report.read_count = Some(u64::from_be_bytes(layout.metadata[8..16].try_into().unwrap_or([0; 8])));
+
+    if layout.header[0] == 0x01 {
+        report.check("tag type", CheckStatus::Passed);
+    } else {
+        report.check("tag type", CheckStatus::Failed(format!("unexpected type 0x{:02x}", layout.header[0])));
+    }
+
+    // The integrity hash is a plain SHA-256, so it can be checked without the key
+    let hasher = RFIDEncryption::new(key.unwrap_or_default());
+    if hasher.hash(&layout.payload) == layout.integrity_hash {
+        report.check("integrity hash", CheckStatus::Passed);
+    } else {
+        report.check("integrity hash", CheckStatus::Failed("hash does not match payload".to_string()));
+    }
+
+    let Some(key) = key else {
+        report.check("decryption", CheckStatus::Skipped("no key supplied".to_string()));
+        return report;
+    };
+
+    let decrypted = tag.payload_cipher(&RFIDEncryption::new(key)).and_then(|encryption| match layout.header[1] {
+        TAG_VERSION_SHARED_KEY => encryption.decrypt(&layout.payload),
+        _ => encryption.decrypt_for_tag(&tag.tag_id, &layout.payload),
+    });
+    let plaintext = match decrypted {
+        Ok(plaintext) => {
+            report.check("decryption", CheckStatus::Passed);
+            plaintext
+        }
+        Err(e) => {
+            report.check("decryption", CheckStatus::Failed(e.to_string()));
+            return report;
+        }
+    };
+
+    match serde_json::from_slice::<Sample>(&plaintext) {
+        Ok(sample) => {
+            report.check("sample", CheckStatus::Passed);
+            let status = match sample.checksum_version() {
+                Some(ChecksumVersion::LengthPrefixed) => CheckStatus::Passed,
+                Some(ChecksumVersion::Legacy) if allow_legacy_checksums => {
+                    CheckStatus::Warning("checksum is in the legacy layout".to_string())
+                }
+                Some(ChecksumVersion::Legacy) => CheckStatus::Failed(
+                    "checksum is in the legacy layout; pass --allow-legacy-checksums to accept it".to_string(),
+                ),
+                None => CheckStatus::Failed("checksum does not match contents".to_string()),
+            };
+            report.check("sample checksum", status);
+            report.sample = Some(sample);
+        }
+        Err(e) => report.check("sample", CheckStatus::Failed(e.to_string())),
+    }
+
+    report
+}
+
+/// Parse the length-prefixed JSON container, recording its checks
+fn decode_json_envelope(data: &[u8], report: &mut TagDecodeReport) -> Option<RFIDTag> {
+    if data.len() < 4 {
+        report.check("length prefix", CheckStatus::Failed(format!("only {} bytes", data.len())));
+        return None;
+    }
+    let declared = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let available = data.len() - 4;
+    if declared > available {
+        report.check(
+            "length prefix",
+            CheckStatus::Failed(format!("declares {} bytes but only {} present", declared, available)),
+        );
+    } else {
+        report.check("length prefix", CheckStatus::Passed);
+    }
+
+    let body = &data[4..4 + declared.min(available)];
+    match serde_json::from_slice(body) {
+        Ok(tag) => {
+            report.format = Some("json (length-prefixed)");
+            report.check("envelope", CheckStatus::Passed);
+            Some(tag)
+        }
+        Err(e) => {
+            report.check("envelope", CheckStatus::Failed(format!("not a recognised tag container: {}", e)));
+            None
+        }
+    }
+}
+
+/// Encode a sample into raw tag bytes, using `key` or the default sample key
+pub fn encode_tag(sample: &Sample, key: Option<&[u8]>) -> Result<Vec<u8>> {
+    let tag = match key {
+        Some(key) => sample.to_tag_with_key(&RFIDEncryption::new(key))?,
+        None => sample.to_tag_with_key(default_encryption())?,
+    };
+    tag.to_bytes()
+}
+
+/// Read key material from the named environment variable
+pub fn key_from_env(var: &str) -> Result<Vec<u8>> {
+    std::env::var(var)
+        .map(String::into_bytes)
+        .map_err(|_| SampleGuardError::ConfigError(format!("Environment variable {} is not set", var)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample::SampleMetadata;
+
+    const KEY: &[u8] = b"decode_test_key_32_bytes_long!!!";
+
+    fn create_test_sample() -> Sample {
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-DECODE".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        Sample::new("DECODE-001".to_string(), metadata, None)
+    }
+
+    #[test]
+    fn test_decode_roundtrip() {
+        let sample = create_test_sample();
+        let bytes = encode_tag(&sample, Some(KEY)).unwrap();
+
+        let report = decode_tag(&bytes, Some(KEY), false);
+        assert!(report.all_passed());
+        assert_eq!(report.sample.unwrap().sample_id, "DECODE-001");
+    }
+
+    #[test]
+    fn test_decode_binary_dump() {
+        let sample = create_test_sample();
+        let bytes = sample.to_tag_with_key(&RFIDEncryption::new(KEY)).unwrap().to_bytes_binary().unwrap();
+
+        let report = decode_tag(&bytes, Some(KEY), false);
+        assert!(report.all_passed());
+        assert_eq!(report.format, Some("binary"));
+        assert_eq!(report.sample.unwrap().sample_id, "DECODE-001");
+    }
+
+    #[test]
+    fn test_decode_legacy_checksum_warns_when_allowed() {
+        let mut sample = create_test_sample();
+        sample.integrity_checksum = Sample::calculate_checksum_with(
+            ChecksumVersion::Legacy,
+            &sample.sample_id,
+            &sample.metadata,
+            &sample.last_updated,
+        );
+        let bytes = encode_tag(&sample, Some(KEY)).unwrap();
+
+        let strict = decode_tag(&bytes, Some(KEY), false);
+        assert!(matches!(strict.check_status("sample checksum"), Some(CheckStatus::Failed(_))));
+        let lenient = decode_tag(&bytes, Some(KEY), true);
+        assert!(matches!(lenient.check_status("sample checksum"), Some(CheckStatus::Warning(_))));
+        assert!(lenient.all_passed());
+    }
+
+    #[test]
+    fn test_decode_without_key_skips_decryption() {
+        let bytes = encode_tag(&create_test_sample(), Some(KEY)).unwrap();
+        let report = decode_tag(&bytes, None, false);
+
+        assert_eq!(report.check_status("integrity hash"), Some(&CheckStatus::Passed));
+        assert!(matches!(report.check_status("decryption"), Some(CheckStatus::Skipped(_))));
+        assert!(report.sample.is_none());
+    }
+
+    #[test]
+    fn test_decode_corrupted_dump_reports_partial_info() {
+        let bytes = encode_tag(&create_test_sample(), Some(KEY)).unwrap();
+        let mut tag = RFIDTag::from_bytes(&bytes).unwrap();
+        tag.memory_layout.payload[20] ^= 0xff;
+        let corrupted = tag.to_bytes().unwrap();
+
+        let report = decode_tag(&corrupted, Some(KEY), false);
+        assert_eq!(report.tag_id.as_deref(), Some("DECODE-001"));
+        assert_eq!(report.header.unwrap()[0], 0x01);
+        assert!(matches!(report.check_status("integrity hash"), Some(CheckStatus::Failed(_))));
+
+        let output = report.to_string();
+        assert!(output.contains("Tag ID:        DECODE-001"));
+        assert!(output.contains("[FAIL] integrity hash"));
+    }
+
+    #[test]
+    fn test_decode_truncated_dump() {
+        let bytes = encode_tag(&create_test_sample(), None).unwrap();
+        let report = decode_tag(&bytes[..bytes.len() / 2], None, false);
+
+        assert!(matches!(report.check_status("length prefix"), Some(CheckStatus::Failed(_))));
+        assert!(matches!(report.check_status("envelope"), Some(CheckStatus::Failed(_))));
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("0x01 02:ff").unwrap(), vec![0x01, 0x02, 0xff]);
+        assert!(parse_hex("zz").is_err());
+    }
+}
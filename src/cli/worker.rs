@@ -0,0 +1,23 @@
+use crate::api::create_app_state_from_config;
+use crate::config::SampleGuardConfig;
+use crate::error::Result;
+use crate::jobs::{JobScheduler, SystemClock};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the worker checks for due jobs
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Run the configured background jobs until `shutdown` is set
+pub fn run_worker(config: &SampleGuardConfig, shutdown: Arc<AtomicBool>) -> Result<()> {
+    let state = create_app_state_from_config(config)?;
+    let mut scheduler = JobScheduler::from_config(state.job_context(), Arc::new(SystemClock), &config.jobs)?
+        .with_registry(state.job_status.clone());
+
+    log::info!("Worker started with {} scheduled jobs", scheduler.job_count());
+    scheduler.run(&shutdown, POLL_INTERVAL);
+    log::info!("Worker stopped");
+
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use crate::error::Result;
+use crate::reader::RFIDReader;
+use crate::tag::TagData;
+use std::time::Duration;
+
+/// Read-back verification settings for verified tag writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Extra read-back attempts after the first one
+    pub verify_retries: u32,
+    /// Delay before the first retry; doubled on each further retry
+    pub initial_backoff: Duration,
+    /// Upper bound for the retry delay
+    pub max_backoff: Duration,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            verify_retries: 3,
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl VerifyOptions {
+    /// Set the number of read-back retries
+    pub fn with_retries(mut self, verify_retries: u32) -> Self {
+        self.verify_retries = verify_retries;
+        self
+    }
+
+    /// Set the initial and maximum retry delay
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Result of verifying a tag write by reading it back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    /// The tag returned exactly the written bytes
+    Verified { attempts: u32 },
+    /// The tag was readable but never returned the written bytes
+    Mismatch { attempts: u32 },
+    /// Every read-back attempt failed, so the contents are unknown
+    ReadFailed { attempts: u32, last_error: String },
+}
+
+impl VerifyOutcome {
+    pub fn is_verified(&self) -> bool {
+        matches!(self, VerifyOutcome::Verified { .. })
+    }
+
+    /// Number of read-back attempts made
+    pub fn attempts(&self) -> u32 {
+        match self {
+            VerifyOutcome::Verified { attempts }
+            | VerifyOutcome::Mismatch { attempts }
+            | VerifyOutcome::ReadFailed { attempts, .. } => *attempts,
+        }
+    }
+}
+
+/// Read the tag back until it returns `expected` or the retry budget runs out.
+///
+/// Retryable read errors are retried; any other error ends verification at
+/// once. A readable tag whose contents never match is reported as a
+/// mismatch, which indicates real corruption.
+pub fn verify_write<R: RFIDReader + ?Sized>(
+    reader: &mut R,
+    expected: &[u8],
+    options: &VerifyOptions,
+) -> VerifyOutcome {
+    verify_with(|| reader.read_tag(), expected, options)
+}
+
+/// Same as [`verify_write`], reading the tag through `read`
+pub fn verify_with<F>(mut read: F, expected: &[u8], options: &VerifyOptions) -> VerifyOutcome
+where
+    F: FnMut() -> Result<TagData>,
+{
+    let max_attempts = options.verify_retries.saturating_add(1);
+    let mut saw_mismatch = false;
+    let mut last_error = String::new();
+
+    for attempt in 1..=max_attempts {
+        if attempt > 1 {
+            std::thread::sleep(options.backoff(attempt - 1));
+        }
+
+        match read() {
+            Ok(data) if data.as_bytes() == expected => {
+                log::debug!(attempt = attempt; "Write verified on attempt {}/{}", attempt, max_attempts);
+                return VerifyOutcome::Verified { attempts: attempt };
+            }
+            Ok(_) => {
+                log::warn!(attempt = attempt; "Read-back mismatch on attempt {}/{}", attempt, max_attempts);
+                saw_mismatch = true;
+            }
+            Err(e) if !e.is_retryable() => {
+                log::warn!(attempt = attempt; "Read-back failed on attempt {}/{} and will not be retried: {}", attempt, max_attempts, e);
+                return VerifyOutcome::ReadFailed { attempts: attempt, last_error: e.to_string() };
+            }
+            Err(e) => {
+                log::warn!(attempt = attempt; "Read-back failed on attempt {}/{}: {}", attempt, max_attempts, e);
+                last_error = e.to_string();
+            }
+        }
+    }
+
+    if saw_mismatch {
+        VerifyOutcome::Mismatch { attempts: max_attempts }
+    } else {
+        VerifyOutcome::ReadFailed { attempts: max_attempts, last_error }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SampleGuardError;
+    use crate::reader::{MockRFIDReader, ReaderCapabilities, ReaderConfig};
+
+    /// Reader whose reads only succeed every `fail_every`-th time, optionally corrupting the data
+    struct FlakyReader {
+        inner: MockRFIDReader,
+        reads: u32,
+        fail_every: u32,
+        corrupt: bool,
+    }
+
+    impl RFIDReader for FlakyReader {
+        fn initialize(&mut self) -> Result<()> {
+            self.inner.initialize()
+        }
+
+        fn read_tag(&mut self) -> Result<TagData> {
+            self.reads += 1;
+            if self.fail_every > 0 && !self.reads.is_multiple_of(self.fail_every) {
+                return Err(SampleGuardError::ReaderError("CRC error on air interface".to_string()));
+            }
+            let mut data = self.inner.read_tag()?;
+            if self.corrupt {
+                data.bytes[0] ^= 0xff;
+            }
+            Ok(data)
+        }
+
+        fn write_tag(&mut self, data: &TagData) -> Result<()> {
+            self.inner.write_tag(data)
+        }
+
+        fn get_config(&self) -> &ReaderConfig {
+            self.inner.get_config()
+        }
+
+        fn get_capabilities(&self) -> &ReaderCapabilities {
+            self.inner.get_capabilities()
+        }
+
+        fn test_connection(&mut self) -> Result<bool> {
+            self.inner.test_connection()
+        }
+    }
+
+    fn options() -> VerifyOptions {
+        VerifyOptions::default()
+            .with_retries(4)
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(4))
+    }
+
+    fn flaky_reader(fail_every: u32, corrupt: bool) -> FlakyReader {
+        let mut reader = FlakyReader { inner: MockRFIDReader::new(), reads: 0, fail_every, corrupt };
+        reader.write_tag(&TagData::new(b"commissioned tag".to_vec())).unwrap();
+        reader
+    }
+
+    #[test]
+    fn test_transient_errors_verify_within_budget() {
+        // Two of every three reads fail
+        let mut reader = flaky_reader(3, false);
+        let outcome = verify_write(&mut reader, b"commissioned tag", &options());
+        assert_eq!(outcome, VerifyOutcome::Verified { attempts: 3 });
+    }
+
+    #[test]
+    fn test_wrong_data_fails_after_all_retries() {
+        let mut reader = flaky_reader(2, true);
+        let outcome = verify_write(&mut reader, b"commissioned tag", &options());
+        assert_eq!(outcome, VerifyOutcome::Mismatch { attempts: 5 });
+    }
+
+    #[test]
+    fn test_unreadable_tag_reports_read_failure() {
+        let mut reader = flaky_reader(100, false);
+        let outcome = verify_write(&mut reader, b"commissioned tag", &options());
+        assert!(matches!(outcome, VerifyOutcome::ReadFailed { attempts: 5, .. }));
+    }
+
+    #[test]
+    fn test_terminal_error_is_not_retried() {
+        let mut reads = 0;
+        let outcome = verify_with(
+            || {
+                reads += 1;
+                Err(SampleGuardError::TagParseError("Invalid tag header".to_string()))
+            },
+            b"commissioned tag",
+            &options(),
+        );
+        assert!(matches!(outcome, VerifyOutcome::ReadFailed { attempts: 1, .. }));
+        assert_eq!(reads, 1);
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let options = options();
+        assert_eq!(options.backoff(1), Duration::from_millis(1));
+        assert_eq!(options.backoff(2), Duration::from_millis(2));
+        assert_eq!(options.backoff(10), Duration::from_millis(4));
+    }
+}
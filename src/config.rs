@@ -0,0 +1,1027 @@
+use crate::audit::RotationPolicy;
+use crate::error::{SampleGuardError, Result};
+use crate::hardware::protocol::DEFAULT_MAX_BATCH_SIZE;
+use crate::region::RegulatoryRegion;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// SampleGuard configuration loaded from a TOML file.
+///
+/// Values are resolved in increasing order of precedence: built-in defaults,
+/// the configuration file, `SAMPLEGUARD_*` environment variables (see
+/// [`ENV_OVERRIDES`]), then command-line flags such as `--db`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SampleGuardConfig {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub audit: AuditConfig,
+    pub temperature: TemperatureConfig,
+    pub auth: AuthConfig,
+    pub rate_limit: RateLimitConfig,
+    pub reader: ReaderSettings,
+    pub readers: Vec<ReaderDefinition>,
+    pub reader_profiles: Vec<ReaderProfile>,
+    pub jobs: Vec<JobConfig>,
+    pub webhooks: Vec<WebhookConfig>,
+    pub samples: SamplesConfig,
+}
+
+/// Environment variables that override configuration values
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("SAMPLEGUARD_SERVER_HOST", "server.host"),
+    ("SAMPLEGUARD_SERVER_PORT", "server.port"),
+    ("SAMPLEGUARD_DATABASE_PATH", "database.path"),
+    ("SAMPLEGUARD_AUDIT_FILE", "audit.file"),
+    ("SAMPLEGUARD_TEMPERATURE_MIN_CELSIUS", "temperature.min_celsius"),
+    ("SAMPLEGUARD_TEMPERATURE_MAX_CELSIUS", "temperature.max_celsius"),
+    ("SAMPLEGUARD_AUTH_ENABLED", "auth.enabled"),
+    ("SAMPLEGUARD_READER_KIND", "reader.kind"),
+];
+
+/// HTTP server settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Requests handled at once across all workers, beyond which the server
+    /// answers 503; unlimited when unset. Health checks get one extra slot.
+    pub max_connections: Option<usize>,
+    /// Open connections per worker, beyond which new connections wait to be
+    /// accepted; actix's default when unset
+    pub max_connections_per_worker: Option<usize>,
+    /// Fill the database with demo samples at startup, for exploring the API
+    pub seed_demo_data: bool,
+    /// Number of demo samples seeded when `seed_demo_data` is on
+    pub demo_sample_count: usize,
+    /// Largest `per_page` honoured by paged listings; larger requests are
+    /// capped. Requests over 500 are rejected regardless.
+    pub max_page_size: usize,
+    /// How long `/statistics` reuses its database aggregates between sample
+    /// changes, e.g. "5s"; "0s" turns the cache off. 5 seconds when unset.
+    pub statistics_cache_ttl: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            max_connections: None,
+            max_connections_per_worker: None,
+            seed_demo_data: false,
+            demo_sample_count: 20,
+            max_page_size: 500,
+            statistics_cache_ttl: None,
+        }
+    }
+}
+
+/// Database settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// Where samples are stored; the API server and most commands need `sqlite`
+    pub backend: DatabaseBackendKind,
+    /// SQLite file path; an in-memory database is used when unset
+    pub path: Option<PathBuf>,
+    /// libpq-style connection string or `postgres://` URL of the `postgres` backend
+    pub url: Option<String>,
+    /// Require TLS on the Postgres connection, verifying the server against
+    /// the web PKI roots and `tls_ca_file`
+    pub tls: bool,
+    /// PEM file of extra CA certificates trusted for the Postgres server
+    pub tls_ca_file: Option<PathBuf>,
+    /// History rows kept per sample, the oldest folded into one rollup row;
+    /// unbounded when unset
+    pub max_history_depth: Option<usize>,
+    /// Accept samples still checksummed in the legacy layout, in the database
+    /// and on tags read by the server; only meant for the time before
+    /// `db verify --upgrade-checksums` has been run and old tags rewritten
+    pub allow_legacy_checksums: bool,
+}
+
+/// Storage behind [`DatabaseConfig`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseBackendKind {
+    #[default]
+    Sqlite,
+    /// Needs the `postgres` cargo feature
+    Postgres,
+}
+
+/// Audit log settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    /// JSON-lines audit file; events are kept in memory only when unset
+    pub file: Option<PathBuf>,
+    /// Rotate `file` by size; it grows without bound when unset
+    pub rotation: Option<RotationPolicy>,
+    /// Attach raw tag bytes to read and violation events for forensic replay
+    pub capture_raw_tags: bool,
+    /// Raw tag bytes kept per event, 4096 when unset
+    pub raw_tag_max_bytes: Option<usize>,
+    /// Events held in memory before the oldest are dropped, 10000 when unset
+    pub max_events: Option<usize>,
+    /// Directory that `POST /audit/archive` writes archives to; archiving
+    /// over the API is off when unset
+    pub archive_dir: Option<PathBuf>,
+    /// Chain events by hash so that edits can be detected
+    pub hash_chain: bool,
+    /// Also keep events in the `audit_events` table of the configured
+    /// database file, so they can be queried after a restart
+    pub database: bool,
+}
+
+/// Sample metadata settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SamplesConfig {
+    /// Allowed `storage_conditions` terms; any text is accepted when empty
+    pub storage_conditions: Vec<String>,
+    /// Largest sample image upload in bytes, 5 MiB when unset
+    pub max_image_bytes: Option<usize>,
+    /// Largest sample image width or height in pixels, 4096 when unset
+    pub max_image_dimension: Option<u32>,
+}
+
+/// Storage temperature monitoring settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemperatureConfig {
+    /// Whether a temperature sensor is attached
+    pub enabled: bool,
+    pub sensor_id: String,
+    pub min_celsius: f32,
+    pub max_celsius: f32,
+    /// Quiet period after a sensor's alert, e.g. `5m`; warning-level
+    /// violations during it are only counted. Unset alerts on every violation.
+    pub alert_cooldown: Option<String>,
+    /// Also keep readings and violations in the configured database file,
+    /// so their history survives a restart
+    pub database: bool,
+}
+
+impl Default for TemperatureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sensor_id: "API-SENSOR".to_string(),
+            min_celsius: 2.0,
+            max_celsius: 8.0,
+            alert_cooldown: None,
+            database: false,
+        }
+    }
+}
+
+/// API key authentication settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub enabled: bool,
+    pub api_keys: Vec<ApiKeyConfig>,
+}
+
+/// A named API key and the role it grants
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    pub name: String,
+    pub key: String,
+    pub role: ApiRole,
+}
+
+/// Access level granted by an API key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiRole {
+    Admin,
+    Operator,
+    ReadOnly,
+}
+
+/// Per-client API request rate limit, keyed by API key name or peer address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub requests_per_second: u32,
+    /// Requests allowed in a burst above the steady rate
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_second: 50,
+            burst: 100,
+        }
+    }
+}
+
+/// RFID reader settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReaderSettings {
+    /// Reader backend: `simulator`, or `none` for database-only deployments
+    pub kind: String,
+    /// Number of blank tags the simulator starts with
+    pub simulated_blank_tags: usize,
+    /// How long to wait for a blank tag in the field
+    pub tag_wait: String,
+    /// Read-back attempts after the first when verifying a write
+    pub verify_retries: u32,
+    /// Most EPCs a reader accepts in one batch read
+    pub max_batch_size: usize,
+    /// Antenna ports the simulator hops across while scanning; every tag
+    /// is visible when empty
+    pub antennas: Vec<u8>,
+    /// Time spent listening on each antenna before hopping to the next
+    pub antenna_dwell: String,
+}
+
+impl Default for ReaderSettings {
+    fn default() -> Self {
+        Self {
+            kind: "simulator".to_string(),
+            simulated_blank_tags: 50,
+            tag_wait: "10s".to_string(),
+            verify_retries: 3,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            antennas: Vec::new(),
+            antenna_dwell: "50ms".to_string(),
+        }
+    }
+}
+
+/// A physical or emulated reader available to the deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReaderDefinition {
+    pub name: String,
+    /// `impinj`, `zebra` or `simulator`
+    pub kind: String,
+    pub region: Option<RegulatoryRegion>,
+    #[serde(default = "default_antenna_gain")]
+    pub antenna_gain: f32,
+}
+
+/// Named operating settings applied to one of the defined readers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReaderProfile {
+    pub name: String,
+    /// Name of the reader in `readers`
+    pub reader: String,
+    /// Conducted power in dBm
+    pub power_dbm: Option<u8>,
+}
+
+fn default_antenna_gain() -> f32 {
+    6.0
+}
+
+/// Schedule entry for a background job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobConfig {
+    /// Built-in job name, e.g. `history_prune`
+    pub name: String,
+    /// Run interval, e.g. `30s`, `15m`, `1h`, `1d`
+    pub every: String,
+    /// Maximum run time before the run is reported as timed out
+    #[serde(default = "default_job_timeout")]
+    pub timeout: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// `history_prune`: history older than this many days is removed
+    pub retention_days: Option<u32>,
+    /// `temperature_downsample`: averaging window
+    pub bucket: Option<String>,
+    /// `temperature_downsample`: only readings older than this are downsampled
+    pub older_than: Option<String>,
+    /// `expiry_warning`: warn about samples expiring within this many days
+    pub warning_days: Option<u32>,
+    /// `expiry_warning`: escalating lead times in days, e.g. `[90, 30, 7, 1]`;
+    /// the shortest alerts as critical, then error, warning and info
+    pub lead_days: Option<Vec<u32>>,
+}
+
+/// HTTP endpoint notifications are POSTed to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// `http[s]://host[:port]/path`
+    pub url: String,
+    /// Maximum time for one delivery
+    #[serde(default = "default_webhook_timeout")]
+    pub timeout: String,
+    /// How long idle connections are kept open for reuse
+    #[serde(default = "default_webhook_keepalive")]
+    pub keepalive: String,
+    /// Maximum simultaneous connections to the endpoint
+    #[serde(default = "default_webhook_connections")]
+    pub max_connections: usize,
+}
+
+fn default_webhook_timeout() -> String {
+    "5s".to_string()
+}
+
+fn default_webhook_keepalive() -> String {
+    "60s".to_string()
+}
+
+fn default_webhook_connections() -> usize {
+    8
+}
+
+fn default_job_timeout() -> String {
+    "5m".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl SampleGuardConfig {
+    /// Load configuration from a TOML file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| SampleGuardError::ConfigError(format!("Failed to read {}: {}", path.display(), e)))?;
+        Self::from_toml_str(&contents)
+    }
+
+    /// Parse configuration from TOML text
+    pub fn from_toml_str(contents: &str) -> Result<Self> {
+        toml::from_str(contents)
+            .map_err(|e| SampleGuardError::ConfigError(format!("Invalid configuration: {}", e)))
+    }
+
+    /// Apply overrides from the process environment
+    pub fn apply_env_overrides(&mut self) -> Result<()> {
+        self.apply_overrides_from(|name| std::env::var(name).ok())
+    }
+
+    /// Apply overrides, looking each variable in [`ENV_OVERRIDES`] up with `lookup`
+    pub fn apply_overrides_from<F>(&mut self, lookup: F) -> Result<()>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        for (var, _) in ENV_OVERRIDES {
+            let Some(value) = lookup(var) else {
+                continue;
+            };
+            let invalid = || SampleGuardError::ConfigError(format!("Invalid value '{}' for {}", value, var));
+
+            match *var {
+                "SAMPLEGUARD_SERVER_HOST" => self.server.host = value.clone(),
+                "SAMPLEGUARD_SERVER_PORT" => self.server.port = value.parse().map_err(|_| invalid())?,
+                "SAMPLEGUARD_DATABASE_PATH" => self.database.path = Some(PathBuf::from(&value)),
+                "SAMPLEGUARD_AUDIT_FILE" => self.audit.file = Some(PathBuf::from(&value)),
+                "SAMPLEGUARD_TEMPERATURE_MIN_CELSIUS" => {
+                    self.temperature.min_celsius = value.parse().map_err(|_| invalid())?
+                }
+                "SAMPLEGUARD_TEMPERATURE_MAX_CELSIUS" => {
+                    self.temperature.max_celsius = value.parse().map_err(|_| invalid())?
+                }
+                "SAMPLEGUARD_AUTH_ENABLED" => self.auth.enabled = value.parse().map_err(|_| invalid())?,
+                "SAMPLEGUARD_READER_KIND" => self.reader.kind = value.clone(),
+                _ => unreachable!("unhandled override {}", var),
+            }
+        }
+        Ok(())
+    }
+
+    /// Cross-check the configuration, returning every problem found
+    pub fn issues(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |path: String, message: String| issues.push(ConfigIssue { path, message });
+
+        if self.temperature.min_celsius >= self.temperature.max_celsius {
+            issue(
+                "temperature.min_celsius".to_string(),
+                format!(
+                    "must be less than temperature.max_celsius ({} >= {})",
+                    self.temperature.min_celsius, self.temperature.max_celsius
+                ),
+            );
+        }
+        if let Some(Err(e)) = self.temperature.alert_cooldown.as_deref().map(parse_duration) {
+            issue("temperature.alert_cooldown".to_string(), e.to_string());
+        }
+
+        match self.database.backend {
+            DatabaseBackendKind::Sqlite => {
+                if self.database.url.is_some() {
+                    issue("database.url".to_string(), "only used by the postgres backend".to_string());
+                }
+            }
+            DatabaseBackendKind::Postgres => {
+                if !cfg!(feature = "postgres") {
+                    issue(
+                        "database.backend".to_string(),
+                        "this build lacks the postgres feature".to_string(),
+                    );
+                }
+                if self.database.url.is_none() {
+                    issue("database.url".to_string(), "required by the postgres backend".to_string());
+                }
+            }
+        }
+
+        if self.auth.enabled && !self.auth.api_keys.iter().any(|k| k.role == ApiRole::Admin) {
+            issue(
+                "auth.api_keys".to_string(),
+                "at least one admin key is required when auth is enabled".to_string(),
+            );
+        }
+        for (i, key) in self.auth.api_keys.iter().enumerate() {
+            if key.key.len() < 16 {
+                issue(format!("auth.api_keys[{}].key", i), "must be at least 16 characters".to_string());
+            }
+        }
+
+        if self.server.max_connections == Some(0) {
+            issue("server.max_connections".to_string(), "must be at least 1".to_string());
+        }
+        if self.server.max_page_size == 0 {
+            issue("server.max_page_size".to_string(), "must be at least 1".to_string());
+        }
+        if let Some(Err(e)) = self.server.statistics_cache_ttl.as_deref().map(parse_duration) {
+            issue("server.statistics_cache_ttl".to_string(), e.to_string());
+        }
+        if self.server.max_connections_per_worker == Some(0) {
+            issue("server.max_connections_per_worker".to_string(), "must be at least 1".to_string());
+        }
+        if self.server.seed_demo_data && self.server.demo_sample_count == 0 {
+            issue(
+                "server.demo_sample_count".to_string(),
+                "must be at least 1 when server.seed_demo_data is on".to_string(),
+            );
+        }
+
+        if self.rate_limit.requests_per_second == 0 {
+            issue("rate_limit.requests_per_second".to_string(), "must be greater than zero".to_string());
+        }
+        if self.rate_limit.burst < self.rate_limit.requests_per_second {
+            issue(
+                "rate_limit.burst".to_string(),
+                "must be at least rate_limit.requests_per_second".to_string(),
+            );
+        }
+
+        if !matches!(self.reader.kind.as_str(), "simulator" | "none") {
+            issue("reader.kind".to_string(), format!("unsupported reader kind '{}'", self.reader.kind));
+        }
+        if let Err(e) = parse_duration(&self.reader.tag_wait) {
+            issue("reader.tag_wait".to_string(), e.to_string());
+        }
+        if self.reader.max_batch_size == 0 {
+            issue("reader.max_batch_size".to_string(), "must be at least 1".to_string());
+        }
+        for (i, port) in self.reader.antennas.iter().enumerate() {
+            if *port == 0 {
+                issue(format!("reader.antennas[{}]", i), "antenna ports are numbered from 1".to_string());
+            } else if self.reader.antennas[..i].contains(port) {
+                issue(format!("reader.antennas[{}]", i), format!("duplicate antenna {}", port));
+            }
+        }
+        if let Err(e) = parse_duration(&self.reader.antenna_dwell) {
+            issue("reader.antenna_dwell".to_string(), e.to_string());
+        }
+
+        for (i, reader) in self.readers.iter().enumerate() {
+            if !matches!(reader.kind.as_str(), "impinj" | "zebra" | "simulator") {
+                issue(format!("readers[{}].kind", i), format!("unknown reader kind '{}'", reader.kind));
+            }
+            if self.readers[..i].iter().any(|r| r.name == reader.name) {
+                issue(format!("readers[{}].name", i), format!("duplicate reader '{}'", reader.name));
+            }
+        }
+        for (i, profile) in self.reader_profiles.iter().enumerate() {
+            let Some(reader) = self.readers.iter().find(|r| r.name == profile.reader) else {
+                issue(
+                    format!("reader_profiles[{}].reader", i),
+                    format!("no reader named '{}' is defined", profile.reader),
+                );
+                continue;
+            };
+            if let (Some(region), Some(power)) = (reader.region, profile.power_dbm) {
+                if let Err(e) = region.profile().check_power(power, reader.antenna_gain) {
+                    issue(format!("reader_profiles[{}].power_dbm", i), e.to_string());
+                }
+            }
+        }
+
+        for (i, job) in self.jobs.iter().enumerate() {
+            if let Err(e) = parse_duration(&job.every) {
+                issue(format!("jobs[{}].every", i), e.to_string());
+            }
+            if let Err(e) = parse_duration(&job.timeout) {
+                issue(format!("jobs[{}].timeout", i), e.to_string());
+            }
+            if let Err(e) = crate::jobs::builtin_job(job) {
+                issue(format!("jobs[{}].name", i), e.to_string());
+            }
+        }
+
+        for (i, webhook) in self.webhooks.iter().enumerate() {
+            if let Err(e) = crate::notifications::WebhookSink::new(&webhook.url) {
+                issue(format!("webhooks[{}].url", i), e.to_string());
+            }
+            if let Err(e) = parse_duration(&webhook.timeout) {
+                issue(format!("webhooks[{}].timeout", i), e.to_string());
+            }
+            if let Err(e) = parse_duration(&webhook.keepalive) {
+                issue(format!("webhooks[{}].keepalive", i), e.to_string());
+            }
+            if webhook.max_connections == 0 {
+                issue(format!("webhooks[{}].max_connections", i), "must be greater than zero".to_string());
+            }
+        }
+
+        for (i, term) in self.samples.storage_conditions.iter().enumerate() {
+            if term.trim().is_empty() {
+                issue(format!("samples.storage_conditions[{}]", i), "must not be empty".to_string());
+            } else if self.samples.storage_conditions[..i].iter().any(|t| t.eq_ignore_ascii_case(term)) {
+                issue(format!("samples.storage_conditions[{}]", i), format!("duplicate term '{}'", term));
+            }
+        }
+
+        if self.samples.max_image_bytes == Some(0) {
+            issue("samples.max_image_bytes".to_string(), "must be greater than zero".to_string());
+        }
+        if self.samples.max_image_dimension == Some(0) {
+            issue("samples.max_image_dimension".to_string(), "must be greater than zero".to_string());
+        }
+
+        if let Some(path) = &self.database.path {
+            if let Err(message) = check_writable(path) {
+                issue("database.path".to_string(), message);
+            }
+        }
+        if self.database.max_history_depth.is_some_and(|depth| depth < 2) {
+            issue(
+                "database.max_history_depth".to_string(),
+                "must be at least 2 to keep a rollup and the latest entry".to_string(),
+            );
+        }
+        if let Some(path) = &self.audit.file {
+            if let Err(message) = check_writable(path) {
+                issue("audit.file".to_string(), message);
+            }
+        }
+        if self.audit.max_events == Some(0) {
+            issue("audit.max_events".to_string(), "must be greater than zero".to_string());
+        }
+        if let Some(dir) = &self.audit.archive_dir {
+            if !dir.is_dir() {
+                issue("audit.archive_dir".to_string(), format!("{} is not a directory", dir.display()));
+            }
+        }
+        if let Some(rotation) = &self.audit.rotation {
+            if rotation.max_bytes == 0 {
+                issue("audit.rotation.max_bytes".to_string(), "must be greater than zero".to_string());
+            }
+            if self.audit.file.is_none() {
+                issue("audit.rotation".to_string(), "needs audit.file".to_string());
+            }
+        }
+
+        issues
+    }
+
+    /// Fail with every problem found by [`issues`](Self::issues)
+    pub fn validate(&self) -> Result<()> {
+        let issues = self.issues();
+        if issues.is_empty() {
+            return Ok(());
+        }
+        let list: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        Err(SampleGuardError::ConfigError(list.join("; ")))
+    }
+
+    /// Optional hardware available to this deployment
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            has_reader: self.reader.kind != "none",
+            has_temperature_sensor: self.temperature.enabled,
+            has_hardware_driver: !self.readers.is_empty(),
+        }
+    }
+
+    /// Copy of the configuration with API keys masked, for display
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if config.database.url.is_some() {
+            config.database.url = Some("********".to_string());
+        }
+        for key in &mut config.auth.api_keys {
+            key.key = "********".to_string();
+        }
+        config
+    }
+
+    /// Normalized TOML rendering of the configuration
+    pub fn to_toml_string(&self) -> Result<String> {
+        toml::to_string_pretty(self)
+            .map_err(|e| SampleGuardError::ConfigError(format!("Failed to serialize configuration: {}", e)))
+    }
+}
+
+/// Optional hardware a deployment may run without
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub has_reader: bool,
+    pub has_temperature_sensor: bool,
+    pub has_hardware_driver: bool,
+}
+
+impl Capabilities {
+    pub const RFID_READER: &'static str = "rfid_reader";
+    pub const TEMPERATURE_SENSOR: &'static str = "temperature_sensor";
+    pub const HARDWARE_DRIVER: &'static str = "hardware_driver";
+
+    /// Every capability present
+    pub fn all() -> Self {
+        Self { has_reader: true, has_temperature_sensor: true, has_hardware_driver: true }
+    }
+
+    /// Names of the capabilities that are present
+    pub fn active(&self) -> Vec<&'static str> {
+        [
+            (self.has_reader, Self::RFID_READER),
+            (self.has_temperature_sensor, Self::TEMPERATURE_SENSOR),
+            (self.has_hardware_driver, Self::HARDWARE_DRIVER),
+        ]
+        .into_iter()
+        .filter_map(|(present, name)| present.then_some(name))
+        .collect()
+    }
+}
+
+/// A configuration problem and the TOML path it was found at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// The file's directory must exist and be writable
+fn check_writable(path: &Path) -> std::result::Result<(), String> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    match std::fs::metadata(dir) {
+        Ok(meta) if !meta.is_dir() => Err(format!("{} is not a directory", dir.display())),
+        Ok(meta) if meta.permissions().readonly() => Err(format!("{} is not writable", dir.display())),
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{} is not accessible: {}", dir.display(), e)),
+    }
+}
+
+/// Parse a duration such as `90s`, `15m`, `2h` or `1d`
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| SampleGuardError::ConfigError(format!("Invalid duration '{}'", value)))?;
+
+    let multiplier = match unit.trim() {
+        "ms" => return Ok(Duration::from_millis(amount)),
+        "s" | "" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        other => {
+            return Err(SampleGuardError::ConfigError(format!(
+                "Unknown duration unit '{}' in '{}'",
+                other, value
+            )))
+        }
+    };
+
+    Ok(Duration::from_secs(amount * multiplier))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("5w").is_err());
+    }
+
+    #[test]
+    fn test_parse_job_schedule() {
+        let config = SampleGuardConfig::from_toml_str(r#"
+            [server]
+            port = 9090
+
+            [[jobs]]
+            name = "history_prune"
+            every = "1d"
+            retention_days = 90
+
+            [[jobs]]
+            name = "expiry_warning"
+            every = "1h"
+            timeout = "30s"
+        "#).unwrap();
+
+        assert_eq!(config.server.port, 9090);
+        assert_eq!(config.server.host, "127.0.0.1");
+        assert_eq!(config.jobs.len(), 2);
+        assert_eq!(config.jobs[0].retention_days, Some(90));
+        assert_eq!(config.jobs[0].timeout, "5m");
+        assert!(config.jobs[1].enabled);
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: Vec<(String, String)> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+    }
+
+    fn issue_paths(config: &SampleGuardConfig) -> Vec<String> {
+        config.issues().into_iter().map(|i| i.path).collect()
+    }
+
+    #[test]
+    fn test_env_overrides_take_precedence_over_file() {
+        let mut config = SampleGuardConfig::from_toml_str(r#"
+            [server]
+            host = "0.0.0.0"
+            port = 9090
+
+            [temperature]
+            min_celsius = -25.0
+            max_celsius = -15.0
+        "#).unwrap();
+
+        config.apply_overrides_from(env(&[
+            ("SAMPLEGUARD_SERVER_PORT", "7070"),
+            ("SAMPLEGUARD_TEMPERATURE_MAX_CELSIUS", "-18"),
+            ("SAMPLEGUARD_DATABASE_PATH", "/var/lib/sample-guard/samples.db"),
+        ])).unwrap();
+
+        // Overridden by the environment
+        assert_eq!(config.server.port, 7070);
+        assert_eq!(config.temperature.max_celsius, -18.0);
+        assert_eq!(config.database.path, Some(PathBuf::from("/var/lib/sample-guard/samples.db")));
+        // Taken from the file
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.temperature.min_celsius, -25.0);
+        // Defaults
+        assert_eq!(config.temperature.sensor_id, "API-SENSOR");
+        assert!(!config.auth.enabled);
+    }
+
+    #[test]
+    fn test_invalid_env_override_is_rejected() {
+        let mut config = SampleGuardConfig::default();
+        let result = config.apply_overrides_from(env(&[("SAMPLEGUARD_SERVER_PORT", "eighty")]));
+        assert!(matches!(result, Err(SampleGuardError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_capabilities_follow_configuration() {
+        let mut config = SampleGuardConfig::default();
+        assert_eq!(config.capabilities().active(), vec![Capabilities::RFID_READER, Capabilities::TEMPERATURE_SENSOR]);
+
+        config.reader.kind = "none".to_string();
+        config.temperature.enabled = false;
+        assert!(config.validate().is_ok());
+        assert!(config.capabilities().active().is_empty());
+    }
+
+    #[test]
+    fn test_webhook_settings_are_checked() {
+        let config = SampleGuardConfig::from_toml_str(r#"
+            [[webhooks]]
+            url = "http://hooks.example.com/events"
+
+            [[webhooks]]
+            url = "ftp://hooks.example.com"
+            timeout = "soon"
+            max_connections = 0
+        "#).unwrap();
+
+        assert_eq!(config.webhooks[0].keepalive, "60s");
+        assert_eq!(
+            issue_paths(&config),
+            vec!["webhooks[1].url", "webhooks[1].timeout", "webhooks[1].max_connections"]
+        );
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(SampleGuardConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(SampleGuardConfig::from_toml_str("[server]\nprot = 80\n").is_err());
+    }
+
+    #[test]
+    fn test_temperature_range_check() {
+        let mut config = SampleGuardConfig::default();
+        config.temperature.min_celsius = 8.0;
+        config.temperature.max_celsius = 2.0;
+        assert_eq!(issue_paths(&config), vec!["temperature.min_celsius"]);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_auth_requires_admin_key() {
+        let mut config = SampleGuardConfig::from_toml_str(r#"
+            [auth]
+            enabled = true
+
+            [[auth.api_keys]]
+            name = "dashboard"
+            key = "0123456789abcdef0123"
+            role = "read_only"
+        "#).unwrap();
+        assert_eq!(issue_paths(&config), vec!["auth.api_keys"]);
+
+        config.auth.api_keys[0].role = ApiRole::Admin;
+        assert!(config.issues().is_empty());
+    }
+
+    #[test]
+    fn test_reader_profile_must_reference_defined_reader() {
+        let config = SampleGuardConfig::from_toml_str(r#"
+            [[readers]]
+            name = "dock-door"
+            kind = "impinj"
+            region = "etsi"
+
+            [[reader_profiles]]
+            name = "receiving"
+            reader = "dock-door"
+            power_dbm = 30
+
+            [[reader_profiles]]
+            name = "freezer"
+            reader = "freezer-reader"
+        "#).unwrap();
+
+        assert_eq!(
+            issue_paths(&config),
+            vec!["reader_profiles[0].power_dbm", "reader_profiles[1].reader"]
+        );
+    }
+
+    #[test]
+    fn test_paths_must_be_writable() {
+        let mut config = SampleGuardConfig::default();
+        config.database.path = Some(PathBuf::from("/nonexistent-sample-guard-dir/samples.db"));
+        config.audit.file = Some(std::env::temp_dir().join("audit.jsonl"));
+        assert_eq!(issue_paths(&config), vec!["database.path"]);
+    }
+
+    #[test]
+    fn test_image_limits_must_be_positive() {
+        let config = SampleGuardConfig::from_toml_str(r#"
+            [samples]
+            max_image_bytes = 0
+            max_image_dimension = 0
+        "#).unwrap();
+        assert_eq!(issue_paths(&config), vec!["samples.max_image_bytes", "samples.max_image_dimension"]);
+    }
+
+    #[test]
+    fn test_audit_rotation_is_checked() {
+        let config = SampleGuardConfig::from_toml_str(r#"
+            [audit.rotation]
+            max_bytes = 0
+            max_files = 5
+        "#).unwrap();
+        assert_eq!(config.audit.rotation.map(|r| (r.max_files, r.compress)), Some((5, false)));
+        assert_eq!(issue_paths(&config), vec!["audit.rotation.max_bytes", "audit.rotation"]);
+    }
+
+    #[test]
+    fn test_audit_retention_is_checked() {
+        let config = SampleGuardConfig::from_toml_str(r#"
+            [audit]
+            max_events = 0
+            archive_dir = "/nonexistent-sample-guard-dir/archive"
+        "#).unwrap();
+        assert_eq!(issue_paths(&config), vec!["audit.max_events", "audit.archive_dir"]);
+    }
+
+    #[test]
+    fn test_history_depth_must_keep_a_recent_entry() {
+        let mut config = SampleGuardConfig::default();
+        config.database.max_history_depth = Some(1);
+        assert_eq!(issue_paths(&config), vec!["database.max_history_depth"]);
+        config.database.max_history_depth = Some(2);
+        assert!(issue_paths(&config).is_empty());
+    }
+
+    #[test]
+    fn test_job_entries_are_checked() {
+        let config = SampleGuardConfig::from_toml_str(r#"
+            [[jobs]]
+            name = "history_prune"
+            every = "1 fortnight"
+
+            [[jobs]]
+            name = "defragment"
+            every = "1h"
+        "#).unwrap();
+        assert_eq!(issue_paths(&config), vec!["jobs[0].every", "jobs[1].name"]);
+    }
+
+    #[test]
+    fn test_rate_limit_check() {
+        let mut config = SampleGuardConfig::default();
+        config.rate_limit.requests_per_second = 200;
+        assert_eq!(issue_paths(&config), vec!["rate_limit.burst"]);
+    }
+
+    #[test]
+    fn test_database_backend_check() {
+        let mut config = SampleGuardConfig::from_toml_str(r#"
+            [database]
+            backend = "postgres"
+        "#).unwrap();
+        let mut expected = vec!["database.url"];
+        if !cfg!(feature = "postgres") {
+            expected.insert(0, "database.backend");
+        }
+        assert_eq!(issue_paths(&config), expected);
+        
+        config.database.backend = DatabaseBackendKind::Sqlite;
+        config.database.url = Some("host=localhost".to_string());
+        assert_eq!(issue_paths(&config), vec!["database.url"]);
+    }
+
+    #[test]
+    fn test_antenna_settings_are_checked() {
+        let config = SampleGuardConfig::from_toml_str(r#"
+            [reader]
+            antennas = [1, 0, 1]
+            antenna_dwell = "soon"
+        "#).unwrap();
+        assert_eq!(
+            issue_paths(&config),
+            vec!["reader.antennas[1]", "reader.antennas[2]", "reader.antenna_dwell"]
+        );
+    }
+
+    #[test]
+    fn test_storage_condition_terms_are_checked() {
+        let config = SampleGuardConfig::from_toml_str(r#"
+            [samples]
+            storage_conditions = ["Refrigerated", "Frozen", "refrigerated", " "]
+        "#).unwrap();
+        assert_eq!(
+            issue_paths(&config),
+            vec!["samples.storage_conditions[2]", "samples.storage_conditions[3]"]
+        );
+    }
+
+    #[test]
+    fn test_redacted_dump_masks_keys() {
+        let mut config = SampleGuardConfig::default();
+        config.auth.api_keys.push(ApiKeyConfig {
+            name: "ops".to_string(),
+            key: "super-secret-key-value".to_string(),
+            role: ApiRole::Admin,
+        });
+        let dump = config.redacted().to_toml_string().unwrap();
+        assert!(!dump.contains("super-secret-key-value"));
+        assert!(SampleGuardConfig::from_toml_str(&dump).is_ok());
+    }
+}
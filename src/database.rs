@@ -1,114 +1,434 @@
-use crate::error::{SampleGuardError, Result};
-use crate::sample::{Sample, SampleMetadata, SampleStatus};
+use crate::audit::{AuditEvent, AuditFilter};
+use crate::config::{DatabaseBackendKind, DatabaseConfig};
+use crate::error::{ColumnError, DatabaseErrorKind, SampleGuardError, Result};
+use crate::images::{ImageReference, SampleImage};
+use crate::integrity::ValidationResult;
+use crate::inventory::InventorySnapshot;
+use crate::sample::{ChecksumVersion, Sample, SampleMetadata, SampleStatus};
+use crate::transitions::{TransitionHook, TransitionHooks};
+use crate::temperature::{TemperatureReading, TemperatureViolation, ViolationSeverity, ViolationType};
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection, Row};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
+
+/// Schema migrations; the schema version is the number of migrations applied
+const MIGRATIONS: &[&str] = &[
+    // 1: samples and status history
+    "CREATE TABLE IF NOT EXISTS samples (
+        id TEXT PRIMARY KEY,
+        sample_id TEXT NOT NULL UNIQUE,
+        status TEXT NOT NULL,
+        batch_number TEXT NOT NULL,
+        production_date TEXT NOT NULL,
+        expiry_date TEXT,
+        temperature_min REAL,
+        temperature_max REAL,
+        storage_conditions TEXT NOT NULL,
+        manufacturer TEXT NOT NULL,
+        product_line TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        last_updated TEXT NOT NULL,
+        read_count INTEGER NOT NULL,
+        location TEXT,
+        integrity_checksum TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS sample_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        sample_id TEXT NOT NULL,
+        status TEXT NOT NULL,
+        location TEXT,
+        timestamp TEXT NOT NULL,
+        FOREIGN KEY (sample_id) REFERENCES samples(sample_id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_sample_id ON samples(sample_id);
+    CREATE INDEX IF NOT EXISTS idx_batch_number ON samples(batch_number);",
+    // 2: temperature excursions linked to samples
+    "CREATE TABLE IF NOT EXISTS temperature_excursions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        sample_id TEXT NOT NULL,
+        sensor_id TEXT NOT NULL,
+        location TEXT NOT NULL,
+        temperature REAL NOT NULL,
+        expected_min REAL NOT NULL,
+        expected_max REAL NOT NULL,
+        violation_type TEXT NOT NULL,
+        severity TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        FOREIGN KEY (sample_id) REFERENCES samples(sample_id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_excursion_sample ON temperature_excursions(sample_id);",
+    // 3: aliquot and pooling lineage
+    "CREATE TABLE IF NOT EXISTS sample_lineage (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        parent_id TEXT NOT NULL,
+        child_id TEXT NOT NULL,
+        relation TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        FOREIGN KEY (parent_id) REFERENCES samples(sample_id),
+        FOREIGN KEY (child_id) REFERENCES samples(sample_id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_lineage_parent ON sample_lineage(parent_id);
+    CREATE INDEX IF NOT EXISTS idx_lineage_child ON sample_lineage(child_id);",
+    // 4: history lookups and the foreign-key check on sample replace
+    "CREATE INDEX IF NOT EXISTS idx_history_sample ON sample_history(sample_id);",
+    // 5: cold storage for long-retained samples
+    "CREATE TABLE IF NOT EXISTS archived_samples (
+        sample_id TEXT PRIMARY KEY,
+        batch_number TEXT NOT NULL,
+        status TEXT NOT NULL,
+        manufacturer TEXT NOT NULL,
+        product_line TEXT NOT NULL,
+        archived_at TEXT NOT NULL,
+        data BLOB NOT NULL
+    );",
+    // 6: API reads, kept apart from tag reads and from sample row replacement
+    "CREATE TABLE IF NOT EXISTS sample_access (
+        sample_id TEXT PRIMARY KEY,
+        api_access_count INTEGER NOT NULL
+    );",
+    // 7: expiry lead-time alerts already raised
+    "CREATE TABLE IF NOT EXISTS expiry_alerts (
+        sample_id TEXT NOT NULL,
+        lead_days INTEGER NOT NULL,
+        alerted_at TEXT NOT NULL,
+        PRIMARY KEY (sample_id, lead_days)
+    );",
+    // 8: history rollups; verbatim entries leave both columns NULL
+    "ALTER TABLE sample_history ADD COLUMN rollup_count INTEGER;
+    ALTER TABLE sample_history ADD COLUMN rollup_start TEXT;",
+    // 9: readings taken where each sample was stored
+    "CREATE TABLE IF NOT EXISTS sample_temperature_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        sample_id TEXT NOT NULL,
+        sensor_id TEXT NOT NULL,
+        location TEXT NOT NULL,
+        temperature REAL NOT NULL,
+        in_range INTEGER NOT NULL,
+        timestamp TEXT NOT NULL,
+        FOREIGN KEY (sample_id) REFERENCES samples(sample_id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_temperature_log_sample ON sample_temperature_log(sample_id, timestamp);",
+    // 10: primary image of each sample with its thumbnail
+    "CREATE TABLE IF NOT EXISTS sample_images (
+        sample_id TEXT PRIMARY KEY,
+        content_type TEXT NOT NULL,
+        width INTEGER NOT NULL,
+        height INTEGER NOT NULL,
+        size_bytes INTEGER NOT NULL,
+        data BLOB NOT NULL,
+        thumbnail BLOB NOT NULL,
+        uploaded_at TEXT NOT NULL,
+        FOREIGN KEY (sample_id) REFERENCES samples(sample_id)
+    );",
+    // 11: inventory snapshots kept as cycle-count baselines
+    "CREATE TABLE IF NOT EXISTS inventory_snapshots (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        taken_at TEXT NOT NULL,
+        tag_count INTEGER NOT NULL,
+        tags TEXT NOT NULL
+    );",
+    // 12: most recent integrity validation of each sample
+    "CREATE TABLE IF NOT EXISTS sample_validations (
+        sample_id TEXT PRIMARY KEY,
+        validated_at TEXT NOT NULL,
+        result TEXT NOT NULL,
+        FOREIGN KEY (sample_id) REFERENCES samples(sample_id)
+    );",
+    // 13: chain of custody, append-only
+    "CREATE TABLE IF NOT EXISTS custody_chain (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        sample_id TEXT NOT NULL,
+        transferred_from TEXT,
+        transferred_to TEXT NOT NULL,
+        transfer_timestamp TEXT NOT NULL,
+        witnessed_by TEXT,
+        notes TEXT,
+        FOREIGN KEY (sample_id) REFERENCES samples(sample_id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_custody_sample ON custody_chain(sample_id, id);
+    CREATE INDEX IF NOT EXISTS idx_custody_to ON custody_chain(transferred_to);
+    CREATE TRIGGER IF NOT EXISTS custody_chain_no_update BEFORE UPDATE ON custody_chain
+    BEGIN SELECT RAISE(ABORT, 'custody chain is append-only'); END;
+    CREATE TRIGGER IF NOT EXISTS custody_chain_no_delete BEFORE DELETE ON custody_chain
+    BEGIN SELECT RAISE(ABORT, 'custody chain is append-only'); END;",
+    // 14: free-form labels, many per sample
+    "CREATE TABLE IF NOT EXISTS sample_labels (
+        sample_id TEXT NOT NULL,
+        label TEXT NOT NULL,
+        PRIMARY KEY (sample_id, label),
+        FOREIGN KEY (sample_id) REFERENCES samples(sample_id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_sample_labels_label ON sample_labels(label);",
+    // 15: container and free-text notes on samples
+    "ALTER TABLE samples ADD COLUMN container_id TEXT;
+    ALTER TABLE samples ADD COLUMN notes TEXT;
+    CREATE INDEX IF NOT EXISTS idx_container_id ON samples(container_id);",
+    // 16: who made each history entry
+    "ALTER TABLE sample_history ADD COLUMN user_id TEXT;",
+    // 17: persistent audit log
+    "CREATE TABLE IF NOT EXISTS audit_events (
+        event_id TEXT PRIMARY KEY,
+        event_type TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        user_id TEXT,
+        sample_id TEXT,
+        details TEXT NOT NULL,
+        severity TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_audit_events_type ON audit_events(event_type);
+    CREATE INDEX IF NOT EXISTS idx_audit_events_sample ON audit_events(sample_id);
+    CREATE INDEX IF NOT EXISTS idx_audit_events_severity ON audit_events(severity);
+    CREATE INDEX IF NOT EXISTS idx_audit_events_timestamp ON audit_events(timestamp);",
+    // 18: audit hash chain
+    "ALTER TABLE audit_events ADD COLUMN prev_hash TEXT;
+    ALTER TABLE audit_events ADD COLUMN event_hash TEXT;",
+    // 19: temperature monitor history
+    "CREATE TABLE IF NOT EXISTS temperature_readings (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        sensor_id TEXT NOT NULL,
+        location TEXT,
+        temperature REAL NOT NULL,
+        timestamp TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_temperature_readings_sensor ON temperature_readings(sensor_id, timestamp);
+    CREATE TABLE IF NOT EXISTS temperature_violations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        sensor_id TEXT NOT NULL,
+        location TEXT,
+        temperature REAL NOT NULL,
+        expected_min REAL NOT NULL,
+        expected_max REAL NOT NULL,
+        violation_type TEXT NOT NULL,
+        severity TEXT NOT NULL,
+        timestamp TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_temperature_violations_sensor ON temperature_violations(sensor_id, timestamp);",
+];
+
+/// Latest schema version known to this build
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// How long a connection waits for another connection's write lock
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Sample storage operations shared by the SQLite [`Database`] and other
+/// backends such as `postgres::PostgresDatabase`. Timestamps are stored as
+/// RFC 3339 text by every backend, so samples round-trip unchanged.
+pub trait DatabaseBackend {
+    /// Store a sample, replacing any sample with the same ID, and record it
+    /// in the sample's history
+    fn store_sample(&self, sample: &Sample) -> Result<()>;
+    fn get_sample(&self, sample_id: &str) -> Result<Option<Sample>>;
+    /// All samples, newest first
+    fn get_all_samples(&self) -> Result<Vec<Sample>>;
+    fn get_samples_by_batch(&self, batch_number: &str) -> Result<Vec<Sample>>;
+    fn get_samples_by_status(&self, status: SampleStatus) -> Result<Vec<Sample>>;
+    /// Delete a sample and its history; `false` if it did not exist
+    fn delete_sample(&self, sample_id: &str) -> Result<bool>;
+    /// Record a status and location, made by `user_id` when known
+    fn add_history_entry(&self, sample_id: &str, status: &SampleStatus, location: Option<&str>, user_id: Option<&str>) -> Result<()>;
+    /// History of a sample, newest first
+    fn get_sample_history(&self, sample_id: &str) -> Result<Vec<HistoryEntry>>;
+    fn get_statistics(&self) -> Result<DatabaseStatistics>;
+}
+
+/// Open the backend `config` selects
+pub fn open_backend(config: &DatabaseConfig) -> Result<Box<dyn DatabaseBackend + Send>> {
+    match config.backend {
+        DatabaseBackendKind::Sqlite => Ok(Box::new(Database::from_config(config)?)),
+        #[cfg(feature = "postgres")]
+        DatabaseBackendKind::Postgres => Ok(Box::new(crate::postgres::PostgresDatabase::from_config(config)?)),
+        #[cfg(not(feature = "postgres"))]
+        DatabaseBackendKind::Postgres => Err(SampleGuardError::ConfigError(
+            "database.backend = postgres needs a build with the postgres feature".to_string(),
+        )),
+    }
+}
 
 /// Database manager for SampleGuard
 pub struct Database {
     conn: Connection,
+    hooks: TransitionHooks,
+    max_history_depth: Option<usize>,
+    allow_legacy_checksums: bool,
+    sample_generation: Cell<u64>,
 }
 
 impl Database {
     /// Create or open a database at the given path
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
         let conn = Connection::open(path)
-            .map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Database connection failed: {}", e)
-            )))?;
+            .and_then(|conn| conn.busy_timeout(BUSY_TIMEOUT).map(|_| conn))
+            .map_err(|e| SampleGuardError::database("Database connection failed", e))?;
         
-        let db = Self { conn };
-        db.init_schema()?;
+        let db = Self { conn, hooks: TransitionHooks::default(), max_history_depth: None, allow_legacy_checksums: false, sample_generation: Cell::new(0) };
+        db.migrate()?;
         Ok(db)
     }
 
     /// Create an in-memory database for testing
     pub fn in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()
-            .map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("In-memory database failed: {}", e)
-            )))?;
+            .map_err(|e| SampleGuardError::database("In-memory database failed", e))?;
         
-        let db = Self { conn };
-        db.init_schema()?;
+        let db = Self { conn, hooks: TransitionHooks::default(), max_history_depth: None, allow_legacy_checksums: false, sample_generation: Cell::new(0) };
+        db.migrate()?;
         Ok(db)
     }
 
-    /// Initialize database schema
-    fn init_schema(&self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS samples (
-                id TEXT PRIMARY KEY,
-                sample_id TEXT NOT NULL UNIQUE,
-                status TEXT NOT NULL,
-                batch_number TEXT NOT NULL,
-                production_date TEXT NOT NULL,
-                expiry_date TEXT,
-                temperature_min REAL,
-                temperature_max REAL,
-                storage_conditions TEXT NOT NULL,
-                manufacturer TEXT NOT NULL,
-                product_line TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                last_updated TEXT NOT NULL,
-                read_count INTEGER NOT NULL,
-                location TEXT,
-                integrity_checksum TEXT NOT NULL
-            )",
-            [],
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Schema initialization failed: {}", e)
-        )))?;
+    /// Open the configured database, in memory when no path is set. Fails
+    /// for any backend but SQLite; see [`open_backend`] for those.
+    pub fn from_config(config: &DatabaseConfig) -> Result<Self> {
+        if config.backend != DatabaseBackendKind::Sqlite {
+            return Err(SampleGuardError::ConfigError(
+                "database.backend = postgres only serves `db stats` and the DatabaseBackend API; \
+                 the server and other commands need the sqlite backend"
+                    .to_string(),
+            ));
+        }
+        let db = match &config.path {
+            Some(path) => Self::new(path)?,
+            None => Self::in_memory()?,
+        };
+        let db = db.with_allow_legacy_checksums(config.allow_legacy_checksums);
+        Ok(match config.max_history_depth {
+            Some(depth) => db.with_max_history_depth(depth),
+            None => db,
+        })
+    }
 
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS sample_history (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                sample_id TEXT NOT NULL,
-                status TEXT NOT NULL,
-                location TEXT,
-                timestamp TEXT NOT NULL,
-                FOREIGN KEY (sample_id) REFERENCES samples(sample_id)
-            )",
-            [],
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("History table creation failed: {}", e)
-        )))?;
+    /// Keep at most `depth` history rows per sample, folding the oldest into
+    /// a single rollup row. Values below 2 are raised to 2 so at least one
+    /// entry stays verbatim.
+    pub fn with_max_history_depth(mut self, depth: usize) -> Self {
+        self.max_history_depth = Some(depth.max(2));
+        self
+    }
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_sample_id ON samples(sample_id)",
-            [],
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Index creation failed: {}", e)
-        )))?;
+    pub fn max_history_depth(&self) -> Option<usize> {
+        self.max_history_depth
+    }
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_batch_number ON samples(batch_number)",
-            [],
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Index creation failed: {}", e)
-        )))?;
+    /// Accept samples whose checksum is still in the legacy layout. Off by
+    /// default: the legacy layout lets different fields hash alike, so it
+    /// should only be allowed until `upgrade_checksums` has been run.
+    pub fn with_allow_legacy_checksums(mut self, allow: bool) -> Self {
+        self.allow_legacy_checksums = allow;
+        self
+    }
 
-        Ok(())
+    /// Whether `sample` carries a checksum this database accepts
+    fn checksum_accepted(&self, sample: &Sample) -> bool {
+        match sample.checksum_version() {
+            Some(ChecksumVersion::LengthPrefixed) => true,
+            Some(ChecksumVersion::Legacy) => self.allow_legacy_checksums,
+            None => false,
+        }
+    }
+
+    /// Open a database without applying pending migrations
+    pub fn open_without_migrate<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let conn = Connection::open(path)
+            .and_then(|conn| conn.busy_timeout(BUSY_TIMEOUT).map(|_| conn))
+            .map_err(|e| SampleGuardError::database("Database connection failed", e))?;
+        
+        Ok(Self { conn, hooks: TransitionHooks::default(), max_history_depth: None, allow_legacy_checksums: false, sample_generation: Cell::new(0) })
+    }
+
+    /// Current schema version of the database
+    pub fn schema_version(&self) -> Result<u32> {
+        self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| SampleGuardError::database("Failed to read schema version", e))
+    }
+
+    /// Apply all pending schema migrations, returning the versions before and after.
+    /// Fails without touching the database if its schema is newer than this
+    /// build knows.
+    pub fn migrate(&self) -> Result<(u32, u32)> {
+        let from = self.schema_version()?;
+        if from > SCHEMA_VERSION {
+            return Err(SampleGuardError::database_message(
+                DatabaseErrorKind::Other,
+                format!(
+                    "Database schema version {} is newer than version {} supported by this build; upgrade SampleGuard to open it",
+                    from, SCHEMA_VERSION
+                ),
+            ));
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            let version = index as u32 + 1;
+            if version <= from {
+                continue;
+            }
+
+            let sql = format!("BEGIN; {} PRAGMA user_version = {}; COMMIT;", migration, version);
+            if let Err(e) = self.conn.execute_batch(&sql) {
+                let _ = self.conn.execute_batch("ROLLBACK;");
+                return Err(SampleGuardError::database(
+                    format!("Migration to schema version {} failed", version),
+                    e,
+                ));
+            }
+        }
+
+        Ok((from, self.schema_version()?))
+    }
+
+    /// Apply all pending schema migrations, returning the resulting version
+    pub fn migrate_to_latest(&self) -> Result<u32> {
+        self.migrate().map(|(_, to)| to)
     }
 
-    /// Store a sample in the database
+    /// Store a sample in the database along with a history entry; neither
+    /// is written unless both are
     pub fn store_sample(&self, sample: &Sample) -> Result<()> {
+        self.store_sample_as(sample, None)
+    }
+
+    /// `store_sample`, crediting the history entry to `user_id`. Fails with
+    /// `InvalidStatusTransition` if the stored sample cannot move to the
+    /// new sample's status.
+    fn store_sample_as(&self, sample: &Sample, user_id: Option<&str>) -> Result<()> {
+        self.in_savepoint("store_sample", || {
+            if let Some(stored) = self.stored_status(&sample.sample_id)? {
+                if !stored.can_transition_to(sample.status) {
+                    return Err(SampleGuardError::InvalidStatusTransition { from: stored, to: sample.status });
+                }
+            }
+            self.insert_sample_row(sample)?;
+
+            // Store history entry
+            self.add_history_entry(&sample.sample_id, &sample.status, sample.location.as_deref(), user_id)
+        })
+    }
+
+    fn stored_status(&self, sample_id: &str) -> Result<Option<SampleStatus>> {
+        self.conn.prepare_cached("SELECT status FROM samples WHERE sample_id = ?1")
+            .and_then(|mut stmt| stmt.query_row(params![sample_id], |row| row.get::<_, String>(0)).optional())
+            .map(|status| status.as_deref().map(parse_status))
+            .map_err(|e| SampleGuardError::database("Failed to read stored status", e))
+    }
+
+    fn insert_sample_row(&self, sample: &Sample) -> Result<()> {
         let checksum_hex = hex::encode(sample.integrity_checksum);
+        self.bump_sample_generation();
         
-        self.conn.execute(
+        self.conn.prepare_cached(
             "INSERT OR REPLACE INTO samples (
                 id, sample_id, status, batch_number, production_date, expiry_date,
                 temperature_min, temperature_max, storage_conditions, manufacturer,
-                product_line, created_at, last_updated, read_count, location, integrity_checksum
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
-            params![
+                product_line, created_at, last_updated, read_count, location, integrity_checksum,
+                container_id, notes
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        ).and_then(|mut stmt| stmt.execute(params![
                 sample.id.to_string(),
                 sample.sample_id,
                 format!("{:?}", sample.status),
@@ -125,15 +445,85 @@ impl Database {
                 sample.read_count,
                 sample.location,
                 checksum_hex,
-            ],
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to store sample: {}", e)
-        )))?;
+                sample.container_id,
+                sample.notes,
+            ]))
+        .map_err(|e| SampleGuardError::database("Failed to store sample", e))?;
+
+        Ok(())
+    }
+
+    /// Store many samples in one transaction, each with its history entry.
+    /// A sample that cannot be stored, including one whose checksum does not
+    /// match, is reported and the rest are kept; with `all_or_nothing` the
+    /// first failure rolls back the whole batch instead.
+    pub fn store_samples_batch(&self, samples: &[Sample], all_or_nothing: bool) -> Result<BatchResult> {
+        self.in_transaction("batch", || {
+            // The transaction commits whatever the closure returns Ok, so a
+            // tripped all_or_nothing batch undoes its writes through a savepoint
+            self.conn.execute_batch("SAVEPOINT batch_items;")
+                .map_err(|e| SampleGuardError::database("Failed to begin batch", e))?;
+
+            let mut result = BatchResult::default();
+            for (index, sample) in samples.iter().enumerate() {
+                let error = if self.checksum_accepted(sample) {
+                    self.store_sample(sample).err().map(|e| e.to_string())
+                } else {
+                    Some("integrity checksum mismatch".to_string())
+                };
+                let failed = error.is_some();
+                result.items.push(BatchItem { index, sample_id: sample.sample_id.clone(), error });
+                if failed && all_or_nothing {
+                    result.rolled_back = true;
+                    break;
+                }
+            }
+
+            let end = if result.rolled_back {
+                "ROLLBACK TO batch_items; RELEASE batch_items;"
+            } else {
+                "RELEASE batch_items;"
+            };
+            self.conn.execute_batch(end)
+                .map_err(|e| SampleGuardError::database("Failed to finish batch", e))?;
+            Ok(result)
+        })
+    }
+
+    /// Counter that moves on whenever a sample is stored or deleted through
+    /// this connection, so cached aggregates can tell they are stale
+    pub fn sample_generation(&self) -> u64 {
+        self.sample_generation.get()
+    }
+
+    fn bump_sample_generation(&self) {
+        self.sample_generation.set(self.sample_generation.get().wrapping_add(1));
+    }
 
-        // Store history entry
-        self.add_history_entry(&sample.sample_id, &sample.status, sample.location.as_deref())?;
+    /// Run `hook` after every status change made through this database
+    pub fn register_transition_hook(&mut self, hook: Arc<dyn TransitionHook>) {
+        self.hooks.register(hook);
+    }
 
+    /// Move a stored sample to `status`, optionally relocating it, and run
+    /// the transition hooks if the status changed. `user_id` is recorded in
+    /// the sample's history as the one making the change.
+    pub fn change_status(
+        &self,
+        sample: &mut Sample,
+        status: SampleStatus,
+        location: Option<String>,
+        user_id: Option<&str>,
+    ) -> Result<()> {
+        let from = sample.status;
+        sample.try_update_status(status)?;
+        if let Some(location) = location {
+            sample.update_location(location);
+        }
+        self.store_sample_as(sample, user_id)?;
+        if from != status {
+            self.hooks.notify(sample, from, status);
+        }
         Ok(())
     }
 
@@ -142,26 +532,18 @@ impl Database {
         let mut stmt = self.conn.prepare(
             "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
              temperature_min, temperature_max, storage_conditions, manufacturer,
-             product_line, created_at, last_updated, read_count, location, integrity_checksum
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
              FROM samples WHERE sample_id = ?1"
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to prepare query: {}", e)
-        )))?;
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
 
         let mut rows = stmt.query_map(params![sample_id], |row| {
             Self::row_to_sample(row)
-        }).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to execute query: {}", e)
-        )))?;
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?;
 
         match rows.next() {
             Some(Ok(sample)) => Ok(Some(sample)),
-            Some(Err(e)) => Err(SampleGuardError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to parse row: {}", e)
-            ))),
+            Some(Err(e)) => Err(SampleGuardError::database("Failed to parse row", e)),
             None => Ok(None),
         }
     }
@@ -171,51 +553,156 @@ impl Database {
         let mut stmt = self.conn.prepare(
             "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
              temperature_min, temperature_max, storage_conditions, manufacturer,
-             product_line, created_at, last_updated, read_count, location, integrity_checksum
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
              FROM samples ORDER BY created_at DESC"
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to prepare query: {}", e)
-        )))?;
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
 
         let samples = stmt.query_map([], |row| {
             Self::row_to_sample(row)
-        }).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to execute query: {}", e)
-        )))?
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
         .collect::<std::result::Result<Vec<_>, _>>()
-        .map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to parse rows: {}", e)
-        )))?;
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
 
         Ok(samples)
     }
 
+    /// Number of stored samples
+    pub fn count_samples(&self) -> Result<usize> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM samples", [], |row| row.get(0))
+            .map_err(|e| SampleGuardError::database("Failed to count samples", e))?;
+        Ok(count as usize)
+    }
+
+    /// Call `f` with every sample in `sample_id` order, one row at a time,
+    /// stopping at the first error `f` returns
+    pub fn stream_samples(&self, mut f: impl FnMut(&Sample) -> Result<()>) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
+             temperature_min, temperature_max, storage_conditions, manufacturer,
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
+             FROM samples ORDER BY sample_id"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let mut rows = stmt.query([])
+            .map_err(|e| SampleGuardError::database("Failed to execute query", e))?;
+        let mut count = 0;
+        while let Some(row) = rows.next()
+            .map_err(|e| SampleGuardError::database("Failed to read row", e))?
+        {
+            let sample = Self::row_to_sample(row)
+                .map_err(|e| SampleGuardError::database("Failed to parse row", e))?;
+            f(&sample)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// One page of all samples
+    pub fn get_samples_paged(&self, offset: usize, limit: usize, order: SortOrder) -> Result<Page<Sample>> {
+        self.query_samples_page("1 = 1", &[], offset, limit, order)
+    }
+
+    /// Page `page` (counting from 1) of all samples, `page_size` at a time.
+    /// A page past the last one is empty.
+    pub fn get_samples_paginated(&self, page: usize, page_size: usize, order_by: SortOrder) -> Result<SamplesPage> {
+        if page == 0 || page_size == 0 {
+            return Err(SampleGuardError::InvalidSampleData(
+                "page and page_size must be at least 1".to_string(),
+            ));
+        }
+        let offset = (page - 1).saturating_mul(page_size);
+        let result = self.get_samples_paged(offset, page_size, order_by)?;
+        Ok(SamplesPage {
+            samples: result.items,
+            total_count: result.total_count,
+            page,
+            page_size,
+            total_pages: result.total_count.div_ceil(page_size),
+        })
+    }
+
+    /// One page of the samples in a batch
+    pub fn get_samples_by_batch_paged(
+        &self,
+        batch_number: &str,
+        offset: usize,
+        limit: usize,
+        order: SortOrder,
+    ) -> Result<Page<Sample>> {
+        self.query_samples_page("batch_number = ?1", params![batch_number], offset, limit, order)
+    }
+
+    /// One page of the samples with a status
+    pub fn get_samples_by_status_paged(
+        &self,
+        status: SampleStatus,
+        offset: usize,
+        limit: usize,
+        order: SortOrder,
+    ) -> Result<Page<Sample>> {
+        self.query_samples_page("status = ?1", params![format!("{:?}", status)], offset, limit, order)
+    }
+
+    /// Up to `limit` samples matching `condition` after skipping `offset`,
+    /// with the total number of matches. An offset past the end yields an
+    /// empty page.
+    fn query_samples_page(
+        &self,
+        condition: &str,
+        params: &[&dyn rusqlite::ToSql],
+        offset: usize,
+        limit: usize,
+        order: SortOrder,
+    ) -> Result<Page<Sample>> {
+        let total_count: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM samples WHERE {}", condition),
+            params,
+            |row| row.get(0),
+        ).map_err(|e| SampleGuardError::database("Failed to count samples", e))?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
+             temperature_min, temperature_max, storage_conditions, manufacturer,
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
+             FROM samples WHERE {} ORDER BY {} LIMIT {} OFFSET {}",
+            condition,
+            order.order_by(),
+            limit.min(i64::MAX as usize),
+            offset.min(i64::MAX as usize),
+        )).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let items = stmt.query_map(params, |row| {
+            Self::row_to_sample(row)
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        let total_count = total_count as usize;
+        Ok(Page {
+            has_more: offset.saturating_add(items.len()) < total_count,
+            items,
+            total_count,
+        })
+    }
+
     /// Get samples by batch number
     pub fn get_samples_by_batch(&self, batch_number: &str) -> Result<Vec<Sample>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
              temperature_min, temperature_max, storage_conditions, manufacturer,
-             product_line, created_at, last_updated, read_count, location, integrity_checksum
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
              FROM samples WHERE batch_number = ?1 ORDER BY created_at DESC"
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to prepare query: {}", e)
-        )))?;
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
 
         let samples = stmt.query_map(params![batch_number], |row| {
             Self::row_to_sample(row)
-        }).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to execute query: {}", e)
-        )))?
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
         .collect::<std::result::Result<Vec<_>, _>>()
-        .map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to parse rows: {}", e)
-        )))?;
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
 
         Ok(samples)
     }
@@ -226,180 +713,674 @@ impl Database {
         let mut stmt = self.conn.prepare(
             "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
              temperature_min, temperature_max, storage_conditions, manufacturer,
-             product_line, created_at, last_updated, read_count, location, integrity_checksum
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
              FROM samples WHERE status = ?1 ORDER BY created_at DESC"
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to prepare query: {}", e)
-        )))?;
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
 
         let samples = stmt.query_map(params![status_str], |row| {
             Self::row_to_sample(row)
-        }).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to execute query: {}", e)
-        )))?
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(samples)
+    }
+
+    /// Get samples stored at a location
+    pub fn get_samples_by_location(&self, location: &str) -> Result<Vec<Sample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
+             temperature_min, temperature_max, storage_conditions, manufacturer,
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
+             FROM samples WHERE location = ?1 ORDER BY created_at DESC"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let samples = stmt.query_map(params![location], |row| {
+            Self::row_to_sample(row)
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(samples)
+    }
+
+    /// Samples that have not yet expired but will by the end of the day
+    /// `days` from today (UTC), soonest first. A sample expiring at the
+    /// midnight that ends that day is included.
+    pub fn get_samples_expiring_within(&self, days: u32) -> Result<Vec<Sample>> {
+        self.samples_expiring_within_at(days, Utc::now())
+    }
+
+    fn samples_expiring_within_at(&self, days: u32, now: DateTime<Utc>) -> Result<Vec<Sample>> {
+        let cutoff = (now.date_naive() + chrono::Days::new(days as u64 + 1))
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc();
+        self.query_samples_by_expiry("expiry_date > ?1 AND expiry_date <= ?2", params![now.to_rfc3339(), cutoff.to_rfc3339()])
+    }
+
+    /// Samples whose expiry date has passed, longest expired first
+    pub fn get_expired_samples(&self) -> Result<Vec<Sample>> {
+        self.query_samples_by_expiry("expiry_date <= ?1", params![Utc::now().to_rfc3339()])
+    }
+
+    /// Samples with an expiry date at or before `cutoff`, including those
+    /// already expired, soonest first
+    pub fn get_samples_expiring_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<Sample>> {
+        self.query_samples_by_expiry("expiry_date <= ?1", params![cutoff.to_rfc3339()])
+    }
+
+    /// Samples produced between `start` and `end` inclusive, oldest first
+    pub fn get_samples_by_production_range(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<Sample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
+             temperature_min, temperature_max, storage_conditions, manufacturer,
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
+             FROM samples WHERE production_date BETWEEN ?1 AND ?2 ORDER BY production_date, sample_id"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let samples = stmt.query_map(params![start.to_rfc3339(), end.to_rfc3339()], |row| {
+            Self::row_to_sample(row)
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(samples)
+    }
+
+    fn query_samples_by_expiry(&self, condition: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<Sample>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
+             temperature_min, temperature_max, storage_conditions, manufacturer,
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
+             FROM samples WHERE expiry_date IS NOT NULL AND {} ORDER BY expiry_date", condition
+        )).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let samples = stmt.query_map(params, |row| {
+            Self::row_to_sample(row)
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(samples)
+    }
+
+    /// Samples matching every criterion set in `query`, newest first.
+    /// Substring and name matches ignore ASCII case.
+    pub fn search_samples(&self, query: &SampleQuery) -> Result<Vec<Sample>> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(id) = &query.id_contains {
+            conditions.push("sample_id LIKE ? ESCAPE '\\'");
+            values.push(like_pattern(id));
+        }
+        if let Some(batch_number) = &query.batch_number {
+            conditions.push("batch_number = ?");
+            values.push(batch_number.clone());
+        }
+        if let Some(status) = query.status {
+            conditions.push("status = ?");
+            values.push(format!("{:?}", status));
+        }
+        if let Some(manufacturer) = &query.manufacturer {
+            conditions.push("manufacturer = ? COLLATE NOCASE");
+            values.push(manufacturer.clone());
+        }
+        if let Some(product_line) = &query.product_line {
+            conditions.push("product_line = ? COLLATE NOCASE");
+            values.push(product_line.clone());
+        }
+        if let Some(location) = &query.location_contains {
+            conditions.push("location LIKE ? ESCAPE '\\'");
+            values.push(like_pattern(location));
+        }
+        if let Some(after) = query.created_after {
+            conditions.push("created_at > ?");
+            values.push(after.to_rfc3339());
+        }
+        if let Some(before) = query.created_before {
+            conditions.push("created_at < ?");
+            values.push(before.to_rfc3339());
+        }
+        if let Some(before) = query.expiring_before {
+            conditions.push("expiry_date IS NOT NULL AND expiry_date < ?");
+            values.push(before.to_rfc3339());
+        }
+        if conditions.is_empty() {
+            conditions.push("1 = 1");
+        }
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
+             temperature_min, temperature_max, storage_conditions, manufacturer,
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
+             FROM samples WHERE {} ORDER BY created_at DESC, sample_id",
+            conditions.join(" AND ")
+        )).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let samples = stmt.query_map(rusqlite::params_from_iter(&values), |row| {
+            Self::row_to_sample(row)
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
         .collect::<std::result::Result<Vec<_>, _>>()
-        .map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to parse rows: {}", e)
-        )))?;
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
 
         Ok(samples)
     }
 
-    /// Delete a sample
+    /// Delete a sample. Samples with a chain of custody are kept, since the
+    /// chain is append-only.
     pub fn delete_sample(&self, sample_id: &str) -> Result<bool> {
+        self.ensure_no_custody(sample_id, "deleted")?;
+
         // Delete history entries first (due to foreign key constraint)
         self.conn.execute(
             "DELETE FROM sample_history WHERE sample_id = ?1",
             params![sample_id],
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to delete history: {}", e)
-        )))?;
+        ).map_err(|e| SampleGuardError::database("Failed to delete history", e))?;
+
+        self.conn.execute(
+            "DELETE FROM temperature_excursions WHERE sample_id = ?1",
+            params![sample_id],
+        ).map_err(|e| SampleGuardError::database("Failed to delete excursions", e))?;
+
+        self.conn.execute(
+            "DELETE FROM sample_lineage WHERE parent_id = ?1 OR child_id = ?1",
+            params![sample_id],
+        ).map_err(|e| SampleGuardError::database("Failed to delete lineage", e))?;
+
+        self.conn.execute(
+            "DELETE FROM sample_images WHERE sample_id = ?1",
+            params![sample_id],
+        ).map_err(|e| SampleGuardError::database("Failed to delete image", e))?;
+
+        self.conn.execute(
+            "DELETE FROM sample_temperature_log WHERE sample_id = ?1",
+            params![sample_id],
+        ).map_err(|e| SampleGuardError::database("Failed to delete temperature log", e))?;
+
+        self.conn.execute(
+            "DELETE FROM sample_validations WHERE sample_id = ?1",
+            params![sample_id],
+        ).map_err(|e| SampleGuardError::database("Failed to delete validation", e))?;
 
+        self.conn.execute(
+            "DELETE FROM sample_access WHERE sample_id = ?1",
+            params![sample_id],
+        ).map_err(|e| SampleGuardError::database("Failed to delete access count", e))?;
+
+        self.conn.execute(
+            "DELETE FROM expiry_alerts WHERE sample_id = ?1",
+            params![sample_id],
+        ).map_err(|e| SampleGuardError::database("Failed to delete expiry alerts", e))?;
+
+        self.conn.execute(
+            "DELETE FROM sample_labels WHERE sample_id = ?1",
+            params![sample_id],
+        ).map_err(|e| SampleGuardError::database("Failed to delete labels", e))?;
+
+        self.bump_sample_generation();
         let rows_affected = self.conn.execute(
             "DELETE FROM samples WHERE sample_id = ?1",
             params![sample_id],
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to delete sample: {}", e)
-        )))?;
+        ).map_err(|e| SampleGuardError::database("Failed to delete sample", e))?;
 
         Ok(rows_affected > 0)
     }
 
+    /// Count a physical tag read of a stored sample, returning the new
+    /// `read_count`, or `None` if the sample is not stored
+    pub fn record_tag_read(&self, sample_id: &str) -> Result<Option<u64>> {
+        self.conn.query_row(
+            "UPDATE samples SET read_count = read_count + 1 WHERE sample_id = ?1 RETURNING read_count",
+            params![sample_id],
+            |row| row.get(0),
+        ).optional()
+        .map_err(|e| SampleGuardError::database("Failed to record tag read", e))
+    }
+
+    /// Count a physical tag read of a stored sample in a single statement,
+    /// so concurrent reads are never lost, and return the new `read_count`
+    pub fn increment_read_count(&self, sample_id: &str) -> Result<u64> {
+        self.record_tag_read(sample_id)?.ok_or_else(|| {
+            SampleGuardError::InvalidSampleData(format!("Sample {} is not stored", sample_id))
+        })
+    }
+
+    /// Count an API read of `sample_id`, returning the new access count.
+    /// Unlike `read_count` this never reflects physical tag reads.
+    pub fn record_api_access(&self, sample_id: &str) -> Result<u64> {
+        self.conn.query_row(
+            "INSERT INTO sample_access (sample_id, api_access_count) VALUES (?1, 1)
+             ON CONFLICT(sample_id) DO UPDATE SET api_access_count = api_access_count + 1
+             RETURNING api_access_count",
+            params![sample_id],
+            |row| row.get(0),
+        ).map_err(|e| SampleGuardError::database("Failed to record API access", e))
+    }
+
+    /// Number of API reads of `sample_id` so far
+    pub fn get_api_access_count(&self, sample_id: &str) -> Result<u64> {
+        self.conn.query_row(
+            "SELECT api_access_count FROM sample_access WHERE sample_id = ?1",
+            params![sample_id],
+            |row| row.get(0),
+        ).optional()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| SampleGuardError::database("Failed to read API access count", e))
+    }
+
+    /// API access counts of `sample_ids`, read in one query per chunk of IDs;
+    /// samples never accessed are absent
+    pub fn get_api_access_counts(&self, sample_ids: &[&str]) -> Result<HashMap<String, u64>> {
+        let rows = self.query_by_sample_ids(
+            "SELECT sample_id, api_access_count FROM sample_access WHERE sample_id IN ({})",
+            sample_ids,
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Remember that the `lead_days` expiry alert fired for `sample_id`,
+    /// returning `false` if it had already fired
+    pub fn record_expiry_alert(&self, sample_id: &str, lead_days: u32, at: DateTime<Utc>) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO expiry_alerts (sample_id, lead_days, alerted_at) VALUES (?1, ?2, ?3)",
+            params![sample_id, lead_days, at.to_rfc3339()],
+        ).map_err(|e| SampleGuardError::database("Failed to record expiry alert", e))?;
+        Ok(inserted > 0)
+    }
+
+    /// Lead times whose expiry alert has fired for `sample_id`, longest first
+    pub fn get_expiry_alerts(&self, sample_id: &str) -> Result<Vec<u32>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT lead_days FROM expiry_alerts WHERE sample_id = ?1 ORDER BY lead_days DESC"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let alerts = stmt.query_map(params![sample_id], |row| row.get(0))
+            .map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+            .collect::<std::result::Result<Vec<u32>, _>>()
+            .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(alerts)
+    }
+
     /// Add history entry
     pub fn add_history_entry(
         &self,
         sample_id: &str,
         status: &SampleStatus,
         location: Option<&str>,
+        user_id: Option<&str>,
     ) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO sample_history (sample_id, status, location, timestamp) VALUES (?1, ?2, ?3, ?4)",
-            params![
-                sample_id,
-                format!("{:?}", status),
-                location,
-                Utc::now().to_rfc3339(),
-            ],
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to add history entry: {}", e)
-        )))?;
+        self.insert_history_entry(&HistoryEntry {
+            sample_id: sample_id.to_string(),
+            status: *status,
+            location: location.map(str::to_string),
+            timestamp: Utc::now(),
+            user_id: user_id.map(str::to_string),
+            rollup: None,
+        })?;
+        self.roll_up_history(sample_id)
+    }
+
+    fn insert_history_entry(&self, entry: &HistoryEntry) -> Result<()> {
+        let rollup = entry.rollup.as_ref();
+        self.conn.prepare_cached(
+            "INSERT INTO sample_history (sample_id, status, location, timestamp, rollup_count, rollup_start, user_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        ).and_then(|mut stmt| stmt.execute(params![
+                entry.sample_id,
+                format!("{:?}", entry.status),
+                entry.location,
+                entry.timestamp.to_rfc3339(),
+                rollup.map(|r| r.count as i64),
+                rollup.map(|r| r.first.to_rfc3339()),
+                entry.user_id,
+            ]))
+        .map_err(|e| SampleGuardError::database("Failed to add history entry", e))?;
 
         Ok(())
     }
 
+    /// Fold the oldest history rows of `sample_id` into one rollup row once
+    /// it has more than the maximum depth. The rollup takes the status,
+    /// location, timestamp and user of the newest row it replaces, so the
+    /// history still reads as a sequence of statuses.
+    fn roll_up_history(&self, sample_id: &str) -> Result<()> {
+        let Some(depth) = self.max_history_depth else {
+            return Ok(());
+        };
+
+        // Oldest first; a previous rollup sorts before verbatim rows sharing its timestamp
+        let mut stmt = self.conn.prepare(
+            "SELECT id, status, location, timestamp, rollup_count, rollup_start, user_id FROM sample_history
+             WHERE sample_id = ?1 ORDER BY timestamp ASC, rollup_count IS NULL ASC, id ASC"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+        let rows = stmt.query_map(params![sample_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+            ))
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        if rows.len() <= depth {
+            return Ok(());
+        }
+        let folded = &rows[..rows.len() - depth + 1];
+        let count: i64 = folded.iter().map(|row| row.4.unwrap_or(1)).sum();
+        let first = folded[0].5.clone().unwrap_or_else(|| folded[0].3.clone());
+        let (_, status, location, last, _, _, user_id) = &folded[folded.len() - 1];
+
+        // A savepoint, since callers may already be inside a transaction
+        self.conn.execute_batch("SAVEPOINT history_rollup;")
+            .map_err(|e| SampleGuardError::database("Failed to roll up history", e))?;
+        let result = folded
+            .iter()
+            .try_for_each(|row| {
+                self.conn.execute("DELETE FROM sample_history WHERE id = ?1", params![row.0]).map(|_| ())
+            })
+            .and_then(|()| {
+                self.conn.execute(
+                    "INSERT INTO sample_history (sample_id, status, location, timestamp, rollup_count, rollup_start, user_id)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![sample_id, status, location, last, count, first, user_id],
+                ).map(|_| ())
+            });
+        let end = if result.is_ok() { "RELEASE history_rollup;" } else { "ROLLBACK TO history_rollup; RELEASE history_rollup;" };
+        self.conn.execute_batch(end)
+            .and(result)
+            .map_err(|e| SampleGuardError::database("Failed to roll up history", e))
+    }
+
     /// Get sample history
     pub fn get_sample_history(&self, sample_id: &str) -> Result<Vec<HistoryEntry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT sample_id, status, location, timestamp FROM sample_history 
-             WHERE sample_id = ?1 ORDER BY timestamp DESC"
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to prepare query: {}", e)
-        )))?;
+            "SELECT sample_id, status, location, timestamp, rollup_count, rollup_start, user_id FROM sample_history
+             WHERE sample_id = ?1 ORDER BY timestamp DESC, rollup_count IS NULL DESC, id DESC"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
 
         let entries = stmt.query_map(params![sample_id], |row| {
-            let status_str: String = row.get(1)?;
-            let status = match status_str.as_str() {
-                "InProduction" => SampleStatus::InProduction,
-                "InTransit" => SampleStatus::InTransit,
-                "Stored" => SampleStatus::Stored,
-                "InUse" => SampleStatus::InUse,
-                "Consumed" => SampleStatus::Consumed,
-                "Discarded" => SampleStatus::Discarded,
-                "Compromised" => SampleStatus::Compromised,
-                _ => SampleStatus::InProduction,
+            let status = status_column(row, 1)?;
+            let timestamp = timestamp_column(row, 3)?;
+            let rollup = match row.get::<_, Option<i64>>(4)? {
+                Some(count) => Some(HistoryRollup {
+                    count: count as usize,
+                    first: timestamp_column(row, 5)?,
+                    last: timestamp,
+                }),
+                None => None,
             };
             Ok(HistoryEntry {
                 sample_id: row.get(0)?,
                 status,
                 location: row.get(2)?,
-                timestamp: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
+                timestamp,
+                user_id: row.get(6)?,
+                rollup,
             })
-        }).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to execute query: {}", e)
-        )))?
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
         .collect::<std::result::Result<Vec<_>, _>>()
-        .map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to parse rows: {}", e)
-        )))?;
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
 
         Ok(entries)
     }
 
-    /// Convert database row to Sample
-    fn row_to_sample(row: &Row) -> rusqlite::Result<Sample> {
-        let id_str: String = row.get(0)?;
-        let id = uuid::Uuid::parse_str(&id_str)
-            .map_err(|_| rusqlite::Error::InvalidColumnType(0, id_str, rusqlite::types::Type::Text))?;
-        
-        let sample_id: String = row.get(1)?;
-        let status_str: String = row.get(2)?;
-        let status = match status_str.as_str() {
-            "InProduction" => SampleStatus::InProduction,
-            "InTransit" => SampleStatus::InTransit,
-            "Stored" => SampleStatus::Stored,
-            "InUse" => SampleStatus::InUse,
-            "Consumed" => SampleStatus::Consumed,
-            "Discarded" => SampleStatus::Discarded,
-            "Compromised" => SampleStatus::Compromised,
-            _ => SampleStatus::InProduction,
-        };
-        
-        let batch_number: String = row.get(3)?;
-        let production_date_str: String = row.get(4)?;
-        let production_date = DateTime::parse_from_rfc3339(&production_date_str)
-            .unwrap()
-            .with_timezone(&Utc);
-        
-        let expiry_date: Option<String> = row.get(5)?;
-        let expiry_date_parsed = expiry_date.and_then(|s| {
-            DateTime::parse_from_rfc3339(&s).ok().map(|d| d.with_timezone(&Utc))
-        });
-        
-        let temp_min: Option<f32> = row.get(6)?;
-        let temp_max: Option<f32> = row.get(7)?;
-        let temperature_range = temp_min.zip(temp_max).map(|(min, max)| (min, max));
-        
-        let storage_conditions: String = row.get(8)?;
-        let manufacturer: String = row.get(9)?;
-        let product_line: String = row.get(10)?;
-        
-        let created_at_str: String = row.get(11)?;
-        let created_at = DateTime::parse_from_rfc3339(&created_at_str)
-            .unwrap()
-            .with_timezone(&Utc);
-        
-        let last_updated_str: String = row.get(12)?;
-        let last_updated = DateTime::parse_from_rfc3339(&last_updated_str)
-            .unwrap()
-            .with_timezone(&Utc);
-        
-        let read_count: u64 = row.get(13)?;
-        let location: Option<String> = row.get(14)?;
-        
-        let checksum_hex: String = row.get(15)?;
-        let checksum_bytes = hex::decode(&checksum_hex)
-            .map_err(|_| rusqlite::Error::InvalidColumnType(15, checksum_hex, rusqlite::types::Type::Text))?;
-        let mut checksum = [0u8; 32];
-        checksum.copy_from_slice(&checksum_bytes[..32]);
+    /// Where a sample has been, oldest first: one entry per history row that
+    /// changed its location or status, the first with nothing before it.
+    /// Rows repeating the previous location and status are left out.
+    pub fn get_movements(&self, sample_id: &str) -> Result<Vec<Movement>> {
+        let mut history = self.get_sample_history(sample_id)?;
+        history.reverse();
 
-        let metadata = SampleMetadata {
-            batch_number,
-            production_date,
-            expiry_date: expiry_date_parsed,
-            temperature_range,
-            storage_conditions,
-            manufacturer,
+        let mut movements: Vec<Movement> = Vec::new();
+        let mut previous: Option<&HistoryEntry> = None;
+        for entry in &history {
+            let unchanged = previous.is_some_and(|p| p.location == entry.location && p.status == entry.status);
+            if !unchanged {
+                movements.push(Movement {
+                    from_location: previous.and_then(|p| p.location.clone()),
+                    to_location: entry.location.clone(),
+                    from_status: previous.map(|p| p.status),
+                    to_status: entry.status,
+                    timestamp: entry.timestamp,
+                    user_id: entry.user_id.clone(),
+                });
+            }
+            previous = Some(entry);
+        }
+        Ok(movements)
+    }
+
+    /// Append a custody transfer to the chain of `entry.sample_id`. The
+    /// transfer must start from the current custodian, or from nobody if
+    /// the sample has none yet.
+    pub fn record_custody_transfer(&self, entry: &CustodyEntry) -> Result<()> {
+        if self.get_sample(&entry.sample_id)?.is_none() {
+            return Err(SampleGuardError::InvalidSampleData(format!("Sample {} not found", entry.sample_id)));
+        }
+        if entry.transferred_to.trim().is_empty() {
+            return Err(SampleGuardError::InvalidSampleData("Custody must be transferred to someone".to_string()));
+        }
+        let current = self.get_current_custodian(&entry.sample_id)?;
+        if entry.transferred_from != current {
+            return Err(SampleGuardError::InvalidSampleData(format!(
+                "Sample {} is held by {}, not {}",
+                entry.sample_id,
+                current.as_deref().unwrap_or("nobody"),
+                entry.transferred_from.as_deref().unwrap_or("nobody")
+            )));
+        }
+
+        self.conn.execute(
+            "INSERT INTO custody_chain (sample_id, transferred_from, transferred_to, transfer_timestamp, witnessed_by, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry.sample_id,
+                entry.transferred_from,
+                entry.transferred_to,
+                entry.transfer_timestamp.to_rfc3339(),
+                entry.witnessed_by,
+                entry.notes,
+            ],
+        ).map_err(|e| SampleGuardError::database("Failed to record custody transfer", e))?;
+
+        Ok(())
+    }
+
+    /// Every custody transfer of a sample, oldest first
+    pub fn get_custody_chain(&self, sample_id: &str) -> Result<Vec<CustodyEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT sample_id, transferred_from, transferred_to, transfer_timestamp, witnessed_by, notes
+             FROM custody_chain WHERE sample_id = ?1 ORDER BY id"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let entries = stmt.query_map(params![sample_id], |row| {
+            Ok(CustodyEntry {
+                sample_id: row.get(0)?,
+                transferred_from: row.get(1)?,
+                transferred_to: row.get(2)?,
+                transfer_timestamp: timestamp_column(row, 3)?,
+                witnessed_by: row.get(4)?,
+                notes: row.get(5)?,
+            })
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(entries)
+    }
+
+    /// Recipient of the latest custody transfer, if any
+    pub fn get_current_custodian(&self, sample_id: &str) -> Result<Option<String>> {
+        self.conn.query_row(
+            "SELECT transferred_to FROM custody_chain WHERE sample_id = ?1 ORDER BY id DESC LIMIT 1",
+            params![sample_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| SampleGuardError::database("Failed to get custodian", e))
+    }
+
+    /// Refuse to remove a sample whose custody chain would be orphaned
+    /// Attach `label` to a stored sample. Returns `false` if the sample
+    /// already had it.
+    pub fn add_label(&self, sample_id: &str, label: &str) -> Result<bool> {
+        let label = label.trim();
+        if label.is_empty() {
+            return Err(SampleGuardError::InvalidSampleData("Label must not be empty".to_string()));
+        }
+        if self.get_sample(sample_id)?.is_none() {
+            return Err(SampleGuardError::InvalidSampleData(format!("Sample {} not found", sample_id)));
+        }
+        let added = self.conn.execute(
+            "INSERT OR IGNORE INTO sample_labels (sample_id, label) VALUES (?1, ?2)",
+            params![sample_id, label],
+        ).map_err(|e| SampleGuardError::database("Failed to add label", e))?;
+        Ok(added > 0)
+    }
+
+    /// Detach `label` from a sample. Returns `false` if it did not have it.
+    pub fn remove_label(&self, sample_id: &str, label: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM sample_labels WHERE sample_id = ?1 AND label = ?2",
+            params![sample_id, label.trim()],
+        ).map_err(|e| SampleGuardError::database("Failed to remove label", e))?;
+        Ok(removed > 0)
+    }
+
+    /// Labels of a sample, alphabetically
+    pub fn get_labels(&self, sample_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT label FROM sample_labels WHERE sample_id = ?1 ORDER BY label"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let labels = stmt.query_map(params![sample_id], |row| row.get(0))
+            .map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+            .collect::<std::result::Result<Vec<String>, _>>()
+            .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(labels)
+    }
+
+    /// Labels of each of `sample_ids`, sorted; samples without labels are absent
+    pub fn get_labels_for_samples(&self, sample_ids: &[&str]) -> Result<HashMap<String, Vec<String>>> {
+        let rows = self.query_by_sample_ids(
+            "SELECT sample_id, label FROM sample_labels WHERE sample_id IN ({}) ORDER BY label",
+            sample_ids,
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )?;
+        let mut labels: HashMap<String, Vec<String>> = HashMap::new();
+        for (sample_id, label) in rows {
+            labels.entry(sample_id).or_default().push(label);
+        }
+        Ok(labels)
+    }
+
+    /// Samples carrying `label`, newest first
+    pub fn get_samples_by_label(&self, label: &str) -> Result<Vec<Sample>> {
+        self.get_samples_by_labels(&[label], LabelMatch::Any)
+    }
+
+    /// Samples carrying any or all of `labels`, newest first
+    pub fn get_samples_by_labels(&self, labels: &[&str], mode: LabelMatch) -> Result<Vec<Sample>> {
+        Ok(self.get_samples_by_labels_paged(labels, mode, 0, usize::MAX, SortOrder::CreatedAt)?.items)
+    }
+
+    /// One page of the samples carrying any or all of `labels`. With no
+    /// labels, `All` matches every sample and `Any` matches none.
+    pub fn get_samples_by_labels_paged(
+        &self,
+        labels: &[&str],
+        mode: LabelMatch,
+        offset: usize,
+        limit: usize,
+        order: SortOrder,
+    ) -> Result<Page<Sample>> {
+        let mut labels: Vec<&str> = labels.iter().map(|l| l.trim()).collect();
+        labels.sort_unstable();
+        labels.dedup();
+        let condition = match (labels.is_empty(), mode) {
+            (true, LabelMatch::All) => "1 = 1".to_string(),
+            (true, LabelMatch::Any) => "0 = 1".to_string(),
+            (false, _) => {
+                let placeholders = vec!["?"; labels.len()].join(", ");
+                let subquery = format!("SELECT sample_id FROM sample_labels WHERE label IN ({})", placeholders);
+                match mode {
+                    LabelMatch::Any => format!("sample_id IN ({})", subquery),
+                    LabelMatch::All => format!(
+                        "sample_id IN ({} GROUP BY sample_id HAVING COUNT(*) = {})",
+                        subquery,
+                        labels.len()
+                    ),
+                }
+            }
+        };
+        let params: Vec<&dyn rusqlite::ToSql> = labels.iter().map(|l| l as &dyn rusqlite::ToSql).collect();
+        self.query_samples_page(&condition, &params, offset, limit, order)
+    }
+
+    fn ensure_no_custody(&self, sample_id: &str, action: &str) -> Result<()> {
+        if self.get_current_custodian(sample_id)?.is_some() {
+            return Err(SampleGuardError::InvalidSampleData(format!(
+                "Sample {} has a chain of custody and cannot be {}",
+                sample_id, action
+            )));
+        }
+        Ok(())
+    }
+
+    /// Convert database row to Sample
+    fn row_to_sample(row: &Row) -> rusqlite::Result<Sample> {
+        let id_str: String = row.get(0)?;
+        let id = uuid::Uuid::parse_str(&id_str)
+            .map_err(|_| rusqlite::Error::InvalidColumnType(0, id_str, rusqlite::types::Type::Text))?;
+        
+        let sample_id: String = row.get(1)?;
+        let status = status_column(row, 2)?;
+        
+        let batch_number: String = row.get(3)?;
+        let production_date = timestamp_column(row, 4)?;
+        let expiry_date_parsed = match row.get::<_, Option<String>>(5)? {
+            Some(_) => Some(timestamp_column(row, 5)?),
+            None => None,
+        };
+        
+        let temp_min: Option<f32> = row.get(6)?;
+        let temp_max: Option<f32> = row.get(7)?;
+        let temperature_range = temp_min.zip(temp_max);
+        
+        let storage_conditions: String = row.get(8)?;
+        let manufacturer: String = row.get(9)?;
+        let product_line: String = row.get(10)?;
+        
+        let created_at = timestamp_column(row, 11)?;
+        let last_updated = timestamp_column(row, 12)?;
+        
+        let read_count: u64 = row.get(13)?;
+        let location: Option<String> = row.get(14)?;
+        
+        let checksum = checksum_column(row, 15)?;
+        let container_id: Option<String> = row.get(16)?;
+        let notes: Option<String> = row.get(17)?;
+
+        let metadata = SampleMetadata {
+            batch_number,
+            production_date,
+            expiry_date: expiry_date_parsed,
+            temperature_range,
+            storage_conditions,
+            manufacturer,
             product_line,
         };
 
@@ -413,92 +1394,2056 @@ impl Database {
             read_count,
             location,
             integrity_checksum: checksum,
+            container_id,
+            notes,
         };
 
         Ok(sample)
     }
 
+    /// Link a temperature violation to every sample stored at the reading's
+    /// location, optionally marking those samples as compromised.
+    /// Returns the IDs of the affected samples.
+    pub fn link_temperature_violation(
+        &self,
+        violation: &TemperatureViolation,
+        flag_compromised: bool,
+    ) -> Result<Vec<String>> {
+        let Some(location) = violation.reading.location.as_deref() else {
+            return Ok(Vec::new());
+        };
+
+        let mut affected = Vec::new();
+        for mut sample in self.get_samples_by_location(location)? {
+            self.insert_excursion(&TemperatureExcursion {
+                sample_id: sample.sample_id.clone(),
+                sensor_id: violation.reading.sensor_id.clone(),
+                location: location.to_string(),
+                temperature: violation.reading.temperature,
+                expected_range: violation.expected_range,
+                violation_type: violation.violation_type.clone(),
+                severity: violation.severity.clone(),
+                timestamp: violation.reading.timestamp,
+            })?;
+
+            if flag_compromised && sample.status != SampleStatus::Compromised {
+                self.change_status(&mut sample, SampleStatus::Compromised, None, None)?;
+            }
+            affected.push(sample.sample_id);
+        }
+
+        Ok(affected)
+    }
+
+    fn insert_excursion(&self, excursion: &TemperatureExcursion) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO temperature_excursions (
+                sample_id, sensor_id, location, temperature, expected_min, expected_max,
+                violation_type, severity, timestamp
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                excursion.sample_id,
+                excursion.sensor_id,
+                excursion.location,
+                excursion.temperature,
+                excursion.expected_range.0,
+                excursion.expected_range.1,
+                format!("{:?}", excursion.violation_type),
+                format!("{:?}", excursion.severity),
+                excursion.timestamp.to_rfc3339(),
+            ],
+        ).map_err(|e| SampleGuardError::database("Failed to record excursion", e))?;
+        Ok(())
+    }
+
+    /// Get temperature excursions recorded against a sample, newest first
+    pub fn get_temperature_excursions(&self, sample_id: &str) -> Result<Vec<TemperatureExcursion>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT sample_id, sensor_id, location, temperature, expected_min, expected_max,
+             violation_type, severity, timestamp
+             FROM temperature_excursions WHERE sample_id = ?1 ORDER BY timestamp DESC"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let excursions = stmt.query_map(params![sample_id], |row| {
+            let violation_type = match row.get::<_, String>(6)?.as_str() {
+                "TooLow" => ViolationType::TooLow,
+                "SensorFailure" => ViolationType::SensorFailure,
+                _ => ViolationType::TooHigh,
+            };
+            let severity = match row.get::<_, String>(7)?.as_str() {
+                "Critical" => ViolationSeverity::Critical,
+                _ => ViolationSeverity::Warning,
+            };
+            let timestamp = timestamp_column(row, 8)?;
+
+            Ok(TemperatureExcursion {
+                sample_id: row.get(0)?,
+                sensor_id: row.get(1)?,
+                location: row.get(2)?,
+                temperature: row.get(3)?,
+                expected_range: (row.get(4)?, row.get(5)?),
+                violation_type,
+                severity,
+                timestamp,
+            })
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(excursions)
+    }
+
+    /// Attach `image` as the primary image of a sample, replacing any previous one
+    pub fn set_sample_image(&self, sample_id: &str, image: &SampleImage) -> Result<()> {
+        let reference = &image.reference;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO sample_images
+             (sample_id, content_type, width, height, size_bytes, data, thumbnail, uploaded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                sample_id,
+                reference.content_type,
+                reference.width,
+                reference.height,
+                reference.size_bytes as i64,
+                image.data,
+                image.thumbnail,
+                reference.uploaded_at.to_rfc3339(),
+            ],
+        ).map_err(|e| SampleGuardError::database("Failed to store image", e))?;
+        Ok(())
+    }
+
+    /// Metadata of a sample's primary image, if it has one
+    pub fn get_sample_image_ref(&self, sample_id: &str) -> Result<Option<ImageReference>> {
+        self.conn.query_row(
+            "SELECT content_type, width, height, size_bytes, uploaded_at FROM sample_images WHERE sample_id = ?1",
+            params![sample_id],
+            |row| {
+                Ok(ImageReference {
+                    content_type: row.get(0)?,
+                    width: row.get(1)?,
+                    height: row.get(2)?,
+                    size_bytes: row.get::<_, i64>(3)? as usize,
+                    uploaded_at: timestamp_column(row, 4)?,
+                })
+            },
+        ).optional()
+        .map_err(|e| SampleGuardError::database("Failed to read image", e))
+    }
+
+    /// Primary image metadata of each of `sample_ids` that has one
+    pub fn get_sample_image_refs(&self, sample_ids: &[&str]) -> Result<HashMap<String, ImageReference>> {
+        let rows = self.query_by_sample_ids(
+            "SELECT sample_id, content_type, width, height, size_bytes, uploaded_at
+             FROM sample_images WHERE sample_id IN ({})",
+            sample_ids,
+            |row| {
+                Ok((row.get(0)?, ImageReference {
+                    content_type: row.get(1)?,
+                    width: row.get(2)?,
+                    height: row.get(3)?,
+                    size_bytes: row.get::<_, i64>(4)? as usize,
+                    uploaded_at: timestamp_column(row, 5)?,
+                }))
+            },
+        )?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Content type and bytes of a sample's primary image, or of its PNG
+    /// thumbnail when `thumbnail` is set
+    pub fn get_sample_image_data(&self, sample_id: &str, thumbnail: bool) -> Result<Option<(String, Vec<u8>)>> {
+        let sql = if thumbnail {
+            "SELECT 'image/png', thumbnail FROM sample_images WHERE sample_id = ?1"
+        } else {
+            "SELECT content_type, data FROM sample_images WHERE sample_id = ?1"
+        };
+        self.conn.query_row(sql, params![sample_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()
+            .map_err(|e| SampleGuardError::database("Failed to read image", e))
+    }
+
+    /// Store an inventory snapshot, returning the ID to reference it by
+    pub fn save_inventory_snapshot(&self, snapshot: &InventorySnapshot) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO inventory_snapshots (taken_at, tag_count, tags) VALUES (?1, ?2, ?3)",
+            params![
+                snapshot.taken_at.to_rfc3339(),
+                snapshot.tags.len() as i64,
+                serde_json::to_string(&snapshot.tags)?,
+            ],
+        ).map_err(|e| SampleGuardError::database("Failed to store snapshot", e))?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn get_inventory_snapshot(&self, id: i64) -> Result<Option<InventorySnapshot>> {
+        let row = self.conn.query_row(
+            "SELECT taken_at, tags FROM inventory_snapshots WHERE id = ?1",
+            params![id],
+            |row| Ok((timestamp_column(row, 0)?, row.get::<_, String>(1)?)),
+        ).optional()
+        .map_err(|e| SampleGuardError::database("Failed to read snapshot", e))?;
+
+        match row {
+            Some((taken_at, tags)) => Ok(Some(InventorySnapshot {
+                taken_at,
+                tags: serde_json::from_str(&tags)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Keep `result` as the latest validation of `sample_id`, replacing the previous one
+    pub fn save_validation(&self, sample_id: &str, result: &ValidationResult) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO sample_validations (sample_id, validated_at, result) VALUES (?1, ?2, ?3)",
+            params![sample_id, Utc::now().to_rfc3339(), serde_json::to_string(result)?],
+        ).map_err(|e| SampleGuardError::database("Failed to store validation", e))?;
+        Ok(())
+    }
+
+    /// Latest saved validation of `sample_id`, if it has been validated
+    pub fn get_last_validation(&self, sample_id: &str) -> Result<Option<ValidationResult>> {
+        let result = self.conn.query_row(
+            "SELECT result FROM sample_validations WHERE sample_id = ?1",
+            params![sample_id],
+            |row| row.get::<_, String>(0),
+        ).optional()
+        .map_err(|e| SampleGuardError::database("Failed to read validation", e))?;
+
+        match result {
+            Some(result) => Ok(Some(serde_json::from_str(&result)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Append `reading` to the temperature history
+    pub fn store_temperature_reading(&self, reading: &TemperatureReading) -> Result<()> {
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO temperature_readings (sensor_id, location, temperature, timestamp)
+             VALUES (?1, ?2, ?3, ?4)",
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+        stmt.execute(params![
+            reading.sensor_id,
+            reading.location,
+            reading.temperature,
+            sortable_timestamp(reading.timestamp),
+        ]).map_err(|e| SampleGuardError::database("Failed to store temperature reading", e))?;
+        Ok(())
+    }
+
+    /// Append `violation` to the temperature history
+    pub fn store_temperature_violation(&self, violation: &TemperatureViolation) -> Result<()> {
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO temperature_violations (
+                sensor_id, location, temperature, expected_min, expected_max, violation_type, severity, timestamp
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+        stmt.execute(params![
+            violation.reading.sensor_id,
+            violation.reading.location,
+            violation.reading.temperature,
+            violation.expected_range.0,
+            violation.expected_range.1,
+            format!("{:?}", violation.violation_type),
+            format!("{:?}", violation.severity),
+            sortable_timestamp(violation.reading.timestamp),
+        ]).map_err(|e| SampleGuardError::database("Failed to store temperature violation", e))?;
+        Ok(())
+    }
+
+    /// Stored readings of `sensor_id` taken from `from` to `to` inclusive,
+    /// oldest first
+    pub fn get_temperature_readings(&self, sensor_id: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<TemperatureReading>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT sensor_id, location, temperature, timestamp FROM temperature_readings
+             WHERE sensor_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3 ORDER BY timestamp, id"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let readings = stmt.query_map(params![sensor_id, sortable_timestamp(from), sortable_timestamp(to)], |row| {
+            Ok(TemperatureReading {
+                sensor_id: row.get(0)?,
+                location: row.get(1)?,
+                temperature: row.get(2)?,
+                timestamp: timestamp_column(row, 3)?,
+            })
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(readings)
+    }
+
+    /// Stored violations of `sensor_id` from `from` to `to` inclusive,
+    /// oldest first
+    pub fn get_temperature_violations(&self, sensor_id: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<TemperatureViolation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT sensor_id, location, temperature, expected_min, expected_max, violation_type, severity, timestamp
+             FROM temperature_violations
+             WHERE sensor_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3 ORDER BY timestamp, id"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let violations = stmt.query_map(params![sensor_id, sortable_timestamp(from), sortable_timestamp(to)], |row| {
+            Ok(TemperatureViolation {
+                reading: TemperatureReading {
+                    sensor_id: row.get(0)?,
+                    location: row.get(1)?,
+                    temperature: row.get(2)?,
+                    timestamp: timestamp_column(row, 7)?,
+                },
+                expected_range: (row.get(3)?, row.get(4)?),
+                violation_type: enum_column(row, 5)?,
+                severity: enum_column(row, 6)?,
+            })
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(violations)
+    }
+
+    /// Append `event` to the persistent audit log
+    pub fn store_audit_event(&self, event: &AuditEvent) -> Result<()> {
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO audit_events (event_id, event_type, timestamp, user_id, sample_id, details, severity, prev_hash, event_hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        ).map_err(|e| SampleGuardError::database("Failed to prepare audit insert", e))?;
+        stmt.execute(params![
+            event.event_id.to_string(),
+            format!("{:?}", event.event_type),
+            sortable_timestamp(event.timestamp),
+            event.user_id,
+            event.sample_id,
+            serde_json::to_string(&event.details)?,
+            format!("{:?}", event.severity),
+            event.prev_hash.map(hex::encode),
+            event.event_hash.map(hex::encode),
+        ]).map_err(|e| SampleGuardError::database("Failed to store audit event", e))?;
+        Ok(())
+    }
+
+    /// Persisted audit events matching `filter`, oldest first
+    pub fn query_audit_events(&self, filter: &AuditFilter) -> Result<Vec<AuditEvent>> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+        if let Some(event_type) = &filter.event_type {
+            conditions.push("event_type = ?");
+            values.push(format!("{:?}", event_type));
+        }
+        if let Some(sample_id) = &filter.sample_id {
+            conditions.push("sample_id = ?");
+            values.push(sample_id.clone());
+        }
+        if let Some(severity) = &filter.severity {
+            conditions.push("severity = ?");
+            values.push(format!("{:?}", severity));
+        }
+        if let Some(start) = filter.start_time {
+            conditions.push("timestamp >= ?");
+            values.push(sortable_timestamp(start));
+        }
+        if let Some(end) = filter.end_time {
+            conditions.push("timestamp <= ?");
+            values.push(sortable_timestamp(end));
+        }
+        if conditions.is_empty() {
+            conditions.push("1 = 1");
+        }
+
+        self.select_audit_events(&format!("WHERE {} ORDER BY timestamp, rowid", conditions.join(" AND ")), &values)
+    }
+
+    /// Every persisted audit event in the order it was stored
+    pub fn audit_events_in_log_order(&self) -> Result<Vec<AuditEvent>> {
+        self.select_audit_events("ORDER BY rowid", &[])
+    }
+
+    /// The most recently stored audit event
+    pub fn last_audit_event(&self) -> Result<Option<AuditEvent>> {
+        Ok(self.select_audit_events("ORDER BY rowid DESC LIMIT 1", &[])?.pop())
+    }
+
+    /// Audit events selected by `clause`, a WHERE/ORDER BY tail bound to `values`
+    fn select_audit_events(&self, clause: &str, values: &[String]) -> Result<Vec<AuditEvent>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT event_id, event_type, timestamp, user_id, sample_id, details, severity, prev_hash, event_hash
+             FROM audit_events {}",
+            clause
+        )).map_err(|e| SampleGuardError::database("Failed to prepare audit query", e))?;
+
+        let events = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+            let event_id: String = row.get(0)?;
+            let details: String = row.get(5)?;
+            Ok(AuditEvent {
+                event_id: event_id.parse()
+                    .map_err(|e| conversion_error(row, 0, format!("invalid event id '{}': {}", event_id, e)))?,
+                event_type: enum_column(row, 1)?,
+                timestamp: timestamp_column(row, 2)?,
+                user_id: row.get(3)?,
+                sample_id: row.get(4)?,
+                details: serde_json::from_str(&details)
+                    .map_err(|e| conversion_error(row, 5, format!("invalid details: {}", e)))?,
+                severity: enum_column(row, 6)?,
+                prev_hash: optional_checksum_column(row, 7)?,
+                event_hash: optional_checksum_column(row, 8)?,
+            })
+        }).map_err(|e| SampleGuardError::database("Failed to query audit events", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse audit events", e))?;
+
+        Ok(events)
+    }
+
+    /// Record `reading` in the temperature log of every sample stored at its
+    /// location, judged against each sample's own range or `default_range`
+    /// when it has none. Returns the IDs of the samples logged against.
+    pub fn log_sample_temperature(&self, reading: &TemperatureReading, default_range: (f32, f32)) -> Result<Vec<String>> {
+        let Some(location) = reading.location.as_deref() else {
+            return Ok(Vec::new());
+        };
+
+        let mut logged = Vec::new();
+        for sample in self.get_samples_by_location(location)? {
+            let (min, max) = sample.metadata.temperature_range.unwrap_or(default_range);
+            let in_range = reading.temperature >= min && reading.temperature <= max;
+            self.conn.execute(
+                "INSERT INTO sample_temperature_log (sample_id, sensor_id, location, temperature, in_range, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    sample.sample_id,
+                    reading.sensor_id,
+                    location,
+                    reading.temperature,
+                    in_range,
+                    reading.timestamp.to_rfc3339(),
+                ],
+            ).map_err(|e| SampleGuardError::database("Failed to log temperature", e))?;
+            logged.push(sample.sample_id);
+        }
+
+        Ok(logged)
+    }
+
+    /// Readings logged against a sample, oldest first, optionally only those
+    /// taken at or after `since`
+    pub fn get_sample_temperature_log(
+        &self,
+        sample_id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<TemperatureReading>> {
+        Ok(self.query_sample_temperature_log(sample_id, since)?
+            .into_iter()
+            .map(|(reading, _)| reading)
+            .collect())
+    }
+
+    /// Summarize a sample's temperature log. Each out-of-range reading counts
+    /// until the next reading, the latest one until `now`.
+    pub fn get_sample_temperature_summary(
+        &self,
+        sample_id: &str,
+        since: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<SampleTemperatureSummary> {
+        let log = self.query_sample_temperature_log(sample_id, since)?;
+        let mut summary = SampleTemperatureSummary {
+            readings: log.len(),
+            out_of_range_readings: log.iter().filter(|(_, in_range)| !in_range).count(),
+            time_out_of_range_secs: 0,
+        };
+        for (i, (reading, in_range)) in log.iter().enumerate() {
+            if *in_range {
+                continue;
+            }
+            let until = log.get(i + 1).map(|(next, _)| next.timestamp).unwrap_or(now);
+            summary.time_out_of_range_secs += (until - reading.timestamp).num_seconds().max(0);
+        }
+        Ok(summary)
+    }
+
+    fn query_sample_temperature_log(
+        &self,
+        sample_id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<(TemperatureReading, bool)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT sensor_id, location, temperature, in_range, timestamp FROM sample_temperature_log
+             WHERE sample_id = ?1 AND (?2 IS NULL OR timestamp >= ?2) ORDER BY timestamp ASC, id ASC"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let log = stmt.query_map(params![sample_id, since.map(|t| t.to_rfc3339())], |row| {
+            let reading = TemperatureReading {
+                sensor_id: row.get(0)?,
+                location: row.get(1)?,
+                temperature: row.get(2)?,
+                timestamp: timestamp_column(row, 4)?,
+            };
+            Ok((reading, row.get(3)?))
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(log)
+    }
+
+    /// Delete history entries older than `cutoff`, always keeping the most
+    /// recent entry for each sample. Returns the number of entries removed.
+    pub fn prune_history_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let removed = self.conn.execute(
+            "DELETE FROM sample_history
+             WHERE timestamp < ?1
+             AND EXISTS (
+                 SELECT 1 FROM sample_history newer
+                 WHERE newer.sample_id = sample_history.sample_id
+                 AND newer.timestamp > sample_history.timestamp
+             )",
+            params![cutoff.to_rfc3339()],
+        ).map_err(|e| SampleGuardError::database("Failed to prune history", e))?;
+
+        Ok(removed)
+    }
+
     /// Get database statistics
     pub fn get_statistics(&self) -> Result<DatabaseStatistics> {
         let total_samples: i64 = self.conn.query_row(
             "SELECT COUNT(*) FROM samples",
             [],
             |row| row.get(0),
-        ).map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to get statistics: {}", e)
-        )))?;
+        ).map_err(|e| SampleGuardError::database("Failed to get statistics", e))?;
 
         let status_counts: Vec<(String, i64)> = self.conn
             .prepare("SELECT status, COUNT(*) FROM samples GROUP BY status")
-            .map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to prepare query: {}", e)
-            )))?
+            .map_err(|e| SampleGuardError::database("Failed to prepare query", e))?
             .query_map([], |row| {
                 Ok((row.get(0)?, row.get(1)?))
             })
-            .map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to execute query: {}", e)
-            )))?
+            .map_err(|e| SampleGuardError::database("Failed to execute query", e))?
             .collect::<std::result::Result<Vec<_>, _>>()
-            .map_err(|e| SampleGuardError::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to parse rows: {}", e)
-            )))?;
+            .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
 
         let status_map: std::collections::HashMap<String, usize> = status_counts
             .into_iter()
             .map(|(k, v)| (k, v as usize))
             .collect();
 
+        let manufacturer_counts: std::collections::HashMap<String, usize> = self.conn
+            .prepare("SELECT manufacturer, COUNT(*) FROM samples GROUP BY manufacturer")
+            .map_err(|e| SampleGuardError::database("Failed to prepare query", e))?
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })
+            .map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        let now = Utc::now();
+        let (expired, expiring_within_30_days): (i64, i64) = self.conn.query_row(
+            "SELECT
+                COALESCE(SUM(CASE WHEN expiry_date <= ?1 THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN expiry_date > ?1 AND expiry_date <= ?2 THEN 1 ELSE 0 END), 0)
+             FROM samples WHERE expiry_date IS NOT NULL",
+            params![now.to_rfc3339(), (now + chrono::Duration::days(30)).to_rfc3339()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).map_err(|e| SampleGuardError::database("Failed to get statistics", e))?;
+
         Ok(DatabaseStatistics {
             total_samples: total_samples as usize,
             status_counts: status_map,
+            manufacturer_counts,
+            expired: expired as usize,
+            expiring_within_30_days: expiring_within_30_days as usize,
         })
     }
-}
 
-/// History entry for sample tracking
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HistoryEntry {
-    pub sample_id: String,
-    pub status: SampleStatus,
-    pub location: Option<String>,
-    pub timestamp: DateTime<Utc>,
-}
+    /// Stored samples that cannot be read back or fail their integrity
+    /// check, found by scanning the whole table without stopping at the
+    /// first bad row
+    pub fn find_corrupt_rows(&self) -> Result<Vec<CorruptSample>> {
+        const SCAN_BATCH_SIZE: usize = 500;
+        Ok(self.verify_samples(SCAN_BATCH_SIZE)?.corrupt)
+    }
 
-/// Database statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DatabaseStatistics {
-    pub total_samples: usize,
-    pub status_counts: std::collections::HashMap<String, usize>,
-}
+    /// Re-check every stored sample in batches of `batch_size`, reporting rows
+    /// that cannot be parsed or whose integrity checksum does not match
+    pub fn verify_samples(&self, batch_size: usize) -> Result<VerificationReport> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, sample_id, status, batch_number, production_date, expiry_date,
+             temperature_min, temperature_max, storage_conditions, manufacturer,
+             product_line, created_at, last_updated, read_count, location, integrity_checksum,
+             container_id, notes
+             FROM samples WHERE sample_id > ?1 ORDER BY sample_id LIMIT ?2"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::sample::SampleMetadata;
-    use chrono::Utc;
+        let mut report = VerificationReport::default();
+        let mut last_sample_id = String::new();
 
-    fn create_test_sample(id: &str) -> Sample {
-        let metadata = SampleMetadata {
-            batch_number: format!("BATCH-{}", id),
-            production_date: Utc::now(),
-            expiry_date: Some(Utc::now() + chrono::Duration::days(365)),
-            temperature_range: Some((2.0, 8.0)),
-            storage_conditions: "Refrigerated".to_string(),
-            manufacturer: "Test".to_string(),
-            product_line: "Test".to_string(),
-        };
-        Sample::new(id.to_string(), metadata, Some("Test Location".to_string()))
+        loop {
+            let batch = stmt.query_map(params![last_sample_id, batch_size.max(1) as i64], |row| {
+                let sample_id: String = row.get(1)?;
+                Ok((sample_id, Self::row_to_sample(row).map_err(|e| e.to_string())))
+            }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+            let Some((last, _)) = batch.last() else {
+                break;
+            };
+            last_sample_id = last.clone();
+
+            for (sample_id, parsed) in batch {
+                report.checked += 1;
+                let reason = match parsed {
+                    Ok(sample) => match sample.checksum_version() {
+                        Some(ChecksumVersion::LengthPrefixed) => continue,
+                        Some(ChecksumVersion::Legacy) => {
+                            report.legacy_checksums += 1;
+                            if self.allow_legacy_checksums {
+                                continue;
+                            }
+                            "legacy checksum layout is not accepted".to_string()
+                        }
+                        None => "integrity checksum mismatch".to_string(),
+                    },
+                    Err(e) => format!("unreadable row: {}", e),
+                };
+                report.corrupt.push(CorruptSample { sample_id, reason });
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Rewrite every valid legacy checksum in the current layout, returning
+    /// the number of samples upgraded. Checksums matching neither layout are
+    /// left untouched for `verify_samples` to report.
+    pub fn upgrade_checksums(&self) -> Result<usize> {
+        let mut legacy = Vec::new();
+        self.stream_samples(|sample| {
+            if sample.checksum_version() == Some(ChecksumVersion::Legacy) {
+                legacy.push(sample.clone());
+            }
+            Ok(())
+        })?;
+
+        self.conn.execute_batch("BEGIN;")
+            .map_err(|e| SampleGuardError::database("Failed to begin upgrade", e))?;
+        let result = legacy.iter_mut().try_for_each(|sample| {
+            sample.upgrade_checksum();
+            self.conn.execute(
+                "UPDATE samples SET integrity_checksum = ?1 WHERE sample_id = ?2",
+                params![hex::encode(sample.integrity_checksum), sample.sample_id],
+            ).map(|_| ())
+        });
+        let end = if result.is_ok() { "COMMIT;" } else { "ROLLBACK;" };
+        self.conn.execute_batch(end)
+            .map_err(|e| SampleGuardError::database("Failed to finish upgrade", e))?;
+        result.map_err(|e| SampleGuardError::database("Failed to upgrade checksum", e))?;
+
+        Ok(legacy.len())
+    }
+
+    /// Import samples, resolving existing sample ids with `strategy`
+    pub fn import_samples(&self, samples: &[Sample], strategy: ConflictStrategy) -> Result<ImportReport> {
+        self.import_records(
+            samples.iter().enumerate().map(|(i, sample)| (i + 1, Ok(sample.clone()))),
+            strategy,
+        )
+    }
+
+    /// Import samples from JSON lines, one serialized `Sample` per line
+    pub fn import_jsonl<R: std::io::BufRead>(&self, reader: R, strategy: ConflictStrategy) -> Result<ImportReport> {
+        let mut records = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str::<Sample>(&line).map_err(|e| format!("invalid record: {}", e));
+            records.push((index + 1, record));
+        }
+        self.import_records(records, strategy)
+    }
+
+    /// Write the samples matching `filter`, or every sample, to `writer` as
+    /// CSV with the columns of [`CSV_HEADER`](crate::export::CSV_HEADER).
+    /// Returns the number of rows written.
+    pub fn export_samples_to_csv<W: std::io::Write>(&self, writer: W, filter: Option<&SampleQuery>) -> Result<usize> {
+        match filter {
+            Some(filter) => crate::export::write_samples_csv(&self.search_samples(filter)?, writer),
+            None => crate::export::write_samples(self, crate::export::ExportFormat::Csv, writer),
+        }
+    }
+
+    /// Import samples from CSV as written by `export_samples_to_csv`.
+    /// Rows that fail validation are reported and the rest imported.
+    pub fn import_samples_from_csv<R: std::io::Read>(&self, reader: R, strategy: ConflictStrategy) -> Result<ImportReport> {
+        self.import_records(crate::export::read_samples_csv(reader)?, strategy)
+    }
+
+    /// Import records in one transaction. Under `ConflictStrategy::Fail` the
+    /// transaction is rolled back at the first existing sample id.
+    fn import_records<I>(&self, records: I, strategy: ConflictStrategy) -> Result<ImportReport>
+    where
+        I: IntoIterator<Item = (usize, std::result::Result<Sample, String>)>,
+    {
+        self.conn.execute_batch("BEGIN;")
+            .map_err(|e| SampleGuardError::database("Failed to begin import", e))?;
+
+        let mut report = ImportReport::default();
+        let result = (|| {
+            for (line, record) in records {
+                let sample = match record {
+                    Ok(sample) if self.checksum_accepted(&sample) => sample,
+                    Ok(sample) => {
+                        report.push(line, Some(sample.sample_id), ImportOutcome::Failed("integrity checksum mismatch".to_string()));
+                        continue;
+                    }
+                    Err(e) => {
+                        report.push(line, None, ImportOutcome::Failed(e));
+                        continue;
+                    }
+                };
+
+                let exists = self.get_sample(&sample.sample_id)?.is_some();
+                let outcome = match (exists, strategy) {
+                    (false, _) => {
+                        self.store_sample(&sample)?;
+                        ImportOutcome::Inserted
+                    }
+                    (true, ConflictStrategy::Overwrite) => match self.store_sample(&sample) {
+                        Ok(()) => ImportOutcome::Updated,
+                        Err(e @ SampleGuardError::InvalidStatusTransition { .. }) => ImportOutcome::Failed(e.to_string()),
+                        Err(e) => return Err(e),
+                    },
+                    (true, ConflictStrategy::Skip) => ImportOutcome::Skipped,
+                    (true, ConflictStrategy::Fail) => {
+                        report.push(line, Some(sample.sample_id), ImportOutcome::Failed("sample already exists".to_string()));
+                        report.rolled_back = true;
+                        return Ok(());
+                    }
+                };
+                report.push(line, Some(sample.sample_id), outcome);
+            }
+            Ok(())
+        })();
+
+        let end = if result.is_err() || report.rolled_back { "ROLLBACK;" } else { "COMMIT;" };
+        self.conn.execute_batch(end)
+            .map_err(|e| SampleGuardError::database("Failed to finish import", e))?;
+        result.map(|_| report)
+    }
+
+    /// Split a sample into aliquots that inherit its metadata and status.
+    /// The parent is kept; each child is linked to it in the lineage.
+    pub fn split_sample(&self, parent_id: &str, children: &[ChildSpec]) -> Result<Vec<Sample>> {
+        if children.is_empty() {
+            return Err(SampleGuardError::InvalidSampleData("A split needs at least one child".to_string()));
+        }
+
+        self.in_transaction("split", || {
+            let parent = self.get_sample(parent_id)?
+                .ok_or_else(|| SampleGuardError::InvalidSampleData(format!("Sample {} not found", parent_id)))?;
+
+            let mut created = Vec::with_capacity(children.len());
+            for spec in children {
+                self.ensure_new_sample_id(&spec.sample_id)?;
+                let location = spec.location.clone().or_else(|| parent.location.clone());
+                let mut child = Sample::new(spec.sample_id.clone(), parent.metadata.clone(), location);
+                child.status = parent.status;
+                self.store_sample(&child)?;
+                self.add_lineage_link(parent_id, &child.sample_id, LineageRelation::Split)?;
+                created.push(child);
+            }
+            Ok(created)
+        })
+    }
+
+    /// Pool several samples into a new one, which takes the metadata and
+    /// location of the first source. The sources are marked consumed.
+    pub fn merge_samples(&self, source_ids: &[&str], new_id: &str) -> Result<Sample> {
+        let mut unique = source_ids.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        if unique.len() < 2 || unique.len() != source_ids.len() {
+            return Err(SampleGuardError::InvalidSampleData(
+                "A merge needs at least two distinct source samples".to_string()
+            ));
+        }
+
+        self.in_transaction("merge", || {
+            let mut sources = Vec::with_capacity(source_ids.len());
+            for id in source_ids {
+                sources.push(self.get_sample(id)?
+                    .ok_or_else(|| SampleGuardError::InvalidSampleData(format!("Sample {} not found", id)))?);
+            }
+            self.ensure_new_sample_id(new_id)?;
+
+            let merged = Sample::new(new_id.to_string(), sources[0].metadata.clone(), sources[0].location.clone());
+            self.store_sample(&merged)?;
+            let mut consumed = Vec::with_capacity(sources.len());
+            for mut source in sources {
+                self.add_lineage_link(&source.sample_id, new_id, LineageRelation::Merge)?;
+                let from = source.status;
+                source.try_update_status(SampleStatus::Consumed)?;
+                self.store_sample(&source)?;
+                consumed.push((source, from));
+            }
+            Ok((merged, consumed))
+        })
+        .map(|(merged, consumed)| {
+            // Only once the merge has committed
+            for (source, from) in consumed.iter().filter(|(_, from)| *from != SampleStatus::Consumed) {
+                self.hooks.notify(source, *from, SampleStatus::Consumed);
+            }
+            merged
+        })
+    }
+
+    /// Parents and children of a sample
+    pub fn get_lineage(&self, sample_id: &str) -> Result<Lineage> {
+        Ok(Lineage {
+            sample_id: sample_id.to_string(),
+            parents: self.query_lineage("child_id", sample_id)?,
+            children: self.query_lineage("parent_id", sample_id)?,
+        })
+    }
+
+    fn query_lineage(&self, column: &str, sample_id: &str) -> Result<Vec<LineageLink>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT parent_id, child_id, relation, timestamp FROM sample_lineage
+             WHERE {} = ?1 ORDER BY id", column
+        )).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let links = stmt.query_map(params![sample_id], |row| {
+            let relation = match row.get::<_, String>(2)?.as_str() {
+                "Merge" => LineageRelation::Merge,
+                _ => LineageRelation::Split,
+            };
+            Ok(LineageLink {
+                parent_id: row.get(0)?,
+                child_id: row.get(1)?,
+                relation,
+                timestamp: timestamp_column(row, 3)?,
+            })
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(links)
+    }
+
+    fn add_lineage_link(&self, parent_id: &str, child_id: &str, relation: LineageRelation) -> Result<()> {
+        self.insert_lineage_link(&LineageLink {
+            parent_id: parent_id.to_string(),
+            child_id: child_id.to_string(),
+            relation,
+            timestamp: Utc::now(),
+        })
+    }
+
+    fn insert_lineage_link(&self, link: &LineageLink) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO sample_lineage (parent_id, child_id, relation, timestamp) VALUES (?1, ?2, ?3, ?4)",
+            params![link.parent_id, link.child_id, format!("{:?}", link.relation), link.timestamp.to_rfc3339()],
+        ).map_err(|e| SampleGuardError::database("Failed to record lineage", e))?;
+        Ok(())
+    }
+
+    /// Move a sample, its history, excursions and lineage links into one
+    /// compressed row of `archived_samples`, removing them from the hot
+    /// tables. Returns `None` if there is no such sample.
+    pub fn archive_sample(&self, sample_id: &str) -> Result<Option<ArchivedSample>> {
+        self.in_transaction("archive", || {
+            let Some(sample) = self.get_sample(sample_id)? else {
+                return Ok(None);
+            };
+            if self.get_archived_blob(sample_id)?.is_some() {
+                return Err(SampleGuardError::InvalidSampleData(format!("Sample {} is already archived", sample_id)));
+            }
+            self.ensure_no_custody(sample_id, "archived")?;
+            let lineage = self.get_lineage(sample_id)?;
+            let record = ArchiveRecord {
+                history: self.get_sample_history(sample_id)?,
+                excursions: self.get_temperature_excursions(sample_id)?,
+                lineage: lineage.parents.into_iter().chain(lineage.children).collect(),
+                labels: self.get_labels(sample_id)?,
+                sample,
+            };
+
+            let summary = ArchivedSample {
+                sample_id: record.sample.sample_id.clone(),
+                batch_number: record.sample.metadata.batch_number.clone(),
+                status: record.sample.status,
+                manufacturer: record.sample.metadata.manufacturer.clone(),
+                product_line: record.sample.metadata.product_line.clone(),
+                archived_at: Utc::now(),
+            };
+            self.conn.execute(
+                "INSERT INTO archived_samples (sample_id, batch_number, status, manufacturer, product_line, archived_at, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    summary.sample_id,
+                    summary.batch_number,
+                    format!("{:?}", summary.status),
+                    summary.manufacturer,
+                    summary.product_line,
+                    summary.archived_at.to_rfc3339(),
+                    record.compress()?,
+                ],
+            ).map_err(|e| SampleGuardError::database("Failed to archive sample", e))?;
+
+            self.delete_sample(sample_id)?;
+            Ok(Some(summary))
+        })
+    }
+
+    /// Bring an archived sample back into the hot tables exactly as it was
+    /// archived. Lineage links are restored only where the other sample is
+    /// still in the hot tables. Returns `None` if nothing is archived under
+    /// `sample_id`.
+    pub fn restore_sample(&self, sample_id: &str) -> Result<Option<Sample>> {
+        self.in_transaction("restore", || {
+            let Some(blob) = self.get_archived_blob(sample_id)? else {
+                return Ok(None);
+            };
+            self.ensure_new_sample_id(sample_id)?;
+            let record = ArchiveRecord::decompress(&blob)?;
+
+            self.insert_sample_row(&record.sample)?;
+            for entry in record.history.iter().rev() {
+                self.insert_history_entry(entry)?;
+            }
+            for excursion in record.excursions.iter().rev() {
+                self.insert_excursion(excursion)?;
+            }
+            for link in &record.lineage {
+                let other = if link.parent_id == sample_id { &link.child_id } else { &link.parent_id };
+                if self.get_sample(other)?.is_some() {
+                    self.insert_lineage_link(link)?;
+                }
+            }
+            for label in &record.labels {
+                self.add_label(sample_id, label)?;
+            }
+            self.conn.execute("DELETE FROM archived_samples WHERE sample_id = ?1", params![sample_id])
+                .map_err(|e| SampleGuardError::database("Failed to remove archive", e))?;
+            Ok(Some(record.sample))
+        })
+    }
+
+    /// Metadata of every archived sample, most recently archived first
+    pub fn list_archived_samples(&self) -> Result<Vec<ArchivedSample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT sample_id, batch_number, status, manufacturer, product_line, archived_at
+             FROM archived_samples ORDER BY archived_at DESC, sample_id"
+        ).map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+
+        let archived = stmt.query_map([], |row| {
+            let status = status_column(row, 2)?;
+            Ok(ArchivedSample {
+                sample_id: row.get(0)?,
+                batch_number: row.get(1)?,
+                status,
+                manufacturer: row.get(3)?,
+                product_line: row.get(4)?,
+                archived_at: timestamp_column(row, 5)?,
+            })
+        }).map_err(|e| SampleGuardError::database("Failed to execute query", e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| SampleGuardError::database("Failed to parse rows", e))?;
+
+        Ok(archived)
+    }
+
+    fn get_archived_blob(&self, sample_id: &str) -> Result<Option<Vec<u8>>> {
+        self.conn.query_row(
+            "SELECT data FROM archived_samples WHERE sample_id = ?1",
+            params![sample_id],
+            |row| row.get(0),
+        ).optional()
+        .map_err(|e| SampleGuardError::database("Failed to read archive", e))
+    }
+
+    /// Run `sql`, whose `{}` becomes the placeholders of an `IN` list, over
+    /// `sample_ids` in chunks that stay within SQLite's parameter limit
+    fn query_by_sample_ids<T>(
+        &self,
+        sql: &str,
+        sample_ids: &[&str],
+        map: impl Fn(&Row) -> rusqlite::Result<T>,
+    ) -> Result<Vec<T>> {
+        const CHUNK: usize = 500;
+        let mut results = Vec::new();
+        for chunk in sample_ids.chunks(CHUNK) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let mut stmt = self.conn.prepare_cached(&sql.replace("{}", &placeholders))
+                .map_err(|e| SampleGuardError::database("Failed to prepare query", e))?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(chunk), |row| map(row))
+                .map_err(|e| SampleGuardError::database("Failed to execute query", e))?;
+            for row in rows {
+                results.push(row.map_err(|e| SampleGuardError::database("Failed to parse rows", e))?);
+            }
+        }
+        Ok(results)
+    }
+
+    fn ensure_new_sample_id(&self, sample_id: &str) -> Result<()> {
+        if self.get_sample(sample_id)?.is_some() {
+            return Err(SampleGuardError::InvalidSampleData(format!("Sample {} already exists", sample_id)));
+        }
+        Ok(())
+    }
+
+    /// Run `f` in a transaction, rolling back if it fails
+    fn in_transaction<T>(&self, what: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("BEGIN;")
+            .map_err(|e| SampleGuardError::database(format!("Failed to begin {}", what), e))?;
+        let result = f();
+        let end = if result.is_ok() { "COMMIT;" } else { "ROLLBACK;" };
+        self.conn.execute_batch(end)
+            .map_err(|e| SampleGuardError::database(format!("Failed to finish {}", what), e))?;
+        result
+    }
+
+    /// Run `f` in a transaction, committing if it succeeds and rolling back
+    /// every write made through the transaction if it fails. Fails if a
+    /// transaction is already open on this database.
+    pub fn with_transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&DatabaseTransaction) -> Result<T>,
+    {
+        let tx = rusqlite::Transaction::new_unchecked(&self.conn, rusqlite::TransactionBehavior::Deferred)
+            .map_err(|e| SampleGuardError::database("Failed to begin transaction", e))?;
+        let transaction = DatabaseTransaction { db: self, tx };
+        let value = f(&transaction)?;
+        transaction.tx.commit()
+            .map_err(|e| SampleGuardError::database("Failed to commit transaction", e))?;
+        Ok(value)
+    }
+
+    /// Store every sample with its first history entry, or none of them if
+    /// any fails. Returns the number stored.
+    pub fn bulk_store_samples(&self, samples: &[Sample]) -> Result<usize> {
+        self.with_transaction(|tx| {
+            for sample in samples {
+                tx.store_sample(sample)?;
+            }
+            Ok(samples.len())
+        })
+    }
+
+    /// Run `f` in a savepoint, undoing it if it fails. Unlike
+    /// `in_transaction` this nests inside a transaction the caller holds.
+    fn in_savepoint<T>(&self, name: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        // Cached, as bulk writes open one savepoint per row
+        let run = |sql: String| self.conn.prepare_cached(&sql).and_then(|mut stmt| stmt.execute([]));
+        run(format!("SAVEPOINT {}", name))
+            .map_err(|e| SampleGuardError::database(format!("Failed to begin {}", name), e))?;
+        let result = f();
+        if result.is_err() {
+            run(format!("ROLLBACK TO {}", name))
+                .map_err(|e| SampleGuardError::database(format!("Failed to roll back {}", name), e))?;
+        }
+        run(format!("RELEASE {}", name))
+            .map_err(|e| SampleGuardError::database(format!("Failed to finish {}", name), e))?;
+        result
+    }
+}
+
+/// Writes grouped by `Database::with_transaction`; they are rolled back
+/// together unless the closure succeeds
+pub struct DatabaseTransaction<'a> {
+    db: &'a Database,
+    tx: rusqlite::Transaction<'a>,
+}
+
+impl DatabaseTransaction<'_> {
+    pub fn store_sample(&self, sample: &Sample) -> Result<()> {
+        self.db.store_sample(sample)
+    }
+
+    pub fn add_history_entry(
+        &self,
+        sample_id: &str,
+        status: &SampleStatus,
+        location: Option<&str>,
+        user_id: Option<&str>,
+    ) -> Result<()> {
+        self.db.add_history_entry(sample_id, status, location, user_id)
+    }
+
+    pub fn record_custody_transfer(&self, entry: &CustodyEntry) -> Result<()> {
+        self.db.record_custody_transfer(entry)
+    }
+
+    /// Reads see the transaction's own uncommitted writes
+    pub fn get_sample(&self, sample_id: &str) -> Result<Option<Sample>> {
+        self.db.get_sample(sample_id)
+    }
+}
+
+/// Criteria for `Database::search_samples`; unset fields match anything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SampleQuery {
+    pub id_contains: Option<String>,
+    pub batch_number: Option<String>,
+    pub status: Option<SampleStatus>,
+    pub manufacturer: Option<String>,
+    pub product_line: Option<String>,
+    pub location_contains: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// Samples with an expiry date before this time
+    pub expiring_before: Option<DateTime<Utc>>,
+}
+
+impl SampleQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_id_contains(mut self, text: impl Into<String>) -> Self {
+        self.id_contains = Some(text.into());
+        self
+    }
+
+    pub fn with_batch_number(mut self, batch_number: impl Into<String>) -> Self {
+        self.batch_number = Some(batch_number.into());
+        self
+    }
+
+    pub fn with_status(mut self, status: SampleStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_manufacturer(mut self, manufacturer: impl Into<String>) -> Self {
+        self.manufacturer = Some(manufacturer.into());
+        self
+    }
+
+    pub fn with_product_line(mut self, product_line: impl Into<String>) -> Self {
+        self.product_line = Some(product_line.into());
+        self
+    }
+
+    pub fn with_location_contains(mut self, text: impl Into<String>) -> Self {
+        self.location_contains = Some(text.into());
+        self
+    }
+
+    pub fn with_created_after(mut self, time: DateTime<Utc>) -> Self {
+        self.created_after = Some(time);
+        self
+    }
+
+    pub fn with_created_before(mut self, time: DateTime<Utc>) -> Self {
+        self.created_before = Some(time);
+        self
+    }
+
+    pub fn with_expiring_before(mut self, time: DateTime<Utc>) -> Self {
+        self.expiring_before = Some(time);
+        self
+    }
+}
+
+/// An aliquot to create when splitting a sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChildSpec {
+    pub sample_id: String,
+    /// Defaults to the parent's location
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
+impl ChildSpec {
+    pub fn new(sample_id: impl Into<String>) -> Self {
+        Self { sample_id: sample_id.into(), location: None }
+    }
+}
+
+/// How a child sample was derived from its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineageRelation {
+    /// Aliquot taken from the parent
+    Split,
+    /// Parent was pooled into the child
+    Merge,
+}
+
+/// One parent-child link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineageLink {
+    pub parent_id: String,
+    pub child_id: String,
+    pub relation: LineageRelation,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Direct parents and children of a sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lineage {
+    pub sample_id: String,
+    pub parents: Vec<LineageLink>,
+    pub children: Vec<LineageLink>,
+}
+
+impl Lineage {
+    pub fn parent_ids(&self) -> Vec<&str> {
+        self.parents.iter().map(|l| l.parent_id.as_str()).collect()
+    }
+
+    pub fn child_ids(&self) -> Vec<&str> {
+        self.children.iter().map(|l| l.child_id.as_str()).collect()
+    }
+}
+
+impl DatabaseBackend for Database {
+    fn store_sample(&self, sample: &Sample) -> Result<()> {
+        Database::store_sample(self, sample)
+    }
+
+    fn get_sample(&self, sample_id: &str) -> Result<Option<Sample>> {
+        Database::get_sample(self, sample_id)
+    }
+
+    fn get_all_samples(&self) -> Result<Vec<Sample>> {
+        Database::get_all_samples(self)
+    }
+
+    fn get_samples_by_batch(&self, batch_number: &str) -> Result<Vec<Sample>> {
+        Database::get_samples_by_batch(self, batch_number)
+    }
+
+    fn get_samples_by_status(&self, status: SampleStatus) -> Result<Vec<Sample>> {
+        Database::get_samples_by_status(self, status)
+    }
+
+    fn delete_sample(&self, sample_id: &str) -> Result<bool> {
+        Database::delete_sample(self, sample_id)
+    }
+
+    fn add_history_entry(&self, sample_id: &str, status: &SampleStatus, location: Option<&str>, user_id: Option<&str>) -> Result<()> {
+        Database::add_history_entry(self, sample_id, status, location, user_id)
+    }
+
+    fn get_sample_history(&self, sample_id: &str) -> Result<Vec<HistoryEntry>> {
+        Database::get_sample_history(self, sample_id)
+    }
+
+    fn get_statistics(&self) -> Result<DatabaseStatistics> {
+        Database::get_statistics(self)
+    }
+}
+
+/// Read a timestamp stored with `to_rfc3339`. Strict RFC 3339 parsing
+/// rejects years outside 0000-9999, which `to_rfc3339` still writes
+/// (e.g. `+10000-01-01T00:00:00+00:00`), so use chrono's wider format.
+fn timestamp_column(row: &Row, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    let value: String = row.get(index)?;
+    value.parse::<DateTime<Utc>>()
+        .map_err(|e| conversion_error(row, index, format!("invalid timestamp '{}': {}", value, e)))
+}
+
+/// Audit events and monitor readings store timestamps with a fixed number
+/// of fractional digits so that text order is time order
+fn sortable_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)
+}
+
+/// Read a unit enum variant stored with `format!("{:?}")`
+fn enum_column<T: serde::de::DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    serde_json::from_value(serde_json::Value::String(value.clone()))
+        .map_err(|_| conversion_error(row, index, format!("unknown value '{}'", value)))
+}
+
+/// Read a status stored with `format!("{:?}")`, treating unknown values as
+/// `InProduction`
+fn status_column(row: &Row, index: usize) -> rusqlite::Result<SampleStatus> {
+    let value: String = row.get(index)?;
+    Ok(parse_status(&value))
+}
+
+pub(crate) fn parse_status(value: &str) -> SampleStatus {
+    match value {
+        "InTransit" => SampleStatus::InTransit,
+        "Stored" => SampleStatus::Stored,
+        "InUse" => SampleStatus::InUse,
+        "Consumed" => SampleStatus::Consumed,
+        "Discarded" => SampleStatus::Discarded,
+        "Compromised" => SampleStatus::Compromised,
+        _ => SampleStatus::InProduction,
+    }
+}
+
+/// Read a hex-encoded SHA-256 checksum, rejecting any other length rather
+/// than slicing into it
+fn checksum_column(row: &Row, index: usize) -> rusqlite::Result<[u8; 32]> {
+    let value: String = row.get(index)?;
+    decode_checksum(&value).map_err(|message| conversion_error(row, index, message))
+}
+
+fn optional_checksum_column(row: &Row, index: usize) -> rusqlite::Result<Option<[u8; 32]>> {
+    match row.get::<_, Option<String>>(index)? {
+        Some(_) => checksum_column(row, index).map(Some),
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn decode_checksum(value: &str) -> std::result::Result<[u8; 32], String> {
+    let bytes = hex::decode(value).map_err(|e| format!("invalid checksum hex: {}", e))?;
+    let len = bytes.len();
+    bytes.try_into()
+        .map_err(|_| format!("integrity checksum must be 32 bytes, got {}", len))
+}
+
+/// `LIKE` pattern matching `text` anywhere, with its wildcards escaped
+fn like_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Conversion failure naming the column and, when the query selected it,
+/// the sample the row belongs to. The column and raw value are kept apart
+/// from the message for `SampleGuardError::DatabaseError`.
+fn conversion_error(row: &Row, index: usize, message: String) -> rusqlite::Error {
+    use rusqlite::types::ValueRef;
+    let column = row.as_ref().column_name(index).unwrap_or("?").to_string();
+    let value = row.get_ref(index).ok().and_then(|value| match value {
+        ValueRef::Null => None,
+        ValueRef::Integer(i) => Some(i.to_string()),
+        ValueRef::Real(f) => Some(f.to_string()),
+        ValueRef::Text(t) => Some(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => Some(hex::encode(b)),
+    });
+    let message = match row.get::<_, String>("sample_id") {
+        Ok(sample_id) => format!("column {} of sample '{}': {}", column, sample_id, message),
+        Err(_) => format!("column {}: {}", column, message),
+    };
+    let error = ColumnError { column, value, message };
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(error))
+}
+
+/// Listing entry for a sample in cold storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedSample {
+    pub sample_id: String,
+    pub batch_number: String,
+    pub status: SampleStatus,
+    pub manufacturer: String,
+    pub product_line: String,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Everything removed from the hot tables when a sample is archived
+#[derive(Serialize, Deserialize)]
+struct ArchiveRecord {
+    sample: Sample,
+    history: Vec<HistoryEntry>,
+    excursions: Vec<TemperatureExcursion>,
+    lineage: Vec<LineageLink>,
+    /// Absent from records archived before labels existed
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+impl ArchiveRecord {
+    fn compress(&self) -> Result<Vec<u8>> {
+        use std::io::Write;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.flush()?;
+        Ok(encoder.finish()?)
+    }
+
+    fn decompress(blob: &[u8]) -> Result<Self> {
+        serde_json::from_reader(GzDecoder::new(blob))
+            .map_err(|e| SampleGuardError::InvalidSampleData(format!("Corrupt archive: {}", e)))
+    }
+}
+
+/// How an import treats a sample whose id already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Replace the stored sample
+    Overwrite,
+    /// Keep the stored sample and ignore the record
+    Skip,
+    /// Abort and roll back the whole import
+    Fail,
+}
+
+impl std::str::FromStr for ConflictStrategy {
+    type Err = SampleGuardError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "overwrite" => Ok(ConflictStrategy::Overwrite),
+            "skip" => Ok(ConflictStrategy::Skip),
+            "fail" => Ok(ConflictStrategy::Fail),
+            other => Err(SampleGuardError::ConfigError(format!(
+                "Unknown conflict strategy '{}' (expected overwrite, skip or fail)", other
+            ))),
+        }
+    }
+}
+
+/// What happened to one imported record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", content = "reason", rename_all = "lowercase")]
+pub enum ImportOutcome {
+    Inserted,
+    Updated,
+    Skipped,
+    Failed(String),
+}
+
+/// Outcome of a single import record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRecord {
+    /// 1-based record number in the input
+    pub line: usize,
+    pub sample_id: Option<String>,
+    pub outcome: ImportOutcome,
+}
+
+/// Outcome of one sample in `Database::store_samples_batch`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchItem {
+    /// Position in the input, from 0
+    pub index: usize,
+    pub sample_id: String,
+    /// Why the sample was not stored
+    pub error: Option<String>,
+}
+
+/// Result of `Database::store_samples_batch`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchResult {
+    /// Samples up to the first failure when the batch was rolled back,
+    /// otherwise every sample
+    pub items: Vec<BatchItem>,
+    /// Set when a failure aborted an all-or-nothing batch; nothing was committed
+    pub rolled_back: bool,
+}
+
+impl BatchResult {
+    pub fn stored(&self) -> usize {
+        if self.rolled_back {
+            0
+        } else {
+            self.items.iter().filter(|i| i.error.is_none()).count()
+        }
+    }
+
+    pub fn failed(&self) -> usize {
+        self.items.iter().filter(|i| i.error.is_some()).count()
+    }
+}
+
+/// Result of an import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub records: Vec<ImportRecord>,
+    /// Set when a conflict aborted the import; nothing was committed
+    pub rolled_back: bool,
+}
+
+impl ImportReport {
+    fn push(&mut self, line: usize, sample_id: Option<String>, outcome: ImportOutcome) {
+        self.records.push(ImportRecord { line, sample_id, outcome });
+    }
+
+    fn count(&self, matches: fn(&ImportOutcome) -> bool) -> usize {
+        self.records.iter().filter(|r| matches(&r.outcome)).count()
+    }
+
+    pub fn inserted(&self) -> usize {
+        self.count(|o| matches!(o, ImportOutcome::Inserted))
+    }
+
+    pub fn updated(&self) -> usize {
+        self.count(|o| matches!(o, ImportOutcome::Updated))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|o| matches!(o, ImportOutcome::Skipped))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|o| matches!(o, ImportOutcome::Failed(_)))
+    }
+}
+
+/// History entry for sample tracking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub sample_id: String,
+    pub status: SampleStatus,
+    pub location: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    /// Who made the change, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Set when this row summarizes older entries dropped to bound the
+    /// history; status, location and timestamp are those of the newest one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollup: Option<HistoryRollup>,
+}
+
+impl HistoryEntry {
+    pub fn is_rollup(&self) -> bool {
+        self.rollup.is_some()
+    }
+}
+
+/// A change of a sample's location or status, derived from its history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Movement {
+    /// `None` for the first entry, or if the sample had no location
+    pub from_location: Option<String>,
+    pub to_location: Option<String>,
+    /// `None` for the first entry
+    pub from_status: Option<SampleStatus>,
+    pub to_status: SampleStatus,
+    pub timestamp: DateTime<Utc>,
+    pub user_id: Option<String>,
+}
+
+impl Movement {
+    /// Whether the sample changed location, rather than only status
+    pub fn relocated(&self) -> bool {
+        self.from_location != self.to_location
+    }
+}
+
+/// One hand-over in a sample's chain of custody
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustodyEntry {
+    pub sample_id: String,
+    /// `None` for the first custodian
+    pub transferred_from: Option<String>,
+    pub transferred_to: String,
+    pub transfer_timestamp: DateTime<Utc>,
+    pub witnessed_by: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Order of paged sample queries. Ties are broken by sample ID so pages
+/// never overlap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Newest first
+    #[default]
+    CreatedAt,
+    /// Most recently changed first
+    LastUpdated,
+    SampleId,
+    /// Soonest expiry first; samples without one last
+    ExpiryDate,
+    BatchNumber,
+}
+
+impl SortOrder {
+    fn order_by(self) -> &'static str {
+        match self {
+            SortOrder::CreatedAt => "created_at DESC, sample_id",
+            SortOrder::LastUpdated => "last_updated DESC, sample_id",
+            SortOrder::SampleId => "sample_id",
+            SortOrder::ExpiryDate => "expiry_date IS NULL, expiry_date, sample_id",
+            SortOrder::BatchNumber => "batch_number, sample_id",
+        }
+    }
+}
+
+/// How a query over several labels combines them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelMatch {
+    /// Samples carrying every label
+    #[default]
+    All,
+    /// Samples carrying at least one of the labels
+    Any,
+}
+
+/// One page of a larger result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Matches across all pages
+    pub total_count: usize,
+    /// Whether pages after this one hold further matches
+    pub has_more: bool,
+}
+
+/// One numbered page of all samples
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplesPage {
+    pub samples: Vec<Sample>,
+    /// Samples across all pages
+    pub total_count: usize,
+    /// Counting from 1
+    pub page: usize,
+    pub page_size: usize,
+    pub total_pages: usize,
+}
+
+/// Summary of history entries folded into a single row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryRollup {
+    /// Number of entries summarized
+    pub count: usize,
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+}
+
+impl HistoryRollup {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} status changes between {} and {}",
+            self.count,
+            self.first.to_rfc3339(),
+            self.last.to_rfc3339()
+        )
+    }
+}
+
+/// Database statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseStatistics {
+    pub total_samples: usize,
+    pub status_counts: std::collections::HashMap<String, usize>,
+    pub manufacturer_counts: std::collections::HashMap<String, usize>,
+    pub expired: usize,
+    pub expiring_within_30_days: usize,
+}
+
+/// Result of `Database::verify_samples`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub checked: usize,
+    pub corrupt: Vec<CorruptSample>,
+    /// Valid samples whose checksum still uses the legacy layout
+    #[serde(default)]
+    pub legacy_checksums: usize,
+}
+
+/// Stored sample that failed verification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorruptSample {
+    pub sample_id: String,
+    pub reason: String,
+}
+
+/// Temperature violation recorded against an affected sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemperatureExcursion {
+    pub sample_id: String,
+    pub sensor_id: String,
+    pub location: String,
+    pub temperature: f32,
+    pub expected_range: (f32, f32),
+    pub violation_type: ViolationType,
+    pub severity: ViolationSeverity,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Time a sample spent outside its temperature range, from its temperature log
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampleTemperatureSummary {
+    pub readings: usize,
+    pub out_of_range_readings: usize,
+    pub time_out_of_range_secs: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DatabaseErrorKind;
+    use crate::sample::SampleMetadata;
+    use chrono::{TimeZone, Utc};
+
+    fn create_test_sample(id: &str) -> Sample {
+        let metadata = SampleMetadata {
+            batch_number: format!("BATCH-{}", id),
+            production_date: Utc::now(),
+            expiry_date: Some(Utc::now() + chrono::Duration::days(365)),
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        Sample::new(id.to_string(), metadata, Some("Test Location".to_string()))
+    }
+
+    #[test]
+    fn test_database_creation() {
+        let db = Database::in_memory().unwrap();
+        assert!(db.get_statistics().is_ok());
+    }
+
+    fn custody(sample_id: &str, from: Option<&str>, to: &str) -> CustodyEntry {
+        CustodyEntry {
+            sample_id: sample_id.to_string(),
+            transferred_from: from.map(str::to_string),
+            transferred_to: to.to_string(),
+            transfer_timestamp: Utc::now(),
+            witnessed_by: Some("QA".to_string()),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_movements_diff_consecutive_history() {
+        let db = Database::in_memory().unwrap();
+        let mut sample = create_test_sample("MOVE-001");
+        db.store_sample(&sample).unwrap();
+        db.change_status(&mut sample, SampleStatus::InTransit, Some("Truck 4".to_string()), Some("courier")).unwrap();
+        // Same status and location again: not a movement
+        db.change_status(&mut sample, SampleStatus::InTransit, None, Some("courier")).unwrap();
+        // Status change in place
+        db.change_status(&mut sample, SampleStatus::Stored, None, Some("clerk")).unwrap();
+        db.change_status(&mut sample, SampleStatus::Stored, Some("Freezer 9".to_string()), None).unwrap();
+
+        let movements = db.get_movements("MOVE-001").unwrap();
+        assert_eq!(movements.len(), 4);
+
+        assert_eq!(movements[0].from_location, None);
+        assert_eq!(movements[0].from_status, None);
+        assert_eq!(movements[0].to_location.as_deref(), Some("Test Location"));
+        assert_eq!(movements[0].user_id, None);
+
+        assert_eq!(movements[1].from_location.as_deref(), Some("Test Location"));
+        assert_eq!(movements[1].to_location.as_deref(), Some("Truck 4"));
+        assert_eq!(movements[1].user_id.as_deref(), Some("courier"));
+        assert!(movements[1].relocated());
+
+        assert_eq!((movements[2].from_status, movements[2].to_status), (Some(SampleStatus::InTransit), SampleStatus::Stored));
+        assert!(!movements[2].relocated());
+        assert_eq!(movements[2].user_id.as_deref(), Some("clerk"));
+
+        assert_eq!(movements[3].to_location.as_deref(), Some("Freezer 9"));
+        assert!(db.get_sample_history("MOVE-001").unwrap().iter().any(|e| e.user_id.as_deref() == Some("clerk")));
+        assert!(db.get_movements("MISSING").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_custody_chain() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&create_test_sample("CUSTODY-001")).unwrap();
+        assert_eq!(db.get_current_custodian("CUSTODY-001").unwrap(), None);
+
+        db.record_custody_transfer(&custody("CUSTODY-001", None, "alice")).unwrap();
+        db.record_custody_transfer(&custody("CUSTODY-001", Some("alice"), "bob")).unwrap();
+
+        let chain = db.get_custody_chain("CUSTODY-001").unwrap();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].transferred_to, "alice");
+        assert_eq!(chain[1].transferred_from.as_deref(), Some("alice"));
+        assert_eq!(db.get_current_custodian("CUSTODY-001").unwrap().as_deref(), Some("bob"));
+
+        // Only the current custodian can hand the sample over
+        assert!(db.record_custody_transfer(&custody("CUSTODY-001", Some("alice"), "carol")).is_err());
+        assert!(db.record_custody_transfer(&custody("MISSING", None, "alice")).is_err());
+    }
+
+    #[test]
+    fn test_custody_chain_is_append_only() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&create_test_sample("CUSTODY-002")).unwrap();
+        db.record_custody_transfer(&custody("CUSTODY-002", None, "alice")).unwrap();
+
+        assert!(db.conn.execute("DELETE FROM custody_chain", []).is_err());
+        assert!(db.conn.execute("UPDATE custody_chain SET transferred_to = 'mallory'", []).is_err());
+
+        // Removing the sample would orphan its chain
+        assert!(db.delete_sample("CUSTODY-002").is_err());
+        assert!(db.archive_sample("CUSTODY-002").is_err());
+        assert!(db.get_sample("CUSTODY-002").unwrap().is_some());
+        assert_eq!(db.get_sample_history("CUSTODY-002").unwrap().len(), 1);
+        assert_eq!(db.get_custody_chain("CUSTODY-002").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_samples_by_label() {
+        let db = Database::in_memory().unwrap();
+        for id in ["LABEL-001", "LABEL-002", "LABEL-003"] {
+            db.store_sample(&create_test_sample(id)).unwrap();
+        }
+        assert!(db.add_label("LABEL-001", "priority").unwrap());
+        assert!(db.add_label("LABEL-003", "priority").unwrap());
+        assert!(!db.add_label("LABEL-003", " priority ").unwrap());
+
+        let mut labelled: Vec<String> = db.get_samples_by_label("priority").unwrap()
+            .into_iter()
+            .map(|s| s.sample_id)
+            .collect();
+        labelled.sort();
+        assert_eq!(labelled, vec!["LABEL-001", "LABEL-003"]);
+        assert!(db.get_samples_by_label("clinical-trial-X").unwrap().is_empty());
+
+        assert!(db.add_label("MISSING", "priority").is_err());
+        assert!(db.add_label("LABEL-001", "  ").is_err());
+
+        assert!(db.remove_label("LABEL-001", "priority").unwrap());
+        assert!(!db.remove_label("LABEL-001", "priority").unwrap());
+        assert_eq!(db.get_samples_by_label("priority").unwrap().len(), 1);
+
+        // Deleting a sample drops its labels
+        assert!(db.delete_sample("LABEL-003").unwrap());
+        assert!(db.get_samples_by_label("priority").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_samples_by_labels_all_or_any() {
+        let db = Database::in_memory().unwrap();
+        for id in ["LABEL-A", "LABEL-B", "LABEL-C"] {
+            db.store_sample(&create_test_sample(id)).unwrap();
+        }
+        db.add_label("LABEL-A", "priority").unwrap();
+        db.add_label("LABEL-A", "trial-x").unwrap();
+        db.add_label("LABEL-B", "priority").unwrap();
+        db.add_label("LABEL-C", "trial-x").unwrap();
+        assert_eq!(db.get_labels("LABEL-A").unwrap(), vec!["priority", "trial-x"]);
+
+        let ids = |labels: &[&str], mode| -> Vec<String> {
+            let mut ids: Vec<String> = db.get_samples_by_labels(labels, mode).unwrap()
+                .into_iter()
+                .map(|s| s.sample_id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&["priority", "trial-x"], LabelMatch::All), vec!["LABEL-A"]);
+        assert_eq!(ids(&["priority", "trial-x"], LabelMatch::Any), vec!["LABEL-A", "LABEL-B", "LABEL-C"]);
+        assert_eq!(ids(&["priority", "priority"], LabelMatch::All), vec!["LABEL-A", "LABEL-B"]);
+        assert_eq!(ids(&[], LabelMatch::All).len(), 3);
+        assert!(ids(&[], LabelMatch::Any).is_empty());
+
+        let page = db.get_samples_by_labels_paged(&["priority"], LabelMatch::Any, 1, 1, SortOrder::SampleId).unwrap();
+        assert_eq!(page.total_count, 2);
+        assert_eq!(page.items[0].sample_id, "LABEL-B");
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn test_batch_lookups_by_sample_id() {
+        let db = Database::in_memory().unwrap();
+        let ids: Vec<String> = (0..600).map(|i| format!("BATCH-{:03}", i)).collect();
+        for id in &ids {
+            db.store_sample(&create_test_sample(id)).unwrap();
+        }
+        db.add_label("BATCH-000", "priority").unwrap();
+        db.add_label("BATCH-000", "trial-x").unwrap();
+        db.add_label("BATCH-599", "priority").unwrap();
+        db.record_api_access("BATCH-000").unwrap();
+        db.record_api_access("BATCH-000").unwrap();
+        db.record_api_access("BATCH-599").unwrap();
+
+        // More ids than fit in a single IN (...) chunk
+        let refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let labels = db.get_labels_for_samples(&refs).unwrap();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels["BATCH-000"], vec!["priority", "trial-x"]);
+        assert_eq!(labels["BATCH-599"], vec!["priority"]);
+
+        let counts = db.get_api_access_counts(&refs).unwrap();
+        assert_eq!(counts.get("BATCH-000"), Some(&2));
+        assert_eq!(counts.get("BATCH-599"), Some(&1));
+        assert!(!counts.contains_key("BATCH-001"));
+
+        assert!(db.get_sample_image_refs(&refs).unwrap().is_empty());
+        assert!(db.get_labels_for_samples(&[]).unwrap().is_empty());
+    }
+
+    fn searchable_sample(id: &str, manufacturer: &str, location: &str, expiry_days: i64) -> Sample {
+        let mut sample = create_test_sample(id);
+        sample.metadata.manufacturer = manufacturer.to_string();
+        sample.metadata.expiry_date = Some(Utc::now() + chrono::Duration::days(expiry_days));
+        sample.location = Some(location.to_string());
+        sample
+    }
+
+    #[test]
+    fn test_search_samples_ands_criteria() {
+        let db = Database::in_memory().unwrap();
+        let base = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        for (i, sample) in [
+            searchable_sample("VAX-001", "PharmaCorp", "Freezer 1", 10),
+            searchable_sample("VAX-002", "PharmaCorp", "Fridge 2", 400),
+            searchable_sample("SERUM-001", "BioLabs", "Freezer 3", 10),
+        ].into_iter().enumerate() {
+            let mut sample = sample;
+            sample.created_at = base + chrono::Duration::days(i as i64);
+            db.store_sample(&sample).unwrap();
+        }
+        let ids = |query: &SampleQuery| -> Vec<String> {
+            db.search_samples(query).unwrap().into_iter().map(|s| s.sample_id).collect()
+        };
+
+        assert_eq!(ids(&SampleQuery::new()), vec!["SERUM-001", "VAX-002", "VAX-001"]);
+        assert_eq!(ids(&SampleQuery::new().with_manufacturer("pharmacorp")), vec!["VAX-002", "VAX-001"]);
+        assert_eq!(ids(&SampleQuery::new().with_id_contains("x-00")), vec!["VAX-002", "VAX-001"]);
+        assert_eq!(ids(&SampleQuery::new().with_location_contains("freezer")), vec!["SERUM-001", "VAX-001"]);
+        assert_eq!(
+            ids(&SampleQuery::new()
+                .with_manufacturer("PharmaCorp")
+                .with_expiring_before(Utc::now() + chrono::Duration::days(30))),
+            vec!["VAX-001"]
+        );
+        assert_eq!(
+            ids(&SampleQuery::new()
+                .with_created_after(base)
+                .with_created_before(base + chrono::Duration::days(2))),
+            vec!["VAX-002"]
+        );
+        assert!(ids(&SampleQuery::new().with_product_line("Other")).is_empty());
+    }
+
+    #[test]
+    fn test_search_samples_combines_batch_status_and_more() {
+        let db = Database::in_memory().unwrap();
+        for (id, batch, status, manufacturer, location) in [
+            ("LOT-001", "LOT-A", SampleStatus::Stored, "PharmaCorp", "Freezer 1"),
+            ("LOT-002", "LOT-A", SampleStatus::InTransit, "PharmaCorp", "Freezer 1"),
+            ("LOT-003", "LOT-A", SampleStatus::Stored, "BioLabs", "Freezer 1"),
+            ("LOT-004", "LOT-B", SampleStatus::Stored, "PharmaCorp", "Freezer 1"),
+            ("LOT-005", "LOT-A", SampleStatus::Stored, "PharmaCorp", "Fridge 2"),
+        ] {
+            let mut sample = searchable_sample(id, manufacturer, location, 30);
+            sample.metadata.batch_number = batch.to_string();
+            sample.update_status(status).unwrap();
+            db.store_sample(&sample).unwrap();
+        }
+
+        let query = SampleQuery::new()
+            .with_batch_number("LOT-A")
+            .with_status(SampleStatus::Stored)
+            .with_manufacturer("PharmaCorp")
+            .with_location_contains("freezer");
+        let found: Vec<String> = db.search_samples(&query).unwrap().into_iter().map(|s| s.sample_id).collect();
+        assert_eq!(found, vec!["LOT-001"]);
+
+        let query = query.with_batch_number("'; DROP TABLE samples;--");
+        assert!(db.search_samples(&query).unwrap().is_empty());
+        assert_eq!(db.count_samples().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_search_samples_treats_input_as_data() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&searchable_sample("100%_PURE", "PharmaCorp", "Freezer 1", 10)).unwrap();
+        db.store_sample(&searchable_sample("100X-PURE", "PharmaCorp", "Freezer 1", 10)).unwrap();
+
+        for hostile in ["'; DROP TABLE samples;--", "\" OR 1=1 --", "' OR '1'='1"] {
+            assert!(db.search_samples(&SampleQuery::new().with_id_contains(hostile)).unwrap().is_empty());
+            assert!(db.search_samples(&SampleQuery::new().with_manufacturer(hostile)).unwrap().is_empty());
+            assert!(db.search_samples(&SampleQuery::new().with_location_contains(hostile)).unwrap().is_empty());
+        }
+        assert_eq!(db.get_all_samples().unwrap().len(), 2);
+
+        // LIKE wildcards in the input match only themselves
+        let found = db.search_samples(&SampleQuery::new().with_id_contains("0%_")).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].sample_id, "100%_PURE");
+        assert!(db.search_samples(&SampleQuery::new().with_id_contains("%")).unwrap().len() == 1);
+    }
+
+    #[test]
+    fn test_split_links_children_to_parent() {
+        let db = Database::in_memory().unwrap();
+        let mut parent = create_test_sample("PARENT-001");
+        parent.update_status(SampleStatus::Stored).unwrap();
+        db.store_sample(&parent).unwrap();
+
+        let children = db.split_sample(
+            "PARENT-001",
+            &[ChildSpec::new("PARENT-001-A"), ChildSpec { sample_id: "PARENT-001-B".to_string(), location: Some("Freezer 2".to_string()) }],
+        ).unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].status, SampleStatus::Stored);
+        assert_eq!(children[0].location.as_deref(), Some("Test Location"));
+        assert_eq!(children[1].location.as_deref(), Some("Freezer 2"));
+
+        for child in ["PARENT-001-A", "PARENT-001-B"] {
+            let lineage = db.get_lineage(child).unwrap();
+            assert_eq!(lineage.parent_ids(), vec!["PARENT-001"]);
+            assert_eq!(lineage.parents[0].relation, LineageRelation::Split);
+            assert!(db.get_sample(child).unwrap().unwrap().metadata == parent.metadata);
+        }
+        assert_eq!(db.get_lineage("PARENT-001").unwrap().child_ids(), vec!["PARENT-001-A", "PARENT-001-B"]);
+
+        // An existing child id aborts the whole split
+        assert!(db.split_sample("PARENT-001", &[ChildSpec::new("PARENT-001-C"), ChildSpec::new("PARENT-001-A")]).is_err());
+        assert!(db.get_sample("PARENT-001-C").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_merge_records_lineage_and_consumes_sources() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&create_test_sample("POOL-A")).unwrap();
+        db.store_sample(&create_test_sample("POOL-B")).unwrap();
+
+        let merged = db.merge_samples(&["POOL-A", "POOL-B"], "POOL-AB").unwrap();
+        assert_eq!(merged.metadata.batch_number, "BATCH-POOL-A");
+
+        let lineage = db.get_lineage("POOL-AB").unwrap();
+        assert_eq!(lineage.parent_ids(), vec!["POOL-A", "POOL-B"]);
+        assert!(lineage.parents.iter().all(|l| l.relation == LineageRelation::Merge));
+        assert_eq!(db.get_sample("POOL-A").unwrap().unwrap().status, SampleStatus::Consumed);
+
+        assert!(db.merge_samples(&["POOL-A"], "POOL-X").is_err());
+        assert!(db.merge_samples(&["POOL-A", "MISSING"], "POOL-X").is_err());
+        assert!(db.get_sample("POOL-X").unwrap().is_none());
+
+        // Deleting a sample removes its lineage links
+        assert!(db.delete_sample("POOL-AB").unwrap());
+        assert!(db.get_lineage("POOL-A").unwrap().children.is_empty());
+    }
+
+    /// Hook recording every transition it sees
+    #[derive(Default)]
+    struct CapturingHook(std::sync::Mutex<Vec<(String, SampleStatus, SampleStatus)>>);
+
+    impl TransitionHook for CapturingHook {
+        fn name(&self) -> &str {
+            "capturing"
+        }
+
+        fn on_transition(&self, sample: &Sample, from: SampleStatus, to: SampleStatus) -> Result<()> {
+            self.0.lock().unwrap().push((sample.sample_id.clone(), from, to));
+            Ok(())
+        }
     }
 
     #[test]
-    fn test_database_creation() {
-        let db = Database::in_memory().unwrap();
-        assert!(db.get_statistics().is_ok());
+    fn test_transition_hooks_observe_status_changes() {
+        let mut db = Database::in_memory().unwrap();
+        let hook = Arc::new(CapturingHook::default());
+        db.register_transition_hook(hook.clone());
+
+        let mut sample = create_test_sample("HOOK-001");
+        sample.update_status(SampleStatus::Stored).unwrap();
+        db.store_sample(&sample).unwrap();
+        db.change_status(&mut sample, SampleStatus::InUse, Some("Lab 2".to_string()), None).unwrap();
+        // Re-applying the same status is not a transition
+        db.change_status(&mut sample, SampleStatus::InUse, None, None).unwrap();
+        assert_eq!(db.get_sample("HOOK-001").unwrap().unwrap().location.as_deref(), Some("Lab 2"));
+
+        db.store_sample(&create_test_sample("HOOK-002")).unwrap();
+        db.merge_samples(&["HOOK-001", "HOOK-002"], "HOOK-POOL").unwrap();
+
+        assert_eq!(*hook.0.lock().unwrap(), vec![
+            ("HOOK-001".to_string(), SampleStatus::Stored, SampleStatus::InUse),
+            ("HOOK-001".to_string(), SampleStatus::InUse, SampleStatus::Consumed),
+            ("HOOK-002".to_string(), SampleStatus::InProduction, SampleStatus::Consumed),
+        ]);
     }
 
     #[test]
@@ -539,6 +3484,30 @@ mod tests {
         assert_eq!(all.len(), 2);
     }
 
+    #[test]
+    fn test_stream_samples_visits_rows_in_order_and_stops_on_error() {
+        let db = Database::in_memory().unwrap();
+        for id in ["TEST-S2", "TEST-S1", "TEST-S3"] {
+            db.store_sample(&create_test_sample(id)).unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let count = db.stream_samples(|sample| {
+            seen.push(sample.sample_id.clone());
+            Ok(())
+        }).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(seen, vec!["TEST-S1", "TEST-S2", "TEST-S3"]);
+
+        let mut visited = 0;
+        let result = db.stream_samples(|_| {
+            visited += 1;
+            Err(SampleGuardError::InvalidSampleData("stop".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(visited, 1);
+    }
+
     #[test]
     fn test_get_samples_by_batch() {
         let db = Database::in_memory().unwrap();
@@ -546,18 +3515,18 @@ mod tests {
         db.store_sample(&sample1).unwrap();
         
         let batch_samples = db.get_samples_by_batch(&sample1.metadata.batch_number).unwrap();
-        assert!(batch_samples.len() > 0);
+        assert!(!batch_samples.is_empty());
     }
 
     #[test]
     fn test_get_samples_by_status() {
         let db = Database::in_memory().unwrap();
         let mut sample = create_test_sample("TEST-006");
-        sample.update_status(SampleStatus::InTransit);
+        sample.update_status(SampleStatus::InTransit).unwrap();
         db.store_sample(&sample).unwrap();
         
         let transit_samples = db.get_samples_by_status(SampleStatus::InTransit).unwrap();
-        assert!(transit_samples.len() > 0);
+        assert!(!transit_samples.is_empty());
     }
 
     #[test]
@@ -587,7 +3556,101 @@ mod tests {
         let sample = create_test_sample("TEST-008");
         db.store_sample(&sample).unwrap();
         // Now we can add history entry
-        assert!(db.add_history_entry("TEST-008", &SampleStatus::InProduction, None).is_ok());
+        assert!(db.add_history_entry("TEST-008", &SampleStatus::InProduction, None, None).is_ok());
+    }
+
+    #[test]
+    fn test_history_depth_rolls_up_oldest_entries() {
+        let db = Database::in_memory().unwrap().with_max_history_depth(4);
+        let sample = create_test_sample("TEST-ROLLUP");
+        db.store_sample(&sample).unwrap();
+        let statuses = [
+            SampleStatus::InTransit,
+            SampleStatus::Stored,
+            SampleStatus::InUse,
+            SampleStatus::Stored,
+            SampleStatus::InTransit,
+            SampleStatus::Stored,
+        ];
+        for status in &statuses {
+            db.add_history_entry("TEST-ROLLUP", status, Some("Freezer A"), None).unwrap();
+        }
+
+        // 7 entries with a depth of 4: the oldest 4 become one rollup row
+        let history = db.get_sample_history("TEST-ROLLUP").unwrap();
+        assert_eq!(history.len(), 4);
+        let recent: Vec<_> = history[..3].iter().map(|e| e.status).collect();
+        assert_eq!(recent, vec![SampleStatus::Stored, SampleStatus::InTransit, SampleStatus::Stored]);
+        assert!(history[..3].iter().all(|e| !e.is_rollup()));
+
+        let rollup = &history[3];
+        let summary = rollup.rollup.as_ref().unwrap();
+        assert_eq!(summary.count, 4);
+        assert_eq!(rollup.status, SampleStatus::InUse);
+        assert!(summary.first <= summary.last);
+        assert!(summary.summary().starts_with("4 status changes between "));
+
+        // Further changes fold into the same rollup
+        db.add_history_entry("TEST-ROLLUP", &SampleStatus::InUse, None, None).unwrap();
+        let history = db.get_sample_history("TEST-ROLLUP").unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[3].rollup.as_ref().unwrap().count, 5);
+        assert_eq!(history.iter().filter(|e| e.is_rollup()).count(), 1);
+    }
+
+    #[test]
+    fn test_sample_temperature_log_and_time_out_of_range() {
+        let db = Database::in_memory().unwrap();
+        let mut sample = create_test_sample("TEMP-LOG-1");
+        sample.location = Some("Fridge-1".to_string());
+        db.store_sample(&sample).unwrap();
+        let mut elsewhere = create_test_sample("TEMP-LOG-2");
+        elsewhere.location = Some("Fridge-2".to_string());
+        db.store_sample(&elsewhere).unwrap();
+
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let reading = |minutes: i64, temperature: f32| TemperatureReading {
+            temperature,
+            timestamp: start + chrono::Duration::minutes(minutes),
+            sensor_id: "SENSOR-1".to_string(),
+            location: Some("Fridge-1".to_string()),
+        };
+        // The sample's own 2-8°C range applies; 9.5°C is out of range for 10 minutes
+        for (minutes, temperature) in [(0, 4.0), (10, 9.5), (20, 5.0)] {
+            assert_eq!(db.log_sample_temperature(&reading(minutes, temperature), (0.0, 20.0)).unwrap(), vec!["TEMP-LOG-1"]);
+        }
+
+        let log = db.get_sample_temperature_log("TEMP-LOG-1", None).unwrap();
+        assert_eq!(log.iter().map(|r| r.temperature).collect::<Vec<_>>(), vec![4.0, 9.5, 5.0]);
+        assert!(db.get_sample_temperature_log("TEMP-LOG-2", None).unwrap().is_empty());
+        let since = db.get_sample_temperature_log("TEMP-LOG-1", Some(start + chrono::Duration::minutes(5))).unwrap();
+        assert_eq!(since.len(), 2);
+
+        let summary = db.get_sample_temperature_summary("TEMP-LOG-1", None, Utc::now()).unwrap();
+        assert_eq!(summary.readings, 3);
+        assert_eq!(summary.out_of_range_readings, 1);
+        assert_eq!(summary.time_out_of_range_secs, 600);
+    }
+
+    #[test]
+    fn test_inventory_snapshot_roundtrip() {
+        let db = Database::in_memory().unwrap();
+        let snapshot = InventorySnapshot {
+            taken_at: Utc::now(),
+            tags: vec![crate::inventory::TagScanResult {
+                epc: "E-1".to_string(),
+                tag_id: "T-1".to_string(),
+                rssi: -55,
+                antenna: 2,
+                timestamp: Utc::now(),
+            }],
+        };
+
+        let id = db.save_inventory_snapshot(&snapshot).unwrap();
+        let stored = db.get_inventory_snapshot(id).unwrap().unwrap();
+        assert_eq!(stored.tags.len(), 1);
+        assert_eq!(stored.tags[0].epc, "E-1");
+        assert!(db.get_inventory_snapshot(id + 1).unwrap().is_none());
     }
 
     #[test]
@@ -597,7 +3660,7 @@ mod tests {
         db.store_sample(&sample).unwrap();
         
         let mut sample2 = sample.clone();
-        sample2.update_status(SampleStatus::InTransit);
+        sample2.update_status(SampleStatus::InTransit).unwrap();
         db.store_sample(&sample2).unwrap();
         
         let history = db.get_sample_history("TEST-009").unwrap();
@@ -625,13 +3688,93 @@ mod tests {
         
         // Store again - should replace
         let mut sample2 = sample.clone();
-        sample2.update_status(SampleStatus::InTransit);
+        sample2.update_status(SampleStatus::InTransit).unwrap();
         db.store_sample(&sample2).unwrap();
         
         let retrieved = db.get_sample("TEST-012").unwrap().unwrap();
         assert_eq!(retrieved.status, SampleStatus::InTransit);
     }
 
+    fn seed_for_import(db: &Database) -> Sample {
+        let existing = create_test_sample("IMP-001");
+        db.store_sample(&existing).unwrap();
+        existing
+    }
+
+    fn import_file(existing: &Sample) -> String {
+        let mut changed = existing.clone();
+        changed.update_location("Cold Room 4".to_string());
+        [create_test_sample("IMP-000"), changed, create_test_sample("IMP-002")]
+            .iter()
+            .map(|s| serde_json::to_string(s).unwrap() + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn test_import_conflict_overwrite() {
+        let db = Database::in_memory().unwrap();
+        let existing = seed_for_import(&db);
+
+        let report = db.import_jsonl(import_file(&existing).as_bytes(), ConflictStrategy::Overwrite).unwrap();
+        assert_eq!((report.inserted(), report.updated(), report.skipped()), (2, 1, 0));
+        assert_eq!(report.records[1].outcome, ImportOutcome::Updated);
+        assert_eq!(db.get_all_samples().unwrap().len(), 3);
+        assert_eq!(db.get_sample("IMP-001").unwrap().unwrap().location.as_deref(), Some("Cold Room 4"));
+    }
+
+    #[test]
+    fn test_import_overwrite_rejects_invalid_transitions() {
+        let db = Database::in_memory().unwrap();
+        let mut existing = seed_for_import(&db);
+        let mut consumed = existing.clone();
+        consumed.update_status(SampleStatus::Consumed).unwrap();
+        db.store_sample(&consumed).unwrap();
+
+        // The file still has the sample in production
+        existing.update_location("Cold Room 4".to_string());
+        let report = db.import_samples(&[existing], ConflictStrategy::Overwrite).unwrap();
+        assert!(matches!(&report.records[0].outcome, ImportOutcome::Failed(reason) if reason.contains("transition")));
+        assert_eq!(db.get_sample("IMP-001").unwrap().unwrap().status, SampleStatus::Consumed);
+    }
+
+    #[test]
+    fn test_import_conflict_skip() {
+        let db = Database::in_memory().unwrap();
+        let existing = seed_for_import(&db);
+
+        let report = db.import_jsonl(import_file(&existing).as_bytes(), ConflictStrategy::Skip).unwrap();
+        assert_eq!((report.inserted(), report.updated(), report.skipped()), (2, 0, 1));
+        assert_eq!(report.records[1].sample_id.as_deref(), Some("IMP-001"));
+        assert_eq!(db.get_all_samples().unwrap().len(), 3);
+        assert_eq!(db.get_sample("IMP-001").unwrap().unwrap().location.as_deref(), Some("Test Location"));
+    }
+
+    #[test]
+    fn test_import_conflict_fail_rolls_back() {
+        let db = Database::in_memory().unwrap();
+        let existing = seed_for_import(&db);
+
+        let report = db.import_jsonl(import_file(&existing).as_bytes(), ConflictStrategy::Fail).unwrap();
+        assert!(report.rolled_back);
+        assert_eq!(report.records.len(), 2);
+        assert_eq!(report.records[1].outcome, ImportOutcome::Failed("sample already exists".to_string()));
+        // IMP-000 was inserted before the conflict but rolled back
+        assert!(db.get_sample("IMP-000").unwrap().is_none());
+        assert_eq!(db.get_all_samples().unwrap().len(), 1);
+        assert_eq!(db.get_sample("IMP-001").unwrap().unwrap().location.as_deref(), Some("Test Location"));
+    }
+
+    #[test]
+    fn test_import_reports_malformed_records() {
+        let db = Database::in_memory().unwrap();
+        let input = format!("{{not json}}\n\n{}\n", serde_json::to_string(&create_test_sample("IMP-003")).unwrap());
+
+        let report = db.import_jsonl(input.as_bytes(), ConflictStrategy::Fail).unwrap();
+        assert_eq!(report.failed(), 1);
+        assert_eq!(report.inserted(), 1);
+        assert_eq!(report.records[1].line, 3);
+    }
+
     #[test]
     fn test_sample_with_no_expiry() {
         let metadata = SampleMetadata {
@@ -714,9 +3857,9 @@ mod tests {
         let mut sample = create_test_sample("TEST-017");
         
         db.store_sample(&sample).unwrap();
-        sample.update_status(SampleStatus::InTransit);
+        sample.update_status(SampleStatus::InTransit).unwrap();
         db.store_sample(&sample).unwrap();
-        sample.update_status(SampleStatus::Stored);
+        sample.update_status(SampleStatus::Stored).unwrap();
         db.store_sample(&sample).unwrap();
         
         let history = db.get_sample_history("TEST-017").unwrap();
@@ -729,5 +3872,593 @@ mod tests {
         let stats = db.get_statistics().unwrap();
         assert_eq!(stats.total_samples, 0);
     }
-}
 
+    #[test]
+    fn test_prune_history_keeps_latest_entry() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&create_test_sample("TEST-PRUNE-1")).unwrap();
+        db.store_sample(&create_test_sample("TEST-PRUNE-2")).unwrap();
+        
+        let old = Utc::now() - chrono::Duration::days(400);
+        for days in [0, 1, 2] {
+            db.conn.execute(
+                "INSERT INTO sample_history (sample_id, status, location, timestamp) VALUES (?1, ?2, NULL, ?3)",
+                params!["TEST-PRUNE-1", "Stored", (old + chrono::Duration::days(days)).to_rfc3339()],
+            ).unwrap();
+        }
+        // The only entry for TEST-PRUNE-2 is old but must survive
+        db.conn.execute(
+            "UPDATE sample_history SET timestamp = ?1 WHERE sample_id = 'TEST-PRUNE-2'",
+            params![old.to_rfc3339()],
+        ).unwrap();
+        
+        let removed = db.prune_history_before(Utc::now() - chrono::Duration::days(365)).unwrap();
+        assert_eq!(removed, 3);
+        assert_eq!(db.get_sample_history("TEST-PRUNE-1").unwrap().len(), 1);
+        assert_eq!(db.get_sample_history("TEST-PRUNE-2").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_violation_links_colocated_samples() {
+        use crate::temperature::TemperatureReading;
+        
+        let db = Database::in_memory().unwrap();
+        for (id, location) in [("EXC-001", "Warehouse-1"), ("EXC-002", "Warehouse-1"), ("EXC-003", "Warehouse-2")] {
+            let mut sample = create_test_sample(id);
+            sample.location = Some(location.to_string());
+            db.store_sample(&sample).unwrap();
+        }
+        
+        let violation = TemperatureViolation {
+            reading: TemperatureReading {
+                temperature: 12.0,
+                timestamp: Utc::now(),
+                sensor_id: "SENSOR-W1".to_string(),
+                location: Some("Warehouse-1".to_string()),
+            },
+            violation_type: ViolationType::TooHigh,
+            expected_range: (2.0, 8.0),
+            severity: ViolationSeverity::Warning,
+        };
+        
+        let mut affected = db.link_temperature_violation(&violation, true).unwrap();
+        affected.sort();
+        assert_eq!(affected, vec!["EXC-001", "EXC-002"]);
+        
+        let excursions = db.get_temperature_excursions("EXC-001").unwrap();
+        assert_eq!(excursions.len(), 1);
+        assert_eq!(excursions[0].violation_type, ViolationType::TooHigh);
+        assert_eq!(db.get_temperature_excursions("EXC-002").unwrap().len(), 1);
+        assert!(db.get_temperature_excursions("EXC-003").unwrap().is_empty());
+        
+        assert_eq!(db.get_sample("EXC-001").unwrap().unwrap().status, SampleStatus::Compromised);
+        assert_eq!(db.get_sample("EXC-003").unwrap().unwrap().status, SampleStatus::InProduction);
+    }
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let db = Database::in_memory().unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        assert_eq!(db.migrate().unwrap(), (SCHEMA_VERSION, SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn test_migrate_v1_file_preserves_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v1.db");
+        let sample = create_test_sample("V1-001");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(&format!("{} PRAGMA user_version = 1;", MIGRATIONS[0])).unwrap();
+            conn.execute(
+                "INSERT INTO samples (
+                    id, sample_id, status, batch_number, production_date, expiry_date,
+                    temperature_min, temperature_max, storage_conditions, manufacturer,
+                    product_line, created_at, last_updated, read_count, location, integrity_checksum
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+                params![
+                    sample.id.to_string(),
+                    sample.sample_id,
+                    format!("{:?}", sample.status),
+                    sample.metadata.batch_number,
+                    sample.metadata.production_date.to_rfc3339(),
+                    sample.metadata.expiry_date.map(|d| d.to_rfc3339()),
+                    sample.metadata.temperature_range.map(|r| r.0),
+                    sample.metadata.temperature_range.map(|r| r.1),
+                    sample.metadata.storage_conditions,
+                    sample.metadata.manufacturer,
+                    sample.metadata.product_line,
+                    sample.created_at.to_rfc3339(),
+                    sample.last_updated.to_rfc3339(),
+                    sample.read_count as i64,
+                    sample.location,
+                    hex::encode(sample.integrity_checksum),
+                ],
+            ).unwrap();
+        }
+
+        let db = Database::open_without_migrate(&path).unwrap();
+        assert_eq!(db.schema_version().unwrap(), 1);
+        assert_eq!(db.migrate_to_latest().unwrap(), SCHEMA_VERSION);
+
+        let migrated = db.get_sample("V1-001").unwrap().unwrap();
+        assert_eq!(migrated.id, sample.id);
+        assert_eq!(migrated.metadata, sample.metadata);
+        assert_eq!(migrated.container_id, None);
+        assert_eq!(migrated.notes, None);
+        assert!(migrated.verify_integrity());
+    }
+
+    #[test]
+    fn test_open_rejects_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("future.db");
+        Database::new(&path).unwrap().conn
+            .execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION + 1))
+            .unwrap();
+
+        match Database::new(&path) {
+            Err(SampleGuardError::DatabaseError { message, .. }) => assert!(message.contains("newer")),
+            other => panic!("expected schema version error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_store_sample_rolls_back_when_history_fails() {
+        let db = Database::in_memory().unwrap();
+        db.conn.execute_batch(
+            "CREATE TRIGGER reject_bad_location BEFORE INSERT ON sample_history
+             WHEN NEW.location = 'Nowhere'
+             BEGIN SELECT RAISE(ABORT, 'bad location'); END;",
+        ).unwrap();
+
+        let mut sample = create_test_sample("ATOMIC-001");
+        sample.location = Some("Nowhere".to_string());
+        assert!(db.store_sample(&sample).is_err());
+        assert!(db.get_sample("ATOMIC-001").unwrap().is_none());
+
+        // The failed store leaves no savepoint open behind it
+        sample.location = Some("Shelf B".to_string());
+        db.store_sample(&sample).unwrap();
+        assert!(db.get_sample("ATOMIC-001").unwrap().is_some());
+        assert_eq!(db.get_sample_history("ATOMIC-001").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_with_transaction_rolls_back_every_write() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&create_test_sample("TX-001")).unwrap();
+
+        let result: Result<()> = db.with_transaction(|tx| {
+            tx.add_history_entry("TX-001", &SampleStatus::InTransit, Some("Truck"), Some("courier"))?;
+            tx.record_custody_transfer(&custody("TX-001", None, "alice"))?;
+            assert!(tx.get_sample("TX-001")?.is_some());
+            Err(SampleGuardError::InvalidSampleData("abandon".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(db.get_sample_history("TX-001").unwrap().len(), 1);
+        assert!(db.get_custody_chain("TX-001").unwrap().is_empty());
+
+        db.with_transaction(|tx| tx.record_custody_transfer(&custody("TX-001", None, "alice"))).unwrap();
+        assert_eq!(db.get_custody_chain("TX-001").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_bulk_store_samples_is_all_or_nothing() {
+        let db = Database::in_memory().unwrap();
+        db.conn.execute_batch(
+            "CREATE TRIGGER reject_bad_location BEFORE INSERT ON sample_history
+             WHEN NEW.location = 'Nowhere'
+             BEGIN SELECT RAISE(ABORT, 'bad location'); END;",
+        ).unwrap();
+        let mut samples: Vec<Sample> = (0..5).map(|i| create_test_sample(&format!("BULK-{}", i))).collect();
+        samples[3].location = Some("Nowhere".to_string());
+
+        assert!(db.bulk_store_samples(&samples).is_err());
+        assert_eq!(db.count_samples().unwrap(), 0);
+        assert!(db.get_sample_history("BULK-0").unwrap().is_empty());
+
+        samples[3].location = None;
+        assert_eq!(db.bulk_store_samples(&samples).unwrap(), 5);
+        assert_eq!(db.count_samples().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_container_and_notes_round_trip() {
+        let db = Database::in_memory().unwrap();
+        let mut sample = create_test_sample("CONTAINER-001");
+        sample.update_container(Some("BOX-7".to_string()));
+        sample.update_notes(Some("Handle with care".to_string()));
+        db.store_sample(&sample).unwrap();
+
+        let retrieved = db.get_sample("CONTAINER-001").unwrap().unwrap();
+        assert_eq!(retrieved.container_id.as_deref(), Some("BOX-7"));
+        assert_eq!(retrieved.notes.as_deref(), Some("Handle with care"));
+    }
+
+    #[test]
+    fn test_verify_samples_flags_corrupt_checksum() {
+        let db = Database::in_memory().unwrap();
+        for i in 0..5 {
+            db.store_sample(&create_test_sample(&format!("VERIFY-{:03}", i))).unwrap();
+        }
+        db.conn.execute(
+            "UPDATE samples SET integrity_checksum = ?1 WHERE sample_id = 'VERIFY-003'",
+            params![hex::encode([0u8; 32])],
+        ).unwrap();
+        
+        let report = db.verify_samples(2).unwrap();
+        assert_eq!(report.checked, 5);
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(report.corrupt[0].sample_id, "VERIFY-003");
+    }
+
+    #[test]
+    fn test_upgrade_legacy_checksums() {
+        let db = Database::in_memory().unwrap();
+        for i in 0..3 {
+            let mut sample = create_test_sample(&format!("LEGACY-{:03}", i));
+            if i > 0 {
+                sample.integrity_checksum = Sample::calculate_checksum_with(
+                    ChecksumVersion::Legacy,
+                    &sample.sample_id,
+                    &sample.metadata,
+                    &sample.last_updated,
+                );
+            }
+            db.store_sample(&sample).unwrap();
+        }
+
+        let report = db.verify_samples(10).unwrap();
+        assert_eq!(report.legacy_checksums, 2);
+        assert_eq!(report.corrupt.len(), 2);
+        assert!(report.corrupt[0].reason.contains("legacy checksum"));
+
+        let lenient = db.with_allow_legacy_checksums(true);
+        let report = lenient.verify_samples(10).unwrap();
+        assert!(report.corrupt.is_empty());
+        assert_eq!(report.legacy_checksums, 2);
+
+        assert_eq!(lenient.upgrade_checksums().unwrap(), 2);
+        assert_eq!(lenient.upgrade_checksums().unwrap(), 0);
+        let report = lenient.with_allow_legacy_checksums(false).verify_samples(10).unwrap();
+        assert!(report.corrupt.is_empty());
+        assert_eq!(report.legacy_checksums, 0);
+    }
+
+    #[test]
+    fn test_colliding_legacy_record_rejected_after_upgrade() {
+        let db = Database::in_memory().unwrap();
+        let mut original = create_test_sample("AB");
+        original.metadata.batch_number = "C".to_string();
+        original.integrity_checksum = Sample::calculate_checksum_with(
+            ChecksumVersion::Legacy,
+            &original.sample_id,
+            &original.metadata,
+            &original.last_updated,
+        );
+        db.store_sample(&original).unwrap();
+        assert_eq!(db.upgrade_checksums().unwrap(), 1);
+
+        // The legacy layout hashes "AB" + "C" and "A" + "BC" alike, so the
+        // old checksum also covers a record with the fields shifted
+        let mut forged = original.clone();
+        forged.sample_id = "A".to_string();
+        forged.metadata.batch_number = "BC".to_string();
+        assert_eq!(forged.checksum_version(), Some(ChecksumVersion::Legacy));
+        assert!(!forged.verify_integrity());
+
+        let report = db.import_samples(std::slice::from_ref(&forged), ConflictStrategy::Skip).unwrap();
+        assert_eq!(report.failed(), 1);
+        let batch = db.store_samples_batch(std::slice::from_ref(&forged), false).unwrap();
+        assert_eq!(batch.items[0].error.as_deref(), Some("integrity checksum mismatch"));
+        assert!(db.get_sample("A").unwrap().is_none());
+        assert!(db.verify_samples(10).unwrap().corrupt.is_empty());
+    }
+
+    #[test]
+    fn test_short_checksum_is_an_error_not_a_panic() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&create_test_sample("SHORT-SUM")).unwrap();
+        db.conn.execute(
+            "UPDATE samples SET integrity_checksum = ?1 WHERE sample_id = 'SHORT-SUM'",
+            params![hex::encode([0xabu8; 16])],
+        ).unwrap();
+
+        let err = db.get_sample("SHORT-SUM").unwrap_err();
+        assert!(err.to_string().contains("must be 32 bytes, got 16"), "{}", err);
+        let report = db.verify_samples(10).unwrap();
+        assert_eq!(report.corrupt.len(), 1);
+        assert!(report.corrupt[0].reason.starts_with("unreadable row"));
+    }
+
+    #[test]
+    fn test_samples_paged() {
+        let db = Database::in_memory().unwrap();
+        for i in 0..5 {
+            let mut sample = create_test_sample(&format!("PAGE-{}", i));
+            if i % 2 == 0 {
+                sample.update_status(SampleStatus::Stored).unwrap();
+            }
+            db.store_sample(&sample).unwrap();
+        }
+
+        let ids = |page: &Page<Sample>| page.items.iter().map(|s| s.sample_id.clone()).collect::<Vec<_>>();
+        let first = db.get_samples_paged(0, 2, SortOrder::SampleId).unwrap();
+        assert_eq!(ids(&first), vec!["PAGE-0", "PAGE-1"]);
+        assert_eq!(first.total_count, 5);
+        assert!(first.has_more);
+
+        let last = db.get_samples_paged(4, 2, SortOrder::SampleId).unwrap();
+        assert_eq!(ids(&last), vec!["PAGE-4"]);
+        assert!(!last.has_more);
+
+        let beyond = db.get_samples_paged(50, 2, SortOrder::SampleId).unwrap();
+        assert!(beyond.items.is_empty());
+        assert_eq!(beyond.total_count, 5);
+        assert!(!beyond.has_more);
+
+        let newest = db.get_samples_paged(0, 1, SortOrder::CreatedAt).unwrap();
+        assert_eq!(ids(&newest), vec!["PAGE-4"]);
+
+        let stored = db.get_samples_by_status_paged(SampleStatus::Stored, 1, 10, SortOrder::SampleId).unwrap();
+        assert_eq!(ids(&stored), vec!["PAGE-2", "PAGE-4"]);
+        assert_eq!(stored.total_count, 3);
+
+        let batch = db.get_samples_by_batch_paged("BATCH-PAGE-3", 0, 10, SortOrder::default()).unwrap();
+        assert_eq!(ids(&batch), vec!["PAGE-3"]);
+    }
+
+    #[test]
+    fn test_samples_paginated_boundaries() {
+        let db = Database::in_memory().unwrap();
+        for i in 0..5 {
+            db.store_sample(&create_test_sample(&format!("NUMBERED-{}", i))).unwrap();
+        }
+        let ids = |page: &SamplesPage| page.samples.iter().map(|s| s.sample_id.clone()).collect::<Vec<_>>();
+
+        let first = db.get_samples_paginated(1, 2, SortOrder::BatchNumber).unwrap();
+        assert_eq!(ids(&first), vec!["NUMBERED-0", "NUMBERED-1"]);
+        assert_eq!((first.total_count, first.page, first.page_size, first.total_pages), (5, 1, 2, 3));
+
+        let last = db.get_samples_paginated(3, 2, SortOrder::BatchNumber).unwrap();
+        assert_eq!(ids(&last), vec!["NUMBERED-4"]);
+
+        let beyond = db.get_samples_paginated(4, 2, SortOrder::BatchNumber).unwrap();
+        assert!(beyond.samples.is_empty());
+        assert_eq!((beyond.total_count, beyond.total_pages), (5, 3));
+
+        assert!(db.get_samples_paginated(0, 2, SortOrder::default()).is_err());
+        assert!(db.get_samples_paginated(1, 0, SortOrder::default()).is_err());
+    }
+
+    #[test]
+    fn test_all_samples_limit_offset() {
+        let db = Database::in_memory().unwrap();
+        assert_eq!(db.count_samples().unwrap(), 0);
+        assert!(db.get_samples_paged(0, 10, SortOrder::CreatedAt).unwrap().items.is_empty());
+
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        for i in 0..4 {
+            let mut sample = create_test_sample(&format!("OFFSET-{}", i));
+            sample.created_at = base + chrono::Duration::hours(i);
+            db.store_sample(&sample).unwrap();
+        }
+        assert_eq!(db.count_samples().unwrap(), 4);
+
+        let ids = |offset, limit| -> Vec<String> {
+            db.get_samples_paged(offset, limit, SortOrder::CreatedAt).unwrap()
+                .items
+                .into_iter()
+                .map(|s| s.sample_id)
+                .collect()
+        };
+        assert_eq!(ids(0, 2), vec!["OFFSET-3", "OFFSET-2"]);
+        assert_eq!(ids(1, 2), vec!["OFFSET-2", "OFFSET-1"]);
+        assert_eq!(ids(3, 2), vec!["OFFSET-0"]);
+        assert!(ids(4, 2).is_empty());
+        assert!(ids(usize::MAX, 2).is_empty());
+        assert!(ids(0, 0).is_empty());
+        assert_eq!(ids(0, usize::MAX).len(), 4);
+    }
+
+    fn store_expiring(db: &Database, sample_id: &str, expiry: DateTime<Utc>) {
+        let mut sample = create_test_sample(sample_id);
+        sample.metadata.expiry_date = Some(expiry);
+        db.store_sample(&sample).unwrap();
+    }
+
+    #[test]
+    fn test_samples_expiring_within_includes_cutoff_midnight() {
+        let db = Database::in_memory().unwrap();
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 14, 30, 0).unwrap();
+        store_expiring(&db, "EXP-PAST", now - chrono::Duration::hours(1));
+        store_expiring(&db, "EXP-SOON", now + chrono::Duration::days(1));
+        store_expiring(&db, "EXP-CUTOFF-START", Utc.with_ymd_and_hms(2026, 3, 17, 0, 0, 0).unwrap());
+        store_expiring(&db, "EXP-CUTOFF-END", Utc.with_ymd_and_hms(2026, 3, 18, 0, 0, 0).unwrap());
+        store_expiring(&db, "EXP-LATER", Utc.with_ymd_and_hms(2026, 3, 18, 0, 0, 1).unwrap());
+
+        let ids: Vec<_> = db.samples_expiring_within_at(7, now).unwrap().into_iter().map(|s| s.sample_id).collect();
+        assert_eq!(ids, vec!["EXP-SOON", "EXP-CUTOFF-START", "EXP-CUTOFF-END"]);
+    }
+
+    #[test]
+    fn test_get_expired_samples() {
+        let db = Database::in_memory().unwrap();
+        store_expiring(&db, "EXP-OLD", Utc::now() - chrono::Duration::days(3));
+        store_expiring(&db, "EXP-RECENT", Utc::now() - chrono::Duration::minutes(1));
+        store_expiring(&db, "EXP-FUTURE", Utc::now() + chrono::Duration::days(3));
+
+        let expired: Vec<_> = db.get_expired_samples().unwrap().into_iter().map(|s| s.sample_id).collect();
+        assert_eq!(expired, vec!["EXP-OLD", "EXP-RECENT"]);
+        assert_eq!(db.get_samples_expiring_within(7).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_samples_expiring_before_skips_samples_without_expiry() {
+        let db = Database::in_memory().unwrap();
+        let cutoff = Utc.with_ymd_and_hms(2026, 3, 10, 0, 0, 0).unwrap();
+        store_expiring(&db, "EXP-BEFORE", cutoff - chrono::Duration::days(30));
+        store_expiring(&db, "EXP-AT", cutoff);
+        store_expiring(&db, "EXP-AFTER", cutoff + chrono::Duration::seconds(1));
+        let mut no_expiry = create_test_sample("EXP-NONE");
+        no_expiry.metadata.expiry_date = None;
+        db.store_sample(&no_expiry).unwrap();
+
+        let ids: Vec<_> = db.get_samples_expiring_before(cutoff).unwrap().into_iter().map(|s| s.sample_id).collect();
+        assert_eq!(ids, vec!["EXP-BEFORE", "EXP-AT"]);
+        let far_future = Utc.with_ymd_and_hms(9999, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(db.get_samples_expiring_before(far_future).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_samples_by_production_range() {
+        let db = Database::in_memory().unwrap();
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2026, 1, 31, 0, 0, 0).unwrap();
+        for (id, produced) in [
+            ("PROD-EARLY", start - chrono::Duration::seconds(1)),
+            ("PROD-END", end),
+            ("PROD-START", start),
+            ("PROD-MID", start + chrono::Duration::days(10)),
+            ("PROD-LATE", end + chrono::Duration::seconds(1)),
+        ] {
+            let mut sample = create_test_sample(id);
+            sample.metadata.production_date = produced;
+            db.store_sample(&sample).unwrap();
+        }
+
+        let ids: Vec<_> = db.get_samples_by_production_range(start, end).unwrap().into_iter().map(|s| s.sample_id).collect();
+        assert_eq!(ids, vec!["PROD-START", "PROD-MID", "PROD-END"]);
+        assert!(db.get_samples_by_production_range(end, start).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_database_errors_carry_their_kind() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&create_test_sample("KIND-001")).unwrap();
+
+        // History rows must reference a stored sample
+        let err = db.add_history_entry("MISSING", &SampleStatus::Stored, None, None).unwrap_err();
+        assert_eq!(err.database_kind(), Some(DatabaseErrorKind::ConstraintViolation), "{}", err);
+
+        db.conn.execute("UPDATE samples SET created_at = 'yesterday' WHERE sample_id = 'KIND-001'", []).unwrap();
+        let err = db.get_sample("KIND-001").unwrap_err();
+        assert_eq!(err.database_kind(), Some(DatabaseErrorKind::Corrupt), "{}", err);
+        match err {
+            SampleGuardError::DatabaseError { column, value, .. } => {
+                assert_eq!(column.as_deref(), Some("created_at"));
+                assert_eq!(value.as_deref(), Some("yesterday"));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+
+        let err = db.add_history_entry("MISSING", &SampleStatus::Stored, None, None).unwrap_err();
+        assert!(matches!(err, SampleGuardError::DatabaseError { column: None, value: None, .. }));
+    }
+
+    #[test]
+    fn test_garbage_timestamp_is_an_error_not_a_panic() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&create_test_sample("BAD-DATE")).unwrap();
+        db.store_sample(&create_test_sample("GOOD-DATE")).unwrap();
+        db.conn.execute("UPDATE samples SET production_date = 'last tuesday' WHERE sample_id = 'BAD-DATE'", []).unwrap();
+        db.conn.execute("UPDATE sample_history SET timestamp = 'soon' WHERE sample_id = 'BAD-DATE'", []).unwrap();
+
+        let err = db.get_sample("BAD-DATE").unwrap_err().to_string();
+        assert!(err.contains("column production_date of sample 'BAD-DATE'"), "{}", err);
+        assert!(err.contains("'last tuesday'"), "{}", err);
+        assert!(db.get_sample_history("BAD-DATE").unwrap_err().to_string().contains("column timestamp"));
+
+        assert!(db.get_sample("GOOD-DATE").unwrap().is_some());
+        assert_eq!(db.get_sample_history("GOOD-DATE").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_find_corrupt_rows_scans_past_bad_rows() {
+        let db = Database::in_memory().unwrap();
+        for id in ["ROW-1", "ROW-2", "ROW-3", "ROW-4"] {
+            db.store_sample(&create_test_sample(id)).unwrap();
+        }
+        db.conn.execute("UPDATE samples SET last_updated = '2024-13-45' WHERE sample_id = 'ROW-1'", []).unwrap();
+        db.conn.execute("UPDATE samples SET integrity_checksum = 'not hex at all' WHERE sample_id = 'ROW-3'", []).unwrap();
+
+        assert!(db.get_all_samples().is_err());
+        assert!(db.get_sample("ROW-3").unwrap_err().to_string().contains("column integrity_checksum of sample 'ROW-3'"));
+
+        let corrupt = db.find_corrupt_rows().unwrap();
+        let ids: Vec<&str> = corrupt.iter().map(|c| c.sample_id.as_str()).collect();
+        assert_eq!(ids, vec!["ROW-1", "ROW-3"]);
+        assert!(corrupt[0].reason.contains("'2024-13-45'"), "{}", corrupt[0].reason);
+        assert!(corrupt[1].reason.contains("column integrity_checksum"), "{}", corrupt[1].reason);
+
+        // Batches of one, so each corrupt row is its own batch
+        let report = db.verify_samples(1).unwrap();
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.corrupt.len(), 2);
+    }
+
+    #[test]
+    fn test_archive_and_restore_sample() {
+        let db = Database::in_memory().unwrap();
+        let mut sample = create_test_sample("COLD-001");
+        db.store_sample(&sample).unwrap();
+        sample.update_status(SampleStatus::Consumed).unwrap();
+        db.store_sample(&sample).unwrap();
+        db.store_sample(&create_test_sample("HOT-001")).unwrap();
+        db.add_label("COLD-001", "priority").unwrap();
+        let history = db.get_sample_history("COLD-001").unwrap();
+
+        let archived = db.archive_sample("COLD-001").unwrap().unwrap();
+        assert_eq!(archived.status, SampleStatus::Consumed);
+        assert!(db.get_sample("COLD-001").unwrap().is_none());
+        assert!(db.get_sample_history("COLD-001").unwrap().is_empty());
+        let hot: Vec<String> = db.get_all_samples().unwrap().into_iter().map(|s| s.sample_id).collect();
+        assert_eq!(hot, vec!["HOT-001"]);
+        let listed = db.list_archived_samples().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].batch_number, "BATCH-COLD-001");
+        assert!(db.archive_sample("COLD-001").unwrap().is_none());
+
+        let restored = db.restore_sample("COLD-001").unwrap().unwrap();
+        assert_eq!(serde_json::to_vec(&restored).unwrap(), serde_json::to_vec(&sample).unwrap());
+        let stored = db.get_sample("COLD-001").unwrap().unwrap();
+        assert_eq!(serde_json::to_vec(&stored).unwrap(), serde_json::to_vec(&sample).unwrap());
+        assert_eq!(
+            serde_json::to_string(&db.get_sample_history("COLD-001").unwrap()).unwrap(),
+            serde_json::to_string(&history).unwrap()
+        );
+        assert_eq!(db.get_labels("COLD-001").unwrap(), vec!["priority"]);
+        assert!(db.list_archived_samples().unwrap().is_empty());
+        assert!(db.restore_sample("COLD-001").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_restore_keeps_lineage_to_hot_samples() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&create_test_sample("POOL-A")).unwrap();
+        db.store_sample(&create_test_sample("POOL-B")).unwrap();
+        db.merge_samples(&["POOL-A", "POOL-B"], "POOL-AB").unwrap();
+
+        db.archive_sample("POOL-A").unwrap().unwrap();
+        assert_eq!(db.get_lineage("POOL-AB").unwrap().parent_ids(), vec!["POOL-B"]);
+
+        db.restore_sample("POOL-A").unwrap().unwrap();
+        let mut parents = db.get_lineage("POOL-AB").unwrap().parent_ids().into_iter().map(str::to_string).collect::<Vec<_>>();
+        parents.sort();
+        assert_eq!(parents, vec!["POOL-A", "POOL-B"]);
+    }
+
+    #[test]
+    fn test_dates_outside_four_digit_years_roundtrip() {
+        let db = Database::in_memory().unwrap();
+        let mut sample = create_test_sample("FAR-DATES");
+        sample.metadata.production_date = Utc.with_ymd_and_hms(-1, 6, 1, 0, 0, 0).unwrap();
+        sample.metadata.expiry_date = Some(Utc.with_ymd_and_hms(10000, 1, 1, 0, 0, 0).unwrap());
+        db.store_sample(&sample).unwrap();
+
+        let stored = db.get_sample("FAR-DATES").unwrap().unwrap();
+        assert_eq!(stored.metadata.production_date, sample.metadata.production_date);
+        assert_eq!(stored.metadata.expiry_date, sample.metadata.expiry_date);
+    }
+}
@@ -1,19 +1,55 @@
+use std::collections::BTreeMap;
 use aes::Aes256;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
 use cbc::{cipher::BlockEncryptMut, Decryptor, Encryptor};
 use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+use hkdf::Hkdf;
 use sha2::{Digest, Sha256};
 use rand::RngCore;
 use crate::error::{SampleGuardError, Result};
 
-/// Secure encryption module for RFID tag data
-/// Implements AES-256-CBC encryption for medical device security compliance
+/// Bytes of the random nonce leading a GCM ciphertext
+const GCM_NONCE_LEN: usize = 12;
+/// Bytes of the authentication tag ending a GCM ciphertext
+const GCM_TAG_LEN: usize = 16;
+/// HKDF salt separating per-tag keys from other uses of the key
+const TAG_KEY_SALT: &[u8] = b"SampleGuard per-tag key v1";
+
+/// Cipher mode of an [`RFIDEncryption`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EncryptionMode {
+    /// AES-256-CBC with PKCS7 padding; confidential but not tamper-evident
+    #[default]
+    Cbc,
+    /// AES-256-GCM; decryption fails if the ciphertext was altered
+    Gcm,
+}
 
+/// Secure encryption module for RFID tag data
+/// Implements AES-256-CBC or AES-256-GCM encryption for medical device security compliance
+#[derive(Clone)]
 pub struct RFIDEncryption {
     key: [u8; 32],
+    mode: EncryptionMode,
+    /// Whether a GCM key still reads tags whose header says CBC
+    cbc_migration: bool,
+    /// Version recorded in tags encrypted with this key
+    version: u8,
+    /// Key retired by the last rotation, still accepted for decryption
+    previous: Option<Box<RFIDEncryption>>,
+}
+
+/// Plaintext from [`RFIDEncryption::decrypt_versioned`] and the version of
+/// the key that decrypted it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecryptResult {
+    pub plaintext: Vec<u8>,
+    pub key_version: u8,
 }
 
 impl RFIDEncryption {
-    /// Create a new encryption instance with a derived key
+    /// Create a new encryption instance with a derived key, using AES-256-CBC
     pub fn new(master_key: &[u8]) -> Self {
         let mut hasher = Sha256::new();
         hasher.update(master_key);
@@ -21,12 +57,177 @@ impl RFIDEncryption {
         
         Self {
             key: key.into(),
+            mode: EncryptionMode::Cbc,
+            cbc_migration: false,
+            version: 0,
+            previous: None,
+        }
+    }
+
+    /// Encrypt with `current` while still decrypting data written under
+    /// `previous`, so tags can be migrated one at a time after a rotation.
+    /// Versions are the caller's, e.g. its key IDs, and must differ.
+    pub fn with_previous_key(current_version: u8, current: &[u8], previous_version: u8, previous: &[u8]) -> Result<Self> {
+        if current_version == previous_version {
+            return Err(SampleGuardError::ConfigError(format!(
+                "Current and previous keys both have version {}", current_version
+            )));
+        }
+        Ok(Self {
+            previous: Some(Box::new(Self::new(previous).with_key_version(previous_version))),
+            ..Self::new(current).with_key_version(current_version)
+        })
+    }
+
+    /// The same key, recording `version` in the tags it encrypts. Keys are
+    /// version 0 unless given one.
+    pub fn with_key_version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Version of the key new data is encrypted with
+    pub fn key_version(&self) -> u8 {
+        self.version
+    }
+
+    /// Create an instance using authenticated AES-256-GCM
+    pub fn new_gcm(master_key: &[u8]) -> Self {
+        Self {
+            mode: EncryptionMode::Gcm,
+            ..Self::new(master_key)
         }
     }
 
-    /// Encrypt data for RFID tag storage
-    /// Uses AES-256-CBC with a random IV for each encryption
+    /// The same key used with `mode`
+    pub fn with_mode(mut self, mode: EncryptionMode) -> Self {
+        self.mode = mode;
+        self.previous = self.previous.map(|previous| Box::new(previous.with_mode(mode)));
+        self
+    }
+
+    pub fn mode(&self) -> EncryptionMode {
+        self.mode
+    }
+
+    /// Let a GCM key read tags written in CBC mode, so they can be migrated
+    /// with `tag::reencrypt`. Off by default, since the mode flag in a tag
+    /// header is not authenticated and would otherwise let a forged tag
+    /// bypass GCM.
+    pub fn with_cbc_migration(mut self, allow: bool) -> Self {
+        self.cbc_migration = allow;
+        self.previous = self.previous.map(|previous| Box::new(previous.with_cbc_migration(allow)));
+        self
+    }
+
+    /// Whether data in `mode` may be read with this key
+    pub fn accepts_mode(&self, mode: EncryptionMode) -> bool {
+        self.mode == mode || self.mode == EncryptionMode::Cbc || self.cbc_migration
+    }
+
+    /// Key unique to the tag with this EPC, derived from the master key with
+    /// HKDF-SHA256, so one recovered tag key exposes no other tag
+    pub fn derive_tag_key(&self, epc: &str) -> [u8; 32] {
+        let mut tag_key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(TAG_KEY_SALT), &self.key)
+            .expand(epc.as_bytes(), &mut tag_key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        tag_key
+    }
+
+    /// Encrypt with the key of the tag with this EPC
+    pub fn encrypt_for_tag(&self, epc: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+        self.for_tag(epc).encrypt(plaintext)
+    }
+
+    /// Decrypt with the key of the tag with this EPC. In GCM mode, data
+    /// encrypted for another EPC always fails; CBC only notices when the
+    /// padding comes out wrong.
+    pub fn decrypt_for_tag(&self, epc: &str, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        self.for_tag(epc).decrypt(ciphertext)
+    }
+
+    /// Decrypt data from the tag with this EPC and encrypt it again under
+    /// the current key, to migrate a tag written before a rotation
+    pub fn re_encrypt(&self, old_ciphertext: &[u8], epc: &str) -> Result<Vec<u8>> {
+        let plaintext = self.decrypt_for_tag(epc, old_ciphertext)?;
+        self.encrypt_for_tag(epc, &plaintext)
+    }
+
+    fn for_tag(&self, epc: &str) -> Self {
+        Self {
+            key: self.derive_tag_key(epc),
+            mode: self.mode,
+            cbc_migration: self.cbc_migration,
+            version: self.version,
+            previous: self.previous.as_ref().map(|previous| Box::new(previous.for_tag(epc))),
+        }
+    }
+
+    /// Encrypt data for RFID tag storage with a random IV or nonce each time.
+    /// GCM output is the nonce, the ciphertext, then the authentication tag.
     pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        match self.mode {
+            EncryptionMode::Cbc => self.encrypt_cbc(plaintext),
+            EncryptionMode::Gcm => {
+                let mut nonce = [0u8; GCM_NONCE_LEN];
+                rand::thread_rng().fill_bytes(&mut nonce);
+                // aes-gcm appends the tag to the ciphertext
+                let sealed = self.gcm_cipher()
+                    .encrypt(Nonce::from_slice(&nonce), plaintext)
+                    .map_err(|_| SampleGuardError::EncryptionError("GCM encryption failed".to_string()))?;
+                Ok([&nonce[..], &sealed].concat())
+            }
+        }
+    }
+
+    /// Decrypt data from RFID tag, falling back to the previous key
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        self.decrypt_versioned(ciphertext).map(|result| result.plaintext)
+    }
+
+    /// Decrypt with the current key, or failing that the previous one, and
+    /// report which succeeded. A wrong CBC key is only noticed when the
+    /// padding comes out wrong, so the fallback is reliable only in GCM mode.
+    pub fn decrypt_versioned(&self, ciphertext: &[u8]) -> Result<DecryptResult> {
+        let error = match self.decrypt_current(ciphertext) {
+            Ok(plaintext) => return Ok(DecryptResult { plaintext, key_version: self.key_version() }),
+            Err(e) => e,
+        };
+        match &self.previous {
+            Some(previous) => previous
+                .decrypt_current(ciphertext)
+                .map(|plaintext| DecryptResult { plaintext, key_version: previous.key_version() })
+                .map_err(|_| error),
+            None => Err(error),
+        }
+    }
+
+    fn decrypt_current(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        match self.mode {
+            EncryptionMode::Cbc => self.decrypt_cbc(ciphertext),
+            EncryptionMode::Gcm => {
+                if ciphertext.len() < GCM_NONCE_LEN + GCM_TAG_LEN {
+                    return Err(SampleGuardError::EncryptionError(format!(
+                        "Ciphertext too short (need at least {} bytes: {} nonce + {} tag)",
+                        GCM_NONCE_LEN + GCM_TAG_LEN, GCM_NONCE_LEN, GCM_TAG_LEN
+                    )));
+                }
+                let (nonce, sealed) = ciphertext.split_at(GCM_NONCE_LEN);
+                self.gcm_cipher()
+                    .decrypt(Nonce::from_slice(nonce), sealed)
+                    .map_err(|_| SampleGuardError::EncryptionError(
+                        "Authentication failed: ciphertext was altered or the key is wrong".to_string()
+                    ))
+            }
+        }
+    }
+
+    fn gcm_cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&self.key.into())
+    }
+
+    fn encrypt_cbc(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
         // Generate random IV
         let mut iv = [0u8; 16];
         rand::thread_rng().fill_bytes(&mut iv);
@@ -50,8 +251,7 @@ impl RFIDEncryption {
         Ok(result)
     }
 
-    /// Decrypt data from RFID tag
-    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    fn decrypt_cbc(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
         // Minimum size: 16 bytes IV + 16 bytes encrypted block
         if ciphertext.len() < 32 {
             return Err(SampleGuardError::EncryptionError(
@@ -91,6 +291,90 @@ impl RFIDEncryption {
     }
 }
 
+/// Source of the keys that tag payloads are encrypted with. Each tag records
+/// the ID of its key in its header, so tags written before a rotation stay
+/// readable.
+pub trait TagKeys {
+    /// ID and key that new tags are encrypted with
+    fn encryption_key(&self) -> (u8, &RFIDEncryption);
+
+    /// Key that a tag recording `key_id` was encrypted with
+    fn decryption_key(&self, key_id: u8) -> Result<&RFIDEncryption>;
+}
+
+/// Key IDs are key versions. A lone key serves every ID, including the 0
+/// that tags written before key IDs carry.
+impl TagKeys for RFIDEncryption {
+    fn encryption_key(&self) -> (u8, &RFIDEncryption) {
+        (self.key_version(), self)
+    }
+
+    fn decryption_key(&self, key_id: u8) -> Result<&RFIDEncryption> {
+        match &self.previous {
+            None => Ok(self),
+            Some(previous) if key_id == previous.key_version() => Ok(previous),
+            Some(_) if key_id == self.key_version() => Ok(self),
+            Some(_) => Err(SampleGuardError::EncryptionError(format!(
+                "No key with version {}: only the current and previous keys are held", key_id
+            ))),
+        }
+    }
+}
+
+/// Keys indexed by a one-byte ID, for rotating keys without losing access to
+/// tags encrypted under the old ones. New tags always use the key added last.
+#[derive(Clone)]
+pub struct KeyRing {
+    keys: BTreeMap<u8, RFIDEncryption>,
+    active: u8,
+}
+
+impl KeyRing {
+    pub fn new(key_id: u8, encryption: RFIDEncryption) -> Self {
+        Self {
+            keys: BTreeMap::from([(key_id, encryption)]),
+            active: key_id,
+        }
+    }
+
+    /// Add a key and encrypt new tags with it from now on. IDs cannot be
+    /// reused, since tags already carrying one would then be unreadable.
+    pub fn rotate(&mut self, key_id: u8, encryption: RFIDEncryption) -> Result<()> {
+        if self.keys.contains_key(&key_id) {
+            return Err(SampleGuardError::ConfigError(format!(
+                "Key ID {} is already in the key ring", key_id
+            )));
+        }
+        self.keys.insert(key_id, encryption);
+        self.active = key_id;
+        Ok(())
+    }
+
+    pub fn active_key_id(&self) -> u8 {
+        self.active
+    }
+
+    pub fn get(&self, key_id: u8) -> Option<&RFIDEncryption> {
+        self.keys.get(&key_id)
+    }
+
+    pub fn key_ids(&self) -> impl Iterator<Item = u8> + '_ {
+        self.keys.keys().copied()
+    }
+}
+
+impl TagKeys for KeyRing {
+    fn encryption_key(&self) -> (u8, &RFIDEncryption) {
+        (self.active, &self.keys[&self.active])
+    }
+
+    fn decryption_key(&self, key_id: u8) -> Result<&RFIDEncryption> {
+        self.get(key_id).ok_or_else(|| SampleGuardError::EncryptionError(format!(
+            "No key with ID {} in the key ring", key_id
+        )))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,5 +414,190 @@ mod tests {
         
         assert_eq!(empty, decrypted.as_slice());
     }
-}
 
+    #[test]
+    fn test_gcm_roundtrip() {
+        let encryption = RFIDEncryption::new_gcm(b"test_master_key_32_bytes_long!!");
+        assert_eq!(encryption.mode(), EncryptionMode::Gcm);
+        
+        for plaintext in [&b""[..], b"Sample data for RFID tag", &[0xA5; 100]] {
+            let ciphertext = encryption.encrypt(plaintext).unwrap();
+            assert_eq!(ciphertext.len(), GCM_NONCE_LEN + plaintext.len() + GCM_TAG_LEN);
+            assert_eq!(encryption.decrypt(&ciphertext).unwrap(), plaintext);
+        }
+    }
+
+    /// GCM instance with a raw key, bypassing the key derivation of `new`
+    fn gcm_with_raw_key(key: [u8; 32]) -> RFIDEncryption {
+        RFIDEncryption { key, mode: EncryptionMode::Gcm, cbc_migration: false, version: 0, previous: None }
+    }
+
+    /// Seal with a fixed nonce so output can be compared to known vectors
+    fn seal(key: [u8; 32], nonce: &[u8; GCM_NONCE_LEN], plaintext: &[u8]) -> Vec<u8> {
+        let sealed = gcm_with_raw_key(key).gcm_cipher().encrypt(Nonce::from_slice(nonce), plaintext).unwrap();
+        [&nonce[..], &sealed].concat()
+    }
+
+    fn vector(hex_str: &str) -> Vec<u8> {
+        hex::decode(hex_str).unwrap()
+    }
+
+    #[test]
+    fn test_gcm_nist_vectors() {
+        // Test cases 13-15 of the GCM specification, in the nonce,
+        // ciphertext, tag layout stored on tags
+        let sealed = seal([0; 32], &[0; 12], &[]);
+        assert_eq!(sealed, [vec![0; 12], vector("530f8afbc74536b9a963b4f1c4cb738b")].concat());
+        assert!(gcm_with_raw_key([0; 32]).decrypt(&sealed).unwrap().is_empty());
+
+        let sealed = seal([0; 32], &[0; 12], &[0; 16]);
+        assert_eq!(
+            sealed,
+            [vec![0; 12], vector("cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919")].concat()
+        );
+        assert_eq!(gcm_with_raw_key([0; 32]).decrypt(&sealed).unwrap(), vec![0; 16]);
+
+        let key: [u8; 32] = vector("feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308")
+            .try_into()
+            .unwrap();
+        let nonce: [u8; 12] = vector("cafebabefacedbaddecaf888").try_into().unwrap();
+        let plaintext = vector(
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+             1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+        );
+        let sealed = seal(key, &nonce, &plaintext);
+        assert_eq!(
+            sealed,
+            [
+                nonce.to_vec(),
+                vector(
+                    "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+                     8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662898015ad"
+                ),
+                vector("b094dac5d93471bdec1a502270e3cc6c"),
+            ]
+            .concat()
+        );
+        assert_eq!(gcm_with_raw_key(key).decrypt(&sealed).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_gcm_detects_tampering() {
+        let encryption = RFIDEncryption::new_gcm(b"test_master_key_32_bytes_long!!");
+        let ciphertext = encryption.encrypt(b"Sample data for RFID tag").unwrap();
+        
+        for i in 0..ciphertext.len() {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 0x01;
+            assert!(matches!(encryption.decrypt(&tampered), Err(SampleGuardError::EncryptionError(_))));
+        }
+        assert!(matches!(encryption.decrypt(&ciphertext[..20]), Err(SampleGuardError::EncryptionError(_))));
+        
+        let other_key = RFIDEncryption::new_gcm(b"another_master_key_32_bytes_long");
+        assert!(matches!(other_key.decrypt(&ciphertext), Err(SampleGuardError::EncryptionError(_))));
+    }
+
+    #[test]
+    fn test_tag_keys_are_per_epc() {
+        let encryption = RFIDEncryption::new_gcm(b"test_master_key_32_bytes_long!!");
+        assert_eq!(encryption.derive_tag_key("EPC-001"), encryption.derive_tag_key("EPC-001"));
+        assert_ne!(encryption.derive_tag_key("EPC-001"), encryption.derive_tag_key("EPC-002"));
+        assert_ne!(encryption.derive_tag_key("EPC-001"), encryption.key);
+        
+        let ciphertext = encryption.encrypt_for_tag("EPC-001", b"Sample data").unwrap();
+        assert_eq!(encryption.decrypt_for_tag("EPC-001", &ciphertext).unwrap(), b"Sample data");
+        assert!(matches!(encryption.decrypt_for_tag("EPC-002", &ciphertext), Err(SampleGuardError::EncryptionError(_))));
+        assert!(matches!(encryption.decrypt(&ciphertext), Err(SampleGuardError::EncryptionError(_))));
+    }
+
+    #[test]
+    fn test_cbc_does_not_detect_tampering() {
+        let encryption = RFIDEncryption::new(b"test_master_key_32_bytes_long!!");
+        assert_eq!(encryption.mode(), EncryptionMode::Cbc);
+        let mut ciphertext = encryption.encrypt(b"Sample data for RFID tag").unwrap();
+        
+        // Flipping an IV bit flips the same plaintext bit, and decryption still succeeds
+        ciphertext[0] ^= 0x01;
+        assert_eq!(encryption.decrypt(&ciphertext).unwrap(), b"Rample data for RFID tag");
+    }
+
+    #[test]
+    fn test_key_ring_rotation() {
+        let mut ring = KeyRing::new(1, RFIDEncryption::new(b"first_master_key_32_bytes_long!!"));
+        assert_eq!(ring.encryption_key().0, 1);
+
+        ring.rotate(2, RFIDEncryption::new_gcm(b"second_master_key_32_bytes_long!")).unwrap();
+        assert_eq!(ring.active_key_id(), 2);
+        assert_eq!(ring.encryption_key().1.mode(), EncryptionMode::Gcm);
+        assert_eq!(ring.key_ids().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(ring.decryption_key(1).unwrap().mode(), EncryptionMode::Cbc);
+
+        assert!(matches!(ring.decryption_key(3), Err(SampleGuardError::EncryptionError(_))));
+        assert!(matches!(
+            ring.rotate(1, RFIDEncryption::new(b"reused_key_id")),
+            Err(SampleGuardError::ConfigError(_))
+        ));
+        assert_eq!(ring.active_key_id(), 2);
+    }
+
+    #[test]
+    fn test_with_mode_keeps_key() {
+        let cbc = RFIDEncryption::new(b"test_master_key_32_bytes_long!!");
+        let gcm = cbc.clone().with_mode(EncryptionMode::Gcm);
+        assert_eq!(gcm.mode(), EncryptionMode::Gcm);
+
+        let ciphertext = RFIDEncryption::new_gcm(b"test_master_key_32_bytes_long!!").encrypt(b"data").unwrap();
+        assert_eq!(gcm.decrypt(&ciphertext).unwrap(), b"data");
+        assert!(cbc.decrypt(&ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_previous_key_fallback() {
+        let old = RFIDEncryption::new_gcm(b"old_master_key_32_bytes_long!!!!");
+        let rotated = RFIDEncryption::with_previous_key(1, b"new_master_key_32_bytes_long!!!!", 0, b"old_master_key_32_bytes_long!!!!")
+            .unwrap()
+            .with_mode(EncryptionMode::Gcm);
+        assert_eq!((old.key_version(), rotated.key_version()), (0, 1));
+
+        let old_ciphertext = old.encrypt(b"written before rotation").unwrap();
+        let result = rotated.decrypt_versioned(&old_ciphertext).unwrap();
+        assert_eq!(result, DecryptResult { plaintext: b"written before rotation".to_vec(), key_version: 0 });
+
+        let new_ciphertext = rotated.encrypt(b"written after rotation").unwrap();
+        assert_eq!(rotated.decrypt_versioned(&new_ciphertext).unwrap().key_version, 1);
+        assert!(old.decrypt(&new_ciphertext).is_err());
+
+        let migrated = rotated.re_encrypt(&old.encrypt_for_tag("EPC-001", b"data").unwrap(), "EPC-001").unwrap();
+        assert!(old.decrypt_for_tag("EPC-001", &migrated).is_err());
+        assert_eq!(rotated.decrypt_for_tag("EPC-001", &migrated).unwrap(), b"data");
+
+        let unrelated = RFIDEncryption::new_gcm(b"unrelated_key_32_bytes_long!!!!!");
+        assert!(matches!(
+            rotated.decrypt_versioned(&unrelated.encrypt(b"data").unwrap()),
+            Err(SampleGuardError::EncryptionError(_))
+        ));
+        assert!(rotated.decryption_key(2).is_err());
+    }
+
+    #[test]
+    fn test_key_versions_survive_a_second_rotation() {
+        let first = RFIDEncryption::new_gcm(b"2026Q1_master_key_32_bytes_long!").with_key_version(1);
+        let second = RFIDEncryption::with_previous_key(2, b"2026Q2_master_key_32_bytes_long!", 1, b"2026Q1_master_key_32_bytes_long!")
+            .unwrap()
+            .with_mode(EncryptionMode::Gcm);
+        let third = RFIDEncryption::with_previous_key(3, b"2026Q3_master_key_32_bytes_long!", 2, b"2026Q2_master_key_32_bytes_long!")
+            .unwrap()
+            .with_mode(EncryptionMode::Gcm);
+
+        // Written under the second key, which is now the previous one
+        let (version, key) = second.encryption_key();
+        let ciphertext = key.encrypt(b"data").unwrap();
+        assert_eq!(version, 2);
+        assert_eq!(third.decrypt_versioned(&ciphertext).unwrap().key_version, 2);
+        assert_eq!(third.decryption_key(version).unwrap().decrypt(&ciphertext).unwrap(), b"data");
+        assert_eq!(third.decryption_key(3).unwrap().key_version(), 3);
+        assert!(third.decryption_key(first.key_version()).is_err());
+
+        assert!(RFIDEncryption::with_previous_key(4, b"a", 4, b"b").is_err());
+    }
+}
@@ -26,8 +26,260 @@ pub enum SampleGuardError {
 
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
+
+    #[error("Configuration error: {0}")]
+    ConfigError(String),
+
+    #[error("Tag data of {needed} bytes does not fit in {available} bytes of tag memory")]
+    TagTooLarge { needed: usize, available: usize },
+
+    #[error("Invalid status transition from {from:?} to {to:?}")]
+    InvalidStatusTransition {
+        from: crate::sample::SampleStatus,
+        to: crate::sample::SampleStatus,
+    },
+
+    /// `column` and `value` name the stored value that could not be
+    /// decoded, when that is what failed
+    #[error("Database error: {message}")]
+    DatabaseError {
+        kind: DatabaseErrorKind,
+        message: String,
+        column: Option<String>,
+        value: Option<String>,
+    },
+}
+
+/// What kind of database failure a [`SampleGuardError::DatabaseError`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseErrorKind {
+    /// Another connection held the lock past the busy timeout
+    Busy,
+    /// A unique, foreign-key or check constraint rejected the write
+    ConstraintViolation,
+    /// A query that must return a row returned none
+    NotFound,
+    /// The file is not a valid database, or a stored value cannot be decoded
+    Corrupt,
+    /// Disk full, I/O failures and anything else
+    Other,
+}
+
+impl DatabaseErrorKind {
+    fn of(error: &rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+        match error {
+            rusqlite::Error::SqliteFailure(e, _) => match e.code {
+                ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => DatabaseErrorKind::Busy,
+                ErrorCode::ConstraintViolation => DatabaseErrorKind::ConstraintViolation,
+                ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase => DatabaseErrorKind::Corrupt,
+                _ => DatabaseErrorKind::Other,
+            },
+            rusqlite::Error::QueryReturnedNoRows => DatabaseErrorKind::NotFound,
+            rusqlite::Error::FromSqlConversionFailure(..)
+            | rusqlite::Error::InvalidColumnType(..)
+            | rusqlite::Error::IntegralValueOutOfRange(..) => DatabaseErrorKind::Corrupt,
+            _ => DatabaseErrorKind::Other,
+        }
+    }
+
+    #[cfg(feature = "postgres")]
+    fn of_postgres(error: &tokio_postgres::Error) -> Self {
+        use tokio_postgres::error::SqlState;
+        let Some(code) = error.code() else {
+            return DatabaseErrorKind::Other;
+        };
+        if [
+            SqlState::UNIQUE_VIOLATION,
+            SqlState::FOREIGN_KEY_VIOLATION,
+            SqlState::NOT_NULL_VIOLATION,
+            SqlState::CHECK_VIOLATION,
+        ].contains(code) {
+            DatabaseErrorKind::ConstraintViolation
+        } else if [
+            SqlState::LOCK_NOT_AVAILABLE,
+            SqlState::T_R_SERIALIZATION_FAILURE,
+            SqlState::T_R_DEADLOCK_DETECTED,
+        ].contains(code) {
+            DatabaseErrorKind::Busy
+        } else if [SqlState::DATA_CORRUPTED, SqlState::INDEX_CORRUPTED].contains(code) {
+            DatabaseErrorKind::Corrupt
+        } else {
+            DatabaseErrorKind::Other
+        }
+    }
+}
+
+/// A stored value that could not be decoded, carried inside
+/// `rusqlite::Error::FromSqlConversionFailure` so the column and value
+/// survive into [`SampleGuardError::DatabaseError`]
+#[derive(Error, Debug)]
+#[error("{message}")]
+pub(crate) struct ColumnError {
+    pub column: String,
+    pub value: Option<String>,
+    pub message: String,
+}
+
+impl ColumnError {
+    fn of(error: &rusqlite::Error) -> Option<&ColumnError> {
+        match error {
+            rusqlite::Error::FromSqlConversionFailure(_, _, e) => e.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl SampleGuardError {
+    /// Database failure of `kind` not caused by a driver error
+    pub fn database_message(kind: DatabaseErrorKind, message: impl Into<String>) -> Self {
+        SampleGuardError::DatabaseError { kind, message: message.into(), column: None, value: None }
+    }
+
+    /// Wrap a database failure, prefixing its message with `context`
+    pub fn database(context: impl std::fmt::Display, error: rusqlite::Error) -> Self {
+        Self::from_rusqlite(format!("{}: {}", context, error), error)
+    }
+
+    fn from_rusqlite(message: String, error: rusqlite::Error) -> Self {
+        let column = ColumnError::of(&error);
+        SampleGuardError::DatabaseError {
+            kind: DatabaseErrorKind::of(&error),
+            column: column.map(|c| c.column.clone()),
+            value: column.and_then(|c| c.value.clone()),
+            message,
+        }
+    }
+
+    /// Wrap a Postgres failure, prefixing its message with `context`
+    #[cfg(feature = "postgres")]
+    pub fn postgres(context: impl std::fmt::Display, error: tokio_postgres::Error) -> Self {
+        Self::database_message(DatabaseErrorKind::of_postgres(&error), format!("{}: {}", context, error))
+    }
+
+    /// Kind of database failure, if this is one
+    pub fn database_kind(&self) -> Option<DatabaseErrorKind> {
+        match self {
+            SampleGuardError::DatabaseError { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for SampleGuardError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::from_rusqlite(error.to_string(), error)
+    }
+}
+
+/// Broad kind of a [`SampleGuardError`], for deciding how to react to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Radio or I/O hiccup that may clear on its own; worth retrying
+    Transient,
+    /// Sample data failed its integrity checks
+    Integrity,
+    /// Malformed, undecodable or conflicting data
+    InvalidData,
+    /// Persistent storage failure
+    Storage,
+    Configuration,
+}
+
+impl SampleGuardError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            // Timeouts, collisions and CRC errors on the air interface
+            SampleGuardError::ReaderError(_) => ErrorCategory::Transient,
+            SampleGuardError::IoError(e) if is_transient_io(e.kind()) => ErrorCategory::Transient,
+            SampleGuardError::IoError(_) => ErrorCategory::Storage,
+            SampleGuardError::DatabaseError { kind: DatabaseErrorKind::Busy, .. } => ErrorCategory::Transient,
+            SampleGuardError::DatabaseError { kind: DatabaseErrorKind::ConstraintViolation, .. } => ErrorCategory::InvalidData,
+            SampleGuardError::DatabaseError { .. } => ErrorCategory::Storage,
+            SampleGuardError::IntegrityViolation(_) => ErrorCategory::Integrity,
+            SampleGuardError::EncryptionError(_)
+            | SampleGuardError::TagParseError(_)
+            | SampleGuardError::InvalidSampleData(_)
+            | SampleGuardError::TagMemoryError(_)
+            | SampleGuardError::SerializationError(_)
+            | SampleGuardError::TagTooLarge { .. }
+            | SampleGuardError::InvalidStatusTransition { .. } => ErrorCategory::InvalidData,
+            SampleGuardError::ConfigError(_) => ErrorCategory::Configuration,
+        }
+    }
+
+    /// Whether repeating the failed operation unchanged may succeed
+    pub fn is_retryable(&self) -> bool {
+        self.category() == ErrorCategory::Transient
+    }
+}
+
+fn is_transient_io(kind: std::io::ErrorKind) -> bool {
+    use std::io::ErrorKind::*;
+    matches!(kind, TimedOut | Interrupted | WouldBlock | ConnectionReset | ConnectionAborted | BrokenPipe)
 }
 
 /// Result type alias for SampleGuard operations
 pub type Result<T> = std::result::Result<T, SampleGuardError>;
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::{ValidationResult, Violation};
+
+    #[test]
+    fn test_read_timeout_is_retryable() {
+        let timeout = SampleGuardError::ReaderError("Read timed out after 2000 ms".to_string());
+        assert!(timeout.is_retryable());
+        let io_timeout = SampleGuardError::IoError(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        assert!(io_timeout.is_retryable());
+    }
+
+    #[test]
+    fn test_terminal_errors_are_not_retryable() {
+        let violation = SampleGuardError::IntegrityViolation(ValidationResult {
+            is_valid: false,
+            violations: vec![Violation::ChecksumMismatch],
+            warnings: Vec::new(),
+        });
+        assert_eq!(violation.category(), ErrorCategory::Integrity);
+        assert!(!violation.is_retryable());
+
+        let duplicate = SampleGuardError::InvalidSampleData("Sample S-1 already exists".to_string());
+        assert_eq!(duplicate.category(), ErrorCategory::InvalidData);
+        assert!(!duplicate.is_retryable());
+
+        let disk = SampleGuardError::IoError(std::io::Error::other("disk full"));
+        assert_eq!(disk.category(), ErrorCategory::Storage);
+        assert!(!disk.is_retryable());
+    }
+
+    fn sqlite_failure(code: std::os::raw::c_int) -> rusqlite::Error {
+        rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(code), None)
+    }
+
+    #[test]
+    fn test_database_error_kinds() {
+        let cases = [
+            (sqlite_failure(rusqlite::ffi::SQLITE_BUSY), DatabaseErrorKind::Busy, ErrorCategory::Transient),
+            (sqlite_failure(rusqlite::ffi::SQLITE_LOCKED), DatabaseErrorKind::Busy, ErrorCategory::Transient),
+            (
+                sqlite_failure(rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE),
+                DatabaseErrorKind::ConstraintViolation,
+                ErrorCategory::InvalidData,
+            ),
+            (rusqlite::Error::QueryReturnedNoRows, DatabaseErrorKind::NotFound, ErrorCategory::Storage),
+            (sqlite_failure(rusqlite::ffi::SQLITE_CORRUPT), DatabaseErrorKind::Corrupt, ErrorCategory::Storage),
+            (sqlite_failure(rusqlite::ffi::SQLITE_FULL), DatabaseErrorKind::Other, ErrorCategory::Storage),
+        ];
+        for (source, kind, category) in cases {
+            let error = SampleGuardError::database("Failed to test", source);
+            assert_eq!(error.database_kind(), Some(kind), "{}", error);
+            assert_eq!(error.category(), category, "{}", error);
+            assert!(error.to_string().starts_with("Database error: Failed to test: "), "{}", error);
+        }
+        assert_eq!(SampleGuardError::ConfigError("x".to_string()).database_kind(), None);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::database::{decode_checksum, Database};
+use crate::error::{SampleGuardError, Result};
+use crate::sample::{Sample, SampleMetadata, SampleStatus};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+/// Column order of CSV exports
+pub const CSV_HEADER: &str = "sample_id,id,status,batch_number,production_date,expiry_date,\
+temperature_min,temperature_max,storage_conditions,manufacturer,product_line,location,\
+created_at,last_updated,read_count,integrity_checksum,container_id,notes";
+
+/// Columns a CSV import cannot do without
+const CSV_REQUIRED_COLUMNS: [&str; 4] = ["sample_id", "status", "batch_number", "production_date"];
+
+/// Row format of a sample export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    /// One serialized `Sample` per line, readable by `Database::import_jsonl`
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Jsonl => "application/x-ndjson",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = SampleGuardError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" | "ndjson" => Ok(ExportFormat::Jsonl),
+            other => Err(SampleGuardError::InvalidSampleData(format!(
+                "Unknown export format '{}'; expected csv or jsonl",
+                other
+            ))),
+        }
+    }
+}
+
+/// Write every stored sample to `out` as it is read, returning the row count.
+///
+/// Only one sample is held at a time; buffering is left to `out`.
+pub fn write_samples<W: Write>(database: &Database, format: ExportFormat, mut out: W) -> Result<usize> {
+    let count = match format {
+        ExportFormat::Csv => {
+            let mut csv = csv_writer(&mut out)?;
+            let count = database.stream_samples(|sample| write_csv_row(&mut csv, sample))?;
+            csv.flush()?;
+            count
+        }
+        ExportFormat::Jsonl => database.stream_samples(|sample| {
+            serde_json::to_writer(&mut out, sample)?;
+            out.write_all(b"\n")?;
+            Ok(())
+        })?,
+    };
+    out.flush()?;
+    Ok(count)
+}
+
+/// Write `samples` to `out` as CSV, returning the row count
+pub fn write_samples_csv<'a, W: Write>(samples: impl IntoIterator<Item = &'a Sample>, mut out: W) -> Result<usize> {
+    let mut csv = csv_writer(&mut out)?;
+    let mut count = 0;
+    for sample in samples {
+        write_csv_row(&mut csv, sample)?;
+        count += 1;
+    }
+    csv.flush()?;
+    Ok(count)
+}
+
+/// CSV writer on `out` with the header row already written
+fn csv_writer<W: Write>(out: W) -> Result<csv::Writer<W>> {
+    let mut csv = csv::WriterBuilder::new().has_headers(false).from_writer(out);
+    csv.write_record(CSV_HEADER.split(',')).map_err(std::io::Error::from)?;
+    Ok(csv)
+}
+
+fn write_csv_row<W: Write>(csv: &mut csv::Writer<W>, sample: &Sample) -> Result<()> {
+    let metadata = &sample.metadata;
+    let (min, max) = match metadata.temperature_range {
+        Some((min, max)) => (min.to_string(), max.to_string()),
+        None => (String::new(), String::new()),
+    };
+    csv.write_record([
+        sample.sample_id.clone(),
+        sample.id.to_string(),
+        format!("{:?}", sample.status),
+        metadata.batch_number.clone(),
+        metadata.production_date.to_rfc3339(),
+        metadata.expiry_date.map(|d| d.to_rfc3339()).unwrap_or_default(),
+        min,
+        max,
+        metadata.storage_conditions.clone(),
+        metadata.manufacturer.clone(),
+        metadata.product_line.clone(),
+        sample.location.clone().unwrap_or_default(),
+        sample.created_at.to_rfc3339(),
+        sample.last_updated.to_rfc3339(),
+        sample.read_count.to_string(),
+        hex::encode(sample.integrity_checksum),
+        sample.container_id.clone().unwrap_or_default(),
+        sample.notes.clone().unwrap_or_default(),
+    ]).map_err(std::io::Error::from)?;
+    Ok(())
+}
+
+/// Samples in CSV with the columns of [`CSV_HEADER`], each with its line
+/// number or why the row was rejected.
+///
+/// Columns are matched by header name, so their order does not matter.
+/// Only `sample_id`, `status`, `batch_number` and `production_date` are
+/// required: a missing `id` gets a fresh one, missing `created_at` and
+/// `last_updated` become now and a missing `integrity_checksum` is computed.
+/// A given checksum must match, as it would for a JSONL import.
+pub fn read_samples_csv<R: Read>(input: R) -> Result<Vec<(usize, std::result::Result<Sample, String>)>> {
+    let mut csv = csv::ReaderBuilder::new().from_reader(input);
+    let headers = csv.headers()
+        .map_err(|e| SampleGuardError::InvalidSampleData(format!("Invalid CSV header: {}", e)))?;
+    let columns: HashMap<String, usize> = headers.iter()
+        .enumerate()
+        .map(|(index, name)| (name.trim().to_string(), index))
+        .collect();
+    if let Some(missing) = CSV_REQUIRED_COLUMNS.iter().find(|c| !columns.contains_key(**c)) {
+        return Err(SampleGuardError::InvalidSampleData(format!("CSV has no '{}' column", missing)));
+    }
+
+    let mut rows = Vec::new();
+    for record in csv.records() {
+        match record {
+            Ok(record) => {
+                let line = record.position().map_or(0, |p| p.line() as usize);
+                rows.push((line, sample_from_csv(&columns, &record)));
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line() as usize);
+                match e.kind() {
+                    csv::ErrorKind::Io(_) => return Err(std::io::Error::from(e).into()),
+                    _ => rows.push((line, Err(format!("invalid CSV row: {}", e)))),
+                }
+            }
+        }
+    }
+    Ok(rows)
+}
+
+fn sample_from_csv(columns: &HashMap<String, usize>, record: &csv::StringRecord) -> std::result::Result<Sample, String> {
+    let field = |name: &str| {
+        columns.get(name)
+            .and_then(|&index| record.get(index))
+            .filter(|value| !value.trim().is_empty())
+    };
+    let text = |name: &str| field(name).unwrap_or_default().to_string();
+    let timestamp = |name: &str| {
+        field(name)
+            .map(|value| value.trim().parse::<DateTime<Utc>>().map_err(|e| format!("invalid {} '{}': {}", name, value, e)))
+            .transpose()
+    };
+    let number = |name: &str| {
+        field(name)
+            .map(|value| value.trim().parse::<f32>().map_err(|e| format!("invalid {} '{}': {}", name, value, e)))
+            .transpose()
+    };
+
+    let sample_id = field("sample_id").ok_or("sample_id is empty")?.trim().to_string();
+    let status_text = field("status").ok_or("status is empty")?.trim();
+    let status: SampleStatus = serde_json::from_value(serde_json::Value::String(status_text.to_string()))
+        .map_err(|_| format!("unknown status '{}'", status_text))?;
+    let temperature_range = match (number("temperature_min")?, number("temperature_max")?) {
+        (Some(min), Some(max)) => Some((min, max)),
+        (None, None) => None,
+        _ => return Err("temperature_min and temperature_max must both be set or both be empty".to_string()),
+    };
+    let metadata = SampleMetadata {
+        batch_number: field("batch_number").ok_or("batch_number is empty")?.to_string(),
+        production_date: timestamp("production_date")?.ok_or("production_date is empty")?,
+        expiry_date: timestamp("expiry_date")?,
+        temperature_range,
+        storage_conditions: text("storage_conditions"),
+        manufacturer: text("manufacturer"),
+        product_line: text("product_line"),
+    };
+
+    let now = Utc::now();
+    let last_updated = timestamp("last_updated")?.unwrap_or(now);
+    let integrity_checksum = match field("integrity_checksum") {
+        Some(value) => decode_checksum(value.trim())?,
+        None => Sample::calculate_checksum(&sample_id, &metadata, &last_updated),
+    };
+    Ok(Sample {
+        id: match field("id") {
+            Some(value) => value.trim().parse().map_err(|e| format!("invalid id '{}': {}", value, e))?,
+            None => uuid::Uuid::new_v4(),
+        },
+        sample_id,
+        status,
+        metadata,
+        created_at: timestamp("created_at")?.unwrap_or(now),
+        last_updated,
+        read_count: match field("read_count") {
+            Some(value) => value.trim().parse().map_err(|e| format!("invalid read_count '{}': {}", value, e))?,
+            None => 0,
+        },
+        location: field("location").map(str::to_string),
+        integrity_checksum,
+        container_id: field("container_id").map(str::to_string),
+        notes: field("notes").map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample::SampleMetadata;
+    use chrono::Utc;
+
+    fn sample(id: &str, storage_conditions: &str) -> Sample {
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-EXPORT".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: storage_conditions.to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        Sample::new(id.to_string(), metadata, None)
+    }
+
+    #[test]
+    fn test_csv_export_quotes_fields() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&sample("EXP-1", "Refrigerated, \"dark\"")).unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(write_samples(&db, ExportFormat::Csv, &mut out).unwrap(), 1);
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[1].starts_with("EXP-1,"));
+        assert!(lines[1].contains(",\"Refrigerated, \"\"dark\"\"\","));
+    }
+
+    #[test]
+    fn test_jsonl_export_can_be_reimported() {
+        let db = Database::in_memory().unwrap();
+        db.store_sample(&sample("EXP-1", "Frozen")).unwrap();
+        db.store_sample(&sample("EXP-2", "Frozen")).unwrap();
+
+        let mut out = Vec::new();
+        write_samples(&db, ExportFormat::Jsonl, &mut out).unwrap();
+
+        let copy = Database::in_memory().unwrap();
+        let report = copy.import_jsonl(out.as_slice(), crate::database::ConflictStrategy::Fail).unwrap();
+        assert_eq!(report.inserted(), 2);
+    }
+
+    #[test]
+    fn test_format_parsing() {
+        assert_eq!("CSV".parse::<ExportFormat>().unwrap(), ExportFormat::Csv);
+        assert_eq!("jsonl".parse::<ExportFormat>().unwrap(), ExportFormat::Jsonl);
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+}
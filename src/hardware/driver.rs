@@ -1,11 +1,12 @@
 use crate::hardware::{ImpinjSpeedwayReader, ZebraFX9600Reader};
-use crate::hardware::protocol::{ReaderProtocol, ReaderCommand};
+use crate::hardware::protocol::{BatchReadEntry, MemoryBank, ReaderConfigReport, ReaderProtocol, ReaderCommand};
+use crate::hardware::metrics::{LatencyReport, ReaderMetrics};
 use crate::hardware::simulator::{TagSimulator, SimulatedTag};
-use crate::sample::{Sample, SampleMetadata};
-use crate::encryption::RFIDEncryption;
+use crate::sample::{default_encryption, Sample, SampleMetadata};
 use chrono::Utc;
 use std::time::Duration;
-use std::sync::mpsc;
+use std::collections::VecDeque;
+use std::sync::{mpsc, Mutex};
 use std::thread;
 
 /// Event types for hardware driver logging
@@ -21,6 +22,35 @@ pub enum DriverEvent {
     ConfigurationChanged { reader_type: String, setting: String },
     NetworkDelay { reader_type: String, delay_ms: u64 },
     ProtocolMessage { reader_type: String, command: String, response_time_ms: u64 },
+    /// `read_tag_any` fell past `failed` readers; `served_by` is `None` if all failed
+    Failover { epc: String, failed: Vec<String>, served_by: Option<String> },
+}
+
+/// Driver events kept for [`HardwareDriver::events_for_correlation`]
+pub const EVENT_HISTORY_LIMIT: usize = 1024;
+
+/// A driver event with the correlation ID of the operation that caused it
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CorrelatedEvent {
+    pub correlation_id: Option<String>,
+    pub timestamp: chrono::DateTime<Utc>,
+    pub event: DriverEvent,
+}
+
+/// One of the driver's readers, for choosing read priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriverReader {
+    Impinj,
+    Zebra,
+}
+
+impl DriverReader {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DriverReader::Impinj => "Impinj Speedway",
+            DriverReader::Zebra => "Zebra FX9600",
+        }
+    }
 }
 
 /// Hardware driver that orchestrates RFID readers and logs events
@@ -29,20 +59,51 @@ pub struct HardwareDriver {
     zebra_reader: ZebraFX9600Reader,
     event_sender: Option<mpsc::Sender<DriverEvent>>,
     event_receiver: Option<mpsc::Receiver<DriverEvent>>,
+    metrics: ReaderMetrics,
+    read_priority: Vec<DriverReader>,
+    correlation_id: Option<String>,
+    history: Mutex<VecDeque<CorrelatedEvent>>,
 }
 
 impl HardwareDriver {
     pub fn new() -> Self {
+        Self::with_metrics(ReaderMetrics::new())
+    }
+    
+    /// Create a driver whose readers record response times into `metrics`
+    pub fn with_metrics(metrics: ReaderMetrics) -> Self {
         let (sender, receiver) = mpsc::channel();
         
         Self {
-            impinj_reader: ImpinjSpeedwayReader::new(),
-            zebra_reader: ZebraFX9600Reader::new(),
+            impinj_reader: ImpinjSpeedwayReader::new().with_metrics(metrics.clone()),
+            zebra_reader: ZebraFX9600Reader::new().with_metrics(metrics.clone()),
             event_sender: Some(sender),
             event_receiver: Some(receiver),
+            metrics,
+            read_priority: vec![DriverReader::Impinj, DriverReader::Zebra],
+            correlation_id: None,
+            history: Mutex::new(VecDeque::new()),
         }
     }
     
+    /// Order in which `read_tag_any` tries the readers
+    pub fn with_read_priority(mut self, priority: Vec<DriverReader>) -> Self {
+        self.read_priority = priority;
+        self
+    }
+    
+    /// Limit both readers to `BatchRead` commands of at most `max` EPCs
+    pub fn with_max_batch_size(mut self, max: usize) -> Self {
+        self.impinj_reader = self.impinj_reader.with_max_batch_size(max);
+        self.zebra_reader = self.zebra_reader.with_max_batch_size(max);
+        self
+    }
+    
+    /// Response-time histograms per reader type and command
+    pub fn latency_report(&self) -> LatencyReport {
+        self.metrics.report()
+    }
+    
     /// Initialize all readers
     pub fn initialize_all(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.log_event(DriverEvent::ReaderInitialized {
@@ -65,8 +126,6 @@ impl HardwareDriver {
     
     /// Setup simulated tags for demonstration
     pub fn setup_demo_tags(&mut self) {
-        let _encryption = RFIDEncryption::new(b"demo_master_key_32_bytes_long!!");
-        
         // Create sample data
         let metadata = SampleMetadata {
             batch_number: "DEMO-BATCH-001".to_string(),
@@ -79,7 +138,7 @@ impl HardwareDriver {
         };
         
         let sample = Sample::new("DEMO-SAMPLE-001".to_string(), metadata, Some("Warehouse A".to_string()));
-        let tag = sample.to_tag().unwrap();
+        let tag = sample.to_tag_with_key(default_encryption()).unwrap();
         let tag_data = tag.to_bytes().unwrap();
         
         // Add tags to both readers' simulators
@@ -234,8 +293,54 @@ impl HardwareDriver {
         }
     }
     
+    /// Read a tag from the first reader in priority order that succeeds.
+    /// If every reader fails, the error lists each reader's failure.
+    pub fn read_tag_any(&mut self, epc: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut failed = Vec::new();
+        let mut errors = Vec::new();
+        for reader in self.read_priority.clone() {
+            let result = match reader {
+                DriverReader::Impinj => self.read_tag_impinj(epc),
+                DriverReader::Zebra => self.read_tag_zebra(epc),
+            };
+            match result {
+                Ok(data) => {
+                    if !failed.is_empty() {
+                        self.log_event(DriverEvent::Failover {
+                            epc: epc.to_string(),
+                            failed,
+                            served_by: Some(reader.label().to_string()),
+                        });
+                    }
+                    return Ok(data);
+                }
+                Err(e) => {
+                    failed.push(reader.label().to_string());
+                    errors.push(format!("{}: {}", reader.label(), e));
+                }
+            }
+        }
+        
+        self.log_event(DriverEvent::Failover { epc: epc.to_string(), failed, served_by: None });
+        if errors.is_empty() {
+            return Err("No readers configured".into());
+        }
+        Err(format!("All readers failed to read {}: {}", epc, errors.join("; ")).into())
+    }
+    
     /// Write tag using Impinj reader
     pub fn write_tag_impinj(&mut self, epc: &str, data: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_tag_impinj_with_password(epc, data, None)
+    }
+    
+    /// Write tag using Impinj reader, forwarding `access_password` to a
+    /// protected tag. Rejected writes are logged as errors.
+    pub fn write_tag_impinj_with_password(
+        &mut self,
+        epc: &str,
+        data: Vec<u8>,
+        access_password: Option<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let start = std::time::Instant::now();
         
         let delay = self.impinj_reader.simulate_delay();
@@ -245,6 +350,7 @@ impl HardwareDriver {
             epc: epc.to_string(),
             bank: crate::hardware::protocol::MemoryBank::User,
             data: data.clone(),
+            access_password,
         };
         
         let response = self.impinj_reader.send_command(command)?;
@@ -267,8 +373,54 @@ impl HardwareDriver {
         }
     }
     
+    /// Permanently lock a tag against writes using the Impinj reader,
+    /// forwarding `access_password` to a protected tag
+    pub fn lock_tag_impinj(&mut self, epc: &str, access_password: Option<u32>) -> Result<(), Box<dyn std::error::Error>> {
+        let command = ReaderCommand::LockTag { epc: epc.to_string(), access_password };
+        let response = self.impinj_reader.send_command(command)?;
+        if response.success {
+            Ok(())
+        } else {
+            let error = response.error.unwrap_or_else(|| "Unknown error".to_string());
+            self.log_event(DriverEvent::Error {
+                reader_type: "Impinj Speedway".to_string(),
+                error: error.clone(),
+            });
+            Err(error.into())
+        }
+    }
+    
+    /// Read any number of tags from the Impinj reader, split into batches
+    /// the reader accepts. Entries come back in the order of `epcs`.
+    pub fn batch_read_impinj(&mut self, epcs: &[String]) -> Result<Vec<BatchReadEntry>, Box<dyn std::error::Error>> {
+        let result = batch_read_in_chunks(&mut self.impinj_reader, epcs);
+        self.log_batch_read("Impinj Speedway", &result);
+        result
+    }
+    
+    /// Read any number of tags from the Zebra reader, split into batches
+    /// the reader accepts. Entries come back in the order of `epcs`.
+    pub fn batch_read_zebra(&mut self, epcs: &[String]) -> Result<Vec<BatchReadEntry>, Box<dyn std::error::Error>> {
+        let result = batch_read_in_chunks(&mut self.zebra_reader, epcs);
+        self.log_batch_read("Zebra FX9600", &result);
+        result
+    }
+    
+    fn log_batch_read(&self, reader_type: &str, result: &Result<Vec<BatchReadEntry>, Box<dyn std::error::Error>>) {
+        match result {
+            Ok(entries) => {
+                for entry in entries {
+                    if let Some(data) = &entry.data {
+                        self.log_event(DriverEvent::TagRead { epc: entry.epc.clone(), data_size: data.len(), duration_ms: 0 });
+                    }
+                }
+            }
+            Err(e) => self.log_event(DriverEvent::Error { reader_type: reader_type.to_string(), error: e.to_string() }),
+        }
+    }
+    
     /// Get configuration from reader
-    pub fn get_reader_config(&mut self, reader_type: &str) -> Result<String, Box<dyn std::error::Error>> {
+    pub fn get_reader_config(&mut self, reader_type: &str) -> Result<ReaderConfigReport, Box<dyn std::error::Error>> {
         let response = match reader_type {
             "impinj" => self.impinj_reader.send_command(ReaderCommand::GetConfiguration)?,
             "zebra" => self.zebra_reader.send_command(ReaderCommand::GetConfiguration)?,
@@ -276,15 +428,43 @@ impl HardwareDriver {
         };
         
         if response.success {
-            let config = String::from_utf8(response.data.unwrap_or_default())?;
-            Ok(config)
+            Ok(ReaderConfigReport::decode(&response.data.unwrap_or_default())?)
         } else {
             Err(response.error.unwrap_or_else(|| "Failed to get configuration".to_string()).into())
         }
     }
     
+    /// Run `operation` with `id` as the correlation ID of every event it
+    /// causes. Outside such a scope, events take the correlation ID of the
+    /// API request being handled, if any.
+    pub fn correlated<R>(&mut self, id: &str, operation: impl FnOnce(&mut Self) -> R) -> R {
+        let previous = self.correlation_id.replace(id.to_string());
+        let result = operation(self);
+        self.correlation_id = previous;
+        result
+    }
+    
+    /// Recent events caused by the operation with correlation ID `id`,
+    /// oldest first. Unlike [`get_events`](Self::get_events) this does not
+    /// consume them.
+    pub fn events_for_correlation(&self, id: &str) -> Vec<CorrelatedEvent> {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        history.iter()
+            .filter(|e| e.correlation_id.as_deref() == Some(id))
+            .cloned()
+            .collect()
+    }
+    
     /// Log an event
     fn log_event(&self, event: DriverEvent) {
+        let correlation_id = self.correlation_id.clone().or_else(crate::logging::correlation_id);
+        {
+            let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+            if history.len() == EVENT_HISTORY_LIMIT {
+                history.pop_front();
+            }
+            history.push_back(CorrelatedEvent { correlation_id, timestamp: Utc::now(), event: event.clone() });
+        }
         if let Some(sender) = &self.event_sender {
             let _ = sender.send(event);
         }
@@ -337,6 +517,10 @@ impl HardwareDriver {
                 DriverEvent::ProtocolMessage { reader_type, command, response_time_ms } => {
                     println!("[PROTOCOL] {} command '{}' completed in {}ms", reader_type, command, response_time_ms);
                 }
+                DriverEvent::Failover { epc, failed, served_by } => match served_by {
+                    Some(reader) => println!("[FAILOVER] Tag {} read by {} after {} failed", epc, reader, failed.join(", ")),
+                    None => println!("[FAILOVER] Tag {} unreadable on {}", epc, failed.join(", ")),
+                },
             }
         }
         println!("=== End of Events ===\n");
@@ -382,9 +566,9 @@ impl HardwareDriver {
         // Get configurations
         println!("[3/5] Reading reader configurations...");
         let impinj_config = self.get_reader_config("impinj")?;
-        println!("  Impinj Config: {}", impinj_config);
+        println!("  Impinj Config: {}", serde_json::to_string(&impinj_config)?);
         let zebra_config = self.get_reader_config("zebra")?;
-        println!("  Zebra Config: {}", zebra_config);
+        println!("  Zebra Config: {}", serde_json::to_string(&zebra_config)?);
         
         // Perform inventory
         println!("[4/5] Performing inventory scan...");
@@ -416,6 +600,23 @@ impl HardwareDriver {
     }
 }
 
+/// Send `epcs` as consecutive `BatchRead` commands no larger than the
+/// reader's limit
+fn batch_read_in_chunks<R: ReaderProtocol>(reader: &mut R, epcs: &[String]) -> Result<Vec<BatchReadEntry>, Box<dyn std::error::Error>> {
+    let mut entries = Vec::with_capacity(epcs.len());
+    for chunk in epcs.chunks(reader.max_batch_size().max(1)) {
+        let response = reader.send_command(ReaderCommand::BatchRead {
+            epcs: chunk.to_vec(),
+            bank: MemoryBank::User,
+        })?;
+        if !response.success {
+            return Err(response.error.unwrap_or_else(|| "Batch read failed".to_string()).into());
+        }
+        entries.extend(BatchReadEntry::decode(&response.data.unwrap_or_default())?);
+    }
+    Ok(entries)
+}
+
 impl Default for HardwareDriver {
     fn default() -> Self {
         Self::new()
@@ -447,8 +648,8 @@ mod tests {
         driver.setup_demo_tags();
         
         // Tags should be added to simulators
-        assert!(driver.impinj_reader.get_simulator().get_tags().len() > 0);
-        assert!(driver.zebra_reader.get_simulator().get_tags().len() > 0);
+        assert!(!driver.impinj_reader.get_simulator().get_tags().is_empty());
+        assert!(!driver.zebra_reader.get_simulator().get_tags().is_empty());
     }
 
     #[test]
@@ -458,10 +659,10 @@ mod tests {
         driver.setup_demo_tags();
         
         let tags = driver.perform_inventory_scan().unwrap();
-        assert!(tags.len() > 0);
+        assert!(!tags.is_empty());
         
         let events = driver.get_events();
-        assert!(events.len() > 0);
+        assert!(!events.is_empty());
     }
 
     #[test]
@@ -478,6 +679,132 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_latency_report_tracks_commands() {
+        let mut driver = HardwareDriver::new();
+        assert!(driver.initialize_all().is_ok());
+        driver.setup_demo_tags();
+        let _ = driver.read_tag_zebra("EPC-DEMO-001");
+        
+        let report = driver.latency_report();
+        assert_eq!(report.entry("Impinj Speedway", "Initialize").unwrap().count, 1);
+        let read = report.entry("Zebra FX9600", "ReadTag").unwrap();
+        assert_eq!(read.count, 1);
+        assert!(read.p95_ms.is_some());
+    }
+
+    #[test]
+    fn test_batch_read_splits_into_legal_batches() {
+        let mut driver = HardwareDriver::new().with_max_batch_size(4);
+        assert!(driver.initialize_all().is_ok());
+        driver.setup_demo_tags();
+        let epcs: Vec<String> = (0..10).map(|i| format!("EPC-DEMO-{:03}", i)).collect();
+        
+        let entries = driver.batch_read_zebra(&epcs).unwrap();
+        let read: Vec<&str> = entries.iter().map(|e| e.epc.as_str()).collect();
+        assert_eq!(read, epcs.iter().map(String::as_str).collect::<Vec<_>>());
+        assert_eq!(driver.latency_report().entry("Zebra FX9600", "BatchRead").unwrap().count, 3);
+    }
+
+    #[test]
+    fn test_oversized_batch_is_rejected() {
+        let mut reader = ZebraFX9600Reader::new().with_max_batch_size(2);
+        reader.get_simulator_mut().add_tag(SimulatedTag::new("EPC-0".to_string(), "TAG-0".to_string(), vec![1]));
+        reader.send_command(ReaderCommand::Initialize).unwrap();
+        let epcs: Vec<String> = (0..3).map(|i| format!("EPC-{}", i)).collect();
+        
+        let response = reader.send_command(ReaderCommand::BatchRead { epcs, bank: MemoryBank::User }).unwrap();
+        assert!(!response.success);
+        assert_eq!(response.error.unwrap(), "Batch of 3 EPCs exceeds the maximum of 2");
+        assert!(reader.get_simulator().get_tags().iter().all(|t| t.read_count == 0));
+    }
+
+    #[test]
+    fn test_read_tag_any_fails_over_to_secondary() {
+        let mut driver = HardwareDriver::new();
+        assert!(driver.initialize_all().is_ok());
+        driver.zebra_reader.get_simulator_mut()
+            .add_tag(SimulatedTag::new("EPC-ZEBRA-ONLY".to_string(), "TAG-Z".to_string(), vec![7, 7]));
+        
+        assert_eq!(driver.read_tag_any("EPC-ZEBRA-ONLY").unwrap(), vec![0x03, 7, 7]);
+        let failover = driver.get_events().into_iter().find_map(|e| match e {
+            DriverEvent::Failover { failed, served_by, .. } => Some((failed, served_by)),
+            _ => None,
+        });
+        assert_eq!(failover, Some((vec!["Impinj Speedway".to_string()], Some("Zebra FX9600".to_string()))));
+        
+        // Reversed priority reads from Zebra first without failing over
+        let mut driver = driver.with_read_priority(vec![DriverReader::Zebra, DriverReader::Impinj]);
+        assert!(driver.read_tag_any("EPC-ZEBRA-ONLY").is_ok());
+        assert!(!driver.get_events().iter().any(|e| matches!(e, DriverEvent::Failover { .. })));
+    }
+
+    #[test]
+    fn test_write_requires_access_password() {
+        let mut driver = HardwareDriver::new();
+        assert!(driver.initialize_all().is_ok());
+        driver.impinj_reader.get_simulator_mut().add_tag(
+            SimulatedTag::new("EPC-LOCKED".to_string(), "TAG-L".to_string(), vec![1]).with_access_password(0xC0FFEE),
+        );
+        driver.get_events();
+        
+        let err = driver.write_tag_impinj_with_password("EPC-LOCKED", vec![2], Some(0xBAD)).unwrap_err();
+        assert!(err.to_string().contains("wrong access password"), "{}", err);
+        assert!(driver.write_tag_impinj("EPC-LOCKED", vec![2]).is_err());
+        let rejected = driver.get_events().into_iter().filter(|e| match e {
+            DriverEvent::Error { error, .. } => error.contains("Access denied to tag EPC-LOCKED"),
+            _ => false,
+        }).count();
+        assert_eq!(rejected, 2);
+        
+        driver.write_tag_impinj_with_password("EPC-LOCKED", vec![3], Some(0xC0FFEE)).unwrap();
+        assert_eq!(driver.read_tag_impinj("EPC-LOCKED").unwrap(), vec![3]);
+        
+        assert!(driver.lock_tag_impinj("EPC-LOCKED", None).is_err());
+        driver.lock_tag_impinj("EPC-LOCKED", Some(0xC0FFEE)).unwrap();
+        assert!(driver.write_tag_impinj_with_password("EPC-LOCKED", vec![4], Some(0xC0FFEE)).is_err());
+    }
+
+    #[test]
+    fn test_events_carry_operation_correlation_id() {
+        let mut driver = HardwareDriver::new();
+        assert!(driver.initialize_all().is_ok());
+        
+        let result = driver.correlated("op-read-1", |driver| driver.read_tag_impinj("EPC-MISSING"));
+        assert!(result.is_err());
+        driver.read_tag_impinj("EPC-MISSING").unwrap_err();
+        
+        let events = driver.events_for_correlation("op-read-1");
+        assert!(events.iter().all(|e| e.correlation_id.as_deref() == Some("op-read-1")));
+        assert!(matches!(events.first().unwrap().event, DriverEvent::NetworkDelay { .. }));
+        assert!(matches!(events.last().unwrap().event, DriverEvent::Error { .. }));
+        // Only the correlated read's events, not the initialization or the uncorrelated read
+        assert_eq!(events.len(), 3);
+        assert!(driver.events_for_correlation("op-other").is_empty());
+    }
+
+    #[test]
+    fn test_events_take_request_correlation_id() {
+        let mut driver = HardwareDriver::new();
+        assert!(driver.initialize_all().is_ok());
+        
+        let read = crate::logging::with_correlation_id("req-42".to_string(), async {
+            driver.read_tag_zebra("EPC-MISSING")
+        });
+        assert!(actix_web::rt::System::new().block_on(read).is_err());
+        assert!(!driver.events_for_correlation("req-42").is_empty());
+    }
+
+    #[test]
+    fn test_read_tag_any_combines_errors_when_all_fail() {
+        let mut driver = HardwareDriver::new();
+        assert!(driver.initialize_all().is_ok());
+        
+        let err = driver.read_tag_any("EPC-NOWHERE").unwrap_err().to_string();
+        assert!(err.starts_with("All readers failed to read EPC-NOWHERE"), "{}", err);
+        assert!(err.contains("Impinj Speedway: ") && err.contains("Zebra FX9600: "), "{}", err);
+    }
+
     #[test]
     fn test_event_logging() {
         let mut driver = HardwareDriver::new();
@@ -1,10 +1,15 @@
-use crate::hardware::protocol::{ReaderProtocol, ReaderCommand, ProtocolResponse};
+use crate::hardware::protocol::{batch_too_large, BatchReadEntry, ReaderConfigReport, ProtocolResponse, ReaderCommand, ReaderProtocol, DEFAULT_MAX_BATCH_SIZE};
+use crate::hardware::metrics::ReaderMetrics;
 use crate::hardware::simulator::TagSimulator;
 use crate::reader::{RFIDReader, ReaderConfig, ReaderCapabilities, ReaderFrequency};
+use crate::region::RegulatoryRegion;
 use crate::tag::TagData;
 use crate::error::{SampleGuardError, Result};
 use std::time::Duration;
 
+/// Reader type label used in metrics
+const READER_TYPE: &str = "Impinj Speedway";
+
 /// Impinj Speedway Reader emulation
 /// Implements LLRP (Low Level Reader Protocol) simulation
 pub struct ImpinjSpeedwayReader {
@@ -13,6 +18,9 @@ pub struct ImpinjSpeedwayReader {
     simulator: TagSimulator,
     connected: bool,
     protocol_version: String,
+    metrics: ReaderMetrics,
+    max_batch_size: usize,
+    reader_id: String,
 }
 
 impl ImpinjSpeedwayReader {
@@ -23,6 +31,7 @@ impl ImpinjSpeedwayReader {
                 power_level: 30,
                 read_timeout_ms: 2000,
                 antenna_gain: 6.0,
+                region: None,
             },
             capabilities: ReaderCapabilities {
                 supports_encryption: true,
@@ -39,6 +48,9 @@ impl ImpinjSpeedwayReader {
                 .with_network_delay(Duration::from_millis(8)),
             connected: false,
             protocol_version: "LLRP-1.0.1".to_string(),
+            metrics: ReaderMetrics::new(),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            reader_id: format!("SPEEDWAY-{:06X}", rand::random::<u32>()),
         }
     }
     
@@ -47,6 +59,32 @@ impl ImpinjSpeedwayReader {
         self
     }
     
+    /// Record command response times into a shared registry
+    pub fn with_metrics(mut self, metrics: ReaderMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+    
+    pub fn metrics(&self) -> &ReaderMetrics {
+        &self.metrics
+    }
+    
+    /// Reject `BatchRead` commands naming more than `max` EPCs
+    pub fn with_max_batch_size(mut self, max: usize) -> Self {
+        self.max_batch_size = max.max(1);
+        self
+    }
+    
+    /// Operate under `region`, clamping the power level to its limit
+    pub fn with_region(mut self, region: RegulatoryRegion) -> Self {
+        self.config.apply_region(region);
+        self
+    }
+    
+    pub fn get_reader_id(&self) -> &str {
+        &self.reader_id
+    }
+    
     pub fn get_protocol_version(&self) -> &str {
         &self.protocol_version
     }
@@ -60,8 +98,8 @@ impl ImpinjSpeedwayReader {
     }
 }
 
-impl ReaderProtocol for ImpinjSpeedwayReader {
-    fn send_command(&mut self, command: ReaderCommand) -> Result<ProtocolResponse> {
+impl ImpinjSpeedwayReader {
+    fn execute_command(&mut self, command: ReaderCommand) -> Result<ProtocolResponse> {
         let start = std::time::Instant::now();
         
         match command {
@@ -73,10 +111,10 @@ impl ReaderProtocol for ImpinjSpeedwayReader {
                 ))
             }
             _ if !self.connected => {
-                return Ok(ProtocolResponse::error(
+                Ok(ProtocolResponse::error(
                     "Reader not connected".to_string(),
                     start.elapsed().as_millis() as u64,
-                ));
+                ))
             }
             ReaderCommand::StartInventory => {
                 Ok(ProtocolResponse::success(
@@ -91,9 +129,9 @@ impl ReaderProtocol for ImpinjSpeedwayReader {
                 ))
             }
             ReaderCommand::ReadTag { epc, .. } => {
-                match self.simulator.read_tag(&epc) {
+                match self.simulator.read_tag_with(&epc, <[u8]>::to_vec) {
                     Ok(data) => Ok(ProtocolResponse::success(
-                        data.as_bytes().to_vec(),
+                        data,
                         start.elapsed().as_millis() as u64,
                     )),
                     Err(e) => Ok(ProtocolResponse::error(
@@ -102,8 +140,8 @@ impl ReaderProtocol for ImpinjSpeedwayReader {
                     )),
                 }
             }
-            ReaderCommand::WriteTag { epc, data, .. } => {
-                match self.simulator.write_tag(&epc, data) {
+            ReaderCommand::WriteTag { epc, data, access_password, .. } => {
+                match self.simulator.write_tag_with_password(&epc, data, access_password) {
                     Ok(_) => Ok(ProtocolResponse::success(
                         b"Write successful".to_vec(),
                         start.elapsed().as_millis() as u64,
@@ -114,24 +152,57 @@ impl ReaderProtocol for ImpinjSpeedwayReader {
                     )),
                 }
             }
+            ReaderCommand::LockTag { epc, access_password } => {
+                match self.simulator.lock_tag(&epc, access_password) {
+                    Ok(_) => Ok(ProtocolResponse::success(
+                        b"Lock successful".to_vec(),
+                        start.elapsed().as_millis() as u64,
+                    )),
+                    Err(e) => Ok(ProtocolResponse::error(
+                        e.to_string(),
+                        start.elapsed().as_millis() as u64,
+                    )),
+                }
+            }
             ReaderCommand::GetConfiguration => {
-                let config_json = serde_json::json!({
-                    "power_level": self.config.power_level,
-                    "frequency": format!("{:?}", self.config.frequency),
-                    "antenna_gain": self.config.antenna_gain,
-                });
+                let report = ReaderConfigReport::new(&self.reader_id, &*self, &self.config, &self.capabilities);
                 Ok(ProtocolResponse::success(
-                    serde_json::to_vec(&config_json).unwrap(),
+                    report.encode()?,
                     start.elapsed().as_millis() as u64,
                 ))
             }
             ReaderCommand::SetConfiguration { power, antenna: _ } => {
-                self.config.power_level = power;
+                if let Err(e) = self.config.set_power_level(power) {
+                    return Ok(ProtocolResponse::error(
+                        e.to_string(),
+                        start.elapsed().as_millis() as u64,
+                    ));
+                }
                 Ok(ProtocolResponse::success(
                     b"Configuration updated".to_vec(),
                     start.elapsed().as_millis() as u64,
                 ))
             }
+            ReaderCommand::BatchRead { epcs, .. } => {
+                // Checked before touching any tag, so oversized batches cost nothing
+                if epcs.len() > self.max_batch_size {
+                    return Ok(ProtocolResponse::error(
+                        batch_too_large(epcs.len(), self.max_batch_size),
+                        start.elapsed().as_millis() as u64,
+                    ));
+                }
+                let entries: Vec<BatchReadEntry> = epcs
+                    .into_iter()
+                    .map(|epc| match self.simulator.read_tag_with(&epc, <[u8]>::to_vec) {
+                        Ok(data) => BatchReadEntry::read(epc, data),
+                        Err(e) => BatchReadEntry::failed(epc, e.to_string()),
+                    })
+                    .collect();
+                Ok(ProtocolResponse::success(
+                    BatchReadEntry::encode(&entries)?,
+                    start.elapsed().as_millis() as u64,
+                ))
+            }
             ReaderCommand::GetStatus => {
                 let status_json = serde_json::json!({
                     "connected": self.connected,
@@ -145,6 +216,15 @@ impl ReaderProtocol for ImpinjSpeedwayReader {
             }
         }
     }
+}
+
+impl ReaderProtocol for ImpinjSpeedwayReader {
+    fn send_command(&mut self, command: ReaderCommand) -> Result<ProtocolResponse> {
+        let name = command.name();
+        let response = self.execute_command(command)?;
+        self.metrics.record(READER_TYPE, name, response.response_time_ms);
+        Ok(response)
+    }
     
     fn protocol_name(&self) -> &str {
         "LLRP"
@@ -154,6 +234,10 @@ impl ReaderProtocol for ImpinjSpeedwayReader {
         &self.protocol_version
     }
     
+    fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
+    
     fn simulate_delay(&self) -> Duration {
         Duration::from_millis(8) // Impinj network delay
     }
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Upper bound of the first latency bucket in milliseconds
+pub const FIRST_BUCKET_MS: u64 = 1;
+/// Number of bounded latency buckets; bounds double from [`FIRST_BUCKET_MS`]
+pub const BUCKET_COUNT: usize = 14;
+
+/// Latency histogram with exponentially growing bucket bounds
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyHistogram {
+    /// Inclusive upper bound of each bucket
+    bounds_ms: Vec<u64>,
+    /// One count per bound, plus an overflow bucket
+    counts: Vec<u64>,
+    count: u64,
+    sum_ms: u64,
+    max_ms: u64,
+}
+
+/// One bucket of a histogram, for reporting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyBucket {
+    /// Inclusive upper bound; `None` for the overflow bucket
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+impl LatencyHistogram {
+    /// Histogram with `buckets` bounds starting at `first_ms`, each `factor` times the previous
+    pub fn exponential(first_ms: u64, factor: u64, buckets: usize) -> Self {
+        let bounds_ms: Vec<u64> = std::iter::successors(Some(first_ms.max(1)), |b| b.checked_mul(factor.max(2)))
+            .take(buckets.max(1))
+            .collect();
+        Self {
+            counts: vec![0; bounds_ms.len() + 1],
+            bounds_ms,
+            count: 0,
+            sum_ms: 0,
+            max_ms: 0,
+        }
+    }
+
+    pub fn record(&mut self, latency_ms: u64) {
+        let index = self.bounds_ms.partition_point(|&bound| bound < latency_ms);
+        self.counts[index] += 1;
+        self.count += 1;
+        self.sum_ms = self.sum_ms.saturating_add(latency_ms);
+        self.max_ms = self.max_ms.max(latency_ms);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean_ms(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum_ms as f64 / self.count as f64)
+    }
+
+    pub fn max_ms(&self) -> u64 {
+        self.max_ms
+    }
+
+    /// Estimate the `p`-th percentile (0-100) by interpolating within the
+    /// bucket that holds it
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * self.count as f64).max(1.0);
+
+        let mut below = 0u64;
+        for (index, &count) in self.counts.iter().enumerate() {
+            if count == 0 || ((below + count) as f64) < rank {
+                below += count;
+                continue;
+            }
+            let lower = if index == 0 { 0 } else { self.bounds_ms[index - 1] } as f64;
+            let upper = self.bounds_ms.get(index).copied().unwrap_or(self.max_ms) as f64;
+            let fraction = (rank - below as f64) / count as f64;
+            return Some((lower + (upper - lower) * fraction).min(self.max_ms as f64));
+        }
+        Some(self.max_ms as f64)
+    }
+
+    pub fn buckets(&self) -> Vec<LatencyBucket> {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(index, &count)| LatencyBucket {
+                le_ms: self.bounds_ms.get(index).copied(),
+                count,
+            })
+            .collect()
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::exponential(FIRST_BUCKET_MS, 2, BUCKET_COUNT)
+    }
+}
+
+/// Latency summary for one reader type and command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyEntry {
+    pub reader: String,
+    pub command: String,
+    pub count: u64,
+    pub mean_ms: Option<f64>,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: u64,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+/// Latency summaries ordered by reader and command
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub entries: Vec<LatencyEntry>,
+}
+
+impl LatencyReport {
+    pub fn entry(&self, reader: &str, command: &str) -> Option<&LatencyEntry> {
+        self.entries.iter().find(|e| e.reader == reader && e.command == command)
+    }
+}
+
+/// Shared response-time histograms keyed by reader type and command
+#[derive(Debug, Clone, Default)]
+pub struct ReaderMetrics {
+    inner: Arc<Mutex<HashMap<(String, String), LatencyHistogram>>>,
+}
+
+impl ReaderMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, reader: &str, command: &str, latency_ms: u64) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner
+            .entry((reader.to_string(), command.to_string()))
+            .or_default()
+            .record(latency_ms);
+    }
+
+    pub fn report(&self) -> LatencyReport {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries: Vec<LatencyEntry> = inner
+            .iter()
+            .map(|((reader, command), histogram)| LatencyEntry {
+                reader: reader.clone(),
+                command: command.clone(),
+                count: histogram.count(),
+                mean_ms: histogram.mean_ms(),
+                p50_ms: histogram.percentile(50.0),
+                p95_ms: histogram.percentile(95.0),
+                p99_ms: histogram.percentile(99.0),
+                max_ms: histogram.max_ms(),
+                buckets: histogram.buckets(),
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.reader, &a.command).cmp(&(&b.reader, &b.command)));
+        LatencyReport { entries }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_distribution_bucket_counts() {
+        let mut histogram = LatencyHistogram::default();
+        for _ in 0..50 {
+            histogram.record(3);
+        }
+        for _ in 0..40 {
+            histogram.record(10);
+        }
+        for _ in 0..10 {
+            histogram.record(100);
+        }
+
+        let buckets = histogram.buckets();
+        let count_for = |le: u64| buckets.iter().find(|b| b.le_ms == Some(le)).unwrap().count;
+        assert_eq!(count_for(4), 50);
+        assert_eq!(count_for(16), 40);
+        assert_eq!(count_for(128), 10);
+        assert_eq!(buckets.iter().map(|b| b.count).sum::<u64>(), 100);
+
+        let p50 = histogram.percentile(50.0).unwrap();
+        assert!((2.0..=4.0).contains(&p50), "p50 = {}", p50);
+        let p95 = histogram.percentile(95.0).unwrap();
+        assert!((64.0..=100.0).contains(&p95), "p95 = {}", p95);
+        assert_eq!(histogram.mean_ms(), Some(15.5));
+    }
+
+    #[test]
+    fn test_bucket_bounds_are_inclusive_and_overflow() {
+        let mut histogram = LatencyHistogram::exponential(1, 2, 3);
+        histogram.record(0);
+        histogram.record(2);
+        histogram.record(4);
+        histogram.record(5000);
+
+        let counts: Vec<u64> = histogram.buckets().iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![1, 1, 1, 1]);
+        assert_eq!(histogram.percentile(100.0), Some(5000.0));
+        assert_eq!(LatencyHistogram::default().percentile(95.0), None);
+    }
+
+    #[test]
+    fn test_report_groups_by_reader_and_command() {
+        let metrics = ReaderMetrics::new();
+        metrics.record("Zebra FX9600", "ReadTag", 12);
+        metrics.record("Impinj Speedway", "ReadTag", 20);
+        metrics.record("Impinj Speedway", "ReadTag", 30);
+
+        let report = metrics.report();
+        assert_eq!(report.entries.len(), 2);
+        assert_eq!(report.entries[0].reader, "Impinj Speedway");
+        assert_eq!(report.entry("Impinj Speedway", "ReadTag").unwrap().count, 2);
+    }
+}
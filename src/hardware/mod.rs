@@ -3,10 +3,12 @@ pub mod zebra;
 pub mod simulator;
 pub mod protocol;
 pub mod driver;
+pub mod metrics;
 
 pub use impinj::ImpinjSpeedwayReader;
 pub use zebra::ZebraFX9600Reader;
-pub use simulator::{TagSimulator, SimulatedTag};
-pub use protocol::{ReaderProtocol, ProtocolMessage, ReaderCommand};
-pub use driver::HardwareDriver;
+pub use simulator::{TagSimulator, SimulatedTag, ScanStrategy, AntennaPolarization};
+pub use protocol::{ReaderProtocol, ProtocolMessage, ReaderCommand, ReaderConfigReport};
+pub use driver::{HardwareDriver, DriverReader};
+pub use metrics::{LatencyHistogram, LatencyReport, ReaderMetrics};
 
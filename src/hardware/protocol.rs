@@ -1,4 +1,5 @@
-use crate::error::Result;
+use crate::error::{SampleGuardError, Result};
+use crate::reader::{ReaderCapabilities, ReaderConfig, ReaderFrequency};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -13,14 +14,49 @@ pub enum ReaderCommand {
     StopInventory,
     /// Read tag data
     ReadTag { epc: String, bank: MemoryBank },
-    /// Write tag data
-    WriteTag { epc: String, bank: MemoryBank, data: Vec<u8> },
+    /// Write tag data, supplying the access password of a protected tag
+    WriteTag {
+        epc: String,
+        bank: MemoryBank,
+        data: Vec<u8>,
+        #[serde(default)]
+        access_password: Option<u32>,
+    },
+    /// Permanently lock tag memory against writes
+    LockTag {
+        epc: String,
+        #[serde(default)]
+        access_password: Option<u32>,
+    },
     /// Get reader configuration
     GetConfiguration,
     /// Set reader configuration
     SetConfiguration { power: u8, antenna: u8 },
     /// Get reader status
     GetStatus,
+    /// Read several tags in one command, answered with encoded [`BatchReadEntry`]s
+    BatchRead { epcs: Vec<String>, bank: MemoryBank },
+}
+
+/// Largest `BatchRead` a reader accepts unless configured otherwise
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 256;
+
+impl ReaderCommand {
+    /// Command name without its arguments, used as a metrics label
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReaderCommand::Initialize => "Initialize",
+            ReaderCommand::StartInventory => "StartInventory",
+            ReaderCommand::StopInventory => "StopInventory",
+            ReaderCommand::ReadTag { .. } => "ReadTag",
+            ReaderCommand::WriteTag { .. } => "WriteTag",
+            ReaderCommand::LockTag { .. } => "LockTag",
+            ReaderCommand::GetConfiguration => "GetConfiguration",
+            ReaderCommand::SetConfiguration { .. } => "SetConfiguration",
+            ReaderCommand::GetStatus => "GetStatus",
+            ReaderCommand::BatchRead { .. } => "BatchRead",
+        }
+    }
 }
 
 /// Memory bank types
@@ -53,6 +89,90 @@ pub trait ReaderProtocol: Send + Sync {
     
     /// Simulate network delay
     fn simulate_delay(&self) -> Duration;
+    
+    /// Most EPCs accepted in one `BatchRead`
+    fn max_batch_size(&self) -> usize {
+        DEFAULT_MAX_BATCH_SIZE
+    }
+}
+
+/// Outcome for one EPC of a `BatchRead`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchReadEntry {
+    pub epc: String,
+    pub data: Option<Vec<u8>>,
+    pub error: Option<String>,
+}
+
+impl BatchReadEntry {
+    pub fn read(epc: String, data: Vec<u8>) -> Self {
+        Self { epc, data: Some(data), error: None }
+    }
+
+    pub fn failed(epc: String, error: String) -> Self {
+        Self { epc, data: None, error: Some(error) }
+    }
+
+    /// Response payload for a batch
+    pub fn encode(entries: &[BatchReadEntry]) -> Result<Vec<u8>> {
+        serde_json::to_vec(entries)
+            .map_err(|e| SampleGuardError::ReaderError(format!("Batch encoding failed: {}", e)))
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Vec<BatchReadEntry>> {
+        serde_json::from_slice(data)
+            .map_err(|e| SampleGuardError::ReaderError(format!("Malformed batch response: {}", e)))
+    }
+}
+
+/// Error message for a `BatchRead` over the reader's limit
+pub fn batch_too_large(requested: usize, max: usize) -> String {
+    format!("Batch of {} EPCs exceeds the maximum of {}", requested, max)
+}
+
+/// Reader configuration as answered to `GetConfiguration`, the same
+/// fields for every vendor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReaderConfigReport {
+    pub reader_id: String,
+    pub protocol: String,
+    pub protocol_version: String,
+    /// Conducted power in dBm
+    pub power: u8,
+    pub frequency: ReaderFrequency,
+    /// Antenna gain in dBi
+    pub antenna_gain: f32,
+    pub capabilities: ReaderCapabilities,
+}
+
+impl ReaderConfigReport {
+    pub fn new(
+        reader_id: &str,
+        protocol: &dyn ReaderProtocol,
+        config: &ReaderConfig,
+        capabilities: &ReaderCapabilities,
+    ) -> Self {
+        Self {
+            reader_id: reader_id.to_string(),
+            protocol: protocol.protocol_name().to_string(),
+            protocol_version: protocol.protocol_version().to_string(),
+            power: config.power_level,
+            frequency: config.frequency,
+            antenna_gain: config.antenna_gain,
+            capabilities: capabilities.clone(),
+        }
+    }
+
+    /// Response payload for `GetConfiguration`
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(self)
+            .map_err(|e| SampleGuardError::ReaderError(format!("Configuration encoding failed: {}", e)))
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        serde_json::from_slice(data)
+            .map_err(|e| SampleGuardError::ReaderError(format!("Malformed configuration response: {}", e)))
+    }
 }
 
 /// Protocol response
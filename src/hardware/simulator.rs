@@ -1,7 +1,7 @@
 use crate::tag::TagData;
 use crate::error::{SampleGuardError, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 
 /// Simulated RFID tag
@@ -15,6 +15,18 @@ pub struct SimulatedTag {
     pub read_count: u64,
     pub last_read: Option<chrono::DateTime<chrono::Utc>>,
     pub error_rate: f32, // 0.0 to 1.0, probability of read error
+    /// Signal seen by further antenna ports besides `antenna`
+    #[serde(default)]
+    pub other_antennas: Vec<(u8, i16)>,
+    /// Gen2 access password that writes and locks must supply; unprotected when `None`
+    #[serde(default)]
+    pub access_password: Option<u32>,
+    /// Set by a lock, after which the tag memory can no longer be written
+    #[serde(default)]
+    pub locked: bool,
+    /// Angle of the tag's dipole in degrees, in the plane facing the antenna
+    #[serde(default)]
+    pub orientation: f32,
 }
 
 impl SimulatedTag {
@@ -28,11 +40,15 @@ impl SimulatedTag {
             read_count: 0,
             last_read: None,
             error_rate: 0.0,
+            other_antennas: Vec::new(),
+            access_password: None,
+            locked: false,
+            orientation: 0.0,
         }
     }
     
     pub fn with_error_rate(mut self, rate: f32) -> Self {
-        self.error_rate = rate.max(0.0).min(1.0);
+        self.error_rate = rate.clamp(0.0, 1.0);
         self
     }
     
@@ -46,18 +62,114 @@ impl SimulatedTag {
         self
     }
     
+    /// Make the tag visible to a further antenna port at `rssi`
+    pub fn with_antenna_rssi(mut self, antenna: u8, rssi: i16) -> Self {
+        self.other_antennas.retain(|(port, _)| *port != antenna);
+        self.other_antennas.push((antenna, rssi));
+        self
+    }
+    
+    pub fn with_orientation(mut self, degrees: f32) -> Self {
+        self.orientation = degrees;
+        self
+    }
+    
+    /// Protect writes and locks with a Gen2 access password
+    pub fn with_access_password(mut self, password: u32) -> Self {
+        self.access_password = Some(password);
+        self
+    }
+    
+    /// Fail unless `password` matches the tag's access password, if it has one
+    pub fn check_access(&self, password: Option<u32>) -> Result<()> {
+        match self.access_password {
+            Some(expected) if password != Some(expected) => Err(SampleGuardError::TagMemoryError(format!(
+                "Access denied to tag {}: {} access password",
+                self.epc,
+                if password.is_none() { "missing" } else { "wrong" }
+            ))),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Signal strength at antenna `port`, if the tag is visible there
+    pub fn rssi_on(&self, port: u8) -> Option<i16> {
+        if port == self.antenna {
+            return Some(self.rssi);
+        }
+        self.other_antennas.iter().find(|(p, _)| *p == port).map(|(_, rssi)| *rssi)
+    }
+    
     pub fn should_error(&self) -> bool {
+        self.should_error_under(AntennaPolarization::Circular)
+    }
+    
+    /// Chance that an operation through an antenna with `polarization`
+    /// succeeds: the tag's own reliability scaled by how much of the
+    /// antenna's field its dipole couples to
+    pub fn read_success_probability(&self, polarization: AntennaPolarization) -> f32 {
+        (1.0 - self.error_rate) * polarization.coupling(self.orientation)
+    }
+    
+    pub fn should_error_under(&self, polarization: AntennaPolarization) -> bool {
         use rand::Rng;
-        rand::thread_rng().gen::<f32>() < self.error_rate
+        rand::thread_rng().gen::<f32>() >= self.read_success_probability(polarization)
+    }
+}
+
+/// Fraction of a linearly polarized field that still couples into a tag
+/// turned square to it (20 dB cross-polarization discrimination)
+const CROSS_POLARIZATION_COUPLING: f32 = 0.01;
+
+/// Polarization of the reader antennas
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AntennaPolarization {
+    /// Field rotates through every angle, so tag orientation does not matter
+    #[default]
+    Circular,
+    /// Field along `angle` degrees; tags couple by the cosine squared of
+    /// their misalignment with it
+    Linear { angle: f32 },
+}
+
+impl AntennaPolarization {
+    /// Share of the field a tag dipole at `orientation` degrees couples to
+    pub fn coupling(&self, orientation: f32) -> f32 {
+        match self {
+            AntennaPolarization::Circular => 1.0,
+            AntennaPolarization::Linear { angle } => {
+                let misalignment = (orientation - angle).to_radians();
+                misalignment.cos().powi(2).max(CROSS_POLARIZATION_COUPLING)
+            }
+        }
     }
 }
 
+/// Weakest signal a scan still picks up, in dBm
+const MIN_SCAN_RSSI: i16 = -80;
+
+/// Which antenna ports `TagSimulator::scan_tags` listens on
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ScanStrategy {
+    /// Every tag in range is seen, whatever port it faces
+    #[default]
+    Unrestricted,
+    /// Only tags visible to one port are seen
+    SingleAntenna(u8),
+    /// Listen on each port in turn for `dwell`, repeating until the scan
+    /// duration is used up (always at least one full round). Tags seen by
+    /// several ports are reported on the strongest.
+    Hopping { antennas: Vec<u8>, dwell: Duration },
+}
+
 /// Tag simulator for realistic RFID behavior
 pub struct TagSimulator {
     tags: HashMap<String, SimulatedTag>,
     read_delay: Duration,
     write_delay: Duration,
     network_delay: Duration,
+    scan_strategy: ScanStrategy,
+    polarization: AntennaPolarization,
 }
 
 impl TagSimulator {
@@ -67,6 +179,8 @@ impl TagSimulator {
             read_delay: Duration::from_millis(10),
             write_delay: Duration::from_millis(50),
             network_delay: Duration::from_millis(5),
+            scan_strategy: ScanStrategy::default(),
+            polarization: AntennaPolarization::default(),
         }
     }
     
@@ -85,6 +199,24 @@ impl TagSimulator {
         self
     }
     
+    pub fn with_scan_strategy(mut self, strategy: ScanStrategy) -> Self {
+        self.scan_strategy = strategy;
+        self
+    }
+    
+    pub fn scan_strategy(&self) -> &ScanStrategy {
+        &self.scan_strategy
+    }
+    
+    pub fn with_polarization(mut self, polarization: AntennaPolarization) -> Self {
+        self.polarization = polarization;
+        self
+    }
+    
+    pub fn polarization(&self) -> AntennaPolarization {
+        self.polarization
+    }
+    
     /// Add a simulated tag
     pub fn add_tag(&mut self, tag: SimulatedTag) {
         self.tags.insert(tag.epc.clone(), tag);
@@ -102,6 +234,11 @@ impl TagSimulator {
     
     /// Simulate reading a tag
     pub fn read_tag(&mut self, epc: &str) -> Result<TagData> {
+        self.read_tag_with(epc, |data| TagData::new(data.to_vec()))
+    }
+    
+    /// Simulate reading a tag, handing its memory to `f` without copying it
+    pub fn read_tag_with<R>(&mut self, epc: &str, f: impl FnOnce(&[u8]) -> R) -> Result<R> {
         // Simulate network delay
         std::thread::sleep(self.network_delay);
         
@@ -109,7 +246,7 @@ impl TagSimulator {
             .ok_or_else(|| SampleGuardError::ReaderError(format!("Tag {} not found", epc)))?;
         
         // Check for read error
-        if tag.should_error() {
+        if tag.should_error_under(self.polarization) {
             return Err(SampleGuardError::ReaderError("Tag read error (simulated)".to_string()));
         }
         
@@ -119,19 +256,28 @@ impl TagSimulator {
         tag.read_count += 1;
         tag.last_read = Some(chrono::Utc::now());
         
-        Ok(TagData::new(tag.data.clone()))
+        Ok(f(&tag.data))
     }
     
     /// Simulate writing to a tag
     pub fn write_tag(&mut self, epc: &str, data: Vec<u8>) -> Result<()> {
+        self.write_tag_with_password(epc, data, None)
+    }
+    
+    /// Simulate writing to a tag, supplying `password` to a protected one
+    pub fn write_tag_with_password(&mut self, epc: &str, data: Vec<u8>, password: Option<u32>) -> Result<()> {
         // Simulate network delay
         std::thread::sleep(self.network_delay);
         
         let tag = self.tags.get_mut(epc)
             .ok_or_else(|| SampleGuardError::ReaderError(format!("Tag {} not found", epc)))?;
+        tag.check_access(password)?;
+        if tag.locked {
+            return Err(SampleGuardError::TagMemoryError(format!("Tag {} is locked", epc)));
+        }
         
         // Check for write error
-        if tag.should_error() {
+        if tag.should_error_under(self.polarization) {
             return Err(SampleGuardError::ReaderError("Tag write error (simulated)".to_string()));
         }
         
@@ -139,27 +285,48 @@ impl TagSimulator {
         std::thread::sleep(self.write_delay);
         
         tag.data = data;
-        tag.read_count += 1;
-        tag.last_read = Some(chrono::Utc::now());
         
         Ok(())
     }
     
+    /// Permanently lock a tag's memory against writes, supplying `password`
+    /// to a protected tag
+    pub fn lock_tag(&mut self, epc: &str, password: Option<u32>) -> Result<()> {
+        std::thread::sleep(self.network_delay);
+        
+        let tag = self.tags.get_mut(epc)
+            .ok_or_else(|| SampleGuardError::ReaderError(format!("Tag {} not found", epc)))?;
+        tag.check_access(password)?;
+        tag.locked = true;
+        Ok(())
+    }
+    
     /// Simulate scanning for tags in range
     pub fn scan_tags(&mut self, duration: Duration) -> Result<Vec<SimulatedTag>> {
         // Simulate network delay
         std::thread::sleep(self.network_delay);
         
+        match &self.scan_strategy {
+            ScanStrategy::Unrestricted => Ok(self.scan_unrestricted(duration)),
+            ScanStrategy::SingleAntenna(port) => Ok(self.scan_hopping(&[*port], duration, duration)),
+            ScanStrategy::Hopping { antennas, dwell } => Ok(self.scan_hopping(antennas, *dwell, duration)),
+        }
+    }
+    
+    fn scan_unrestricted(&self, duration: Duration) -> Vec<SimulatedTag> {
         let start = Instant::now();
         let mut found_tags = Vec::new();
+        let mut found_epcs = HashSet::new();
         
         while start.elapsed() < duration && found_tags.len() < self.tags.len() {
             for tag in self.tags.values() {
                 // Simulate tags appearing/disappearing based on RSSI
-                if tag.rssi > -80 && !found_tags.iter().any(|t: &SimulatedTag| t.epc == tag.epc) {
-                    if !tag.should_error() {
-                        found_tags.push(tag.clone());
-                    }
+                if tag.rssi > MIN_SCAN_RSSI
+                    && !found_epcs.contains(tag.epc.as_str())
+                    && !tag.should_error_under(self.polarization)
+                {
+                    found_epcs.insert(tag.epc.as_str());
+                    found_tags.push(tag.clone());
                 }
             }
             
@@ -167,7 +334,48 @@ impl TagSimulator {
             std::thread::sleep(Duration::from_millis(10));
         }
         
-        Ok(found_tags)
+        found_tags
+    }
+    
+    fn scan_hopping(&self, antennas: &[u8], dwell: Duration, duration: Duration) -> Vec<SimulatedTag> {
+        let start = Instant::now();
+        // EPC -> (port, rssi) of the strongest sighting so far
+        let mut best: HashMap<&str, (u8, i16)> = HashMap::new();
+        
+        loop {
+            for &port in antennas {
+                let dwell_start = Instant::now();
+                loop {
+                    for tag in self.tags.values() {
+                        let Some(rssi) = tag.rssi_on(port) else { continue };
+                        if rssi <= MIN_SCAN_RSSI || tag.should_error_under(self.polarization) {
+                            continue;
+                        }
+                        let entry = best.entry(tag.epc.as_str()).or_insert((port, rssi));
+                        if rssi > entry.1 {
+                            *entry = (port, rssi);
+                        }
+                    }
+                    if dwell_start.elapsed() >= dwell || best.len() == self.tags.len() {
+                        break;
+                    }
+                    std::thread::sleep(Duration::from_millis(10).min(dwell));
+                }
+            }
+            // After a full round every port has had its chance at each tag
+            if start.elapsed() >= duration || best.len() == self.tags.len() || antennas.is_empty() {
+                break;
+            }
+        }
+        
+        best.into_iter()
+            .map(|(epc, (port, rssi))| {
+                let mut tag = self.tags[epc].clone();
+                tag.antenna = port;
+                tag.rssi = rssi;
+                tag
+            })
+            .collect()
     }
     
     /// Get read delay
@@ -229,8 +437,29 @@ mod tests {
         simulator.add_tag(tag);
         
         simulator.write_tag("EPC-003", vec![7, 8, 9]).unwrap();
+        // Writes are not reads
+        assert_eq!(simulator.get_tags()[0].read_count, 0);
         let data = simulator.read_tag("EPC-003").unwrap();
         assert_eq!(data.as_bytes(), &[7, 8, 9]);
+        assert_eq!(simulator.get_tags()[0].read_count, 1);
+    }
+
+    #[test]
+    fn test_access_password_gates_writes_and_locks() {
+        let mut simulator = TagSimulator::new();
+        let tag = SimulatedTag::new("EPC-005".to_string(), "TAG-005".to_string(), vec![1])
+            .with_access_password(0x1234_5678);
+        simulator.add_tag(tag);
+        
+        assert!(simulator.write_tag("EPC-005", vec![2]).is_err());
+        assert!(simulator.write_tag_with_password("EPC-005", vec![2], Some(0xDEAD_BEEF)).is_err());
+        assert!(simulator.lock_tag("EPC-005", None).is_err());
+        simulator.write_tag_with_password("EPC-005", vec![3], Some(0x1234_5678)).unwrap();
+        assert_eq!(simulator.read_tag("EPC-005").unwrap().as_bytes(), &[3]);
+        
+        simulator.lock_tag("EPC-005", Some(0x1234_5678)).unwrap();
+        assert!(simulator.write_tag_with_password("EPC-005", vec![4], Some(0x1234_5678)).is_err());
+        assert_eq!(simulator.read_tag("EPC-005").unwrap().as_bytes(), &[3]);
     }
 
     #[test]
@@ -257,7 +486,93 @@ mod tests {
         }
         
         let found = simulator.scan_tags(Duration::from_millis(100)).unwrap();
-        assert!(found.len() > 0);
+        assert!(!found.is_empty());
+    }
+
+    fn field_on_ports(ports: &[u8]) -> TagSimulator {
+        let mut simulator = TagSimulator::new().with_network_delay(Duration::ZERO);
+        for &port in ports {
+            simulator.add_tag(
+                SimulatedTag::new(format!("EPC-A{}", port), format!("TAG-A{}", port), vec![])
+                    .with_antenna(port)
+                    .with_rssi(-60),
+            );
+        }
+        simulator
+    }
+
+    fn found_ports(found: &[SimulatedTag]) -> Vec<u8> {
+        let mut ports: Vec<u8> = found.iter().map(|t| t.antenna).collect();
+        ports.sort();
+        ports
+    }
+
+    #[test]
+    fn test_hopping_scan_covers_every_enabled_port() {
+        let mut single = field_on_ports(&[1, 2, 3, 4]).with_scan_strategy(ScanStrategy::SingleAntenna(2));
+        let found = single.scan_tags(Duration::from_millis(30)).unwrap();
+        assert_eq!(found_ports(&found), vec![2]);
+
+        let mut hopping = field_on_ports(&[1, 2, 3, 4]).with_scan_strategy(ScanStrategy::Hopping {
+            antennas: vec![1, 2, 3],
+            dwell: Duration::from_millis(5),
+        });
+        let found = hopping.scan_tags(Duration::from_millis(30)).unwrap();
+        assert_eq!(found_ports(&found), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_hopping_scan_reports_strongest_antenna() {
+        let mut simulator = TagSimulator::new()
+            .with_network_delay(Duration::ZERO)
+            .with_scan_strategy(ScanStrategy::Hopping { antennas: vec![1, 2], dwell: Duration::ZERO });
+        simulator.add_tag(
+            SimulatedTag::new("EPC-BOTH".to_string(), "TAG-BOTH".to_string(), vec![])
+                .with_antenna(1)
+                .with_rssi(-72)
+                .with_antenna_rssi(2, -48),
+        );
+
+        let found = simulator.scan_tags(Duration::ZERO).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].antenna, found[0].rssi), (2, -48));
+    }
+
+    #[test]
+    fn test_linear_polarization_is_orientation_sensitive() {
+        let linear = AntennaPolarization::Linear { angle: 0.0 };
+        let aligned = SimulatedTag::new("EPC-AL".to_string(), "TAG-AL".to_string(), vec![1]);
+        let orthogonal = aligned.clone().with_orientation(90.0);
+        assert_eq!(aligned.read_success_probability(linear), 1.0);
+        assert!(orthogonal.read_success_probability(linear) <= CROSS_POLARIZATION_COUPLING);
+        let diagonal = aligned.clone().with_orientation(45.0).with_error_rate(0.2);
+        assert!((diagonal.read_success_probability(linear) - 0.4).abs() < 1e-6);
+
+        let mut simulator = TagSimulator::new()
+            .with_network_delay(Duration::ZERO)
+            .with_read_delay(Duration::ZERO)
+            .with_polarization(linear);
+        simulator.add_tag(aligned);
+        simulator.add_tag(SimulatedTag { epc: "EPC-OR".to_string(), ..orthogonal });
+        let successes = |simulator: &mut TagSimulator, epc: &str| {
+            (0..500).filter(|_| simulator.read_tag(epc).is_ok()).count()
+        };
+        assert_eq!(successes(&mut simulator, "EPC-AL"), 500);
+        assert!(successes(&mut simulator, "EPC-OR") < 50);
     }
-}
 
+    #[test]
+    fn test_circular_polarization_is_orientation_insensitive() {
+        let mut simulator = TagSimulator::new()
+            .with_network_delay(Duration::ZERO)
+            .with_read_delay(Duration::ZERO);
+        assert_eq!(simulator.polarization(), AntennaPolarization::Circular);
+        for angle in [0.0, 30.0, 90.0, 135.0] {
+            let tag = SimulatedTag::new(format!("EPC-{}", angle), "TAG".to_string(), vec![])
+                .with_orientation(angle);
+            assert_eq!(tag.read_success_probability(AntennaPolarization::Circular), 1.0);
+            simulator.add_tag(tag);
+        }
+        assert!((0..50).all(|_| simulator.read_tag("EPC-90").is_ok()));
+    }
+}
@@ -1,10 +1,15 @@
-use crate::hardware::protocol::{ReaderProtocol, ReaderCommand, ProtocolResponse, MemoryBank};
+use crate::hardware::protocol::{batch_too_large, BatchReadEntry, ReaderConfigReport, MemoryBank, ProtocolResponse, ReaderCommand, ReaderProtocol, DEFAULT_MAX_BATCH_SIZE};
+use crate::hardware::metrics::ReaderMetrics;
 use crate::hardware::simulator::TagSimulator;
 use crate::reader::{RFIDReader, ReaderConfig, ReaderCapabilities, ReaderFrequency};
+use crate::region::RegulatoryRegion;
 use crate::tag::TagData;
 use crate::error::{SampleGuardError, Result};
 use std::time::Duration;
 
+/// Reader type label used in metrics
+const READER_TYPE: &str = "Zebra FX9600";
+
 /// Zebra FX9600 Reader emulation
 /// Implements proprietary Zebra protocol simulation
 pub struct ZebraFX9600Reader {
@@ -13,6 +18,8 @@ pub struct ZebraFX9600Reader {
     simulator: TagSimulator,
     connected: bool,
     protocol_version: String,
+    metrics: ReaderMetrics,
+    max_batch_size: usize,
     reader_id: String,
 }
 
@@ -24,6 +31,7 @@ impl ZebraFX9600Reader {
                 power_level: 27,
                 read_timeout_ms: 1500,
                 antenna_gain: 6.5,
+                region: None,
             },
             capabilities: ReaderCapabilities {
                 supports_encryption: true,
@@ -40,6 +48,8 @@ impl ZebraFX9600Reader {
                 .with_network_delay(Duration::from_millis(6)),
             connected: false,
             protocol_version: "Zebra-2.0".to_string(),
+            metrics: ReaderMetrics::new(),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
             reader_id: format!("FX9600-{:06X}", rand::random::<u32>()),
         }
     }
@@ -49,6 +59,28 @@ impl ZebraFX9600Reader {
         self
     }
     
+    /// Record command response times into a shared registry
+    pub fn with_metrics(mut self, metrics: ReaderMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+    
+    pub fn metrics(&self) -> &ReaderMetrics {
+        &self.metrics
+    }
+    
+    /// Reject `BatchRead` commands naming more than `max` EPCs
+    pub fn with_max_batch_size(mut self, max: usize) -> Self {
+        self.max_batch_size = max.max(1);
+        self
+    }
+    
+    /// Operate under `region`, clamping the power level to its limit
+    pub fn with_region(mut self, region: RegulatoryRegion) -> Self {
+        self.config.apply_region(region);
+        self
+    }
+    
     pub fn get_reader_id(&self) -> &str {
         &self.reader_id
     }
@@ -66,8 +98,8 @@ impl ZebraFX9600Reader {
     }
 }
 
-impl ReaderProtocol for ZebraFX9600Reader {
-    fn send_command(&mut self, command: ReaderCommand) -> Result<ProtocolResponse> {
+impl ZebraFX9600Reader {
+    fn execute_command(&mut self, command: ReaderCommand) -> Result<ProtocolResponse> {
         let start = std::time::Instant::now();
         
         match command {
@@ -79,10 +111,10 @@ impl ReaderProtocol for ZebraFX9600Reader {
                 ))
             }
             _ if !self.connected => {
-                return Ok(ProtocolResponse::error(
+                Ok(ProtocolResponse::error(
                     "Reader not connected".to_string(),
                     start.elapsed().as_millis() as u64,
-                ));
+                ))
             }
             ReaderCommand::StartInventory => {
                 Ok(ProtocolResponse::success(
@@ -97,16 +129,21 @@ impl ReaderProtocol for ZebraFX9600Reader {
                 ))
             }
             ReaderCommand::ReadTag { epc, bank } => {
-                match self.simulator.read_tag(&epc) {
-                    Ok(data) => {
-                        // Zebra-specific: include memory bank info
-                        let mut response_data = data.as_bytes().to_vec();
-                        response_data.insert(0, match bank {
-                            MemoryBank::Reserved => 0x00,
-                            MemoryBank::Epc => 0x01,
-                            MemoryBank::Tid => 0x02,
-                            MemoryBank::User => 0x03,
-                        });
+                // Zebra-specific: include memory bank info
+                let bank_byte = match bank {
+                    MemoryBank::Reserved => 0x00,
+                    MemoryBank::Epc => 0x01,
+                    MemoryBank::Tid => 0x02,
+                    MemoryBank::User => 0x03,
+                };
+                let read = self.simulator.read_tag_with(&epc, |data| {
+                    let mut response_data = Vec::with_capacity(data.len() + 1);
+                    response_data.push(bank_byte);
+                    response_data.extend_from_slice(data);
+                    response_data
+                });
+                match read {
+                    Ok(response_data) => {
                         Ok(ProtocolResponse::success(
                             response_data,
                             start.elapsed().as_millis() as u64,
@@ -118,8 +155,8 @@ impl ReaderProtocol for ZebraFX9600Reader {
                     )),
                 }
             }
-            ReaderCommand::WriteTag { epc, data, bank: _ } => {
-                match self.simulator.write_tag(&epc, data) {
+            ReaderCommand::WriteTag { epc, data, access_password, .. } => {
+                match self.simulator.write_tag_with_password(&epc, data, access_password) {
                     Ok(_) => Ok(ProtocolResponse::success(
                         b"Tag write completed".to_vec(),
                         start.elapsed().as_millis() as u64,
@@ -130,26 +167,58 @@ impl ReaderProtocol for ZebraFX9600Reader {
                     )),
                 }
             }
+            ReaderCommand::LockTag { epc, access_password } => {
+                match self.simulator.lock_tag(&epc, access_password) {
+                    Ok(_) => Ok(ProtocolResponse::success(
+                        b"Tag lock completed".to_vec(),
+                        start.elapsed().as_millis() as u64,
+                    )),
+                    Err(e) => Ok(ProtocolResponse::error(
+                        e.to_string(),
+                        start.elapsed().as_millis() as u64,
+                    )),
+                }
+            }
             ReaderCommand::GetConfiguration => {
-                let config_json = serde_json::json!({
-                    "reader_id": self.reader_id,
-                    "power_level": self.config.power_level,
-                    "frequency": format!("{:?}", self.config.frequency),
-                    "antenna_gain": self.config.antenna_gain,
-                });
+                let report = ReaderConfigReport::new(&self.reader_id, &*self, &self.config, &self.capabilities);
                 Ok(ProtocolResponse::success(
-                    serde_json::to_vec(&config_json).unwrap(),
+                    report.encode()?,
                     start.elapsed().as_millis() as u64,
                 ))
             }
             ReaderCommand::SetConfiguration { power, antenna } => {
-                self.config.power_level = power;
+                if let Err(e) = self.config.set_power_level(power) {
+                    return Ok(ProtocolResponse::error(
+                        e.to_string(),
+                        start.elapsed().as_millis() as u64,
+                    ));
+                }
                 // Zebra supports antenna selection
                 Ok(ProtocolResponse::success(
                     format!("Configuration updated: power={}, antenna={}", power, antenna).into_bytes(),
                     start.elapsed().as_millis() as u64,
                 ))
             }
+            ReaderCommand::BatchRead { epcs, .. } => {
+                // Checked before touching any tag, so oversized batches cost nothing
+                if epcs.len() > self.max_batch_size {
+                    return Ok(ProtocolResponse::error(
+                        batch_too_large(epcs.len(), self.max_batch_size),
+                        start.elapsed().as_millis() as u64,
+                    ));
+                }
+                let entries: Vec<BatchReadEntry> = epcs
+                    .into_iter()
+                    .map(|epc| match self.simulator.read_tag_with(&epc, <[u8]>::to_vec) {
+                        Ok(data) => BatchReadEntry::read(epc, data),
+                        Err(e) => BatchReadEntry::failed(epc, e.to_string()),
+                    })
+                    .collect();
+                Ok(ProtocolResponse::success(
+                    BatchReadEntry::encode(&entries)?,
+                    start.elapsed().as_millis() as u64,
+                ))
+            }
             ReaderCommand::GetStatus => {
                 let status_json = serde_json::json!({
                     "connected": self.connected,
@@ -164,6 +233,15 @@ impl ReaderProtocol for ZebraFX9600Reader {
             }
         }
     }
+}
+
+impl ReaderProtocol for ZebraFX9600Reader {
+    fn send_command(&mut self, command: ReaderCommand) -> Result<ProtocolResponse> {
+        let name = command.name();
+        let response = self.execute_command(command)?;
+        self.metrics.record(READER_TYPE, name, response.response_time_ms);
+        Ok(response)
+    }
     
     fn protocol_name(&self) -> &str {
         "Zebra"
@@ -173,6 +251,10 @@ impl ReaderProtocol for ZebraFX9600Reader {
         &self.protocol_version
     }
     
+    fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
+    
     fn simulate_delay(&self) -> Duration {
         Duration::from_millis(6) // Zebra network delay
     }
@@ -0,0 +1,168 @@
+use crate::config::SamplesConfig;
+use crate::error::{SampleGuardError, Result};
+use chrono::{DateTime, Utc};
+use image::{ImageFormat, ImageReader};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+/// Longest side of generated thumbnails, in pixels
+pub const THUMBNAIL_SIZE: u32 = 128;
+/// Largest accepted upload unless configured otherwise
+pub const DEFAULT_MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+/// Largest accepted width or height unless configured otherwise
+pub const DEFAULT_MAX_IMAGE_DIMENSION: u32 = 4096;
+
+/// Size limits applied to sample image uploads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+    pub max_bytes: usize,
+    pub max_dimension: u32,
+}
+
+impl Default for ImageLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_IMAGE_BYTES,
+            max_dimension: DEFAULT_MAX_IMAGE_DIMENSION,
+        }
+    }
+}
+
+impl ImageLimits {
+    pub fn from_config(config: &SamplesConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_bytes: config.max_image_bytes.unwrap_or(defaults.max_bytes),
+            max_dimension: config.max_image_dimension.unwrap_or(defaults.max_dimension),
+        }
+    }
+}
+
+/// Whether `content_type` names an image, ignoring any parameters
+pub fn is_image_content_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    essence.len() > "image/".len() && essence.get(..6).is_some_and(|prefix| prefix.eq_ignore_ascii_case("image/"))
+}
+
+/// Content type of `data` judged from its leading bytes, if it is an image
+/// format that can be stored
+pub fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    match image::guess_format(data).ok()? {
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        _ => None,
+    }
+}
+
+/// Metadata of the image attached to a sample
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageReference {
+    pub content_type: String,
+    pub width: u32,
+    pub height: u32,
+    pub size_bytes: usize,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// A validated sample image and its PNG thumbnail
+#[derive(Debug, Clone)]
+pub struct SampleImage {
+    pub reference: ImageReference,
+    pub data: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+}
+
+impl SampleImage {
+    /// Validate an uploaded PNG or JPEG against `limits` and render its
+    /// thumbnail. Dimensions are checked before the image is decoded.
+    pub fn from_upload(data: Vec<u8>, limits: &ImageLimits) -> Result<Self> {
+        if data.len() > limits.max_bytes {
+            return Err(invalid(format!(
+                "Image of {} bytes exceeds the limit of {} bytes",
+                data.len(),
+                limits.max_bytes
+            )));
+        }
+        let content_type = sniff_content_type(&data)
+            .ok_or_else(|| invalid("Upload is not a PNG or JPEG image".to_string()))?;
+
+        let reader = || ImageReader::new(Cursor::new(data.as_slice())).with_guessed_format();
+        let (width, height) = reader()?
+            .into_dimensions()
+            .map_err(|e| invalid(format!("Unreadable image: {}", e)))?;
+        if width > limits.max_dimension || height > limits.max_dimension {
+            return Err(invalid(format!(
+                "Image of {}x{} exceeds the limit of {} pixels per side",
+                width, height, limits.max_dimension
+            )));
+        }
+
+        let decoded = reader()?
+            .decode()
+            .map_err(|e| invalid(format!("Unreadable image: {}", e)))?;
+        let mut thumbnail = Vec::new();
+        decoded
+            .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+            .write_to(&mut Cursor::new(&mut thumbnail), ImageFormat::Png)
+            .map_err(|e| SampleGuardError::IoError(std::io::Error::other(format!("Thumbnail encoding failed: {}", e))))?;
+
+        Ok(Self {
+            reference: ImageReference {
+                content_type: content_type.to_string(),
+                width,
+                height,
+                size_bytes: data.len(),
+                uploaded_at: Utc::now(),
+            },
+            data,
+            thumbnail,
+        })
+    }
+}
+
+fn invalid(message: String) -> SampleGuardError {
+    SampleGuardError::InvalidSampleData(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        image::RgbImage::new(width, height)
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_upload_generates_bounded_thumbnail() {
+        let image = SampleImage::from_upload(png(640, 320), &ImageLimits::default()).unwrap();
+        assert_eq!(image.reference.content_type, "image/png");
+        assert_eq!((image.reference.width, image.reference.height), (640, 320));
+
+        let thumbnail = image::load_from_memory(&image.thumbnail).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2));
+    }
+
+    #[test]
+    fn test_upload_limits() {
+        let limits = ImageLimits { max_bytes: DEFAULT_MAX_IMAGE_BYTES, max_dimension: 100 };
+        assert!(SampleImage::from_upload(png(101, 10), &limits).is_err());
+        assert!(SampleImage::from_upload(png(100, 10), &limits).is_ok());
+
+        let limits = ImageLimits { max_bytes: 16, max_dimension: 100 };
+        assert!(SampleImage::from_upload(png(10, 10), &limits).is_err());
+        assert!(SampleImage::from_upload(b"plain text".to_vec(), &ImageLimits::default()).is_err());
+    }
+
+    #[test]
+    fn test_content_types() {
+        assert!(is_image_content_type("image/png"));
+        assert!(is_image_content_type("IMAGE/JPEG; q=0.9"));
+        assert!(!is_image_content_type("image/"));
+        assert!(!is_image_content_type("text/plain"));
+        assert!(!is_image_content_type("application/octet-stream"));
+    }
+}
@@ -1,9 +1,10 @@
-use crate::sample::{Sample, SampleStatus};
+use crate::sample::{ChecksumVersion, Sample, SampleStatus};
 use crate::error::Result;
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
 
 /// Validation result for sample integrity checks
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ValidationResult {
     pub is_valid: bool,
     pub violations: Vec<Violation>,
@@ -11,7 +12,7 @@ pub struct ValidationResult {
 }
 
 /// Types of integrity violations
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Violation {
     ChecksumMismatch,
     Expired,
@@ -22,11 +23,13 @@ pub enum Violation {
 }
 
 /// Types of warnings (non-critical issues)
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Warning {
     HighReadCount,
     ApproachingExpiry,
     LocationChanged,
+    /// Checksum in the legacy layout, accepted by `with_allow_legacy_checksums`
+    LegacyChecksum,
 }
 
 /// Integrity validator for medical device samples
@@ -35,6 +38,8 @@ pub struct IntegrityValidator {
     max_read_count: u64,
     #[allow(dead_code)]
     temperature_tolerance: f32, // Reserved for future temperature validation
+    expiry_warning_days: u32,
+    allow_legacy_checksums: bool,
 }
 
 impl IntegrityValidator {
@@ -42,17 +47,34 @@ impl IntegrityValidator {
         Self {
             max_read_count: 1000,
             temperature_tolerance: 2.0, // ±2°C tolerance
+            expiry_warning_days: 30,
+            allow_legacy_checksums: false,
         }
     }
 
+    /// Warn about samples expiring within `days` days instead of 30
+    pub fn with_expiry_warning_days(mut self, days: u32) -> Self {
+        self.expiry_warning_days = days;
+        self
+    }
+
+    /// Report checksums in the legacy layout as a warning rather than a
+    /// violation, matching `Database::with_allow_legacy_checksums`
+    pub fn with_allow_legacy_checksums(mut self, allow: bool) -> Self {
+        self.allow_legacy_checksums = allow;
+        self
+    }
+
     /// Validate a sample's integrity
     pub fn validate(&self, sample: &Sample) -> Result<ValidationResult> {
         let mut violations = Vec::new();
         let mut warnings = Vec::new();
 
         // Check integrity checksum
-        if !sample.verify_integrity() {
-            violations.push(Violation::ChecksumMismatch);
+        match sample.checksum_version() {
+            Some(ChecksumVersion::LengthPrefixed) => {}
+            Some(ChecksumVersion::Legacy) if self.allow_legacy_checksums => warnings.push(Warning::LegacyChecksum),
+            Some(ChecksumVersion::Legacy) | None => violations.push(Violation::ChecksumMismatch),
         }
 
         // Check expiry
@@ -60,7 +82,7 @@ impl IntegrityValidator {
             violations.push(Violation::Expired);
         } else if let Some(expiry) = sample.metadata.expiry_date {
             let days_until_expiry = (expiry - Utc::now()).num_days();
-            if days_until_expiry <= 30 && days_until_expiry > 0 {
+            if days_until_expiry <= self.expiry_warning_days as i64 && days_until_expiry > 0 {
                 warnings.push(Warning::ApproachingExpiry);
             }
         }
@@ -118,6 +140,44 @@ impl ValidationResult {
     pub fn has_warnings(&self) -> bool {
         !self.warnings.is_empty()
     }
+
+    /// What changed since `previous`, a validation of the same sample
+    pub fn delta(&self, previous: &ValidationResult) -> ValidationDelta {
+        ValidationDelta {
+            added_violations: missing_from(&self.violations, &previous.violations),
+            resolved_violations: missing_from(&previous.violations, &self.violations),
+            added_warnings: missing_from(&self.warnings, &previous.warnings),
+            resolved_warnings: missing_from(&previous.warnings, &self.warnings),
+        }
+    }
+}
+
+/// Violations and warnings that appeared or cleared between two validations
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationDelta {
+    pub added_violations: Vec<Violation>,
+    pub resolved_violations: Vec<Violation>,
+    pub added_warnings: Vec<Warning>,
+    pub resolved_warnings: Vec<Warning>,
+}
+
+impl ValidationDelta {
+    pub fn is_empty(&self) -> bool {
+        self.added_violations.is_empty()
+            && self.resolved_violations.is_empty()
+            && self.added_warnings.is_empty()
+            && self.resolved_warnings.is_empty()
+    }
+
+    /// Whether anything got worse, even if something else improved
+    pub fn degraded(&self) -> bool {
+        !self.added_violations.is_empty() || !self.added_warnings.is_empty()
+    }
+}
+
+/// Items of `items` not present in `other`
+fn missing_from<T: Clone + PartialEq>(items: &[T], other: &[T]) -> Vec<T> {
+    items.iter().filter(|item| !other.contains(item)).cloned().collect()
 }
 
 impl Default for IntegrityValidator {
@@ -168,16 +228,66 @@ mod tests {
         assert!(result.violations.contains(&Violation::Expired));
     }
 
+    #[test]
+    fn test_expiry_warning_threshold_is_configurable() {
+        let mut sample = create_valid_sample();
+        sample.metadata.expiry_date = Some(Utc::now() + chrono::Duration::days(45) + chrono::Duration::hours(1));
+
+        let default = IntegrityValidator::new().validate(&sample).unwrap();
+        assert!(default.warnings.is_empty());
+        let wider = IntegrityValidator::new().with_expiry_warning_days(60).validate(&sample).unwrap();
+        assert_eq!(wider.warnings, vec![Warning::ApproachingExpiry]);
+    }
+
+    #[test]
+    fn test_legacy_checksum_is_a_warning_when_allowed() {
+        let mut sample = create_valid_sample();
+        sample.integrity_checksum = Sample::calculate_checksum_with(
+            ChecksumVersion::Legacy,
+            &sample.sample_id,
+            &sample.metadata,
+            &sample.last_updated,
+        );
+
+        let strict = IntegrityValidator::new().validate(&sample).unwrap();
+        assert_eq!(strict.violations, vec![Violation::ChecksumMismatch]);
+        let lenient = IntegrityValidator::new().with_allow_legacy_checksums(true).validate(&sample).unwrap();
+        assert!(lenient.is_valid());
+        assert_eq!(lenient.warnings, vec![Warning::LegacyChecksum]);
+
+        sample.integrity_checksum = [0u8; 32];
+        let forged = IntegrityValidator::new().with_allow_legacy_checksums(true).validate(&sample).unwrap();
+        assert_eq!(forged.violations, vec![Violation::ChecksumMismatch]);
+    }
+
     #[test]
     fn test_compromised_sample_validation() {
         let validator = IntegrityValidator::new();
         let mut sample = create_valid_sample();
-        sample.update_status(SampleStatus::Compromised);
+        sample.update_status(SampleStatus::Compromised).unwrap();
         
         let result = validator.validate(&sample).unwrap();
         
         assert!(!result.is_valid());
         assert!(result.violations.contains(&Violation::StatusInvalid));
     }
-}
 
+    #[test]
+    fn test_delta_reports_added_and_resolved() {
+        let validator = IntegrityValidator::new();
+        let mut sample = create_valid_sample();
+        sample.metadata.expiry_date = Some(Utc::now() + chrono::Duration::days(10));
+        let before = validator.validate(&sample).unwrap();
+        assert_eq!(before.warnings, vec![Warning::ApproachingExpiry]);
+
+        sample.metadata.expiry_date = Some(Utc::now() - chrono::Duration::days(1));
+        let after = validator.validate(&sample).unwrap();
+        let delta = after.delta(&before);
+        assert_eq!(delta.added_violations, vec![Violation::Expired]);
+        assert_eq!(delta.resolved_warnings, vec![Warning::ApproachingExpiry]);
+        assert!(delta.degraded());
+
+        assert!(after.delta(&after).is_empty());
+        assert_eq!(before.delta(&after).resolved_violations, vec![Violation::Expired]);
+    }
+}
@@ -1,9 +1,15 @@
+use crate::database::Database;
 use crate::error::{SampleGuardError, Result};
+use crate::hardware::simulator::TagSimulator;
+use crate::hardware::{ImpinjSpeedwayReader, ZebraFX9600Reader};
+use crate::notifications::Notification;
 use crate::reader::RFIDReader;
-use crate::sample::Sample;
+use crate::encryption::RFIDEncryption;
+use crate::sample::{default_encryption, Sample};
 #[allow(unused_imports)]
 use crate::tag::{RFIDTag, TagData};
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::time::Duration;
 
@@ -24,18 +30,102 @@ pub enum InventoryFilter {
     EpcPrefix(String),
     /// Filter by minimum RSSI
     MinRssi(i16),
+    /// Filter by RSSI between `min` and `max` inclusive
+    RssiRange { min: i16, max: i16 },
     /// Filter by antenna number
     Antenna(u8),
     /// Filter by tag ID
     TagId(String),
+    /// Every filter matches; stops at the first that does not. Empty matches all.
+    And(Vec<InventoryFilter>),
+    /// Any filter matches; stops at the first that does. Empty matches none.
+    Or(Vec<InventoryFilter>),
+    /// The filter does not match
+    Not(Box<InventoryFilter>),
     /// No filter
     None,
 }
 
+impl InventoryFilter {
+    /// Whether `tag` satisfies this filter
+    pub fn matches(&self, tag: &TagScanResult) -> bool {
+        match self {
+            InventoryFilter::EpcPrefix(prefix) => tag.epc.starts_with(prefix),
+            InventoryFilter::MinRssi(min_rssi) => tag.rssi >= *min_rssi,
+            InventoryFilter::RssiRange { min, max } => (*min..=*max).contains(&tag.rssi),
+            InventoryFilter::Antenna(antenna) => tag.antenna == *antenna,
+            InventoryFilter::TagId(tag_id) => tag.tag_id == *tag_id,
+            InventoryFilter::And(filters) => filters.iter().all(|f| f.matches(tag)),
+            InventoryFilter::Or(filters) => filters.iter().any(|f| f.matches(tag)),
+            InventoryFilter::Not(filter) => !filter.matches(tag),
+            InventoryFilter::None => true,
+        }
+    }
+}
+
+/// Tags collected by a scan, possibly capped at a maximum result count
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InventoryScan {
+    pub tags: Vec<TagScanResult>,
+    /// Set when more distinct tags were seen than the cap allowed
+    pub truncated: bool,
+    /// Distinct tags seen during the scan, including those not collected
+    pub total_seen: usize,
+    /// Tags that arrived or departed since the previous scan
+    #[serde(default)]
+    pub events: Vec<InventoryEvent>,
+}
+
+/// Name of the notification sent when a tag enters the field
+pub const TAG_ARRIVED_EVENT: &str = "tag.arrived";
+/// Name of the notification sent when a tag leaves the field
+pub const TAG_DEPARTED_EVENT: &str = "tag.departed";
+
+/// Change in the tag population between two consecutive scans
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum InventoryEvent {
+    /// Seen in this scan but not the previous one; every tag arrives on the first scan
+    TagArrived(TagScanResult),
+    /// Seen in the previous scan but not this one, as last seen
+    TagDeparted(TagScanResult),
+}
+
+impl InventoryEvent {
+    pub fn tag(&self) -> &TagScanResult {
+        match self {
+            InventoryEvent::TagArrived(tag) | InventoryEvent::TagDeparted(tag) => tag,
+        }
+    }
+
+    /// Notification for the event bus
+    pub fn notification(&self) -> Notification {
+        let event = match self {
+            InventoryEvent::TagArrived(_) => TAG_ARRIVED_EVENT,
+            InventoryEvent::TagDeparted(_) => TAG_DEPARTED_EVENT,
+        };
+        let tag = self.tag();
+        Notification::new(
+            event,
+            None,
+            serde_json::json!({
+                "epc": tag.epc,
+                "tag_id": tag.tag_id,
+                "antenna": tag.antenna,
+                "rssi": tag.rssi,
+            }),
+        )
+    }
+}
+
 /// Multi-tag inventory manager
 pub struct InventoryManager {
     scanned_tags: HashMap<String, TagScanResult>,
     last_scan_time: Option<chrono::DateTime<chrono::Utc>>,
+    max_results: Option<usize>,
+    /// Tags seen by the previous scan, `None` before the first
+    present: Option<HashMap<String, TagScanResult>>,
+    /// Key that tags are decrypted with when reading samples
+    encryption: RFIDEncryption,
 }
 
 impl InventoryManager {
@@ -44,21 +134,52 @@ impl InventoryManager {
         Self {
             scanned_tags: HashMap::new(),
             last_scan_time: None,
+            max_results: None,
+            present: None,
+            encryption: default_encryption().clone(),
         }
     }
 
-    /// Scan for multiple RFID tags
+    /// Decrypt sample tags with `encryption` instead of the default key
+    pub fn with_encryption(mut self, encryption: RFIDEncryption) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// Cap the number of tags collected by each `scan_tags` call
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    pub fn max_results(&self) -> Option<usize> {
+        self.max_results
+    }
+
+    /// Scan for multiple RFID tags, up to the configured maximum
     pub fn scan_tags<R: RFIDReader>(
         &mut self,
         reader: &mut R,
         duration: Duration,
     ) -> Result<Vec<TagScanResult>> {
+        Ok(self.scan_tags_capped(reader, duration, self.max_results)?.tags)
+    }
+
+    /// Scan for multiple RFID tags, collecting at most `max_results` distinct tags.
+    /// Tags seen after the cap is reached are only counted.
+    pub fn scan_tags_capped<R: RFIDReader>(
+        &mut self,
+        reader: &mut R,
+        duration: Duration,
+        max_results: Option<usize>,
+    ) -> Result<InventoryScan> {
         let start_time = chrono::Utc::now();
         let end_time = start_time + chrono::Duration::from_std(duration)
             .map_err(|e| SampleGuardError::ReaderError(format!("Invalid duration: {}", e)))?;
         
         let mut results = Vec::new();
-        let mut seen_epcs = std::collections::HashSet::new();
+        let mut seen = HashMap::new();
+        let cap = max_results.unwrap_or(usize::MAX);
 
         // Simulate scanning multiple tags
         // In production, this would continuously read from the reader
@@ -69,10 +190,8 @@ impl InventoryManager {
                         Ok(tag) => {
                             let epc = format!("EPC-{}", tag.tag_id);
                             
-                            // Avoid duplicates
-                            if !seen_epcs.contains(&epc) {
-                                seen_epcs.insert(epc.clone());
-                                
+                            // Avoid duplicates; past the cap, only count new tags
+                            if let Entry::Vacant(slot) = seen.entry(epc.clone()) {
                                 let scan_result = TagScanResult {
                                     epc: epc.clone(),
                                     tag_id: tag.tag_id.clone(),
@@ -80,9 +199,12 @@ impl InventoryManager {
                                     antenna: 1,
                                     timestamp: chrono::Utc::now(),
                                 };
-                                
-                                results.push(scan_result.clone());
-                                self.scanned_tags.insert(epc, scan_result);
+
+                                if results.len() < cap {
+                                    results.push(scan_result.clone());
+                                    self.scanned_tags.insert(epc, scan_result.clone());
+                                }
+                                slot.insert(scan_result);
                             }
                         }
                         Err(_) => {
@@ -100,20 +222,70 @@ impl InventoryManager {
         }
 
         self.last_scan_time = Some(chrono::Utc::now());
-        Ok(results)
+        Ok(InventoryScan {
+            truncated: seen.len() > results.len(),
+            total_seen: seen.len(),
+            tags: results,
+            events: self.track_presence(seen),
+        })
+    }
+
+    /// Scan a multi-tag source once, collecting at most `max_results` distinct tags
+    pub fn scan_source<S: TagSource + ?Sized>(
+        &mut self,
+        source: &mut S,
+        duration: Duration,
+        max_results: Option<usize>,
+    ) -> Result<InventoryScan> {
+        let cap = max_results.unwrap_or(usize::MAX);
+        let mut seen = HashMap::new();
+        let mut tags = Vec::new();
+
+        for result in source.scan_cycle(duration)? {
+            if let Entry::Vacant(slot) = seen.entry(result.epc.clone()) {
+                if tags.len() < cap {
+                    self.scanned_tags.insert(result.epc.clone(), result.clone());
+                    tags.push(result.clone());
+                }
+                slot.insert(result);
+            }
+        }
+
+        self.last_scan_time = Some(chrono::Utc::now());
+        Ok(InventoryScan {
+            truncated: seen.len() > tags.len(),
+            total_seen: seen.len(),
+            tags,
+            events: self.track_presence(seen),
+        })
+    }
+
+    /// Compare a scan's tags, including those beyond the cap, with the
+    /// previous scan's. Events are ordered by EPC.
+    ///
+    /// A tag that flickers out of one scan departs and then arrives again.
+    fn track_presence(&mut self, seen: HashMap<String, TagScanResult>) -> Vec<InventoryEvent> {
+        let previous = self.present.take().unwrap_or_default();
+        let mut events: Vec<InventoryEvent> = previous
+            .iter()
+            .filter(|(epc, _)| !seen.contains_key(*epc))
+            .map(|(_, tag)| InventoryEvent::TagDeparted(tag.clone()))
+            .chain(
+                seen.iter()
+                    .filter(|(epc, _)| !previous.contains_key(*epc))
+                    .map(|(_, tag)| InventoryEvent::TagArrived(tag.clone())),
+            )
+            .collect();
+        events.sort_by(|a, b| a.tag().epc.cmp(&b.tag().epc));
+        self.present = Some(seen);
+        events
     }
 
     /// Filter scanned tags based on criteria
     pub fn filter_tags(&self, filter: &InventoryFilter) -> Vec<&TagScanResult> {
         self.scanned_tags
             .values()
-            .filter(|tag| match filter {
-                InventoryFilter::EpcPrefix(prefix) => tag.epc.starts_with(prefix),
-                InventoryFilter::MinRssi(min_rssi) => tag.rssi >= *min_rssi,
-                InventoryFilter::Antenna(antenna) => tag.antenna == *antenna,
-                InventoryFilter::TagId(tag_id) => tag.tag_id == *tag_id,
-                InventoryFilter::None => true,
-            })
+            .filter(|tag| filter.matches(tag))
             .collect()
     }
 
@@ -131,6 +303,17 @@ impl InventoryManager {
     pub fn clear(&mut self) {
         self.scanned_tags.clear();
         self.last_scan_time = None;
+        self.present = None;
+    }
+
+    /// Snapshot of the tags currently held, for comparing with a later scan
+    pub fn snapshot(&self) -> InventorySnapshot {
+        let mut tags: Vec<TagScanResult> = self.scanned_tags.values().cloned().collect();
+        tags.sort_by(|a, b| a.epc.cmp(&b.epc));
+        InventorySnapshot {
+            taken_at: chrono::Utc::now(),
+            tags,
+        }
     }
 
     /// Get last scan time
@@ -138,6 +321,25 @@ impl InventoryManager {
         self.last_scan_time
     }
 
+    /// Run one cycle of a continuous inventory session, recording the tags
+    /// seen and returning the tags that appeared or went missing
+    pub fn scan_cycle<S: TagSource + ?Sized>(
+        &mut self,
+        source: &mut S,
+        session: &mut InventorySession,
+        duration: Duration,
+    ) -> Result<Vec<SessionEvent>> {
+        let results = source.scan_cycle(duration)?;
+        let now = chrono::Utc::now();
+
+        for result in &results {
+            self.scanned_tags.insert(result.epc.clone(), result.clone());
+        }
+        self.last_scan_time = Some(now);
+
+        Ok(session.observe(results, now))
+    }
+
     /// Batch read samples from tags
     pub fn batch_read_samples<R: RFIDReader>(
         &self,
@@ -151,7 +353,7 @@ impl InventoryManager {
                 Ok(tag_data) => {
                     match RFIDTag::from_bytes(tag_data.as_bytes()) {
                         Ok(tag) => {
-                            match Sample::from_tag(&tag) {
+                            match Sample::from_tag_with_key(&tag, &self.encryption) {
                                 Ok(sample) => {
                                     if sample.sample_id == *tag_id {
                                         samples.push(sample);
@@ -171,6 +373,22 @@ impl InventoryManager {
         Ok(samples)
     }
 
+    /// Compare samples read from tags against their stored records, telling
+    /// genuine content changes apart from plain re-reads
+    pub fn reconcile_samples(&self, database: &Database, samples: &[Sample]) -> Result<ReconciliationReport> {
+        let mut report = ReconciliationReport::default();
+
+        for sample in samples {
+            match database.get_sample(&sample.sample_id)? {
+                Some(stored) if stored.content_eq(sample) => report.unchanged.push(sample.sample_id.clone()),
+                Some(_) => report.changed.push(sample.sample_id.clone()),
+                None => report.unknown.push(sample.sample_id.clone()),
+            }
+        }
+
+        Ok(report)
+    }
+
     /// Generate inventory report
     pub fn generate_report(&self) -> InventoryReport {
         let total_tags = self.scanned_tags.len();
@@ -203,6 +421,168 @@ impl Default for InventoryManager {
     }
 }
 
+/// Source of multi-tag scan cycles, such as an emulated reader's field
+pub trait TagSource {
+    /// Scan for up to `duration`, returning each tag in range once
+    fn scan_cycle(&mut self, duration: Duration) -> Result<Vec<TagScanResult>>;
+}
+
+impl TagSource for TagSimulator {
+    fn scan_cycle(&mut self, duration: Duration) -> Result<Vec<TagScanResult>> {
+        let now = chrono::Utc::now();
+        Ok(self.scan_tags(duration)?
+            .into_iter()
+            .map(|tag| TagScanResult {
+                epc: tag.epc,
+                tag_id: tag.tag_id,
+                rssi: tag.rssi,
+                antenna: tag.antenna,
+                timestamp: now,
+            })
+            .collect())
+    }
+}
+
+impl TagSource for ImpinjSpeedwayReader {
+    fn scan_cycle(&mut self, duration: Duration) -> Result<Vec<TagScanResult>> {
+        self.get_simulator_mut().scan_cycle(duration)
+    }
+}
+
+impl TagSource for ZebraFX9600Reader {
+    fn scan_cycle(&mut self, duration: Duration) -> Result<Vec<TagScanResult>> {
+        self.get_simulator_mut().scan_cycle(duration)
+    }
+}
+
+/// Direction of a tag's signal strength between the last two sightings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RssiTrend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+/// A tag tracked across the cycles of an inventory session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTag {
+    pub scan: TagScanResult,
+    pub first_seen: chrono::DateTime<chrono::Utc>,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+    /// Consecutive cycles the tag was not seen in
+    pub missed_cycles: u32,
+    previous_rssi: Option<i16>,
+}
+
+impl SessionTag {
+    pub fn rssi_trend(&self) -> RssiTrend {
+        match self.previous_rssi {
+            Some(previous) if self.scan.rssi > previous => RssiTrend::Rising,
+            Some(previous) if self.scan.rssi < previous => RssiTrend::Falling,
+            _ => RssiTrend::Steady,
+        }
+    }
+
+    /// Whether the tag was missed in the latest cycle but is not yet declared missing
+    pub fn is_fading(&self) -> bool {
+        self.missed_cycles > 0
+    }
+}
+
+/// Change in the tag population detected by a session cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SessionEvent {
+    /// A tag not currently tracked was seen
+    Appeared(TagScanResult),
+    /// A tag went unseen for the session's missing threshold
+    Missing(SessionTag),
+}
+
+/// State of a continuous inventory session
+#[derive(Debug, Clone)]
+pub struct InventorySession {
+    tags: HashMap<String, SessionTag>,
+    missing_after: u32,
+    cycles: u64,
+}
+
+impl InventorySession {
+    /// Tags unseen for `missing_after` consecutive cycles are declared missing
+    pub fn new(missing_after: u32) -> Self {
+        Self {
+            tags: HashMap::new(),
+            missing_after: missing_after.max(1),
+            cycles: 0,
+        }
+    }
+
+    /// Fold one cycle's scan results into the session. Events are ordered by EPC.
+    pub fn observe(&mut self, mut results: Vec<TagScanResult>, now: chrono::DateTime<chrono::Utc>) -> Vec<SessionEvent> {
+        self.cycles += 1;
+        results.sort_by(|a, b| a.epc.cmp(&b.epc));
+        let mut events = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+
+        for result in results {
+            seen.insert(result.epc.clone());
+            match self.tags.get_mut(&result.epc) {
+                Some(tag) => {
+                    tag.previous_rssi = Some(tag.scan.rssi);
+                    tag.scan = result;
+                    tag.last_seen = now;
+                    tag.missed_cycles = 0;
+                }
+                None => {
+                    events.push(SessionEvent::Appeared(result.clone()));
+                    self.tags.insert(result.epc.clone(), SessionTag {
+                        scan: result,
+                        first_seen: now,
+                        last_seen: now,
+                        missed_cycles: 0,
+                        previous_rssi: None,
+                    });
+                }
+            }
+        }
+
+        let mut missing: Vec<String> = Vec::new();
+        for (epc, tag) in self.tags.iter_mut() {
+            if seen.contains(epc) {
+                continue;
+            }
+            tag.missed_cycles += 1;
+            if tag.missed_cycles >= self.missing_after {
+                missing.push(epc.clone());
+            }
+        }
+        missing.sort();
+        for epc in missing {
+            if let Some(tag) = self.tags.remove(&epc) {
+                events.push(SessionEvent::Missing(tag));
+            }
+        }
+
+        events
+    }
+
+    /// Tracked tags ordered by EPC
+    pub fn tags(&self) -> Vec<&SessionTag> {
+        let mut tags: Vec<&SessionTag> = self.tags.values().collect();
+        tags.sort_by(|a, b| a.scan.epc.cmp(&b.scan.epc));
+        tags
+    }
+
+    /// Number of cycles observed so far
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Forget all tracked tags
+    pub fn clear(&mut self) {
+        self.tags.clear();
+    }
+}
+
 /// Inventory report
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InventoryReport {
@@ -212,6 +592,85 @@ pub struct InventoryReport {
     pub last_scan: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Smallest RSSI change, in dB, reported by [`diff_snapshots`]; smaller
+/// differences are normal read-to-read jitter
+pub const MIN_RSSI_CHANGE: i16 = 6;
+
+/// The tags held by an inventory manager at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventorySnapshot {
+    pub taken_at: chrono::DateTime<chrono::Utc>,
+    /// Ordered by EPC
+    pub tags: Vec<TagScanResult>,
+}
+
+/// A tag whose signal strength moved between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RssiChange {
+    pub epc: String,
+    pub old_rssi: i16,
+    pub new_rssi: i16,
+}
+
+/// Difference between two inventory snapshots, each list ordered by EPC
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    /// In the new snapshot only
+    pub added: Vec<TagScanResult>,
+    /// In the old snapshot only, as last seen there
+    pub removed: Vec<TagScanResult>,
+    /// In both, with an RSSI change of at least [`MIN_RSSI_CHANGE`]
+    pub rssi_changed: Vec<RssiChange>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.rssi_changed.is_empty()
+    }
+}
+
+/// Compare a baseline snapshot with a later one
+pub fn diff_snapshots(old: &InventorySnapshot, new: &InventorySnapshot) -> SnapshotDiff {
+    let old_tags: HashMap<&str, &TagScanResult> = old.tags.iter().map(|t| (t.epc.as_str(), t)).collect();
+    let new_tags: HashMap<&str, &TagScanResult> = new.tags.iter().map(|t| (t.epc.as_str(), t)).collect();
+    let mut diff = SnapshotDiff::default();
+
+    for tag in &new.tags {
+        match old_tags.get(tag.epc.as_str()) {
+            None => diff.added.push(tag.clone()),
+            Some(previous) if (tag.rssi - previous.rssi).abs() >= MIN_RSSI_CHANGE => {
+                diff.rssi_changed.push(RssiChange {
+                    epc: tag.epc.clone(),
+                    old_rssi: previous.rssi,
+                    new_rssi: tag.rssi,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    diff.removed = old.tags
+        .iter()
+        .filter(|t| !new_tags.contains_key(t.epc.as_str()))
+        .cloned()
+        .collect();
+
+    diff.added.sort_by(|a, b| a.epc.cmp(&b.epc));
+    diff.removed.sort_by(|a, b| a.epc.cmp(&b.epc));
+    diff.rssi_changed.sort_by(|a, b| a.epc.cmp(&b.epc));
+    diff
+}
+
+/// Result of reconciling tag contents with the database
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    /// Samples whose tag content matches the stored record
+    pub unchanged: Vec<String>,
+    /// Samples whose tag content differs from the stored record
+    pub changed: Vec<String>,
+    /// Samples with no stored record
+    pub unknown: Vec<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,17 +726,17 @@ mod tests {
         
         // Create and write a sample
         let sample = create_test_sample("TEST-001");
-        let tag = sample.to_tag().unwrap();
+        let tag = sample.to_tag_with_key(default_encryption()).unwrap();
         let tag_data = crate::tag::TagData::new(tag.to_bytes().unwrap());
         reader.write_tag(&tag_data).unwrap();
         
         // Scan tags
         let results = manager.scan_tags(&mut reader, Duration::from_millis(100)).unwrap();
-        assert!(results.len() > 0);
+        assert!(!results.is_empty());
         
         // Filter by EPC prefix
         let filtered = manager.filter_tags(&InventoryFilter::EpcPrefix("EPC-".to_string()));
-        assert!(filtered.len() > 0);
+        assert!(!filtered.is_empty());
     }
 
     #[test]
@@ -286,7 +745,7 @@ mod tests {
         let mut reader = MockRFIDReader::new();
         
         let sample = create_test_sample("TEST-002");
-        let tag = sample.to_tag().unwrap();
+        let tag = sample.to_tag_with_key(default_encryption()).unwrap();
         let tag_data = TagData::new(tag.to_bytes().unwrap());
         reader.write_tag(&tag_data).unwrap();
         
@@ -302,7 +761,7 @@ mod tests {
         let mut reader = MockRFIDReader::new();
         
         let sample = create_test_sample("TEST-003");
-        let tag = sample.to_tag().unwrap();
+        let tag = sample.to_tag_with_key(default_encryption()).unwrap();
         let tag_data = TagData::new(tag.to_bytes().unwrap());
         reader.write_tag(&tag_data).unwrap();
         
@@ -318,7 +777,7 @@ mod tests {
         let mut reader = MockRFIDReader::new();
         
         let sample = create_test_sample("TEST-004");
-        let tag = sample.to_tag().unwrap();
+        let tag = sample.to_tag_with_key(default_encryption()).unwrap();
         let tag_data = TagData::new(tag.to_bytes().unwrap());
         reader.write_tag(&tag_data).unwrap();
         
@@ -328,6 +787,211 @@ mod tests {
         assert!(!filtered.is_empty() || filtered.is_empty()); // Just check it doesn't panic
     }
 
+    fn manager_with(tags: &[(&str, i16, u8)]) -> InventoryManager {
+        let mut manager = InventoryManager::new();
+        for &(epc, rssi, antenna) in tags {
+            let tag = TagScanResult { antenna, ..scan(epc, rssi) };
+            manager.scanned_tags.insert(tag.epc.clone(), tag);
+        }
+        manager
+    }
+
+    fn filtered_epcs(manager: &InventoryManager, filter: &InventoryFilter) -> Vec<String> {
+        let mut epcs: Vec<String> = manager.filter_tags(filter).into_iter().map(|t| t.epc.clone()).collect();
+        epcs.sort();
+        epcs
+    }
+
+    #[test]
+    fn test_filter_and_requires_every_criterion() {
+        let manager = manager_with(&[
+            ("EPC-A1", -60, 1),
+            ("EPC-A2", -80, 1),
+            ("EPC-B1", -50, 2),
+        ]);
+        let filter = InventoryFilter::And(vec![InventoryFilter::MinRssi(-70), InventoryFilter::Antenna(1)]);
+        assert_eq!(filtered_epcs(&manager, &filter), vec!["EPC-A1"]);
+        assert_eq!(filtered_epcs(&manager, &InventoryFilter::And(vec![])).len(), 3);
+    }
+
+    #[test]
+    fn test_filter_or_matches_any_criterion() {
+        let manager = manager_with(&[
+            ("EPC-A1", -60, 1),
+            ("EPC-B1", -50, 2),
+            ("EPC-C1", -40, 1),
+        ]);
+        let filter = InventoryFilter::Or(vec![
+            InventoryFilter::EpcPrefix("EPC-A".to_string()),
+            InventoryFilter::EpcPrefix("EPC-B".to_string()),
+        ]);
+        assert_eq!(filtered_epcs(&manager, &filter), vec!["EPC-A1", "EPC-B1"]);
+        assert!(filtered_epcs(&manager, &InventoryFilter::Or(vec![])).is_empty());
+    }
+
+    #[test]
+    fn test_filter_not_and_rssi_range_compose() {
+        let manager = manager_with(&[
+            ("EPC-A1", -75, 1),
+            ("EPC-A2", -60, 2),
+            ("EPC-B1", -50, 1),
+            ("EPC-B2", -40, 1),
+        ]);
+        assert_eq!(
+            filtered_epcs(&manager, &InventoryFilter::RssiRange { min: -60, max: -50 }),
+            vec!["EPC-A2", "EPC-B1"]
+        );
+        let filter = InventoryFilter::And(vec![
+            InventoryFilter::Not(Box::new(InventoryFilter::Antenna(2))),
+            InventoryFilter::Or(vec![
+                InventoryFilter::EpcPrefix("EPC-A".to_string()),
+                InventoryFilter::RssiRange { min: -45, max: -30 },
+            ]),
+        ]);
+        assert_eq!(filtered_epcs(&manager, &filter), vec!["EPC-A1", "EPC-B2"]);
+    }
+
+    fn scan(epc: &str, rssi: i16) -> TagScanResult {
+        TagScanResult {
+            epc: epc.to_string(),
+            tag_id: epc.to_string(),
+            rssi,
+            antenna: 1,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_session_tracks_appearance_and_disappearance() {
+        let mut session = InventorySession::new(2);
+        let now = Utc::now();
+
+        let events = session.observe(vec![scan("A", -60), scan("B", -70)], now);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], SessionEvent::Appeared(t) if t.epc == "A"));
+
+        // B missed once: fading but still tracked
+        let events = session.observe(vec![scan("A", -55)], now);
+        assert!(events.is_empty());
+        let tags = session.tags();
+        assert_eq!(tags[0].rssi_trend(), RssiTrend::Rising);
+        assert!(tags[1].is_fading());
+
+        // B missed twice: declared missing
+        let events = session.observe(vec![scan("A", -58)], now);
+        assert!(matches!(&events[..], [SessionEvent::Missing(t)] if t.scan.epc == "B"));
+        assert_eq!(session.tags().len(), 1);
+        assert_eq!(session.tags()[0].rssi_trend(), RssiTrend::Falling);
+        assert_eq!(session.cycles(), 3);
+    }
+
+    #[test]
+    fn test_scan_cycle_from_simulator() {
+        use crate::hardware::simulator::SimulatedTag;
+
+        let mut simulator = TagSimulator::new()
+            .with_network_delay(Duration::ZERO);
+        simulator.add_tag(SimulatedTag::new("E2001".to_string(), "TEST-007".to_string(), vec![]));
+
+        let mut manager = InventoryManager::new();
+        let mut session = InventorySession::new(1);
+        let events = manager.scan_cycle(&mut simulator, &mut session, Duration::from_millis(50)).unwrap();
+
+        assert!(matches!(&events[..], [SessionEvent::Appeared(t)] if t.tag_id == "TEST-007"));
+        assert_eq!(manager.tag_count(), 1);
+    }
+
+    #[test]
+    fn test_scan_source_caps_results() {
+        use crate::hardware::simulator::SimulatedTag;
+
+        let mut simulator = TagSimulator::new().with_network_delay(Duration::ZERO);
+        for i in 0..25 {
+            simulator.add_tag(SimulatedTag::new(format!("E2-{:03}", i), format!("T-{}", i), vec![]));
+        }
+
+        let mut manager = InventoryManager::new();
+        let scan = manager.scan_source(&mut simulator, Duration::from_millis(50), Some(10)).unwrap();
+        assert_eq!(scan.tags.len(), 10);
+        assert!(scan.truncated);
+        assert_eq!(scan.total_seen, 25);
+        let distinct: std::collections::HashSet<_> = scan.tags.iter().map(|t| &t.epc).collect();
+        assert_eq!(distinct.len(), 10);
+        assert_eq!(manager.tag_count(), 10);
+
+        let scan = manager.scan_source(&mut simulator, Duration::from_millis(50), Some(100)).unwrap();
+        assert_eq!(scan.tags.len(), 25);
+        assert!(!scan.truncated);
+    }
+
+    #[test]
+    fn test_scan_delta_reports_arrivals_and_departures() {
+        use crate::hardware::simulator::SimulatedTag;
+
+        let mut simulator = TagSimulator::new().with_network_delay(Duration::ZERO);
+        simulator.add_tag(SimulatedTag::new("E2-A".to_string(), "T-A".to_string(), vec![]));
+        simulator.add_tag(SimulatedTag::new("E2-B".to_string(), "T-B".to_string(), vec![]));
+        let mut manager = InventoryManager::new();
+
+        // First scan: everything arrives
+        let scan = manager.scan_source(&mut simulator, Duration::from_millis(50), None).unwrap();
+        assert_eq!(scan.events.len(), 2);
+        assert!(scan.events.iter().all(|e| matches!(e, InventoryEvent::TagArrived(_))));
+
+        // Unchanged field: nothing to report
+        let scan = manager.scan_source(&mut simulator, Duration::from_millis(50), None).unwrap();
+        assert!(scan.events.is_empty());
+
+        // A tag that flickers out departs, then arrives again
+        simulator.remove_tag("E2-B");
+        let scan = manager.scan_source(&mut simulator, Duration::from_millis(50), None).unwrap();
+        assert!(matches!(&scan.events[..], [InventoryEvent::TagDeparted(t)] if t.epc == "E2-B"));
+
+        simulator.add_tag(SimulatedTag::new("E2-B".to_string(), "T-B".to_string(), vec![]));
+        let scan = manager.scan_source(&mut simulator, Duration::from_millis(50), None).unwrap();
+        assert!(matches!(&scan.events[..], [InventoryEvent::TagArrived(t)] if t.epc == "E2-B"));
+        assert_eq!(scan.events[0].notification().event, TAG_ARRIVED_EVENT);
+    }
+
+    #[test]
+    fn test_diff_snapshots_reports_added_and_removed() {
+        let tag = |epc: &str, rssi: i16| TagScanResult {
+            epc: epc.to_string(),
+            tag_id: format!("T-{}", epc),
+            rssi,
+            antenna: 1,
+            timestamp: Utc::now(),
+        };
+        let baseline = InventorySnapshot {
+            taken_at: Utc::now(),
+            tags: vec![tag("E-1", -50), tag("E-2", -50), tag("E-3", -50)],
+        };
+        let current = InventorySnapshot {
+            taken_at: Utc::now(),
+            // E-1 jitters, E-2 moved closer, E-3 is gone and E-4 is new
+            tags: vec![tag("E-1", -52), tag("E-2", -40), tag("E-4", -60)],
+        };
+
+        let diff = diff_snapshots(&baseline, &current);
+        assert_eq!(diff.added.iter().map(|t| t.epc.as_str()).collect::<Vec<_>>(), vec!["E-4"]);
+        assert_eq!(diff.removed.iter().map(|t| t.epc.as_str()).collect::<Vec<_>>(), vec!["E-3"]);
+        assert_eq!(diff.rssi_changed, vec![RssiChange { epc: "E-2".to_string(), old_rssi: -50, new_rssi: -40 }]);
+        assert!(diff_snapshots(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn test_scan_tags_respects_max_results() {
+        let mut manager = InventoryManager::new().with_max_results(0);
+        let mut reader = MockRFIDReader::new();
+        let tag = create_test_sample("TEST-CAP").to_tag_with_key(default_encryption()).unwrap();
+        reader.write_tag(&TagData::new(tag.to_bytes().unwrap())).unwrap();
+
+        let scan = manager.scan_tags_capped(&mut reader, Duration::from_millis(20), manager.max_results()).unwrap();
+        assert!(scan.tags.is_empty());
+        assert!(scan.truncated);
+        assert_eq!(scan.total_seen, 1);
+    }
+
     #[test]
     fn test_get_all_tags() {
         let manager = InventoryManager::new();
@@ -361,7 +1025,7 @@ mod tests {
         let mut reader = MockRFIDReader::new();
         
         let sample = create_test_sample("TEST-005");
-        let tag = sample.to_tag().unwrap();
+        let tag = sample.to_tag_with_key(default_encryption()).unwrap();
         let tag_data = TagData::new(tag.to_bytes().unwrap());
         reader.write_tag(&tag_data).unwrap();
         
@@ -378,13 +1042,13 @@ mod tests {
         let mut reader = MockRFIDReader::new();
         
         let sample = create_test_sample("TEST-006");
-        let tag = sample.to_tag().unwrap();
+        let tag = sample.to_tag_with_key(default_encryption()).unwrap();
         let tag_data = TagData::new(tag.to_bytes().unwrap());
         reader.write_tag(&tag_data).unwrap();
         
         let tag_ids = vec!["TEST-006".to_string()];
         let samples = manager.batch_read_samples(&mut reader, &tag_ids).unwrap();
-        assert!(samples.len() > 0);
+        assert!(!samples.is_empty());
     }
 
     #[test]
@@ -403,7 +1067,7 @@ mod tests {
         let mut reader = MockRFIDReader::new();
         
         let sample = create_test_sample("TEST-007");
-        let tag = sample.to_tag().unwrap();
+        let tag = sample.to_tag_with_key(default_encryption()).unwrap();
         let tag_data = TagData::new(tag.to_bytes().unwrap());
         reader.write_tag(&tag_data).unwrap();
         
@@ -427,5 +1091,25 @@ mod tests {
         assert_eq!(report.total_tags, deserialized.total_tags);
         assert_eq!(report.average_rssi, deserialized.average_rssi);
     }
-}
 
+    #[test]
+    fn test_reconcile_samples() {
+        let manager = InventoryManager::new();
+        let db = Database::in_memory().unwrap();
+        let unchanged = create_test_sample("REC-001");
+        let changed = create_test_sample("REC-002");
+        db.store_sample(&unchanged).unwrap();
+        db.store_sample(&changed).unwrap();
+        
+        let mut reread = unchanged.clone();
+        reread.increment_read_count();
+        let mut modified = changed.clone();
+        modified.update_status(crate::sample::SampleStatus::Stored).unwrap();
+        let unknown = create_test_sample("REC-003");
+        
+        let report = manager.reconcile_samples(&db, &[reread, modified, unknown]).unwrap();
+        assert_eq!(report.unchanged, vec!["REC-001"]);
+        assert_eq!(report.changed, vec!["REC-002"]);
+        assert_eq!(report.unknown, vec!["REC-003"]);
+    }
+}
@@ -0,0 +1,970 @@
+use crate::audit::{AuditEventType, AuditLogger, AuditSeverity};
+use crate::config::{parse_duration, JobConfig};
+use crate::database::Database;
+use crate::error::{SampleGuardError, Result};
+use crate::inventory::InventoryManager;
+use crate::temperature::TemperatureMonitor;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of the current time for the scheduler
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall clock time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Manually advanced clock for deterministic scheduling
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(start) }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, by: chrono::Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Shared resources available to jobs
+#[derive(Clone)]
+pub struct JobContext {
+    pub database: Arc<Mutex<Database>>,
+    pub temperature_monitor: Arc<Mutex<TemperatureMonitor>>,
+    pub audit_logger: Arc<Mutex<AuditLogger>>,
+    pub inventory: Arc<Mutex<InventoryManager>>,
+}
+
+/// Tells a running job that the scheduler has stopped waiting for it
+///
+/// A thread cannot be stopped from outside, so timeouts are advisory: a job
+/// past its timeout keeps running, and keeps any locks it holds, until it
+/// next checks its token. Long-running jobs should call
+/// [`check`](Self::check) between units of work.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// A token that is only cancelled explicitly
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also count as cancelled once `deadline` has passed
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst) || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Fail with a timed-out error once cancelled
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(SampleGuardError::IoError(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "job cancelled after its timeout",
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A unit of periodic maintenance work
+pub trait Job: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Run the job at scheduler time `now`, returning a short summary.
+    /// `cancel` is cancelled when the run times out; the job should then
+    /// return promptly so it releases the shared resources it holds.
+    fn run(&self, ctx: &JobContext, now: DateTime<Utc>, cancel: &CancellationToken) -> Result<String>;
+}
+
+/// Outcome of a single job run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum JobOutcome {
+    Succeeded { summary: String },
+    Failed { error: String },
+    TimedOut,
+    SkippedOverlap,
+}
+
+/// Record of a single scheduled run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRunReport {
+    pub job: String,
+    pub scheduled_at: DateTime<Utc>,
+    pub outcome: JobOutcome,
+    pub duration_ms: u64,
+}
+
+/// Current status of a scheduled job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub interval_secs: u64,
+    pub timeout_secs: u64,
+    pub next_run: DateTime<Utc>,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_outcome: Option<JobOutcome>,
+    pub last_duration_ms: Option<u64>,
+    pub run_count: u64,
+    pub failure_count: u64,
+    pub skipped_count: u64,
+}
+
+/// Shared view of job statuses, readable while the scheduler runs
+#[derive(Clone, Default)]
+pub struct JobStatusRegistry {
+    inner: Arc<Mutex<HashMap<String, JobStatus>>>,
+}
+
+impl JobStatusRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot of all job statuses ordered by name
+    pub fn snapshot(&self) -> Vec<JobStatus> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut statuses: Vec<JobStatus> = inner.values().cloned().collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    fn update(&self, status: &JobStatus) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.insert(status.name.clone(), status.clone());
+    }
+}
+
+struct ScheduledJob {
+    job: Arc<dyn Job>,
+    interval: Duration,
+    timeout: Duration,
+    running: Arc<AtomicBool>,
+    status: JobStatus,
+}
+
+/// Clears the running flag when a job thread finishes, even by panicking
+struct RunningGuard(Arc<AtomicBool>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Runs registered jobs on their intervals with per-job timeouts
+///
+/// Each run executes on its own thread so a failing, panicking or hung job
+/// cannot take down the scheduler. Timeouts are advisory: the scheduler
+/// stops waiting and cancels the job's [`CancellationToken`], but the thread
+/// runs on until the job notices. Until that thread has finished, each due
+/// run of the job is skipped rather than started twice.
+pub struct JobScheduler {
+    jobs: Vec<ScheduledJob>,
+    clock: Arc<dyn Clock>,
+    context: JobContext,
+    registry: JobStatusRegistry,
+}
+
+impl JobScheduler {
+    /// Create an empty scheduler
+    pub fn new(context: JobContext, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            jobs: Vec::new(),
+            clock,
+            context,
+            registry: JobStatusRegistry::new(),
+        }
+    }
+
+    /// Publish job statuses to an existing registry
+    pub fn with_registry(mut self, registry: JobStatusRegistry) -> Self {
+        self.registry = registry;
+        for scheduled in &self.jobs {
+            self.registry.update(&scheduled.status);
+        }
+        self
+    }
+
+    /// Build a scheduler from the `[[jobs]]` section of the configuration
+    pub fn from_config(context: JobContext, clock: Arc<dyn Clock>, jobs: &[JobConfig]) -> Result<Self> {
+        let mut scheduler = Self::new(context, clock);
+        for config in jobs.iter().filter(|j| j.enabled) {
+            let job = builtin_job(config)?;
+            scheduler.register(job, parse_duration(&config.every)?, parse_duration(&config.timeout)?)?;
+        }
+        Ok(scheduler)
+    }
+
+    /// Register a job; its first run is due one interval from now
+    pub fn register(&mut self, job: Arc<dyn Job>, interval: Duration, timeout: Duration) -> Result<()> {
+        if interval.is_zero() {
+            return Err(SampleGuardError::ConfigError(format!(
+                "Job '{}' must have a non-zero interval",
+                job.name()
+            )));
+        }
+        if self.jobs.iter().any(|j| j.job.name() == job.name()) {
+            return Err(SampleGuardError::ConfigError(format!(
+                "Job '{}' is registered twice",
+                job.name()
+            )));
+        }
+
+        let status = JobStatus {
+            name: job.name().to_string(),
+            interval_secs: interval.as_secs(),
+            timeout_secs: timeout.as_secs(),
+            next_run: self.clock.now() + to_chrono(interval),
+            last_run: None,
+            last_outcome: None,
+            last_duration_ms: None,
+            run_count: 0,
+            failure_count: 0,
+            skipped_count: 0,
+        };
+        self.registry.update(&status);
+        self.jobs.push(ScheduledJob {
+            job,
+            interval,
+            timeout,
+            running: Arc::new(AtomicBool::new(false)),
+            status,
+        });
+        Ok(())
+    }
+
+    /// Number of registered jobs
+    pub fn job_count(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Registry exposing job statuses
+    pub fn registry(&self) -> JobStatusRegistry {
+        self.registry.clone()
+    }
+
+    /// Run every job that is due, earliest first, and report the outcomes
+    pub fn tick(&mut self) -> Vec<JobRunReport> {
+        let now = self.clock.now();
+        let mut due: Vec<usize> = (0..self.jobs.len())
+            .filter(|&i| self.jobs[i].status.next_run <= now)
+            .collect();
+        due.sort_by_key(|&i| self.jobs[i].status.next_run);
+
+        due.into_iter().map(|i| self.run_job(i, now)).collect()
+    }
+
+    /// Tick repeatedly until `shutdown` is set
+    pub fn run(&mut self, shutdown: &AtomicBool, poll_interval: Duration) {
+        while !shutdown.load(Ordering::SeqCst) {
+            self.tick();
+            std::thread::sleep(poll_interval);
+        }
+    }
+
+    fn run_job(&mut self, index: usize, now: DateTime<Utc>) -> JobRunReport {
+        let scheduled = &mut self.jobs[index];
+        let started = Instant::now();
+
+        let outcome = if scheduled.running.swap(true, Ordering::SeqCst) {
+            JobOutcome::SkippedOverlap
+        } else {
+            let (tx, rx) = mpsc::channel();
+            let job = Arc::clone(&scheduled.job);
+            let context = self.context.clone();
+            let guard = RunningGuard(Arc::clone(&scheduled.running));
+            let cancel = CancellationToken::new().with_deadline(started + scheduled.timeout);
+            let job_cancel = cancel.clone();
+
+            let spawned = std::thread::Builder::new()
+                .name(format!("job-{}", job.name()))
+                .spawn(move || {
+                    let result = job.run(&context, now, &job_cancel);
+                    // Clear the running flag before reporting so an immediate
+                    // re-run is not mistaken for an overlap
+                    drop(guard);
+                    let _ = tx.send(result);
+                });
+
+            match spawned {
+                Ok(_) => match rx.recv_timeout(scheduled.timeout) {
+                    Ok(Ok(summary)) => JobOutcome::Succeeded { summary },
+                    Ok(Err(e)) => JobOutcome::Failed { error: e.to_string() },
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        cancel.cancel();
+                        JobOutcome::TimedOut
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => JobOutcome::Failed {
+                        error: "job panicked".to_string(),
+                    },
+                },
+                Err(e) => {
+                    scheduled.running.store(false, Ordering::SeqCst);
+                    JobOutcome::Failed { error: format!("failed to spawn job thread: {}", e) }
+                }
+            }
+        };
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let status = &mut scheduled.status;
+        status.next_run = now + to_chrono(scheduled.interval);
+        status.last_run = Some(now);
+        status.last_outcome = Some(outcome.clone());
+        status.last_duration_ms = Some(duration_ms);
+        match outcome {
+            JobOutcome::Succeeded { .. } => status.run_count += 1,
+            JobOutcome::Failed { .. } | JobOutcome::TimedOut => {
+                status.run_count += 1;
+                status.failure_count += 1;
+            }
+            JobOutcome::SkippedOverlap => status.skipped_count += 1,
+        }
+        self.registry.update(status);
+
+        let report = JobRunReport {
+            job: status.name.clone(),
+            scheduled_at: now,
+            outcome,
+            duration_ms,
+        };
+        self.record_outcome(&report);
+        report
+    }
+
+    fn record_outcome(&self, report: &JobRunReport) {
+        let severity = match report.outcome {
+            JobOutcome::Succeeded { .. } => AuditSeverity::Info,
+            JobOutcome::SkippedOverlap => AuditSeverity::Warning,
+            JobOutcome::Failed { .. } | JobOutcome::TimedOut => AuditSeverity::Error,
+        };
+        let details = serde_json::json!({
+            "job": report.job,
+            "scheduled_at": report.scheduled_at,
+            "duration_ms": report.duration_ms,
+            "result": report.outcome,
+        });
+
+        let logged = self.context.audit_logger
+            .lock()
+            .map_err(|e| SampleGuardError::IoError(std::io::Error::other(e.to_string())))
+            .and_then(|mut logger| {
+                logger.log_event(AuditEventType::JobExecuted, None, None, details, severity)
+            });
+        if let Err(e) = logged {
+            log::error!(job = report.job.as_str(); "Failed to record outcome of job {}: {}", report.job, e);
+        }
+    }
+}
+
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX)
+}
+
+fn lock_error<T>(e: std::sync::PoisonError<T>) -> SampleGuardError {
+    SampleGuardError::IoError(std::io::Error::other(format!("Lock poisoned: {}", e)))
+}
+
+/// Construct a built-in job from its configuration entry
+pub fn builtin_job(config: &JobConfig) -> Result<Arc<dyn Job>> {
+    let job: Arc<dyn Job> = match config.name.as_str() {
+        "history_prune" => Arc::new(HistoryPruneJob {
+            retention: chrono::Duration::days(config.retention_days.unwrap_or(365) as i64),
+        }),
+        "temperature_downsample" => Arc::new(TemperatureDownsampleJob {
+            bucket: to_chrono(parse_duration(config.bucket.as_deref().unwrap_or("1h"))?),
+            older_than: to_chrono(parse_duration(config.older_than.as_deref().unwrap_or("1d"))?),
+        }),
+        "expiry_warning" => Arc::new(match (&config.lead_days, config.warning_days) {
+            (Some(lead_days), _) => ExpiryWarningJob::with_ladder(lead_days)?,
+            (None, Some(days)) => ExpiryWarningJob::single(days),
+            (None, None) => ExpiryWarningJob::default(),
+        }),
+        "inventory_reconciliation" => Arc::new(InventoryReconciliationJob),
+        other => {
+            return Err(SampleGuardError::ConfigError(format!("Unknown job '{}'", other)));
+        }
+    };
+    Ok(job)
+}
+
+/// Removes sample history older than the retention period
+pub struct HistoryPruneJob {
+    pub retention: chrono::Duration,
+}
+
+impl Job for HistoryPruneJob {
+    fn name(&self) -> &str {
+        "history_prune"
+    }
+
+    fn run(&self, ctx: &JobContext, now: DateTime<Utc>, cancel: &CancellationToken) -> Result<String> {
+        let db = ctx.database.lock().map_err(lock_error)?;
+        cancel.check()?;
+        let removed = db.prune_history_before(now - self.retention)?;
+        Ok(format!("removed {} history entries", removed))
+    }
+}
+
+/// Averages old temperature readings into fixed windows
+pub struct TemperatureDownsampleJob {
+    pub bucket: chrono::Duration,
+    pub older_than: chrono::Duration,
+}
+
+impl Job for TemperatureDownsampleJob {
+    fn name(&self) -> &str {
+        "temperature_downsample"
+    }
+
+    fn run(&self, ctx: &JobContext, now: DateTime<Utc>, cancel: &CancellationToken) -> Result<String> {
+        let mut monitor = ctx.temperature_monitor.lock().map_err(lock_error)?;
+        cancel.check()?;
+        let removed = monitor.downsample(self.bucket, now - self.older_than)?;
+        Ok(format!("downsampled {} readings", removed))
+    }
+}
+
+/// Lead times of the default expiry alert ladder, in days
+pub const DEFAULT_EXPIRY_LEAD_DAYS: [u32; 4] = [90, 30, 7, 1];
+
+/// One rung of the expiry alert ladder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiryThreshold {
+    pub lead_days: u32,
+    pub severity: AuditSeverity,
+}
+
+/// Emits an audit event when a sample comes within each lead time of its
+/// expiry date. Each threshold alerts once per sample; a sample first seen
+/// past several thresholds alerts only for the shortest.
+pub struct ExpiryWarningJob {
+    /// Longest lead time first
+    thresholds: Vec<ExpiryThreshold>,
+}
+
+impl ExpiryWarningJob {
+    /// Ladder over `lead_days`, escalating from critical at the shortest
+    /// lead time through error and warning to info
+    pub fn with_ladder(lead_days: &[u32]) -> Result<Self> {
+        let mut days = lead_days.to_vec();
+        days.sort_unstable_by(|a, b| b.cmp(a));
+        days.dedup();
+        if days.is_empty() || days.contains(&0) {
+            return Err(SampleGuardError::ConfigError(
+                "expiry lead days must be a non-empty list of positive day counts".to_string(),
+            ));
+        }
+        let escalation = [AuditSeverity::Critical, AuditSeverity::Error, AuditSeverity::Warning];
+        let thresholds = days
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(rank, &lead_days)| ExpiryThreshold {
+                lead_days,
+                severity: escalation.get(rank).cloned().unwrap_or(AuditSeverity::Info),
+            })
+            .rev()
+            .collect();
+        Ok(Self { thresholds })
+    }
+
+    /// A single warning `days` ahead of expiry
+    pub fn single(days: u32) -> Self {
+        Self {
+            thresholds: vec![ExpiryThreshold { lead_days: days, severity: AuditSeverity::Warning }],
+        }
+    }
+
+    pub fn thresholds(&self) -> &[ExpiryThreshold] {
+        &self.thresholds
+    }
+}
+
+impl Default for ExpiryWarningJob {
+    fn default() -> Self {
+        Self::with_ladder(&DEFAULT_EXPIRY_LEAD_DAYS).expect("default ladder is valid")
+    }
+}
+
+impl Job for ExpiryWarningJob {
+    fn name(&self) -> &str {
+        "expiry_warning"
+    }
+
+    fn run(&self, ctx: &JobContext, now: DateTime<Utc>, cancel: &CancellationToken) -> Result<String> {
+        let longest = self.thresholds.first().map_or(0, |t| t.lead_days);
+        let horizon = now + chrono::Duration::days(longest as i64);
+        let db = ctx.database.lock().map_err(lock_error)?;
+        let expiring: Vec<_> = db
+            .get_all_samples()?
+            .into_iter()
+            .filter(|s| s.metadata.expiry_date.is_some_and(|d| d > now && d <= horizon))
+            .collect();
+
+        let mut alerts = 0;
+        let mut logger = ctx.audit_logger.lock().map_err(lock_error)?;
+        for sample in &expiring {
+            cancel.check()?;
+            let expiry = sample.metadata.expiry_date.unwrap_or(horizon);
+            let crossed: Vec<&ExpiryThreshold> = self
+                .thresholds
+                .iter()
+                .filter(|t| expiry <= now + chrono::Duration::days(t.lead_days as i64))
+                .collect();
+
+            // Mark every crossed rung, but only the shortest newly crossed one alerts
+            let mut newest = None;
+            for threshold in crossed {
+                if db.record_expiry_alert(&sample.sample_id, threshold.lead_days, now)? {
+                    newest = Some(threshold);
+                }
+            }
+            let Some(threshold) = newest else {
+                continue;
+            };
+            logger.log_event(
+                AuditEventType::ExpiryWarning,
+                None,
+                Some(sample.sample_id.clone()),
+                serde_json::json!({
+                    "expiry_date": expiry,
+                    "days_remaining": (expiry - now).num_days(),
+                    "lead_days": threshold.lead_days,
+                }),
+                threshold.severity.clone(),
+            )?;
+            alerts += 1;
+        }
+
+        Ok(format!(
+            "{} samples expire within {} days, {} new alerts",
+            expiring.len(),
+            longest,
+            alerts
+        ))
+    }
+}
+
+/// Compares the last inventory scan against stored samples
+pub struct InventoryReconciliationJob;
+
+impl Job for InventoryReconciliationJob {
+    fn name(&self) -> &str {
+        "inventory_reconciliation"
+    }
+
+    fn run(&self, ctx: &JobContext, _now: DateTime<Utc>, cancel: &CancellationToken) -> Result<String> {
+        let tag_ids: Vec<String> = {
+            let inventory = ctx.inventory.lock().map_err(lock_error)?;
+            inventory.get_all_tags().iter().map(|t| t.tag_id.clone()).collect()
+        };
+
+        let db = ctx.database.lock().map_err(lock_error)?;
+        let mut matched = 0;
+        for tag_id in &tag_ids {
+            cancel.check()?;
+            if db.get_sample(tag_id)?.is_some() {
+                matched += 1;
+            }
+        }
+
+        Ok(format!(
+            "{} tags scanned, {} matched stored samples, {} unknown",
+            tag_ids.len(),
+            matched,
+            tag_ids.len() - matched
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temperature::MockTemperatureSensor;
+    use std::sync::atomic::AtomicUsize;
+
+    struct RecordingJob {
+        name: String,
+        runs: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Job for RecordingJob {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn run(&self, _ctx: &JobContext, _now: DateTime<Utc>, _cancel: &CancellationToken) -> Result<String> {
+            self.runs.lock().unwrap().push(self.name.clone());
+            Ok("ok".to_string())
+        }
+    }
+
+    /// Ignores cancellation and waits to be released
+    struct BlockingJob {
+        release: Mutex<mpsc::Receiver<()>>,
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl Job for BlockingJob {
+        fn name(&self) -> &str {
+            "blocking"
+        }
+
+        fn run(&self, _ctx: &JobContext, _now: DateTime<Utc>, _cancel: &CancellationToken) -> Result<String> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let _ = self.release.lock().unwrap().recv();
+            Ok("released".to_string())
+        }
+    }
+
+    /// Holds the database lock until cancelled
+    struct CooperativeJob {
+        cancelled: Arc<AtomicBool>,
+    }
+
+    impl Job for CooperativeJob {
+        fn name(&self) -> &str {
+            "cooperative"
+        }
+
+        fn run(&self, ctx: &JobContext, _now: DateTime<Utc>, cancel: &CancellationToken) -> Result<String> {
+            let _db = ctx.database.lock().map_err(lock_error)?;
+            while !cancel.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            self.cancelled.store(true, Ordering::SeqCst);
+            cancel.check().map(|_| "finished".to_string())
+        }
+    }
+
+    struct FailingJob;
+
+    impl Job for FailingJob {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn run(&self, _ctx: &JobContext, _now: DateTime<Utc>, _cancel: &CancellationToken) -> Result<String> {
+            Err(SampleGuardError::InvalidSampleData("boom".to_string()))
+        }
+    }
+
+    struct PanickingJob;
+
+    impl Job for PanickingJob {
+        fn name(&self) -> &str {
+            "panicking"
+        }
+
+        fn run(&self, _ctx: &JobContext, _now: DateTime<Utc>, _cancel: &CancellationToken) -> Result<String> {
+            panic!("job panicked on purpose");
+        }
+    }
+
+    fn create_context() -> JobContext {
+        let sensor = Box::new(MockTemperatureSensor::new("JOB-SENSOR".to_string(), 5.0));
+        JobContext {
+            database: Arc::new(Mutex::new(Database::in_memory().unwrap())),
+            temperature_monitor: Arc::new(Mutex::new(TemperatureMonitor::new(sensor, (2.0, 8.0)).unwrap())),
+            audit_logger: Arc::new(Mutex::new(AuditLogger::new())),
+            inventory: Arc::new(Mutex::new(InventoryManager::new())),
+        }
+    }
+
+    fn start_time() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    /// Wait for the thread of the job at `index` to finish
+    fn wait_until_idle(scheduler: &JobScheduler, index: usize) {
+        let running = Arc::clone(&scheduler.jobs[index].running);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while running.load(Ordering::SeqCst) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(!running.load(Ordering::SeqCst), "job thread still running");
+    }
+
+    #[test]
+    fn test_jobs_run_in_due_order() {
+        let clock = Arc::new(ManualClock::new(start_time()));
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = JobScheduler::new(create_context(), clock.clone());
+
+        for (name, secs) in [("slow", 300), ("fast", 60)] {
+            let job = Arc::new(RecordingJob { name: name.to_string(), runs: runs.clone() });
+            scheduler.register(job, Duration::from_secs(secs), Duration::from_secs(5)).unwrap();
+        }
+
+        assert!(scheduler.tick().is_empty());
+
+        clock.advance(chrono::Duration::seconds(60));
+        scheduler.tick();
+        clock.advance(chrono::Duration::seconds(240));
+        scheduler.tick();
+
+        // At t=300 both are due: "fast" (due since 120s) runs before "slow"
+        assert_eq!(*runs.lock().unwrap(), vec!["fast", "fast", "slow"]);
+        let statuses = scheduler.registry().snapshot();
+        assert_eq!(statuses[0].name, "fast");
+        assert_eq!(statuses[0].run_count, 2);
+        assert_eq!(statuses[1].run_count, 1);
+    }
+
+    #[test]
+    fn test_skip_on_overlap() {
+        let clock = Arc::new(ManualClock::new(start_time()));
+        let (release, receiver) = mpsc::channel();
+        let mut scheduler = JobScheduler::new(create_context(), clock.clone());
+        scheduler.register(
+            Arc::new(BlockingJob { release: Mutex::new(receiver), runs: Arc::new(AtomicUsize::new(0)) }),
+            Duration::from_secs(60),
+            Duration::from_millis(50),
+        ).unwrap();
+
+        clock.advance(chrono::Duration::seconds(60));
+        let reports = scheduler.tick();
+        assert_eq!(reports[0].outcome, JobOutcome::TimedOut);
+
+        clock.advance(chrono::Duration::seconds(60));
+        let reports = scheduler.tick();
+        assert_eq!(reports[0].outcome, JobOutcome::SkippedOverlap);
+
+        release.send(()).unwrap();
+        wait_until_idle(&scheduler, 0);
+
+        release.send(()).unwrap();
+        clock.advance(chrono::Duration::seconds(60));
+        let reports = scheduler.tick();
+        assert_eq!(reports[0].outcome, JobOutcome::Succeeded { summary: "released".to_string() });
+        assert_eq!(scheduler.registry().snapshot()[0].skipped_count, 1);
+    }
+
+    #[test]
+    fn test_next_tick_skips_still_running_job() {
+        let clock = Arc::new(ManualClock::new(start_time()));
+        let (release, receiver) = mpsc::channel();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut scheduler = JobScheduler::new(create_context(), clock.clone());
+        scheduler.register(
+            Arc::new(BlockingJob { release: Mutex::new(receiver), runs: runs.clone() }),
+            Duration::from_secs(60),
+            Duration::from_millis(20),
+        ).unwrap();
+
+        clock.advance(chrono::Duration::seconds(60));
+        assert_eq!(scheduler.tick()[0].outcome, JobOutcome::TimedOut);
+
+        // The timed-out thread ignores its token, so no tick starts a second copy
+        for _ in 0..3 {
+            clock.advance(chrono::Duration::seconds(60));
+            assert_eq!(scheduler.tick()[0].outcome, JobOutcome::SkippedOverlap);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        release.send(()).unwrap();
+        wait_until_idle(&scheduler, 0);
+        release.send(()).unwrap();
+        clock.advance(chrono::Duration::seconds(60));
+        assert!(matches!(scheduler.tick()[0].outcome, JobOutcome::Succeeded { .. }));
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(scheduler.registry().snapshot()[0].skipped_count, 3);
+    }
+
+    #[test]
+    fn test_timed_out_job_is_cancelled() {
+        let clock = Arc::new(ManualClock::new(start_time()));
+        let context = create_context();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut scheduler = JobScheduler::new(context.clone(), clock.clone());
+        scheduler.register(
+            Arc::new(CooperativeJob { cancelled: cancelled.clone() }),
+            Duration::from_secs(60),
+            Duration::from_millis(20),
+        ).unwrap();
+
+        clock.advance(chrono::Duration::seconds(60));
+        assert_eq!(scheduler.tick()[0].outcome, JobOutcome::TimedOut);
+
+        // The job sees its token cancelled, stops and releases the database
+        wait_until_idle(&scheduler, 0);
+        assert!(cancelled.load(Ordering::SeqCst));
+        assert!(context.database.try_lock().is_ok());
+    }
+
+    #[test]
+    fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        assert!(token.check().is_ok());
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        assert!(token.check().is_err());
+
+        let expired = CancellationToken::new().with_deadline(Instant::now());
+        assert!(expired.is_cancelled());
+        let pending = CancellationToken::new().with_deadline(Instant::now() + Duration::from_secs(60));
+        assert!(!pending.is_cancelled());
+    }
+
+    #[test]
+    fn test_failure_isolation() {
+        let clock = Arc::new(ManualClock::new(start_time()));
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let context = create_context();
+        let mut scheduler = JobScheduler::new(context.clone(), clock.clone());
+
+        scheduler.register(Arc::new(FailingJob), Duration::from_secs(60), Duration::from_secs(5)).unwrap();
+        scheduler.register(Arc::new(PanickingJob), Duration::from_secs(60), Duration::from_secs(5)).unwrap();
+        scheduler.register(
+            Arc::new(RecordingJob { name: "healthy".to_string(), runs: runs.clone() }),
+            Duration::from_secs(60),
+            Duration::from_secs(5),
+        ).unwrap();
+
+        clock.advance(chrono::Duration::seconds(60));
+        let reports = scheduler.tick();
+
+        assert_eq!(reports.len(), 3);
+        assert!(matches!(reports[0].outcome, JobOutcome::Failed { .. }));
+        assert_eq!(reports[1].outcome, JobOutcome::Failed { error: "job panicked".to_string() });
+        assert!(matches!(reports[2].outcome, JobOutcome::Succeeded { .. }));
+        assert_eq!(runs.lock().unwrap().len(), 1);
+
+        let logger = context.audit_logger.lock().unwrap();
+        assert_eq!(logger.get_events_by_type(&AuditEventType::JobExecuted).len(), 3);
+    }
+
+    #[test]
+    fn test_from_config_rejects_unknown_job() {
+        let config = JobConfig {
+            name: "unknown".to_string(),
+            every: "1h".to_string(),
+            timeout: "5m".to_string(),
+            enabled: true,
+            retention_days: None,
+            bucket: None,
+            older_than: None,
+            warning_days: None,
+            lead_days: None,
+        };
+        let result = JobScheduler::from_config(create_context(), Arc::new(SystemClock), &[config]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_expiry_warning_job() {
+        let context = create_context();
+        let metadata = crate::sample::SampleMetadata {
+            batch_number: "BATCH-EXP".to_string(),
+            production_date: start_time(),
+            expiry_date: Some(start_time() + chrono::Duration::days(10)),
+            temperature_range: None,
+            storage_conditions: "Ambient".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        let sample = crate::sample::Sample::new("EXP-001".to_string(), metadata, None);
+        context.database.lock().unwrap().store_sample(&sample).unwrap();
+
+        let job = ExpiryWarningJob::single(30);
+        let summary = job.run(&context, start_time(), &CancellationToken::new()).unwrap();
+        assert!(summary.starts_with("1 samples"));
+
+        let logger = context.audit_logger.lock().unwrap();
+        assert_eq!(logger.get_events_by_type(&AuditEventType::ExpiryWarning).len(), 1);
+    }
+
+    #[test]
+    fn test_expiry_ladder_alerts_once_per_threshold() {
+        let context = create_context();
+        let metadata = crate::sample::SampleMetadata {
+            batch_number: "BATCH-EXP".to_string(),
+            production_date: start_time(),
+            expiry_date: Some(start_time() + chrono::Duration::days(10)),
+            temperature_range: None,
+            storage_conditions: "Ambient".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        let sample = crate::sample::Sample::new("EXP-LADDER".to_string(), metadata, None);
+        context.database.lock().unwrap().store_sample(&sample).unwrap();
+        let job = ExpiryWarningJob::default();
+        let severities = |context: &JobContext| -> Vec<AuditSeverity> {
+            let logger = context.audit_logger.lock().unwrap();
+            logger.get_events_by_type(&AuditEventType::ExpiryWarning).iter().map(|e| e.severity.clone()).collect()
+        };
+
+        // 10 days out: past the 90- and 30-day marks, alerting once for the 30-day one
+        job.run(&context, start_time(), &CancellationToken::new()).unwrap();
+        assert_eq!(severities(&context), vec![AuditSeverity::Warning]);
+
+        // 6 days out crosses the 7-day mark
+        job.run(&context, start_time() + chrono::Duration::days(4), &CancellationToken::new()).unwrap();
+        assert_eq!(severities(&context), vec![AuditSeverity::Warning, AuditSeverity::Error]);
+
+        // Nothing new until the 1-day mark
+        job.run(&context, start_time() + chrono::Duration::days(5), &CancellationToken::new()).unwrap();
+        assert_eq!(severities(&context).len(), 2);
+
+        job.run(&context, start_time() + chrono::Duration::hours(9 * 24 + 12), &CancellationToken::new()).unwrap();
+        assert_eq!(
+            severities(&context),
+            vec![AuditSeverity::Warning, AuditSeverity::Error, AuditSeverity::Critical]
+        );
+        assert_eq!(
+            context.database.lock().unwrap().get_expiry_alerts("EXP-LADDER").unwrap(),
+            vec![90, 30, 7, 1]
+        );
+    }
+
+    #[test]
+    fn test_expiry_ladder_severities() {
+        let job = ExpiryWarningJob::with_ladder(&[7, 90, 1, 30, 180]).unwrap();
+        let ladder: Vec<(u32, AuditSeverity)> = job.thresholds().iter().map(|t| (t.lead_days, t.severity.clone())).collect();
+        assert_eq!(ladder, vec![
+            (180, AuditSeverity::Info),
+            (90, AuditSeverity::Info),
+            (30, AuditSeverity::Warning),
+            (7, AuditSeverity::Error),
+            (1, AuditSeverity::Critical),
+        ]);
+        assert!(ExpiryWarningJob::with_ladder(&[]).is_err());
+        assert!(ExpiryWarningJob::with_ladder(&[7, 0]).is_err());
+    }
+}
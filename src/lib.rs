@@ -1,5 +1,6 @@
 pub mod encryption;
 pub mod reader;
+pub mod region;
 pub mod sample;
 pub mod tag;
 pub mod error;
@@ -10,39 +11,119 @@ pub mod temperature;
 pub mod audit;
 pub mod api;
 pub mod hardware;
+pub mod config;
+pub mod commission;
+pub mod jobs;
+pub mod logging;
+pub mod notifications;
+pub mod cli;
+pub mod vocabulary;
+pub mod export;
+pub mod transitions;
+pub mod images;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "postgres")]
+pub mod postgres;
 
-pub use error::{SampleGuardError, Result};
-pub use sample::{Sample, SampleStatus, SampleMetadata};
-pub use tag::{RFIDTag, TagData, TagMemoryLayout};
+pub use error::{SampleGuardError, DatabaseErrorKind, ErrorCategory, Result};
+pub use sample::{Sample, ChecksumVersion, SampleStatus, SampleMetadata, IdGenerator, RandomIdGenerator, SequentialIdGenerator};
+pub use tag::{RFIDTag, TagData, TagMemoryLayout, TagFormat, reencrypt};
+pub use encryption::{RFIDEncryption, EncryptionMode, DecryptResult, KeyRing, TagKeys};
 pub use reader::{RFIDReader, ReaderConfig, ReaderCapabilities};
-pub use integrity::{IntegrityValidator, ValidationResult};
-pub use inventory::{InventoryManager, InventoryFilter, TagScanResult, InventoryReport};
-pub use database::{Database, HistoryEntry, DatabaseStatistics};
-pub use temperature::{TemperatureMonitor, TemperatureSensor, TemperatureReading, TemperatureViolation, TemperatureStatistics};
-pub use audit::{AuditLogger, AuditEventType, AuditEvent, AuditSeverity, AuditStatistics};
+pub use region::{RegulatoryRegion, RegionProfile};
+pub use integrity::{IntegrityValidator, ValidationResult, ValidationDelta};
+pub use inventory::{InventoryManager, InventoryFilter, TagScanResult, InventoryReport, ReconciliationReport, InventorySession, SessionEvent, TagSource, InventoryScan, InventoryEvent, InventorySnapshot, SnapshotDiff, diff_snapshots};
+pub use database::{Database, DatabaseBackend, DatabaseTransaction, Page, SamplesPage, SortOrder, LabelMatch, SampleQuery, HistoryEntry, HistoryRollup, CustodyEntry, SampleTemperatureSummary, DatabaseStatistics, TemperatureExcursion, VerificationReport, ConflictStrategy, ImportOutcome, ImportReport, BatchItem, BatchResult, Movement, ChildSpec, Lineage, LineageLink, LineageRelation, ArchivedSample};
+pub use temperature::{TemperatureMonitor, TemperatureSensor, TemperatureReading, TemperatureViolation, TemperatureStatistics, TemperatureAlert, ReadingStrategy, SensorDisagreement, SingleSensorStats, DateRange, TrendDirection, TrendAlert};
+pub use audit::{AuditLogger, AuditEventType, AuditEvent, AuditSeverity, AuditStatistics, FlushPolicy, AuditCursor, AuditFilter, AuditSink, DatabaseAuditSink, RotationPolicy, RotatingFileWriter, ChainVerification, BrokenLink, ArchiveReport, AUDIT_CSV_HEADER, write_events_csv};
+pub use config::{SampleGuardConfig, Capabilities};
+pub use vocabulary::StorageVocabulary;
+pub use transitions::{TransitionHook, NotificationHook};
+pub use commission::{VerifyOptions, VerifyOutcome};
+pub use jobs::{CancellationToken, Job, JobScheduler, JobOutcome, JobStatus};
 pub use hardware::{ImpinjSpeedwayReader, ZebraFX9600Reader, TagSimulator, SimulatedTag, HardwareDriver};
-pub use hardware::protocol::{ReaderProtocol, ReaderCommand, ProtocolResponse, MemoryBank};
+pub use hardware::protocol::{ReaderProtocol, ReaderCommand, ProtocolResponse, MemoryBank, BatchReadEntry};
 
 /// Main entry point for SampleGuard RFID system
 pub struct SampleGuard {
     reader: Box<dyn RFIDReader>,
     validator: IntegrityValidator,
+    encryption: RFIDEncryption,
 }
 
 impl SampleGuard {
-    /// Create a new SampleGuard instance with a configured RFID reader
+    /// Create a new SampleGuard instance with a configured RFID reader,
+    /// encrypting tags with the default key
     pub fn new(reader: Box<dyn RFIDReader>) -> Self {
         Self {
             reader,
             validator: IntegrityValidator::new(),
+            encryption: sample::default_encryption().clone(),
         }
     }
 
+    /// Encrypt and decrypt tags with `encryption` instead of the default key
+    pub fn with_encryption(mut self, encryption: RFIDEncryption) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// Accept samples on tags whose checksum is still in the legacy layout,
+    /// reporting them with a warning. Tags cannot be upgraded in place, so
+    /// this is the only way to keep reading them.
+    pub fn with_allow_legacy_checksums(mut self, allow: bool) -> Self {
+        self.validator = self.validator.with_allow_legacy_checksums(allow);
+        self
+    }
+
+    /// Builder for a SampleGuard with its own tag key
+    pub fn builder(reader: Box<dyn RFIDReader>) -> SampleGuardBuilder {
+        SampleGuardBuilder::new(reader)
+    }
+
     /// Read and validate a sample from an RFID tag
     pub fn read_sample(&mut self) -> Result<Sample> {
         let tag_data = self.reader.read_tag()?;
+        self.decode_sample(&tag_data)
+    }
+
+    /// Read a sample like [`read_sample`](Self::read_sample), logging a
+    /// `SampleRead` event on success or `ViolationDetected` when the tag
+    /// fails to decode or validate. Reader errors are returned unlogged.
+    pub fn read_sample_audited(&mut self, logger: &mut AuditLogger, user_id: Option<String>) -> Result<Sample> {
+        let tag_data = self.reader.read_tag()?;
+        match self.decode_sample(&tag_data) {
+            Ok(sample) => {
+                logger.log_sample_read_with_tag(&sample, tag_data.as_bytes(), user_id)?;
+                Ok(sample)
+            }
+            Err(e) => {
+                let sample_id = RFIDTag::from_bytes(tag_data.as_bytes()).ok().map(|tag| tag.tag_id);
+                let violations = match &e {
+                    SampleGuardError::IntegrityViolation(result) => {
+                        result.violations.iter().map(|v| format!("{:?}", v)).collect()
+                    }
+                    other => vec![other.to_string()],
+                };
+                logger.log_tag_violation(sample_id.as_deref(), violations, tag_data.as_bytes(), user_id)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Read a sample like [`read_sample`](Self::read_sample) and count the
+    /// read against its stored record, returning the sample with the stored
+    /// `read_count`. Fails if the sample is not in `database`.
+    pub fn read_sample_recorded(&mut self, database: &Database) -> Result<Sample> {
+        let mut sample = self.read_sample()?;
+        sample.read_count = database.increment_read_count(&sample.sample_id)?;
+        Ok(sample)
+    }
+
+    fn decode_sample(&self, tag_data: &TagData) -> Result<Sample> {
         let tag = RFIDTag::from_bytes(tag_data.as_bytes())?;
-        let sample = Sample::from_tag(&tag)?;
+        let sample = Sample::from_tag_with_key(&tag, &self.encryption)?;
         
         // Validate integrity
         let validation = self.validator.validate(&sample)?;
@@ -53,21 +134,93 @@ impl SampleGuard {
         Ok(sample)
     }
 
-    /// Write a sample to an RFID tag
+    /// Write a sample to an RFID tag, refusing before the write if it does
+    /// not fit in the reader's tag memory
     pub fn write_sample(&mut self, sample: &Sample) -> Result<()> {
-        let tag = sample.to_tag()?;
+        let tag = sample.to_tag_with_key(&self.encryption)?;
         let tag_bytes = tag.to_bytes()?;
+        self.check_fits(&tag_bytes)?;
         let tag_data = TagData::new(tag_bytes);
         self.reader.write_tag(&tag_data)?;
         Ok(())
     }
 
+    /// Write a sample to an RFID tag and confirm it by reading it back,
+    /// retrying transient read errors according to `options`
+    pub fn write_sample_verified(&mut self, sample: &Sample, options: &VerifyOptions) -> Result<VerifyOutcome> {
+        let tag_bytes = sample.to_tag_with_key(&self.encryption)?.to_bytes()?;
+        self.check_fits(&tag_bytes)?;
+        self.reader.write_tag(&TagData::new(tag_bytes.clone()))?;
+        Ok(commission::verify_write(self.reader.as_mut(), &tag_bytes, options))
+    }
+
+    fn check_fits(&self, tag_bytes: &[u8]) -> Result<()> {
+        let available = self.reader.get_capabilities().max_tag_memory;
+        if tag_bytes.len() > available {
+            return Err(SampleGuardError::TagTooLarge { needed: tag_bytes.len(), available });
+        }
+        Ok(())
+    }
+
     /// Perform integrity check on a sample
     pub fn check_integrity(&self, sample: &Sample) -> Result<ValidationResult> {
         self.validator.validate(sample)
     }
 }
 
+/// Environment variable conventionally holding the tag master key
+pub const MASTER_KEY_ENV: &str = "SAMPLEGUARD_MASTER_KEY";
+
+/// Builds a [`SampleGuard`] whose tags are encrypted with a supplied key
+pub struct SampleGuardBuilder {
+    reader: Box<dyn RFIDReader>,
+    master_key: Option<Vec<u8>>,
+    mode: EncryptionMode,
+    cbc_migration: bool,
+}
+
+impl SampleGuardBuilder {
+    pub fn new(reader: Box<dyn RFIDReader>) -> Self {
+        Self { reader, master_key: None, mode: EncryptionMode::default(), cbc_migration: false }
+    }
+
+    /// Derive the tag key from `master_key`
+    pub fn master_key(mut self, master_key: &[u8]) -> Self {
+        self.master_key = Some(master_key.to_vec());
+        self
+    }
+
+    /// Derive the tag key from the environment variable `var`, such as
+    /// [`MASTER_KEY_ENV`]. Fails if it is unset or empty.
+    pub fn master_key_from_env(self, var: &str) -> Result<Self> {
+        match std::env::var(var) {
+            Ok(key) if !key.is_empty() => Ok(self.master_key(key.as_bytes())),
+            _ => Err(SampleGuardError::ConfigError(format!("Master key variable {} is not set", var))),
+        }
+    }
+
+    pub fn mode(mut self, mode: EncryptionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Still read CBC tags in GCM mode, while they are being re-encrypted
+    pub fn cbc_migration(mut self, allow: bool) -> Self {
+        self.cbc_migration = allow;
+        self
+    }
+
+    /// Build the SampleGuard, using the default key if none was supplied
+    pub fn build(self) -> SampleGuard {
+        let key = self.master_key.as_deref().unwrap_or(sample::DEFAULT_MASTER_KEY);
+        let encryption = match self.mode {
+            EncryptionMode::Cbc => RFIDEncryption::new(key),
+            EncryptionMode::Gcm => RFIDEncryption::new_gcm(key),
+        };
+        SampleGuard::new(self.reader).with_encryption(encryption.with_cbc_migration(self.cbc_migration))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,5 +231,189 @@ mod tests {
         let reader = Box::new(MockRFIDReader::new());
         let _guard = SampleGuard::new(reader);
     }
-}
 
+    #[test]
+    fn test_write_sample_verified() {
+        let mut guard = SampleGuard::new(Box::new(MockRFIDReader::new()));
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-VERIFY".to_string(),
+            production_date: chrono::Utc::now(),
+            expiry_date: None,
+            temperature_range: None,
+            storage_conditions: "Ambient".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        let sample = Sample::new("VERIFY-001".to_string(), metadata, None);
+        
+        let outcome = guard.write_sample_verified(&sample, &VerifyOptions::default()).unwrap();
+        assert_eq!(outcome, VerifyOutcome::Verified { attempts: 1 });
+    }
+
+    #[test]
+    fn test_read_sample_recorded_counts_stored_reads() {
+        let mut guard = SampleGuard::new(Box::new(MockRFIDReader::new()));
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-READS".to_string(),
+            production_date: chrono::Utc::now(),
+            expiry_date: None,
+            temperature_range: None,
+            storage_conditions: "Ambient".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        let sample = Sample::new("READS-001".to_string(), metadata, None);
+        let database = Database::in_memory().unwrap();
+        guard.write_sample(&sample).unwrap();
+        assert!(guard.read_sample_recorded(&database).is_err());
+
+        database.store_sample(&sample).unwrap();
+        assert_eq!(guard.read_sample_recorded(&database).unwrap().read_count, 1);
+        assert_eq!(guard.read_sample_recorded(&database).unwrap().read_count, 2);
+        assert_eq!(database.get_sample("READS-001").unwrap().unwrap().read_count, 2);
+    }
+
+    #[test]
+    fn test_write_rejects_tag_larger_than_tag_memory() {
+        let mut guard = SampleGuard::new(Box::new(MockRFIDReader::new().with_max_tag_memory(512)));
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-OVERSIZED".to_string(),
+            production_date: chrono::Utc::now(),
+            expiry_date: Some(chrono::Utc::now() + chrono::Duration::days(365)),
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated, protect from light".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        let sample = Sample::new("OVERSIZED-001".to_string(), metadata, None);
+        assert!(serde_json::to_vec(&sample).unwrap().len() > 512);
+
+        let err = guard.write_sample(&sample).unwrap_err();
+        assert!(matches!(err, SampleGuardError::TagTooLarge { needed, available: 512 } if needed > 512));
+        assert!(matches!(
+            guard.write_sample_verified(&sample, &VerifyOptions::default()),
+            Err(SampleGuardError::TagTooLarge { .. })
+        ));
+        // Nothing reached the tag
+        assert!(guard.read_sample().is_err());
+    }
+
+    #[test]
+    fn test_tag_written_with_one_key_is_unreadable_with_another() {
+        let mut reader = MockRFIDReader::new();
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-KEYS".to_string(),
+            production_date: chrono::Utc::now(),
+            expiry_date: None,
+            temperature_range: None,
+            storage_conditions: "Ambient".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        let sample = Sample::new("KEYS-001".to_string(), metadata, None);
+        let key_a = RFIDEncryption::new(b"site_a_master_key_32_bytes_long!");
+        let tag = sample.to_tag_with_key(&key_a).unwrap();
+        reader.write_tag(&TagData::new(tag.to_bytes().unwrap())).unwrap();
+
+        let mut guard = SampleGuard::new(Box::new(reader))
+            .with_encryption(RFIDEncryption::new(b"site_b_master_key_32_bytes_long!"));
+        // The integrity hash is unkeyed, so a wrong key fails at decryption or decoding
+        assert!(matches!(
+            guard.read_sample(),
+            Err(SampleGuardError::EncryptionError(_) | SampleGuardError::InvalidSampleData(_))
+        ));
+
+        let mut guard = guard.with_encryption(key_a);
+        assert_eq!(guard.read_sample().unwrap().sample_id, "KEYS-001");
+        assert!(Sample::from_tag_with_key(&tag, sample::default_encryption()).is_err());
+    }
+
+    #[test]
+    fn test_builder_keys_reject_each_others_tags() {
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-BUILDER".to_string(),
+            production_date: chrono::Utc::now(),
+            expiry_date: None,
+            temperature_range: None,
+            storage_conditions: "Ambient".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        let sample = Sample::new("BUILDER-001".to_string(), metadata, None);
+        let mut writer = SampleGuard::builder(Box::new(MockRFIDReader::new()))
+            .master_key(b"site_a_master_key_32_bytes_long!")
+            .mode(EncryptionMode::Gcm)
+            .build();
+        writer.write_sample(&sample).unwrap();
+        let tag = writer.reader.read_tag().unwrap();
+
+        std::env::set_var("SAMPLEGUARD_TEST_BUILDER_KEY", "site_b_master_key_32_bytes_long!");
+        let mut reader = MockRFIDReader::new();
+        reader.write_tag(&tag).unwrap();
+        let mut guard = SampleGuard::builder(Box::new(reader))
+            .master_key_from_env("SAMPLEGUARD_TEST_BUILDER_KEY")
+            .unwrap()
+            .mode(EncryptionMode::Gcm)
+            .build();
+        assert!(matches!(guard.read_sample(), Err(SampleGuardError::EncryptionError(_))));
+        assert_eq!(writer.read_sample().unwrap().sample_id, "BUILDER-001");
+
+        assert!(SampleGuard::builder(Box::new(MockRFIDReader::new()))
+            .master_key_from_env("SAMPLEGUARD_TEST_UNSET_KEY")
+            .is_err());
+    }
+
+    #[test]
+    fn test_read_violation_records_raw_tag() {
+        let mut guard = SampleGuard::new(Box::new(MockRFIDReader::new()));
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-FORENSIC".to_string(),
+            production_date: chrono::Utc::now(),
+            expiry_date: None,
+            temperature_range: None,
+            storage_conditions: "Ambient".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        let mut sample = Sample::new("FORENSIC-001".to_string(), metadata, None);
+        sample.update_status(SampleStatus::Compromised).unwrap();
+        guard.write_sample(&sample).unwrap();
+
+        let mut logger = AuditLogger::new().with_raw_tag_capture(audit::DEFAULT_RAW_TAG_MAX_BYTES);
+        let result = guard.read_sample_audited(&mut logger, None);
+        assert!(matches!(result, Err(SampleGuardError::IntegrityViolation(_))));
+
+        let events = logger.get_events_by_type(&AuditEventType::ViolationDetected);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sample_id.as_deref(), Some("FORENSIC-001"));
+        assert_eq!(events[0].details["raw_tag"]["truncated"], false);
+        let raw = events[0].raw_tag().unwrap();
+        let replayed = Sample::from_tag_with_key(&RFIDTag::from_bytes(&raw).unwrap(), sample::default_encryption()).unwrap();
+        assert_eq!(replayed.status, SampleStatus::Compromised);
+    }
+
+    #[test]
+    fn test_oversized_raw_tag_is_truncated() {
+        let mut guard = SampleGuard::new(Box::new(MockRFIDReader::new()));
+        guard.reader.write_tag(&TagData::new(vec![0xAB; 64])).unwrap();
+
+        let mut logger = AuditLogger::new().with_raw_tag_capture(16);
+        assert!(guard.read_sample_audited(&mut logger, None).is_err());
+
+        let event = &logger.get_events_by_type(&AuditEventType::ViolationDetected)[0];
+        assert_eq!(event.sample_id, None);
+        assert_eq!(event.details["raw_tag"]["truncated"], true);
+        assert_eq!(event.details["raw_tag"]["length"], 64);
+        assert_eq!(event.raw_tag().unwrap(), vec![0xAB; 16]);
+    }
+
+    #[test]
+    fn test_raw_tags_are_not_captured_by_default() {
+        let mut guard = SampleGuard::new(Box::new(MockRFIDReader::new()));
+        guard.reader.write_tag(&TagData::new(vec![0xAB; 64])).unwrap();
+
+        let mut logger = AuditLogger::new();
+        assert!(guard.read_sample_audited(&mut logger, None).is_err());
+        assert!(logger.get_events_by_type(&AuditEventType::ViolationDetected)[0].raw_tag().is_none());
+    }
+}
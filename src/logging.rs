@@ -0,0 +1,221 @@
+use crate::error::{SampleGuardError, Result};
+use log::kv::{Key, Value, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::{Map, Value as JsonValue};
+use std::cell::RefCell;
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+/// Output format of the process-wide logger
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable env_logger lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = SampleGuardError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(SampleGuardError::ConfigError(format!(
+                "Unknown log format '{}' (expected text or json)", other
+            ))),
+        }
+    }
+}
+
+/// Logger settings chosen on the command line
+#[derive(Debug, Clone, Default)]
+pub struct LoggingOptions {
+    pub format: LogFormat,
+    /// Overrides `RUST_LOG` when set
+    pub level: Option<LevelFilter>,
+}
+
+/// Install the process-wide logger
+pub fn init(options: &LoggingOptions) -> Result<()> {
+    match options.format {
+        LogFormat::Text => {
+            let mut builder = env_logger::Builder::from_env(env_logger::Env::new().default_filter_or("info"));
+            if let Some(level) = options.level {
+                builder.filter_level(level);
+            }
+            builder.try_init().map_err(|e| SampleGuardError::ConfigError(e.to_string()))
+        }
+        LogFormat::Json => {
+            let level = options.level.unwrap_or_else(|| {
+                std::env::var("RUST_LOG").ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(LevelFilter::Info)
+            });
+            JsonLogger::new(level, Box::new(std::io::stderr())).install()
+        }
+    }
+}
+
+thread_local! {
+    static CORRELATION_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Correlation ID of the request being handled on this thread, if any
+pub fn correlation_id() -> Option<String> {
+    CORRELATION_ID.with(|id| id.borrow().clone())
+}
+
+/// Run `future` with `id` as the correlation ID of every record it logs
+pub fn with_correlation_id<F: Future>(id: String, future: F) -> WithCorrelationId<F> {
+    WithCorrelationId { id, inner: Box::pin(future) }
+}
+
+/// Future returned by [`with_correlation_id`]
+pub struct WithCorrelationId<F> {
+    id: String,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for WithCorrelationId<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let previous = CORRELATION_ID.with(|id| id.replace(Some(self.id.clone())));
+        let result = self.inner.as_mut().poll(cx);
+        CORRELATION_ID.with(|id| *id.borrow_mut() = previous);
+        result
+    }
+}
+
+/// Logger writing one JSON object per record: timestamp, level, target,
+/// message, the correlation ID when inside a request, and any key-values
+/// given at the call site (`log::info!(sample_id = id; "...")`)
+pub struct JsonLogger {
+    level: LevelFilter,
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLogger {
+    pub fn new(level: LevelFilter, output: Box<dyn Write + Send>) -> Self {
+        Self { level, output: Mutex::new(output) }
+    }
+
+    /// Install as the process-wide logger
+    pub fn install(self) -> Result<()> {
+        let level = self.level;
+        log::set_boxed_logger(Box::new(self)).map_err(|e| SampleGuardError::ConfigError(e.to_string()))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+
+    /// JSON line for a record, without the trailing newline
+    pub fn format_record(record: &Record) -> String {
+        let mut fields = Map::new();
+        fields.insert("timestamp".to_string(), chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true).into());
+        fields.insert("level".to_string(), record.level().as_str().into());
+        fields.insert("target".to_string(), record.target().into());
+        fields.insert("message".to_string(), record.args().to_string().into());
+        if let Some(id) = correlation_id() {
+            fields.insert("correlation_id".to_string(), id.into());
+        }
+        let _ = record.key_values().visit(&mut FieldCollector(&mut fields));
+        JsonValue::Object(fields).to_string()
+    }
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = Self::format_record(record);
+        let mut output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(output, "{}", line);
+    }
+
+    fn flush(&self) {
+        let _ = self.output.lock().unwrap_or_else(|e| e.into_inner()).flush();
+    }
+}
+
+struct FieldCollector<'a>(&'a mut Map<String, JsonValue>);
+
+impl<'kvs> VisitSource<'kvs> for FieldCollector<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> std::result::Result<(), log::kv::Error> {
+        let value = if let Some(n) = value.to_i64() {
+            n.into()
+        } else if let Some(n) = value.to_u64() {
+            n.into()
+        } else if let Some(b) = value.to_bool() {
+            b.into()
+        } else if let Some(n) = value.to_f64() {
+            n.into()
+        } else {
+            value.to_string().into()
+        };
+        self.0.insert(key.as_str().to_string(), value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(record: &Record) -> JsonValue {
+        serde_json::from_str(&JsonLogger::format_record(record)).unwrap()
+    }
+
+    #[test]
+    fn test_log_format_parsing() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("TEXT".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_record_fields_and_key_values() {
+        let pairs: [(&str, Value); 2] = [("sample_id", Value::from("S-1")), ("attempt", Value::from(2u32))];
+        let json = parse(
+            &Record::builder()
+                .args(format_args!("stored"))
+                .level(log::Level::Warn)
+                .target("sample_guard::database")
+                .key_values(&pairs)
+                .build(),
+        );
+
+        assert_eq!(json["level"], "WARN");
+        assert_eq!(json["target"], "sample_guard::database");
+        assert_eq!(json["message"], "stored");
+        assert_eq!(json["sample_id"], "S-1");
+        assert_eq!(json["attempt"], 2);
+        assert!(json["timestamp"].as_str().unwrap().ends_with('Z'));
+        assert!(json.get("correlation_id").is_none());
+    }
+
+    #[test]
+    fn test_correlation_id_scoped_to_future() {
+        let future = with_correlation_id("req-1".to_string(), async {
+            parse(&Record::builder().args(format_args!("inside")).build())
+        });
+        let json = block_on(future);
+        assert_eq!(json["correlation_id"], "req-1");
+        assert_eq!(correlation_id(), None);
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+    }
+}
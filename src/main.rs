@@ -1,78 +1,406 @@
+use clap::{Parser, Subcommand};
 use sample_guard::*;
+use std::path::PathBuf;
+
+/// SampleGuard - RFID Sample Integrity Tracking System
+#[derive(Parser)]
+#[command(name = "sample-guard", version, about)]
+struct Cli {
+    /// Path to a TOML configuration file
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Log output format: text or json
+    #[arg(long, global = true, default_value = "text")]
+    log_format: logging::LogFormat,
+
+    /// Log level (error, warn, info, debug, trace); defaults to RUST_LOG or info
+    #[arg(long, global = true)]
+    log_level: Option<log::LevelFilter>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the HTTP API, scheduling configured jobs in-process
+    Serve,
+    /// Run the configured background jobs until interrupted
+    Worker,
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Inspect and create raw tag dumps
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
+    /// Configuration file tools
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Live inventory tools
+    Inventory {
+        #[command(subcommand)]
+        command: InventoryCommand,
+    },
+    /// Exercise the database, reader, temperature, audit and HTTP subsystems;
+    /// exits with status 1 if any step fails
+    SelfTest,
+    /// Encode tags for work orders read from stdin, one per line
+    EncodeStation {
+        /// Run the preflight checks without writing tags
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write, read and validate a sample using the mock reader
+    Demo,
+}
+
+#[derive(Subcommand)]
+enum InventoryCommand {
+    /// Continuously scan and show the tags in the field.
+    ///
+    /// Type p (pause), c (clear), d (dump CSV) or q (quit) followed by Enter.
+    Watch {
+        /// Emulated reader: impinj, zebra or simulator
+        #[arg(long, default_value = "impinj")]
+        reader: String,
+        /// Time between scan cycles, e.g. 2s
+        #[arg(long, default_value = "2s")]
+        interval: String,
+        /// Look up sample status in this database
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Cycles a tag may go unseen before it is reported missing
+        #[arg(long, default_value_t = 3)]
+        missing_after: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Validate the configuration and print it normalized; exits with status 1 on errors
+    Check,
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Apply pending schema migrations
+    Migrate {
+        /// SQLite database file (defaults to database.path from the config)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+    /// Print database statistics
+    Stats {
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Import samples from a JSON-lines file; exits with status 1 if any record fails
+    Import {
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// File with one sample JSON object per line
+        #[arg(long)]
+        file: PathBuf,
+        /// What to do with samples that already exist: overwrite, skip or fail
+        #[arg(long, default_value = "fail")]
+        on_conflict: ConflictStrategy,
+    },
+    /// Re-verify every stored sample; exits with status 2 if any are corrupt
+    Verify {
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// First rewrite valid legacy checksums in the current layout
+        #[arg(long)]
+        upgrade_checksums: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagCommand {
+    /// Decode a raw tag dump, reporting which checks pass
+    Decode {
+        /// Tag bytes as hex
+        #[arg(long, conflicts_with = "file", required_unless_present = "file")]
+        hex: Option<String>,
+        /// File containing the raw tag bytes
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Environment variable holding the decryption key
+        #[arg(long)]
+        key_env: Option<String>,
+        /// Accept a sample checksum in the legacy layout with a warning
+        #[arg(long)]
+        allow_legacy_checksums: bool,
+    },
+    /// Encode a sample JSON file into a raw tag dump
+    Encode {
+        /// Sample as JSON
+        #[arg(long)]
+        sample: PathBuf,
+        /// Output file for the raw tag bytes
+        #[arg(long)]
+        out: PathBuf,
+        /// Environment variable holding the encryption key
+        #[arg(long)]
+        key_env: Option<String>,
+    },
+}
 
 fn main() -> Result<()> {
-    env_logger::init();
+    let args = Cli::parse();
+    logging::init(&logging::LoggingOptions { format: args.log_format, level: args.log_level })?;
     
-    println!("SampleGuard - RFID Sample Integrity Tracking System");
-    println!("===================================================\n");
-    
-    // Demonstrate the system with a mock reader
-    #[cfg(test)]
-    {
-        let reader = Box::new(reader::MockRFIDReader::new());
-        let mut guard = SampleGuard::new(reader);
-        
-        // Create a sample
-        let metadata = sample::SampleMetadata {
-            batch_number: "BATCH2024-001".to_string(),
-            production_date: chrono::Utc::now(),
-            expiry_date: Some(chrono::Utc::now() + chrono::Duration::days(365)),
-            temperature_range: Some((2.0, 8.0)),
-            storage_conditions: "Refrigerated 2-8°C".to_string(),
-            manufacturer: "PharmaCorp".to_string(),
-            product_line: "Vaccines".to_string(),
-        };
-        
-        let sample = sample::Sample::new(
-            "SAMPLE-2024-001".to_string(),
-            metadata,
-            Some("Warehouse A, Shelf 3".to_string()),
-        );
-        
-        println!("Created sample: {}", sample.sample_id);
-        println!("Status: {:?}", sample.status);
-        println!("Batch: {}", sample.metadata.batch_number);
-        println!();
-        
-        // Write sample to tag
-        println!("Writing sample to RFID tag...");
-        guard.write_sample(&sample)?;
-        println!("✓ Sample written successfully\n");
-        
-        // Read sample from tag
-        println!("Reading sample from RFID tag...");
-        let read_sample = guard.read_sample()?;
-        println!("✓ Sample read successfully");
-        println!("Sample ID: {}", read_sample.sample_id);
-        println!("Read count: {}", read_sample.read_count);
-        println!();
-        
-        // Check integrity
-        println!("Validating sample integrity...");
-        let validation = guard.check_integrity(&read_sample)?;
-        if validation.is_valid() {
-            println!("✓ Sample integrity validated");
-        } else {
-            println!("✗ Integrity violations detected:");
-            for violation in &validation.violations {
-                println!("  - {:?}", violation);
+    match args.command {
+        Some(Command::Serve) => {
+            let config = cli::load_valid_config(args.config.as_deref())?;
+            actix_web::rt::System::new().block_on(cli::run_serve(&config))
+        }
+        Some(Command::Worker) => {
+            let config = cli::load_valid_config(args.config.as_deref())?;
+            let shutdown = cli::shutdown_flag()?;
+            cli::run_worker(&config, shutdown)
+        }
+        Some(Command::Db { command }) => {
+            let config = cli::load_config(args.config.as_deref())?;
+            run_db(command, &config)
+        }
+        Some(Command::Tag { command }) => run_tag(command),
+        Some(Command::Config { command: ConfigCommand::Check }) => {
+            let (valid, report) = cli::check_config(args.config.as_deref());
+            print!("{}", report);
+            if !valid {
+                std::process::exit(1);
             }
+            Ok(())
+        }
+        Some(Command::Inventory { command: InventoryCommand::Watch { reader, interval, db, missing_after } }) => {
+            let config = cli::load_config(args.config.as_deref())?;
+            run_inventory_watch(&config, &reader, &interval, db, missing_after)
         }
-        
-        if validation.has_warnings() {
-            println!("\n⚠ Warnings:");
-            for warning in &validation.warnings {
-                println!("  - {:?}", warning);
+        Some(Command::SelfTest) => {
+            let config = cli::load_config(args.config.as_deref())?;
+            let report = cli::self_test::run_self_test(&config);
+            println!("{}", report);
+            if !report.passed() {
+                std::process::exit(1);
             }
+            Ok(())
+        }
+        Some(Command::EncodeStation { dry_run }) => {
+            let config = cli::load_valid_config(args.config.as_deref())?;
+            run_encode_station(&config, dry_run)
+        }
+        Some(Command::Demo) => run_demo(),
+        None => {
+            println!("SampleGuard - RFID Sample Integrity Tracking System");
+            println!("===================================================\n");
+            println!("Run 'sample-guard --help' to list the available commands");
+            Ok(())
         }
     }
+}
+
+fn run_inventory_watch(
+    config: &SampleGuardConfig,
+    reader: &str,
+    interval: &str,
+    db: Option<PathBuf>,
+    missing_after: u32,
+) -> Result<()> {
+    use cli::inventory::{reader_source, watch, WatchCommand, WatchOptions};
+    use std::io::IsTerminal;
+
+    let simulator = cli::encode_station::simulator_from_settings(&config.reader)?;
+    let mut source = reader_source(reader, simulator)?;
+    let database = db.map(Database::new).transpose()?;
+    let options = WatchOptions {
+        interval: config::parse_duration(interval)?,
+        missing_after,
+        tty: std::io::stdout().is_terminal(),
+        ..WatchOptions::default()
+    };
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines().map_while(|l| l.ok()) {
+            if let Some(command) = WatchCommand::parse(&line) {
+                if tx.send(command).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let shutdown = cli::shutdown_flag()?;
+    let mut stdout = std::io::stdout();
+    watch(source.as_mut(), database.as_ref(), &options, &mut stdout, &rx, &shutdown)?;
+    Ok(())
+}
+
+fn run_encode_station(config: &SampleGuardConfig, dry_run: bool) -> Result<()> {
+    use cli::encode_station::{simulator_from_settings, EncodeStation, StationOptions};
+
+    let database = Database::from_config(&config.database)?;
+    let audit_logger = AuditLogger::from_config(&config.audit)?;
+    let mut options = StationOptions::from_settings(&config.reader)?;
+    options.dry_run = dry_run;
+
+    let field = simulator_from_settings(&config.reader)?;
+    let shutdown = cli::shutdown_flag()?;
+    let mut station = EncodeStation::new(field, database, audit_logger, options);
+
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    let summary = station.run(stdin.lock(), &mut stdout, &shutdown)?;
+    if summary.failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_db(command: DbCommand, config: &SampleGuardConfig) -> Result<()> {
+    match command {
+        DbCommand::Migrate { db } => {
+            let path = cli::database_path(db, config)?;
+            let (from, to) = cli::db::migrate(&path)?;
+            if from == to {
+                println!("Schema is up to date (version {})", to);
+            } else {
+                println!("Migrated schema from version {} to {}", from, to);
+            }
+            Ok(())
+        }
+        DbCommand::Stats { db, json } => {
+            let stats = match db {
+                None if config.database.backend == sample_guard::config::DatabaseBackendKind::Postgres => {
+                    cli::db::backend_stats(&config.database)?
+                }
+                db => cli::db::stats(&cli::database_path(db, config)?)?,
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                print!("{}", cli::db::format_stats_table(&stats));
+            }
+            Ok(())
+        }
+        DbCommand::Import { db, file, on_conflict } => {
+            let report = cli::db::import(&cli::database_path(db, config)?, &file, on_conflict)?;
+            print!("{}", cli::db::format_import(&report));
+            if report.rolled_back || report.failed() > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        DbCommand::Verify { db, upgrade_checksums } => {
+            let path = cli::database_path(db, config)?;
+            if upgrade_checksums {
+                println!("Upgraded {} legacy checksums", cli::db::upgrade_checksums(&path)?);
+            }
+            let report = cli::db::verify(&path)?;
+            print!("{}", cli::db::format_verification(&report));
+            if !report.corrupt.is_empty() {
+                std::process::exit(2);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn run_tag(command: TagCommand) -> Result<()> {
+    match command {
+        TagCommand::Decode { hex, file, key_env, allow_legacy_checksums } => {
+            let bytes = match (hex, file) {
+                (Some(hex), _) => cli::tag::parse_hex(&hex)?,
+                (None, Some(file)) => std::fs::read(file)?,
+                (None, None) => unreachable!("clap requires --hex or --file"),
+            };
+            let key = key_env.as_deref().map(cli::tag::key_from_env).transpose()?;
+            let report = cli::tag::decode_tag(&bytes, key.as_deref(), allow_legacy_checksums);
+            print!("{}", report);
+            if !report.all_passed() {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        TagCommand::Encode { sample, out, key_env } => {
+            let sample: Sample = serde_json::from_slice(&std::fs::read(sample)?)?;
+            let key = key_env.as_deref().map(cli::tag::key_from_env).transpose()?;
+            let bytes = cli::tag::encode_tag(&sample, key.as_deref())?;
+            std::fs::write(&out, &bytes)?;
+            println!("Wrote {} bytes to {}", bytes.len(), out.display());
+            Ok(())
+        }
+    }
+}
+
+fn run_demo() -> Result<()> {
+    let reader = Box::new(reader::MockRFIDReader::new());
+    let mut guard = SampleGuard::new(reader);
+    
+    // Create a sample
+    let metadata = sample::SampleMetadata {
+        batch_number: "BATCH2024-001".to_string(),
+        production_date: chrono::Utc::now(),
+        expiry_date: Some(chrono::Utc::now() + chrono::Duration::days(365)),
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Refrigerated 2-8°C".to_string(),
+        manufacturer: "PharmaCorp".to_string(),
+        product_line: "Vaccines".to_string(),
+    };
+    
+    let sample = sample::Sample::new(
+        "SAMPLE-2024-001".to_string(),
+        metadata,
+        Some("Warehouse A, Shelf 3".to_string()),
+    );
     
-    #[cfg(not(test))]
-    {
-        println!("Run with 'cargo test' to see the system in action");
-        println!("Or implement hardware reader integration for production use");
+    println!("Created sample: {}", sample.sample_id);
+    println!("Status: {:?}", sample.status);
+    println!("Batch: {}", sample.metadata.batch_number);
+    println!();
+    
+    // Write sample to tag
+    println!("Writing sample to RFID tag...");
+    guard.write_sample(&sample)?;
+    println!("✓ Sample written successfully\n");
+    
+    // Read sample from tag
+    println!("Reading sample from RFID tag...");
+    let read_sample = guard.read_sample()?;
+    println!("✓ Sample read successfully");
+    println!("Sample ID: {}", read_sample.sample_id);
+    println!("Read count: {}", read_sample.read_count);
+    println!();
+    
+    // Check integrity
+    println!("Validating sample integrity...");
+    let validation = guard.check_integrity(&read_sample)?;
+    if validation.is_valid() {
+        println!("✓ Sample integrity validated");
+    } else {
+        println!("✗ Integrity violations detected:");
+        for violation in &validation.violations {
+            println!("  - {:?}", violation);
+        }
+    }
+    
+    if validation.has_warnings() {
+        println!("\n⚠ Warnings:");
+        for warning in &validation.warnings {
+            println!("  - {:?}", warning);
+        }
     }
     
     Ok(())
 }
-
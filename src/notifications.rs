@@ -0,0 +1,278 @@
+use crate::config::{parse_duration, WebhookConfig};
+use crate::error::{SampleGuardError, Result};
+use crate::hardware::metrics::LatencyHistogram;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Event pushed to external systems
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub event: String,
+    pub sample_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub details: serde_json::Value,
+}
+
+impl Notification {
+    pub fn new(event: impl Into<String>, sample_id: Option<String>, details: serde_json::Value) -> Self {
+        Self {
+            event: event.into(),
+            sample_id,
+            timestamp: Utc::now(),
+            details,
+        }
+    }
+}
+
+/// Destination for notifications
+pub trait NotificationSink: Send + Sync {
+    fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// Delivery counters for a sink
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeliveryStats {
+    pub successes: u64,
+    pub failures: u64,
+    pub mean_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+}
+
+#[derive(Default)]
+struct DeliveryMetrics {
+    successes: u64,
+    failures: u64,
+    latency: LatencyHistogram,
+}
+
+/// Response bytes read back from the endpoint; anything longer is dropped
+/// along with its connection
+const MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+
+/// Check that `url` is an absolute `http://` or `https://` URL with a host
+fn validate_url(url: &str) -> Result<()> {
+    let invalid = || SampleGuardError::ConfigError(format!(
+        "Invalid webhook URL '{}' (expected http[s]://host[:port]/path)", url
+    ));
+    let uri: ureq::http::Uri = url.parse().map_err(|_| invalid())?;
+    let has_host = uri.host().is_some_and(|host| !host.is_empty());
+    if !matches!(uri.scheme_str(), Some("http" | "https")) || !has_host {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+/// Sink POSTing notifications as JSON to an HTTP(S) endpoint through a
+/// `ureq` agent that keeps connections alive for reuse. Deliveries run on
+/// the caller's thread, at most `max_connections` at a time.
+pub struct WebhookSink {
+    url: String,
+    timeout: Duration,
+    keepalive: Duration,
+    max_connections: usize,
+    agent: ureq::Agent,
+    in_flight: Mutex<usize>,
+    released: Condvar,
+    metrics: Mutex<DeliveryMetrics>,
+}
+
+impl WebhookSink {
+    /// Sink for `url` with a 5 s timeout, 60 s keepalive and up to 8 connections
+    pub fn new(url: &str) -> Result<Self> {
+        validate_url(url)?;
+        let sink = Self {
+            url: url.to_string(),
+            timeout: Duration::from_secs(5),
+            keepalive: Duration::from_secs(60),
+            max_connections: 8,
+            agent: ureq::Agent::new_with_defaults(),
+            in_flight: Mutex::new(0),
+            released: Condvar::new(),
+            metrics: Mutex::new(DeliveryMetrics::default()),
+        };
+        Ok(sink.with_agent())
+    }
+
+    pub fn from_config(config: &WebhookConfig) -> Result<Self> {
+        Ok(Self::new(&config.url)?
+            .with_timeout(parse_duration(&config.timeout)?)
+            .with_keepalive(parse_duration(&config.keepalive)?)
+            .with_max_connections(config.max_connections))
+    }
+
+    /// Maximum time for one delivery, including waiting for a free connection
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How long an idle connection is kept for reuse
+    pub fn with_keepalive(mut self, keepalive: Duration) -> Self {
+        self.keepalive = keepalive;
+        self.with_agent()
+    }
+
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections.max(1);
+        self.with_agent()
+    }
+
+    /// Rebuild the agent for the current keepalive and connection limit
+    fn with_agent(mut self) -> Self {
+        let config = ureq::Agent::config_builder()
+            .max_idle_age(self.keepalive)
+            .max_idle_connections(self.max_connections)
+            .max_idle_connections_per_host(self.max_connections)
+            .build();
+        self.agent = ureq::Agent::new_with_config(config);
+        self
+    }
+
+    pub fn stats(&self) -> DeliveryStats {
+        let metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner());
+        DeliveryStats {
+            successes: metrics.successes,
+            failures: metrics.failures,
+            mean_latency_ms: metrics.latency.mean_ms(),
+            p95_latency_ms: metrics.latency.percentile(95.0),
+        }
+    }
+
+    fn deliver(&self, body: &[u8], deadline: Instant) -> Result<()> {
+        self.acquire(deadline)?;
+        let result = self.post(body, deadline);
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        *in_flight -= 1;
+        self.released.notify_one();
+        result
+    }
+
+    /// Wait for one of the `max_connections` delivery slots
+    fn acquire(&self, deadline: Instant) -> Result<()> {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        while *in_flight >= self.max_connections {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(delivery_error("timed out waiting for a free connection".to_string()));
+            }
+            in_flight = self.released.wait_timeout(in_flight, remaining).unwrap_or_else(|e| e.into_inner()).0;
+        }
+        *in_flight += 1;
+        Ok(())
+    }
+
+    fn post(&self, body: &[u8], deadline: Instant) -> Result<()> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(delivery_error("timed out".to_string()));
+        }
+        let response = self.agent
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .config()
+            .timeout_global(Some(remaining))
+            .build()
+            .send(body)
+            .map_err(|e| match e {
+                ureq::Error::StatusCode(status) => delivery_error(format!("endpoint returned HTTP {}", status)),
+                e => delivery_error(e.to_string()),
+            })?;
+        // Read the response to the end so the connection can be reused; one
+        // that is too long is simply not returned to the pool
+        let _ = response.into_body().with_config().limit(MAX_RESPONSE_BYTES).read_to_vec();
+        Ok(())
+    }
+}
+
+impl NotificationSink for WebhookSink {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let start = Instant::now();
+        let body = serde_json::to_vec(notification)?;
+        let result = self.deliver(&body, start + self.timeout);
+
+        let mut metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner());
+        match &result {
+            Ok(()) => {
+                metrics.successes += 1;
+                metrics.latency.record(start.elapsed().as_millis() as u64);
+            }
+            Err(_) => metrics.failures += 1,
+        }
+        result
+    }
+}
+
+fn delivery_error(message: String) -> SampleGuardError {
+    SampleGuardError::IoError(std::io::Error::other(format!("Webhook delivery failed: {}", message)))
+}
+
+/// Fans notifications out to several sinks at once, so a slow endpoint
+/// only delays its own delivery
+#[derive(Clone, Default)]
+pub struct NotificationDispatcher {
+    sinks: Vec<Arc<dyn NotificationSink>>,
+}
+
+impl NotificationDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_sink(mut self, sink: Arc<dyn NotificationSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Sinks for every configured webhook
+    pub fn from_config(webhooks: &[WebhookConfig]) -> Result<Self> {
+        let mut dispatcher = Self::new();
+        for webhook in webhooks {
+            dispatcher = dispatcher.with_sink(Arc::new(WebhookSink::from_config(webhook)?));
+        }
+        Ok(dispatcher)
+    }
+
+    /// Deliver to every sink in parallel, returning each sink's result in order
+    pub fn dispatch(&self, notification: &Notification) -> Vec<Result<()>> {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = self.sinks
+                .iter()
+                .map(|sink| scope.spawn(move || sink.send(notification)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|_| Err(delivery_error("sink panicked".to_string()))))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_validation() {
+        assert!(validate_url("http://hooks.example.com:9000/sample-guard/events").is_ok());
+        assert!(validate_url("http://localhost").is_ok());
+        assert!(validate_url("https://hooks.example.com/").is_ok());
+
+        assert!(validate_url("ftp://hooks.example.com/").is_err());
+        assert!(validate_url("hooks.example.com/events").is_err());
+        assert!(validate_url("http://:80/").is_err());
+    }
+
+    #[test]
+    fn test_unreachable_endpoint_counts_failure() {
+        // Bind then drop a listener so the port is very likely closed
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let sink = WebhookSink::new(&format!("http://127.0.0.1:{}/", port)).unwrap()
+            .with_timeout(Duration::from_millis(500));
+
+        assert!(sink.send(&Notification::new("test", None, serde_json::json!({}))).is_err());
+        let stats = sink.stats();
+        assert_eq!((stats.successes, stats.failures), (0, 1));
+    }
+}
@@ -0,0 +1,412 @@
+//! Postgres implementation of [`DatabaseBackend`]
+//!
+//! Mirrors the SQLite schema for samples and their history: timestamps are
+//! RFC 3339 text and statuses their `Debug` names, so rows read the same
+//! from either backend.
+
+use crate::database::{decode_checksum, parse_status, DatabaseBackend, DatabaseStatistics, HistoryEntry, HistoryRollup};
+use crate::error::{DatabaseErrorKind, Result, SampleGuardError};
+use crate::sample::{Sample, SampleMetadata, SampleStatus};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use crate::config::DatabaseConfig;
+use futures_executor::block_on;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::runtime::Runtime;
+use tokio_postgres::tls::TlsStream;
+use tokio_postgres::{Client, Connection, NoTls, Row};
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS samples (
+        id TEXT PRIMARY KEY,
+        sample_id TEXT NOT NULL UNIQUE,
+        status TEXT NOT NULL,
+        batch_number TEXT NOT NULL,
+        production_date TEXT NOT NULL,
+        expiry_date TEXT,
+        temperature_min REAL,
+        temperature_max REAL,
+        storage_conditions TEXT NOT NULL,
+        manufacturer TEXT NOT NULL,
+        product_line TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        last_updated TEXT NOT NULL,
+        read_count BIGINT NOT NULL,
+        location TEXT,
+        integrity_checksum TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS sample_history (
+        id BIGSERIAL PRIMARY KEY,
+        sample_id TEXT NOT NULL REFERENCES samples(sample_id),
+        status TEXT NOT NULL,
+        location TEXT,
+        timestamp TEXT NOT NULL,
+        rollup_count BIGINT,
+        rollup_start TEXT
+    );
+    ALTER TABLE samples ADD COLUMN IF NOT EXISTS container_id TEXT;
+    ALTER TABLE samples ADD COLUMN IF NOT EXISTS notes TEXT;
+    ALTER TABLE sample_history ADD COLUMN IF NOT EXISTS user_id TEXT;
+    CREATE INDEX IF NOT EXISTS idx_batch_number ON samples(batch_number);
+    CREATE INDEX IF NOT EXISTS idx_status ON samples(status);
+    CREATE INDEX IF NOT EXISTS idx_history_sample ON sample_history(sample_id);
+";
+
+const SAMPLE_COLUMNS: &str = "id, sample_id, status, batch_number, production_date, expiry_date,
+    temperature_min, temperature_max, storage_conditions, manufacturer,
+    product_line, created_at, last_updated, read_count, location, integrity_checksum,
+    container_id, notes";
+
+/// Sample storage in a Postgres database.
+///
+/// The connection is driven by a runtime of its own, so every call blocks
+/// the calling thread, as with the SQLite backend, but is safe to make from
+/// inside an async context such as an actix handler.
+pub struct PostgresDatabase {
+    client: Client,
+    _runtime: BackgroundRuntime,
+}
+
+/// Runtime shut down without waiting when dropped, since a blocking drop
+/// panics inside an async context
+struct BackgroundRuntime(Option<Runtime>);
+
+impl Drop for BackgroundRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// TLS used for a Postgres connection
+#[derive(Debug, Clone, Default)]
+pub enum PostgresTls {
+    #[default]
+    Disabled,
+    /// Verify the server against the web PKI roots and the PEM certificates
+    /// in `ca_file`
+    Required { ca_file: Option<PathBuf> },
+}
+
+impl PostgresTls {
+    pub fn from_config(config: &DatabaseConfig) -> Self {
+        if config.tls {
+            PostgresTls::Required { ca_file: config.tls_ca_file.clone() }
+        } else {
+            PostgresTls::Disabled
+        }
+    }
+
+    fn connector(ca_file: Option<&Path>) -> Result<MakeRustlsConnect> {
+        let mut roots = rustls::RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        if let Some(ca_file) = ca_file {
+            let invalid = |e: &dyn std::fmt::Display| {
+                SampleGuardError::ConfigError(format!("Invalid CA file {}: {}", ca_file.display(), e))
+            };
+            for cert in CertificateDer::pem_file_iter(ca_file).map_err(|e| invalid(&e))? {
+                roots.add(cert.map_err(|e| invalid(&e))?).map_err(|e| invalid(&e))?;
+            }
+        }
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| SampleGuardError::ConfigError(format!("Invalid TLS settings: {}", e)))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Ok(MakeRustlsConnect::new(config))
+    }
+}
+
+impl PostgresDatabase {
+    /// Connect without TLS using a libpq-style connection string and create
+    /// the schema if it does not exist
+    pub fn connect(url: &str) -> Result<Self> {
+        Self::connect_with_tls(url, &PostgresTls::Disabled)
+    }
+
+    /// Connect to the database `config.url` names, with the TLS it asks for
+    pub fn from_config(config: &DatabaseConfig) -> Result<Self> {
+        let url = config.url.as_deref().ok_or_else(|| {
+            SampleGuardError::ConfigError("database.url is required by the postgres backend".to_string())
+        })?;
+        Self::connect_with_tls(url, &PostgresTls::from_config(config))
+    }
+
+    /// Connect using a libpq-style connection string, over TLS when `tls`
+    /// asks for it, and create the schema if it does not exist
+    pub fn connect_with_tls(url: &str, tls: &PostgresTls) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("sampleguard-postgres")
+            .enable_all()
+            .build()?;
+        let runtime = BackgroundRuntime(Some(runtime));
+        let handle = runtime.0.as_ref().map(Runtime::handle).expect("runtime just built");
+        let url = url.to_string();
+        let connecting = match tls {
+            PostgresTls::Disabled => handle.spawn(async move {
+                let (client, connection) = tokio_postgres::connect(&url, NoTls).await?;
+                tokio::spawn(drive(connection));
+                Ok(client)
+            }),
+            PostgresTls::Required { ca_file } => {
+                let connector = PostgresTls::connector(ca_file.as_deref())?;
+                handle.spawn(async move {
+                    let (client, connection) = tokio_postgres::connect(&url, connector).await?;
+                    tokio::spawn(drive(connection));
+                    Ok(client)
+                })
+            }
+        };
+        let client = block_on(connecting)
+            .map_err(|e| SampleGuardError::database_message(DatabaseErrorKind::Other, e.to_string()))?
+            .map_err(|e| SampleGuardError::postgres("Database connection failed", e))?;
+
+        let db = Self { client, _runtime: runtime };
+        block_on(db.client.batch_execute(SCHEMA))
+            .map_err(|e| SampleGuardError::postgres("Failed to create schema", e))?;
+        Ok(db)
+    }
+
+    fn query_samples(&self, filter: &str, params: &[&(dyn tokio_postgres::types::ToSql + Sync)]) -> Result<Vec<Sample>> {
+        let query = format!("SELECT {} FROM samples {} ORDER BY created_at DESC", SAMPLE_COLUMNS, filter);
+        let rows = block_on(self.client.query(query.as_str(), params))
+            .map_err(|e| SampleGuardError::postgres("Failed to execute query", e))?;
+        rows.iter().map(row_to_sample).collect()
+    }
+}
+
+impl DatabaseBackend for PostgresDatabase {
+    fn store_sample(&self, sample: &Sample) -> Result<()> {
+        let checksum_hex = hex::encode(sample.integrity_checksum);
+        block_on(self.client.execute(
+            "INSERT INTO samples (
+                id, sample_id, status, batch_number, production_date, expiry_date,
+                temperature_min, temperature_max, storage_conditions, manufacturer,
+                product_line, created_at, last_updated, read_count, location, integrity_checksum,
+                container_id, notes
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+            ON CONFLICT (sample_id) DO UPDATE SET
+                id = EXCLUDED.id,
+                status = EXCLUDED.status,
+                batch_number = EXCLUDED.batch_number,
+                production_date = EXCLUDED.production_date,
+                expiry_date = EXCLUDED.expiry_date,
+                temperature_min = EXCLUDED.temperature_min,
+                temperature_max = EXCLUDED.temperature_max,
+                storage_conditions = EXCLUDED.storage_conditions,
+                manufacturer = EXCLUDED.manufacturer,
+                product_line = EXCLUDED.product_line,
+                created_at = EXCLUDED.created_at,
+                last_updated = EXCLUDED.last_updated,
+                read_count = EXCLUDED.read_count,
+                location = EXCLUDED.location,
+                integrity_checksum = EXCLUDED.integrity_checksum,
+                container_id = EXCLUDED.container_id,
+                notes = EXCLUDED.notes",
+            &[
+                &sample.id.to_string(),
+                &sample.sample_id,
+                &format!("{:?}", sample.status),
+                &sample.metadata.batch_number,
+                &sample.metadata.production_date.to_rfc3339(),
+                &sample.metadata.expiry_date.map(|d| d.to_rfc3339()),
+                &sample.metadata.temperature_range.map(|r| r.0),
+                &sample.metadata.temperature_range.map(|r| r.1),
+                &sample.metadata.storage_conditions,
+                &sample.metadata.manufacturer,
+                &sample.metadata.product_line,
+                &sample.created_at.to_rfc3339(),
+                &sample.last_updated.to_rfc3339(),
+                &(sample.read_count as i64),
+                &sample.location,
+                &checksum_hex,
+                &sample.container_id,
+                &sample.notes,
+            ],
+        )).map_err(|e| SampleGuardError::postgres("Failed to store sample", e))?;
+
+        self.add_history_entry(&sample.sample_id, &sample.status, sample.location.as_deref(), None)
+    }
+
+    fn get_sample(&self, sample_id: &str) -> Result<Option<Sample>> {
+        Ok(self.query_samples("WHERE sample_id = $1", &[&sample_id])?.pop())
+    }
+
+    fn get_all_samples(&self) -> Result<Vec<Sample>> {
+        self.query_samples("", &[])
+    }
+
+    fn get_samples_by_batch(&self, batch_number: &str) -> Result<Vec<Sample>> {
+        self.query_samples("WHERE batch_number = $1", &[&batch_number])
+    }
+
+    fn get_samples_by_status(&self, status: SampleStatus) -> Result<Vec<Sample>> {
+        self.query_samples("WHERE status = $1", &[&format!("{:?}", status)])
+    }
+
+    fn delete_sample(&self, sample_id: &str) -> Result<bool> {
+        block_on(async {
+            self.client.execute("DELETE FROM sample_history WHERE sample_id = $1", &[&sample_id]).await
+                .map_err(|e| SampleGuardError::postgres("Failed to delete history", e))?;
+            let deleted = self.client.execute("DELETE FROM samples WHERE sample_id = $1", &[&sample_id]).await
+                .map_err(|e| SampleGuardError::postgres("Failed to delete sample", e))?;
+            Ok(deleted > 0)
+        })
+    }
+
+    fn add_history_entry(&self, sample_id: &str, status: &SampleStatus, location: Option<&str>, user_id: Option<&str>) -> Result<()> {
+        block_on(self.client.execute(
+            "INSERT INTO sample_history (sample_id, status, location, timestamp, user_id) VALUES ($1, $2, $3, $4, $5)",
+            &[&sample_id, &format!("{:?}", status), &location, &Utc::now().to_rfc3339(), &user_id],
+        )).map_err(|e| SampleGuardError::postgres("Failed to add history entry", e))?;
+        Ok(())
+    }
+
+    fn get_sample_history(&self, sample_id: &str) -> Result<Vec<HistoryEntry>> {
+        let rows = block_on(self.client.query(
+            "SELECT sample_id, status, location, timestamp, rollup_count, rollup_start, user_id FROM sample_history
+             WHERE sample_id = $1 ORDER BY timestamp DESC, rollup_count IS NULL DESC, id DESC",
+            &[&sample_id],
+        )).map_err(|e| SampleGuardError::postgres("Failed to execute query", e))?;
+
+        rows.iter().map(|row| {
+            let timestamp = timestamp_column(row, "timestamp")?;
+            let rollup = match column::<Option<i64>>(row, "rollup_count")? {
+                Some(count) => Some(HistoryRollup {
+                    count: count as usize,
+                    first: timestamp_column(row, "rollup_start")?,
+                    last: timestamp,
+                }),
+                None => None,
+            };
+            Ok(HistoryEntry {
+                sample_id: column(row, "sample_id")?,
+                status: parse_status(&column::<String>(row, "status")?),
+                location: column(row, "location")?,
+                timestamp,
+                user_id: column(row, "user_id")?,
+                rollup,
+            })
+        }).collect()
+    }
+
+    fn get_statistics(&self) -> Result<DatabaseStatistics> {
+        let now = Utc::now();
+        block_on(async {
+            let total_samples: i64 = self.client.query_one("SELECT COUNT(*) FROM samples", &[]).await
+                .map_err(|e| SampleGuardError::postgres("Failed to get statistics", e))?
+                .get(0);
+
+            let status_counts: HashMap<String, usize> = self.client
+                .query("SELECT status, COUNT(*) FROM samples GROUP BY status", &[]).await
+                .map_err(|e| SampleGuardError::postgres("Failed to execute query", e))?
+                .iter()
+                .map(|row| (row.get(0), row.get::<_, i64>(1) as usize))
+                .collect();
+
+            let manufacturer_counts: HashMap<String, usize> = self.client
+                .query("SELECT manufacturer, COUNT(*) FROM samples GROUP BY manufacturer", &[]).await
+                .map_err(|e| SampleGuardError::postgres("Failed to execute query", e))?
+                .iter()
+                .map(|row| (row.get(0), row.get::<_, i64>(1) as usize))
+                .collect();
+
+            let expiry = self.client.query_one(
+                "SELECT
+                    COALESCE(SUM(CASE WHEN expiry_date <= $1 THEN 1 ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN expiry_date > $1 AND expiry_date <= $2 THEN 1 ELSE 0 END), 0)
+                 FROM samples WHERE expiry_date IS NOT NULL",
+                &[&now.to_rfc3339(), &(now + chrono::Duration::days(30)).to_rfc3339()],
+            ).await.map_err(|e| SampleGuardError::postgres("Failed to get statistics", e))?;
+
+            Ok(DatabaseStatistics {
+                total_samples: total_samples as usize,
+                status_counts,
+                manufacturer_counts,
+                expired: expiry.get::<_, i64>(0) as usize,
+                expiring_within_30_days: expiry.get::<_, i64>(1) as usize,
+            })
+        })
+    }
+}
+
+/// Run a connection until it closes. Client calls only exchange messages
+/// with this task, so they can wait on it from any thread.
+async fn drive<S, T>(connection: Connection<S, T>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: TlsStream + Unpin,
+{
+    if let Err(e) = connection.await {
+        log::error!("Postgres connection closed: {}", e);
+    }
+}
+
+fn row_to_sample(row: &Row) -> Result<Sample> {
+    let id_str: String = column(row, "id")?;
+    let id = uuid::Uuid::parse_str(&id_str)
+        .map_err(|e| corrupt(row, "id", format!("invalid UUID '{}': {}", id_str, e)))?;
+    let checksum: String = column(row, "integrity_checksum")?;
+    let integrity_checksum = decode_checksum(&checksum)
+        .map_err(|message| corrupt(row, "integrity_checksum", message))?;
+    let expiry_date = match column::<Option<String>>(row, "expiry_date")? {
+        Some(_) => Some(timestamp_column(row, "expiry_date")?),
+        None => None,
+    };
+    let temp_min: Option<f32> = column(row, "temperature_min")?;
+    let temp_max: Option<f32> = column(row, "temperature_max")?;
+
+    Ok(Sample {
+        id,
+        sample_id: column(row, "sample_id")?,
+        status: parse_status(&column::<String>(row, "status")?),
+        metadata: SampleMetadata {
+            batch_number: column(row, "batch_number")?,
+            production_date: timestamp_column(row, "production_date")?,
+            expiry_date,
+            temperature_range: temp_min.zip(temp_max),
+            storage_conditions: column(row, "storage_conditions")?,
+            manufacturer: column(row, "manufacturer")?,
+            product_line: column(row, "product_line")?,
+        },
+        created_at: timestamp_column(row, "created_at")?,
+        last_updated: timestamp_column(row, "last_updated")?,
+        read_count: column::<i64>(row, "read_count")? as u64,
+        location: column(row, "location")?,
+        integrity_checksum,
+        container_id: column(row, "container_id")?,
+        notes: column(row, "notes")?,
+    })
+}
+
+fn column<'a, T: tokio_postgres::types::FromSql<'a>>(row: &'a Row, name: &str) -> Result<T> {
+    row.try_get(name).map_err(|e| SampleGuardError::postgres("Failed to parse row", e))
+}
+
+/// Read a timestamp stored with `to_rfc3339`, using chrono's wider format
+/// for the same reason as the SQLite backend
+fn timestamp_column(row: &Row, name: &str) -> Result<DateTime<Utc>> {
+    let value: String = column(row, name)?;
+    value.parse::<DateTime<Utc>>()
+        .map_err(|e| corrupt(row, name, format!("invalid timestamp '{}': {}", value, e)))
+}
+
+fn corrupt(row: &Row, name: &str, message: String) -> SampleGuardError {
+    let message = match row.try_get::<_, String>("sample_id") {
+        Ok(sample_id) => format!("column {} of sample '{}': {}", name, sample_id, message),
+        Err(_) => format!("column {}: {}", name, message),
+    };
+    SampleGuardError::DatabaseError {
+        kind: DatabaseErrorKind::Corrupt,
+        message,
+        column: Some(name.to_string()),
+        value: row.try_get::<_, Option<String>>(name).ok().flatten(),
+    }
+}
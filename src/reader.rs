@@ -1,17 +1,60 @@
 use crate::error::{SampleGuardError, Result};
+use crate::region::RegulatoryRegion;
 use crate::tag::TagData;
+use serde::{Deserialize, Serialize};
 
 /// RFID Reader configuration
 #[derive(Debug, Clone)]
 pub struct ReaderConfig {
     pub frequency: ReaderFrequency,
-    pub power_level: u8, // 0-100
+    pub power_level: u8, // 0-100; conducted dBm on UHF readers
     pub read_timeout_ms: u32,
     pub antenna_gain: f32,
+    /// Regulatory region; UHF power is unconstrained when unset
+    pub region: Option<RegulatoryRegion>,
+}
+
+impl ReaderConfig {
+    /// Check the configuration against its regulatory region
+    pub fn validate(&self) -> Result<()> {
+        match self.region {
+            Some(region) if self.frequency == ReaderFrequency::UltraHighFrequency => {
+                region.profile().check_power(self.power_level, self.antenna_gain)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Set the power level, refusing settings beyond the region's limit
+    pub fn set_power_level(&mut self, power_level: u8) -> Result<()> {
+        let previous = self.power_level;
+        self.power_level = power_level;
+        if let Err(e) = self.validate() {
+            self.power_level = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Switch to `region`, clamping the power level to its limit
+    pub fn apply_region(&mut self, region: RegulatoryRegion) {
+        self.region = Some(region);
+        if self.frequency == ReaderFrequency::UltraHighFrequency {
+            let clamped = region.profile().clamp_power(self.power_level, self.antenna_gain);
+            if clamped < self.power_level {
+                log::warn!(
+                    region:? = region, power_dbm = self.power_level, limit_dbm = clamped;
+                    "Power level {} dBm exceeds the {:?} limit, clamping to {} dBm",
+                    self.power_level, region, clamped
+                );
+                self.power_level = clamped;
+            }
+        }
+    }
 }
 
 /// RFID frequency bands
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReaderFrequency {
     LowFrequency,    // 125-134 kHz
     HighFrequency,   // 13.56 MHz
@@ -19,7 +62,7 @@ pub enum ReaderFrequency {
 }
 
 /// RFID Reader capabilities
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ReaderCapabilities {
     pub supports_encryption: bool,
     pub max_tag_memory: usize,
@@ -66,10 +109,12 @@ impl MockRFIDReader {
                 power_level: 50,
                 read_timeout_ms: 1000,
                 antenna_gain: 6.0,
+                region: None,
             },
             capabilities: ReaderCapabilities {
                 supports_encryption: true,
-                max_tag_memory: 512,
+                // Room for a JSON-encoded sample tag
+                max_tag_memory: 8192,
                 read_range_cm: 100,
                 write_speed_ms: 50,
                 supported_frequencies: vec![
@@ -80,6 +125,12 @@ impl MockRFIDReader {
             stored_data: None,
         }
     }
+
+    /// Simulate tags with `bytes` of user memory
+    pub fn with_max_tag_memory(mut self, bytes: usize) -> Self {
+        self.capabilities.max_tag_memory = bytes;
+        self
+    }
 }
 
 impl RFIDReader for MockRFIDReader {
@@ -112,6 +163,12 @@ impl RFIDReader for MockRFIDReader {
     }
 }
 
+impl Default for MockRFIDReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Example implementation for a real RFID reader
 /// This would be implemented for specific hardware (e.g., Impinj Speedway, Zebra FX9600)
 pub struct HardwareRFIDReader {
@@ -145,7 +202,7 @@ impl RFIDReader for HardwareRFIDReader {
     fn initialize(&mut self) -> Result<()> {
         // In production: Initialize hardware connection
         // e.g., connect via USB, Ethernet, or serial port
-        log::info!("Initializing RFID reader hardware");
+        log::info!(frequency:? = self.config.frequency; "Initializing RFID reader hardware");
         Ok(())
     }
     
@@ -157,7 +214,7 @@ impl RFIDReader for HardwareRFIDReader {
         // 3. Parsing EPC/TID/User memory banks
         // 4. Returning TagData
         
-        log::info!("Reading RFID tag from hardware");
+        log::info!(frequency:? = self.config.frequency; "Reading RFID tag from hardware");
         Err(SampleGuardError::ReaderError(
             "Hardware reader not connected (simulation mode)".to_string()
         ))
@@ -170,7 +227,7 @@ impl RFIDReader for HardwareRFIDReader {
         // 2. Writing to appropriate memory bank
         // 3. Verifying write success
         
-        log::info!("Writing RFID tag to hardware");
+        log::info!(frequency:? = self.config.frequency; "Writing RFID tag to hardware");
         Err(SampleGuardError::ReaderError(
             "Hardware reader not connected (simulation mode)".to_string()
         ))
@@ -186,7 +243,7 @@ impl RFIDReader for HardwareRFIDReader {
     
     fn test_connection(&mut self) -> Result<bool> {
         // In production: Test hardware connectivity
-        log::info!("Testing RFID reader connection");
+        log::info!(frequency:? = self.config.frequency; "Testing RFID reader connection");
         Ok(false) // Simulating disconnected state
     }
 }
@@ -0,0 +1,115 @@
+use crate::error::{SampleGuardError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Regulatory region governing UHF frequencies and radiated power
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegulatoryRegion {
+    /// United States / FCC Part 15
+    Fcc,
+    /// Europe / ETSI EN 302 208
+    Etsi,
+}
+
+/// Allowed UHF sub-band and power limit for a region
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegionProfile {
+    pub region: RegulatoryRegion,
+    /// Lowest allowed carrier frequency in kHz
+    pub min_frequency_khz: u32,
+    /// Highest allowed carrier frequency in kHz
+    pub max_frequency_khz: u32,
+    /// Maximum radiated power (EIRP) in dBm
+    pub max_eirp_dbm: f32,
+}
+
+impl RegulatoryRegion {
+    /// Frequency and power limits for this region
+    pub fn profile(&self) -> RegionProfile {
+        match self {
+            // 902-928 MHz, 4 W EIRP
+            RegulatoryRegion::Fcc => RegionProfile {
+                region: *self,
+                min_frequency_khz: 902_000,
+                max_frequency_khz: 928_000,
+                max_eirp_dbm: 36.0,
+            },
+            // 865-868 MHz, 2 W ERP
+            RegulatoryRegion::Etsi => RegionProfile {
+                region: *self,
+                min_frequency_khz: 865_000,
+                max_frequency_khz: 868_000,
+                max_eirp_dbm: 35.15,
+            },
+        }
+    }
+}
+
+impl RegionProfile {
+    /// Highest conducted reader power (dBm) allowed with the given antenna gain
+    pub fn max_power_dbm(&self, antenna_gain: f32) -> u8 {
+        (self.max_eirp_dbm - antenna_gain).floor().clamp(0.0, u8::MAX as f32) as u8
+    }
+
+    /// Check a conducted power setting against the region's limit
+    pub fn check_power(&self, power_dbm: u8, antenna_gain: f32) -> Result<()> {
+        let max = self.max_power_dbm(antenna_gain);
+        if power_dbm > max {
+            return Err(SampleGuardError::ReaderError(format!(
+                "Power {} dBm with {:.1} dBi antenna exceeds the {:?} limit of {} dBm",
+                power_dbm, antenna_gain, self.region, max
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reduce a power setting to the region's limit if needed
+    pub fn clamp_power(&self, power_dbm: u8, antenna_gain: f32) -> u8 {
+        power_dbm.min(self.max_power_dbm(antenna_gain))
+    }
+
+    /// Whether a carrier frequency lies inside the region's sub-band
+    pub fn allows_frequency_khz(&self, frequency_khz: u32) -> bool {
+        (self.min_frequency_khz..=self.max_frequency_khz).contains(&frequency_khz)
+    }
+
+    /// Check a carrier frequency against the region's sub-band
+    pub fn check_frequency_khz(&self, frequency_khz: u32) -> Result<()> {
+        if !self.allows_frequency_khz(frequency_khz) {
+            return Err(SampleGuardError::ReaderError(format!(
+                "Frequency {} kHz is outside the {:?} band {}-{} kHz",
+                frequency_khz, self.region, self.min_frequency_khz, self.max_frequency_khz
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_limits_account_for_antenna_gain() {
+        let fcc = RegulatoryRegion::Fcc.profile();
+        let etsi = RegulatoryRegion::Etsi.profile();
+
+        assert_eq!(fcc.max_power_dbm(6.0), 30);
+        assert_eq!(etsi.max_power_dbm(6.0), 29);
+        assert!(fcc.check_power(30, 6.0).is_ok());
+        assert!(etsi.check_power(30, 6.0).is_err());
+        assert_eq!(etsi.clamp_power(30, 6.0), 29);
+        assert_eq!(etsi.clamp_power(20, 6.0), 20);
+    }
+
+    #[test]
+    fn test_frequency_sub_bands() {
+        let fcc = RegulatoryRegion::Fcc.profile();
+        let etsi = RegulatoryRegion::Etsi.profile();
+
+        assert!(fcc.allows_frequency_khz(915_250));
+        assert!(!etsi.allows_frequency_khz(915_250));
+        assert!(etsi.allows_frequency_khz(866_300));
+        assert!(fcc.check_frequency_khz(866_300).is_err());
+    }
+}
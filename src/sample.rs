@@ -4,6 +4,8 @@ use uuid::Uuid;
 use crate::tag::RFIDTag;
 use crate::encryption::RFIDEncryption;
 use crate::error::{SampleGuardError, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
 
 /// Sample status for tracking lifecycle
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -24,8 +26,38 @@ pub enum SampleStatus {
     Compromised,
 }
 
+impl SampleStatus {
+    /// Whether a sample may move from this status to `next`. Staying in the
+    /// same status is always allowed; consumed and discarded samples can only
+    /// be marked compromised, and compromised samples can go nowhere else.
+    pub fn can_transition_to(&self, next: SampleStatus) -> bool {
+        use SampleStatus::*;
+        *self == next
+            || matches!(
+                (self, next),
+                // Consumed straight from production, e.g. pooled by a merge
+                (InProduction, InTransit | Stored | Consumed | Discarded | Compromised)
+                    | (InTransit, Stored | InUse | Discarded | Compromised)
+                    | (Stored, InTransit | InUse | Consumed | Discarded | Compromised)
+                    | (InUse, Stored | Consumed | Discarded | Compromised)
+                    // A finished sample can still turn out to have been tampered with
+                    | (Consumed | Discarded, Compromised)
+            )
+    }
+}
+
+/// Master key of tags written without one of their own. It ships with the
+/// binary, so deployments should configure their own key.
+pub const DEFAULT_MASTER_KEY: &[u8] = b"default_master_key_32_bytes_long!!";
+
+/// Encryption with the default sample key, derived once rather than per tag
+pub(crate) fn default_encryption() -> &'static RFIDEncryption {
+    static DEFAULT: OnceLock<RFIDEncryption> = OnceLock::new();
+    DEFAULT.get_or_init(|| RFIDEncryption::new(DEFAULT_MASTER_KEY))
+}
+
 /// Sample metadata for medical device tracking
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SampleMetadata {
     pub batch_number: String,
     pub production_date: DateTime<Utc>,
@@ -36,6 +68,61 @@ pub struct SampleMetadata {
     pub product_line: String,
 }
 
+/// Source of sample ids
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> Uuid;
+}
+
+/// Random v4 ids, used by [`Sample::new`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIdGenerator;
+
+impl IdGenerator for RandomIdGenerator {
+    fn next_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Reproducible ids for tests: the seed fills the upper 64 bits and a
+/// counter starting at 1 the lower, so seed 0 yields
+/// `00000000-0000-0000-0000-000000000001`, `...0002`, ...
+#[derive(Debug, Default)]
+pub struct SequentialIdGenerator {
+    seed: u64,
+    next: AtomicU64,
+}
+
+impl SequentialIdGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, next: AtomicU64::new(1) }
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_id(&self) -> Uuid {
+        let n = self.next.fetch_add(1, Ordering::Relaxed);
+        Uuid::from_u64_pair(self.seed, n)
+    }
+}
+
+/// Version byte that opens the hash input of a length-prefixed checksum
+const CHECKSUM_VERSION_LENGTH_PREFIXED: u8 = 2;
+
+/// Layout of the bytes hashed into `Sample::integrity_checksum`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumVersion {
+    /// Fields concatenated without separators, so ("AB", "C") and ("A", "BC")
+    /// collide. Only accepted when verifying older samples.
+    Legacy,
+    /// A version byte, then each string field prefixed with its length
+    LengthPrefixed,
+}
+
+impl ChecksumVersion {
+    /// Layout used for every newly computed checksum
+    pub const CURRENT: ChecksumVersion = ChecksumVersion::LengthPrefixed;
+}
+
 /// Sample entity representing a tracked medical sample
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sample {
@@ -48,6 +135,12 @@ pub struct Sample {
     pub read_count: u64,
     pub location: Option<String>,
     pub integrity_checksum: [u8; 32],
+    /// Box, rack or tray holding the sample
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_id: Option<String>,
+    /// Free-text remarks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
 }
 
 impl Sample {
@@ -56,9 +149,19 @@ impl Sample {
         sample_id: String,
         metadata: SampleMetadata,
         location: Option<String>,
+    ) -> Self {
+        Self::new_with_ids(sample_id, metadata, location, &RandomIdGenerator)
+    }
+
+    /// Create a new sample whose id comes from `ids`
+    pub fn new_with_ids(
+        sample_id: String,
+        metadata: SampleMetadata,
+        location: Option<String>,
+        ids: &dyn IdGenerator,
     ) -> Self {
         let now = Utc::now();
-        let id = Uuid::new_v4();
+        let id = ids.next_id();
         
         // Calculate initial integrity checksum
         let integrity_checksum = Self::calculate_checksum(&sample_id, &metadata, &now);
@@ -73,26 +176,44 @@ impl Sample {
             read_count: 0,
             location,
             integrity_checksum,
+            container_id: None,
+            notes: None,
         }
     }
 
-    /// Convert sample to RFID tag for writing
+    /// A new sample with this one's metadata and location, as if freshly
+    /// created: new `id`, `InProduction`, no reads and current timestamps
+    pub fn clone_as_new(&self, new_sample_id: String) -> Sample {
+        Self::new(new_sample_id, self.metadata.clone(), self.location.clone())
+    }
+
+    /// Convert sample to RFID tag for writing, encrypted with the default key
+    #[deprecated(note = "the default key ships with the binary; use `to_tag_with_key`")]
     pub fn to_tag(&self) -> Result<RFIDTag> {
-        let encryption = RFIDEncryption::new(b"default_master_key_32_bytes_long!!");
-        
+        self.to_tag_with_key(default_encryption())
+    }
+
+    /// Encode the sample onto a tag encrypted with `encryption`, under the
+    /// key of the sample ID, which the tag carries as its EPC
+    pub fn to_tag_with_key(&self, encryption: &RFIDEncryption) -> Result<RFIDTag> {
         // Serialize sample data
         let sample_data = serde_json::to_vec(self)
             .map_err(|e| SampleGuardError::InvalidSampleData(format!("Serialization failed: {}", e)))?;
         
-        RFIDTag::new(self.sample_id.clone(), &sample_data, &encryption)
+        RFIDTag::new(self.sample_id.clone(), &self.sample_id, &sample_data, encryption)
     }
 
-    /// Create sample from RFID tag
+    /// Create sample from RFID tag encrypted with the default key
+    #[deprecated(note = "the default key ships with the binary; use `from_tag_with_key`")]
     pub fn from_tag(tag: &RFIDTag) -> Result<Self> {
-        let encryption = RFIDEncryption::new(b"default_master_key_32_bytes_long!!");
-        
+        Self::from_tag_with_key(tag, default_encryption())
+    }
+
+    /// Decode a sample from a tag encrypted with `encryption`, under the key
+    /// of the tag's ID
+    pub fn from_tag_with_key(tag: &RFIDTag, encryption: &RFIDEncryption) -> Result<Self> {
         // Decrypt payload
-        let decrypted = tag.decrypt_payload(&encryption)?;
+        let decrypted = tag.decrypt_payload(&tag.tag_id, encryption)?;
         
         // Deserialize sample
         let sample: Sample = serde_json::from_slice(&decrypted)
@@ -101,37 +222,109 @@ impl Sample {
         Ok(sample)
     }
 
-    /// Update sample status
-    pub fn update_status(&mut self, new_status: SampleStatus) {
+    /// Update sample status, refusing transitions `can_transition_to` forbids
+    pub fn try_update_status(&mut self, new_status: SampleStatus) -> Result<()> {
+        if !self.status.can_transition_to(new_status) {
+            return Err(SampleGuardError::InvalidStatusTransition { from: self.status, to: new_status });
+        }
         self.status = new_status;
-        self.last_updated = Utc::now();
-        self.integrity_checksum = Self::calculate_checksum(
-            &self.sample_id,
-            &self.metadata,
-            &self.last_updated,
-        );
+        self.touch();
+        Ok(())
+    }
+
+    /// Update sample status
+    ///
+    /// Kept for existing callers; behaves like `try_update_status` but also
+    /// logs a warning for every rejected transition.
+    pub fn update_status(&mut self, new_status: SampleStatus) -> Result<()> {
+        self.try_update_status(new_status).inspect_err(|e| {
+            log::warn!(sample_id = self.sample_id.as_str(); "Status update rejected: {}", e);
+        })
     }
 
     /// Update sample location
     pub fn update_location(&mut self, location: String) {
         self.location = Some(location);
-        self.last_updated = Utc::now();
+        self.touch();
+    }
+
+    /// Place the sample in a container, or take it out of one
+    pub fn update_container(&mut self, container_id: Option<String>) {
+        self.container_id = container_id;
+        self.touch();
+    }
+
+    /// Replace the sample's notes
+    pub fn update_notes(&mut self, notes: Option<String>) {
+        self.notes = notes;
+        self.touch();
     }
 
     /// Increment read count (for tracking tag access)
     pub fn increment_read_count(&mut self) {
         self.read_count += 1;
-        self.last_updated = Utc::now();
+        self.touch();
     }
 
-    /// Verify sample integrity
-    pub fn verify_integrity(&self) -> bool {
-        let calculated = Self::calculate_checksum(
+    /// Bump `last_updated` and refresh the checksum that covers it
+    fn touch(&mut self) {
+        self.last_updated = Utc::now();
+        self.integrity_checksum = Self::calculate_checksum(
             &self.sample_id,
             &self.metadata,
             &self.last_updated,
         );
-        calculated == self.integrity_checksum
+    }
+
+    /// Verify sample integrity
+    ///
+    /// Only checksums in the current layout verify. Samples stored before the
+    /// layout changed must be upgraded with `upgrade_checksum`, or accepted
+    /// explicitly through `Database::with_allow_legacy_checksums`.
+    pub fn verify_integrity(&self) -> bool {
+        self.checksum_version() == Some(ChecksumVersion::CURRENT)
+    }
+
+    /// Layout the stored checksum was computed with, or `None` if it matches
+    /// neither layout
+    pub fn checksum_version(&self) -> Option<ChecksumVersion> {
+        [ChecksumVersion::CURRENT, ChecksumVersion::Legacy]
+            .into_iter()
+            .find(|&version| {
+                Self::calculate_checksum_with(version, &self.sample_id, &self.metadata, &self.last_updated)
+                    == self.integrity_checksum
+            })
+    }
+
+    /// Recompute a valid legacy checksum in the current layout, returning
+    /// whether anything changed. Unlike `touch`, `last_updated` is kept.
+    pub fn upgrade_checksum(&mut self) -> bool {
+        if self.checksum_version() != Some(ChecksumVersion::Legacy) {
+            return false;
+        }
+        self.integrity_checksum = Self::calculate_checksum(&self.sample_id, &self.metadata, &self.last_updated);
+        true
+    }
+
+    /// Compare the stable business fields (sample ID, status and metadata),
+    /// ignoring volatile fields such as `id`, `read_count` and `last_updated`
+    pub fn content_eq(&self, other: &Sample) -> bool {
+        self.sample_id == other.sample_id
+            && self.status == other.status
+            && self.metadata == other.metadata
+    }
+
+    /// Hash of the fields compared by `content_eq`, for deduplication
+    pub fn canonical_hash(&self) -> [u8; 32] {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        hasher.update(self.sample_id.as_bytes());
+        hasher.update([0]);
+        hasher.update(format!("{:?}", self.status).as_bytes());
+        hasher.update([0]);
+        // Struct fields serialize in declaration order, so this is deterministic
+        hasher.update(serde_json::to_vec(&self.metadata).unwrap_or_default());
+        hasher.finalize().into()
     }
 
     /// Check if sample is expired
@@ -143,16 +336,37 @@ impl Sample {
         }
     }
 
-    /// Calculate integrity checksum
-    fn calculate_checksum(
+    /// Calculate integrity checksum in the current layout
+    pub(crate) fn calculate_checksum(
+        sample_id: &str,
+        metadata: &SampleMetadata,
+        timestamp: &DateTime<Utc>,
+    ) -> [u8; 32] {
+        Self::calculate_checksum_with(ChecksumVersion::CURRENT, sample_id, metadata, timestamp)
+    }
+
+    /// Calculate integrity checksum in the given layout
+    pub fn calculate_checksum_with(
+        version: ChecksumVersion,
         sample_id: &str,
         metadata: &SampleMetadata,
         timestamp: &DateTime<Utc>,
     ) -> [u8; 32] {
         use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
-        hasher.update(sample_id.as_bytes());
-        hasher.update(metadata.batch_number.as_bytes());
+        match version {
+            ChecksumVersion::Legacy => {
+                hasher.update(sample_id.as_bytes());
+                hasher.update(metadata.batch_number.as_bytes());
+            }
+            ChecksumVersion::LengthPrefixed => {
+                hasher.update([CHECKSUM_VERSION_LENGTH_PREFIXED]);
+                for field in [sample_id, metadata.batch_number.as_str()] {
+                    hasher.update((field.len() as u64).to_be_bytes());
+                    hasher.update(field.as_bytes());
+                }
+            }
+        }
         hasher.update(timestamp.timestamp().to_be_bytes());
         hasher.finalize().into()
     }
@@ -186,19 +400,163 @@ mod tests {
     #[test]
     fn test_sample_status_update() {
         let mut sample = create_test_sample();
-        sample.update_status(SampleStatus::InTransit);
+        sample.update_status(SampleStatus::InTransit).unwrap();
         assert_eq!(sample.status, SampleStatus::InTransit);
         assert!(sample.verify_integrity());
     }
 
+    #[test]
+    fn test_status_transition_matrix() {
+        use SampleStatus::*;
+        let all = [InProduction, InTransit, Stored, InUse, Consumed, Discarded, Compromised];
+        let allowed = [
+            (InProduction, &[InProduction, InTransit, Stored, Consumed, Discarded, Compromised][..]),
+            (InTransit, &[InTransit, Stored, InUse, Discarded, Compromised][..]),
+            (Stored, &[Stored, InTransit, InUse, Consumed, Discarded, Compromised][..]),
+            (InUse, &[InUse, Stored, Consumed, Discarded, Compromised][..]),
+            (Consumed, &[Consumed, Compromised][..]),
+            (Discarded, &[Discarded, Compromised][..]),
+            (Compromised, &[Compromised][..]),
+        ];
+        for (from, targets) in allowed {
+            for to in all {
+                let expected = targets.contains(&to);
+                assert_eq!(from.can_transition_to(to), expected, "{:?} -> {:?}", from, to);
+
+                let mut sample = create_test_sample();
+                sample.status = from;
+                let result = sample.try_update_status(to);
+                if expected {
+                    assert!(result.is_ok());
+                    assert_eq!(sample.status, to);
+                } else {
+                    assert!(matches!(
+                        result,
+                        Err(SampleGuardError::InvalidStatusTransition { from: f, to: t }) if f == from && t == to
+                    ));
+                    assert_eq!(sample.status, from);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tag_payload_is_bound_to_tag_id() {
+        let encryption = RFIDEncryption::new_gcm(DEFAULT_MASTER_KEY);
+        let mut tag = create_test_sample().to_tag_with_key(&encryption).unwrap();
+        assert!(Sample::from_tag_with_key(&tag, &encryption).is_ok());
+        
+        // A payload copied onto a tag with another ID does not decrypt
+        tag.tag_id = "OTHER-001".to_string();
+        assert!(matches!(Sample::from_tag_with_key(&tag, &encryption), Err(SampleGuardError::EncryptionError(_))));
+    }
+
     #[test]
     fn test_sample_to_tag_conversion() {
         let sample = create_test_sample();
-        let tag = sample.to_tag().unwrap();
-        let restored = Sample::from_tag(&tag).unwrap();
+        let tag = sample.to_tag_with_key(default_encryption()).unwrap();
+        let restored = Sample::from_tag_with_key(&tag, default_encryption()).unwrap();
         
         assert_eq!(sample.sample_id, restored.sample_id);
         assert_eq!(sample.status, restored.status);
     }
-}
 
+    #[test]
+    fn test_clone_as_new_resets_volatile_state() {
+        let mut source = create_test_sample();
+        source.update_status(SampleStatus::Stored).unwrap();
+        source.increment_read_count();
+        
+        let clone = source.clone_as_new("SAMPLE-CLONE".to_string());
+        assert_eq!(clone.sample_id, "SAMPLE-CLONE");
+        assert_ne!(clone.id, source.id);
+        assert_eq!(clone.status, SampleStatus::InProduction);
+        assert_eq!(clone.read_count, 0);
+        assert_eq!(clone.metadata, source.metadata);
+        assert!(clone.created_at >= source.last_updated);
+        assert!(clone.verify_integrity());
+    }
+
+    #[test]
+    fn test_content_eq_ignores_volatile_fields() {
+        let sample = create_test_sample();
+        let mut reread = sample.clone();
+        reread.id = Uuid::new_v4();
+        reread.increment_read_count();
+        
+        assert!(sample.content_eq(&reread));
+        assert_eq!(sample.canonical_hash(), reread.canonical_hash());
+        
+        reread.metadata.storage_conditions = "Frozen".to_string();
+        assert!(!sample.content_eq(&reread));
+        assert_ne!(sample.canonical_hash(), reread.canonical_hash());
+    }
+
+    #[test]
+    fn test_checksum_separates_fields() {
+        let mut first = create_test_sample();
+        first.sample_id = "AB".to_string();
+        first.metadata.batch_number = "C".to_string();
+        let mut second = first.clone();
+        second.sample_id = "A".to_string();
+        second.metadata.batch_number = "BC".to_string();
+        let at = first.last_updated;
+
+        let legacy = |s: &Sample| Sample::calculate_checksum_with(ChecksumVersion::Legacy, &s.sample_id, &s.metadata, &at);
+        assert_eq!(legacy(&first), legacy(&second));
+        assert_ne!(
+            Sample::calculate_checksum(&first.sample_id, &first.metadata, &at),
+            Sample::calculate_checksum(&second.sample_id, &second.metadata, &at)
+        );
+    }
+
+    #[test]
+    fn test_legacy_checksum_fails_verification_until_upgraded() {
+        let mut sample = create_test_sample();
+        assert_eq!(sample.checksum_version(), Some(ChecksumVersion::CURRENT));
+        assert!(!sample.upgrade_checksum());
+
+        sample.integrity_checksum = Sample::calculate_checksum_with(
+            ChecksumVersion::Legacy,
+            &sample.sample_id,
+            &sample.metadata,
+            &sample.last_updated,
+        );
+        let last_updated = sample.last_updated;
+        assert!(!sample.verify_integrity());
+        assert_eq!(sample.checksum_version(), Some(ChecksumVersion::Legacy));
+
+        assert!(sample.upgrade_checksum());
+        assert!(sample.verify_integrity());
+        assert_eq!(sample.checksum_version(), Some(ChecksumVersion::CURRENT));
+        assert_eq!(sample.last_updated, last_updated);
+
+        sample.integrity_checksum = [0; 32];
+        assert_eq!(sample.checksum_version(), None);
+        assert!(!sample.upgrade_checksum());
+    }
+
+    #[test]
+    fn test_to_tag_within_budget() {
+        // Generous bound, even for unoptimized builds; see benches/hot_paths_bench.rs
+        let sample = create_test_sample();
+        let start = std::time::Instant::now();
+        for _ in 0..100 {
+            sample.to_tag_with_key(default_encryption()).unwrap();
+        }
+        let per_call = start.elapsed() / 100;
+        assert!(per_call < std::time::Duration::from_millis(2), "to_tag took {:?}", per_call);
+    }
+
+    #[test]
+    fn test_sequential_id_generator_is_reproducible() {
+        let metadata = create_test_sample().metadata;
+        let ids = SequentialIdGenerator::new(42);
+        let first = Sample::new_with_ids("SEQ-1".to_string(), metadata.clone(), None, &ids);
+        let second = Sample::new_with_ids("SEQ-2".to_string(), metadata, None, &ids);
+
+        assert_eq!(first.id.to_string(), "00000000-0000-002a-0000-000000000001");
+        assert_eq!(second.id.to_string(), "00000000-0000-002a-0000-000000000002");
+        assert_eq!(SequentialIdGenerator::new(42).next_id(), first.id);
+    }
+}
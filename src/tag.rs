@@ -1,12 +1,12 @@
 use serde::{Deserialize, Serialize};
-use crate::encryption::RFIDEncryption;
+use crate::encryption::{EncryptionMode, KeyRing, RFIDEncryption, TagKeys};
 use crate::error::{SampleGuardError, Result};
 
 /// RFID Tag memory layout specification
 /// Optimized for medical device sample tracking
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TagMemoryLayout {
-    /// Header section (16 bytes): Tag type, version, flags
+    /// Header section (16 bytes): Tag type, version, flags, key ID
     pub header: [u8; 16],
     /// Encrypted payload section (variable, typically 64-128 bytes)
     pub payload: Vec<u8>,
@@ -16,6 +16,36 @@ pub struct TagMemoryLayout {
     pub metadata: [u8; 16],
 }
 
+/// Header version of tags whose payload is encrypted with the master key itself
+pub const TAG_VERSION_SHARED_KEY: u8 = 0x01;
+/// Header version of tags whose payload is encrypted with a key derived from their EPC
+pub const TAG_VERSION_EPC_KEY: u8 = 0x02;
+/// Header version of EPC-keyed tags whose flags also record the cipher mode
+pub const TAG_VERSION_MODE_FLAGS: u8 = 0x03;
+/// Header flag set on tags with an encrypted payload
+pub const TAG_FLAG_ENCRYPTED: u8 = 0x01;
+/// Header flag set on tags encrypted with AES-256-GCM rather than CBC
+pub const TAG_FLAG_GCM: u8 = 0x02;
+/// Header offset of the ID of the key the payload is encrypted with
+pub const TAG_KEY_ID_OFFSET: usize = 3;
+
+/// First bytes of a tag in the binary format
+pub const BINARY_TAG_MAGIC: [u8; 4] = *b"SGTG";
+/// Version of the binary format written by [`RFIDTag::to_bytes_binary`]
+pub const BINARY_FORMAT_VERSION: u8 = 0x01;
+/// Binary format flag set when `encryption_enabled` is
+const BINARY_FLAG_ENCRYPTED: u8 = 0x01;
+
+/// Encoding of a tag in tag memory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TagFormat {
+    /// Length-prefixed JSON, readable by every version
+    #[default]
+    Json,
+    /// Fixed binary layout, a fraction of the size of JSON
+    Binary,
+}
+
 /// RFID Tag data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RFIDTag {
@@ -31,10 +61,13 @@ pub struct TagData {
 }
 
 impl RFIDTag {
-    /// Create a new RFID tag with encrypted payload
-    pub fn new(tag_id: String, payload: &[u8], encryption: &RFIDEncryption) -> Result<Self> {
+    /// Create a new RFID tag with its payload encrypted under the key of the
+    /// tag with this EPC, so it cannot be read back as any other tag. The
+    /// newest of `keys` is used and its ID recorded in the header.
+    pub fn new(tag_id: String, epc: &str, payload: &[u8], keys: &impl TagKeys) -> Result<Self> {
         // Encrypt payload
-        let encrypted_payload = encryption.encrypt(payload)?;
+        let (key_id, encryption) = keys.encryption_key();
+        let encrypted_payload = encryption.encrypt_for_tag(epc, payload)?;
         
         // Calculate integrity hash
         let integrity_hash = encryption.hash(&encrypted_payload);
@@ -42,8 +75,12 @@ impl RFIDTag {
         // Create header
         let mut header = [0u8; 16];
         header[0] = 0x01; // Tag type: Sample tracking
-        header[1] = 0x01; // Version
-        header[2] = 0x01; // Encryption enabled flag
+        header[1] = TAG_VERSION_MODE_FLAGS; // Version
+        header[2] = match encryption.mode() {
+            EncryptionMode::Cbc => TAG_FLAG_ENCRYPTED,
+            EncryptionMode::Gcm => TAG_FLAG_ENCRYPTED | TAG_FLAG_GCM,
+        };
+        header[TAG_KEY_ID_OFFSET] = key_id;
         
         // Create metadata (timestamp, read count)
         let mut metadata = [0u8; 16];
@@ -65,24 +102,95 @@ impl RFIDTag {
         })
     }
 
+    /// Convert tag to bytes in `format` for writing to RFID hardware
+    pub fn to_bytes_as(&self, format: TagFormat) -> Result<Vec<u8>> {
+        match format {
+            TagFormat::Json => self.to_bytes(),
+            TagFormat::Binary => self.to_bytes_binary(),
+        }
+    }
+
     /// Convert tag to bytes for writing to RFID hardware
     pub fn to_bytes(&self) -> Result<Vec<u8>> {
-        let mut bytes = Vec::new();
-        
-        // Serialize to JSON for structured storage
-        let json = serde_json::to_vec(self)
+        // Serialize to JSON for structured storage, straight after a
+        // placeholder length prefix so the JSON is not copied again
+        let mut bytes = Vec::with_capacity(4 + 64 + 4 * self.memory_layout.payload.len());
+        bytes.extend_from_slice(&[0u8; 4]);
+        serde_json::to_writer(&mut bytes, self)
             .map_err(|e| SampleGuardError::TagParseError(format!("Serialization failed: {}", e)))?;
         
-        // Add length prefix
-        let len = json.len() as u32;
-        bytes.extend_from_slice(&len.to_be_bytes());
-        bytes.extend_from_slice(&json);
+        // Fill in length prefix
+        let len = (bytes.len() - 4) as u32;
+        bytes[..4].copy_from_slice(&len.to_be_bytes());
         
         Ok(bytes)
     }
 
-    /// Create tag from bytes read from RFID hardware
+    /// Encode the tag compactly: magic, format version, flags, tag ID
+    /// length and tag ID, header, big-endian payload length and payload,
+    /// integrity hash, then metadata
+    pub fn to_bytes_binary(&self) -> Result<Vec<u8>> {
+        let layout = &self.memory_layout;
+        let tag_id_len = u8::try_from(self.tag_id.len()).map_err(|_| SampleGuardError::TagParseError(
+            format!("Tag ID of {} bytes is too long for the binary format", self.tag_id.len())
+        ))?;
+        let payload_len = u16::try_from(layout.payload.len()).map_err(|_| SampleGuardError::TagParseError(
+            format!("Payload of {} bytes is too long for the binary format", layout.payload.len())
+        ))?;
+
+        let mut bytes = Vec::with_capacity(7 + self.tag_id.len() + 16 + 2 + layout.payload.len() + 32 + 16);
+        bytes.extend_from_slice(&BINARY_TAG_MAGIC);
+        bytes.push(BINARY_FORMAT_VERSION);
+        bytes.push(if self.encryption_enabled { BINARY_FLAG_ENCRYPTED } else { 0 });
+        bytes.push(tag_id_len);
+        bytes.extend_from_slice(self.tag_id.as_bytes());
+        bytes.extend_from_slice(&layout.header);
+        bytes.extend_from_slice(&payload_len.to_be_bytes());
+        bytes.extend_from_slice(&layout.payload);
+        bytes.extend_from_slice(&layout.integrity_hash);
+        bytes.extend_from_slice(&layout.metadata);
+        Ok(bytes)
+    }
+
+    /// Decode a tag written by [`RFIDTag::to_bytes_binary`]
+    pub fn from_bytes_binary(data: &[u8]) -> Result<Self> {
+        let mut reader = BinaryReader { data };
+        if reader.take(4)? != BINARY_TAG_MAGIC {
+            return Err(SampleGuardError::TagParseError("Not a binary tag".to_string()));
+        }
+        let version = reader.take(1)?[0];
+        if version != BINARY_FORMAT_VERSION {
+            return Err(SampleGuardError::TagParseError(format!(
+                "Unsupported binary tag version 0x{:02x}", version
+            )));
+        }
+        let flags = reader.take(1)?[0];
+        let tag_id_len = reader.take(1)?[0] as usize;
+        let tag_id = String::from_utf8(reader.take(tag_id_len)?.to_vec())
+            .map_err(|_| SampleGuardError::TagParseError("Tag ID is not valid UTF-8".to_string()))?;
+        let header = reader.take_array::<16>()?;
+        let payload_len = u16::from_be_bytes(reader.take_array::<2>()?) as usize;
+        let payload = reader.take(payload_len)?.to_vec();
+        let integrity_hash = reader.take_array::<32>()?;
+        let metadata = reader.take_array::<16>()?;
+        if !reader.data.is_empty() {
+            return Err(SampleGuardError::TagParseError(format!(
+                "{} unexpected bytes after binary tag", reader.data.len()
+            )));
+        }
+
+        Ok(Self {
+            tag_id,
+            memory_layout: TagMemoryLayout { header, payload, integrity_hash, metadata },
+            encryption_enabled: flags & BINARY_FLAG_ENCRYPTED != 0,
+        })
+    }
+
+    /// Create tag from bytes read from RFID hardware, in either format
     pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.starts_with(&BINARY_TAG_MAGIC) {
+            return Self::from_bytes_binary(data);
+        }
         if data.len() < 4 {
             return Err(SampleGuardError::TagParseError(
                 "Data too short for tag".to_string()
@@ -92,7 +200,8 @@ impl RFIDTag {
         // Read length prefix
         let len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
         
-        if data.len() < 4 + len {
+        // Compared without adding, so a huge prefix cannot overflow
+        if data.len() - 4 < len {
             return Err(SampleGuardError::TagParseError(
                 "Incomplete tag data".to_string()
             ));
@@ -105,8 +214,45 @@ impl RFIDTag {
         Ok(tag)
     }
 
-    /// Decrypt and verify tag payload
-    pub fn decrypt_payload(&self, encryption: &RFIDEncryption) -> Result<Vec<u8>> {
+    /// ID of the key the payload is encrypted with
+    pub fn key_id(&self) -> u8 {
+        self.memory_layout.header[TAG_KEY_ID_OFFSET]
+    }
+
+    /// Cipher mode recorded in the header, or `None` for tags written before
+    /// the mode was recorded
+    pub fn encryption_mode(&self) -> Option<EncryptionMode> {
+        if self.memory_layout.header[1] < TAG_VERSION_MODE_FLAGS {
+            return None;
+        }
+        Some(if self.memory_layout.header[2] & TAG_FLAG_GCM != 0 {
+            EncryptionMode::Gcm
+        } else {
+            EncryptionMode::Cbc
+        })
+    }
+
+    /// `encryption` switched to the mode this tag was written with. Tags that
+    /// do not record one are assumed to use the mode `encryption` is set to.
+    /// A GCM key refuses CBC tags unless it allows CBC migration, because the
+    /// header flags are not authenticated and could be flipped to downgrade.
+    pub fn payload_cipher(&self, encryption: &RFIDEncryption) -> Result<RFIDEncryption> {
+        let mode = self.encryption_mode().unwrap_or(encryption.mode());
+        if !encryption.accepts_mode(mode) {
+            return Err(SampleGuardError::EncryptionError(format!(
+                "Tag is encrypted in {:?} mode but the key requires {:?}",
+                mode, encryption.mode()
+            )));
+        }
+        Ok(encryption.clone().with_mode(mode))
+    }
+
+    /// Decrypt and verify the payload of the tag with this EPC, using the
+    /// key of `keys` whose ID the header records in the mode its flags
+    /// record. Tags from before per-tag keys are decrypted with the master key.
+    pub fn decrypt_payload(&self, epc: &str, keys: &impl TagKeys) -> Result<Vec<u8>> {
+        let encryption = self.payload_cipher(keys.decryption_key(self.key_id())?)?;
+
         // Verify integrity hash
         let calculated_hash = encryption.hash(&self.memory_layout.payload);
         if calculated_hash != self.memory_layout.integrity_hash {
@@ -116,7 +262,10 @@ impl RFIDTag {
         }
         
         // Decrypt payload
-        encryption.decrypt(&self.memory_layout.payload)
+        match self.memory_layout.header[1] {
+            TAG_VERSION_SHARED_KEY => encryption.decrypt(&self.memory_layout.payload),
+            _ => encryption.decrypt_for_tag(epc, &self.memory_layout.payload),
+        }
     }
 
     /// Update read count in metadata
@@ -132,16 +281,63 @@ impl RFIDTag {
             self.memory_layout.metadata[15],
         ]);
         
-        let new_count = read_count + 1;
+        // The count comes off the tag, so it may already be at the maximum
+        let new_count = read_count.saturating_add(1);
         self.memory_layout.metadata[8..16].copy_from_slice(&new_count.to_be_bytes());
     }
 }
 
+/// Re-encrypt the payload of the tag with this EPC under the newest key of
+/// `keys`, keeping its metadata. Returns whether the tag changed; tags
+/// already on the newest key are left alone.
+pub fn reencrypt(tag: &mut RFIDTag, epc: &str, keys: &KeyRing) -> Result<bool> {
+    let (key_id, encryption) = keys.encryption_key();
+    if tag.key_id() == key_id && tag.encryption_mode() == Some(encryption.mode()) {
+        return Ok(false);
+    }
+
+    let payload = tag.decrypt_payload(epc, keys)?;
+    let layout = &mut tag.memory_layout;
+    layout.payload = encryption.encrypt_for_tag(epc, &payload)?;
+    layout.integrity_hash = encryption.hash(&layout.payload);
+    layout.header[1] = TAG_VERSION_MODE_FLAGS;
+    layout.header[2] = match encryption.mode() {
+        EncryptionMode::Cbc => TAG_FLAG_ENCRYPTED,
+        EncryptionMode::Gcm => TAG_FLAG_ENCRYPTED | TAG_FLAG_GCM,
+    };
+    layout.header[TAG_KEY_ID_OFFSET] = key_id;
+    Ok(true)
+}
+
+/// Cursor over binary tag bytes that fails cleanly when they run out
+struct BinaryReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BinaryReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(SampleGuardError::TagParseError("Incomplete tag data".to_string()));
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("took exactly N bytes"))
+    }
+}
+
 impl TagData {
     pub fn new(bytes: Vec<u8>) -> Self {
         Self { bytes }
     }
     
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+    
     pub fn as_bytes(&self) -> &[u8] {
         &self.bytes
     }
@@ -150,13 +346,14 @@ impl TagData {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sample::{Sample, SampleMetadata};
 
     #[test]
     fn test_tag_creation() {
         let encryption = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
         let payload = b"test sample data";
         
-        let tag = RFIDTag::new("TAG001".to_string(), payload, &encryption).unwrap();
+        let tag = RFIDTag::new("TAG001".to_string(), "TAG001", payload, &encryption).unwrap();
         assert_eq!(tag.tag_id, "TAG001");
         assert!(tag.encryption_enabled);
     }
@@ -166,7 +363,7 @@ mod tests {
         let encryption = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
         let payload = b"test sample data";
         
-        let tag = RFIDTag::new("TAG001".to_string(), payload, &encryption).unwrap();
+        let tag = RFIDTag::new("TAG001".to_string(), "TAG001", payload, &encryption).unwrap();
         let bytes = tag.to_bytes().unwrap();
         let restored = RFIDTag::from_bytes(&bytes).unwrap();
         
@@ -179,10 +376,240 @@ mod tests {
         let encryption = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
         let payload = b"test sample data";
         
-        let tag = RFIDTag::new("TAG001".to_string(), payload, &encryption).unwrap();
-        let decrypted = tag.decrypt_payload(&encryption).unwrap();
+        let tag = RFIDTag::new("TAG001".to_string(), "TAG001", payload, &encryption).unwrap();
+        let decrypted = tag.decrypt_payload("TAG001", &encryption).unwrap();
         
         assert_eq!(payload, decrypted.as_slice());
     }
-}
 
+    #[test]
+    fn test_gcm_payload_rejects_rehashed_tampering() {
+        let encryption = RFIDEncryption::new_gcm(b"test_key_32_bytes_long_for_aes256!!");
+        let mut tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"test sample data", &encryption).unwrap();
+        assert_eq!(tag.decrypt_payload("TAG001", &encryption).unwrap(), b"test sample data");
+
+        // The integrity hash is unkeyed, so an attacker can recompute it
+        tag.memory_layout.payload[20] ^= 0x01;
+        tag.memory_layout.integrity_hash = encryption.hash(&tag.memory_layout.payload);
+        assert!(matches!(tag.decrypt_payload("TAG001", &encryption), Err(SampleGuardError::EncryptionError(_))));
+    }
+
+    #[test]
+    fn test_payload_is_bound_to_epc() {
+        let encryption = RFIDEncryption::new_gcm(b"test_key_32_bytes_long_for_aes256!!");
+        let tag = RFIDTag::new("TAG001".to_string(), "EPC-001", b"test sample data", &encryption).unwrap();
+        assert_eq!(tag.memory_layout.header[1], TAG_VERSION_MODE_FLAGS);
+        assert_eq!(tag.decrypt_payload("EPC-001", &encryption).unwrap(), b"test sample data");
+        assert!(matches!(tag.decrypt_payload("EPC-002", &encryption), Err(SampleGuardError::EncryptionError(_))));
+    }
+
+    #[test]
+    fn test_shared_key_tags_still_decrypt() {
+        let encryption = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
+        let mut tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"data", &encryption).unwrap();
+        // As written before per-tag keys
+        tag.memory_layout.header[1] = TAG_VERSION_SHARED_KEY;
+        tag.memory_layout.payload = encryption.encrypt(b"legacy data").unwrap();
+        tag.memory_layout.integrity_hash = encryption.hash(&tag.memory_layout.payload);
+        assert_eq!(tag.decrypt_payload("ANY-EPC", &encryption).unwrap(), b"legacy data");
+    }
+
+    #[test]
+    fn test_read_count_saturates_at_maximum() {
+        // Regression: metadata bytes of 0xFF from a tampered tag overflowed the count
+        let encryption = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
+        let mut tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"data", &encryption).unwrap();
+        tag.memory_layout.metadata = [0xFF; 16];
+        let mut bytes = tag.to_bytes().unwrap();
+
+        let mut parsed = RFIDTag::from_bytes(&bytes).unwrap();
+        parsed.increment_read_count();
+        assert_eq!(parsed.memory_layout.metadata[8..16], [0xFF; 8]);
+
+        // Regression: a length prefix near u32::MAX is rejected without overflowing
+        bytes[..4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(RFIDTag::from_bytes(&bytes), Err(SampleGuardError::TagParseError(_))));
+    }
+
+    #[test]
+    fn test_key_id_selects_decryption_key() {
+        let ring = KeyRing::new(7, RFIDEncryption::new(b"first_master_key_32_bytes_long!!"));
+        let tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"data", &ring).unwrap();
+        assert_eq!(tag.key_id(), 7);
+        assert_eq!(tag.decrypt_payload("TAG001", &ring).unwrap(), b"data");
+
+        // A ring that has lost key 7 reports it rather than trying another key
+        let other = KeyRing::new(8, RFIDEncryption::new(b"first_master_key_32_bytes_long!!"));
+        let err = tag.decrypt_payload("TAG001", &other).unwrap_err();
+        assert!(matches!(err, SampleGuardError::EncryptionError(ref msg) if msg.contains("ID 7")));
+
+        // Tags from before key IDs carry 0 and decrypt with a single key
+        let encryption = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
+        let legacy = RFIDTag::new("TAG001".to_string(), "TAG001", b"data", &encryption).unwrap();
+        assert_eq!(legacy.key_id(), 0);
+    }
+
+    #[test]
+    fn test_reencrypt_across_two_rotations() {
+        let mut ring = KeyRing::new(0, RFIDEncryption::new(b"2026Q1_master_key_32_bytes_long!"));
+        let mut tag = RFIDTag::new("TAG001".to_string(), "EPC-001", b"test sample data", &ring).unwrap();
+        tag.increment_read_count();
+        let metadata = tag.memory_layout.metadata;
+
+        ring.rotate(1, RFIDEncryption::new_gcm(b"2026Q2_master_key_32_bytes_long!")).unwrap();
+        ring.rotate(2, RFIDEncryption::new_gcm(b"2026Q3_master_key_32_bytes_long!")).unwrap();
+        let newer = RFIDTag::new("TAG002".to_string(), "EPC-002", b"newer data", &ring).unwrap();
+        assert_eq!(newer.key_id(), 2);
+
+        // Old tags stay readable after both rotations
+        assert_eq!(tag.decrypt_payload("EPC-001", &ring).unwrap(), b"test sample data");
+
+        assert!(reencrypt(&mut tag, "EPC-001", &ring).unwrap());
+        assert_eq!(tag.key_id(), 2);
+        assert_eq!(tag.memory_layout.metadata, metadata);
+        let restored = RFIDTag::from_bytes(&tag.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.decrypt_payload("EPC-001", &ring).unwrap(), b"test sample data");
+        assert!(!reencrypt(&mut tag, "EPC-001", &ring).unwrap());
+
+        // Once re-encrypted, the retired key is no longer needed
+        let current_only = KeyRing::new(2, RFIDEncryption::new_gcm(b"2026Q3_master_key_32_bytes_long!"));
+        assert_eq!(tag.decrypt_payload("EPC-001", &current_only).unwrap(), b"test sample data");
+    }
+
+    #[test]
+    fn test_reencrypt_upgrades_shared_key_tags() {
+        let ring = KeyRing::new(0, RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!"));
+        let encryption = ring.get(0).unwrap();
+        let mut tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"data", encryption).unwrap();
+        tag.memory_layout.header[1] = TAG_VERSION_SHARED_KEY;
+        tag.memory_layout.payload = encryption.encrypt(b"legacy data").unwrap();
+        tag.memory_layout.integrity_hash = encryption.hash(&tag.memory_layout.payload);
+
+        assert!(reencrypt(&mut tag, "TAG001", &ring).unwrap());
+        assert_eq!(tag.memory_layout.header[1], TAG_VERSION_MODE_FLAGS);
+        assert_eq!(tag.decrypt_payload("TAG001", &ring).unwrap(), b"legacy data");
+    }
+
+    #[test]
+    fn test_header_records_cipher_mode() {
+        let cbc = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
+        let gcm = cbc.clone().with_mode(EncryptionMode::Gcm);
+
+        let cbc_tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"cbc data", &cbc).unwrap();
+        let gcm_tag = RFIDTag::new("TAG002".to_string(), "TAG002", b"gcm data", &gcm).unwrap();
+        assert_eq!(cbc_tag.memory_layout.header[2], TAG_FLAG_ENCRYPTED);
+        assert_eq!(gcm_tag.memory_layout.header[2], TAG_FLAG_ENCRYPTED | TAG_FLAG_GCM);
+        assert_eq!(gcm_tag.encryption_mode(), Some(EncryptionMode::Gcm));
+
+        // A CBC configuration reads both kinds of tag; a GCM one reads CBC
+        // tags only while migrating
+        let migrating = gcm.clone().with_cbc_migration(true);
+        for encryption in [&cbc, &migrating] {
+            let restored = RFIDTag::from_bytes(&cbc_tag.to_bytes().unwrap()).unwrap();
+            assert_eq!(restored.decrypt_payload("TAG001", encryption).unwrap(), b"cbc data");
+        }
+        assert!(matches!(cbc_tag.decrypt_payload("TAG001", &gcm), Err(SampleGuardError::EncryptionError(_))));
+        for encryption in [&cbc, &gcm] {
+            let restored = RFIDTag::from_bytes(&gcm_tag.to_bytes().unwrap()).unwrap();
+            assert_eq!(restored.decrypt_payload("TAG002", encryption).unwrap(), b"gcm data");
+        }
+
+        // Tampering is caught even when the reader defaults to CBC
+        let mut tampered = gcm_tag.clone();
+        tampered.memory_layout.payload[15] ^= 0x01;
+        tampered.memory_layout.integrity_hash = cbc.hash(&tampered.memory_layout.payload);
+        assert!(matches!(tampered.decrypt_payload("TAG002", &cbc), Err(SampleGuardError::EncryptionError(_))));
+    }
+
+    #[test]
+    fn test_gcm_reader_rejects_downgraded_tag() {
+        let gcm = RFIDEncryption::new_gcm(b"test_key_32_bytes_long_for_aes256!!");
+        let mut tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"data", &gcm).unwrap();
+
+        // Flipping the unauthenticated mode flag and fixing up the plain
+        // SHA-256 hash leaves the tag looking intact
+        tag.memory_layout.header[2] &= !TAG_FLAG_GCM;
+        tag.memory_layout.integrity_hash = gcm.hash(&tag.memory_layout.payload);
+        assert_eq!(tag.encryption_mode(), Some(EncryptionMode::Cbc));
+        let err = tag.decrypt_payload("TAG001", &gcm).unwrap_err();
+        assert!(err.to_string().contains("requires Gcm"), "{}", err);
+
+        // Likewise for a payload that is valid CBC under the same key
+        let cbc = gcm.clone().with_mode(EncryptionMode::Cbc);
+        tag.memory_layout.payload = cbc.encrypt_for_tag("TAG001", b"forged").unwrap();
+        tag.memory_layout.integrity_hash = cbc.hash(&tag.memory_layout.payload);
+        assert!(tag.decrypt_payload("TAG001", &gcm).is_err());
+        assert_eq!(tag.decrypt_payload("TAG001", &gcm.clone().with_cbc_migration(true)).unwrap(), b"forged");
+    }
+
+    #[test]
+    fn test_tags_without_mode_flags_use_configured_mode() {
+        // As written before the header recorded the mode
+        let gcm = RFIDEncryption::new_gcm(b"test_key_32_bytes_long_for_aes256!!");
+        let mut tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"data", &gcm).unwrap();
+        tag.memory_layout.header[1] = TAG_VERSION_EPC_KEY;
+        tag.memory_layout.header[2] = TAG_FLAG_ENCRYPTED;
+        assert_eq!(tag.encryption_mode(), None);
+
+        let restored = RFIDTag::from_bytes(&tag.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.decrypt_payload("TAG001", &gcm).unwrap(), b"data");
+        let cbc = gcm.clone().with_mode(EncryptionMode::Cbc);
+        assert!(restored.decrypt_payload("TAG001", &cbc).is_err());
+    }
+
+    #[test]
+    fn test_binary_format_is_compact_and_lossless() {
+        let encryption = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-2026-042".to_string(),
+            production_date: chrono::Utc::now(),
+            expiry_date: Some(chrono::Utc::now() + chrono::Duration::days(365)),
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Example Labs".to_string(),
+            product_line: "Diagnostics".to_string(),
+        };
+        let sample = Sample::new("SAMPLE-2026-0001".to_string(), metadata, Some("Freezer 3".to_string()));
+        let payload = serde_json::to_vec(&sample).unwrap();
+        let mut tag = sample.to_tag_with_key(&encryption).unwrap();
+        tag.increment_read_count();
+
+        let json = tag.to_bytes_as(TagFormat::Json).unwrap();
+        let binary = tag.to_bytes_as(TagFormat::Binary).unwrap();
+        assert!(binary.len() * 2 <= json.len(), "binary {} bytes, json {} bytes", binary.len(), json.len());
+
+        // from_bytes recognises either format
+        for bytes in [&binary, &json] {
+            let restored = RFIDTag::from_bytes(bytes).unwrap();
+            assert_eq!(restored.to_bytes_binary().unwrap(), binary);
+            assert_eq!(restored.tag_id, tag.tag_id);
+            assert_eq!(restored.encryption_enabled, tag.encryption_enabled);
+            assert_eq!(restored.memory_layout.header, tag.memory_layout.header);
+            assert_eq!(restored.memory_layout.payload, tag.memory_layout.payload);
+            assert_eq!(restored.memory_layout.integrity_hash, tag.memory_layout.integrity_hash);
+            assert_eq!(restored.memory_layout.metadata, tag.memory_layout.metadata);
+            assert_eq!(restored.decrypt_payload("SAMPLE-2026-0001", &encryption).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn test_malformed_binary_tags_are_rejected() {
+        let encryption = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
+        let tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"data", &encryption).unwrap();
+        let binary = tag.to_bytes_binary().unwrap();
+
+        for len in 0..binary.len() {
+            assert!(matches!(RFIDTag::from_bytes(&binary[..len]), Err(SampleGuardError::TagParseError(_))));
+        }
+        let mut trailing = binary.clone();
+        trailing.push(0);
+        assert!(matches!(RFIDTag::from_bytes(&trailing), Err(SampleGuardError::TagParseError(_))));
+        let mut future = binary.clone();
+        future[4] = 0x7F;
+        assert!(matches!(RFIDTag::from_bytes(&future), Err(SampleGuardError::TagParseError(_))));
+
+        let mut oversized = tag.clone();
+        oversized.memory_layout.payload = vec![0; 70_000];
+        assert!(matches!(oversized.to_bytes_binary(), Err(SampleGuardError::TagParseError(_))));
+    }
+}
@@ -1,7 +1,15 @@
+use crate::database::Database;
 use crate::error::{SampleGuardError, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+
+/// Activation energy used for mean kinetic temperature, in kJ/mol (USP <1079>)
+pub const DEFAULT_ACTIVATION_ENERGY_KJ: f64 = 83.144;
+
+/// Gas constant in J/(mol·K)
+const GAS_CONSTANT: f64 = 8.314_462_618;
 
 /// Temperature reading from a sensor
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +20,20 @@ pub struct TemperatureReading {
     pub location: Option<String>,
 }
 
+/// Time span scoping an export; either end may be open, and both are
+/// inclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateRange {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl DateRange {
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.start.is_none_or(|start| time >= start) && self.end.is_none_or(|end| time <= end)
+    }
+}
+
 /// Temperature violation type
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ViolationType {
@@ -36,6 +58,86 @@ pub enum ViolationSeverity {
     Critical,
 }
 
+/// Notification raised by a [`TemperatureMonitor`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TemperatureAlert {
+    /// A violation worth notifying about
+    Violation(TemperatureViolation),
+    /// Violations of one sensor that were recorded but not notified because
+    /// its alert cooldown was running; raised once the cooldown lapses
+    Suppressed {
+        sensor_id: String,
+        count: usize,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    },
+    /// Sensors read together disagreed by more than the monitor's tolerance
+    SensorDisagreement(SensorDisagreement),
+}
+
+/// Sensors of one monitor, read together, that strayed from the others
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorDisagreement {
+    pub timestamp: DateTime<Utc>,
+    /// Median of all the readings taken together
+    pub median: f32,
+    /// Sensors further than the tolerance from the median
+    pub outliers: Vec<String>,
+}
+
+/// How `TemperatureMonitor::read_temperature` uses several sensors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReadingStrategy {
+    /// Read the first sensor that answers, primary first
+    #[default]
+    Any,
+    /// Read every sensor and record each reading
+    All,
+    /// Read every sensor and record only the median reading
+    Majority,
+}
+
+/// Spread between sensors read together tolerated before they are reported
+/// as disagreeing, in °C
+pub const DEFAULT_DISAGREEMENT_TOLERANCE: f32 = 1.0;
+
+/// Readings the trend and excursion prediction are fitted to unless
+/// configured otherwise
+pub const DEFAULT_TREND_WINDOW: usize = 10;
+
+/// How far ahead a predicted excursion raises a trend alert unless
+/// configured otherwise
+pub const DEFAULT_TREND_HORIZON_MINUTES: i64 = 30;
+
+/// Change per reading, in °C, below which a trend counts as stable
+const STABLE_TREND_RATE: f32 = 0.01;
+
+/// Direction of the recent temperature trend, with its rate in °C per reading
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TrendDirection {
+    Rising(f32),
+    Falling(f32),
+    Stable,
+}
+
+/// Excursion predicted from the recent trend, before it happens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendAlert {
+    /// When the fitted trend crosses the expected range
+    pub predicted_violation_at: DateTime<Utc>,
+    /// °C per reading, negative when falling
+    pub current_rate: f32,
+    pub direction: TrendDirection,
+}
+
+/// Running alert cooldown of one sensor
+#[derive(Debug, Clone)]
+struct AlertCooldown {
+    started: DateTime<Utc>,
+    until: DateTime<Utc>,
+    suppressed: usize,
+}
+
 /// Temperature sensor interface
 pub trait TemperatureSensor: Send + Sync {
     fn read_temperature(&self) -> Result<f32>;
@@ -45,7 +147,9 @@ pub trait TemperatureSensor: Send + Sync {
 /// Mock temperature sensor for testing
 pub struct MockTemperatureSensor {
     sensor_id: String,
-    current_temperature: f32,
+    current_temperature: std::sync::Mutex<f32>,
+    /// Added to the temperature after every read
+    drift: f32,
     readings: VecDeque<f32>,
 }
 
@@ -53,13 +157,21 @@ impl MockTemperatureSensor {
     pub fn new(sensor_id: String, initial_temp: f32) -> Self {
         Self {
             sensor_id,
-            current_temperature: initial_temp,
+            current_temperature: std::sync::Mutex::new(initial_temp),
+            drift: 0.0,
             readings: VecDeque::new(),
         }
     }
 
+    /// Change the temperature by `celsius` after every read, e.g. to
+    /// simulate a failing fridge
+    pub fn with_drift(mut self, celsius: f32) -> Self {
+        self.drift = celsius;
+        self
+    }
+
     pub fn set_temperature(&mut self, temp: f32) {
-        self.current_temperature = temp;
+        *self.current_temperature.get_mut().unwrap_or_else(|e| e.into_inner()) = temp;
         self.readings.push_back(temp);
         if self.readings.len() > 100 {
             self.readings.pop_front();
@@ -69,7 +181,10 @@ impl MockTemperatureSensor {
 
 impl TemperatureSensor for MockTemperatureSensor {
     fn read_temperature(&self) -> Result<f32> {
-        Ok(self.current_temperature)
+        let mut current = self.current_temperature.lock().unwrap_or_else(|e| e.into_inner());
+        let temperature = *current;
+        *current += self.drift;
+        Ok(temperature)
     }
 
     fn get_sensor_id(&self) -> &str {
@@ -80,12 +195,28 @@ impl TemperatureSensor for MockTemperatureSensor {
 
 /// Temperature monitor for sample tracking
 pub struct TemperatureMonitor {
-    sensor: Box<dyn TemperatureSensor>,
+    /// Every probe, by sensor id
+    sensors: HashMap<String, Box<dyn TemperatureSensor>>,
+    /// Sensor `read_temperature` tries first
+    primary_sensor: String,
+    reading_strategy: ReadingStrategy,
+    disagreement_tolerance: f32,
     expected_range: (f32, f32),
     readings: VecDeque<TemperatureReading>,
     violations: VecDeque<TemperatureViolation>,
     max_readings: usize,
     max_violations: usize,
+    alert_cooldown: Option<chrono::Duration>,
+    cooldowns: HashMap<String, AlertCooldown>,
+    alerts: VecDeque<TemperatureAlert>,
+    trend_window: usize,
+    trend_horizon: chrono::Duration,
+    trend_callbacks: Vec<Box<dyn Fn(TrendAlert) + Send>>,
+    /// Whether the current predicted excursion has been reported
+    trend_alert_raised: bool,
+    /// Keeps every recorded reading and violation, beyond `max_readings`
+    /// and across restarts
+    database: Option<Database>,
 }
 
 impl TemperatureMonitor {
@@ -93,44 +224,350 @@ impl TemperatureMonitor {
     pub fn new(
         sensor: Box<dyn TemperatureSensor>,
         expected_range: (f32, f32),
+    ) -> Result<Self> {
+        Self::new_multi(vec![sensor], expected_range)
+    }
+
+    /// Create a monitor over several sensors, the first of which is the
+    /// primary. Sensors sharing an id replace earlier ones.
+    pub fn new_multi(
+        sensors: Vec<Box<dyn TemperatureSensor>>,
+        expected_range: (f32, f32),
     ) -> Result<Self> {
         if expected_range.0 >= expected_range.1 {
             return Err(SampleGuardError::InvalidSampleData(
                 "Invalid temperature range: min must be less than max".to_string()
             ));
         }
+        let Some(primary_sensor) = sensors.first().map(|s| s.get_sensor_id().to_string()) else {
+            return Err(SampleGuardError::InvalidSampleData(
+                "A temperature monitor needs at least one sensor".to_string()
+            ));
+        };
 
         Ok(Self {
-            sensor,
+            sensors: sensors.into_iter().map(|s| (s.get_sensor_id().to_string(), s)).collect(),
+            primary_sensor,
+            reading_strategy: ReadingStrategy::default(),
+            disagreement_tolerance: DEFAULT_DISAGREEMENT_TOLERANCE,
             expected_range,
             readings: VecDeque::new(),
             violations: VecDeque::new(),
             max_readings: 1000,
             max_violations: 100,
+            alert_cooldown: None,
+            cooldowns: HashMap::new(),
+            alerts: VecDeque::new(),
+            trend_window: DEFAULT_TREND_WINDOW,
+            trend_horizon: chrono::Duration::minutes(DEFAULT_TREND_HORIZON_MINUTES),
+            trend_callbacks: Vec::new(),
+            trend_alert_raised: false,
+            database: None,
         })
     }
 
-    /// Read current temperature
+    /// After an alert fires for a sensor, record but do not alert on its
+    /// further warning-level violations for `cooldown`. Critical violations
+    /// always alert.
+    pub fn with_alert_cooldown(mut self, cooldown: chrono::Duration) -> Self {
+        self.alert_cooldown = Some(cooldown);
+        self
+    }
+
+    /// Also monitor `sensor`, e.g. another probe in the same fridge. A
+    /// sensor with the same id as one already held replaces it.
+    pub fn with_sensor(mut self, sensor: Box<dyn TemperatureSensor>) -> Self {
+        self.sensors.insert(sensor.get_sensor_id().to_string(), sensor);
+        self
+    }
+
+    /// Ids of the monitored sensors, sorted
+    pub fn sensor_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.sensors.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// How `read_temperature` uses the sensors; `Any` by default
+    pub fn with_reading_strategy(mut self, strategy: ReadingStrategy) -> Self {
+        self.reading_strategy = strategy;
+        self
+    }
+
+    /// Report sensors read together that stray more than `celsius` from
+    /// their median
+    pub fn with_disagreement_tolerance(mut self, celsius: f32) -> Self {
+        self.disagreement_tolerance = celsius;
+        self
+    }
+
+    pub fn reading_strategy(&self) -> ReadingStrategy {
+        self.reading_strategy
+    }
+
+    /// Also store every recorded reading and violation in `database`,
+    /// usually a connection of its own to the database file
+    pub fn with_database(mut self, database: Database) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Fit the trend and excursion prediction to the last `readings`
+    /// readings, at least 2
+    pub fn with_trend_window(mut self, readings: usize) -> Self {
+        self.trend_window = readings.max(2);
+        self
+    }
+
+    pub fn trend_window(&self) -> usize {
+        self.trend_window
+    }
+
+    /// Raise trend alerts for excursions predicted within `horizon`
+    pub fn with_trend_horizon(mut self, horizon: chrono::Duration) -> Self {
+        self.trend_horizon = horizon;
+        self
+    }
+
+    /// Call `callback` once whenever the trend starts predicting an
+    /// excursion within the trend horizon; it is called again only after
+    /// the prediction has lapsed
+    pub fn on_trend_alert(&mut self, callback: Box<dyn Fn(TrendAlert) + Send>) {
+        self.trend_callbacks.push(callback);
+    }
+
+    /// Read current temperature as the reading strategy says. Under `All`
+    /// the reading furthest outside the range is returned, or the first in
+    /// sensor id order when all are within it. `All` and `Majority` skip
+    /// sensors that fail as long as one answers, and raise a
+    /// `SensorDisagreement` alert when the sensors disagree.
     pub fn read_temperature(&mut self, location: Option<String>) -> Result<TemperatureReading> {
-        let temperature = self.sensor.read_temperature()?;
-        
-        let reading = TemperatureReading {
-            temperature,
+        self.read_temperature_checked(location).map(|(reading, _)| reading)
+    }
+
+    /// Like `read_temperature`, also returning the violation the returned
+    /// reading was recorded with, if it was outside the expected range
+    pub fn read_temperature_checked(
+        &mut self,
+        location: Option<String>,
+    ) -> Result<(TemperatureReading, Option<TemperatureViolation>)> {
+        if self.reading_strategy == ReadingStrategy::Any {
+            return self.read_any(location);
+        }
+
+        let mut readings = self.poll_sensors(location)?;
+        self.check_agreement(&readings);
+        match self.reading_strategy {
+            ReadingStrategy::Majority => {
+                readings.sort_by(|a, b| a.temperature.total_cmp(&b.temperature));
+                let median = readings.swap_remove((readings.len() - 1) / 2);
+                let violation = self.record_reading(median.clone())?;
+                Ok((median, violation))
+            }
+            _ => {
+                let mut violations = readings
+                    .iter()
+                    .map(|reading| self.record_reading(reading.clone()))
+                    .collect::<Result<Vec<_>>>()?;
+                let (min, max) = self.expected_range;
+                let excess = |r: &TemperatureReading| (min - r.temperature).max(r.temperature - max).max(0.0);
+                let worst = readings
+                    .iter()
+                    .enumerate()
+                    .max_by(|(i, a), (j, b)| excess(a).total_cmp(&excess(b)).then(j.cmp(i)))
+                    .map(|(i, _)| i)
+                    .unwrap_or(0);
+                Ok((readings.swap_remove(worst), violations.swap_remove(worst)))
+            }
+        }
+    }
+
+    /// Read every sensor in id order, recording and returning the readings
+    pub fn read_all(&mut self) -> Result<Vec<TemperatureReading>> {
+        let ids: Vec<String> = self.sensor_ids().into_iter().map(str::to_string).collect();
+        let readings = ids.iter().map(|id| self.take_reading(id, None)).collect::<Result<Vec<_>>>()?;
+        self.check_agreement(&readings);
+        for reading in &readings {
+            self.record_reading(reading.clone())?;
+        }
+        Ok(readings)
+    }
+
+    /// Record the reading of the first sensor that answers, primary first
+    fn read_any(&mut self, location: Option<String>) -> Result<(TemperatureReading, Option<TemperatureViolation>)> {
+        let mut ids: Vec<String> = self.sensor_ids().into_iter().map(str::to_string).collect();
+        ids.retain(|id| *id != self.primary_sensor);
+        ids.insert(0, self.primary_sensor.clone());
+
+        let mut last_error = None;
+        for id in &ids {
+            match self.take_reading(id, location.clone()) {
+                Ok(reading) => {
+                    let violation = self.record_reading(reading.clone())?;
+                    return Ok((reading, violation));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| SampleGuardError::InvalidSampleData("No temperature sensors".to_string())))
+    }
+
+    /// Readings of every sensor that answers, in sensor id order, without
+    /// recording them. Fails only if none answers.
+    fn poll_sensors(&self, location: Option<String>) -> Result<Vec<TemperatureReading>> {
+        let mut readings = Vec::new();
+        let mut last_error = None;
+        for id in self.sensor_ids() {
+            match self.take_reading(id, location.clone()) {
+                Ok(reading) => readings.push(reading),
+                Err(e) => {
+                    log::warn!(sensor_id = id; "Temperature sensor failed: {}", e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if readings.is_empty() => Err(e),
+            _ => Ok(readings),
+        }
+    }
+
+    fn take_reading(&self, sensor_id: &str, location: Option<String>) -> Result<TemperatureReading> {
+        let sensor = self.sensors.get(sensor_id).ok_or_else(|| {
+            SampleGuardError::InvalidSampleData(format!("Unknown temperature sensor '{}'", sensor_id))
+        })?;
+        Ok(TemperatureReading {
+            temperature: sensor.read_temperature()?,
             timestamp: Utc::now(),
-            sensor_id: self.sensor.get_sensor_id().to_string(),
+            sensor_id: sensor_id.to_string(),
             location,
-        };
+        })
+    }
+
+    /// Alert on sensors further than the tolerance from the median of
+    /// `readings`, taken together
+    fn check_agreement(&mut self, readings: &[TemperatureReading]) {
+        if readings.len() < 2 {
+            return;
+        }
+        let mut temperatures: Vec<f32> = readings.iter().map(|r| r.temperature).collect();
+        temperatures.sort_by(f32::total_cmp);
+        let median = temperatures[(temperatures.len() - 1) / 2];
+        let outliers: Vec<String> = readings
+            .iter()
+            .filter(|r| (r.temperature - median).abs() > self.disagreement_tolerance)
+            .map(|r| r.sensor_id.clone())
+            .collect();
+        if !outliers.is_empty() {
+            self.push_alert(TemperatureAlert::SensorDisagreement(SensorDisagreement {
+                timestamp: readings[0].timestamp,
+                median,
+                outliers,
+            }));
+        }
+    }
 
+    /// Record an externally obtained reading, checking it for violations.
+    /// Returns the violation the reading caused, if any.
+    pub fn record_reading(&mut self, reading: TemperatureReading) -> Result<Option<TemperatureViolation>> {
         // Check for violations
-        self.check_violation(&reading)?;
+        let violation = self.check_violation(&reading)?;
+        self.raise_alerts(&reading, violation.as_ref());
+        if let Some(database) = &self.database {
+            database.store_temperature_reading(&reading)?;
+            if let Some(violation) = &violation {
+                database.store_temperature_violation(violation)?;
+            }
+        }
 
         // Store reading
-        self.readings.push_back(reading.clone());
+        self.readings.push_back(reading);
         if self.readings.len() > self.max_readings {
             self.readings.pop_front();
         }
+        self.check_trend();
+
+        Ok(violation)
+    }
+
+    /// Direction of the least-squares trend over the last `window`
+    /// readings; `None` with fewer than 2
+    pub fn compute_trend(&self, window: usize) -> Option<TrendDirection> {
+        let recent = self.readings.iter().skip(self.readings.len().saturating_sub(window));
+        let (rate, _) = fit_line(recent.enumerate().map(|(i, r)| (i as f64, r.temperature as f64)))?;
+        let rate = rate as f32;
+        Some(if rate.abs() < STABLE_TREND_RATE {
+            TrendDirection::Stable
+        } else if rate > 0.0 {
+            TrendDirection::Rising(rate)
+        } else {
+            TrendDirection::Falling(rate.abs())
+        })
+    }
+
+    /// Time from the latest reading until the trend over the trend window
+    /// leaves the expected range, by linear regression against time.
+    /// `None` when the trend is stable, heading away from the boundary, or
+    /// already out of range.
+    pub fn predict_violation_in(&self) -> Option<chrono::Duration> {
+        if matches!(self.compute_trend(self.trend_window)?, TrendDirection::Stable) {
+            return None;
+        }
+        let recent: Vec<&TemperatureReading> = self.readings.iter().skip(self.readings.len().saturating_sub(self.trend_window)).collect();
+        let (first, last) = (recent.first()?.timestamp, recent.last()?.timestamp);
+        let seconds = |r: &TemperatureReading| (r.timestamp - first).num_microseconds().unwrap_or(i64::MAX) as f64 / 1e6;
+        let (per_second, intercept) = fit_line(recent.iter().map(|r| (seconds(r), r.temperature as f64)))?;
+
+        let now = (last - first).num_microseconds().unwrap_or(i64::MAX) as f64 / 1e6;
+        let fitted = intercept + per_second * now;
+        let (min, max) = (self.expected_range.0 as f64, self.expected_range.1 as f64);
+        if !(min..=max).contains(&fitted) {
+            return None;
+        }
+        let boundary = match per_second {
+            rate if rate > 0.0 => max,
+            rate if rate < 0.0 => min,
+            _ => return None,
+        };
+        let millis = (boundary - fitted) / per_second * 1000.0;
+        Some(chrono::Duration::milliseconds(millis.min(i64::MAX as f64) as i64))
+    }
+
+    /// The predicted excursion as an alert, if there is one
+    pub fn trend_alert(&self) -> Option<TrendAlert> {
+        let wait = self.predict_violation_in()?;
+        let direction = self.compute_trend(self.trend_window)?;
+        let current_rate = match direction {
+            TrendDirection::Rising(rate) => rate,
+            TrendDirection::Falling(rate) => -rate,
+            TrendDirection::Stable => 0.0,
+        };
+        Some(TrendAlert {
+            predicted_violation_at: self.readings.back()?.timestamp + wait,
+            current_rate,
+            direction,
+        })
+    }
 
-        Ok(reading)
+    /// Call the trend callbacks when an excursion is newly predicted within
+    /// the horizon
+    fn check_trend(&mut self) {
+        if self.trend_callbacks.is_empty() {
+            return;
+        }
+        let alert = self.predict_violation_in()
+            .filter(|wait| *wait <= self.trend_horizon)
+            .and_then(|_| self.trend_alert());
+        match alert {
+            Some(alert) if !self.trend_alert_raised => {
+                self.trend_alert_raised = true;
+                for callback in &self.trend_callbacks {
+                    callback(alert.clone());
+                }
+            }
+            Some(_) => {}
+            None => self.trend_alert_raised = false,
+        }
     }
 
     /// Check if temperature is within expected range
@@ -139,7 +576,7 @@ impl TemperatureMonitor {
     }
 
     /// Check for temperature violations
-    fn check_violation(&mut self, reading: &TemperatureReading) -> Result<()> {
+    fn check_violation(&mut self, reading: &TemperatureReading) -> Result<Option<TemperatureViolation>> {
         let temp = reading.temperature;
         let (min, max) = self.expected_range;
 
@@ -154,7 +591,8 @@ impl TemperatureMonitor {
                     ViolationSeverity::Warning
                 },
             };
-            self.record_violation(violation);
+            self.record_violation(violation.clone());
+            return Ok(Some(violation));
         } else if temp > max {
             let violation = TemperatureViolation {
                 reading: reading.clone(),
@@ -166,10 +604,11 @@ impl TemperatureMonitor {
                     ViolationSeverity::Warning
                 },
             };
-            self.record_violation(violation);
+            self.record_violation(violation.clone());
+            return Ok(Some(violation));
         }
 
-        Ok(())
+        Ok(None)
     }
 
     /// Record a temperature violation
@@ -180,11 +619,72 @@ impl TemperatureMonitor {
         }
     }
 
+    /// Queue the alerts a reading causes, applying the sensor's cooldown.
+    /// The reading's timestamp is the clock, so replayed readings behave as
+    /// they did live.
+    fn raise_alerts(&mut self, reading: &TemperatureReading, violation: Option<&TemperatureViolation>) {
+        let sensor_id = &reading.sensor_id;
+        let now = reading.timestamp;
+
+        if let Some(lapsed) = self.cooldowns.get(sensor_id).filter(|c| now >= c.until).cloned() {
+            self.cooldowns.remove(sensor_id);
+            if lapsed.suppressed > 0 {
+                self.push_alert(TemperatureAlert::Suppressed {
+                    sensor_id: sensor_id.clone(),
+                    count: lapsed.suppressed,
+                    since: lapsed.started,
+                    until: lapsed.until,
+                });
+            }
+        }
+
+        let Some(violation) = violation else {
+            return;
+        };
+        if let Some(cooldown) = self.cooldowns.get_mut(sensor_id) {
+            if violation.severity != ViolationSeverity::Critical {
+                cooldown.suppressed += 1;
+                return;
+            }
+        } else if let Some(length) = self.alert_cooldown {
+            self.cooldowns.insert(
+                sensor_id.clone(),
+                AlertCooldown { started: now, until: now + length, suppressed: 0 },
+            );
+        }
+        self.push_alert(TemperatureAlert::Violation(violation.clone()));
+    }
+
+    fn push_alert(&mut self, alert: TemperatureAlert) {
+        self.alerts.push_back(alert);
+        if self.alerts.len() > self.max_violations {
+            self.alerts.pop_front();
+        }
+    }
+
+    /// Remove and return the alerts raised since the last call, oldest first
+    pub fn take_alerts(&mut self) -> Vec<TemperatureAlert> {
+        self.alerts.drain(..).collect()
+    }
+
+    /// Violations of `sensor_id` suppressed by its running cooldown
+    pub fn suppressed_alerts(&self, sensor_id: &str) -> usize {
+        self.cooldowns.get(sensor_id).map_or(0, |c| c.suppressed)
+    }
+
     /// Get all violations
     pub fn get_violations(&self) -> Vec<&TemperatureViolation> {
         self.violations.iter().collect()
     }
 
+    /// Violations recorded from `sensor_id`'s readings
+    pub fn get_violations_for(&self, sensor_id: &str) -> Vec<&TemperatureViolation> {
+        self.violations
+            .iter()
+            .filter(|v| v.reading.sensor_id == sensor_id)
+            .collect()
+    }
+
     /// Get critical violations only
     pub fn get_critical_violations(&self) -> Vec<&TemperatureViolation> {
         self.violations
@@ -226,23 +726,141 @@ impl TemperatureMonitor {
 
     /// Get temperature statistics
     pub fn get_statistics(&self) -> TemperatureStatistics {
-        let readings: Vec<f32> = self.readings.iter().map(|r| r.temperature).collect();
-        
-        let min = readings.iter().copied().fold(f32::INFINITY, f32::min);
-        let max = readings.iter().copied().fold(f32::NEG_INFINITY, f32::max);
-        let avg = if !readings.is_empty() {
-            readings.iter().sum::<f32>() / readings.len() as f32
-        } else {
-            0.0
-        };
+        let mut stats = statistics_of(self.readings.iter(), self.violations.iter());
+        let (total, longest) = self.excursions();
+        stats.total_excursion_seconds = total.num_seconds();
+        stats.longest_excursion_seconds = longest.num_seconds();
+        stats
+    }
+
+    /// Statistics over the readings and violations of `sensor_id` alone
+    pub fn get_statistics_for(&self, sensor_id: &str) -> TemperatureStatistics {
+        let mut stats = statistics_of(
+            self.readings.iter().filter(|r| r.sensor_id == sensor_id),
+            self.violations.iter().filter(|v| v.reading.sensor_id == sensor_id),
+        );
+        let (total, longest) = self.sensor_excursions(sensor_id);
+        stats.total_excursion_seconds = total.num_seconds();
+        stats.longest_excursion_seconds = longest.num_seconds();
+        stats
+    }
+
+    /// Time the stored readings spent outside the expected range, summed
+    /// over sensors. Each reading is taken to hold until the same sensor's
+    /// next one, so a sensor's newest reading adds nothing.
+    pub fn total_excursion_duration(&self) -> chrono::Duration {
+        self.excursions().0
+    }
+
+    /// Longest unbroken stretch of any one sensor outside the expected range, measured like
+    /// [`total_excursion_duration`](Self::total_excursion_duration)
+    pub fn longest_continuous_excursion(&self) -> chrono::Duration {
+        self.excursions().1
+    }
+
+    /// Total and longest time out of range over all sensors. Readings are
+    /// paired within each sensor, since probes are interleaved.
+    fn excursions(&self) -> (chrono::Duration, chrono::Duration) {
+        let mut ids: Vec<&str> = self.readings.iter().map(|r| r.sensor_id.as_str()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids.into_iter()
+            .map(|id| self.sensor_excursions(id))
+            .fold((chrono::Duration::zero(), chrono::Duration::zero()), |(total, longest), (t, l)| {
+                (total + t, longest.max(l))
+            })
+    }
+
+    /// Total and longest time out of range for one sensor, pairing its
+    /// consecutive readings
+    fn sensor_excursions(&self, sensor_id: &str) -> (chrono::Duration, chrono::Duration) {
+        let mut total = chrono::Duration::zero();
+        let mut longest = chrono::Duration::zero();
+        let mut current = chrono::Duration::zero();
+        let readings: Vec<&TemperatureReading> = self.readings.iter().filter(|r| r.sensor_id == sensor_id).collect();
+        for (reading, next) in readings.iter().zip(readings.iter().skip(1)) {
+            if self.is_within_range(reading.temperature) {
+                current = chrono::Duration::zero();
+                continue;
+            }
+            let span = (next.timestamp - reading.timestamp).max(chrono::Duration::zero());
+            total += span;
+            current += span;
+            longest = longest.max(current);
+        }
+        (total, longest)
+    }
 
-        TemperatureStatistics {
-            total_readings: self.readings.len(),
-            min_temperature: if min.is_finite() { Some(min) } else { None },
-            max_temperature: if max.is_finite() { Some(max) } else { None },
-            average_temperature: if !readings.is_empty() { Some(avg) } else { None },
-            violation_count: self.violations.len(),
-            critical_violation_count: self.get_critical_violations().len(),
+    /// Write the stored readings in `range` to `writer` as CSV, oldest
+    /// first, with RFC 3339 timestamps. `violation_type` is empty for
+    /// readings within range. Returns the number of rows written.
+    pub fn export_readings_csv<W: Write>(&self, writer: W, range: Option<&DateRange>) -> Result<usize> {
+        write_csv(
+            writer,
+            ["timestamp", "sensor_id", "location", "temperature_celsius", "within_range", "violation_type"],
+            self.readings.iter()
+                .filter(|r| range.is_none_or(|range| range.contains(r.timestamp)))
+                .map(|r| {
+                    let violation_type = if r.temperature < self.expected_range.0 {
+                        format!("{:?}", ViolationType::TooLow)
+                    } else if r.temperature > self.expected_range.1 {
+                        format!("{:?}", ViolationType::TooHigh)
+                    } else {
+                        String::new()
+                    };
+                    [
+                        r.timestamp.to_rfc3339(),
+                        r.sensor_id.clone(),
+                        r.location.clone().unwrap_or_default(),
+                        r.temperature.to_string(),
+                        self.is_within_range(r.temperature).to_string(),
+                        violation_type,
+                    ]
+                }),
+        )
+    }
+
+    /// Write the stored violations in `range` to `writer` as CSV, oldest
+    /// first, with RFC 3339 timestamps. Returns the number of rows written.
+    pub fn export_violations_csv<W: Write>(&self, writer: W, range: Option<&DateRange>) -> Result<usize> {
+        write_csv(
+            writer,
+            ["timestamp", "sensor_id", "violation_type", "temperature", "range_min", "range_max", "severity"],
+            self.violations.iter()
+                .filter(|v| range.is_none_or(|range| range.contains(v.reading.timestamp)))
+                .map(|v| [
+                    v.reading.timestamp.to_rfc3339(),
+                    v.reading.sensor_id.clone(),
+                    format!("{:?}", v.violation_type),
+                    v.reading.temperature.to_string(),
+                    v.expected_range.0.to_string(),
+                    v.expected_range.1.to_string(),
+                    format!("{:?}", v.severity),
+                ]),
+        )
+    }
+
+    /// Mean kinetic temperature of the stored readings in °C, weighting
+    /// warm excursions by the Arrhenius equation for an activation energy
+    /// in kJ/mol. `None` without readings.
+    pub fn mean_kinetic_temperature(&self, activation_energy_kj: f64) -> Option<f32> {
+        mean_kinetic_temperature_of(self.readings.iter().map(|r| r.temperature), activation_energy_kj)
+    }
+
+    /// Copy another monitor's readings and violations into this one, in
+    /// timestamp order. Sensor ids and each violation's original expected
+    /// range are kept; the history caps still apply, dropping the oldest.
+    pub fn merge_readings_from(&mut self, other: &TemperatureMonitor) {
+        self.readings.extend(other.readings.iter().cloned());
+        self.readings.make_contiguous().sort_by_key(|r| r.timestamp);
+        while self.readings.len() > self.max_readings {
+            self.readings.pop_front();
+        }
+
+        self.violations.extend(other.violations.iter().cloned());
+        self.violations.make_contiguous().sort_by_key(|v| v.reading.timestamp);
+        while self.violations.len() > self.max_violations {
+            self.violations.pop_front();
         }
     }
 
@@ -262,11 +880,179 @@ impl TemperatureMonitor {
         Ok(())
     }
 
-    /// Clear all readings and violations
+    /// Collapse readings older than `cutoff` into one averaged reading per
+    /// sensor, location and `bucket` window. Returns the number of readings removed.
+    pub fn downsample(&mut self, bucket: chrono::Duration, cutoff: DateTime<Utc>) -> Result<usize> {
+        let bucket_secs = bucket.num_seconds();
+        if bucket_secs <= 0 {
+            return Err(SampleGuardError::InvalidSampleData(
+                "Downsample bucket must be at least one second".to_string()
+            ));
+        }
+
+        let before = self.readings.len();
+        let mut downsampled: Vec<(TemperatureReading, usize)> = Vec::new();
+        let mut recent = VecDeque::new();
+
+        for reading in self.readings.drain(..) {
+            if reading.timestamp >= cutoff {
+                recent.push_back(reading);
+                continue;
+            }
+
+            let bucket_start = reading.timestamp.timestamp().div_euclid(bucket_secs) * bucket_secs;
+            let existing = downsampled.iter_mut().find(|(r, _)| {
+                r.timestamp.timestamp() == bucket_start
+                    && r.sensor_id == reading.sensor_id
+                    && r.location == reading.location
+            });
+
+            match existing {
+                Some((aggregate, count)) => {
+                    aggregate.temperature += reading.temperature;
+                    *count += 1;
+                }
+                None => {
+                    let timestamp = DateTime::from_timestamp(bucket_start, 0).unwrap_or(reading.timestamp);
+                    downsampled.push((TemperatureReading { timestamp, ..reading }, 1));
+                }
+            }
+        }
+
+        self.readings = downsampled
+            .into_iter()
+            .map(|(mut reading, count)| {
+                reading.temperature /= count as f32;
+                reading
+            })
+            .collect();
+        self.readings.extend(recent);
+
+        Ok(before - self.readings.len())
+    }
+
+    /// Clear all readings, violations and pending alerts held in memory;
+    /// those stored in the database are kept
     pub fn clear(&mut self) {
         self.readings.clear();
         self.violations.clear();
+        self.cooldowns.clear();
+        self.alerts.clear();
+        self.trend_alert_raised = false;
+    }
+}
+
+/// Facility-wide statistics over the readings and violations of several monitors
+pub fn aggregate_statistics(monitors: &[&TemperatureMonitor]) -> TemperatureStatistics {
+    let mut stats = statistics_of(
+        monitors.iter().flat_map(|m| m.readings.iter()),
+        monitors.iter().flat_map(|m| m.violations.iter()),
+    );
+    // Excursions are per monitor; pairing readings across zones means nothing
+    for (total, longest) in monitors.iter().map(|m| m.excursions()) {
+        stats.total_excursion_seconds += total.num_seconds();
+        stats.longest_excursion_seconds = stats.longest_excursion_seconds.max(longest.num_seconds());
+    }
+    stats
+}
+
+/// Least-squares slope and intercept of `points`; `None` with fewer than
+/// two distinct x values
+fn fit_line(points: impl Iterator<Item = (f64, f64)>) -> Option<(f64, f64)> {
+    let points: Vec<(f64, f64)> = points.collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if points.len() < 2 || variance == 0.0 {
+        return None;
+    }
+    let slope = covariance / variance;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+/// Write `header` and `rows` to `writer` as CSV, quoting fields where
+/// needed, and return the number of rows
+fn write_csv<W: Write, const N: usize>(writer: W, header: [&str; N], rows: impl Iterator<Item = [String; N]>) -> Result<usize> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(header).map_err(std::io::Error::from)?;
+    let mut count = 0;
+    for record in rows {
+        csv.write_record(&record).map_err(std::io::Error::from)?;
+        count += 1;
+    }
+    csv.flush()?;
+    Ok(count)
+}
+
+fn mean_kinetic_temperature_of(temperatures: impl Iterator<Item = f32>, activation_energy_kj: f64) -> Option<f32> {
+    let ratio = activation_energy_kj * 1000.0 / GAS_CONSTANT;
+    let (sum, count) = temperatures.fold((0.0, 0usize), |(sum, count), t| {
+        (sum + (-ratio / (f64::from(t) + 273.15)).exp(), count + 1)
+    });
+    if count == 0 {
+        return None;
+    }
+    Some((ratio / -(sum / count as f64).ln() - 273.15) as f32)
+}
+
+fn statistics_of<'a>(
+    readings: impl Iterator<Item = &'a TemperatureReading>,
+    violations: impl Iterator<Item = &'a TemperatureViolation>,
+) -> TemperatureStatistics {
+    let mut per_sensor_statistics: HashMap<String, SingleSensorStats> = HashMap::new();
+    let mut sensor_sums: HashMap<&str, f32> = HashMap::new();
+    let readings: Vec<f32> = readings
+        .map(|r| {
+            let stats = per_sensor_statistics.entry(r.sensor_id.clone()).or_default();
+            stats.total_readings += 1;
+            stats.min_temperature = Some(stats.min_temperature.map_or(r.temperature, |t| t.min(r.temperature)));
+            stats.max_temperature = Some(stats.max_temperature.map_or(r.temperature, |t| t.max(r.temperature)));
+            *sensor_sums.entry(&r.sensor_id).or_default() += r.temperature;
+            r.temperature
+        })
+        .collect();
+    for (sensor_id, sum) in sensor_sums {
+        if let Some(stats) = per_sensor_statistics.get_mut(sensor_id) {
+            stats.average_temperature = Some(sum / stats.total_readings as f32);
+        }
     }
+    
+    let min = readings.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = readings.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let avg = if !readings.is_empty() {
+        readings.iter().sum::<f32>() / readings.len() as f32
+    } else {
+        0.0
+    };
+    let (violation_count, critical_violation_count) = violations.fold((0, 0), |(all, critical), v| {
+        per_sensor_statistics.entry(v.reading.sensor_id.clone()).or_default().violation_count += 1;
+        (all + 1, critical + usize::from(v.severity == ViolationSeverity::Critical))
+    });
+
+    TemperatureStatistics {
+        total_readings: readings.len(),
+        min_temperature: if min.is_finite() { Some(min) } else { None },
+        max_temperature: if max.is_finite() { Some(max) } else { None },
+        average_temperature: if !readings.is_empty() { Some(avg) } else { None },
+        mean_kinetic_temperature: mean_kinetic_temperature_of(readings.iter().copied(), DEFAULT_ACTIVATION_ENERGY_KJ),
+        violation_count,
+        critical_violation_count,
+        per_sensor_statistics,
+        total_excursion_seconds: 0,
+        longest_excursion_seconds: 0,
+    }
+}
+
+/// Statistics of one sensor's readings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SingleSensorStats {
+    pub total_readings: usize,
+    pub min_temperature: Option<f32>,
+    pub max_temperature: Option<f32>,
+    pub average_temperature: Option<f32>,
+    pub violation_count: usize,
 }
 
 /// Temperature statistics
@@ -276,8 +1062,20 @@ pub struct TemperatureStatistics {
     pub min_temperature: Option<f32>,
     pub max_temperature: Option<f32>,
     pub average_temperature: Option<f32>,
+    /// At the default activation energy
+    #[serde(default)]
+    pub mean_kinetic_temperature: Option<f32>,
     pub violation_count: usize,
     pub critical_violation_count: usize,
+    /// The same figures for each sensor, by sensor id
+    #[serde(default)]
+    pub per_sensor_statistics: HashMap<String, SingleSensorStats>,
+    /// Seconds spent out of range, summed over monitors when aggregated
+    #[serde(default)]
+    pub total_excursion_seconds: i64,
+    /// Longest unbroken excursion of any one monitor, in seconds
+    #[serde(default)]
+    pub longest_excursion_seconds: i64,
 }
 
 #[cfg(test)]
@@ -327,7 +1125,7 @@ mod tests {
         
         monitor.read_temperature(None).unwrap();
         let violations = monitor.get_violations();
-        assert!(violations.len() > 0);
+        assert!(!violations.is_empty());
         assert_eq!(violations[0].violation_type, ViolationType::TooLow);
     }
 
@@ -338,7 +1136,7 @@ mod tests {
         
         monitor.read_temperature(None).unwrap();
         let violations = monitor.get_violations();
-        assert!(violations.len() > 0);
+        assert!(!violations.is_empty());
         assert_eq!(violations[0].violation_type, ViolationType::TooHigh);
     }
 
@@ -349,7 +1147,7 @@ mod tests {
         
         monitor.read_temperature(None).unwrap();
         let critical = monitor.get_critical_violations();
-        assert!(critical.len() > 0);
+        assert!(!critical.is_empty());
         assert_eq!(critical[0].severity, ViolationSeverity::Critical);
     }
 
@@ -441,5 +1239,457 @@ mod tests {
         let violations = monitor.get_violations();
         assert_eq!(violations.len(), 0);
     }
-}
 
+    #[test]
+    fn test_downsample_old_readings() {
+        let sensor = Box::new(MockTemperatureSensor::new("SENSOR-015".to_string(), 5.0));
+        let mut monitor = TemperatureMonitor::new(sensor, (2.0, 8.0)).unwrap();
+        let base = DateTime::from_timestamp(1_700_000_000 - 1_700_000_000 % 3600, 0).unwrap();
+        
+        for (offset_mins, temp) in [(0, 4.0), (10, 6.0), (70, 5.0), (200, 7.0)] {
+            monitor.record_reading(TemperatureReading {
+                temperature: temp,
+                timestamp: base + chrono::Duration::minutes(offset_mins),
+                sensor_id: "SENSOR-015".to_string(),
+                location: None,
+            }).unwrap();
+        }
+        
+        let removed = monitor
+            .downsample(chrono::Duration::hours(1), base + chrono::Duration::minutes(120))
+            .unwrap();
+        assert_eq!(removed, 1);
+        
+        let readings = monitor.get_all_readings();
+        assert_eq!(readings.len(), 3);
+        assert_eq!(readings[0].timestamp, base);
+        assert!((readings[0].temperature - 5.0).abs() < f32::EPSILON);
+        assert_eq!(readings[2].timestamp, base + chrono::Duration::minutes(200));
+    }
+
+    fn zone_monitor(sensor_id: &str, temps: &[(i64, f32)]) -> TemperatureMonitor {
+        let sensor = Box::new(MockTemperatureSensor::new(sensor_id.to_string(), 5.0));
+        let mut monitor = TemperatureMonitor::new(sensor, (2.0, 8.0)).unwrap();
+        let base = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        for &(offset_mins, temp) in temps {
+            monitor.record_reading(TemperatureReading {
+                temperature: temp,
+                timestamp: base + chrono::Duration::minutes(offset_mins),
+                sensor_id: sensor_id.to_string(),
+                location: Some(format!("Zone {}", sensor_id)),
+            }).unwrap();
+        }
+        monitor
+    }
+
+    #[test]
+    fn test_excursion_durations_pair_consecutive_readings() {
+        // Below range from minute 20 until the reading at 50, above it from 70 to 80
+        let monitor = zone_monitor("EXC", &[
+            (0, 5.0), (10, 5.0), (20, 1.0), (30, 0.0), (40, 1.5), (50, 5.0),
+            (60, 5.0), (70, 9.0), (80, 5.0), (90, 12.0),
+        ]);
+
+        assert_eq!(monitor.total_excursion_duration(), chrono::Duration::minutes(40));
+        assert_eq!(monitor.longest_continuous_excursion(), chrono::Duration::minutes(30));
+        let stats = monitor.get_statistics();
+        assert_eq!(stats.total_excursion_seconds, 40 * 60);
+        assert_eq!(stats.longest_excursion_seconds, 30 * 60);
+
+        let in_range = zone_monitor("OK", &[(0, 3.0), (10, 7.0)]);
+        assert_eq!(in_range.total_excursion_duration(), chrono::Duration::zero());
+        let aggregate = aggregate_statistics(&[&monitor, &in_range]);
+        assert_eq!(aggregate.total_excursion_seconds, 40 * 60);
+        assert_eq!(aggregate.longest_excursion_seconds, 30 * 60);
+    }
+
+    #[test]
+    fn test_read_all_attributes_violations_to_their_sensor() {
+        let top = Box::new(MockTemperatureSensor::new("FRIDGE-TOP".to_string(), 5.0));
+        let bottom = Box::new(MockTemperatureSensor::new("FRIDGE-BOTTOM".to_string(), 11.0));
+        let mut monitor = TemperatureMonitor::new(top, (2.0, 8.0)).unwrap().with_sensor(bottom);
+        assert_eq!(monitor.sensor_ids(), vec!["FRIDGE-BOTTOM", "FRIDGE-TOP"]);
+
+        for _ in 0..3 {
+            let readings = monitor.read_all().unwrap();
+            let ids: Vec<&str> = readings.iter().map(|r| r.sensor_id.as_str()).collect();
+            assert_eq!(ids, vec!["FRIDGE-BOTTOM", "FRIDGE-TOP"]);
+        }
+
+        assert_eq!(monitor.get_violations_for("FRIDGE-BOTTOM").len(), 3);
+        assert!(monitor.get_violations_for("FRIDGE-TOP").is_empty());
+        assert_eq!(monitor.get_violations().len(), 3);
+
+        let bottom_stats = monitor.get_statistics_for("FRIDGE-BOTTOM");
+        assert_eq!((bottom_stats.total_readings, bottom_stats.violation_count), (3, 3));
+        assert_eq!(bottom_stats.max_temperature, Some(11.0));
+        let top_stats = monitor.get_statistics_for("FRIDGE-TOP");
+        assert_eq!((top_stats.total_readings, top_stats.violation_count), (3, 0));
+        assert_eq!(monitor.get_statistics_for("MISSING").total_readings, 0);
+        assert_eq!(monitor.get_statistics().total_readings, 6);
+
+        // The single-sensor API still reads the sensor the monitor was created with
+        assert_eq!(monitor.read_temperature(None).unwrap().sensor_id, "FRIDGE-TOP");
+    }
+
+    /// Sensor that always fails to read
+    struct FailingSensor(String);
+
+    impl TemperatureSensor for FailingSensor {
+        fn read_temperature(&self) -> Result<f32> {
+            Err(SampleGuardError::ReaderError(format!("{} unplugged", self.0)))
+        }
+
+        fn get_sensor_id(&self) -> &str {
+            &self.0
+        }
+    }
+
+    fn probes(temps: &[(&str, f32)]) -> Vec<Box<dyn TemperatureSensor>> {
+        temps
+            .iter()
+            .map(|&(id, temp)| Box::new(MockTemperatureSensor::new(id.to_string(), temp)) as Box<dyn TemperatureSensor>)
+            .collect()
+    }
+
+    fn disagreements(monitor: &mut TemperatureMonitor) -> Vec<SensorDisagreement> {
+        monitor
+            .take_alerts()
+            .into_iter()
+            .filter_map(|alert| match alert {
+                TemperatureAlert::SensorDisagreement(d) => Some(d),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_new_multi_needs_a_sensor() {
+        assert!(TemperatureMonitor::new_multi(Vec::new(), (2.0, 8.0)).is_err());
+        let monitor = TemperatureMonitor::new_multi(probes(&[("A", 5.0), ("B", 5.0)]), (2.0, 8.0)).unwrap();
+        assert_eq!(monitor.sensor_ids(), vec!["A", "B"]);
+        assert_eq!(monitor.reading_strategy(), ReadingStrategy::Any);
+    }
+
+    #[test]
+    fn test_majority_records_the_median_and_flags_the_outlier() {
+        let mut monitor = TemperatureMonitor::new_multi(probes(&[("A", 4.0), ("B", 5.0), ("C", 12.0)]), (2.0, 8.0))
+            .unwrap()
+            .with_reading_strategy(ReadingStrategy::Majority);
+
+        let reading = monitor.read_temperature(None).unwrap();
+        assert_eq!((reading.sensor_id.as_str(), reading.temperature), ("B", 5.0));
+        assert_eq!(monitor.get_all_readings().len(), 1);
+        assert!(monitor.get_violations().is_empty());
+
+        let found = disagreements(&mut monitor);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].median, 5.0);
+        assert_eq!(found[0].outliers, vec!["C".to_string()]);
+    }
+
+    #[test]
+    fn test_all_records_every_reading_and_returns_the_worst() {
+        let mut monitor = TemperatureMonitor::new_multi(probes(&[("A", 4.0), ("B", 5.0), ("C", 12.0)]), (2.0, 8.0))
+            .unwrap()
+            .with_reading_strategy(ReadingStrategy::All);
+
+        let reading = monitor.read_temperature(None).unwrap();
+        assert_eq!(reading.sensor_id, "C");
+        assert_eq!(monitor.get_all_readings().len(), 3);
+        assert_eq!(monitor.get_violations_for("C").len(), 1);
+
+        let stats = monitor.get_statistics();
+        assert_eq!(stats.per_sensor_statistics.len(), 3);
+        assert_eq!(stats.per_sensor_statistics["C"].violation_count, 1);
+        assert_eq!(stats.per_sensor_statistics["A"].violation_count, 0);
+        assert_eq!(stats.per_sensor_statistics["B"].average_temperature, Some(5.0));
+    }
+
+    #[test]
+    fn test_checked_read_returns_the_violation_of_the_returned_reading() {
+        let mut monitor = TemperatureMonitor::new_multi(probes(&[("A", 1.0), ("B", 5.0), ("C", 12.0)]), (2.0, 8.0))
+            .unwrap()
+            .with_reading_strategy(ReadingStrategy::All);
+        let (reading, violation) = monitor.read_temperature_checked(None).unwrap();
+        let violation = violation.unwrap();
+        assert_eq!(reading.sensor_id, "C");
+        assert_eq!((violation.reading.sensor_id.as_str(), violation.violation_type), ("C", ViolationType::TooHigh));
+        assert_eq!(monitor.get_violations().len(), 2);
+
+        let mut monitor = TemperatureMonitor::new_multi(probes(&[("A", 4.0), ("B", 5.0), ("C", 12.0)]), (2.0, 8.0))
+            .unwrap()
+            .with_reading_strategy(ReadingStrategy::Majority);
+        let (reading, violation) = monitor.read_temperature_checked(None).unwrap();
+        assert_eq!(reading.sensor_id, "B");
+        assert!(violation.is_none());
+    }
+
+    #[test]
+    fn test_agreeing_sensors_raise_no_disagreement() {
+        let mut monitor = TemperatureMonitor::new_multi(probes(&[("A", 4.0), ("B", 4.5), ("C", 5.0)]), (2.0, 8.0))
+            .unwrap()
+            .with_reading_strategy(ReadingStrategy::All);
+        monitor.read_temperature(None).unwrap();
+        assert!(disagreements(&mut monitor).is_empty());
+
+        // A tighter tolerance makes the same spread a disagreement
+        let mut strict = TemperatureMonitor::new_multi(probes(&[("A", 4.0), ("B", 4.5), ("C", 5.0)]), (2.0, 8.0))
+            .unwrap()
+            .with_reading_strategy(ReadingStrategy::All)
+            .with_disagreement_tolerance(0.25);
+        strict.read_temperature(None).unwrap();
+        assert_eq!(disagreements(&mut strict)[0].outliers, vec!["A".to_string(), "C".to_string()]);
+    }
+
+    #[test]
+    fn test_failed_sensors_are_skipped() {
+        let mut sensors: Vec<Box<dyn TemperatureSensor>> = vec![Box::new(FailingSensor("PRIMARY".to_string()))];
+        sensors.extend(probes(&[("BACKUP", 6.0)]));
+        let mut monitor = TemperatureMonitor::new_multi(sensors, (2.0, 8.0)).unwrap();
+        assert_eq!(monitor.read_temperature(None).unwrap().sensor_id, "BACKUP");
+
+        let mut monitor = monitor.with_reading_strategy(ReadingStrategy::Majority);
+        assert_eq!(monitor.read_temperature(None).unwrap().sensor_id, "BACKUP");
+
+        let mut dead = TemperatureMonitor::new(Box::new(FailingSensor("DEAD".to_string())), (2.0, 8.0))
+            .unwrap()
+            .with_reading_strategy(ReadingStrategy::All);
+        assert!(dead.read_temperature(None).is_err());
+    }
+
+    #[test]
+    fn test_excursions_pair_readings_per_sensor() {
+        let sensor = Box::new(MockTemperatureSensor::new("A".to_string(), 5.0));
+        let mut monitor = TemperatureMonitor::new(sensor, (2.0, 8.0)).unwrap();
+        let base = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        // B is out of range for 20 minutes; A's in-range readings in between
+        // must not cut B's excursion short
+        for (offset_mins, sensor_id, temp) in [(0, "B", 10.0), (5, "A", 5.0), (10, "B", 10.0), (15, "A", 5.0), (20, "B", 5.0)] {
+            monitor.record_reading(TemperatureReading {
+                temperature: temp,
+                timestamp: base + chrono::Duration::minutes(offset_mins),
+                sensor_id: sensor_id.to_string(),
+                location: None,
+            }).unwrap();
+        }
+
+        assert_eq!(monitor.longest_continuous_excursion(), chrono::Duration::minutes(20));
+        assert_eq!(monitor.get_statistics_for("B").total_excursion_seconds, 20 * 60);
+        assert_eq!(monitor.get_statistics_for("A").total_excursion_seconds, 0);
+    }
+
+    #[test]
+    fn test_csv_exports_quote_locations() {
+        let mut monitor = zone_monitor("CSV", &[(0, 5.0), (10, 9.5), (20, 1.0)]);
+        monitor.record_reading(TemperatureReading {
+            temperature: 4.0,
+            timestamp: DateTime::from_timestamp(1_700_003_600, 0).unwrap(),
+            sensor_id: "CSV".to_string(),
+            location: Some("Cold room 2, shelf \"B\"".to_string()),
+        }).unwrap();
+
+        let mut readings = Vec::new();
+        assert_eq!(monitor.export_readings_csv(&mut readings, None).unwrap(), 4);
+        let readings = String::from_utf8(readings).unwrap();
+        let lines: Vec<&str> = readings.lines().collect();
+        assert_eq!(lines.len(), monitor.get_all_readings().len() + 1);
+        assert_eq!(lines[0], "timestamp,sensor_id,location,temperature_celsius,within_range,violation_type");
+        assert_eq!(lines[1], "2023-11-14T22:13:20+00:00,CSV,Zone CSV,5,true,");
+        assert_eq!(lines[2], "2023-11-14T22:23:20+00:00,CSV,Zone CSV,9.5,false,TooHigh");
+        assert_eq!(lines[4], "2023-11-14T23:13:20+00:00,CSV,\"Cold room 2, shelf \"\"B\"\"\",4,true,");
+
+        let mut violations = Vec::new();
+        assert_eq!(monitor.export_violations_csv(&mut violations, None).unwrap(), 2);
+        let violations = String::from_utf8(violations).unwrap();
+        let lines: Vec<&str> = violations.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "timestamp,sensor_id,violation_type,temperature,range_min,range_max,severity");
+        assert_eq!(lines[1], "2023-11-14T22:23:20+00:00,CSV,TooHigh,9.5,2,8,Warning");
+        assert!(lines[2].ends_with(",CSV,TooLow,1,2,8,Warning"));
+    }
+
+    #[test]
+    fn test_csv_exports_scoped_by_date_range() {
+        let monitor = zone_monitor("CSV", &[(0, 5.0), (10, 9.5), (20, 1.0), (30, 4.0)]);
+        let at = |minutes: i64| DateTime::from_timestamp(1_700_000_000 + minutes * 60, 0).unwrap();
+
+        let range = DateRange { start: Some(at(10)), end: Some(at(20)) };
+        let mut out = Vec::new();
+        assert_eq!(monitor.export_readings_csv(&mut out, Some(&range)).unwrap(), 2);
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 3);
+
+        let from = DateRange { start: Some(at(15)), end: None };
+        let mut out = Vec::new();
+        assert_eq!(monitor.export_violations_csv(&mut out, Some(&from)).unwrap(), 1);
+        assert!(String::from_utf8(out).unwrap().lines().nth(1).unwrap().contains(",TooLow,"));
+
+        let empty = DateRange { start: Some(at(40)), end: None };
+        let mut out = Vec::new();
+        assert_eq!(monitor.export_readings_csv(&mut out, Some(&empty)).unwrap(), 0);
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn test_aggregate_statistics_across_monitors() {
+        let zone_a = zone_monitor("A", &[(0, 4.0), (10, 9.0)]);
+        let zone_b = zone_monitor("B", &[(5, 1.0), (15, 6.0)]);
+
+        let stats = aggregate_statistics(&[&zone_a, &zone_b]);
+        assert_eq!(stats.total_readings, 4);
+        assert_eq!(stats.min_temperature, Some(1.0));
+        assert_eq!(stats.max_temperature, Some(9.0));
+        assert_eq!(stats.average_temperature, Some(5.0));
+        assert_eq!(stats.violation_count, 2);
+        assert_eq!(aggregate_statistics(&[]).total_readings, 0);
+    }
+
+    #[test]
+    fn test_mean_kinetic_temperature_weights_excursions() {
+        let monitor = zone_monitor("MKT", &[(0, 2.0), (10, 8.0), (20, 25.0)]);
+
+        let mkt = monitor.mean_kinetic_temperature(DEFAULT_ACTIVATION_ENERGY_KJ).unwrap();
+        assert!((mkt - 17.02).abs() < 0.01, "MKT was {}", mkt);
+        let stats = monitor.get_statistics();
+        assert!((stats.average_temperature.unwrap() - 11.67).abs() < 0.01);
+        assert_eq!(stats.mean_kinetic_temperature, Some(mkt));
+
+        // A steady temperature is its own MKT
+        let steady = zone_monitor("STEADY", &[(0, 5.0), (10, 5.0)]);
+        assert!((steady.mean_kinetic_temperature(DEFAULT_ACTIVATION_ENERGY_KJ).unwrap() - 5.0).abs() < 1e-3);
+        assert_eq!(zone_monitor("EMPTY", &[]).mean_kinetic_temperature(DEFAULT_ACTIVATION_ENERGY_KJ), None);
+    }
+
+    #[test]
+    fn test_merge_readings_keeps_sensor_attribution() {
+        let mut zone_a = zone_monitor("A", &[(0, 4.0), (10, 9.0)]);
+        let zone_b = zone_monitor("B", &[(5, 1.0), (15, 6.0)]);
+
+        zone_a.merge_readings_from(&zone_b);
+        let sensors: Vec<&str> = zone_a.get_all_readings().iter().map(|r| r.sensor_id.as_str()).collect();
+        assert_eq!(sensors, vec!["A", "B", "A", "B"]);
+        let violations: Vec<&str> = zone_a.get_violations().iter().map(|v| v.reading.sensor_id.as_str()).collect();
+        assert_eq!(violations, vec!["B", "A"]);
+        assert_eq!(zone_a.get_statistics().total_readings, 4);
+    }
+
+    #[test]
+    fn test_alert_cooldown_suppresses_repeat_warnings() {
+        let sensor = Box::new(MockTemperatureSensor::new("COOL".to_string(), 5.0));
+        let mut monitor = TemperatureMonitor::new(sensor, (2.0, 8.0))
+            .unwrap()
+            .with_alert_cooldown(chrono::Duration::minutes(10));
+        let base = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut record = |offset_mins: i64, temperature: f32| {
+            monitor.record_reading(TemperatureReading {
+                temperature,
+                timestamp: base + chrono::Duration::minutes(offset_mins),
+                sensor_id: "COOL".to_string(),
+                location: None,
+            }).unwrap();
+        };
+
+        // Bouncing in and out of range within the cooldown
+        for (offset, temperature) in [(0, 9.0), (1, 5.0), (2, 9.5), (3, 5.0), (4, 9.0)] {
+            record(offset, temperature);
+        }
+        // A critical excursion notifies at once
+        record(5, 20.0);
+        // First reading after the cooldown lapses
+        record(11, 5.0);
+
+        assert_eq!(monitor.get_violations().len(), 4);
+        let alerts = monitor.take_alerts();
+        assert_eq!(alerts.len(), 3, "{:?}", alerts);
+        assert!(matches!(&alerts[0], TemperatureAlert::Violation(v) if v.reading.temperature == 9.0));
+        assert!(matches!(&alerts[1], TemperatureAlert::Violation(v) if v.severity == ViolationSeverity::Critical));
+        assert!(matches!(&alerts[2], TemperatureAlert::Suppressed { count: 2, since, .. } if *since == base));
+        assert!(monitor.take_alerts().is_empty());
+    }
+
+    #[test]
+    fn test_every_violation_alerts_without_cooldown() {
+        let mut monitor = zone_monitor("A", &[(0, 9.0), (1, 9.0), (2, 9.0)]);
+        assert_eq!(monitor.take_alerts().len(), 3);
+        assert_eq!(monitor.suppressed_alerts("A"), 0);
+    }
+
+    /// Monitor over (2, 8) °C fed `temps` one minute apart
+    fn minute_monitor(temps: &[f32]) -> TemperatureMonitor {
+        let sensor = Box::new(MockTemperatureSensor::new("TREND".to_string(), 5.0));
+        let mut monitor = TemperatureMonitor::new(sensor, (2.0, 8.0)).unwrap();
+        feed_minutes(&mut monitor, 0, temps);
+        monitor
+    }
+
+    fn feed_minutes(monitor: &mut TemperatureMonitor, start_minute: i64, temps: &[f32]) {
+        let base = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        for (i, &temperature) in temps.iter().enumerate() {
+            monitor.record_reading(TemperatureReading {
+                temperature,
+                timestamp: base + chrono::Duration::minutes(start_minute + i as i64),
+                sensor_id: "TREND".to_string(),
+                location: None,
+            }).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_compute_trend_with_drifting_sensor() {
+        let sensor = Box::new(MockTemperatureSensor::new("DRIFT".to_string(), 2.0).with_drift(1.0));
+        let mut monitor = TemperatureMonitor::new(sensor, (2.0, 8.0)).unwrap();
+        assert_eq!(monitor.compute_trend(5), None);
+        let temps: Vec<f32> = (0..4).map(|_| monitor.read_temperature(None).unwrap().temperature).collect();
+        assert_eq!(temps, vec![2.0, 3.0, 4.0, 5.0]);
+
+        assert_eq!(monitor.compute_trend(4), Some(TrendDirection::Rising(1.0)));
+        assert!(monitor.predict_violation_in().is_some_and(|wait| wait >= chrono::Duration::zero()));
+        assert_eq!(minute_monitor(&[6.0, 5.5, 5.0]).compute_trend(3), Some(TrendDirection::Falling(0.5)));
+        assert_eq!(minute_monitor(&[5.0, 5.0, 5.0]).compute_trend(3), Some(TrendDirection::Stable));
+    }
+
+    #[test]
+    fn test_predict_violation_by_linear_regression() {
+        // 1 °C a minute from 5 °C reaches 8 °C in three minutes
+        let monitor = minute_monitor(&[2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(monitor.predict_violation_in(), Some(chrono::Duration::minutes(3)));
+        let alert = monitor.trend_alert().unwrap();
+        assert_eq!(alert.direction, TrendDirection::Rising(1.0));
+        assert_eq!(alert.current_rate, 1.0);
+        assert_eq!(alert.predicted_violation_at, monitor.get_recent_readings(1)[0].timestamp + chrono::Duration::minutes(3));
+
+        let falling = minute_monitor(&[6.0, 5.0, 4.0]);
+        assert_eq!(falling.predict_violation_in(), Some(chrono::Duration::minutes(2)));
+        assert_eq!(falling.trend_alert().unwrap().current_rate, -1.0);
+
+        assert_eq!(minute_monitor(&[5.0, 5.0, 5.0]).predict_violation_in(), None);
+        assert_eq!(minute_monitor(&[7.0, 8.0, 9.0]).predict_violation_in(), None);
+        assert_eq!(minute_monitor(&[5.0]).predict_violation_in(), None);
+    }
+
+    #[test]
+    fn test_trend_alert_callback_fires_once_per_prediction() {
+        let sensor = Box::new(MockTemperatureSensor::new("TREND".to_string(), 5.0));
+        let mut monitor = TemperatureMonitor::new(sensor, (2.0, 8.0)).unwrap().with_trend_window(3);
+        let alerts = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = std::sync::Arc::clone(&alerts);
+        monitor.on_trend_alert(Box::new(move |alert| sink.lock().unwrap().push(alert)));
+
+        feed_minutes(&mut monitor, 0, &[3.0, 3.5, 4.0, 4.5]);
+        assert_eq!(alerts.lock().unwrap().len(), 1);
+        assert_eq!(alerts.lock().unwrap()[0].direction, TrendDirection::Rising(0.5));
+
+        // Levelling off lapses the prediction, so the next rise alerts again
+        feed_minutes(&mut monitor, 4, &[4.5, 4.5, 4.5, 5.0, 5.5]);
+        assert_eq!(alerts.lock().unwrap().len(), 2);
+
+        // Nothing is raised for an excursion beyond the horizon
+        let sensor = Box::new(MockTemperatureSensor::new("TREND".to_string(), 5.0));
+        let mut distant = TemperatureMonitor::new(sensor, (2.0, 8.0)).unwrap()
+            .with_trend_horizon(chrono::Duration::minutes(1));
+        let sink = std::sync::Arc::clone(&alerts);
+        distant.on_trend_alert(Box::new(move |alert| sink.lock().unwrap().push(alert)));
+        feed_minutes(&mut distant, 0, &[3.0, 3.5, 4.0]);
+        assert_eq!(alerts.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,277 @@
+//! Proptest strategies and round-trip helpers for property tests.
+//!
+//! Available to the crate's own tests and, for downstream crates, behind the
+//! `testing` feature. The crate's types implement
+//! [`proptest::arbitrary::Arbitrary`], so `any::<Sample>()` works in a
+//! `proptest!` block; values proptest cannot generate for foreign types
+//! (timestamps, awkward text) have strategy functions here.
+
+use crate::database::Database;
+use crate::encryption::RFIDEncryption;
+use crate::error::{SampleGuardError, Result};
+use crate::inventory::TagScanResult;
+use crate::sample::{Sample, SampleMetadata, SampleStatus};
+use crate::tag::RFIDTag;
+use chrono::{DateTime, TimeZone, Utc};
+use proptest::prelude::*;
+use proptest::sample::select;
+
+/// Upper bound on generated string lengths
+const MAX_TEXT_LEN: usize = 24;
+
+/// Characters that have broken encoders before: multi-byte and astral-plane
+/// text, combining marks, bidi controls, quotes, escapes and control codes
+const AWKWARD_CHARS: &[char] = &[
+    'é', 'ß', 'Ω', '中', '🧪', '\u{0301}', '\u{200D}', '\u{202E}', '\u{FEFF}',
+    '"', '\'', '\\', '/', ',', ';', '\n', '\t', '\r', '\0',
+];
+
+/// Printable ASCII with one character in four drawn from [`AWKWARD_CHARS`]
+pub fn awkward_string() -> impl Strategy<Value = String> {
+    let chars = prop_oneof![
+        3 => proptest::char::range(' ', '~'),
+        1 => select(AWKWARD_CHARS),
+    ];
+    proptest::collection::vec(chars, 0..=MAX_TEXT_LEN).prop_map(|chars| chars.into_iter().collect())
+}
+
+/// Counts as SQLite stores them: at most `i64::MAX`, often at either end
+pub fn stored_u64() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        1 => 0..10u64,
+        1 => (0..10u64).prop_map(|d| i64::MAX as u64 - d),
+        2 => 0..=i64::MAX as u64,
+    ]
+}
+
+/// Finite values only: NaN never equals itself, so it cannot round-trip
+pub fn finite_f32() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        1 => select(vec![0.0, -0.0, f32::MIN_POSITIVE, f32::MAX, f32::MIN, f32::EPSILON]),
+        1 => (0..=0x007F_FFFFu32).prop_map(f32::from_bits), // subnormal
+        3 => -300.0f32..300.0,
+    ]
+}
+
+/// Timestamps around now, in years 0000-9999 and anywhere chrono can
+/// represent, including years beyond 9999 and before 0
+pub fn timestamp() -> impl Strategy<Value = DateTime<Utc>> {
+    let now = Utc::now().timestamp();
+    let seconds = prop_oneof![
+        1 => now - 86_400 * 365..now + 86_400 * 365,
+        1 => -62_167_219_200i64..253_402_300_799,
+        2 => DateTime::<Utc>::MIN_UTC.timestamp()..DateTime::<Utc>::MAX_UTC.timestamp(),
+    ];
+    let nanos = prop_oneof![1 => Just(0u32), 2 => 0..1_000_000_000u32];
+    (seconds, nanos).prop_map(|(seconds, nanos)| {
+        Utc.timestamp_opt(seconds, nanos).single().unwrap_or(DateTime::<Utc>::UNIX_EPOCH)
+    })
+}
+
+fn optional<S: Strategy>(strategy: S) -> impl Strategy<Value = Option<S::Value>> {
+    proptest::option::weighted(0.75, strategy)
+}
+
+impl Arbitrary for SampleStatus {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        select(vec![
+            SampleStatus::InProduction,
+            SampleStatus::InTransit,
+            SampleStatus::Stored,
+            SampleStatus::InUse,
+            SampleStatus::Consumed,
+            SampleStatus::Discarded,
+            SampleStatus::Compromised,
+        ])
+        .boxed()
+    }
+}
+
+impl Arbitrary for SampleMetadata {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            awkward_string(),
+            timestamp(),
+            optional(timestamp()),
+            optional((finite_f32(), finite_f32())),
+            awkward_string(),
+            awkward_string(),
+            awkward_string(),
+        )
+            .prop_map(|(batch_number, production_date, expiry_date, temperature_range, storage_conditions, manufacturer, product_line)| {
+                Self {
+                    batch_number,
+                    production_date,
+                    expiry_date,
+                    temperature_range,
+                    storage_conditions,
+                    manufacturer,
+                    product_line,
+                }
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Sample {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            awkward_string(),
+            any::<SampleMetadata>(),
+            optional(awkward_string()),
+            any::<[u8; 16]>(),
+            any::<SampleStatus>(),
+            timestamp(),
+            timestamp(),
+            stored_u64(),
+        )
+            .prop_map(|(sample_id, metadata, location, id, status, created_at, last_updated, read_count)| {
+                let mut sample = Sample::new(sample_id, metadata, location);
+                sample.id = uuid::Uuid::from_bytes(id);
+                sample.status = status;
+                sample.created_at = created_at;
+                sample.last_updated = last_updated;
+                sample.read_count = read_count;
+                // Recompute the checksum over the fields just replaced
+                sample.integrity_checksum =
+                    Sample::calculate_checksum(&sample.sample_id, &sample.metadata, &sample.last_updated);
+                sample
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for TagScanResult {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (awkward_string(), awkward_string(), any::<i16>(), any::<u8>(), timestamp())
+            .prop_map(|(epc, tag_id, rssi, antenna, timestamp)| Self { epc, tag_id, rssi, antenna, timestamp })
+            .boxed()
+    }
+}
+
+/// Inputs to [`RFIDTag::new`]
+#[derive(Debug, Clone)]
+pub struct TagParams {
+    pub tag_id: String,
+    pub payload: Vec<u8>,
+    pub key: Vec<u8>,
+}
+
+impl Arbitrary for TagParams {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let key = select(vec![0usize, 1, 16, 32, 33, 64])
+            .prop_flat_map(|len| proptest::collection::vec(any::<u8>(), len));
+        (awkward_string(), proptest::collection::vec(any::<u8>(), 0..MAX_TEXT_LEN * 8), key)
+            .prop_map(|(tag_id, payload, key)| Self { tag_id, payload, key })
+            .boxed()
+    }
+}
+
+/// Encode `sample` onto a tag with `key`, serialize the tag to bytes and
+/// decode it back
+pub fn roundtrip_sample_via_tag(sample: &Sample, key: &[u8]) -> Result<Sample> {
+    let encryption = RFIDEncryption::new(key);
+    let bytes = sample.to_tag_with_key(&encryption)?.to_bytes()?;
+    Sample::from_tag_with_key(&RFIDTag::from_bytes(&bytes)?, &encryption)
+}
+
+/// Store `sample` and read it back
+pub fn roundtrip_sample_via_db(db: &Database, sample: &Sample) -> Result<Sample> {
+    db.store_sample(sample)?;
+    db.get_sample(&sample.sample_id)?
+        .ok_or_else(|| SampleGuardError::InvalidSampleData(format!("Sample {:?} was not stored", sample.sample_id)))
+}
+
+/// Compare every field of two samples, describing the first difference
+pub fn compare_samples(expected: &Sample, actual: &Sample) -> std::result::Result<(), String> {
+    macro_rules! same {
+        ($($field:ident).+) => {
+            if expected.$($field).+ != actual.$($field).+ {
+                return Err(format!(
+                    "{} differs: expected {:?}, got {:?}",
+                    stringify!($($field).+), expected.$($field).+, actual.$($field).+
+                ));
+            }
+        };
+    }
+    same!(id);
+    same!(sample_id);
+    same!(status);
+    same!(metadata.batch_number);
+    same!(metadata.production_date);
+    same!(metadata.expiry_date);
+    same!(metadata.temperature_range);
+    same!(metadata.storage_conditions);
+    same!(metadata.manufacturer);
+    same!(metadata.product_line);
+    same!(created_at);
+    same!(last_updated);
+    same!(read_count);
+    same!(location);
+    same!(integrity_checksum);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::test_runner::TestCaseError;
+
+    fn fail(e: impl ToString) -> TestCaseError {
+        TestCaseError::fail(e.to_string())
+    }
+
+    proptest! {
+        #[test]
+        fn test_sample_roundtrips_through_tag(sample in any::<Sample>()) {
+            let decoded = roundtrip_sample_via_tag(&sample, b"property_test_key_32_bytes_long!").map_err(fail)?;
+            compare_samples(&sample, &decoded).map_err(fail)?;
+        }
+
+        #[test]
+        fn test_tag_bytes_roundtrip(params in any::<TagParams>()) {
+            let encryption = RFIDEncryption::new(&params.key);
+            let tag = RFIDTag::new(params.tag_id.clone(), &params.tag_id, &params.payload, &encryption).map_err(fail)?;
+            let decoded = RFIDTag::from_bytes(&tag.to_bytes().map_err(fail)?).map_err(fail)?;
+            prop_assert_eq!(&decoded.tag_id, &params.tag_id);
+            let payload = decoded.decrypt_payload(&params.tag_id, &encryption).map_err(fail)?;
+            prop_assert_eq!(payload, params.payload);
+        }
+
+        #[test]
+        fn test_scan_result_json_roundtrip(scan in any::<TagScanResult>()) {
+            let json = serde_json::to_string(&scan).map_err(fail)?;
+            let back: TagScanResult = serde_json::from_str(&json).map_err(fail)?;
+            prop_assert_eq!(
+                (&back.epc, &back.tag_id, back.rssi, back.antenna, back.timestamp),
+                (&scan.epc, &scan.tag_id, scan.rssi, scan.antenna, scan.timestamp)
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_roundtrips_through_database() {
+        // One connection for every case, so each removes its row again
+        let db = Database::in_memory().unwrap();
+        proptest!(|(sample in any::<Sample>())| {
+            let stored = roundtrip_sample_via_db(&db, &sample);
+            db.delete_sample(&sample.sample_id).map_err(fail)?;
+            compare_samples(&sample, &stored.map_err(fail)?).map_err(fail)?;
+        });
+    }
+}
@@ -0,0 +1,164 @@
+use crate::error::{SampleGuardError, Result};
+use crate::notifications::{Notification, NotificationDispatcher};
+use crate::sample::{Sample, SampleStatus};
+use std::sync::Arc;
+
+/// Reacts to sample status changes, e.g. to start a shipment when a sample
+/// goes `InTransit`.
+///
+/// Hooks run after the change is stored, while the database is still
+/// borrowed, so they must not call back into it. Errors are logged and do
+/// not undo the change.
+pub trait TransitionHook: Send + Sync {
+    /// Name used when logging hook failures
+    fn name(&self) -> &str;
+
+    fn on_transition(&self, sample: &Sample, from: SampleStatus, to: SampleStatus) -> Result<()>;
+}
+
+/// Registered hooks, run in registration order
+#[derive(Clone, Default)]
+pub struct TransitionHooks {
+    hooks: Vec<Arc<dyn TransitionHook>>,
+}
+
+impl TransitionHooks {
+    pub fn register(&mut self, hook: Arc<dyn TransitionHook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Run every hook, logging the ones that fail
+    pub fn notify(&self, sample: &Sample, from: SampleStatus, to: SampleStatus) {
+        for hook in &self.hooks {
+            if let Err(e) = hook.on_transition(sample, from, to) {
+                log::warn!(
+                    sample_id = sample.sample_id.as_str(), hook = hook.name();
+                    "Transition hook {} failed for {:?} -> {:?}: {}", hook.name(), from, to, e
+                );
+            }
+        }
+    }
+}
+
+/// Name of the notification sent for a status change
+pub const STATUS_CHANGED_EVENT: &str = "sample.status_changed";
+
+/// Pushes every status change to the notification sinks.
+///
+/// Delivery happens on a background thread so slow endpoints never hold up
+/// the change; delivery failures are logged there.
+pub struct NotificationHook {
+    dispatcher: NotificationDispatcher,
+}
+
+impl NotificationHook {
+    pub fn new(dispatcher: NotificationDispatcher) -> Self {
+        Self { dispatcher }
+    }
+
+    /// Notification describing a change from `from` to `to`
+    pub fn notification(sample: &Sample, from: SampleStatus, to: SampleStatus) -> Notification {
+        Notification::new(
+            STATUS_CHANGED_EVENT,
+            Some(sample.sample_id.clone()),
+            serde_json::json!({
+                "from": from,
+                "to": to,
+                "location": sample.location,
+                "batch_number": sample.metadata.batch_number,
+            }),
+        )
+    }
+}
+
+impl TransitionHook for NotificationHook {
+    fn name(&self) -> &str {
+        "notification"
+    }
+
+    fn on_transition(&self, sample: &Sample, from: SampleStatus, to: SampleStatus) -> Result<()> {
+        let notification = Self::notification(sample, from, to);
+        let dispatcher = self.dispatcher.clone();
+        std::thread::Builder::new()
+            .name("transition-notify".to_string())
+            .spawn(move || {
+                for result in dispatcher.dispatch(&notification) {
+                    if let Err(e) = result {
+                        log::warn!("Status change notification for {:?} failed: {}", notification.sample_id, e);
+                    }
+                }
+            })
+            .map(|_| ())
+            .map_err(SampleGuardError::IoError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::NotificationSink;
+    use crate::sample::SampleMetadata;
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    struct ChannelSink(Mutex<mpsc::Sender<Notification>>);
+
+    impl NotificationSink for ChannelSink {
+        fn send(&self, notification: &Notification) -> Result<()> {
+            let _ = self.0.lock().unwrap().send(notification.clone());
+            Ok(())
+        }
+    }
+
+    struct FailingHook;
+
+    impl TransitionHook for FailingHook {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn on_transition(&self, _sample: &Sample, _from: SampleStatus, _to: SampleStatus) -> Result<()> {
+            Err(SampleGuardError::InvalidSampleData("hook failure".to_string()))
+        }
+    }
+
+    fn sample() -> Sample {
+        let metadata = SampleMetadata {
+            batch_number: "BATCH-HOOK".to_string(),
+            production_date: chrono::Utc::now(),
+            expiry_date: None,
+            temperature_range: None,
+            storage_conditions: "Frozen".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+        };
+        Sample::new("HOOK-001".to_string(), metadata, None)
+    }
+
+    #[test]
+    fn test_notification_hook_sends_status_change() {
+        let (tx, rx) = mpsc::channel();
+        let dispatcher = NotificationDispatcher::new().with_sink(Arc::new(ChannelSink(Mutex::new(tx))));
+        let mut hooks = TransitionHooks::default();
+        hooks.register(Arc::new(FailingHook));
+        hooks.register(Arc::new(NotificationHook::new(dispatcher)));
+
+        hooks.notify(&sample(), SampleStatus::Stored, SampleStatus::InTransit);
+
+        // A failing hook does not stop the ones after it
+        let notification = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(notification.event, STATUS_CHANGED_EVENT);
+        assert_eq!(notification.sample_id.as_deref(), Some("HOOK-001"));
+        assert_eq!(notification.details["from"], "Stored");
+        assert_eq!(notification.details["to"], "InTransit");
+    }
+}
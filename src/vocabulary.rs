@@ -0,0 +1,135 @@
+use crate::error::{SampleGuardError, Result};
+
+/// Largest edit distance still treated as a misspelling of a term
+const MAX_TYPO_DISTANCE: usize = 2;
+
+/// Controlled vocabulary for `storage_conditions`.
+///
+/// Values are accepted when they match a term ignoring case, spacing and
+/// punctuation, when their first word is a term ("refrigerated 2-8"), or
+/// when they are a near misspelling of exactly one term. Accepted values
+/// are rewritten to the canonical term.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageVocabulary {
+    terms: Vec<String>,
+}
+
+impl StorageVocabulary {
+    pub fn new<I, S>(terms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            terms: terms.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Vocabulary for configured `terms`; `None` keeps free-text mode
+    pub fn from_terms(terms: &[String]) -> Option<Self> {
+        (!terms.is_empty()).then(|| Self::new(terms.iter().cloned()))
+    }
+
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    /// Canonical term for `value`, or an error listing the allowed terms
+    pub fn normalize(&self, value: &str) -> Result<String> {
+        self.find(value).map(str::to_string).ok_or_else(|| {
+            SampleGuardError::InvalidSampleData(format!(
+                "Unknown storage condition '{}'; allowed terms: {}",
+                value,
+                self.terms.join(", ")
+            ))
+        })
+    }
+
+    fn find(&self, value: &str) -> Option<&str> {
+        let key = term_key(value);
+        if key.is_empty() {
+            return None;
+        }
+        if let Some(term) = self.terms.iter().find(|t| term_key(t) == key) {
+            return Some(term);
+        }
+
+        let first_word = value
+            .split(|c: char| !c.is_alphanumeric())
+            .find(|w| !w.is_empty())
+            .map(term_key)
+            .unwrap_or_default();
+        if let Some(term) = self.terms.iter().find(|t| term_key(t) == first_word) {
+            return Some(term);
+        }
+
+        // Only short edits of reasonably long words, and only when unambiguous
+        if key.chars().count() <= MAX_TYPO_DISTANCE * 2 {
+            return None;
+        }
+        let mut close = self
+            .terms
+            .iter()
+            .filter(|t| edit_distance(&term_key(t), &key) <= MAX_TYPO_DISTANCE);
+        match (close.next(), close.next()) {
+            (Some(term), None) => Some(term),
+            _ => None,
+        }
+    }
+}
+
+/// Lowercased alphanumeric characters of `value`
+fn term_key(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocabulary() -> StorageVocabulary {
+        StorageVocabulary::new(["Refrigerated", "Frozen", "Ambient", "Controlled"])
+    }
+
+    #[test]
+    fn test_close_matches_normalize_to_term() {
+        let vocabulary = vocabulary();
+        assert_eq!(vocabulary.normalize("Refrigerated").unwrap(), "Refrigerated");
+        assert_eq!(vocabulary.normalize("  FROZEN ").unwrap(), "Frozen");
+        assert_eq!(vocabulary.normalize("refrigerated 2-8").unwrap(), "Refrigerated");
+        assert_eq!(vocabulary.normalize("Refridgerated").unwrap(), "Refrigerated");
+    }
+
+    #[test]
+    fn test_unknown_value_lists_allowed_terms() {
+        let err = vocabulary().normalize("Fridge").unwrap_err().to_string();
+        assert!(err.contains("'Fridge'"));
+        assert!(err.contains("Refrigerated, Frozen, Ambient, Controlled"));
+        assert!(vocabulary().normalize("").is_err());
+    }
+
+    #[test]
+    fn test_empty_configuration_keeps_free_text() {
+        assert!(StorageVocabulary::from_terms(&[]).is_none());
+        assert!(StorageVocabulary::from_terms(&["Frozen".to_string()]).is_some());
+    }
+}
@@ -150,8 +150,168 @@ async fn test_get_all_samples() {
     let resp = test::call_service(&app, req).await;
     assert!(resp.status().is_success());
     
-    let body: Vec<SampleResponse> = test::read_body_json(resp).await;
-    assert!(body.len() >= 3);
+    let body: SamplePageResponse = test::read_body_json(resp).await;
+    assert!(body.items.len() >= 3);
+    assert_eq!(body.total, body.items.len());
+    assert!(!body.has_more);
+}
+
+#[actix_web::test]
+async fn test_get_samples_paged() {
+    let mut config = sample_guard::SampleGuardConfig::default();
+    config.server.max_page_size = 3;
+    let app_state = sample_guard::api::create_app_state_from_config(&config).unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for i in 0..5 {
+        let create_req = CreateSampleRequest {
+            sample_id: format!("API-PAGE-{}", i),
+            batch_number: "BATCH-PAGE".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        };
+        let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+    }
+    
+    let get_page = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+    
+    let body: SamplePageResponse = test::read_body_json(
+        test::call_service(&app, get_page("/api/v1/samples?page=2&per_page=2&sort=sample_id")).await
+    ).await;
+    let ids: Vec<_> = body.items.iter().map(|s| s.sample_id.as_str()).collect();
+    assert_eq!(ids, vec!["API-PAGE-2", "API-PAGE-3"]);
+    assert_eq!(body.total, 5);
+    assert!(body.has_more);
+    
+    // per_page is capped by the server
+    let body: SamplePageResponse = test::read_body_json(
+        test::call_service(&app, get_page("/api/v1/samples?per_page=500")).await
+    ).await;
+    assert_eq!((body.items.len(), body.per_page), (3, 3));
+    
+    // Out-of-range pages are empty rather than errors
+    let resp = test::call_service(&app, get_page("/api/v1/samples?page=40")).await;
+    assert_eq!(resp.status(), 200);
+    let body: SamplePageResponse = test::read_body_json(resp).await;
+    assert!(body.items.is_empty() && !body.has_more);
+    
+    let body: SamplePageResponse = test::read_body_json(
+        test::call_service(&app, get_page("/api/v1/samples/batch/BATCH-PAGE?page=2&per_page=3")).await
+    ).await;
+    assert_eq!(body.items.len(), 2);
+    
+    assert_eq!(test::call_service(&app, get_page("/api/v1/samples?page=0")).await.status(), 400);
+    assert_eq!(test::call_service(&app, get_page("/api/v1/samples?sort=colour")).await.status(), 400);
+    assert_eq!(test::call_service(&app, get_page("/api/v1/samples?per_page=501")).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_get_samples_numbered_pages() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for i in 0..5 {
+        let create_req = CreateSampleRequest {
+            sample_id: format!("API-NUMBERED-{}", i),
+            batch_number: format!("BATCH-NUMBERED-{}", 4 - i),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        };
+        let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+    }
+    
+    let get_page = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+    
+    // Last page holds the remainder
+    let resp = test::call_service(&app, get_page("/api/v1/samples?page=3&page_size=2&order_by=batch_number")).await;
+    assert_eq!(resp.status(), 200);
+    let body: SamplePageResponse = test::read_body_json(resp).await;
+    let ids: Vec<_> = body.items.iter().map(|s| s.sample_id.as_str()).collect();
+    assert_eq!(ids, vec!["API-NUMBERED-0"]);
+    assert_eq!((body.total, body.page, body.per_page, body.total_pages), (5, 3, 2, 3));
+    assert!(!body.has_more);
+    
+    let body: SamplePageResponse = test::read_body_json(
+        test::call_service(&app, get_page("/api/v1/samples?page=4&page_size=2")).await
+    ).await;
+    assert!(body.items.is_empty());
+    assert_eq!((body.page, body.total_pages), (4, 3));
+    
+    let resp = test::call_service(&app, get_page("/api/v1/samples?page_size=500")).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(test::call_service(&app, get_page("/api/v1/samples?page_size=501")).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_get_samples_limit_offset() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for i in 0..4 {
+        let create_req = CreateSampleRequest {
+            sample_id: format!("API-OFFSET-{}", i),
+            batch_number: "BATCH-OFFSET".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        };
+        let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+    }
+    
+    let get_page = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+    
+    let resp = test::call_service(&app, get_page("/api/v1/samples?limit=2&offset=1&sort=sample_id")).await;
+    assert_eq!(resp.status(), 200);
+    let body: SamplePageResponse = test::read_body_json(resp).await;
+    let ids: Vec<_> = body.items.iter().map(|s| s.sample_id.as_str()).collect();
+    assert_eq!(ids, vec!["API-OFFSET-1", "API-OFFSET-2"]);
+    assert_eq!((body.total, body.offset, body.per_page), (4, 1, 2));
+    assert!(body.has_more);
+    
+    let body: SamplePageResponse = test::read_body_json(
+        test::call_service(&app, get_page("/api/v1/samples?limit=2&offset=4")).await
+    ).await;
+    assert!(body.items.is_empty() && !body.has_more);
+    assert_eq!(body.total, 4);
+    
+    // Defaults to the first 50
+    let body: SamplePageResponse = test::read_body_json(
+        test::call_service(&app, get_page("/api/v1/samples")).await
+    ).await;
+    assert_eq!((body.items.len(), body.offset, body.per_page), (4, 0, 50));
+    
+    assert_eq!(test::call_service(&app, get_page("/api/v1/samples?limit=0")).await.status(), 400);
+    assert_eq!(test::call_service(&app, get_page("/api/v1/samples?page=2&offset=1")).await.status(), 400);
+    assert_eq!(test::call_service(&app, get_page("/api/v1/samples?per_page=2&limit=2")).await.status(), 400);
 }
 
 #[actix_web::test]
@@ -186,6 +346,7 @@ async fn test_update_sample_status() {
     let update_req = UpdateSampleStatusRequest {
         status: "InTransit".to_string(),
         location: Some("New Location".to_string()),
+        user_id: Some("courier-7".to_string()),
     };
     
     let req = test::TestRequest::put()
@@ -199,6 +360,18 @@ async fn test_update_sample_status() {
     let body: SampleResponse = test::read_body_json(resp).await;
     assert_eq!(body.status, "InTransit");
     assert_eq!(body.location, Some("New Location".to_string()));
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/API-TEST-003/movements").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let movements: Vec<sample_guard::Movement> = test::read_body_json(resp).await;
+    assert_eq!(movements.len(), 2);
+    assert_eq!(movements[1].from_location, movements[0].to_location);
+    assert_eq!(movements[1].to_location.as_deref(), Some("New Location"));
+    assert_eq!(movements[1].user_id.as_deref(), Some("courier-7"));
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/MISSING/movements").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
 }
 
 #[actix_web::test]
@@ -284,8 +457,172 @@ async fn test_get_samples_by_batch() {
     let resp = test::call_service(&app, req).await;
     assert!(resp.status().is_success());
     
+    let body: SamplePageResponse = test::read_body_json(resp).await;
+    assert!(body.items.len() >= 2);
+}
+
+#[actix_web::test]
+async fn test_get_expiring_and_expired_samples() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for (sample_id, expires_in_days) in [("API-EXP-PAST", -2), ("API-EXP-SOON", 5), ("API-EXP-LATER", 60)] {
+        let create_req = CreateSampleRequest {
+            sample_id: sample_id.to_string(),
+            batch_number: "BATCH-EXPIRY".to_string(),
+            production_date: Utc::now() - chrono::Duration::days(90),
+            expiry_date: Some(Utc::now() + chrono::Duration::days(expires_in_days)),
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        };
+        let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+    }
+    
+    let ids = |body: Vec<SampleResponse>| body.into_iter().map(|s| s.sample_id).collect::<Vec<_>>();
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/expiring?days=30").to_request();
+    let body: Vec<SampleResponse> = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(ids(body), vec!["API-EXP-SOON"]);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/expiring?days=90").to_request();
+    let body: Vec<SampleResponse> = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(ids(body), vec!["API-EXP-SOON", "API-EXP-LATER"]);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/expired").to_request();
+    let body: Vec<SampleResponse> = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(ids(body), vec!["API-EXP-PAST"]);
+    
+    let before = (Utc::now() + chrono::Duration::days(30)).format("%Y-%m-%d");
+    let req = test::TestRequest::get().uri(&format!("/api/v1/samples/expiring?before={}", before)).to_request();
+    let body: Vec<SampleResponse> = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(ids(body), vec!["API-EXP-PAST", "API-EXP-SOON"]);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/expiring?before=soon").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+    let req = test::TestRequest::get().uri(&format!("/api/v1/samples/expiring?days=5&before={}", before)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_search_samples() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for (sample_id, manufacturer, expires_in_days) in [
+        ("API-SEARCH-001", "PharmaCorp", 10),
+        ("API-SEARCH-002", "PharmaCorp", 400),
+        ("API-SEARCH-003", "BioLabs", 10),
+    ] {
+        let create_req = CreateSampleRequest {
+            sample_id: sample_id.to_string(),
+            batch_number: "BATCH-SEARCH".to_string(),
+            production_date: Utc::now(),
+            expiry_date: Some(Utc::now() + chrono::Duration::days(expires_in_days)),
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: manufacturer.to_string(),
+            product_line: "Test".to_string(),
+            location: Some("Freezer 1".to_string()),
+        };
+        let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+    }
+    
+    let ids = |body: Vec<SampleResponse>| body.into_iter().map(|s| s.sample_id).collect::<Vec<_>>();
+    
+    let cutoff = (Utc::now() + chrono::Duration::days(30)).format("%Y-%m-%d");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/samples/search?manufacturer=PharmaCorp&expiring_before={}", cutoff))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Vec<SampleResponse> = test::read_body_json(resp).await;
+    assert_eq!(ids(body), vec!["API-SEARCH-001"]);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/search?id_contains=search-00&location_contains=freezer").to_request();
+    let body: Vec<SampleResponse> = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(body.len(), 3);
+    
+    // '; DROP TABLE samples;--
+    let req = test::TestRequest::get()
+        .uri("/api/v1/samples/search?manufacturer=%27%3B%20DROP%20TABLE%20samples%3B--")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Vec<SampleResponse> = test::read_body_json(resp).await;
+    assert!(body.is_empty());
+    let req = test::TestRequest::get().uri("/api/v1/samples/search").to_request();
+    let body: Vec<SampleResponse> = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(body.len(), 3);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/search?expiring_before=next-week").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_search_samples_by_body() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for (sample_id, batch_number, manufacturer) in [
+        ("API-FILTER-001", "BATCH-FILTER-A", "PharmaCorp"),
+        ("API-FILTER-002", "BATCH-FILTER-A", "BioLabs"),
+        ("API-FILTER-003", "BATCH-FILTER-B", "PharmaCorp"),
+    ] {
+        let create_req = CreateSampleRequest {
+            sample_id: sample_id.to_string(),
+            batch_number: batch_number.to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: manufacturer.to_string(),
+            product_line: "Test".to_string(),
+            location: Some("Freezer 1".to_string()),
+        };
+        let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+    }
+    
+    let search = |body: serde_json::Value| test::TestRequest::post().uri("/api/v1/samples/search").set_json(body).to_request();
+    
+    let resp = test::call_service(&app, search(serde_json::json!({
+        "batch_number": "BATCH-FILTER-A",
+        "status": "InProduction",
+        "manufacturer": "pharmacorp",
+        "created_after": (Utc::now() - chrono::Duration::hours(1)).to_rfc3339(),
+    }))).await;
+    assert_eq!(resp.status(), 200);
     let body: Vec<SampleResponse> = test::read_body_json(resp).await;
-    assert!(body.len() >= 2);
+    let ids: Vec<_> = body.iter().map(|s| s.sample_id.as_str()).collect();
+    assert_eq!(ids, vec!["API-FILTER-001"]);
+    
+    let resp = test::call_service(&app, search(serde_json::json!({
+        "batch_number": "BATCH-FILTER-A' OR '1'='1",
+        "manufacturer": "PharmaCorp",
+        "location_contains": "Freezer",
+    }))).await;
+    let body: Vec<SampleResponse> = test::read_body_json(resp).await;
+    assert!(body.is_empty());
+    
+    let resp = test::call_service(&app, search(serde_json::json!({ "status": "Misplaced" }))).await;
+    assert_eq!(resp.status(), 400);
 }
 
 #[actix_web::test]
@@ -308,6 +645,27 @@ async fn test_scan_inventory() {
     // count is always non-negative (usize)
 }
 
+#[actix_web::test]
+async fn test_scan_inventory_with_max_results() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/inventory/scan?max_results=5")
+        .to_request();
+    
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    
+    let body: InventoryScanResponse = test::read_body_json(resp).await;
+    assert!(body.count <= 5);
+    assert_eq!(body.truncated, body.total_seen > body.count);
+}
+
 #[actix_web::test]
 async fn test_get_inventory_report() {
     let app_state = create_app_state();
@@ -362,6 +720,45 @@ async fn test_get_temperature_statistics() {
     assert!(resp.status().is_success());
 }
 
+#[actix_web::test]
+async fn test_export_temperature_csv() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for _ in 0..3 {
+        let req = test::TestRequest::post().uri("/api/v1/temperature/read?location=Cold%20room,%20bay%201").to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+    }
+    
+    let req = test::TestRequest::get().uri("/api/v1/temperature/export.csv").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("content-type").unwrap().to_str().unwrap().starts_with("text/csv"));
+    let body = test::read_body(resp).await;
+    let csv = std::str::from_utf8(&body).unwrap();
+    assert_eq!(csv.lines().count(), 4);
+    assert!(csv.lines().nth(1).unwrap().contains(",\"Cold room, bay 1\","));
+    
+    let req = test::TestRequest::get().uri("/api/v1/temperature/export.csv?violations=true").to_request();
+    let body = test::read_body(test::call_service(&app, req).await).await;
+    assert!(std::str::from_utf8(&body).unwrap().starts_with("timestamp,sensor_id,violation_type,temperature,range_min,range_max,severity"));
+
+    let req = test::TestRequest::get().uri("/api/v1/temperature/violations/export.csv").to_request();
+    let body = test::read_body(test::call_service(&app, req).await).await;
+    assert!(std::str::from_utf8(&body).unwrap().starts_with("timestamp,sensor_id,violation_type"));
+
+    let req = test::TestRequest::get().uri("/api/v1/temperature/export.csv?start=2100-01-01").to_request();
+    let body = test::read_body(test::call_service(&app, req).await).await;
+    assert_eq!(std::str::from_utf8(&body).unwrap().lines().count(), 1);
+
+    let req = test::TestRequest::get().uri("/api/v1/temperature/export.csv?end=yesterday").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
 #[actix_web::test]
 async fn test_get_audit_events() {
     let app_state = create_app_state();
@@ -438,6 +835,54 @@ async fn test_get_statistics() {
     // samples is always non-negative (usize)
 }
 
+#[actix_web::test]
+async fn test_statistics_are_cached_until_samples_change() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let create = |sample_id: &str| {
+        let create_req = CreateSampleRequest {
+            sample_id: sample_id.to_string(),
+            batch_number: "BATCH-STATS".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        };
+        test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request()
+    };
+    let get = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+    
+    assert_eq!(test::call_service(&app, create("API-STATS-001")).await.status(), 201);
+    
+    let first: StatisticsResponse = test::read_body_json(test::call_service(&app, get("/api/v1/statistics")).await).await;
+    let second: StatisticsResponse = test::read_body_json(test::call_service(&app, get("/api/v1/statistics")).await).await;
+    assert_eq!((first.samples, second.samples), (1, 1));
+    let metrics: CacheMetricsResponse = test::read_body_json(test::call_service(&app, get("/api/v1/metrics/cache")).await).await;
+    assert_eq!((metrics.statistics.hits, metrics.statistics.misses), (1, 1));
+    
+    // A new sample invalidates the cached counts
+    assert_eq!(test::call_service(&app, create("API-STATS-002")).await.status(), 201);
+    let third: StatisticsResponse = test::read_body_json(test::call_service(&app, get("/api/v1/statistics")).await).await;
+    assert_eq!(third.samples, 2);
+    let metrics: CacheMetricsResponse = test::read_body_json(test::call_service(&app, get("/api/v1/metrics/cache")).await).await;
+    assert_eq!((metrics.statistics.hits, metrics.statistics.misses), (1, 2));
+    
+    let bypassed: StatisticsResponse = test::read_body_json(
+        test::call_service(&app, get("/api/v1/statistics?no_cache=true")).await
+    ).await;
+    assert_eq!(bypassed.samples, 2);
+    let metrics: CacheMetricsResponse = test::read_body_json(test::call_service(&app, get("/api/v1/metrics/cache")).await).await;
+    assert_eq!((metrics.statistics.hits, metrics.statistics.misses, metrics.statistics.bypassed), (1, 2, 1));
+}
+
 #[actix_web::test]
 async fn test_invalid_status_update() {
     let app_state = create_app_state();
@@ -470,6 +915,7 @@ async fn test_invalid_status_update() {
     let update_req = UpdateSampleStatusRequest {
         status: "InvalidStatus".to_string(),
         location: None,
+        user_id: None,
     };
     
     let req = test::TestRequest::put()
@@ -481,6 +927,45 @@ async fn test_invalid_status_update() {
     assert_eq!(resp.status(), 400);
 }
 
+#[actix_web::test]
+async fn test_forbidden_status_transition_is_conflict() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "API-TEST-TRANSITION".to_string(),
+        batch_number: "BATCH-TRANSITION".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Refrigerated".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: None,
+    };
+    let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+    test::call_service(&app, req).await;
+    
+    let set_status = |status: &str| test::TestRequest::put()
+        .uri("/api/v1/samples/API-TEST-TRANSITION/status")
+        .set_json(UpdateSampleStatusRequest { status: status.to_string(), location: None, user_id: None })
+        .to_request();
+    assert_eq!(test::call_service(&app, set_status("Compromised")).await.status(), 200);
+    
+    let resp = test::call_service(&app, set_status("InProduction")).await;
+    assert_eq!(resp.status(), 409);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["from"], "Compromised");
+    assert_eq!(body["to"], "InProduction");
+    
+    let stored = app_state.database.lock().unwrap().get_sample("API-TEST-TRANSITION").unwrap().unwrap();
+    assert_eq!(stored.status, sample_guard::SampleStatus::Compromised);
+}
+
 #[actix_web::test]
 async fn test_audit_events_by_sample() {
     let app_state = create_app_state();
@@ -521,3 +1006,1321 @@ async fn test_audit_events_by_sample() {
     assert!(body.total > 0);
 }
 
+
+#[actix_web::test]
+async fn test_get_job_statuses() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/jobs")
+        .to_request();
+    
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_temperature_excursions_link_colocated_samples() {
+    let app_state = create_app_state();
+    // The mock sensor reads 5.0°C, which is below this range
+    app_state.temperature_monitor.lock().unwrap().set_expected_range((6.0, 8.0)).unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for sample_id in ["EXC-API-001", "EXC-API-002"] {
+        let create_req = CreateSampleRequest {
+            sample_id: sample_id.to_string(),
+            batch_number: "BATCH-EXC".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((6.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: Some("Warehouse-1".to_string()),
+        };
+        let req = test::TestRequest::post()
+            .uri("/api/v1/samples")
+            .set_json(&create_req)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+    }
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/temperature/read?location=Warehouse-1")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: TemperatureResponse = test::read_body_json(resp).await;
+    assert!(!body.within_range);
+    assert_eq!(body.affected_samples.len(), 2);
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/samples/EXC-API-002/temperature-excursions")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let excursions: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(excursions.len(), 1);
+    assert_eq!(excursions[0]["location"], "Warehouse-1");
+}
+
+#[actix_web::test]
+async fn test_sample_temperature_log_endpoint() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "TEMP-API-001".to_string(),
+        batch_number: "BATCH-TEMP".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        // The mock sensor reads 5.0°C, out of this sample's range but within the monitor's
+        temperature_range: Some((6.0, 8.0)),
+        storage_conditions: "Refrigerated".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: Some("Cold-Room-1".to_string()),
+    };
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples")
+        .set_json(&create_req)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    
+    for location in ["Cold-Room-1", "Cold-Room-2"] {
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/v1/temperature/read?location={}", location))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+    }
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/samples/TEMP-API-001/temperature")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let readings = body["readings"].as_array().unwrap();
+    assert_eq!(readings.len(), 1);
+    assert_eq!(readings[0]["location"], "Cold-Room-1");
+    assert_eq!(body["summary"]["out_of_range_readings"], 1);
+    assert!(body["summary"]["time_out_of_range_secs"].as_i64().unwrap() >= 0);
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/samples/NOPE/temperature")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+}
+
+#[actix_web::test]
+async fn test_validate_sample_reports_delta() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "VALID-API-001".to_string(),
+        batch_number: "BATCH-VALID".to_string(),
+        production_date: Utc::now(),
+        expiry_date: Some(Utc::now() + chrono::Duration::hours(1)),
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Refrigerated".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: Some("Cold-Room-1".to_string()),
+    };
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples")
+        .set_json(&create_req)
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 201);
+    
+    let validate = || test::TestRequest::post().uri("/api/v1/samples/VALID-API-001/validate").to_request();
+    let body: ValidationResponse = test::read_body_json(test::call_service(&app, validate()).await).await;
+    assert!(body.is_valid);
+    assert!(body.delta.is_none());
+    
+    // Let the expiry pass
+    {
+        let db = app_state.database.lock().unwrap();
+        let mut sample = db.get_sample("VALID-API-001").unwrap().unwrap();
+        sample.metadata.expiry_date = Some(Utc::now() - chrono::Duration::hours(1));
+        sample.update_location("Cold-Room-1".to_string());
+        db.store_sample(&sample).unwrap();
+    }
+    
+    let body: ValidationResponse = test::read_body_json(test::call_service(&app, validate()).await).await;
+    assert!(!body.is_valid);
+    let delta = body.delta.unwrap();
+    assert_eq!(delta.added_violations, vec![sample_guard::integrity::Violation::Expired]);
+    assert!(delta.resolved_violations.is_empty());
+    
+    let body: ValidationResponse = test::read_body_json(test::call_service(&app, validate()).await).await;
+    assert!(body.delta.unwrap().is_empty());
+    
+    let req = test::TestRequest::post().uri("/api/v1/samples/NOPE/validate").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+}
+
+#[actix_web::test]
+async fn test_sample_image_upload() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "IMG-API-001".to_string(),
+        batch_number: "BATCH-IMG".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Refrigerated".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: None,
+    };
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples")
+        .set_json(&create_req)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    
+    // Non-images are refused, whatever the body claims to be
+    for (content_type, body) in [("text/plain", b"a label".to_vec()), ("image/png", b"not a png".to_vec())] {
+        let req = test::TestRequest::put()
+            .uri("/api/v1/samples/IMG-API-001/image")
+            .insert_header(("Content-Type", content_type))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 415);
+    }
+    
+    let mut png = Vec::new();
+    image::RgbImage::new(400, 200)
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    let req = test::TestRequest::put()
+        .uri("/api/v1/samples/IMG-API-001/image")
+        .insert_header(("Content-Type", "image/png"))
+        .set_payload(png.clone())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/samples/IMG-API-001")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let sample: SampleResponse = test::read_body_json(resp).await;
+    let reference = sample.primary_image.unwrap();
+    assert_eq!(reference.content_type, "image/png");
+    assert_eq!((reference.width, reference.height), (400, 200));
+    assert_eq!(reference.size_bytes, png.len());
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/samples/IMG-API-001/image/thumbnail")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(resp.headers().get("content-type").unwrap(), "image/png");
+    let thumbnail = image::load_from_memory(&test::read_body(resp).await).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (128, 64));
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/samples/IMG-API-001/image")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(test::read_body(resp).await.to_vec(), png);
+}
+
+#[actix_web::test]
+async fn test_inventory_compare_against_baseline() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    // The mock reader sees no tags, so the baseline and current inventory are both empty
+    let req = test::TestRequest::post()
+        .uri("/api/v1/inventory/snapshots")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let snapshot: InventorySnapshotResponse = test::read_body_json(resp).await;
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/inventory/compare")
+        .set_json(serde_json::json!({ "baseline_id": snapshot.id }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let body: InventoryCompareResponse = test::read_body_json(resp).await;
+    assert_eq!(body.baseline_id, snapshot.id);
+    assert!(body.diff.is_empty());
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/inventory/compare")
+        .set_json(serde_json::json!({ "baseline_id": snapshot.id + 100 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+}
+
+#[actix_web::test]
+async fn test_reader_latency_metrics_endpoint() {
+    use sample_guard::api::create_app_state_from_config;
+
+    let config = sample_guard::SampleGuardConfig::from_toml_str(r#"
+        [[readers]]
+        name = "dock-door"
+        kind = "impinj"
+    "#).unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_app_state_from_config(&config).unwrap()))
+            .configure(configure_routes)
+    ).await;
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/metrics/reader-latency")
+        .to_request();
+    
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    
+    // The server's driver recorded initializing its readers
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let entries = body["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["reader"], "Impinj Speedway");
+    assert_eq!(entries[0]["command"], "Initialize");
+    assert_eq!(entries[0]["count"], 1);
+}
+
+#[actix_web::test]
+async fn test_reader_latency_needs_hardware_driver() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_app_state()))
+            .configure(configure_routes)
+    ).await;
+    let req = test::TestRequest::get()
+        .uri("/api/v1/metrics/reader-latency")
+        .to_request();
+    
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 501);
+}
+
+#[actix_web::test]
+async fn test_clone_sample() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "CLONE-SRC".to_string(),
+        batch_number: "BATCH-CLONE".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Refrigerated".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: Some("Cold-Room-1".to_string()),
+    };
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples")
+        .set_json(&create_req)
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 201);
+    app_state.database.lock().unwrap().record_tag_read("CLONE-SRC").unwrap();
+    
+    let clone = |source: &str, sample_id: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/samples/{}/clone", source))
+            .set_json(CloneSampleRequest { sample_id: sample_id.to_string() })
+            .to_request()
+    };
+    let resp = test::call_service(&app, clone("CLONE-SRC", "CLONE-NEW")).await;
+    assert_eq!(resp.status(), 201);
+    let body: SampleResponse = test::read_body_json(resp).await;
+    assert_eq!(body.sample_id, "CLONE-NEW");
+    assert_eq!(body.read_count, 0);
+    assert_eq!(body.status, "InProduction");
+    
+    {
+        let db = app_state.database.lock().unwrap();
+        let source = db.get_sample("CLONE-SRC").unwrap().unwrap();
+        let stored = db.get_sample("CLONE-NEW").unwrap().unwrap();
+        assert_eq!(stored.metadata, source.metadata);
+        assert_ne!(stored.id, source.id);
+    }
+    
+    assert_eq!(test::call_service(&app, clone("CLONE-SRC", "CLONE-NEW")).await.status(), 409);
+    assert_eq!(test::call_service(&app, clone("NOPE", "CLONE-OTHER")).await.status(), 404);
+}
+
+#[actix_web::test]
+async fn test_split_sample_and_lineage() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "API-SPLIT-001".to_string(),
+        batch_number: "BATCH-SPLIT".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Refrigerated".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: Some("Freezer 1".to_string()),
+    };
+    let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 201);
+    
+    let split_req = serde_json::json!({
+        "children": [{ "sample_id": "API-SPLIT-001-A" }, { "sample_id": "API-SPLIT-001-B" }]
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples/API-SPLIT-001/split")
+        .set_json(&split_req)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let children: Vec<SampleResponse> = test::read_body_json(resp).await;
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0].batch_number, "BATCH-SPLIT");
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/samples/API-SPLIT-001-B/lineage")
+        .to_request();
+    let lineage: sample_guard::Lineage = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(lineage.parent_ids(), vec!["API-SPLIT-001"]);
+    
+    // Splitting into an existing sample is rejected
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples/API-SPLIT-001/split")
+        .set_json(&split_req)
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_get_sample_custody_chain() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "API-CUSTODY-001".to_string(),
+        batch_number: "BATCH-CUSTODY".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Refrigerated".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: None,
+    };
+    let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 201);
+    {
+        let db = app_state.database.lock().unwrap();
+        for (from, to) in [(None, "courier"), (Some("courier"), "lab")] {
+            db.record_custody_transfer(&sample_guard::CustodyEntry {
+                sample_id: "API-CUSTODY-001".to_string(),
+                transferred_from: from.map(str::to_string),
+                transferred_to: to.to_string(),
+                transfer_timestamp: Utc::now(),
+                witnessed_by: None,
+                notes: None,
+            }).unwrap();
+        }
+    }
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/API-CUSTODY-001/custody").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let chain: Vec<sample_guard::CustodyEntry> = test::read_body_json(resp).await;
+    let custodians: Vec<_> = chain.iter().map(|e| e.transferred_to.as_str()).collect();
+    assert_eq!(custodians, vec!["courier", "lab"]);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/NOPE/custody").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+}
+
+#[actix_web::test]
+async fn test_filter_samples_by_label() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for id in ["API-LABEL-001", "API-LABEL-002", "API-LABEL-003"] {
+        let create_req = CreateSampleRequest {
+            sample_id: id.to_string(),
+            batch_number: "BATCH-LABEL".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        };
+        let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+    }
+    for (id, label) in [("API-LABEL-001", "priority"), ("API-LABEL-003", "priority"), ("API-LABEL-003", "trial-x")] {
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/v1/samples/{}/labels", id))
+            .set_json(&AddLabelRequest { label: label.to_string() })
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+    
+    let ids = |page: SamplePageResponse| {
+        let mut ids: Vec<String> = page.items.into_iter().map(|s| s.sample_id).collect();
+        ids.sort();
+        ids
+    };
+    let req = test::TestRequest::get().uri("/api/v1/samples?label=priority").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let page: SamplePageResponse = test::read_body_json(resp).await;
+    assert_eq!(page.total, 2);
+    assert_eq!(ids(page), vec!["API-LABEL-001", "API-LABEL-003"]);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples?label=priority,trial-x").to_request();
+    let page: SamplePageResponse = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(page.items[0].labels, vec!["priority", "trial-x"]);
+    assert_eq!(ids(page), vec!["API-LABEL-003"]);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples?label=trial-x,missing&label_match=any").to_request();
+    let page: SamplePageResponse = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(ids(page), vec!["API-LABEL-003"]);
+    
+    let req = test::TestRequest::delete().uri("/api/v1/samples/API-LABEL-001/labels/priority").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+    let req = test::TestRequest::delete().uri("/api/v1/samples/API-LABEL-001/labels/priority").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+    let req = test::TestRequest::get().uri("/api/v1/samples/API-LABEL-001/labels").to_request();
+    let labels: Vec<String> = test::read_body_json(test::call_service(&app, req).await).await;
+    assert!(labels.is_empty());
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples/NOPE/labels")
+        .set_json(&AddLabelRequest { label: "priority".to_string() })
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples/API-LABEL-002/labels")
+        .set_json(&AddLabelRequest { label: " ".to_string() })
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_archive_and_restore_sample() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "API-COLD-001".to_string(),
+        batch_number: "BATCH-COLD".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: None,
+        storage_conditions: "Ambient".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: None,
+    };
+    let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 201);
+    
+    let req = test::TestRequest::post().uri("/api/v1/admin/archive/API-COLD-001").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+    let req = test::TestRequest::get().uri("/api/v1/samples/API-COLD-001").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+    
+    let req = test::TestRequest::get().uri("/api/v1/admin/archive").to_request();
+    let archived: serde_json::Value = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(archived[0]["sample_id"], "API-COLD-001");
+    assert_eq!(archived[0]["batch_number"], "BATCH-COLD");
+    
+    let req = test::TestRequest::post().uri("/api/v1/admin/archive/API-COLD-001/restore").to_request();
+    let restored: SampleResponse = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(restored.sample_id, "API-COLD-001");
+    let req = test::TestRequest::get().uri("/api/v1/samples/API-COLD-001").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+    
+    let req = test::TestRequest::post().uri("/api/v1/admin/archive/API-COLD-001/restore").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+}
+
+#[actix_web::test]
+async fn test_database_only_deployment_reports_missing_capabilities() {
+    let mut config = sample_guard::SampleGuardConfig::default();
+    config.reader.kind = "none".to_string();
+    config.temperature.enabled = false;
+    let app_state = sample_guard::api::create_app_state_from_config(&config).unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let req = test::TestRequest::get().uri("/api/v1/health").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let health: HealthResponse = test::read_body_json(resp).await;
+    assert_eq!(health.status, "ok");
+    assert!(!health.capabilities.unwrap().has_reader);
+    
+    let req = test::TestRequest::get().uri("/api/v1/capabilities").to_request();
+    let capabilities: sample_guard::Capabilities = test::read_body_json(test::call_service(&app, req).await).await;
+    assert!(!capabilities.has_reader);
+    assert!(!capabilities.has_temperature_sensor);
+    assert!(!capabilities.has_hardware_driver);
+    
+    for (uri, capability) in [
+        ("/api/v1/inventory/scan", "rfid_reader"),
+        ("/api/v1/temperature/read", "temperature_sensor"),
+    ] {
+        let req = test::TestRequest::post().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 501, "{}", uri);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "CAPABILITY_MISSING");
+        assert_eq!(body["capability"], capability);
+    }
+    
+    // Database endpoints keep working
+    let req = test::TestRequest::get().uri("/api/v1/samples").to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_storage_condition_vocabulary_is_enforced() {
+    let mut config = sample_guard::SampleGuardConfig::default();
+    config.samples.storage_conditions = vec!["Refrigerated".to_string(), "Frozen".to_string()];
+    let app_state = sample_guard::api::create_app_state_from_config(&config).unwrap();
+    let database = app_state.database.clone();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let request = |sample_id: &str, storage_conditions: &str| CreateSampleRequest {
+        sample_id: sample_id.to_string(),
+        batch_number: "BATCH-VOCAB".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: None,
+        storage_conditions: storage_conditions.to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: None,
+    };
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples")
+        .set_json(request("VOCAB-001", "Fridge"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["message"].as_str().unwrap().contains("Refrigerated, Frozen"));
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples")
+        .set_json(request("VOCAB-002", "refrigerated 2-8"))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 201);
+    let stored = database.lock().unwrap().get_sample("VOCAB-002").unwrap().unwrap();
+    assert_eq!(stored.metadata.storage_conditions, "Refrigerated");
+}
+
+#[actix_web::test]
+async fn test_export_streams_every_stored_sample() {
+    let app_state = create_app_state();
+    {
+        let db = app_state.database.lock().unwrap();
+        for i in 0..3000 {
+            let metadata = sample_guard::SampleMetadata {
+                batch_number: format!("BATCH-{}", i % 7),
+                production_date: Utc::now(),
+                expiry_date: None,
+                temperature_range: Some((2.0, 8.0)),
+                storage_conditions: "Refrigerated, protect from light".to_string(),
+                manufacturer: "Test".to_string(),
+                product_line: "Test".to_string(),
+            };
+            db.store_sample(&sample_guard::Sample::new(format!("EXPORT-{:05}", i), metadata, None)).unwrap();
+        }
+    }
+    let stored = app_state.database.lock().unwrap().get_statistics().unwrap().total_samples;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/export?format=csv").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("content-type").unwrap().to_str().unwrap().starts_with("text/csv"));
+    let body = test::read_body(resp).await;
+    let csv = std::str::from_utf8(&body).unwrap();
+    assert_eq!(csv.lines().count(), stored + 1);
+    assert!(csv.lines().nth(1).unwrap().starts_with("EXPORT-00000,"));
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/export?format=jsonl").to_request();
+    let body = test::read_body(test::call_service(&app, req).await).await;
+    let rows: Vec<sample_guard::Sample> = std::str::from_utf8(&body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(rows.len(), stored);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/export?format=xml").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_csv_export_and_import_endpoints() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    for (sample_id, batch_number) in [("API-CSV-001", "BATCH-CSV-A"), ("API-CSV-002", "BATCH-CSV-B")] {
+        let create_req = CreateSampleRequest {
+            sample_id: sample_id.to_string(),
+            batch_number: batch_number.to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        };
+        let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+    }
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/export.csv?batch_number=BATCH-CSV-B").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("content-type").unwrap().to_str().unwrap().starts_with("text/csv"));
+    let body = test::read_body(resp).await;
+    let csv = std::str::from_utf8(&body).unwrap();
+    assert_eq!(csv.lines().count(), 2);
+    assert!(csv.lines().nth(1).unwrap().starts_with("API-CSV-002,"));
+    
+    let empty = ",".repeat(13);
+    let import = format!(
+        "{}API-CSV-003,,Stored,BATCH-CSV-C,2026-01-05T00:00:00Z{}\nAPI-CSV-004,,Stored,BATCH-CSV-C,not-a-date{}\n",
+        csv, empty, empty
+    );
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples/import?on_conflict=skip")
+        .insert_header(("content-type", "text/csv"))
+        .set_payload(import)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((body["skipped"].as_u64(), body["inserted"].as_u64(), body["failed"].as_u64()), (Some(1), Some(1), Some(1)));
+    assert_eq!(body["records"][2]["line"], 4);
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/API-CSV-003").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples/import")
+        .set_payload("sample_id\nAPI-CSV-005\n")
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_api_reads_and_tag_reads_are_counted_separately() {
+    let app_state = create_app_state();
+    let database = app_state.database.clone();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "API-COUNT-001".to_string(),
+        batch_number: "BATCH-COUNT".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: None,
+        storage_conditions: "Frozen".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: None,
+    };
+    let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 201);
+    
+    for expected in 1..=2 {
+        let req = test::TestRequest::get().uri("/api/v1/samples/API-COUNT-001").to_request();
+        let body: SampleResponse = test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(body.api_access_count, expected);
+        assert_eq!(body.read_count, 0);
+    }
+    
+    {
+        let db = database.lock().unwrap();
+        assert_eq!(db.record_tag_read("API-COUNT-001").unwrap(), Some(1));
+        assert_eq!(db.get_api_access_count("API-COUNT-001").unwrap(), 2);
+        assert_eq!(db.record_tag_read("API-MISSING").unwrap(), None);
+    }
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples").to_request();
+    let body: SamplePageResponse = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!((body.items[0].read_count, body.items[0].api_access_count), (1, 2));
+}
+
+#[actix_web::test]
+async fn test_status_change_runs_transition_hooks() {
+    use sample_guard::{SampleStatus, TransitionHook};
+    use std::sync::{Arc, Mutex};
+    
+    #[derive(Default)]
+    struct CapturingHook(Mutex<Vec<(SampleStatus, SampleStatus)>>);
+    
+    impl TransitionHook for CapturingHook {
+        fn name(&self) -> &str {
+            "capturing"
+        }
+        
+        fn on_transition(&self, _sample: &sample_guard::Sample, from: SampleStatus, to: SampleStatus) -> sample_guard::Result<()> {
+            self.0.lock().unwrap().push((from, to));
+            Ok(())
+        }
+    }
+    
+    let app_state = create_app_state();
+    let hook = Arc::new(CapturingHook::default());
+    app_state.database.lock().unwrap().register_transition_hook(hook.clone());
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let create_req = CreateSampleRequest {
+        sample_id: "API-HOOK-001".to_string(),
+        batch_number: "BATCH-HOOK".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: None,
+        storage_conditions: "Frozen".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: None,
+    };
+    let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 201);
+    
+    for status in ["Stored", "InUse"] {
+        let req = test::TestRequest::put()
+            .uri("/api/v1/samples/API-HOOK-001/status")
+            .set_json(&UpdateSampleStatusRequest { status: status.to_string(), location: None, user_id: None })
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+    
+    assert_eq!(*hook.0.lock().unwrap(), vec![
+        (SampleStatus::InProduction, SampleStatus::Stored),
+        (SampleStatus::Stored, SampleStatus::InUse),
+    ]);
+}
+
+#[actix_web::test]
+async fn test_connection_limit_rejects_with_503_but_keeps_health() {
+    use actix_web::middleware::from_fn;
+    use sample_guard::api::create_app_state_from_config;
+    use sample_guard::api::middleware::{limit_connections, ConnectionReport};
+    
+    let mut config = sample_guard::SampleGuardConfig::default();
+    config.server.max_connections = Some(1);
+    let app_state = create_app_state_from_config(&config).unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .wrap(from_fn(limit_connections))
+            .configure(configure_routes)
+    ).await;
+    let get = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+    
+    // Saturate the server with a request that is still being handled
+    let busy = app_state.connections.try_acquire(false).unwrap();
+    let resp = test::call_service(&app, get("/api/v1/samples")).await;
+    assert_eq!(resp.status(), 503);
+    assert_eq!(resp.headers().get("retry-after").unwrap(), "1");
+    assert_eq!(test::call_service(&app, get("/api/v1/health")).await.status(), 200);
+    
+    // With the reserved slot taken too, health checks are turned away as well
+    let health = app_state.connections.try_acquire(true).unwrap();
+    assert_eq!(test::call_service(&app, get("/api/v1/health")).await.status(), 503);
+    drop((busy, health));
+    
+    let resp = test::call_service(&app, get("/api/v1/metrics/connections")).await;
+    assert_eq!(resp.status(), 200);
+    let report: ConnectionReport = test::read_body_json(resp).await;
+    assert_eq!(report.max_connections, Some(1));
+    assert_eq!(report.rejected, 2);
+    // The metrics request itself holds the only slot
+    assert_eq!(report.active, 1);
+    assert_eq!(app_state.connections.report().active, 0);
+}
+
+#[actix_web::test]
+async fn test_api_keys_and_roles_are_enforced() {
+    use actix_web::middleware::from_fn;
+    use sample_guard::api::create_app_state_from_config;
+    use sample_guard::api::middleware::authenticate;
+    use sample_guard::config::{ApiKeyConfig, ApiRole};
+    
+    let mut config = sample_guard::SampleGuardConfig::default();
+    config.auth.enabled = true;
+    for (name, role) in [("admin", ApiRole::Admin), ("reader", ApiRole::ReadOnly)] {
+        config.auth.api_keys.push(ApiKeyConfig {
+            name: name.to_string(),
+            key: format!("{}-key-0123456789", name),
+            role,
+        });
+    }
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_app_state_from_config(&config).unwrap()))
+            .wrap(from_fn(authenticate))
+            .configure(configure_routes)
+    ).await;
+    let create = |key: Option<&str>| {
+        let mut req = test::TestRequest::post().uri("/api/v1/samples").set_json(CreateSampleRequest {
+            sample_id: "API-AUTH-1".to_string(),
+            batch_number: "BATCH-AUTH".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        });
+        if let Some(key) = key {
+            req = req.insert_header(("Authorization", format!("Bearer {}", key)));
+        }
+        req.to_request()
+    };
+    
+    let resp = test::call_service(&app, create(None)).await;
+    assert_eq!(resp.status(), 401);
+    assert_eq!(resp.headers().get("www-authenticate").unwrap(), "Bearer");
+    assert_eq!(test::call_service(&app, create(Some("not-a-key-0123456789"))).await.status(), 401);
+    assert_eq!(test::call_service(&app, create(Some("reader-key-0123456789"))).await.status(), 403);
+    assert_eq!(test::call_service(&app, create(Some("admin-key-0123456789"))).await.status(), 201);
+    
+    let req = test::TestRequest::get()
+        .uri("/api/v1/samples")
+        .insert_header(("X-Api-Key", "reader-key-0123456789"))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+    let req = test::TestRequest::get().uri("/api/v1/health").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+}
+
+#[actix_web::test]
+async fn test_rate_limit_rejects_with_429() {
+    use actix_web::middleware::from_fn;
+    use sample_guard::api::create_app_state_from_config;
+    use sample_guard::api::middleware::rate_limit;
+    
+    let mut config = sample_guard::SampleGuardConfig::default();
+    config.rate_limit.enabled = true;
+    config.rate_limit.requests_per_second = 1;
+    config.rate_limit.burst = 3;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_app_state_from_config(&config).unwrap()))
+            .wrap(from_fn(rate_limit))
+            .configure(configure_routes)
+    ).await;
+    let get = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+    
+    for _ in 0..3 {
+        assert_eq!(test::call_service(&app, get("/api/v1/samples")).await.status(), 200);
+    }
+    let resp = test::call_service(&app, get("/api/v1/samples")).await;
+    assert_eq!(resp.status(), 429);
+    assert_eq!(resp.headers().get("retry-after").unwrap(), "1");
+    assert_eq!(test::call_service(&app, get("/api/v1/health")).await.status(), 200);
+}
+
+#[actix_web::test]
+async fn test_seed_demo_data() {
+    use sample_guard::api::create_app_state_from_config;
+    use sample_guard::api::demo::DEMO_PREFIX;
+    
+    fn sample_count(config: &sample_guard::SampleGuardConfig) -> usize {
+        let app_state = create_app_state_from_config(config).unwrap();
+        let count = app_state.database.lock().unwrap().get_all_samples().unwrap().len();
+        count
+    }
+    
+    let mut config = sample_guard::SampleGuardConfig::default();
+    assert_eq!(sample_count(&config), 0);
+    
+    config.server.seed_demo_data = true;
+    config.server.demo_sample_count = 7;
+    assert_eq!(sample_count(&config), 7);
+    
+    // Seeded samples are visible through the API and can be purged
+    let app_state = create_app_state_from_config(&config).unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+    let req = test::TestRequest::get().uri("/api/v1/samples").to_request();
+    let body: SamplePageResponse = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(body.total, 7);
+    assert!(body.items.iter().all(|s| s.sample_id.starts_with(DEMO_PREFIX)));
+    
+    let req = test::TestRequest::delete().uri("/api/v1/admin/demo-data").to_request();
+    let purge: DemoPurgeResponse = test::read_body_json(test::call_service(&app, req).await).await;
+    assert_eq!(purge.purged, 7);
+    assert!(app_state.database.lock().unwrap().get_all_samples().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn test_audit_export_pages_with_cursor() {
+    let app_state = create_app_state();
+    let logger = app_state.audit_logger.clone();
+    for n in 0..7 {
+        logger.lock().unwrap().log_event(
+            sample_guard::AuditEventType::UserAction,
+            None,
+            None,
+            serde_json::json!({ "n": n }),
+            sample_guard::AuditSeverity::Info,
+        ).unwrap();
+    }
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let mut seen = Vec::new();
+    let mut uri = "/api/v1/audit/export?limit=3".to_string();
+    loop {
+        let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let next = resp.headers().get("x-next-cursor").map(|v| v.to_str().unwrap().to_string());
+        let body = test::read_body(resp).await;
+        for line in std::str::from_utf8(&body).unwrap().lines() {
+            let event: sample_guard::AuditEvent = serde_json::from_str(line).unwrap();
+            seen.push(event.details["n"].as_u64().unwrap());
+        }
+        match next {
+            Some(cursor) => uri = format!("/api/v1/audit/export?limit=3&cursor={}", cursor),
+            None => break,
+        }
+    }
+    assert_eq!(seen, (0..7).collect::<Vec<_>>());
+    
+    let req = test::TestRequest::get().uri("/api/v1/audit/export?cursor=abc").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+    let req = test::TestRequest::get().uri("/api/v1/audit/export?limit=0").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_create_samples_batch() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_routes)
+    ).await;
+    
+    let requests: Vec<CreateSampleRequest> = (0..3)
+        .map(|i| CreateSampleRequest {
+            sample_id: format!("API-BULK-{}", i),
+            batch_number: "BATCH-BULK".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        })
+        .collect();
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples/batch?all_or_nothing=true")
+        .set_json(&requests)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: BatchStoreResponse = test::read_body_json(resp).await;
+    assert_eq!((body.stored, body.failed, body.rolled_back), (3, 0, false));
+    assert_eq!(body.items[1].sample_id, "API-BULK-1");
+    
+    let req = test::TestRequest::get().uri("/api/v1/samples/API-BULK-2").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+    
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples/batch")
+        .set_payload("{\"sample_id\": \"not-an-array\"}")
+        .insert_header(("content-type", "application/json"))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_archive_audit_events() {
+    use sample_guard::api::create_app_state_from_config;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = sample_guard::SampleGuardConfig::default();
+    config.audit.archive_dir = Some(dir.path().to_path_buf());
+    let app_state = create_app_state_from_config(&config).unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+    for i in 0..3 {
+        let create_req = CreateSampleRequest {
+            sample_id: format!("API-ARCHIVE-{}", i),
+            batch_number: "BATCH-ARCHIVE".to_string(),
+            production_date: Utc::now(),
+            expiry_date: None,
+            temperature_range: Some((2.0, 8.0)),
+            storage_conditions: "Refrigerated".to_string(),
+            manufacturer: "Test".to_string(),
+            product_line: "Test".to_string(),
+            location: None,
+        };
+        let req = test::TestRequest::post().uri("/api/v1/samples").set_json(&create_req).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 201);
+    }
+    let held = app_state.audit_logger.lock().unwrap().get_all_events().len();
+    assert!(held >= 3);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/audit/archive")
+        .set_json(serde_json::json!({ "cutoff": chrono::Utc::now() }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let report: sample_guard::ArchiveReport = test::read_body_json(resp).await;
+    assert_eq!(report.events_archived, held);
+    assert!(report.path.starts_with(dir.path()) && report.path.exists());
+    // Only the record of the archive is left
+    assert_eq!(app_state.audit_logger.lock().unwrap().get_all_events().len(), 1);
+
+    let future = chrono::Utc::now() + chrono::Duration::days(1);
+    let req = test::TestRequest::post()
+        .uri("/api/v1/audit/archive")
+        .set_json(serde_json::json!({ "cutoff": future }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_app_state()))
+            .configure(configure_routes)
+    ).await;
+    let req = test::TestRequest::post()
+        .uri("/api/v1/audit/archive")
+        .set_json(serde_json::json!({ "cutoff": chrono::Utc::now() }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_export_audit_events_csv() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+    {
+        let mut logger = app_state.audit_logger.lock().unwrap();
+        for i in 0..4 {
+            logger.log_event(
+                sample_guard::AuditEventType::UserAction,
+                Some("USER-CSV".to_string()),
+                Some(format!("CSV-{}", i)),
+                serde_json::json!({ "comment": "a, b\nc" }),
+                sample_guard::AuditSeverity::Info,
+            ).unwrap();
+        }
+    }
+    let events = app_state.audit_logger.lock().unwrap().query_events(None, None, None, None, None).unwrap();
+    let start = events[1].timestamp.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/audit/events/export?format=csv&start={}", start))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("content-type").unwrap().to_str().unwrap().starts_with("text/csv"));
+    assert_eq!(resp.headers().get("content-disposition").unwrap(), "attachment; filename=\"audit-events.csv\"");
+    let body = test::read_body(resp).await;
+    let rows: Vec<csv::StringRecord> = csv::Reader::from_reader(&body[..]).records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), events.len() - 1);
+    assert_eq!(&rows[0][0], events[1].event_id.to_string());
+    assert_eq!(&rows[0][6], r#"{"comment":"a, b\nc"}"#);
+
+    let req = test::TestRequest::get().uri("/api/v1/audit/events/export?format=jsonl&sample_id=CSV-2").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("content-disposition").unwrap(), "attachment; filename=\"audit-events.jsonl\"");
+    let body = test::read_body(resp).await;
+    assert_eq!(std::str::from_utf8(&body).unwrap().lines().count(), 1);
+
+    for uri in ["/api/v1/audit/events/export?format=xml", "/api/v1/audit/events/export?end=soon", "/api/v1/audit/events/export?severity=Loud"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400, "{}", uri);
+    }
+}
+
+#[actix_web::test]
+async fn test_get_temperature_trend() {
+    let app_state = create_app_state();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+    let get = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+
+    let resp = test::call_service(&app, get("/api/v1/temperature/trend")).await;
+    assert_eq!(resp.status(), 200);
+    let trend: TemperatureTrendResponse = test::read_body_json(resp).await;
+    assert!(trend.direction.is_none() && trend.alert.is_none());
+
+    {
+        let mut monitor = app_state.temperature_monitor.lock().unwrap();
+        let base = Utc::now();
+        for (minute, temperature) in [3.0, 4.0, 5.0].into_iter().enumerate() {
+            monitor.record_reading(sample_guard::TemperatureReading {
+                temperature,
+                timestamp: base + chrono::Duration::minutes(minute as i64),
+                sensor_id: "TEMP-001".to_string(),
+                location: None,
+            }).unwrap();
+        }
+    }
+    let trend: TemperatureTrendResponse = test::read_body_json(test::call_service(&app, get("/api/v1/temperature/trend")).await).await;
+    assert_eq!(trend.direction, Some(sample_guard::TrendDirection::Rising(1.0)));
+    assert!(trend.predicted_violation_in_ms.is_some_and(|ms| ms > 0));
+    assert!(trend.alert.is_some());
+
+    assert_eq!(test::call_service(&app, get("/api/v1/temperature/trend?window=1")).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_get_temperature_history() {
+    use sample_guard::api::create_app_state_from_config;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = sample_guard::SampleGuardConfig::default();
+    config.database.path = Some(dir.path().join("samples.db"));
+    config.temperature.database = true;
+    let app_state = create_app_state_from_config(&config).unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_routes)
+    ).await;
+
+    for _ in 0..3 {
+        let req = test::TestRequest::post().uri("/api/v1/temperature/read").to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+    }
+    app_state.temperature_monitor.lock().unwrap().clear();
+
+    let req = test::TestRequest::get().uri("/api/v1/temperature/history?sensor_id=API-SENSOR").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let history: TemperatureHistoryResponse = test::read_body_json(resp).await;
+    assert_eq!(history.readings.len(), 3);
+    assert!(history.violations.is_empty());
+    assert!(history.to - history.from == chrono::Duration::days(1));
+
+    let req = test::TestRequest::get().uri("/api/v1/temperature/history?sensor_id=API-SENSOR&from=2000-01-01&to=2000-01-02").to_request();
+    let history: TemperatureHistoryResponse = test::read_body_json(test::call_service(&app, req).await).await;
+    assert!(history.readings.is_empty());
+
+    for uri in [
+        "/api/v1/temperature/history",
+        "/api/v1/temperature/history?sensor_id=API-SENSOR&from=2000-01-02&to=2000-01-01",
+    ] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400, "{}", uri);
+    }
+}
@@ -1,4 +1,4 @@
-use sample_guard::audit::{AuditLogger, AuditEventType, AuditSeverity};
+use sample_guard::audit::{AuditLogger, AuditFilter, AUDIT_CSV_HEADER, DEFAULT_MAX_EVENTS, AuditEvent, AuditEventType, AuditSeverity, DatabaseAuditSink, RotatingFileWriter, RotationPolicy};
 use sample_guard::sample::{Sample, SampleMetadata, SampleStatus};
 use chrono::Utc;
 
@@ -131,3 +131,383 @@ fn test_audit_export_json() {
     assert!(json.contains("SystemStartup"));
 }
 
+
+/// Every query the persistence test compares, as JSON so events can be compared
+fn persisted_queries(logger: &AuditLogger, start: chrono::DateTime<Utc>, end: chrono::DateTime<Utc>) -> Vec<serde_json::Value> {
+    let results: Vec<Vec<AuditEvent>> = vec![
+        logger.query_events(None, None, None, None, None).unwrap(),
+        logger.query_events(Some(&AuditEventType::ViolationDetected), None, None, None, None).unwrap(),
+        logger.query_events(None, Some("SAMPLE-3"), None, None, None).unwrap(),
+        logger.query_events(None, None, Some(&AuditSeverity::Error), None, None).unwrap(),
+        logger.query_events(None, None, None, Some(start), Some(end)).unwrap(),
+    ];
+    results.iter().map(|events| serde_json::to_value(events).unwrap()).collect()
+}
+
+#[test]
+fn test_database_sink_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.db");
+
+    let mut logger = AuditLogger::new().with_sink(DatabaseAuditSink::open(&path).unwrap());
+    let mut middle = (Utc::now(), Utc::now());
+    for i in 0..100 {
+        let (event_type, severity) = match i % 4 {
+            0 => (AuditEventType::SampleCreated, AuditSeverity::Info),
+            1 => (AuditEventType::SampleRead, AuditSeverity::Info),
+            2 => (AuditEventType::ViolationDetected, AuditSeverity::Error),
+            _ => (AuditEventType::StatusChanged, AuditSeverity::Warning),
+        };
+        logger.log_event(
+            event_type,
+            Some("USER-001".to_string()),
+            Some(format!("SAMPLE-{}", i % 10)),
+            serde_json::json!({ "index": i }),
+            severity,
+        ).unwrap();
+        if i == 40 {
+            middle.0 = logger.get_recent_events(1)[0].timestamp;
+        }
+        if i == 59 {
+            middle.1 = logger.get_recent_events(1)[0].timestamp;
+        }
+    }
+
+    let before = persisted_queries(&logger, middle.0, middle.1);
+    assert_eq!(before[0].as_array().unwrap().len(), 100);
+    assert_eq!(before[1].as_array().unwrap().len(), 25);
+    assert_eq!(before[2].as_array().unwrap().len(), 10);
+    assert_eq!(before[3].as_array().unwrap().len(), 25);
+    assert_eq!(before[4].as_array().unwrap().len(), 20);
+    assert_eq!(before[0][7]["details"]["index"], 7);
+    drop(logger);
+
+    let logger = AuditLogger::new().with_sink(DatabaseAuditSink::open(&path).unwrap());
+    assert!(logger.get_all_events().is_empty());
+    assert_eq!(persisted_queries(&logger, middle.0, middle.1), before);
+}
+
+#[test]
+fn test_database_sink_keeps_chain_hashes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.db");
+
+    let mut logger = AuditLogger::new()
+        .with_hash_chain()
+        .with_sink(DatabaseAuditSink::open(&path).unwrap());
+    for i in 0..3 {
+        logger.log_event(AuditEventType::UserAction, None, None, serde_json::json!({ "index": i }), AuditSeverity::Info).unwrap();
+    }
+
+    let stored = logger.query_events(None, None, None, None, None).unwrap();
+    let held = logger.get_all_events();
+    assert_eq!(stored.len(), 3);
+    for (stored, held) in stored.iter().zip(held) {
+        assert_eq!(stored.prev_hash, held.prev_hash);
+        assert_eq!(stored.event_hash, held.event_hash);
+        assert_eq!(stored.compute_hash(), stored.event_hash.unwrap());
+    }
+}
+
+fn log_indexed(logger: &mut AuditLogger, index: usize) {
+    logger.log_event(AuditEventType::UserAction, None, None, serde_json::json!({ "index": index }), AuditSeverity::Info).unwrap();
+}
+
+#[test]
+fn test_hash_chain_continues_across_restarts() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.db");
+
+    let mut logger = AuditLogger::new()
+        .with_hash_chain()
+        .with_sink(DatabaseAuditSink::open(&path).unwrap());
+    for i in 0..3 {
+        log_indexed(&mut logger, i);
+    }
+    let head = logger.get_all_events()[2].event_hash;
+    drop(logger);
+
+    let mut logger = AuditLogger::new()
+        .with_hash_chain()
+        .with_sink(DatabaseAuditSink::open(&path).unwrap());
+    log_indexed(&mut logger, 3);
+    assert_eq!(logger.get_all_events()[0].prev_hash, head);
+
+    // Every stored event is walked, not just the one held in memory
+    let verification = logger.verify_chain().unwrap();
+    assert!(verification.is_intact());
+    assert_eq!(verification.events_checked, 4);
+}
+
+#[test]
+fn test_verify_chain_checks_persisted_events() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.db");
+
+    // Events from before chaining was turned on are skipped
+    let mut logger = AuditLogger::new().with_sink(DatabaseAuditSink::open(&path).unwrap());
+    log_indexed(&mut logger, 0);
+    drop(logger);
+    let mut logger = AuditLogger::new()
+        .with_hash_chain()
+        .with_sink(DatabaseAuditSink::open(&path).unwrap());
+    for i in 1..4 {
+        log_indexed(&mut logger, i);
+    }
+    assert!(logger.verify_chain().unwrap().is_intact());
+
+    let tampered = logger.get_all_events()[1].event_id;
+    rusqlite::Connection::open(&path).unwrap().execute(
+        "UPDATE audit_events SET details = '{\"index\":99}' WHERE event_id = ?1",
+        [tampered.to_string()],
+    ).unwrap();
+
+    let broken = logger.verify_chain().unwrap().broken_link.unwrap();
+    assert_eq!(broken.index, 2);
+    assert_eq!(broken.event_id, tampered);
+}
+
+#[test]
+fn test_hash_chain_continues_from_audit_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+
+    let mut logger = AuditLogger::with_file(&path).unwrap().with_hash_chain();
+    log_indexed(&mut logger, 0);
+    log_indexed(&mut logger, 1);
+    let head = logger.get_all_events()[1].event_hash;
+    drop(logger);
+
+    let mut logger = AuditLogger::with_file(&path).unwrap().with_hash_chain();
+    log_indexed(&mut logger, 2);
+    assert_eq!(logger.get_all_events()[0].prev_hash, head);
+}
+
+#[test]
+fn test_query_events_without_sink_uses_memory() {
+    let mut logger = AuditLogger::new();
+    for severity in [AuditSeverity::Info, AuditSeverity::Critical, AuditSeverity::Info] {
+        logger.log_event(AuditEventType::UserAction, None, None, serde_json::json!({}), severity).unwrap();
+    }
+
+    let critical = logger.query_events(None, None, Some(&AuditSeverity::Critical), None, None).unwrap();
+    assert_eq!(critical.len(), 1);
+}
+
+/// Event ids in an audit file, gunzipping rotated `.gz` files
+fn event_ids_in(path: &std::path::Path) -> Vec<String> {
+    use std::io::Read;
+
+    let mut text = String::new();
+    let file = std::fs::File::open(path).unwrap();
+    if path.extension().is_some_and(|e| e == "gz") {
+        flate2::read::GzDecoder::new(file).read_to_string(&mut text).unwrap();
+    } else {
+        std::io::BufReader::new(file).read_to_string(&mut text).unwrap();
+    }
+    text.lines()
+        .map(|line| serde_json::from_str::<AuditEvent>(line).unwrap().event_id.to_string())
+        .collect()
+}
+
+#[test]
+fn test_rotated_files_hold_every_event_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.log");
+    let policy = RotationPolicy { max_bytes: 64 * 1024, max_files: 100, compress: true };
+    let writer = RotatingFileWriter::open(&path, policy).unwrap();
+
+    // Two loggers on one file, as two threads sharing it would be
+    let padding = "x".repeat(512);
+    let handles: Vec<_> = (0..2).map(|thread| {
+        let writer = writer.clone();
+        let padding = padding.clone();
+        std::thread::spawn(move || {
+            let mut logger = AuditLogger::with_writer(writer);
+            for i in 0..2000 {
+                logger.log_event(
+                    AuditEventType::UserAction,
+                    None,
+                    Some(format!("T{}-{}", thread, i)),
+                    serde_json::json!({ "padding": padding }),
+                    AuditSeverity::Info,
+                ).unwrap();
+            }
+            logger.get_all_events().iter().map(|e| e.event_id.to_string()).collect::<Vec<_>>()
+        })
+    }).collect();
+    let mut logged: Vec<String> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+
+    let rotated = writer.list_rotated_files();
+    assert!(rotated.len() > 20, "only {} rotations", rotated.len());
+    assert!(rotated.iter().all(|p| p.extension().is_some_and(|e| e == "gz")));
+    assert!(std::fs::metadata(&path).unwrap().len() < policy.max_bytes);
+
+    let mut written: Vec<String> = rotated.iter().chain([&path]).flat_map(|p| event_ids_in(p)).collect();
+    written.sort();
+    logged.sort();
+    assert_eq!(written, logged);
+}
+
+#[test]
+fn test_rotation_keeps_at_most_max_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.log");
+    let policy = RotationPolicy { max_bytes: 4096, max_files: 3, compress: false };
+    let mut logger = AuditLogger::with_rotating_file(&path, policy).unwrap();
+
+    for i in 0..500 {
+        logger.log_event(
+            AuditEventType::UserAction,
+            None,
+            Some(format!("SAMPLE-{}", i)),
+            serde_json::json!({}),
+            AuditSeverity::Info,
+        ).unwrap();
+    }
+
+    let rotated = logger.list_rotated_files();
+    let expected: Vec<_> = (1..=3).map(|n| dir.path().join(format!("audit.log.{}", n))).collect();
+    assert_eq!(rotated, expected);
+    assert!(!dir.path().join("audit.log.4").exists());
+    // The newest events are in the current file and the one before it
+    let newest = logger.get_recent_events(1)[0].event_id.to_string();
+    let current = event_ids_in(&path);
+    let previous = event_ids_in(&rotated[0]);
+    assert!(current.last() == Some(&newest) || (current.is_empty() && previous.last() == Some(&newest)));
+}
+
+#[test]
+fn test_rotation_shrinks_active_file_and_with_file_never_rotates() {
+    let dir = tempfile::tempdir().unwrap();
+    let rotating_path = dir.path().join("rotating.log");
+    let plain_path = dir.path().join("plain.log");
+    let policy = RotationPolicy { max_bytes: 2048, max_files: 2, compress: false };
+    let mut rotating = AuditLogger::with_rotating_file(&rotating_path, policy).unwrap();
+    let mut plain = AuditLogger::with_file(&plain_path).unwrap();
+
+    let mut largest = 0;
+    let mut shrank = false;
+    for i in 0..100 {
+        for logger in [&mut rotating, &mut plain] {
+            logger.log_event(
+                AuditEventType::UserAction,
+                None,
+                Some(format!("SAMPLE-{}", i)),
+                serde_json::json!({}),
+                AuditSeverity::Info,
+            ).unwrap();
+        }
+        let size = std::fs::metadata(&rotating_path).unwrap().len();
+        shrank |= size < largest;
+        largest = largest.max(size);
+    }
+    drop(plain);
+
+    assert!(shrank);
+    assert!(dir.path().join("rotating.log.1").exists());
+    assert!(std::fs::metadata(&plain_path).unwrap().len() > policy.max_bytes);
+    assert!(!dir.path().join("plain.log.1").exists());
+}
+
+#[test]
+fn test_archive_and_restore_events() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.json.gz");
+    let mut logger = AuditLogger::new().with_max_events(20);
+    assert_eq!(AuditLogger::new().max_events(), DEFAULT_MAX_EVENTS);
+    for i in 0..5 {
+        logger.log_event(
+            AuditEventType::SampleRead,
+            None,
+            Some(format!("SAMPLE-{}", i)),
+            serde_json::json!({}),
+            AuditSeverity::Info,
+        ).unwrap();
+    }
+    let cutoff = logger.get_all_events()[3].timestamp;
+    let end = logger.end_cursor();
+
+    let report = logger.archive_events_before(cutoff, &path).unwrap();
+    assert_eq!(report.events_archived, 3);
+    assert!(report.newest.unwrap() < cutoff);
+    assert!(path.exists());
+    // The two newer events stay, followed by the record of the archive
+    let held = logger.get_all_events();
+    assert_eq!(held.len(), 3);
+    assert_eq!(held[0].sample_id.as_deref(), Some("SAMPLE-3"));
+    assert!(matches!(held[2].event_type, AuditEventType::ConfigurationChanged));
+    assert_eq!(held[2].details["events_archived"], 3);
+    // A consumer tailing from before the archive sees only the new record
+    let (tail, _) = logger.export_batch(Some(end), 10);
+    assert_eq!(tail.len(), 1);
+    assert_eq!(tail[0].event_id, held[2].event_id);
+
+    // An archive is never replaced
+    assert!(logger.archive_events_before(chrono::Utc::now(), &path).is_err());
+    assert_eq!(logger.get_all_events().len(), 3);
+
+    assert_eq!(logger.import_archive(&path).unwrap(), 3);
+    assert_eq!(logger.import_archive(&path).unwrap(), 0);
+    let samples: Vec<_> = logger.get_all_events().iter().filter_map(|e| e.sample_id.clone()).collect();
+    assert_eq!(samples, ["SAMPLE-0", "SAMPLE-1", "SAMPLE-2", "SAMPLE-3", "SAMPLE-4"]);
+    let restored = logger.query_events(None, Some("SAMPLE-1"), None, None, None).unwrap();
+    assert_eq!(restored.len(), 1);
+
+    let empty = logger.archive_events_before(cutoff - chrono::Duration::days(1), dir.path().join("empty.json.gz")).unwrap();
+    assert_eq!(empty.events_archived, 0);
+    assert!(!dir.path().join("empty.json.gz").exists());
+}
+
+#[test]
+fn test_import_archive_respects_max_events() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.json.gz");
+    let mut logger = AuditLogger::new().with_max_events(4);
+    for _ in 0..4 {
+        logger.log_event(AuditEventType::UserAction, None, None, serde_json::json!({}), AuditSeverity::Info).unwrap();
+    }
+    logger.archive_events_before(chrono::Utc::now(), &path).unwrap();
+    for _ in 0..3 {
+        logger.log_event(AuditEventType::UserAction, None, None, serde_json::json!({}), AuditSeverity::Info).unwrap();
+    }
+
+    assert!(logger.import_archive(&path).is_err());
+    assert_eq!(logger.get_all_events().len(), 4);
+}
+
+#[test]
+fn test_export_csv_round_trips_through_csv_reader() {
+    let mut logger = AuditLogger::new();
+    logger.log_event(
+        AuditEventType::SampleCreated,
+        Some("USER-001".to_string()),
+        Some("SAMPLE-001".to_string()),
+        serde_json::json!({ "note": "line one,\nline \"two\"" }),
+        AuditSeverity::Info,
+    ).unwrap();
+    logger.log_event(AuditEventType::ViolationDetected, None, Some("SAMPLE-002".to_string()), serde_json::json!({}), AuditSeverity::Critical).unwrap();
+    logger.log_event(AuditEventType::UserAction, Some("USER-002".to_string()), None, serde_json::json!([1, 2]), AuditSeverity::Warning).unwrap();
+
+    let csv = logger.export_csv(None).unwrap();
+    let mut reader = csv::Reader::from_reader(csv.as_bytes());
+    assert_eq!(reader.headers().unwrap(), &csv::StringRecord::from(AUDIT_CSV_HEADER.to_vec()));
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    let events = logger.get_all_events();
+    assert_eq!(rows.len(), events.len());
+    for (row, event) in rows.iter().zip(&events) {
+        assert_eq!(&row[0], event.event_id.to_string());
+        assert_eq!(row[1].parse::<chrono::DateTime<Utc>>().unwrap(), event.timestamp);
+        assert_eq!(&row[2], format!("{:?}", event.event_type));
+        assert_eq!(&row[3], format!("{:?}", event.severity));
+        assert_eq!(&row[4], event.user_id.as_deref().unwrap_or(""));
+        assert_eq!(&row[5], event.sample_id.as_deref().unwrap_or(""));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&row[6]).unwrap(), event.details);
+    }
+
+    let filter = AuditFilter { severity: Some(AuditSeverity::Critical), ..Default::default() };
+    let critical = logger.export_csv(Some(&filter)).unwrap();
+    let rows: Vec<_> = csv::Reader::from_reader(critical.as_bytes()).records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(&rows[0][5], "SAMPLE-002");
+}
@@ -0,0 +1,101 @@
+use sample_guard::database::{Database, DatabaseBackend};
+use sample_guard::sample::{Sample, SampleMetadata, SampleStatus};
+use chrono::Utc;
+
+fn create_test_sample(id: &str, batch: &str) -> Sample {
+    let metadata = SampleMetadata {
+        batch_number: batch.to_string(),
+        production_date: Utc::now() - chrono::Duration::days(10),
+        expiry_date: Some(Utc::now() + chrono::Duration::days(365)),
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Refrigerated".to_string(),
+        manufacturer: "Backend Test".to_string(),
+        product_line: "Test".to_string(),
+    };
+    Sample::new(id.to_string(), metadata, Some("Shelf A".to_string()))
+}
+
+/// Round-trip samples through `db`; `prefix` keeps IDs apart on shared databases
+fn check_backend(db: &dyn DatabaseBackend, prefix: &str) {
+    let batch = format!("{}-BATCH", prefix);
+    let first = create_test_sample(&format!("{}-001", prefix), &batch);
+    let mut second = create_test_sample(&format!("{}-002", prefix), &batch);
+    second.update_status(SampleStatus::InTransit).unwrap();
+
+    db.store_sample(&first).unwrap();
+    db.store_sample(&second).unwrap();
+
+    let retrieved = db.get_sample(&first.sample_id).unwrap().unwrap();
+    assert_eq!(retrieved.id, first.id);
+    assert_eq!(retrieved.metadata, first.metadata);
+    assert_eq!(retrieved.created_at, first.created_at);
+    assert_eq!(retrieved.last_updated, first.last_updated);
+    assert_eq!(retrieved.location, first.location);
+    assert_eq!(retrieved.integrity_checksum, first.integrity_checksum);
+    assert!(retrieved.verify_integrity());
+    assert!(db.get_sample(&format!("{}-missing", prefix)).unwrap().is_none());
+
+    let in_batch = db.get_samples_by_batch(&batch).unwrap();
+    assert_eq!(in_batch.len(), 2);
+    assert!(db.get_samples_by_status(SampleStatus::InTransit).unwrap()
+        .iter()
+        .any(|s| s.sample_id == second.sample_id));
+    assert!(db.get_all_samples().unwrap().len() >= 2);
+
+    second.update_status(SampleStatus::Stored).unwrap();
+    db.store_sample(&second).unwrap();
+    let history = db.get_sample_history(&second.sample_id).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].status, SampleStatus::Stored);
+
+    let stats = db.get_statistics().unwrap();
+    assert!(stats.total_samples >= 2);
+    assert!(stats.manufacturer_counts["Backend Test"] >= 2);
+
+    assert!(db.delete_sample(&first.sample_id).unwrap());
+    assert!(db.delete_sample(&second.sample_id).unwrap());
+    assert!(!db.delete_sample(&first.sample_id).unwrap());
+    assert!(db.get_samples_by_batch(&batch).unwrap().is_empty());
+}
+
+#[test]
+fn test_sqlite_backend() {
+    let db = Database::in_memory().unwrap();
+    check_backend(&db, "SQLITE");
+}
+
+/// Runs against the database named by `SAMPLEGUARD_POSTGRES_URL`, e.g.
+/// `host=localhost user=postgres dbname=sampleguard_test`
+#[cfg(feature = "postgres")]
+#[test]
+fn test_postgres_backend() {
+    use sample_guard::postgres::PostgresDatabase;
+
+    let Ok(url) = std::env::var("SAMPLEGUARD_POSTGRES_URL") else {
+        eprintln!("SAMPLEGUARD_POSTGRES_URL not set; skipping Postgres backend test");
+        return;
+    };
+    let db = PostgresDatabase::connect(&url).unwrap();
+    check_backend(&db, &format!("PG-{}", uuid::Uuid::new_v4().simple()));
+}
+
+/// The backend blocks on its own runtime, so it must not panic when called
+/// from a handler running on another one
+#[cfg(feature = "postgres")]
+#[actix_web::test]
+async fn test_postgres_backend_inside_async_context() {
+    use sample_guard::postgres::{PostgresDatabase, PostgresTls};
+
+    let refused = PostgresDatabase::connect_with_tls(
+        "host=127.0.0.1 port=1 user=postgres connect_timeout=2",
+        &PostgresTls::Required { ca_file: None },
+    );
+    assert!(refused.is_err());
+
+    let Ok(url) = std::env::var("SAMPLEGUARD_POSTGRES_URL") else {
+        eprintln!("SAMPLEGUARD_POSTGRES_URL not set; skipping Postgres backend test");
+        return;
+    };
+    let db = PostgresDatabase::connect(&url).unwrap();
+    check_backend(&db, &format!("PG-ASYNC-{}", uuid::Uuid::new_v4().simple()));
+}
@@ -0,0 +1,110 @@
+use sample_guard::cli::encode_station::{EncodeStation, StationOptions};
+use sample_guard::hardware::simulator::{SimulatedTag, TagSimulator};
+use sample_guard::*;
+use std::io::Cursor;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+fn blank_field(count: usize) -> TagSimulator {
+    let mut simulator = TagSimulator::new()
+        .with_read_delay(Duration::ZERO)
+        .with_write_delay(Duration::ZERO)
+        .with_network_delay(Duration::ZERO);
+    for i in 0..count {
+        simulator.add_tag(SimulatedTag::new(
+            format!("E200{:04}", i),
+            format!("BLANK-{}", i),
+            Vec::new(),
+        ));
+    }
+    simulator
+}
+
+fn options(dry_run: bool) -> StationOptions {
+    StationOptions {
+        dry_run,
+        tag_wait: Duration::ZERO,
+        verify: VerifyOptions::default()
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(2)),
+    }
+}
+
+#[test]
+fn test_encode_station_encodes_scripted_work_orders() {
+    let mut station = EncodeStation::new(
+        blank_field(3),
+        Database::in_memory().unwrap(),
+        AuditLogger::new(),
+        options(false),
+    );
+
+    let mut output = Vec::new();
+    let shutdown = AtomicBool::new(false);
+    let summary = station
+        .run(Cursor::new("WO-001\nWO-002\n\nWO-003\n"), &mut output, &shutdown)
+        .unwrap();
+
+    assert_eq!(summary.encoded, 3);
+    assert_eq!(summary.failed, 0);
+    assert_eq!(summary.skipped, 0);
+
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output.matches("PASS").count(), 3);
+
+    let mut encoded_ids: Vec<String> = station
+        .field()
+        .get_tags()
+        .into_iter()
+        .map(|tag| {
+            let sample = Sample::from_tag_with_key(&RFIDTag::from_bytes(&tag.data).unwrap(), &RFIDEncryption::new(sample::DEFAULT_MASTER_KEY)).unwrap();
+            assert!(sample.verify_integrity());
+            sample.sample_id
+        })
+        .collect();
+    encoded_ids.sort();
+    assert_eq!(encoded_ids, vec!["WO-001", "WO-002", "WO-003"]);
+
+    assert!(station.database().get_sample("WO-002").unwrap().is_some());
+    let session = station.audit_logger().get_events_by_type(&AuditEventType::UserAction);
+    assert_eq!(session.len(), 1);
+    assert_eq!(session[0].details["encoded"], 3);
+}
+
+#[test]
+fn test_encode_station_skips_when_field_is_empty() {
+    let mut station = EncodeStation::new(
+        blank_field(1),
+        Database::in_memory().unwrap(),
+        AuditLogger::new(),
+        options(false),
+    );
+
+    let mut output = Vec::new();
+    let shutdown = AtomicBool::new(false);
+    let summary = station
+        .run(Cursor::new("WO-001\nWO-002\n"), &mut output, &shutdown)
+        .unwrap();
+
+    assert_eq!(summary.encoded, 1);
+    assert_eq!(summary.skipped, 1);
+    assert!(String::from_utf8(output).unwrap().contains("FAIL WO-002 no blank tag in field"));
+}
+
+#[test]
+fn test_encode_station_dry_run_leaves_tags_blank() {
+    let mut station = EncodeStation::new(
+        blank_field(2),
+        Database::in_memory().unwrap(),
+        AuditLogger::new(),
+        options(true),
+    );
+
+    let shutdown = AtomicBool::new(false);
+    let summary = station
+        .run(Cursor::new("WO-001\n"), &mut Vec::new(), &shutdown)
+        .unwrap();
+
+    assert_eq!(summary.encoded, 1);
+    assert!(station.field().get_tags().iter().all(|tag| tag.data.is_empty()));
+    assert!(station.database().get_sample("WO-001").unwrap().is_none());
+}
@@ -1,7 +1,7 @@
 use sample_guard::hardware::*;
 use sample_guard::hardware::protocol::{ReaderCommand, MemoryBank};
 use sample_guard::hardware::simulator::{TagSimulator, SimulatedTag};
-use sample_guard::reader::RFIDReader;
+use sample_guard::reader::{RFIDReader, ReaderFrequency};
 use std::time::Duration;
 
 #[test]
@@ -118,7 +118,7 @@ fn test_tag_simulator_scan() {
     }
     
     let found = simulator.scan_tags(Duration::from_millis(100)).unwrap();
-    assert!(found.len() > 0);
+    assert!(!found.is_empty());
 }
 
 #[test]
@@ -167,7 +167,7 @@ fn test_hardware_driver_inventory() {
     driver.setup_demo_tags();
     
     let tags = driver.perform_inventory_scan().unwrap();
-    assert!(tags.len() > 0);
+    assert!(!tags.is_empty());
 }
 
 #[test]
@@ -203,6 +203,32 @@ fn test_reader_configuration() {
     assert!(response.success);
 }
 
+#[test]
+fn test_regional_power_limits() {
+    use sample_guard::region::RegulatoryRegion;
+    
+    let mut fcc = ImpinjSpeedwayReader::new().with_region(RegulatoryRegion::Fcc);
+    fcc.initialize().unwrap();
+    let response = fcc.send_command(ReaderCommand::SetConfiguration {
+        power: 30,
+        antenna: 1,
+    }).unwrap();
+    assert!(response.success);
+    assert_eq!(fcc.get_config().power_level, 30);
+    
+    let mut etsi = ImpinjSpeedwayReader::new().with_region(RegulatoryRegion::Etsi);
+    etsi.initialize().unwrap();
+    // The default 30 dBm is clamped to the ETSI limit on switching region
+    assert_eq!(etsi.get_config().power_level, 29);
+    
+    let response = etsi.send_command(ReaderCommand::SetConfiguration {
+        power: 30,
+        antenna: 1,
+    }).unwrap();
+    assert!(!response.success);
+    assert_eq!(etsi.get_config().power_level, 29);
+}
+
 #[test]
 fn test_inventory_commands() {
     let mut impinj = ImpinjSpeedwayReader::new();
@@ -232,7 +258,7 @@ fn test_error_condition_simulation() {
     }
     
     // With 50% error rate, we should see some errors
-    assert!(errors >= 0 && errors <= 10);
+    assert!((0..=10).contains(&errors));
 }
 
 #[test]
@@ -250,3 +276,40 @@ fn test_multiple_readers_comparison() {
     assert_ne!(impinj.simulate_delay(), zebra.simulate_delay());
 }
 
+#[test]
+fn test_reader_configurations_share_one_schema() {
+    let mut impinj = ImpinjSpeedwayReader::new();
+    let mut zebra = ZebraFX9600Reader::new();
+    impinj.initialize().unwrap();
+    zebra.initialize().unwrap();
+
+    let decode = |reader: &mut dyn ReaderProtocol| {
+        let response = reader.send_command(ReaderCommand::GetConfiguration).unwrap();
+        assert!(response.success);
+        ReaderConfigReport::decode(&response.data.unwrap()).unwrap()
+    };
+    let impinj_config = decode(&mut impinj);
+    let zebra_config = decode(&mut zebra);
+
+    assert_eq!(impinj_config.reader_id, impinj.get_reader_id());
+    assert_eq!(zebra_config.reader_id, zebra.get_reader_id());
+    assert_eq!(impinj_config.protocol, "LLRP");
+    assert_eq!(zebra_config.protocol, zebra.protocol_name());
+    for config in [&impinj_config, &zebra_config] {
+        assert!(!config.protocol_version.is_empty());
+        assert!(config.power > 0);
+        assert_eq!(config.frequency, ReaderFrequency::UltraHighFrequency);
+        assert!(config.antenna_gain > 0.0);
+        assert!(config.capabilities.max_tag_memory > 0);
+        assert!(config.capabilities.supported_frequencies.contains(&ReaderFrequency::UltraHighFrequency));
+    }
+
+    // Both serialize to the same set of fields
+    let keys = |config: &ReaderConfigReport| {
+        let value = serde_json::to_value(config).unwrap();
+        let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    };
+    assert_eq!(keys(&impinj_config), keys(&zebra_config));
+}
@@ -34,7 +34,7 @@ fn test_full_sample_lifecycle() {
     assert_eq!(sample.sample_id, read_sample.sample_id);
     
     // Update status
-    sample.update_status(SampleStatus::InTransit);
+    sample.update_status(SampleStatus::InTransit).unwrap();
     guard.write_sample(&sample).unwrap();
     
     // Read updated sample
@@ -96,7 +96,7 @@ fn test_tag_memory_layout() {
     let encryption = RFIDEncryption::new(b"test_key_32_bytes_long_for_aes256!!");
     let payload = b"Test payload data";
     
-    let tag = RFIDTag::new("TAG001".to_string(), payload, &encryption).unwrap();
+    let tag = RFIDTag::new("TAG001".to_string(), "TAG001", payload, &encryption).unwrap();
     
     // Verify tag structure
     assert_eq!(tag.tag_id, "TAG001");
@@ -110,7 +110,42 @@ fn test_tag_memory_layout() {
     assert_eq!(tag.tag_id, restored.tag_id);
     
     // Test decryption
-    let decrypted = restored.decrypt_payload(&encryption).unwrap();
+    let decrypted = restored.decrypt_payload("TAG001", &encryption).unwrap();
     assert_eq!(payload, decrypted.as_slice());
 }
 
+
+#[test]
+fn test_tags_readable_after_key_rotation() {
+    use sample_guard::tag::RFIDTag;
+    use sample_guard::encryption::RFIDEncryption;
+    
+    let old_key = b"old_master_key_32_bytes_long!!!!";
+    let new_key = b"new_master_key_32_bytes_long!!!!";
+    let before = RFIDEncryption::new_gcm(old_key);
+    let tag = RFIDTag::new("TAG001".to_string(), "TAG001", b"Test payload data", &before).unwrap();
+    let bytes = tag.to_bytes().unwrap();
+    
+    let rotated = RFIDEncryption::with_previous_key(1, new_key, 0, old_key).unwrap().with_mode(EncryptionMode::Gcm);
+    let restored = RFIDTag::from_bytes(&bytes).unwrap();
+    assert_eq!(restored.key_id(), 0);
+    assert_eq!(restored.decrypt_payload("TAG001", &rotated).unwrap(), b"Test payload data");
+    
+    let result = rotated.decrypt_versioned(&before.encrypt(b"raw payload").unwrap()).unwrap();
+    assert_eq!(result.key_version, 0);
+    assert_eq!(result.plaintext, b"raw payload");
+    
+    // Migrate the tag lazily; afterwards only the new key is needed
+    let mut migrated = restored.clone();
+    migrated.memory_layout.payload = rotated.re_encrypt(&restored.memory_layout.payload, "TAG001").unwrap();
+    migrated.memory_layout.integrity_hash = rotated.hash(&migrated.memory_layout.payload);
+    migrated.memory_layout.header[sample_guard::tag::TAG_KEY_ID_OFFSET] = rotated.key_version();
+    let after = RFIDEncryption::new_gcm(new_key);
+    assert!(restored.decrypt_payload("TAG001", &after).is_err());
+    assert_eq!(migrated.decrypt_payload("TAG001", &after).unwrap(), b"Test payload data");
+    
+    // New tags record the new key's version
+    let fresh = RFIDTag::new("TAG002".to_string(), "TAG002", b"fresh", &rotated).unwrap();
+    assert_eq!(fresh.key_id(), 1);
+    assert_eq!(fresh.decrypt_payload("TAG002", &rotated).unwrap(), b"fresh");
+}
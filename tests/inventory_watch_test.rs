@@ -0,0 +1,94 @@
+use sample_guard::cli::inventory::{watch, WatchOptions};
+use sample_guard::hardware::simulator::{SimulatedTag, TagSimulator};
+use sample_guard::inventory::{TagScanResult, TagSource};
+use sample_guard::*;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Simulator whose tag population changes after a given number of cycles
+struct ScriptedField {
+    simulator: TagSimulator,
+    cycles: u32,
+    arrive_at: u32,
+    leave_at: u32,
+}
+
+impl TagSource for ScriptedField {
+    fn scan_cycle(&mut self, duration: Duration) -> Result<Vec<TagScanResult>> {
+        self.cycles += 1;
+        if self.cycles == self.arrive_at {
+            self.simulator.add_tag(SimulatedTag::new("E200-0003".to_string(), "S-3".to_string(), vec![]));
+        }
+        if self.cycles == self.leave_at {
+            self.simulator.remove_tag("E200-0001");
+        }
+        self.simulator.scan_cycle(duration)
+    }
+}
+
+fn field() -> ScriptedField {
+    let mut simulator = TagSimulator::new().with_network_delay(Duration::ZERO);
+    simulator.add_tag(SimulatedTag::new("E200-0001".to_string(), "S-1".to_string(), vec![]));
+    simulator.add_tag(SimulatedTag::new("E200-0002".to_string(), "S-2".to_string(), vec![]));
+    ScriptedField { simulator, cycles: 0, arrive_at: 2, leave_at: 3 }
+}
+
+fn options() -> WatchOptions {
+    WatchOptions {
+        interval: Duration::ZERO,
+        scan_duration: Duration::from_millis(20),
+        missing_after: 2,
+        tty: false,
+        max_cycles: Some(5),
+        ..WatchOptions::default()
+    }
+}
+
+#[test]
+fn test_watch_prints_appearance_and_disappearance_lines() {
+    let (_tx, rx) = mpsc::channel();
+    let mut output = Vec::new();
+    let mut source = field();
+
+    let session = watch(&mut source, None, &options(), &mut output, &rx, &AtomicBool::new(false)).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+
+    assert_eq!(lines.len(), 4, "unexpected output:\n{}", output);
+    assert!(lines[0].contains("APPEARED E200-0001 sample=S-1"));
+    assert!(lines[1].contains("APPEARED E200-0002 sample=S-2"));
+    assert!(lines[2].contains("APPEARED E200-0003 sample=S-3"));
+    // Gone from cycle 3 on, declared missing after two missed cycles
+    assert!(lines[3].contains("MISSING E200-0001 sample=S-1"));
+    assert!(!output.contains('\x1b'));
+
+    assert_eq!(session.cycles(), 5);
+    assert_eq!(session.tags().len(), 2);
+}
+
+#[test]
+fn test_watch_reports_database_status() {
+    let database = Database::in_memory().unwrap();
+    let metadata = SampleMetadata {
+        batch_number: "BATCH-W".to_string(),
+        production_date: chrono::Utc::now(),
+        expiry_date: None,
+        temperature_range: None,
+        storage_conditions: "Ambient".to_string(),
+        manufacturer: "Acme".to_string(),
+        product_line: "Test".to_string(),
+    };
+    database.store_sample(&Sample::new("S-2".to_string(), metadata, None)).unwrap();
+
+    let (_tx, rx) = mpsc::channel();
+    let mut output = Vec::new();
+    let mut source = field();
+    let options = WatchOptions { max_cycles: Some(1), ..options() };
+
+    watch(&mut source, Some(&database), &options, &mut output, &rx, &AtomicBool::new(false)).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.contains("APPEARED E200-0001 sample=S-1 rssi=-60 status=Unknown"));
+    assert!(output.contains("APPEARED E200-0002 sample=S-2 rssi=-60 status=InProduction"));
+}
@@ -0,0 +1,109 @@
+use actix_web::{middleware::from_fn, test, web, App};
+use chrono::Utc;
+use sample_guard::api::middleware::{correlation_id, REQUEST_ID_HEADER};
+use sample_guard::api::models::*;
+use sample_guard::api::{configure_routes, create_app_state};
+use sample_guard::logging::JsonLogger;
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Log output shared between the installed logger and the tests
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The logger is process-wide, so every test shares one installed instance
+fn captured() -> &'static SharedBuffer {
+    static BUFFER: OnceLock<SharedBuffer> = OnceLock::new();
+    BUFFER.get_or_init(|| {
+        let buffer = SharedBuffer::default();
+        JsonLogger::new(log::LevelFilter::Info, Box::new(buffer.clone())).install().unwrap();
+        buffer
+    })
+}
+
+fn log_lines() -> Vec<serde_json::Value> {
+    let bytes = captured().0.lock().unwrap().clone();
+    String::from_utf8(bytes)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("log line is not JSON"))
+        .collect()
+}
+
+fn create_request(sample_id: &str) -> CreateSampleRequest {
+    CreateSampleRequest {
+        sample_id: sample_id.to_string(),
+        batch_number: "BATCH-LOG".to_string(),
+        production_date: Utc::now(),
+        expiry_date: None,
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Refrigerated".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+        location: None,
+    }
+}
+
+#[actix_web::test]
+async fn test_handler_logs_are_json_with_correlation_id() {
+    captured();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_app_state()))
+            .wrap(from_fn(correlation_id))
+            .configure(configure_routes)
+    ).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples")
+        .insert_header((REQUEST_ID_HEADER, "req-log-001"))
+        .set_json(create_request("LOG-001"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "req-log-001");
+
+    let line = log_lines()
+        .into_iter()
+        .find(|l| l["correlation_id"] == "req-log-001" && l["message"] == "Sample created")
+        .expect("no log line for the request");
+    assert_eq!(line["level"], "INFO");
+    assert_eq!(line["target"], "sample_guard::api::handlers");
+    assert_eq!(line["sample_id"], "LOG-001");
+    assert!(chrono::DateTime::parse_from_rfc3339(line["timestamp"].as_str().unwrap()).is_ok());
+}
+
+#[actix_web::test]
+async fn test_correlation_id_generated_when_absent() {
+    captured();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_app_state()))
+            .wrap(from_fn(correlation_id))
+            .configure(configure_routes)
+    ).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/samples")
+        .set_json(create_request("LOG-002"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let id = resp.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+    assert!(uuid::Uuid::parse_str(&id).is_ok());
+
+    let line = log_lines()
+        .into_iter()
+        .find(|l| l["sample_id"] == "LOG-002")
+        .expect("no log line for the request");
+    assert_eq!(line["correlation_id"], id.as_str());
+}
@@ -0,0 +1,180 @@
+use sample_guard::notifications::{Notification, NotificationDispatcher, NotificationSink, WebhookSink};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Response written for every request
+const NO_CONTENT: &[u8] = b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n";
+
+/// Minimal keep-alive HTTP server counting connections and requests
+struct MockServer {
+    url: String,
+    connections: Arc<AtomicUsize>,
+    requests: Arc<AtomicUsize>,
+}
+
+impl MockServer {
+    fn start(delay: Duration) -> Self {
+        Self::start_with_response(delay, NO_CONTENT)
+    }
+
+    fn start_with_response(delay: Duration, response: &'static [u8]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let (conn_count, req_count) = (Arc::clone(&connections), Arc::clone(&requests));
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                conn_count.fetch_add(1, Ordering::SeqCst);
+                let req_count = Arc::clone(&req_count);
+                std::thread::spawn(move || serve_connection(stream, delay, response, &req_count));
+            }
+        });
+
+        Self { url, connections, requests }
+    }
+}
+
+fn serve_connection(stream: TcpStream, delay: Duration, response: &[u8], requests: &AtomicUsize) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    loop {
+        let mut content_length = 0;
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            if line.trim_end().is_empty() {
+                break;
+            }
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+        let notification: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(notification["event"].is_string());
+        requests.fetch_add(1, Ordering::SeqCst);
+
+        std::thread::sleep(delay);
+        if writer.write_all(response).is_err() {
+            return;
+        }
+    }
+}
+
+fn notification(i: usize) -> Notification {
+    Notification::new("sample_created", Some(format!("S-{}", i)), serde_json::json!({ "seq": i }))
+}
+
+#[test]
+fn test_rapid_notifications_reuse_connections() {
+    let server = MockServer::start(Duration::ZERO);
+    let sink = WebhookSink::new(&server.url).unwrap().with_max_connections(4);
+
+    for i in 0..50 {
+        sink.send(&notification(i)).unwrap();
+    }
+
+    let stats = sink.stats();
+    assert_eq!(stats.successes, 50);
+    assert_eq!(stats.failures, 0);
+    assert_eq!(server.connections.load(Ordering::SeqCst), 1);
+    assert_eq!(server.requests.load(Ordering::SeqCst), 50);
+    assert!(stats.mean_latency_ms.is_some());
+}
+
+#[test]
+fn test_chunked_responses_are_read_before_reuse() {
+    let server = MockServer::start_with_response(
+        Duration::ZERO,
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+    );
+    let sink = WebhookSink::new(&server.url).unwrap();
+
+    for i in 0..5 {
+        sink.send(&notification(i)).unwrap();
+    }
+    assert_eq!(sink.stats().successes, 5);
+    assert_eq!(server.connections.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_huge_content_length_is_not_allocated() {
+    let server = MockServer::start_with_response(
+        Duration::ZERO,
+        b"HTTP/1.1 200 OK\r\nContent-Length: 1000000000000\r\n\r\nok",
+    );
+    let sink = WebhookSink::new(&server.url).unwrap().with_timeout(Duration::from_millis(500));
+
+    // The response is cut short but the notification was accepted
+    sink.send(&notification(1)).unwrap();
+    assert_eq!(server.requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_concurrent_deliveries_stay_within_max_connections() {
+    let server = MockServer::start(Duration::from_millis(20));
+    let sink = Arc::new(WebhookSink::new(&server.url).unwrap().with_max_connections(3));
+
+    let handles: Vec<_> = (0..6)
+        .map(|t| {
+            let sink = Arc::clone(&sink);
+            std::thread::spawn(move || {
+                for i in 0..5 {
+                    sink.send(&notification(t * 10 + i)).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(sink.stats().successes, 30);
+    assert!(server.connections.load(Ordering::SeqCst) <= 3);
+}
+
+#[test]
+fn test_per_delivery_timeout_is_enforced() {
+    let server = MockServer::start(Duration::from_secs(3));
+    let sink = WebhookSink::new(&server.url).unwrap().with_timeout(Duration::from_millis(200));
+
+    let start = Instant::now();
+    let result = sink.send(&notification(1));
+    assert!(result.is_err());
+    assert!(start.elapsed() < Duration::from_secs(2), "delivery took {:?}", start.elapsed());
+    assert_eq!(sink.stats().failures, 1);
+}
+
+#[test]
+fn test_slow_endpoint_does_not_delay_other_sinks() {
+    let slow = MockServer::start(Duration::from_secs(3));
+    let fast = MockServer::start(Duration::ZERO);
+    let slow_sink = Arc::new(WebhookSink::new(&slow.url).unwrap().with_timeout(Duration::from_millis(300)));
+    let fast_sink = Arc::new(WebhookSink::new(&fast.url).unwrap());
+
+    let dispatcher = NotificationDispatcher::new()
+        .with_sink(slow_sink.clone())
+        .with_sink(fast_sink.clone());
+
+    let start = Instant::now();
+    let results = dispatcher.dispatch(&notification(1));
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(results[0].is_err());
+    assert!(results[1].is_ok());
+    assert_eq!(fast_sink.stats().successes, 1);
+    assert_eq!(slow_sink.stats().failures, 1);
+}
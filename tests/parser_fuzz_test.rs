@@ -0,0 +1,141 @@
+//! Seeded fuzzing of the parsers that see raw tag and reader bytes.
+//!
+//! Every input must produce `Ok` or a typed error; a panic fails the test
+//! with the offending input in hex so it can be turned into a regression test.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sample_guard::cli::tag::decode_tag;
+use sample_guard::hardware::protocol::{BatchReadEntry, ReaderConfigReport};
+use sample_guard::encryption::RFIDEncryption;
+use sample_guard::sample::{Sample, SampleMetadata, DEFAULT_MASTER_KEY};
+use sample_guard::tag::{RFIDTag, TagData};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+const SEED: u64 = 0x5A4D_504C_4547_5244;
+const ITERATIONS: usize = 2000;
+
+/// Run every parser over `input`, then over a decoded tag's derived paths
+fn parse_all(input: &[u8]) {
+    let data = TagData::new(input.to_vec());
+    if let Ok(mut tag) = RFIDTag::from_bytes(data.as_bytes()) {
+        let _ = Sample::from_tag_with_key(&tag, &RFIDEncryption::new(DEFAULT_MASTER_KEY));
+        tag.increment_read_count();
+        let _ = tag.to_bytes();
+    }
+    let _ = decode_tag(input, None, false);
+    let _ = decode_tag(input, Some(DEFAULT_MASTER_KEY), false);
+    let _ = BatchReadEntry::decode(input);
+    let _ = ReaderConfigReport::decode(input);
+}
+
+fn check(input: &[u8]) {
+    if catch_unwind(AssertUnwindSafe(|| parse_all(input))).is_err() {
+        panic!("parser panicked on input {}", hex::encode(input));
+    }
+}
+
+fn valid_tag() -> RFIDTag {
+    let metadata = SampleMetadata {
+        batch_number: "BATCH-FUZZ".to_string(),
+        production_date: chrono::Utc::now(),
+        expiry_date: None,
+        temperature_range: Some((2.0, 8.0)),
+        storage_conditions: "Frozen".to_string(),
+        manufacturer: "Test".to_string(),
+        product_line: "Test".to_string(),
+    };
+    Sample::new("FUZZ-001".to_string(), metadata, None)
+        .to_tag_with_key(&RFIDEncryption::new(DEFAULT_MASTER_KEY))
+        .unwrap()
+}
+
+fn random_bytes(rng: &mut StdRng, max_len: usize) -> Vec<u8> {
+    let len = rng.gen_range(0..=max_len);
+    (0..len).map(|_| rng.gen()).collect()
+}
+
+#[test]
+fn fuzz_random_buffers() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for _ in 0..ITERATIONS {
+        let mut input = random_bytes(&mut rng, 256);
+        // Half the time, give the buffer a length prefix that fits
+        if input.len() >= 4 && rng.gen_bool(0.5) {
+            let len = (input.len() - 4) as u32;
+            input[..4].copy_from_slice(&len.to_be_bytes());
+        }
+        check(&input);
+    }
+}
+
+#[test]
+fn fuzz_mutated_tags() {
+    let mut rng = StdRng::seed_from_u64(SEED + 1);
+    let encoded = valid_tag().to_bytes().unwrap();
+    for _ in 0..ITERATIONS {
+        let mut input = encoded.clone();
+        match rng.gen_range(0..4) {
+            0 => {
+                for _ in 0..rng.gen_range(1..8) {
+                    let i = rng.gen_range(0..input.len());
+                    input[i] ^= 1 << rng.gen_range(0..8);
+                }
+            }
+            1 => input.truncate(rng.gen_range(0..input.len())),
+            2 => {
+                let len: u32 = rng.gen();
+                input[..4].copy_from_slice(&len.to_be_bytes());
+            }
+            _ => {
+                let at = rng.gen_range(0..=input.len());
+                let splice = random_bytes(&mut rng, 32);
+                input.splice(at..at, splice);
+            }
+        }
+        check(&input);
+    }
+}
+
+#[test]
+fn fuzz_structured_tag_fields() {
+    let mut rng = StdRng::seed_from_u64(SEED + 2);
+    let template = valid_tag();
+    for _ in 0..ITERATIONS / 4 {
+        let mut tag = template.clone();
+        let layout = &mut tag.memory_layout;
+        match rng.gen_range(0..4) {
+            0 => layout.payload = random_bytes(&mut rng, 96),
+            1 => rng.fill(&mut layout.integrity_hash[..]),
+            2 => rng.fill(&mut layout.metadata[..]),
+            _ => layout.metadata = [0xFF; 16],
+        }
+        rng.fill(&mut layout.header[..]);
+        check(&tag.to_bytes().unwrap());
+    }
+}
+
+#[test]
+fn fuzz_mutated_binary_tags() {
+    let mut rng = StdRng::seed_from_u64(SEED + 3);
+    let encoded = valid_tag().to_bytes_binary().unwrap();
+    for _ in 0..ITERATIONS {
+        let mut input = encoded.clone();
+        match rng.gen_range(0..3) {
+            0 => {
+                // Past the magic, so the binary parser is the one exercised
+                for _ in 0..rng.gen_range(1..8) {
+                    let i = rng.gen_range(4..input.len());
+                    input[i] ^= 1 << rng.gen_range(0..8);
+                }
+            }
+            1 => input.truncate(rng.gen_range(0..input.len())),
+            _ => {
+                let at = rng.gen_range(4..=input.len());
+                let splice = random_bytes(&mut rng, 32);
+                input.splice(at..at, splice);
+            }
+        }
+        check(&input);
+    }
+}
@@ -10,6 +10,7 @@ fn test_reader_configuration() {
         power_level: 75,
         read_timeout_ms: 2000,
         antenna_gain: 6.0,
+        region: None,
     };
     
     assert_eq!(config.frequency, ReaderFrequency::HighFrequency);
@@ -26,7 +27,7 @@ fn test_reader_capabilities() {
     let capabilities = reader.get_capabilities();
     assert!(capabilities.supports_encryption);
     assert!(capabilities.max_tag_memory >= 512);
-    assert!(capabilities.supported_frequencies.len() > 0);
+    assert!(!capabilities.supported_frequencies.is_empty());
 }
 
 #[test]
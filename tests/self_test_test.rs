@@ -0,0 +1,43 @@
+use sample_guard::cli::self_test::{run_self_test, StepStatus};
+use sample_guard::config::SampleGuardConfig;
+
+#[test]
+fn test_self_test_passes_against_emulated_stack() {
+    let mut config = SampleGuardConfig::default();
+    config.reader.simulated_blank_tags = 1;
+
+    let report = run_self_test(&config);
+    assert!(report.passed(), "self-test failed:\n{}", report);
+
+    let names: Vec<&str> = report.steps.iter().map(|s| s.name).collect();
+    assert_eq!(names, vec!["config", "database", "reader", "integrity", "temperature", "audit", "http"]);
+    assert!(report.to_string().ends_with("Self-test passed (7 steps)"));
+}
+
+#[test]
+fn test_self_test_continues_after_failure() {
+    let mut config = SampleGuardConfig::default();
+    config.temperature.min_celsius = 10.0;
+    config.temperature.max_celsius = 4.0;
+
+    let report = run_self_test(&config);
+    assert!(!report.passed());
+    assert!(matches!(report.step("temperature").unwrap().status, StepStatus::Fail(_)));
+    // Later, unrelated steps still ran
+    assert_eq!(report.step("audit").unwrap().status, StepStatus::Pass);
+    assert_eq!(report.step("reader").unwrap().status, StepStatus::Pass);
+    assert!(report.to_string().contains("FAIL temperature"));
+}
+
+#[test]
+fn test_self_test_skips_unconfigured_hardware() {
+    let mut config = SampleGuardConfig::default();
+    config.reader.kind = "none".to_string();
+    config.temperature.enabled = false;
+
+    let report = run_self_test(&config);
+    assert!(report.passed(), "self-test failed:\n{}", report);
+    assert!(matches!(report.step("reader").unwrap().status, StepStatus::Skip(_)));
+    assert!(matches!(report.step("temperature").unwrap().status, StepStatus::Skip(_)));
+    assert!(report.to_string().contains("SKIP reader"));
+}
@@ -1,4 +1,6 @@
-use sample_guard::temperature::{TemperatureMonitor, MockTemperatureSensor, ViolationType};
+use sample_guard::temperature::{TemperatureMonitor, MockTemperatureSensor, TemperatureReading, ViolationType};
+use sample_guard::database::Database;
+use chrono::{DateTime, Duration};
 
 #[test]
 fn test_temperature_monitoring() {
@@ -18,7 +20,7 @@ fn test_temperature_violation_detection() {
     monitor.read_temperature(None).unwrap();
     let violations = monitor.get_violations();
     
-    assert!(violations.len() > 0);
+    assert!(!violations.is_empty());
     assert_eq!(violations[0].violation_type, ViolationType::TooLow);
 }
 
@@ -65,6 +67,48 @@ fn test_critical_violation() {
     monitor.read_temperature(None).unwrap();
     let critical = monitor.get_critical_violations();
     
-    assert!(critical.len() > 0);
+    assert!(!critical.is_empty());
 }
 
+
+#[test]
+fn test_persisted_readings_survive_clear() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("samples.db");
+    let sensor = Box::new(MockTemperatureSensor::new("TEMP-DB".to_string(), 5.0));
+    let mut monitor = TemperatureMonitor::new(sensor, (2.0, 8.0))
+        .unwrap()
+        .with_database(Database::new(&path).unwrap());
+
+    let base = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    for (minute, temperature) in [5.0, 9.5, 4.0, 1.0].into_iter().enumerate() {
+        monitor.record_reading(TemperatureReading {
+            temperature,
+            timestamp: base + Duration::minutes(minute as i64),
+            sensor_id: "TEMP-DB".to_string(),
+            location: Some("Fridge 1".to_string()),
+        }).unwrap();
+    }
+    monitor.read_temperature(None).unwrap();
+    monitor.clear();
+    assert!(monitor.get_all_readings().is_empty());
+
+    let db = Database::new(&path).unwrap();
+    let readings = db.get_temperature_readings("TEMP-DB", base, base + Duration::minutes(3)).unwrap();
+    let temperatures: Vec<f32> = readings.iter().map(|r| r.temperature).collect();
+    assert_eq!(temperatures, vec![5.0, 9.5, 4.0, 1.0]);
+    assert_eq!(readings[0].location.as_deref(), Some("Fridge 1"));
+    assert_eq!(readings[1].timestamp, base + Duration::minutes(1));
+    // The live reading was stored too
+    assert_eq!(db.get_temperature_readings("TEMP-DB", base, chrono::Utc::now()).unwrap().len(), 5);
+
+    let violations = db.get_temperature_violations("TEMP-DB", base, base + Duration::minutes(3)).unwrap();
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].violation_type, ViolationType::TooHigh);
+    assert_eq!(violations[1].violation_type, ViolationType::TooLow);
+    assert_eq!(violations[1].expected_range, (2.0, 8.0));
+
+    // Bounds are inclusive and other sensors are excluded
+    assert_eq!(db.get_temperature_readings("TEMP-DB", base + Duration::minutes(1), base + Duration::minutes(2)).unwrap().len(), 2);
+    assert!(db.get_temperature_readings("OTHER", base, chrono::Utc::now()).unwrap().is_empty());
+}